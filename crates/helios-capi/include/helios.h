@@ -0,0 +1,122 @@
+#ifndef HELIOS_H
+#define HELIOS_H
+
+/* This file is generated by cbindgen from `crates/helios-capi`, so don't edit
+ * it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The index of a node that doesn't exist (such as the parent of the root).
+#define HELIOS_NONE UINT32_MAX
+
+typedef enum HeliosSeverity {
+  HELIOS_SEVERITY_NOTE = 0,
+  HELIOS_SEVERITY_WARNING = 1,
+  HELIOS_SEVERITY_ERROR = 2,
+  HELIOS_SEVERITY_BUG = 3,
+} HeliosSeverity;
+
+// The result of parsing a source text.
+typedef struct HeliosParse HeliosParse;
+
+// A node or a token of a syntax tree.
+typedef struct HeliosNode {
+  // The kind of the node or token (see [`helios_syntax_kind_name`]).
+  uint16_t kind;
+  // Whether this is a token (a leaf of the tree) rather than a node.
+  bool is_token;
+  // The offset (in bytes) of the start of the node in the source text.
+  uint32_t start;
+  // The offset (in bytes) of the end of the node in the source text.
+  uint32_t end;
+  uint32_t parent;
+  uint32_t first_child;
+  uint32_t next_sibling;
+} HeliosNode;
+
+// A diagnostic reported while parsing. Its strings are null-terminated
+// Markdown, owned by the [`HeliosParse`] it comes from.
+typedef struct HeliosDiagnostic {
+  enum HeliosSeverity severity;
+  // The offset (in bytes) of the start of the diagnostic in the source
+  // text.
+  uint32_t start;
+  // The offset (in bytes) of the end of the diagnostic in the source text.
+  uint32_t end;
+  // A short summary of the diagnostic (such as `Unexpected end of file`).
+  const char *title;
+  const char *message;
+  // How to fix what the diagnostic is about, which may be null.
+  const char *hint;
+} HeliosDiagnostic;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Parses a source text of the given length (in bytes), which doesn't need to
+// be null-terminated.
+//
+// Returns null if the source text isn't valid UTF-8. Otherwise, the result
+// must be freed with [`helios_parse_free`].
+//
+// # Safety
+//
+// `source` must point to `len` readable bytes.
+struct HeliosParse *helios_parse(const char *source, uintptr_t len);
+
+// Frees the result of [`helios_parse`] (doing nothing if it's null).
+//
+// # Safety
+//
+// `parse` must have been returned by [`helios_parse`], and must not be used
+// afterwards.
+void helios_parse_free(struct HeliosParse *parse);
+
+// The nodes and tokens of the syntax tree, in preorder, whose number is
+// written to `len`.
+//
+// # Safety
+//
+// `parse` must be a live result of [`helios_parse`], and `len` must be
+// writable.
+const struct HeliosNode *helios_parse_nodes(const struct HeliosParse *parse, uintptr_t *len);
+
+// The diagnostics reported while parsing, whose number is written to `len`.
+//
+// # Safety
+//
+// `parse` must be a live result of [`helios_parse`], and `len` must be
+// writable.
+const struct HeliosDiagnostic *helios_parse_diagnostics(const struct HeliosParse *parse,
+                                                        uintptr_t *len);
+
+// The text of the node or token at the given index (which isn't
+// null-terminated), whose length (in bytes) is written to `len`.
+//
+// Returns null if there isn't a node at that index.
+//
+// # Safety
+//
+// `parse` must be a live result of [`helios_parse`], and `len` must be
+// writable.
+const char *helios_parse_text(const struct HeliosParse *parse, uint32_t index, uintptr_t *len);
+
+// The name of a kind of node or token (such as `Exp_Binary`), which is
+// null-terminated and lives as long as the program.
+//
+// Returns null if there isn't a kind with that value.
+const char *helios_syntax_kind_name(uint16_t kind);
+
+// The version of Helios, which is null-terminated and lives as long as the
+// program.
+const char *helios_version(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* HELIOS_H */
@@ -0,0 +1,434 @@
+//! A C API for embedding the Helios parser in editors and build tools written
+//! in other languages.
+//!
+//! A source text is parsed with [`helios_parse`], which returns a
+//! [`HeliosParse`] owning everything the other functions return pointers to,
+//! until it is freed with [`helios_parse_free`]:
+//!
+//! - The syntax tree, flattened in preorder into an array of [`HeliosNode`]s
+//!   (see [`helios_parse_nodes`]). Nodes and tokens refer to their parent,
+//!   their first child and their next sibling by their index in the array
+//!   (or [`HELIOS_NONE`] if they don't have one), so the root is always at
+//!   index `0`.
+//! - The diagnostics reported while parsing, as an array of
+//!   [`HeliosDiagnostic`]s (see [`helios_parse_diagnostics`]).
+//!
+//! The header declaring the API (`include/helios.h`) is generated by cbindgen,
+//! as configured in `cbindgen.toml`. It is checked by the tests of this crate,
+//! and regenerated by running them with `UPDATE_EXPECT=1`.
+
+use helios_diagnostics::{Diagnostic, FileId, Severity};
+use helios_syntax::{HeliosLanguage, SyntaxKind, SyntaxNode};
+use rowan::{Language, NodeOrToken, WalkEvent};
+use std::ffi::{c_char, CString};
+use std::sync::OnceLock;
+
+/// The index of a node that doesn't exist (such as the parent of the root).
+pub const HELIOS_NONE: u32 = u32::MAX;
+
+/// A node or a token of a syntax tree.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C)]
+pub struct HeliosNode {
+    /// The kind of the node or token (see [`helios_syntax_kind_name`]).
+    pub kind: u16,
+    /// Whether this is a token (a leaf of the tree) rather than a node.
+    pub is_token: bool,
+    /// The offset (in bytes) of the start of the node in the source text.
+    pub start: u32,
+    /// The offset (in bytes) of the end of the node in the source text.
+    pub end: u32,
+    pub parent: u32,
+    pub first_child: u32,
+    pub next_sibling: u32,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C)]
+pub enum HeliosSeverity {
+    Note = 0,
+    Warning = 1,
+    Error = 2,
+    Bug = 3,
+}
+
+impl From<Severity> for HeliosSeverity {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Note => Self::Note,
+            Severity::Warning => Self::Warning,
+            Severity::Error => Self::Error,
+            Severity::Bug => Self::Bug,
+        }
+    }
+}
+
+/// A diagnostic reported while parsing. Its strings are null-terminated
+/// Markdown, owned by the [`HeliosParse`] it comes from.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct HeliosDiagnostic {
+    pub severity: HeliosSeverity,
+    /// The offset (in bytes) of the start of the diagnostic in the source
+    /// text.
+    pub start: u32,
+    /// The offset (in bytes) of the end of the diagnostic in the source text.
+    pub end: u32,
+    /// A short summary of the diagnostic (such as `Unexpected end of file`).
+    pub title: *const c_char,
+    pub message: *const c_char,
+    /// How to fix what the diagnostic is about, which may be null.
+    pub hint: *const c_char,
+}
+
+/// The result of parsing a source text.
+pub struct HeliosParse {
+    source: String,
+    nodes: Vec<HeliosNode>,
+    diagnostics: Vec<HeliosDiagnostic>,
+    /// The strings the diagnostics point to.
+    _strings: Vec<CString>,
+}
+
+impl HeliosParse {
+    fn new(source: String) -> Self {
+        let parse = helios_parser::parse(FileId::default(), &source);
+        let nodes = flatten(&parse.syntax());
+
+        let mut strings = Vec::new();
+        let mut string = |string: String| {
+            let string = CString::new(string.replace('\0', "\u{fffd}"))
+                .expect("Null characters were replaced");
+            let ptr = string.as_ptr();
+            strings.push(string);
+            ptr
+        };
+
+        let diagnostics = parse
+            .messages()
+            .iter()
+            .map(|message| {
+                let diagnostic = Diagnostic::<FileId>::from(message);
+                let range = &diagnostic.location.range;
+                HeliosDiagnostic {
+                    severity: diagnostic.severity.into(),
+                    start: range.start as u32,
+                    end: range.end as u32,
+                    title: string(diagnostic.title.clone()),
+                    message: string(diagnostic.message.to_markdown()),
+                    hint: match &diagnostic.hint {
+                        Some(hint) => string(hint.to_markdown()),
+                        None => std::ptr::null(),
+                    },
+                }
+            })
+            .collect();
+
+        Self {
+            source,
+            nodes,
+            diagnostics,
+            _strings: strings,
+        }
+    }
+}
+
+/// Flattens a syntax tree into an array of its nodes and tokens, in preorder.
+fn flatten(root: &SyntaxNode) -> Vec<HeliosNode> {
+    let mut nodes = Vec::<HeliosNode>::new();
+    // The nodes from the root to the current one, along with the last child
+    // of each that was seen so far.
+    let mut ancestors = Vec::<(u32, u32)>::new();
+
+    for event in root.preorder_with_tokens() {
+        let element = match event {
+            WalkEvent::Enter(element) => element,
+            WalkEvent::Leave(NodeOrToken::Node(_)) => {
+                ancestors.pop();
+                continue;
+            }
+            WalkEvent::Leave(NodeOrToken::Token(_)) => continue,
+        };
+
+        let index = nodes.len() as u32;
+        let range = element.text_range();
+        let mut node = HeliosNode {
+            kind: HeliosLanguage::kind_to_raw(element.kind()).0,
+            is_token: element.as_token().is_some(),
+            start: range.start().into(),
+            end: range.end().into(),
+            parent: HELIOS_NONE,
+            first_child: HELIOS_NONE,
+            next_sibling: HELIOS_NONE,
+        };
+
+        if let Some((parent, last_child)) = ancestors.last_mut() {
+            node.parent = *parent;
+            match *last_child {
+                HELIOS_NONE => nodes[*parent as usize].first_child = index,
+                sibling => nodes[sibling as usize].next_sibling = index,
+            }
+            *last_child = index;
+        }
+
+        nodes.push(node);
+        if element.as_node().is_some() {
+            ancestors.push((index, HELIOS_NONE));
+        }
+    }
+
+    nodes
+}
+
+/// Parses a source text of the given length (in bytes), which doesn't need to
+/// be null-terminated.
+///
+/// Returns null if the source text isn't valid UTF-8. Otherwise, the result
+/// must be freed with [`helios_parse_free`].
+///
+/// # Safety
+///
+/// `source` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn helios_parse(
+    source: *const c_char,
+    len: usize,
+) -> *mut HeliosParse {
+    if source.is_null() {
+        return std::ptr::null_mut();
+    }
+
+    let bytes = std::slice::from_raw_parts(source.cast::<u8>(), len);
+    match std::str::from_utf8(bytes) {
+        Ok(source) => {
+            Box::into_raw(Box::new(HeliosParse::new(source.to_string())))
+        }
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Frees the result of [`helios_parse`] (doing nothing if it's null).
+///
+/// # Safety
+///
+/// `parse` must have been returned by [`helios_parse`], and must not be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn helios_parse_free(parse: *mut HeliosParse) {
+    if !parse.is_null() {
+        drop(Box::from_raw(parse));
+    }
+}
+
+/// The nodes and tokens of the syntax tree, in preorder, whose number is
+/// written to `len`.
+///
+/// # Safety
+///
+/// `parse` must be a live result of [`helios_parse`], and `len` must be
+/// writable.
+#[no_mangle]
+pub unsafe extern "C" fn helios_parse_nodes(
+    parse: *const HeliosParse,
+    len: *mut usize,
+) -> *const HeliosNode {
+    let parse = &*parse;
+    *len = parse.nodes.len();
+    parse.nodes.as_ptr()
+}
+
+/// The diagnostics reported while parsing, whose number is written to `len`.
+///
+/// # Safety
+///
+/// `parse` must be a live result of [`helios_parse`], and `len` must be
+/// writable.
+#[no_mangle]
+pub unsafe extern "C" fn helios_parse_diagnostics(
+    parse: *const HeliosParse,
+    len: *mut usize,
+) -> *const HeliosDiagnostic {
+    let parse = &*parse;
+    *len = parse.diagnostics.len();
+    parse.diagnostics.as_ptr()
+}
+
+/// The text of the node or token at the given index (which isn't
+/// null-terminated), whose length (in bytes) is written to `len`.
+///
+/// Returns null if there isn't a node at that index.
+///
+/// # Safety
+///
+/// `parse` must be a live result of [`helios_parse`], and `len` must be
+/// writable.
+#[no_mangle]
+pub unsafe extern "C" fn helios_parse_text(
+    parse: *const HeliosParse,
+    index: u32,
+    len: *mut usize,
+) -> *const c_char {
+    let parse = &*parse;
+    match parse.nodes.get(index as usize) {
+        Some(node) => {
+            let text = &parse.source[node.start as usize..node.end as usize];
+            *len = text.len();
+            text.as_ptr().cast()
+        }
+        None => std::ptr::null(),
+    }
+}
+
+/// The name of a kind of node or token (such as `Exp_Binary`), which is
+/// null-terminated and lives as long as the program.
+///
+/// Returns null if there isn't a kind with that value.
+#[no_mangle]
+pub extern "C" fn helios_syntax_kind_name(kind: u16) -> *const c_char {
+    static NAMES: OnceLock<Vec<CString>> = OnceLock::new();
+    let names = NAMES.get_or_init(|| {
+        (0..=SyntaxKind::Root as u16)
+            .map(|raw| {
+                let kind =
+                    HeliosLanguage::kind_from_raw(rowan::SyntaxKind(raw));
+                CString::new(format!("{kind:?}")).unwrap()
+            })
+            .collect()
+    });
+
+    match names.get(kind as usize) {
+        Some(name) => name.as_ptr(),
+        None => std::ptr::null(),
+    }
+}
+
+/// The version of Helios, which is null-terminated and lives as long as the
+/// program.
+#[no_mangle]
+pub extern "C" fn helios_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    fn parse(source: &str) -> *mut HeliosParse {
+        unsafe { helios_parse(source.as_ptr().cast(), source.len()) }
+    }
+
+    fn nodes(parse: *const HeliosParse) -> &'static [HeliosNode] {
+        let mut len = 0;
+        unsafe {
+            let nodes = helios_parse_nodes(parse, &mut len);
+            std::slice::from_raw_parts(nodes, len)
+        }
+    }
+
+    fn text(parse: *const HeliosParse, index: u32) -> Option<&'static str> {
+        let mut len = 0;
+        unsafe {
+            let text = helios_parse_text(parse, index, &mut len);
+            (!text.is_null()).then(|| {
+                let bytes = std::slice::from_raw_parts(text.cast(), len);
+                std::str::from_utf8(bytes).unwrap()
+            })
+        }
+    }
+
+    fn string(ptr: *const c_char) -> Option<&'static str> {
+        (!ptr.is_null())
+            .then(|| unsafe { CStr::from_ptr(ptr) }.to_str().unwrap())
+    }
+
+    #[test]
+    fn test_parse_tree() {
+        let parse = parse("let a = 1\n");
+        let nodes = nodes(parse);
+
+        let root = nodes[0];
+        assert_eq!(string(helios_syntax_kind_name(root.kind)), Some("Root"));
+        assert_eq!((root.start, root.end, root.parent), (0, 10, HELIOS_NONE));
+
+        // Every child refers back to its parent, in the order they appear.
+        let binding = &nodes[root.first_child as usize];
+        assert_eq!(binding.kind, SyntaxKind::Dec_GlobalBinding as u16);
+        let mut children = Vec::new();
+        let mut child = binding.first_child;
+        while child != HELIOS_NONE {
+            let node = &nodes[child as usize];
+            assert_eq!(node.parent, root.first_child);
+            children.push(text(parse, child).unwrap());
+            child = node.next_sibling;
+        }
+        assert_eq!(children, ["let", " ", "a", " ", "=", " ", "1\n"]);
+
+        assert!(nodes.iter().filter(|node| node.is_token).count() > 0);
+        assert_eq!(text(parse, nodes.len() as u32), None);
+        unsafe { helios_parse_free(parse) };
+    }
+
+    #[test]
+    fn test_parse_diagnostics() {
+        let parse = parse("let a = 1 +");
+        let diagnostics = unsafe {
+            let mut len = 0;
+            let diagnostics = helios_parse_diagnostics(parse, &mut len);
+            std::slice::from_raw_parts(diagnostics, len)
+        };
+
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = diagnostics[0];
+        assert_eq!(diagnostic.severity, HeliosSeverity::Error);
+        assert_eq!(string(diagnostic.title), Some("Unexpected end of file"));
+        assert!(string(diagnostic.message).is_some());
+        unsafe { helios_parse_free(parse) };
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let bytes = [b'a', 0xff];
+        let parse = unsafe { helios_parse(bytes.as_ptr().cast(), 2) };
+        assert!(parse.is_null());
+        unsafe { helios_parse_free(parse) };
+
+        assert_eq!(helios_syntax_kind_name(u16::MAX), std::ptr::null());
+        assert_eq!(string(helios_version()), Some(env!("CARGO_PKG_VERSION")));
+    }
+
+    #[test]
+    fn test_header() {
+        let dir = env!("CARGO_MANIFEST_DIR");
+        let config =
+            cbindgen::Config::from_file(format!("{dir}/cbindgen.toml"))
+                .unwrap();
+        let mut header = Vec::new();
+        cbindgen::generate_with_config(dir, config)
+            .unwrap()
+            .write(&mut header);
+        let header = String::from_utf8(header).unwrap();
+
+        let path = format!("{dir}/include/helios.h");
+        if std::env::var_os("UPDATE_EXPECT").is_some() {
+            std::fs::write(&path, &header).unwrap();
+        }
+
+        let expected = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            expected == header,
+            "`include/helios.h` is out of date, run the tests with \
+            `UPDATE_EXPECT=1` to regenerate it"
+        );
+
+        // The system may not have a C compiler to check the header with.
+        let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+        let output = std::process::Command::new(cc)
+            .args(["-fsyntax-only", "-x", "c", &path])
+            .output();
+        if let Ok(output) = output {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(output.status.success(), "{stderr}");
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{prelude_value, unsupported_operator, CodegenError, Result};
+use cranelift_codegen::ir::condcodes::{FloatCC, IntCC};
+use cranelift_codegen::ir::{types, Function, InstBuilder, Value};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use cranelift_module::{FuncId, Module as _};
+use cranelift_object::ObjectModule;
+use helios_hir::{
+    BinaryOp, Body, Const, ConstValues, Expr, ExprId, ItemId, Literal,
+    NameResolutions, UnaryOp,
+};
+use helios_types::{InferenceResult, Ty};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The body of the item being compiled.
+struct ItemBody<'a> {
+    item_id: ItemId,
+    body: &'a Body,
+    resolutions: &'a NameResolutions,
+}
+
+/// Compiles the body of a single function.
+pub(crate) struct FunctionCompiler<'a> {
+    object: &'a mut ObjectModule,
+    builder: FunctionBuilder<'a>,
+    types: &'a InferenceResult,
+    consts: &'a ConstValues,
+    /// The function every global binding is compiled to.
+    functions: &'a HashMap<ItemId, FuncId>,
+}
+
+impl<'a> FunctionCompiler<'a> {
+    pub(crate) fn new(
+        object: &'a mut ObjectModule,
+        func: &'a mut Function,
+        builder_ctx: &'a mut FunctionBuilderContext,
+        types: &'a InferenceResult,
+        consts: &'a ConstValues,
+        functions: &'a HashMap<ItemId, FuncId>,
+    ) -> Self {
+        let mut builder = FunctionBuilder::new(func, builder_ctx);
+        let block = builder.create_block();
+        builder.switch_to_block(block);
+        builder.seal_block(block);
+
+        Self {
+            object,
+            builder,
+            types,
+            consts,
+            functions,
+        }
+    }
+
+    /// Compiles a function returning the value of a global binding.
+    pub(crate) fn compile_binding(
+        mut self,
+        item_id: ItemId,
+        body: &Body,
+        resolutions: &NameResolutions,
+    ) -> Result<()> {
+        let item = ItemBody {
+            item_id,
+            body,
+            resolutions,
+        };
+
+        let value = self.compile_expr(&item, body.value)?;
+        self.builder.ins().return_(&[value]);
+        self.builder.finalize();
+        Ok(())
+    }
+
+    /// Compiles the `main` function from the expressions at the top level of
+    /// the module, followed by a call to the entry point of the program (see
+    /// [`helios_hir::Module::main`]) if there is one.
+    pub(crate) fn compile_main(
+        mut self,
+        bodies: &[(ItemId, Arc<Body>, Arc<NameResolutions>)],
+        entry_point: Option<ItemId>,
+    ) -> Result<()> {
+        let mut exit_code = None;
+        for (item_id, body, resolutions) in bodies {
+            let item = ItemBody {
+                item_id: *item_id,
+                body,
+                resolutions,
+            };
+
+            let value = self.compile_expr(&item, body.value)?;
+            let is_int =
+                self.types.expr_type(*item_id, body.value) == Some(Ty::Int);
+            exit_code = is_int.then_some(value);
+        }
+
+        if let Some(item_id) = entry_point {
+            let id = self.functions[&item_id];
+            let func_ref =
+                self.object.declare_func_in_func(id, self.builder.func);
+            let call = self.builder.ins().call(func_ref, &[]);
+            exit_code = Some(self.builder.inst_results(call)[0]);
+        }
+
+        let exit_code = match exit_code {
+            Some(value) => self.builder.ins().ireduce(types::I32, value),
+            None => self.builder.ins().iconst(types::I32, 0),
+        };
+
+        self.builder.ins().return_(&[exit_code]);
+        self.builder.finalize();
+        Ok(())
+    }
+
+    fn compile_expr(
+        &mut self,
+        item: &ItemBody,
+        expr_id: ExprId,
+    ) -> Result<Value> {
+        let value = match &item.body.exprs[expr_id] {
+            Expr::Literal(Literal::Integer(value)) => {
+                self.builder.ins().iconst(types::I64, *value as i64)
+            }
+            Expr::Literal(Literal::Float(value)) => {
+                self.builder.ins().f64const(value.value())
+            }
+            Expr::Name(name) => {
+                let prelude = prelude_value(
+                    item.resolutions,
+                    self.consts,
+                    item.item_id,
+                    expr_id,
+                );
+                if let Some(value) = prelude {
+                    return Ok(self.compile_const(value));
+                }
+
+                let id = item
+                    .resolutions
+                    .get(expr_id)
+                    .and_then(|target| self.functions.get(&target))
+                    .ok_or_else(|| {
+                        CodegenError::Unsupported(format!(
+                            "`{name}`, as it isn't a global binding"
+                        ))
+                    })?;
+
+                let func_ref =
+                    self.object.declare_func_in_func(*id, self.builder.func);
+                let call = self.builder.ins().call(func_ref, &[]);
+                self.builder.inst_results(call)[0]
+            }
+            Expr::Unary { op, operand } => {
+                let ty = self.expr_type(item, *operand)?;
+                let operand = self.compile_expr(item, *operand)?;
+                match (op, ty) {
+                    (UnaryOp::Neg, Ty::Int) => self.builder.ins().ineg(operand),
+                    (UnaryOp::Neg, Ty::Float) => {
+                        self.builder.ins().fneg(operand)
+                    }
+                    (UnaryOp::Not, Ty::Bool) => {
+                        self.builder.ins().bxor_imm(operand, 1)
+                    }
+                    _ => return Err(unsupported_operator(op.symbol(), ty)),
+                }
+            }
+            Expr::Binary { op, lhs, rhs } => {
+                let ty = self.expr_type(item, *lhs)?;
+                let lhs = self.compile_expr(item, *lhs)?;
+                let rhs = self.compile_expr(item, *rhs)?;
+                self.compile_binary(*op, ty, lhs, rhs)?
+            }
+            Expr::Sequence(exprs) => {
+                let mut value = None;
+                for &expr_id in exprs {
+                    value = Some(self.compile_expr(item, expr_id)?);
+                }
+                value.ok_or_else(|| {
+                    CodegenError::Unsupported("empty sequences".to_string())
+                })?
+            }
+            Expr::Assign { .. } => {
+                return Err(CodegenError::Unsupported(
+                    "assignments".to_string(),
+                ))
+            }
+            Expr::Missing => {
+                return Err(CodegenError::Unsupported(
+                    "missing expressions".to_string(),
+                ))
+            }
+        };
+
+        Ok(value)
+    }
+
+    fn compile_const(&mut self, value: Const) -> Value {
+        match value {
+            Const::Int(value) => self.builder.ins().iconst(types::I64, value),
+            Const::Float(value) => self.builder.ins().f64const(value.value()),
+            Const::Bool(value) => {
+                self.builder.ins().iconst(types::I8, i64::from(value))
+            }
+        }
+    }
+
+    fn compile_binary(
+        &mut self,
+        op: BinaryOp,
+        ty: Ty,
+        lhs: Value,
+        rhs: Value,
+    ) -> Result<Value> {
+        let ins = self.builder.ins();
+        let value = match (ty, op) {
+            (Ty::Int, BinaryOp::Add) => ins.iadd(lhs, rhs),
+            (Ty::Int, BinaryOp::Sub) => ins.isub(lhs, rhs),
+            (Ty::Int, BinaryOp::Mul) => ins.imul(lhs, rhs),
+            (Ty::Int, BinaryOp::Div) => ins.sdiv(lhs, rhs),
+            (Ty::Float, BinaryOp::Add) => ins.fadd(lhs, rhs),
+            (Ty::Float, BinaryOp::Sub) => ins.fsub(lhs, rhs),
+            (Ty::Float, BinaryOp::Mul) => ins.fmul(lhs, rhs),
+            (Ty::Float, BinaryOp::Div) => ins.fdiv(lhs, rhs),
+            (Ty::Int, op) => match condition(op) {
+                Some((cc, _)) => ins.icmp(cc, lhs, rhs),
+                None => return Err(unsupported_operator(op.symbol(), ty)),
+            },
+            (Ty::Float, op) => match condition(op) {
+                Some((_, cc)) => ins.fcmp(cc, lhs, rhs),
+                None => return Err(unsupported_operator(op.symbol(), ty)),
+            },
+            (Ty::Bool, BinaryOp::Eq) => ins.icmp(IntCC::Equal, lhs, rhs),
+            (Ty::Bool, BinaryOp::NotEq) => ins.icmp(IntCC::NotEqual, lhs, rhs),
+            (ty, op) => return Err(unsupported_operator(op.symbol(), ty)),
+        };
+
+        Ok(value)
+    }
+
+    /// The type of an expression, which must be one values can be compiled
+    /// for.
+    fn expr_type(&self, item: &ItemBody, expr_id: ExprId) -> Result<Ty> {
+        match self.types.expr_type(item.item_id, expr_id) {
+            Some(ty @ (Ty::Int | Ty::Float | Ty::Bool)) => Ok(ty),
+            _ => Err(CodegenError::Unsupported(
+                "expressions whose type isn't an `Int`, `Float` or `Bool`"
+                    .to_string(),
+            )),
+        }
+    }
+}
+
+/// The condition codes a comparison operator compares integers and floats
+/// with.
+fn condition(op: BinaryOp) -> Option<(IntCC, FloatCC)> {
+    let condition = match op {
+        BinaryOp::Eq => (IntCC::Equal, FloatCC::Equal),
+        BinaryOp::NotEq => (IntCC::NotEqual, FloatCC::NotEqual),
+        BinaryOp::Lt => (IntCC::SignedLessThan, FloatCC::LessThan),
+        BinaryOp::LtEq => {
+            (IntCC::SignedLessThanOrEqual, FloatCC::LessThanOrEqual)
+        }
+        BinaryOp::Gt => (IntCC::SignedGreaterThan, FloatCC::GreaterThan),
+        BinaryOp::GtEq => {
+            (IntCC::SignedGreaterThanOrEqual, FloatCC::GreaterThanOrEqual)
+        }
+        _ => return None,
+    };
+
+    Some(condition)
+}
@@ -0,0 +1,571 @@
+//! Native code generation for Helios, using Cranelift.
+//!
+//! Helios doesn't have functions of its own yet, so a module is compiled as
+//! follows:
+//!
+//! - Every global binding is compiled to a function that takes no arguments
+//!   and returns the value of the binding, which is called wherever the
+//!   binding is referred to.
+//! - The expressions at the top level of the module are compiled to the
+//!   `main` function of the program, in the order they appear, followed by a
+//!   call to the global binding named `main` if there is one (see
+//!   [`Module::main`]). The value of that binding (which must be an `Int`) is
+//!   the exit code of the program. Without it, the value of the last
+//!   expression is the exit code if it's an `Int` (and `0` otherwise).
+//! - Tests are left out of the program.
+//!
+//! Only expressions of a type that is known (i.e. `Int`, `Float` or `Bool`)
+//! can be compiled, and neither imports nor assignments are supported yet.
+//! Bindings of the prelude are only supported if they're constants, whose
+//! values are compiled wherever they're referred to.
+//!
+//! External functions are called wherever they're referred to, like global
+//! bindings, but are provided by the host: they're C functions taking no
+//! arguments that the object file is linked with, or functions the
+//! WebAssembly module imports from its `env` module.
+//!
+//! A module is either emitted as an object file for the host, which [`link`]
+//! turns into an executable with the system's C compiler, or as a WebAssembly
+//! module (see [`compile_wasm_module`]), which [`WASM_RUNTIME`] runs.
+
+mod function;
+mod wasm;
+
+pub use crate::wasm::{compile_wasm_module, WASM_RUNTIME};
+
+use crate::function::FunctionCompiler;
+use cranelift_codegen::ir::{types, AbiParam, Type};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::FunctionBuilderContext;
+use cranelift_module::{FuncId, Linkage, Module as _};
+use cranelift_object::{ObjectBuilder, ObjectModule};
+use helios_hir::{
+    Body, Const, ConstValues, ExprId, Import, Item, ItemId, Module, Name,
+    NameResolutions,
+};
+use helios_types::{InferenceResult, Ty};
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+pub type Result<T> = std::result::Result<T, CodegenError>;
+
+#[derive(Debug)]
+pub enum CodegenError {
+    /// The module uses something that can't be compiled yet.
+    Unsupported(String),
+    /// Cranelift failed to compile or emit the module.
+    Backend(String),
+    /// The object file couldn't be linked into an executable.
+    Link(String),
+}
+
+impl std::error::Error for CodegenError {}
+
+impl Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported(message) => {
+                write!(f, "Code generation isn't supported yet for {message}")
+            }
+            Self::Backend(message) => {
+                write!(f, "Failed to generate code: {message}")
+            }
+            Self::Link(message) => write!(f, "Failed to link: {message}"),
+        }
+    }
+}
+
+/// How much the code compiled for the host is optimized.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OptLevel {
+    /// The code is compiled as quickly as possible, without optimizations.
+    #[default]
+    None,
+    /// The code is optimized to run as fast as possible.
+    Speed,
+}
+
+impl From<cranelift_module::ModuleError> for CodegenError {
+    fn from(error: cranelift_module::ModuleError) -> Self {
+        Self::Backend(error.to_string())
+    }
+}
+
+/// Compiles a module to an object file for the host, given the types inferred
+/// for it, the values of its constant expressions and a function returning the
+/// body of an item along with what the names in it resolve to.
+///
+/// The module is expected to be free of errors.
+pub fn compile_module<F>(
+    name: &str,
+    opt_level: OptLevel,
+    module: &Module,
+    types: &InferenceResult,
+    consts: &ConstValues,
+    mut body: F,
+) -> Result<Vec<u8>>
+where
+    F: FnMut(ItemId) -> (Arc<Body>, Arc<NameResolutions>),
+{
+    let mut flags = settings::builder();
+    let opt_level = match opt_level {
+        OptLevel::None => "none",
+        OptLevel::Speed => "speed",
+    };
+    flags
+        .set("is_pic", "true")
+        .and_then(|_| flags.set("opt_level", opt_level))
+        .map_err(|error| CodegenError::Backend(error.to_string()))?;
+    let isa = cranelift_native::builder()
+        .map_err(|error| CodegenError::Backend(error.to_string()))?
+        .finish(settings::Flags::new(flags))
+        .map_err(|error| CodegenError::Backend(error.to_string()))?;
+
+    let builder = ObjectBuilder::new(
+        isa,
+        name,
+        cranelift_module::default_libcall_names(),
+    )?;
+    let mut object = ObjectModule::new(builder);
+
+    // Every binding is declared first, so that they can call each other
+    // regardless of the order they're defined in.
+    let mut functions = HashMap::new();
+    for (item_id, binding) in module.bindings() {
+        let name = match &binding.name {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let ty = binding_type(types, item_id, Some(name)).and_then(|ty| {
+            value_type(ty).ok_or_else(|| unsupported_binding(Some(name)))
+        })?;
+
+        let mut signature = object.make_signature();
+        signature.returns.push(AbiParam::new(ty));
+        let symbol = format!("helios.{name}");
+        let id =
+            object.declare_function(&symbol, Linkage::Local, &signature)?;
+        functions.insert(item_id, id);
+    }
+
+    // External functions are C functions the program is linked with, named
+    // after them.
+    for (item_id, external) in module.externals() {
+        let name = match &external.name {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let ty = binding_type(types, item_id, Some(name)).and_then(|ty| {
+            value_type(ty).ok_or_else(|| unsupported_binding(Some(name)))
+        })?;
+
+        let mut signature = object.make_signature();
+        signature.returns.push(AbiParam::new(ty));
+        let id = object.declare_function(
+            name.as_str(),
+            Linkage::Import,
+            &signature,
+        )?;
+        functions.insert(item_id, id);
+    }
+
+    let mut ctx = object.make_context();
+    let mut builder_ctx = FunctionBuilderContext::new();
+    let mut top_level = Vec::new();
+
+    for (item_id, item) in module.items.iter() {
+        match item {
+            Item::Binding(_) => {
+                let id = match functions.get(&item_id) {
+                    Some(id) => *id,
+                    None => continue,
+                };
+
+                let (body, resolutions) = body(item_id);
+                ctx.func.signature = object
+                    .declarations()
+                    .get_function_decl(id)
+                    .signature
+                    .clone();
+                FunctionCompiler::new(
+                    &mut object,
+                    &mut ctx.func,
+                    &mut builder_ctx,
+                    types,
+                    consts,
+                    &functions,
+                )
+                .compile_binding(
+                    item_id,
+                    &body,
+                    &resolutions,
+                )?;
+                define(&mut object, id, &mut ctx)?;
+            }
+            Item::Import(import) => return Err(unsupported_import(import)),
+            Item::External(_) | Item::Test(_) | Item::Macro(_) => {}
+            Item::Expr => top_level.push(item_id),
+        }
+    }
+
+    let entry_point = entry_point(module, types)?;
+    let mut signature = object.make_signature();
+    signature.returns.push(AbiParam::new(types::I32));
+    let main = object.declare_function("main", Linkage::Export, &signature)?;
+    ctx.func.signature = signature;
+
+    let bodies = top_level
+        .into_iter()
+        .map(|item_id| {
+            let (body, resolutions) = body(item_id);
+            (item_id, body, resolutions)
+        })
+        .collect::<Vec<_>>();
+    FunctionCompiler::new(
+        &mut object,
+        &mut ctx.func,
+        &mut builder_ctx,
+        types,
+        consts,
+        &functions,
+    )
+    .compile_main(&bodies, entry_point)?;
+    define(&mut object, main, &mut ctx)?;
+
+    object
+        .finish()
+        .emit()
+        .map_err(|error| CodegenError::Backend(error.to_string()))
+}
+
+fn define(
+    object: &mut ObjectModule,
+    id: FuncId,
+    ctx: &mut cranelift_codegen::Context,
+) -> Result<()> {
+    object.define_function(id, ctx)?;
+    object.clear_context(ctx);
+    Ok(())
+}
+
+/// The Cranelift type values of the given type are represented with, if they
+/// can be compiled.
+fn value_type(ty: Ty) -> Option<Type> {
+    match ty {
+        Ty::Int => Some(types::I64),
+        Ty::Float => Some(types::F64),
+        Ty::Bool => Some(types::I8),
+        Ty::Unit | Ty::Var(_) | Ty::Error => None,
+    }
+}
+
+/// The type of a global binding (or of the value of an external function)
+/// with the given name, which must be one values can be compiled for.
+fn binding_type(
+    types: &InferenceResult,
+    item_id: ItemId,
+    name: Option<&Name>,
+) -> Result<Ty> {
+    match types.binding_type(item_id) {
+        Some(scheme) if scheme.vars.is_empty() => match scheme.ty {
+            ty @ (Ty::Int | Ty::Float | Ty::Bool) => Ok(ty),
+            _ => Err(unsupported_binding(name)),
+        },
+        _ => Err(unsupported_binding(name)),
+    }
+}
+
+/// The entry point of the program (see [`Module::main`]), if the module
+/// declares one, whose value must be an `Int` (the exit code of the program).
+fn entry_point(
+    module: &Module,
+    types: &InferenceResult,
+) -> Result<Option<ItemId>> {
+    let main = match module.main() {
+        Some(main) => main,
+        None => return Ok(None),
+    };
+
+    match types.binding_type(main) {
+        Some(scheme) if scheme.vars.is_empty() && scheme.ty == Ty::Int => {
+            Ok(Some(main))
+        }
+        _ => Err(CodegenError::Unsupported(
+            "`main`, as it isn't an `Int`".to_string(),
+        )),
+    }
+}
+
+fn unsupported_binding(name: Option<&Name>) -> CodegenError {
+    let name = name.map_or("_", |name| name.as_str());
+    CodegenError::Unsupported(format!(
+        "`{name}`, as its type isn't an `Int`, `Float` or `Bool`"
+    ))
+}
+
+/// The value of a name referring to a binding of the prelude, which must be a
+/// constant.
+fn prelude_value(
+    resolutions: &NameResolutions,
+    consts: &ConstValues,
+    item_id: ItemId,
+    expr_id: ExprId,
+) -> Option<Const> {
+    resolutions.prelude(expr_id)?;
+    consts.expr_value(item_id, expr_id)
+}
+
+fn unsupported_operator(symbol: &str, ty: Ty) -> CodegenError {
+    CodegenError::Unsupported(format!("`{symbol}` on values of type `{ty}`"))
+}
+
+fn unsupported_import(import: &Import) -> CodegenError {
+    let path = import
+        .path
+        .iter()
+        .map(|name| name.as_str())
+        .collect::<Vec<_>>()
+        .join(".");
+    CodegenError::Unsupported(format!("imports (such as `import {path}`)"))
+}
+
+/// Links an object file into an executable with the system's C compiler
+/// (`cc`, unless the `CC` environment variable says otherwise).
+pub fn link(object: &Path, output: &Path) -> Result<()> {
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let result = Command::new(&cc)
+        .arg(object)
+        .arg("-o")
+        .arg(output)
+        .output()
+        .map_err(|error| CodegenError::Link(format!("`{cc}`: {error}")))?;
+
+    if result.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        Err(CodegenError::Link(stderr.trim().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helios_hir::{item_nodes, lower_body, lower_module, ModuleScope};
+    use helios_parser::FileId;
+
+    type BodyFn<'a> =
+        dyn FnMut(ItemId) -> (Arc<Body>, Arc<NameResolutions>) + 'a;
+
+    /// The prelude the names of the modules compiled in these tests may refer
+    /// to.
+    const PRELUDE: &str = "let true = 0 = 0\nlet ten = 2 * 5\n";
+
+    /// Lowers a module and infers its types (along with the prelude's), and
+    /// gives them to the given function.
+    fn lower<T>(
+        source: &str,
+        compile: impl FnOnce(
+            &Module,
+            &InferenceResult,
+            &ConstValues,
+            &mut BodyFn,
+        ) -> T,
+    ) -> T {
+        let (prelude, prelude_bodies) = parse(PRELUDE);
+        let prelude_scope = ModuleScope::new(&prelude);
+        let prelude_body = |item_id: ItemId| {
+            let body = Arc::clone(&prelude_bodies[item_id.into_raw() as usize]);
+            let resolutions = NameResolutions::new(&prelude_scope, &body);
+            (body, Arc::new(resolutions))
+        };
+        let prelude_types = helios_types::infer_module(&prelude, prelude_body);
+        let prelude_consts = helios_hir::eval_module(&prelude, prelude_body);
+
+        let (module, bodies) = parse(source);
+        let scope = ModuleScope::new(&module);
+        let mut body = |item_id: ItemId| {
+            let body = Arc::clone(&bodies[item_id.into_raw() as usize]);
+            let resolutions =
+                NameResolutions::with_prelude(&scope, &prelude_scope, &body);
+            (body, Arc::new(resolutions))
+        };
+
+        let types = helios_types::infer_module_with_prelude(
+            &module,
+            &prelude_types,
+            body,
+        );
+        let consts = helios_hir::eval_module_with_prelude(
+            &module,
+            &prelude_consts,
+            body,
+        );
+        compile(&module, &types, &consts, &mut body)
+    }
+
+    fn parse(source: &str) -> (Module, Vec<Arc<Body>>) {
+        let root = helios_parser::parse(FileId::default(), source).syntax();
+        let (module, _) = lower_module(&root);
+        let bodies = item_nodes(&root)
+            .map(|node| Arc::new(lower_body(&node).0))
+            .collect();
+
+        (module, bodies)
+    }
+
+    fn compile(source: &str, opt_level: OptLevel) -> Result<Vec<u8>> {
+        lower(source, |module, types, consts, body| {
+            compile_module("test", opt_level, module, types, consts, body)
+        })
+    }
+
+    /// Compiles a module to WebAssembly and returns the exit code of running
+    /// it (where `external func now: Int` always returns `1000`).
+    fn run_wasm(source: &str) -> Result<i32> {
+        let wasm = lower(source, |module, types, consts, body| {
+            compile_wasm_module(module, types, consts, body)
+        })?;
+
+        let engine = wasmi::Engine::default();
+        let module = wasmi::Module::new(&engine, &wasm).unwrap();
+        let mut store = wasmi::Store::new(&engine, ());
+        let mut linker = wasmi::Linker::<()>::new(&engine);
+        linker.func_wrap("env", "now", || 1000_i64).unwrap();
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .unwrap()
+            .start(&mut store)
+            .unwrap();
+        let main = instance.get_typed_func::<(), i32>(&store, "main").unwrap();
+
+        Ok(main.call(&mut store, ()).unwrap())
+    }
+
+    #[test]
+    fn test_compile_module() {
+        let source = "let f = -(1.5 / 2.0) < 0.0\nlet g = f != (1 >= 2)\n";
+        assert!(!compile(source, OptLevel::None).unwrap().is_empty());
+        assert!(!compile(source, OptLevel::Speed).unwrap().is_empty());
+
+        let error = compile("import foo.bar\n", OptLevel::None).unwrap_err();
+        assert!(matches!(error, CodegenError::Unsupported(_)));
+    }
+
+    #[test]
+    fn test_link_and_run() {
+        // The system may not have a C compiler to link with.
+        if Command::new("cc").arg("--version").output().is_err() {
+            return;
+        }
+
+        let source = "a - b\nlet a = 6 * 7\nlet b = c / 2 + ten\n\
+            let c = (true != (ten < 0); 4)\n";
+        let dir = std::env::temp_dir()
+            .join(format!("helios-codegen-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let object = dir.join("test.o");
+        let executable = dir.join("test");
+
+        std::fs::write(&object, compile(source, OptLevel::Speed).unwrap())
+            .unwrap();
+        link(&object, &executable).unwrap();
+        let status = Command::new(&executable).status().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(status.code(), Some(30));
+    }
+
+    #[test]
+    fn test_compile_wasm_module() {
+        let source = "a - b\nlet a = 6 * 7\nlet b = c / 2\nlet c = 4\n";
+        assert_eq!(run_wasm(source).unwrap(), 40);
+
+        let source = "(-a; 1.5 < a)\nlet a = -(2.0 / 4.0)\n";
+        assert_eq!(run_wasm(source).unwrap(), 0);
+        let source = "a\n(!(1 = 1) != (2.5 >= 2.0))\nlet a = 1\n";
+        assert_eq!(run_wasm(source).unwrap(), 0);
+        assert_eq!(run_wasm("1\n0 - -(1 - 2 * 3)\n").unwrap(), -5);
+
+        let source = "a + ten\nlet a = (true = (ten > 9); 4)\n";
+        assert_eq!(run_wasm(source).unwrap(), 14);
+
+        let error = run_wasm("import foo.bar\n").unwrap_err();
+        assert!(matches!(error, CodegenError::Unsupported(_)));
+    }
+
+    #[test]
+    fn test_compile_entry_point() {
+        // The entry point is called after every top-level expression, and its
+        // value is the exit code.
+        let source = "1\nlet main = a * 2\nlet a = 21\n";
+        assert_eq!(run_wasm(source).unwrap(), 42);
+        assert_eq!(
+            run_wasm("let main = now - 999\nexternal func now: Int\n").unwrap(),
+            1
+        );
+
+        let error = run_wasm("let main = 1.5\n").unwrap_err();
+        assert!(matches!(error, CodegenError::Unsupported(_)));
+        let error = compile("let main = 1 = 1\n", OptLevel::None).unwrap_err();
+        assert!(matches!(error, CodegenError::Unsupported(_)));
+
+        // The system may not have a C compiler to link with.
+        if Command::new("cc").arg("--version").output().is_err() {
+            return;
+        }
+
+        let dir = std::env::temp_dir()
+            .join(format!("helios-codegen-main-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let object = dir.join("test.o");
+        let executable = dir.join("test");
+
+        std::fs::write(
+            &object,
+            compile("0\nlet main = 3\n", OptLevel::None).unwrap(),
+        )
+        .unwrap();
+        link(&object, &executable).unwrap();
+        let status = Command::new(&executable).status().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(status.code(), Some(3));
+    }
+
+    #[test]
+    fn test_compile_externals() {
+        let source = "a - 958\nlet a = now\nexternal func now: Int\n";
+        assert_eq!(run_wasm(source).unwrap(), 42);
+
+        let error = run_wasm("now\nexternal func now: Foo\n").unwrap_err();
+        assert!(matches!(error, CodegenError::Unsupported(_)));
+
+        // The system may not have a C compiler to link with.
+        if Command::new("cc").arg("--version").output().is_err() {
+            return;
+        }
+
+        // `clock` comes from the C standard library, which every program is
+        // linked with.
+        let source = "(clock < 0; 7)\nexternal func clock: Int\n";
+        let dir = std::env::temp_dir()
+            .join(format!("helios-codegen-externals-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let object = dir.join("test.o");
+        let executable = dir.join("test");
+
+        std::fs::write(&object, compile(source, OptLevel::None).unwrap())
+            .unwrap();
+        link(&object, &executable).unwrap();
+        let status = Command::new(&executable).status().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(status.code(), Some(7));
+    }
+}
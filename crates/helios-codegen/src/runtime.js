@@ -0,0 +1,33 @@
+// Runs a Helios program compiled with `helios build --target=wasm32`, in a
+// browser or in Node.js.
+//
+//     import { run } from "./helios.js";
+//     const exitCode = await run(fetch("main.wasm"));
+//
+// The program is given as anything `WebAssembly.instantiate` accepts (such as
+// the bytes of the `.wasm` file), or as a `Response` (or a promise of one).
+//
+// The external functions the program declares (such as `external func now:
+// Int`) are looked up by name in the `host` object:
+//
+//     await run(fetch("main.wasm"), { now: () => BigInt(Date.now()) });
+//
+// A function returning an `Int` must return a `BigInt`, and one returning a
+// `Bool` must return `0` or `1`.
+
+/**
+ * Instantiates a compiled Helios program and runs its `main` function.
+ *
+ * @param {object} host The functions the external functions of the program
+ *     are bound to, by name.
+ * @returns {Promise<number>} The exit code of the program.
+ */
+export async function run(program, host = {}) {
+  let source = await program;
+  if (typeof Response !== "undefined" && source instanceof Response) {
+    source = await source.arrayBuffer();
+  }
+
+  const { instance } = await WebAssembly.instantiate(source, { env: host });
+  return instance.exports.main();
+}
@@ -0,0 +1,335 @@
+use crate::{
+    binding_type, entry_point, prelude_value, unsupported_import,
+    unsupported_operator,
+};
+use crate::{CodegenError, Result};
+use helios_hir::{
+    BinaryOp, Body, Const, ConstValues, Expr, ExprId, Item, ItemId, Literal,
+    Module, NameResolutions, UnaryOp,
+};
+use helios_types::{InferenceResult, Ty};
+use std::collections::HashMap;
+use std::sync::Arc;
+use wasm_encoder::{
+    CodeSection, EntityType, ExportKind, ExportSection, Function,
+    FunctionSection, ImportSection, InstructionSink, TypeSection, ValType,
+};
+
+/// A small JavaScript module running a program compiled to WebAssembly, in a
+/// browser or in Node.js.
+pub const WASM_RUNTIME: &str = include_str!("runtime.js");
+
+/// Compiles a module to a WebAssembly module, given the types inferred for it,
+/// the values of its constant expressions and a function returning the body of
+/// an item along with what the names in it resolve to.
+///
+/// The compiled module doesn't allocate anything, and only imports the external
+/// functions of the module (from the `env` module, by name). It exports a
+/// `main` function, which returns the exit code of the program as an `i32`.
+/// The module is expected to be free of errors.
+pub fn compile_wasm_module<F>(
+    module: &Module,
+    types: &InferenceResult,
+    consts: &ConstValues,
+    mut body: F,
+) -> Result<Vec<u8>>
+where
+    F: FnMut(ItemId) -> (Arc<Body>, Arc<NameResolutions>),
+{
+    let mut type_section = TypeSection::new();
+    let mut import_section = ImportSection::new();
+    let mut function_section = FunctionSection::new();
+    let mut code_section = CodeSection::new();
+
+    // Imported functions come before every other function, so external
+    // functions are given the first indices.
+    let mut functions = HashMap::new();
+    for (item_id, external) in module.externals() {
+        let name = match &external.name {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let ty = binding_type(types, item_id, Some(name))?;
+        let index = functions.len() as u32;
+        let type_index = type_section.len();
+        type_section.ty().function([], [value_type(ty)]);
+        import_section.import(
+            "env",
+            name.as_str(),
+            EntityType::Function(type_index),
+        );
+        functions.insert(item_id, index);
+    }
+
+    // Every binding is given an index first, so that they can call each other
+    // regardless of the order they're defined in.
+    for (item_id, binding) in module.bindings() {
+        if binding.name.is_none() {
+            continue;
+        }
+
+        let ty = binding_type(types, item_id, binding.name.as_ref())?;
+        let index = functions.len() as u32;
+        let type_index = type_section.len();
+        type_section.ty().function([], [value_type(ty)]);
+        function_section.function(type_index);
+        functions.insert(item_id, index);
+    }
+
+    let mut top_level = Vec::new();
+    for (item_id, item) in module.items.iter() {
+        match item {
+            Item::Binding(_) => {
+                if !functions.contains_key(&item_id) {
+                    continue;
+                }
+
+                let (body, resolutions) = body(item_id);
+                let item = ItemBody {
+                    item_id,
+                    body: &body,
+                    resolutions: &resolutions,
+                };
+
+                let mut function = Function::new([]);
+                let mut compiler = FunctionCompiler {
+                    sink: function.instructions(),
+                    types,
+                    consts,
+                    functions: &functions,
+                };
+                compiler.compile_expr(&item, body.value)?;
+                compiler.sink.end();
+                code_section.function(&function);
+            }
+            Item::Import(import) => return Err(unsupported_import(import)),
+            Item::External(_) | Item::Test(_) | Item::Macro(_) => {}
+            Item::Expr => top_level.push(item_id),
+        }
+    }
+
+    let main = functions.len() as u32;
+    let type_index = type_section.len();
+    type_section.ty().function([], [ValType::I32]);
+    function_section.function(type_index);
+
+    let mut function = Function::new([]);
+    let mut compiler = FunctionCompiler {
+        sink: function.instructions(),
+        types,
+        consts,
+        functions: &functions,
+    };
+
+    // The value of the entry point (or else of the last expression, if it's an
+    // `Int`) is the exit code, and every other value is discarded.
+    let entry_point = entry_point(module, types)?;
+    let mut has_exit_code = false;
+    let count = top_level.len();
+    for (index, item_id) in top_level.into_iter().enumerate() {
+        let (body, resolutions) = body(item_id);
+        let item = ItemBody {
+            item_id,
+            body: &body,
+            resolutions: &resolutions,
+        };
+
+        compiler.compile_expr(&item, body.value)?;
+        let is_int = types.expr_type(item_id, body.value) == Some(Ty::Int);
+        if index + 1 == count && is_int && entry_point.is_none() {
+            compiler.sink.i32_wrap_i64();
+            has_exit_code = true;
+        } else {
+            compiler.sink.drop();
+        }
+    }
+
+    if let Some(item_id) = entry_point {
+        compiler.sink.call(functions[&item_id]);
+        compiler.sink.i32_wrap_i64();
+        has_exit_code = true;
+    }
+
+    if !has_exit_code {
+        compiler.sink.i32_const(0);
+    }
+    compiler.sink.end();
+    code_section.function(&function);
+
+    let mut export_section = ExportSection::new();
+    export_section.export("main", ExportKind::Func, main);
+
+    let mut wasm = wasm_encoder::Module::new();
+    wasm.section(&type_section);
+    if !import_section.is_empty() {
+        wasm.section(&import_section);
+    }
+    wasm.section(&function_section)
+        .section(&export_section)
+        .section(&code_section);
+
+    Ok(wasm.finish())
+}
+
+/// The WebAssembly type values of the given type are represented with.
+fn value_type(ty: Ty) -> ValType {
+    match ty {
+        Ty::Int => ValType::I64,
+        Ty::Float => ValType::F64,
+        _ => ValType::I32,
+    }
+}
+
+/// The body of the item being compiled.
+struct ItemBody<'a> {
+    item_id: ItemId,
+    body: &'a Body,
+    resolutions: &'a NameResolutions,
+}
+
+/// Compiles the body of a single function.
+struct FunctionCompiler<'a> {
+    sink: InstructionSink<'a>,
+    types: &'a InferenceResult,
+    consts: &'a ConstValues,
+    /// The index of the function every global binding is compiled to.
+    functions: &'a HashMap<ItemId, u32>,
+}
+
+impl FunctionCompiler<'_> {
+    /// Compiles an expression, leaving its value on the stack.
+    fn compile_expr(&mut self, item: &ItemBody, expr_id: ExprId) -> Result<()> {
+        match &item.body.exprs[expr_id] {
+            Expr::Literal(Literal::Integer(value)) => {
+                self.sink.i64_const(*value as i64);
+            }
+            Expr::Literal(Literal::Float(value)) => {
+                self.sink.f64_const(value.value().into());
+            }
+            Expr::Name(name) => {
+                let prelude = prelude_value(
+                    item.resolutions,
+                    self.consts,
+                    item.item_id,
+                    expr_id,
+                );
+                if let Some(value) = prelude {
+                    match value {
+                        Const::Int(value) => self.sink.i64_const(value),
+                        Const::Float(value) => {
+                            self.sink.f64_const(value.value().into())
+                        }
+                        Const::Bool(value) => {
+                            self.sink.i32_const(i32::from(value))
+                        }
+                    };
+                    return Ok(());
+                }
+
+                let index = item
+                    .resolutions
+                    .get(expr_id)
+                    .and_then(|target| self.functions.get(&target))
+                    .ok_or_else(|| {
+                        CodegenError::Unsupported(format!(
+                            "`{name}`, as it isn't a global binding"
+                        ))
+                    })?;
+                self.sink.call(*index);
+            }
+            Expr::Unary { op, operand } => {
+                let ty = self.expr_type(item, *operand)?;
+                match (op, ty) {
+                    // There's no instruction negating an integer, so it's
+                    // subtracted from zero instead.
+                    (UnaryOp::Neg, Ty::Int) => {
+                        self.sink.i64_const(0);
+                        self.compile_expr(item, *operand)?;
+                        self.sink.i64_sub();
+                    }
+                    (UnaryOp::Neg, Ty::Float) => {
+                        self.compile_expr(item, *operand)?;
+                        self.sink.f64_neg();
+                    }
+                    (UnaryOp::Not, Ty::Bool) => {
+                        self.compile_expr(item, *operand)?;
+                        self.sink.i32_eqz();
+                    }
+                    _ => return Err(unsupported_operator(op.symbol(), ty)),
+                }
+            }
+            Expr::Binary { op, lhs, rhs } => {
+                let ty = self.expr_type(item, *lhs)?;
+                self.compile_expr(item, *lhs)?;
+                self.compile_expr(item, *rhs)?;
+                self.compile_binary(*op, ty)?;
+            }
+            Expr::Sequence(exprs) => {
+                let (last, rest) = exprs.split_last().ok_or_else(|| {
+                    CodegenError::Unsupported("empty sequences".to_string())
+                })?;
+                for &expr_id in rest {
+                    self.compile_expr(item, expr_id)?;
+                    self.sink.drop();
+                }
+                self.compile_expr(item, *last)?;
+            }
+            Expr::Assign { .. } => {
+                return Err(CodegenError::Unsupported(
+                    "assignments".to_string(),
+                ))
+            }
+            Expr::Missing => {
+                return Err(CodegenError::Unsupported(
+                    "missing expressions".to_string(),
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn compile_binary(&mut self, op: BinaryOp, ty: Ty) -> Result<()> {
+        let sink = &mut self.sink;
+        match (ty, op) {
+            (Ty::Int, BinaryOp::Add) => sink.i64_add(),
+            (Ty::Int, BinaryOp::Sub) => sink.i64_sub(),
+            (Ty::Int, BinaryOp::Mul) => sink.i64_mul(),
+            (Ty::Int, BinaryOp::Div) => sink.i64_div_s(),
+            (Ty::Int, BinaryOp::Eq) => sink.i64_eq(),
+            (Ty::Int, BinaryOp::NotEq) => sink.i64_ne(),
+            (Ty::Int, BinaryOp::Lt) => sink.i64_lt_s(),
+            (Ty::Int, BinaryOp::LtEq) => sink.i64_le_s(),
+            (Ty::Int, BinaryOp::Gt) => sink.i64_gt_s(),
+            (Ty::Int, BinaryOp::GtEq) => sink.i64_ge_s(),
+            (Ty::Float, BinaryOp::Add) => sink.f64_add(),
+            (Ty::Float, BinaryOp::Sub) => sink.f64_sub(),
+            (Ty::Float, BinaryOp::Mul) => sink.f64_mul(),
+            (Ty::Float, BinaryOp::Div) => sink.f64_div(),
+            (Ty::Float, BinaryOp::Eq) => sink.f64_eq(),
+            (Ty::Float, BinaryOp::NotEq) => sink.f64_ne(),
+            (Ty::Float, BinaryOp::Lt) => sink.f64_lt(),
+            (Ty::Float, BinaryOp::LtEq) => sink.f64_le(),
+            (Ty::Float, BinaryOp::Gt) => sink.f64_gt(),
+            (Ty::Float, BinaryOp::GtEq) => sink.f64_ge(),
+            (Ty::Bool, BinaryOp::Eq) => sink.i32_eq(),
+            (Ty::Bool, BinaryOp::NotEq) => sink.i32_ne(),
+            (ty, op) => return Err(unsupported_operator(op.symbol(), ty)),
+        };
+
+        Ok(())
+    }
+
+    /// The type of an expression, which must be one values can be compiled
+    /// for.
+    fn expr_type(&self, item: &ItemBody, expr_id: ExprId) -> Result<Ty> {
+        match self.types.expr_type(item.item_id, expr_id) {
+            Some(ty @ (Ty::Int | Ty::Float | Ty::Bool)) => Ok(ty),
+            _ => Err(CodegenError::Unsupported(
+                "expressions whose type isn't an `Int`, `Float` or `Bool`"
+                    .to_string(),
+            )),
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use helios_formatting::FormattedString;
+use serde::{Deserialize, Serialize};
 use std::ops::Range;
 
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Location<FileId> {
     pub file_id: FileId,
     pub range: Range<usize>,
@@ -16,7 +17,34 @@ impl<FileId> Location<FileId> {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+/// A location related to a diagnostic (other than the one it's reported at),
+/// with a short message explaining how it's related.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Label<FileId> {
+    pub location: Location<FileId>,
+    pub message: String,
+}
+
+/// A change to the source text that would fix the issue a diagnostic is about
+/// (such as removing an unused binding), which editors can offer as a quick
+/// fix.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Fix<FileId> {
+    /// A short description of the change.
+    pub title: String,
+    /// Where the text to replace is.
+    pub location: Location<FileId>,
+    /// The text to replace it with.
+    pub replacement: String,
+    /// Whether the change can be applied without anyone looking at it (such
+    /// as by `helios build --fix`), because it can't change what the program
+    /// does.
+    pub machine_applicable: bool,
+}
+
+#[derive(
+    Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize,
+)]
 #[repr(u8)]
 pub enum Severity {
     Bug = 3,
@@ -33,7 +61,7 @@ impl Default for Severity {
 
 /// A diagnostic that provides information about a found issue in a Helios
 /// source file like errors or warnings.
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Diagnostic<FileId> {
     pub location: Location<FileId>,
     pub severity: Severity,
@@ -41,6 +69,8 @@ pub struct Diagnostic<FileId> {
     pub description: Option<FormattedString>,
     pub message: FormattedString,
     pub hint: Option<FormattedString>,
+    pub labels: Vec<Label<FileId>>,
+    pub fixes: Vec<Fix<FileId>>,
 }
 
 impl<FileId> Diagnostic<FileId>
@@ -62,6 +92,8 @@ where
             description: description.into(),
             message: message.into(),
             hint: hint.into(),
+            labels: Vec::new(),
+            fixes: Vec::new(),
         }
     }
 
@@ -129,6 +161,113 @@ where
         self.hint = Some(hint.into());
         self
     }
+
+    /// Adds a label pointing at a related location (such as a previous
+    /// definition of the name the diagnostic is about).
+    pub fn label(
+        mut self,
+        location: Location<FileId>,
+        message: impl Into<String>,
+    ) -> Self {
+        self.labels.push(Label {
+            location,
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Adds a change that would fix the issue, replacing the text at the given
+    /// location.
+    pub fn fix(
+        mut self,
+        title: impl Into<String>,
+        location: Location<FileId>,
+        replacement: impl Into<String>,
+    ) -> Self {
+        self.fixes.push(Fix {
+            title: title.into(),
+            location,
+            replacement: replacement.into(),
+            machine_applicable: false,
+        });
+        self
+    }
+
+    /// Adds a change that would fix the issue without changing what the
+    /// program does, so that it can be applied automatically.
+    pub fn machine_applicable_fix(
+        mut self,
+        title: impl Into<String>,
+        location: Location<FileId>,
+        replacement: impl Into<String>,
+    ) -> Self {
+        self = self.fix(title, location, replacement);
+        if let Some(fix) = self.fixes.last_mut() {
+            fix.machine_applicable = true;
+        }
+        self
+    }
+}
+
+impl<FileId> Location<FileId> {
+    /// Replaces the file of the location with another way of identifying it.
+    pub fn map_file<T>(self, f: impl FnOnce(FileId) -> T) -> Location<T> {
+        Location {
+            file_id: f(self.file_id),
+            range: self.range,
+        }
+    }
+}
+
+impl<FileId> Diagnostic<FileId> {
+    /// The code identifying the kind of the diagnostic on the command line,
+    /// which is its title in kebab-case (such as `unused-binding` for
+    /// "Unused binding").
+    pub fn code(&self) -> String {
+        let mut code = String::new();
+        for word in self.title.split(|c: char| !c.is_alphanumeric()) {
+            if !word.is_empty() {
+                if !code.is_empty() {
+                    code.push('-');
+                }
+                code.push_str(&word.to_lowercase());
+            }
+        }
+
+        code
+    }
+
+    /// Replaces the files the diagnostic refers to with another way of
+    /// identifying them (such as their paths, to store the diagnostic outside
+    /// of the database it was computed with).
+    pub fn map_files<T>(self, mut f: impl FnMut(FileId) -> T) -> Diagnostic<T> {
+        Diagnostic {
+            location: self.location.map_file(&mut f),
+            severity: self.severity,
+            title: self.title,
+            description: self.description,
+            message: self.message,
+            hint: self.hint,
+            labels: self
+                .labels
+                .into_iter()
+                .map(|label| Label {
+                    location: label.location.map_file(&mut f),
+                    message: label.message,
+                })
+                .collect(),
+            fixes: self
+                .fixes
+                .into_iter()
+                .map(|fix| Fix {
+                    title: fix.title,
+                    location: fix.location.map_file(&mut f),
+                    replacement: fix.replacement,
+                    machine_applicable: fix.machine_applicable,
+                })
+                .collect(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -156,4 +295,28 @@ mod tests {
 
         assert!(!is_ok);
     }
+
+    #[test]
+    fn test_map_files() {
+        let location = |file_id| Location::new(file_id, 1..2);
+        let diagnostic = Diagnostic::error("Oops")
+            .location(location(1))
+            .label(location(2), "here")
+            .fix("Remove it", location(1), "");
+
+        let diagnostic = diagnostic.map_files(|file_id| file_id * 10);
+        assert_eq!(diagnostic.location, location(10));
+        assert_eq!(diagnostic.labels[0].location, location(20));
+        assert_eq!(diagnostic.fixes[0].location, location(10));
+    }
+
+    #[test]
+    fn test_code() {
+        let code = |title| Diagnostic::<()>::warning(title).code();
+        assert_eq!(code("Unused binding"), "unused-binding");
+        assert_eq!(
+            code("Test failed: Division by zero"),
+            "test-failed-division-by-zero"
+        );
+    }
 }
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// The identifier of a source file.
+///
+/// File ids are handed out by a [`FileRegistry`] (or by whatever else keeps
+/// track of a set of files, such as [`ManyFiles`]), starting at zero.
+///
+/// [`ManyFiles`]: crate::ManyFiles
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId(pub u32);
+
+impl FileId {
+    /// The index of the file in the list of files it was handed out for.
+    #[inline]
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Interns the paths of source files, handing out a [`FileId`] for each of
+/// them.
+///
+/// Ids are handed out in the order paths are first interned, so interning the
+/// same paths in the same order always gives the same ids.
+#[derive(Clone, Debug, Default)]
+pub struct FileRegistry {
+    paths: Vec<PathBuf>,
+    ids: HashMap<PathBuf, FileId>,
+}
+
+impl FileRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the id of the file at the given path, handing out the next id
+    /// if the path wasn't interned before.
+    pub fn intern(&mut self, path: impl AsRef<Path>) -> FileId {
+        let path = path.as_ref();
+        if let Some(&file_id) = self.ids.get(path) {
+            return file_id;
+        }
+
+        self.add(path)
+    }
+
+    /// Hands out a new id for the file at the given path, even if the path was
+    /// interned before (such as for a new version of a file that should be
+    /// kept apart from the old one). Interning the path afterwards gives back
+    /// the new id.
+    pub fn add(&mut self, path: impl AsRef<Path>) -> FileId {
+        let path = path.as_ref();
+        let file_id = FileId(self.paths.len() as u32);
+        self.paths.push(path.to_path_buf());
+        self.ids.insert(path.to_path_buf(), file_id);
+        file_id
+    }
+
+    /// The id of the file at the given path, if it was interned.
+    pub fn file_id(&self, path: impl AsRef<Path>) -> Option<FileId> {
+        self.ids.get(path.as_ref()).copied()
+    }
+
+    /// The path of the file with the given id, if it was handed out by this
+    /// registry.
+    pub fn path(&self, file_id: FileId) -> Option<&Path> {
+        self.paths.get(file_id.index()).map(PathBuf::as_path)
+    }
+
+    /// The number of ids handed out.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Iterates over the ids handed out (along with their paths) in order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (FileId, &Path)> {
+        self.paths
+            .iter()
+            .enumerate()
+            .map(|(index, path)| (FileId(index as u32), path.as_path()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_registry() {
+        let mut registry = FileRegistry::new();
+        assert!(registry.is_empty());
+
+        let a = registry.intern("src/a.helios");
+        let b = registry.intern(Path::new("src/b.helios"));
+        assert_eq!((a, b), (FileId(0), FileId(1)));
+
+        // Interning a path again gives back the same id.
+        assert_eq!(registry.intern(PathBuf::from("src/a.helios")), a);
+        assert_eq!(registry.len(), 2);
+
+        assert_eq!(registry.file_id("src/b.helios"), Some(b));
+        assert_eq!(registry.file_id("src/c.helios"), None);
+        assert_eq!(registry.path(a), Some(Path::new("src/a.helios")));
+        assert_eq!(registry.path(FileId(2)), None);
+        assert_eq!(
+            registry.iter().collect::<Vec<_>>(),
+            vec![
+                (a, Path::new("src/a.helios")),
+                (b, Path::new("src/b.helios"))
+            ]
+        );
+
+        // Adding a path hands out a new id either way.
+        let c = registry.add("src/a.helios");
+        assert_eq!(c, FileId(2));
+        assert_eq!(registry.intern("src/a.helios"), c);
+        assert_eq!(registry.path(a), registry.path(c));
+    }
+}
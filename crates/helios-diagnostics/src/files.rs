@@ -2,16 +2,26 @@ use std::cmp::min;
 use std::fmt::Display;
 use std::ops::Range;
 
-use crate::{Error, Result};
+use crate::{Error, FileId, Result};
 
 /// Generates all the byte indexes where a line feed character (`'\n'`) appears.
 ///
 /// Carriage returns (`'\r'`) are treated as ordinary characters to keep things
-/// simple and platform-agnostic.
+/// simple and platform-agnostic, so the one of a `\r\n` line break is at the
+/// end of its line (see [`strip_line_break`]).
 fn line_indexes(source: &str) -> impl '_ + Iterator<Item = usize> {
     std::iter::once(0).chain(source.match_indices('\n').map(|(i, _)| i + 1))
 }
 
+/// Strips the line break (a line feed, or a carriage return followed by one)
+/// ending the given line, if it has one.
+pub(crate) fn strip_line_break(line: &str) -> &str {
+    match line.strip_suffix('\n') {
+        Some(line) => line.strip_suffix('\r').unwrap_or(line),
+        None => line,
+    }
+}
+
 /// A trait to inspect the texts, lines and columns of files.
 ///
 /// This trait's primary purpose is to get the line and column positions of a
@@ -91,11 +101,12 @@ pub trait FileInspector<'a> {
         // If the current line's range is empty, we must be looking at an empty
         // line (i.e. a LF immediately followed by EOF). If this is the case,
         // we'll get the last line's range and get its last column position
-        // (i.e. the index of the LF character).
+        // (i.e. the index of the line break, which may be a CRLF).
         if line_range.is_empty() {
             let last_line_index = line_index.checked_sub(1).unwrap_or_default();
             let last_line_range = self.line_range(id, last_line_index)?;
-            return Ok(last_line_range.end);
+            let last_line = &source.as_ref()[last_line_range];
+            return Ok(strip_line_break(last_line).chars().count());
         }
 
         let end_index = min(byte_index, min(line_range.end, source_len));
@@ -228,9 +239,6 @@ where
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
-pub struct ManyFilesId(usize);
-
 /// An abstraction over multiple related Helios source files.
 ///
 /// Use this struct to inspect a Helios program that consists of multiple files
@@ -251,14 +259,14 @@ where
         Self { files: Vec::new() }
     }
 
-    pub fn add(&mut self, name: Name, source: Source) -> ManyFilesId {
-        let file_id = self.files.len();
+    pub fn add(&mut self, name: Name, source: Source) -> FileId {
+        let file_id = FileId(self.files.len() as u32);
         self.files.push(OneFile::new(name, source));
-        ManyFilesId(file_id)
+        file_id
     }
 
-    pub fn get(&self, file_id: ManyFilesId) -> Result<&OneFile<Name, Source>> {
-        self.files.get(file_id.0).ok_or(Error::MissingFile)
+    pub fn get(&self, file_id: FileId) -> Result<&OneFile<Name, Source>> {
+        self.files.get(file_id.index()).ok_or(Error::MissingFile)
     }
 }
 
@@ -277,7 +285,7 @@ where
     Name: 'a + std::fmt::Display + Clone,
     Source: 'a + AsRef<str>,
 {
-    type FileId = ManyFilesId;
+    type FileId = FileId;
     type Name = Name;
     type Source = &'a str;
 
@@ -418,6 +426,29 @@ mod tests {
         check_last_line_is_empty(&file_b, FILE_B_LINE_INDEXES, false);
     }
 
+    #[test]
+    fn test_column_index() {
+        let file_a = OneFile::new(FILE_A_NAME, FILE_A_SOURCE);
+        assert_eq!(file_a.column_index((), 4), Ok(4));
+        assert_eq!(file_a.column_index((), 9), Ok(9)); // "\n"
+        assert_eq!(file_a.column_index((), 19), Ok(9)); // "\r\n"
+
+        // The empty line at the end of a file is at the line break before it,
+        // whether or not it's a CRLF.
+        assert_eq!(file_a.column_index((), 37), Ok(3));
+        let file_c = OneFile::new("c.hl", "let a = 0\r\nfoo\r\n");
+        assert_eq!(file_c.column_index((), 16), Ok(3));
+    }
+
+    #[test]
+    fn test_strip_line_break() {
+        assert_eq!(strip_line_break("foo\n"), "foo");
+        assert_eq!(strip_line_break("foo\r\n"), "foo");
+        assert_eq!(strip_line_break("foo\r"), "foo\r");
+        assert_eq!(strip_line_break("\r\r\n"), "\r");
+        assert_eq!(strip_line_break("foo"), "foo");
+    }
+
     #[test]
     fn test_many_files() {
         let mut files = ManyFiles::new();
@@ -1,13 +1,15 @@
 pub mod diagnostic;
+pub mod file_id;
 pub mod files;
+pub mod source_map;
 
 use colored::*;
 use std::{fmt::Display, io::Write};
 
 pub use crate::diagnostic::*;
+pub use crate::file_id::*;
 pub use crate::files::*;
-
-use crate::files::FileInspector;
+pub use crate::source_map::*;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -51,6 +53,32 @@ impl From<std::fmt::Error> for Error {
     }
 }
 
+/// Emits every diagnostic in the given iterator, skipping diagnostics that
+/// point at a location that has already been reported.
+///
+/// Returns the number of diagnostics that were emitted.
+pub fn emit_all<'a, 'd, F, I>(
+    f: &mut dyn Write,
+    inspector: &'a F,
+    diagnostics: I,
+) -> Result<usize>
+where
+    F: FileInspector<'a>,
+    F::FileId: 'd,
+    I: IntoIterator<Item = &'d Diagnostic<F::FileId>>,
+{
+    let mut emitted_locations = Vec::new();
+
+    for diagnostic in diagnostics {
+        if !emitted_locations.contains(&&diagnostic.location) {
+            emitted_locations.push(&diagnostic.location);
+            emit(f, inspector, diagnostic)?;
+        }
+    }
+
+    Ok(emitted_locations.len())
+}
+
 pub fn emit<'a, F: FileInspector<'a>>(
     f: &mut dyn Write,
     inspector: &'a F,
@@ -58,19 +86,11 @@ pub fn emit<'a, F: FileInspector<'a>>(
 ) -> Result<()> {
     let file_id = diagnostic.location.file_id;
     let file_name = inspector.name(file_id)?;
-    let source = inspector.source(file_id)?;
 
     let severity = diagnostic.severity;
-    let error_range = diagnostic.location.range.clone();
-    let error_start = error_range.start;
-    let error_end = error_range.end;
-
-    let line_index = inspector.line_index(file_id, error_range.start)?;
-    let line_range = inspector.line_range(file_id, line_index)?;
-    let line_number = line_index + 1;
-
+    let error_start = diagnostic.location.range.start;
+    let line_number = inspector.line_index(file_id, error_start)? + 1;
     let column_start = inspector.column_number(file_id, error_start)?;
-    let column_end = inspector.column_number(file_id, error_end)?;
 
     let (color, header, underline) = {
         let make_header = |msg: String| {
@@ -127,6 +147,64 @@ pub fn emit<'a, F: FileInspector<'a>>(
         writeln!(f, "{}\n", wrap!(description))?;
     }
 
+    emit_snippet(f, inspector, &diagnostic.location, underline, "", color)?;
+
+    writeln!(f, "{}\n", wrap!(diagnostic.message).trim_end())?;
+
+    for label in &diagnostic.labels {
+        let location = &label.location;
+        if location.file_id != file_id {
+            let file_name = inspector.name(location.file_id)?;
+            let line_number = inspector
+                .line_number(location.file_id, location.range.start)?;
+            let column_number = inspector
+                .column_number(location.file_id, location.range.start)?;
+            let location_str =
+                format!("-> {file_name}:{line_number}:{column_number}");
+            writeln!(f, "{}", location_str.dimmed())?;
+        }
+
+        let message = format!(" {}", label.message);
+        emit_snippet(f, inspector, location, "-", &message, Color::Blue)?;
+        writeln!(f)?;
+    }
+
+    if let Some(hint) = &diagnostic.hint {
+        writeln!(f, "{}\n", wrap!("{}: {hint}", "Hint".underline()))?;
+    }
+
+    Ok(())
+}
+
+/// Emits the line of source text at the given location (with a gutter showing
+/// the line number), followed by a line underlining the location with the
+/// given string and ending with the given suffix.
+fn emit_snippet<'a, F: FileInspector<'a>>(
+    f: &mut dyn Write,
+    inspector: &'a F,
+    location: &Location<F::FileId>,
+    underline: &str,
+    suffix: &str,
+    color: Color,
+) -> Result<()> {
+    let file_id = location.file_id;
+    let source = inspector.source(file_id)?;
+    let range = location.range.clone();
+
+    let line_index = inspector.line_index(file_id, range.start)?;
+    let line_range = inspector.line_range(file_id, line_index)?;
+    let line_number = line_index + 1;
+
+    // Only the first line of a location spanning multiple lines is shown, so
+    // we'll underline up to the end of that line (counting its line break as
+    // a single column, even if it's a CRLF).
+    let line_text = &source.as_ref()[line_range.clone()];
+    let line_end = line_range.start + files::strip_line_break(line_text).len();
+    let end = std::cmp::min(range.end, line_end + 1);
+
+    let column_start = inspector.column_number(file_id, range.start)?;
+    let column_end = inspector.column_number(file_id, end)?;
+
     let gutter = format!("{line_number:>4} | ");
     let line = &source.as_ref()[line_range].trim_end(); // remove trailing LF
     writeln!(f, "{}{line}", gutter.dimmed())?;
@@ -134,16 +212,12 @@ pub fn emit<'a, F: FileInspector<'a>>(
     // `column_start` is indexed by 1
     let offset = " ".repeat(gutter.len() + column_start - 1);
     // The difference of the column positions, or 1, whichever is larger
-    let underline_count = std::cmp::max(1, column_end - column_start);
-    // Underline string repeated `underline_count` times
-    let underline = underline.repeat(underline_count).color(color);
-    writeln!(f, "{offset}{underline}")?;
+    let underline_count =
+        std::cmp::max(1, column_end.saturating_sub(column_start));
 
-    writeln!(f, "{}\n", wrap!(diagnostic.message).trim_end())?;
-
-    if let Some(hint) = &diagnostic.hint {
-        writeln!(f, "{}\n", wrap!("{}: {hint}", "Hint".underline()))?;
-    }
+    // Underline string repeated `underline_count` times
+    let underline = format!("{}{suffix}", underline.repeat(underline_count));
+    writeln!(f, "{offset}{}", underline.color(color))?;
 
     Ok(())
 }
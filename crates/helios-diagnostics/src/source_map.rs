@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::{Diagnostic, FileId, Location};
+
+/// Keeps track of where the text of generated files (such as the output of a
+/// macro expansion) comes from, so that diagnostics about generated code can
+/// be reported against the files the user actually wrote.
+///
+/// Generated files are given a [`FileId`] like any other file. Their text is
+/// then mapped back to the text it was generated from in one of two ways:
+///
+/// - A _copy_ is text copied verbatim from another file, much like the lines
+///   following a `#line` directive in C. Every offset inside of it maps to the
+///   same offset in the original text.
+/// - An _expansion_ is text generated from another piece of text (such as the
+///   code a macro call expands to), which as a whole maps to the whole of the
+///   original text.
+///
+/// The original text may itself be generated, in which case locations are
+/// mapped through every layer of generated code (see [`SourceMap::original`]).
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    mappings: HashMap<FileId, Vec<Mapping>>,
+}
+
+#[derive(Clone, Debug)]
+struct Mapping {
+    generated: Range<usize>,
+    original: Location<FileId>,
+    kind: MappingKind,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum MappingKind {
+    Copy,
+    Expansion,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the text at the given location was copied verbatim from
+    /// the text starting at the given offset of the original file.
+    pub fn add_copy(
+        &mut self,
+        generated: Location<FileId>,
+        original_file_id: FileId,
+        original_start: usize,
+    ) {
+        let len = generated.range.len();
+        let original = Location::new(
+            original_file_id,
+            original_start..original_start + len,
+        );
+        self.add(generated, original, MappingKind::Copy);
+    }
+
+    /// Records that the text at the given location was generated from the text
+    /// at the original location.
+    pub fn add_expansion(
+        &mut self,
+        generated: Location<FileId>,
+        original: Location<FileId>,
+    ) {
+        self.add(generated, original, MappingKind::Expansion);
+    }
+
+    fn add(
+        &mut self,
+        generated: Location<FileId>,
+        original: Location<FileId>,
+        kind: MappingKind,
+    ) {
+        self.mappings
+            .entry(generated.file_id)
+            .or_default()
+            .push(Mapping {
+                generated: generated.range,
+                original,
+                kind,
+            });
+    }
+
+    /// Whether any of the text of the given file is known to be generated.
+    pub fn is_generated(&self, file_id: FileId) -> bool {
+        self.mappings.contains_key(&file_id)
+    }
+
+    /// Maps a location in generated code to where it was generated from, going
+    /// back a single layer of generated code.
+    ///
+    /// Returns `None` if the location isn't inside of a copy or an expansion
+    /// (where the innermost one is used if there are several).
+    pub fn map_once(
+        &self,
+        location: &Location<FileId>,
+    ) -> Option<Location<FileId>> {
+        self.mapping(location)
+            .map(|mapping| mapping.map(&location.range))
+    }
+
+    /// Maps a location through every layer of generated code, to the text the
+    /// user actually wrote. Locations outside of generated code are left as
+    /// they are.
+    pub fn original(&self, location: Location<FileId>) -> Location<FileId> {
+        // Only requiring every layer to be a copy can fail.
+        self.resolve(location, false).unwrap()
+    }
+
+    /// Maps the location of a diagnostic (along with the locations of its
+    /// labels and fixes) to the text the user actually wrote.
+    ///
+    /// A fix can only be applied to the original text if everything it
+    /// replaces was copied from there, so any other fix is dropped.
+    pub fn map_diagnostic(
+        &self,
+        diagnostic: Diagnostic<FileId>,
+    ) -> Diagnostic<FileId> {
+        let labels = diagnostic
+            .labels
+            .into_iter()
+            .map(|mut label| {
+                label.location = self.original(label.location);
+                label
+            })
+            .collect();
+        let fixes = diagnostic
+            .fixes
+            .into_iter()
+            .filter_map(|mut fix| {
+                fix.location = self.resolve(fix.location, true)?;
+                Some(fix)
+            })
+            .collect();
+
+        Diagnostic {
+            location: self.original(diagnostic.location),
+            labels,
+            fixes,
+            ..diagnostic
+        }
+    }
+
+    /// Maps a location through every layer of generated code, giving up if
+    /// `copies_only` is set and one of the layers is an expansion.
+    fn resolve(
+        &self,
+        mut location: Location<FileId>,
+        copies_only: bool,
+    ) -> Option<Location<FileId>> {
+        // Every layer maps to another file, so there can't be more layers than
+        // there are mappings (unless some of them form a cycle).
+        let layers = self.mappings.values().map(Vec::len).sum::<usize>();
+        for _ in 0..layers {
+            let Some(mapping) = self.mapping(&location) else {
+                break;
+            };
+            if copies_only && mapping.kind == MappingKind::Expansion {
+                return None;
+            }
+
+            location = mapping.map(&location.range);
+        }
+
+        Some(location)
+    }
+
+    /// The innermost mapping the whole of the given location is inside of.
+    fn mapping(&self, location: &Location<FileId>) -> Option<&Mapping> {
+        let range = &location.range;
+        self.mappings
+            .get(&location.file_id)?
+            .iter()
+            .filter(|mapping| {
+                mapping.generated.start <= range.start
+                    && range.end <= mapping.generated.end
+            })
+            .min_by_key(|mapping| mapping.generated.len())
+    }
+}
+
+impl Mapping {
+    fn map(&self, range: &Range<usize>) -> Location<FileId> {
+        match self.kind {
+            MappingKind::Copy => {
+                let offset = |offset| {
+                    offset - self.generated.start + self.original.range.start
+                };
+                Location::new(
+                    self.original.file_id,
+                    offset(range.start)..offset(range.end),
+                )
+            }
+            MappingKind::Expansion => self.original.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_map() {
+        let (user, expanded, output) = (FileId(0), FileId(1), FileId(2));
+        let mut source_map = SourceMap::new();
+
+        // `expanded` copies `user` from offset 100 onwards, except for the
+        // expansion of a macro call at 120..130.
+        source_map.add_copy(Location::new(expanded, 0..50), user, 100);
+        source_map.add_expansion(
+            Location::new(expanded, 20..40),
+            Location::new(user, 120..130),
+        );
+        // `output` copies all of `expanded`.
+        source_map.add_copy(Location::new(output, 10..60), expanded, 0);
+
+        assert!(source_map.is_generated(output));
+        assert!(!source_map.is_generated(user));
+
+        let map_once = |file_id, range| {
+            source_map.map_once(&Location::new(file_id, range))
+        };
+        assert_eq!(
+            map_once(expanded, 5..8),
+            Some(Location::new(user, 105..108))
+        );
+        assert_eq!(
+            map_once(expanded, 25..30),
+            Some(Location::new(user, 120..130))
+        );
+        assert_eq!(
+            map_once(output, 15..18),
+            Some(Location::new(expanded, 5..8))
+        );
+        assert_eq!(map_once(output, 0..15), None);
+        assert_eq!(map_once(user, 0..5), None);
+
+        let original =
+            |file_id, range| source_map.original(Location::new(file_id, range));
+        assert_eq!(original(output, 15..18), Location::new(user, 105..108));
+        assert_eq!(original(output, 35..40), Location::new(user, 120..130));
+        assert_eq!(original(output, 0..5), Location::new(output, 0..5));
+    }
+
+    #[test]
+    fn test_map_diagnostic() {
+        let (user, generated) = (FileId(0), FileId(1));
+        let mut source_map = SourceMap::new();
+        source_map.add_copy(Location::new(generated, 0..10), user, 40);
+        source_map.add_expansion(
+            Location::new(generated, 10..30),
+            Location::new(user, 50..60),
+        );
+
+        let diagnostic = Diagnostic::error("Unused binding")
+            .location(Location::new(generated, 12..15))
+            .label(Location::new(generated, 2..4), "declared here")
+            .fix("Remove it", Location::new(generated, 0..10), "")
+            .fix("Rename it", Location::new(generated, 12..15), "_a");
+        let diagnostic = source_map.map_diagnostic(diagnostic);
+
+        assert_eq!(diagnostic.location, Location::new(user, 50..60));
+        assert_eq!(diagnostic.labels[0].location, Location::new(user, 42..44));
+        assert_eq!(diagnostic.fixes.len(), 1);
+        assert_eq!(diagnostic.fixes[0].location, Location::new(user, 40..50));
+    }
+}
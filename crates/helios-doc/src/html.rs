@@ -0,0 +1,145 @@
+use crate::{ItemDoc, ModuleDoc};
+use std::fmt::Write;
+
+const STYLE: &str = "\
+body { font-family: sans-serif; max-width: 48rem; margin: 2rem auto; \
+padding: 0 1rem; line-height: 1.5; color: #222; }
+code { font-family: monospace; background: #f3f3f3; padding: 0 0.2em; }
+h2 code { background: none; padding: 0; }
+section { border-top: 1px solid #ddd; padding-top: 0.5rem; }
+nav a, h2 a { color: inherit; text-decoration: none; }
+.kind { color: #888; font-size: 0.8em; text-transform: uppercase; }
+";
+
+/// Renders the page listing every documented module.
+pub fn render_index(modules: &[ModuleDoc]) -> String {
+    let mut body = String::from("<h1>Modules</h1>\n<ul>\n");
+    for module in modules {
+        let path = escape(&module.path);
+        let count = module.items.len();
+        let suffix = if count == 1 { "" } else { "s" };
+        writeln!(
+            body,
+            "<li><a href=\"{path}.html\"><code>{path}</code></a> \
+            ({count} item{suffix})</li>"
+        )
+        .unwrap();
+    }
+
+    body.push_str("</ul>\n");
+    page("Modules", &body)
+}
+
+/// Renders the page documenting a module.
+pub fn render_module(module: &ModuleDoc) -> String {
+    let path = escape(&module.path);
+    let mut body = format!(
+        "<nav><a href=\"index.html\">Modules</a></nav>\n\
+        <h1>Module <code>{path}</code></h1>\n"
+    );
+
+    if module.items.is_empty() {
+        body.push_str("<p>This module doesn't declare anything.</p>\n");
+    }
+
+    for item in &module.items {
+        render_item(&mut body, item);
+    }
+
+    page(&module.path, &body)
+}
+
+fn render_item(out: &mut String, item: &ItemDoc) {
+    let id = format!("{}.{}", item.kind.as_str(), escape(&item.name));
+    writeln!(
+        out,
+        "<section id=\"{id}\">\n<span class=\"kind\">{kind}</span>\n\
+        <h2><a href=\"#{id}\"><code>{signature}</code></a></h2>",
+        kind = item.kind.as_str(),
+        signature = escape(&item.signature),
+    )
+    .unwrap();
+
+    if let Some(doc) = &item.doc {
+        render_doc(out, doc);
+    }
+
+    out.push_str("</section>\n");
+}
+
+/// Renders the paragraphs of a documentation comment, where text between
+/// backticks is code.
+fn render_doc(out: &mut String, doc: &str) {
+    for paragraph in doc.split("\n\n") {
+        let paragraph = paragraph.trim();
+        if paragraph.is_empty() {
+            continue;
+        }
+
+        out.push_str("<p>");
+        for (i, part) in paragraph.split('`').enumerate() {
+            if i % 2 == 1 {
+                write!(out, "<code>{}</code>", escape(part)).unwrap();
+            } else {
+                out.push_str(&escape(part));
+            }
+        }
+        out.push_str("</p>\n");
+    }
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n\
+        <meta charset=\"utf-8\">\n\
+        <title>{title} - Helios documentation</title>\n\
+        <style>\n{STYLE}</style>\n</head>\n<body>\n{body}</body>\n</html>\n",
+        title = escape(title),
+    )
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ItemKind;
+
+    #[test]
+    fn test_render_module() {
+        let module = ModuleDoc {
+            path: "main".to_string(),
+            items: vec![ItemDoc {
+                kind: ItemKind::Binding,
+                name: "a".to_string(),
+                signature: "let a: Int".to_string(),
+                doc: Some("Is `a < b`?\n\nMaybe <not>.".to_string()),
+            }],
+        };
+
+        let html = render_module(&module);
+        assert!(html.contains("<title>main - Helios documentation</title>"));
+        assert!(html.contains("<section id=\"binding.a\">"));
+        assert!(html.contains("<code>let a: Int</code>"));
+        assert!(html.contains("<p>Is <code>a &lt; b</code>?</p>\n"));
+        assert!(html.contains("<p>Maybe &lt;not&gt;.</p>\n"));
+
+        let index = render_index(&[module]);
+        assert!(index
+            .contains("<a href=\"main.html\"><code>main</code></a> (1 item)"));
+    }
+}
@@ -0,0 +1,199 @@
+//! Documentation generation for Helios.
+//!
+//! The items a module declares are collected in a [`ModuleDoc`] (see
+//! [`module_doc`]), along with their signatures and the documentation
+//! comments directly above them. A set of modules is then written as a static
+//! site (see [`write_site`]), made of an index of every module and of an HTML
+//! page and a JSON file per module.
+//!
+//! Documentation comments are rendered as paragraphs (separated by blank
+//! lines), where text between backticks is rendered as code.
+
+mod html;
+
+pub use crate::html::{render_index, render_module};
+
+use helios_hir::Item;
+use helios_query::{Bindings, FileId, Types};
+use serde::Serialize;
+use std::path::Path;
+
+/// The documentation of a module.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ModuleDoc {
+    /// The path of the module (such as `foo.bar`).
+    pub path: String,
+    /// The documented items of the module, in the order they appear.
+    pub items: Vec<ItemDoc>,
+}
+
+/// The documentation of a global binding or an external function.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ItemDoc {
+    pub kind: ItemKind,
+    pub name: String,
+    /// How the item is declared, along with its type (such as `let a: Int`).
+    pub signature: String,
+    /// The documentation comments directly above the item, if there are any.
+    pub doc: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemKind {
+    Binding,
+    External,
+}
+
+impl ItemKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ItemKind::Binding => "binding",
+            ItemKind::External => "external",
+        }
+    }
+}
+
+/// Collects the documentation of the global bindings and external functions
+/// of a file. Imports, tests and expressions aren't documented, and neither
+/// are declarations without a name.
+pub fn module_doc<DB>(db: &DB, file_id: FileId) -> ModuleDoc
+where
+    DB: Bindings + Types + ?Sized,
+{
+    let module = db.hir_module(file_id);
+    let types = db.infer(file_id);
+    let docs = db.item_docs(file_id);
+
+    let items = module
+        .items
+        .iter()
+        .zip(docs.iter())
+        .filter_map(|((item_id, item), doc)| {
+            let (kind, name, signature) = match item {
+                Item::Binding(binding) => {
+                    let name = binding.name.as_ref()?;
+                    let signature = match types.binding_type(item_id) {
+                        Some(scheme) => format!("let {name}: {scheme}"),
+                        None => format!("let {name}"),
+                    };
+                    (ItemKind::Binding, name, signature)
+                }
+                Item::External(external) => {
+                    let name = external.name.as_ref()?;
+                    let signature = match &external.ty {
+                        Some(ty) => format!("external func {name}: {ty}"),
+                        None => format!("external func {name}"),
+                    };
+                    (ItemKind::External, name, signature)
+                }
+                Item::Import(_)
+                | Item::Test(_)
+                | Item::Macro(_)
+                | Item::Expr => return None,
+            };
+
+            Some(ItemDoc {
+                kind,
+                name: name.to_string(),
+                signature,
+                doc: doc.clone(),
+            })
+        })
+        .collect();
+
+    ModuleDoc {
+        path: db.module_of_file(file_id).join("."),
+        items,
+    }
+}
+
+/// Serializes the documentation of a module to (pretty-printed) JSON.
+pub fn to_json(module: &ModuleDoc) -> String {
+    serde_json::to_string_pretty(module)
+        .expect("The documentation of a module can always be serialized")
+}
+
+/// Writes the documentation of the given modules to a directory (creating it
+/// if needed): `index.html` lists every module, whose documentation is written
+/// to `<path>.html` and `<path>.json`.
+pub fn write_site(modules: &[ModuleDoc], dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(dir.join("index.html"), render_index(modules))?;
+
+    for module in modules {
+        let html = render_module(module);
+        std::fs::write(dir.join(format!("{}.html", module.path)), html)?;
+        std::fs::write(
+            dir.join(format!("{}.json", module.path)),
+            to_json(module),
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helios_query::{HeliosDatabase, Input};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_module_doc() {
+        let source = "## The answer.\nlet a = 42\n\n\
+            ## The current time,\n## in seconds.\nexternal func now: Int\n\
+            import foo\nlet b = a = 1\n1 + a\n";
+        let mut db = HeliosDatabase::default();
+        db.set_file_name(FileId(0), Arc::new("src/main.helios".to_string()));
+        db.set_source(FileId(0), Arc::new(source.to_string()));
+
+        let module = module_doc(&db, FileId(0));
+        assert_eq!(module.path, "src.main");
+
+        let items = module
+            .items
+            .iter()
+            .map(|item| {
+                (item.kind, item.signature.as_str(), item.doc.as_deref())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            items,
+            [
+                (ItemKind::Binding, "let a: Int", Some("The answer.")),
+                (
+                    ItemKind::External,
+                    "external func now: Int",
+                    Some("The current time,\nin seconds.")
+                ),
+                (ItemKind::Binding, "let b: Bool", None),
+            ]
+        );
+
+        let json = to_json(&module);
+        assert!(json.contains(r#""path": "src.main""#), "{json}");
+        assert!(json.contains(r#""kind": "external""#), "{json}");
+        assert!(json.contains(r#""doc": null"#), "{json}");
+    }
+
+    #[test]
+    fn test_write_site() {
+        let dir = std::env::temp_dir()
+            .join(format!("helios-doc-test-{}", std::process::id()));
+        let module = ModuleDoc {
+            path: "foo.bar".to_string(),
+            items: Vec::new(),
+        };
+
+        write_site(&[module], &dir).unwrap();
+        let mut files = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files, ["foo.bar.html", "foo.bar.json", "index.html"]);
+    }
+}
@@ -1,13 +1,20 @@
 use colored::*;
+use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display};
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum FormattedStringSegment {
     LineBreak,
     Text(String),
     Code(String),
     CodeBlock(String),
     List(Vec<FormattedString>),
+    /// Two pieces of code that were expected to be the same (such as two
+    /// types that couldn't be unified), shown one after the other.
+    Diff {
+        expected: String,
+        found: String,
+    },
 }
 
 impl FormattedStringSegment {
@@ -26,6 +33,13 @@ impl FormattedStringSegment {
     pub fn list(list: impl Into<Vec<FormattedString>>) -> Self {
         Self::List(list.into())
     }
+
+    pub fn diff(expected: impl Into<String>, found: impl Into<String>) -> Self {
+        Self::Diff {
+            expected: expected.into(),
+            found: found.into(),
+        }
+    }
 }
 
 impl Display for FormattedStringSegment {
@@ -42,11 +56,18 @@ impl Display for FormattedStringSegment {
                 }
             }
             Self::CodeBlock(block) => {
-                if colorize {
-                    write!(f, "    {}", block.yellow())
-                } else {
-                    write!(f, "    {block}")
+                for (i, line) in block.lines().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+
+                    if colorize {
+                        write!(f, "    {}", line.yellow())?;
+                    } else {
+                        write!(f, "    {line}")?;
+                    }
                 }
+                Ok(())
             }
             Self::List(lines) => {
                 for line in lines {
@@ -54,6 +75,54 @@ impl Display for FormattedStringSegment {
                 }
                 Ok(())
             }
+            Self::Diff { expected, found } => {
+                if colorize {
+                    writeln!(f, "    {}", format!("- {expected}").green())?;
+                    write!(f, "    {}", format!("+ {found}").red())
+                } else {
+                    writeln!(f, "    - {expected}")?;
+                    write!(f, "    + {found}")
+                }
+            }
+        }
+    }
+}
+
+impl FormattedStringSegment {
+    fn write_markdown(&self, markdown: &mut String) {
+        match self {
+            // Consecutive line breaks (such as between a code block and a
+            // list) would otherwise pile up, so they're collapsed into one.
+            Self::LineBreak if !markdown.is_empty() => {
+                markdown.truncate(markdown.trim_end_matches('\n').len());
+                markdown.push_str("\n\n");
+            }
+            Self::LineBreak => {}
+            Self::Text(text) => markdown.push_str(text),
+            Self::Code(code) => {
+                markdown.push('`');
+                markdown.push_str(code);
+                markdown.push('`');
+            }
+            Self::CodeBlock(block) => {
+                markdown.push_str("```helios\n");
+                markdown.push_str(block);
+                markdown.push_str("\n```");
+            }
+            Self::List(items) => {
+                for item in items {
+                    markdown.push_str("- ");
+                    markdown.push_str(&item.to_markdown());
+                    markdown.push('\n');
+                }
+            }
+            Self::Diff { expected, found } => {
+                markdown.push_str("```diff\n- ");
+                markdown.push_str(expected);
+                markdown.push_str("\n+ ");
+                markdown.push_str(found);
+                markdown.push_str("\n```");
+            }
         }
     }
 }
@@ -70,7 +139,7 @@ impl From<&str> for FormattedStringSegment {
     }
 }
 
-#[derive(Clone, Default, Debug, Eq, PartialEq)]
+#[derive(Clone, Default, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct FormattedString {
     segments: Vec<FormattedStringSegment>,
 }
@@ -113,9 +182,125 @@ impl FormattedString {
             .with(FormattedStringSegment::LineBreak)
     }
 
+    /// Shows what was expected (prefixed with `-`) above what was found
+    /// instead (prefixed with `+`).
+    pub fn diff(
+        self,
+        expected: impl Into<String>,
+        found: impl Into<String>,
+    ) -> Self {
+        self.with(FormattedStringSegment::LineBreak)
+            .with(FormattedStringSegment::diff(expected, found))
+            .with(FormattedStringSegment::LineBreak)
+    }
+
     pub fn finish(self) -> String {
         self.to_string().trim_end().to_string()
     }
+
+    /// Renders the [`FormattedString`] as Markdown (for example, to be shown
+    /// by an editor).
+    ///
+    /// This is the inverse of [`FormattedString::from_markdown`]: inline code
+    /// is surrounded by backticks, code blocks are fenced and list items start
+    /// with `-`. Unlike [`FormattedString::finish`], the result is never
+    /// colored.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        for segment in &self.segments {
+            segment.write_markdown(&mut markdown);
+        }
+
+        markdown.trim().to_string()
+    }
+
+    /// Builds a [`FormattedString`] from a small subset of Markdown, as used in
+    /// documentation comments.
+    ///
+    /// Paragraphs are separated by blank lines, inline code is surrounded by
+    /// backticks, code blocks are fenced with three backticks and list items
+    /// start with `-` or `*`. Everything else is treated as plain text.
+    pub fn from_markdown(markdown: &str) -> Self {
+        fn push_inline(formatted: &mut FormattedString, text: &str) {
+            for (i, part) in text.split('`').enumerate() {
+                if part.is_empty() {
+                    continue;
+                }
+
+                // Every odd part is surrounded by backticks.
+                if i % 2 == 1 {
+                    formatted.push(FormattedStringSegment::code(part));
+                } else {
+                    formatted.push(part);
+                }
+            }
+        }
+
+        fn flush_paragraph(
+            formatted: &mut FormattedString,
+            paragraph: &mut Vec<&str>,
+        ) {
+            if !paragraph.is_empty() {
+                if !formatted.segments.is_empty() {
+                    formatted.push(FormattedStringSegment::LineBreak);
+                }
+                push_inline(formatted, &paragraph.join(" "));
+                paragraph.clear();
+            }
+        }
+
+        fn flush_list(
+            formatted: &mut FormattedString,
+            list: &mut Vec<FormattedString>,
+        ) {
+            if !list.is_empty() {
+                let items = std::mem::take(list);
+                *formatted = std::mem::take(formatted).list(items);
+            }
+        }
+
+        let mut formatted = Self::new();
+        let mut paragraph = Vec::new();
+        let mut list = Vec::new();
+        let mut code_block: Option<Vec<&str>> = None;
+
+        for line in markdown.lines() {
+            let trimmed = line.trim();
+
+            if code_block.is_some() && trimmed.starts_with("```") {
+                let block = code_block.take().unwrap_or_default();
+                formatted = formatted.code_block(block.join("\n"));
+            } else if let Some(block) = &mut code_block {
+                block.push(line);
+            } else if trimmed.starts_with("```") {
+                flush_paragraph(&mut formatted, &mut paragraph);
+                flush_list(&mut formatted, &mut list);
+                code_block = Some(Vec::new());
+            } else if let Some(item) = trimmed
+                .strip_prefix("- ")
+                .or_else(|| trimmed.strip_prefix("* "))
+            {
+                flush_paragraph(&mut formatted, &mut paragraph);
+                let mut formatted_item = FormattedString::new();
+                push_inline(&mut formatted_item, item);
+                list.push(formatted_item);
+            } else if trimmed.is_empty() {
+                flush_paragraph(&mut formatted, &mut paragraph);
+                flush_list(&mut formatted, &mut list);
+            } else {
+                flush_list(&mut formatted, &mut list);
+                paragraph.push(trimmed);
+            }
+        }
+
+        if let Some(block) = code_block {
+            formatted = formatted.code_block(block.join("\n"));
+        }
+
+        flush_paragraph(&mut formatted, &mut paragraph);
+        flush_list(&mut formatted, &mut list);
+        formatted
+    }
 }
 
 impl Display for FormattedString {
@@ -140,6 +325,61 @@ impl From<&str> for FormattedString {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_markdown() {
+        let markdown =
+            "Adds `x` to\nthe total.\n\n```\nadd 1\n```\n- one\n- `two`";
+        let expected = FormattedString::new()
+            .text("Adds ")
+            .code("x")
+            .text(" to the total.")
+            .code_block("add 1")
+            .list(vec![
+                FormattedString::new().text("one"),
+                FormattedString::new().code("two"),
+            ]);
+
+        assert_eq!(FormattedString::from_markdown(markdown), expected);
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let markdown = "Adds `x` to the total.\n\n```helios\nlet a = 1\nadd a\n```\n\n- one\n- `two`";
+        let formatted = FormattedString::from_markdown(markdown);
+
+        assert_eq!(formatted.to_markdown(), markdown);
+        assert_eq!(
+            FormattedString::new()
+                .text("an integer literal (such as ")
+                .code("123")
+                .text(")")
+                .to_markdown(),
+            "an integer literal (such as `123`)"
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        colored::control::set_override(false);
+        let formatted = FormattedString::new()
+            .text("I expected another type:")
+            .diff("Int", "Float");
+
+        assert_eq!(
+            formatted.clone().finish(),
+            "I expected another type:\n\n    - Int\n    + Float"
+        );
+        assert_eq!(
+            formatted.to_markdown(),
+            "I expected another type:\n\n```diff\n- Int\n+ Float\n```"
+        );
+    }
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use super::*;
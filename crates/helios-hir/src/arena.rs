@@ -0,0 +1,119 @@
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::Index;
+
+/// The index of a value allocated in an [`Arena`].
+pub struct Idx<T> {
+    raw: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Idx<T> {
+    /// Creates an index from its raw value.
+    pub fn from_raw(raw: u32) -> Self {
+        Self {
+            raw,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The raw value of the index (i.e. its position in the arena).
+    pub fn into_raw(self) -> u32 {
+        self.raw
+    }
+}
+
+// These are implemented by hand, as deriving them would require `T` to
+// implement them as well.
+
+impl<T> Clone for Idx<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Idx<T> {}
+
+impl<T> PartialEq for Idx<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl<T> Eq for Idx<T> {}
+
+impl<T> PartialOrd for Idx<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Idx<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.raw.cmp(&other.raw)
+    }
+}
+
+impl<T> Hash for Idx<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.raw.hash(state);
+    }
+}
+
+impl<T> Debug for Idx<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let type_name = std::any::type_name::<T>();
+        let type_name = type_name.rsplit("::").next().unwrap_or(type_name);
+        write!(f, "Idx::<{type_name}>({})", self.raw)
+    }
+}
+
+/// A list of values that are referred to by their [`Idx`] rather than by
+/// reference, so that they can refer to each other.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Arena<T> {
+    data: Vec<T>,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self { data: Vec::new() }
+    }
+}
+
+impl<T> Arena<T> {
+    /// Adds a value to the arena, returning its index.
+    pub fn alloc(&mut self, value: T) -> Idx<T> {
+        let idx = Idx::from_raw(self.data.len() as u32);
+        self.data.push(value);
+        idx
+    }
+
+    /// The number of values in the arena.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Whether or not the arena is empty.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Every value in the arena along with its index, in the order they were
+    /// allocated.
+    pub fn iter(&self) -> impl Iterator<Item = (Idx<T>, &T)> + '_ {
+        self.data
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (Idx::from_raw(index as u32), value))
+    }
+}
+
+impl<T> Index<Idx<T>> for Arena<T> {
+    type Output = T;
+
+    fn index(&self, idx: Idx<T>) -> &Self::Output {
+        &self.data[idx.raw as usize]
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{Arena, Expr, ExprId, MacroError, Pat, PatId};
+use rowan::{TextRange, TextSize};
+
+/// The expressions and patterns of an item.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Body {
+    pub exprs: Arena<Expr>,
+    pub pats: Arena<Pat>,
+    /// The pattern the value of the body is bound to, if any (such as `a` in
+    /// `let a = 1`).
+    pub pat: Option<PatId>,
+    /// The expression the body evaluates to.
+    pub value: ExprId,
+}
+
+/// Where the expressions and patterns of a [`Body`] come from in the source
+/// text.
+///
+/// Parenthesized expressions don't have a range of their own (they are lowered
+/// to the expressions they contain), and missing expressions have an empty
+/// range where they should have been.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BodySourceMap {
+    pub(crate) expr_sources: Vec<ExprSource>,
+    pub(crate) pat_ranges: Vec<TextRange>,
+    pub(crate) macro_errors: Vec<MacroError>,
+}
+
+impl BodySourceMap {
+    pub fn expr_source(&self, expr_id: ExprId) -> ExprSource {
+        self.expr_sources[expr_id.into_raw() as usize]
+    }
+
+    /// The range of the source text an expression was lowered from, which is
+    /// always written by the user (even if the expression was synthesized).
+    pub fn expr_range(&self, expr_id: ExprId) -> TextRange {
+        self.expr_source(expr_id).range()
+    }
+
+    pub fn pat_range(&self, pat_id: PatId) -> TextRange {
+        self.pat_ranges[pat_id.into_raw() as usize]
+    }
+
+    /// The macro calls of the body that couldn't be expanded, in the order
+    /// they appear.
+    pub fn macro_errors(&self) -> &[MacroError] {
+        &self.macro_errors
+    }
+
+    /// The innermost expression at the given offset (including an offset
+    /// right after it), if there is one. Missing expressions are never found,
+    /// and written expressions are preferred over desugared ones with the same
+    /// range.
+    pub fn expr_at_offset(&self, offset: TextSize) -> Option<ExprId> {
+        self.expr_sources
+            .iter()
+            .enumerate()
+            .filter(|(_, source)| {
+                let range = source.range();
+                !range.is_empty() && range.contains_inclusive(offset)
+            })
+            .min_by_key(|(_, source)| {
+                (source.range().len(), source.is_desugared())
+            })
+            .map(|(index, _)| ExprId::from_raw(index as u32))
+    }
+}
+
+/// Where an expression of a [`Body`] comes from in the source text.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExprSource {
+    /// The expression is written (as it is) at the given range.
+    Written(TextRange),
+    /// The expression was synthesized when desugaring the syntax at the given
+    /// range (such as the [`Expr::Sequence`] a chain of `;` is lowered to), so
+    /// that anything reported about it points at what was actually written.
+    ///
+    /// [`Expr::Sequence`]: crate::Expr::Sequence
+    Desugared(TextRange),
+}
+
+impl ExprSource {
+    pub fn range(self) -> TextRange {
+        match self {
+            ExprSource::Written(range) | ExprSource::Desugared(range) => range,
+        }
+    }
+
+    pub fn is_desugared(self) -> bool {
+        matches!(self, ExprSource::Desugared(_))
+    }
+}
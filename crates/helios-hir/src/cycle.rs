@@ -0,0 +1,192 @@
+use crate::{Item, ItemId, Module, NameResolutions};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+/// Finds the global bindings of a module whose values depend on themselves
+/// (such as `let a = b` and `let b = a`), given a function returning what the
+/// names in the body of an item resolve to.
+///
+/// Bindings don't take any arguments, so a binding referring to itself (even
+/// indirectly) can never be computed. Every group of bindings referring to
+/// each other is reported once, as the path of the shortest cycle through the
+/// binding of the group that comes first: `[a, b]` means that `a` refers to
+/// `b`, which refers back to `a`. Cycles are listed in the order their first
+/// bindings appear.
+pub fn binding_cycles<F>(
+    module: &Module,
+    mut resolutions: F,
+) -> Vec<Vec<ItemId>>
+where
+    F: FnMut(ItemId) -> Arc<NameResolutions>,
+{
+    let mut references = HashMap::new();
+    for (item_id, _) in module.bindings() {
+        let mut targets = resolutions(item_id)
+            .resolved_items()
+            .filter(|&target| matches!(module.items[target], Item::Binding(_)))
+            .collect::<Vec<_>>();
+        targets.sort();
+        targets.dedup();
+        references.insert(item_id, targets);
+    }
+
+    let mut cycles = strongly_connected_components(module, &references)
+        .into_iter()
+        .filter_map(|mut component| {
+            component.sort();
+            let start = component[0];
+            shortest_cycle(start, &component, &references)
+        })
+        .collect::<Vec<_>>();
+
+    cycles.sort();
+    cycles
+}
+
+/// The shortest path from a binding back to itself, only going through the
+/// given bindings, if there is one.
+fn shortest_cycle(
+    start: ItemId,
+    component: &[ItemId],
+    references: &HashMap<ItemId, Vec<ItemId>>,
+) -> Option<Vec<ItemId>> {
+    let mut previous = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(item_id) = queue.pop_front() {
+        for &target in &references[&item_id] {
+            if target == start {
+                let mut path = vec![item_id];
+                while let Some(&item_id) = previous.get(path.last().unwrap()) {
+                    path.push(item_id);
+                }
+
+                path.reverse();
+                return Some(path);
+            }
+
+            if component.binary_search(&target).is_ok()
+                && !previous.contains_key(&target)
+            {
+                previous.insert(target, item_id);
+                queue.push_back(target);
+            }
+        }
+    }
+
+    None
+}
+
+/// Groups the global bindings of a module into the strongly connected
+/// components of the graph of their references (with Tarjan's algorithm).
+fn strongly_connected_components(
+    module: &Module,
+    references: &HashMap<ItemId, Vec<ItemId>>,
+) -> Vec<Vec<ItemId>> {
+    struct Tarjan<'a> {
+        references: &'a HashMap<ItemId, Vec<ItemId>>,
+        indexes: HashMap<ItemId, usize>,
+        stack: Vec<ItemId>,
+        components: Vec<Vec<ItemId>>,
+    }
+
+    impl Tarjan<'_> {
+        /// Visits a binding that hasn't been visited yet, returning the lowest
+        /// index reachable from it that is still on the stack.
+        fn visit(&mut self, item_id: ItemId) -> usize {
+            let index = self.indexes.len();
+            let mut low_link = index;
+            self.indexes.insert(item_id, index);
+            self.stack.push(item_id);
+
+            for &target in &self.references[&item_id] {
+                match self.indexes.get(&target) {
+                    None => low_link = low_link.min(self.visit(target)),
+                    Some(&other) if self.stack.contains(&target) => {
+                        low_link = low_link.min(other);
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            if low_link == index {
+                let start =
+                    self.stack.iter().rposition(|&it| it == item_id).unwrap();
+                self.components.push(self.stack.split_off(start));
+            }
+
+            low_link
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        references,
+        indexes: HashMap::new(),
+        stack: Vec::new(),
+        components: Vec::new(),
+    };
+
+    for (item_id, _) in module.bindings() {
+        if !tarjan.indexes.contains_key(&item_id) {
+            tarjan.visit(item_id);
+        }
+    }
+
+    tarjan.components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{item_nodes, lower_body, lower_module, ModuleScope, Name};
+    use helios_parser::FileId;
+
+    fn cycles(source: &str) -> Vec<Vec<String>> {
+        let root = helios_parser::parse(FileId::default(), source).syntax();
+        let (module, _) = lower_module(&root);
+        let scope = ModuleScope::new(&module);
+        let bodies = item_nodes(&root)
+            .map(|node| lower_body(&node).0)
+            .collect::<Vec<_>>();
+
+        let cycles = binding_cycles(&module, |item_id| {
+            let body = &bodies[item_id.into_raw() as usize];
+            Arc::new(NameResolutions::new(&scope, body))
+        });
+
+        let name = |item_id: ItemId| match &module.items[item_id] {
+            Item::Binding(binding) => {
+                binding.name.as_ref().map(Name::to_string).unwrap()
+            }
+            _ => unreachable!(),
+        };
+
+        cycles
+            .into_iter()
+            .map(|cycle| cycle.into_iter().map(name).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_binding_cycles() {
+        // Bindings may refer to the ones declared after them.
+        assert!(cycles("let a = b + 1\nlet b = 2\n").is_empty());
+
+        assert_eq!(cycles("let a = a + 1\n"), [["a"]]);
+        assert_eq!(
+            cycles("let c = 1\nlet a = b\nlet b = c + a\n"),
+            [["a", "b"]]
+        );
+        assert_eq!(
+            cycles("let a = b\nlet b = c + a\nlet c = a\nlet d = d\n"),
+            [vec!["a", "b"], vec!["d"]]
+        );
+        assert_eq!(
+            cycles("let a = b\nlet b = c\nlet c = a\n"),
+            [["a", "b", "c"]]
+        );
+
+        // External functions don't refer to anything.
+        assert!(cycles("external func now: Int\nlet a = now\n").is_empty());
+    }
+}
@@ -0,0 +1,441 @@
+use crate::{
+    BinaryOp, Body, Expr, ExprId, Float, Item, ItemId, Literal, Module,
+    NameResolutions, UnaryOp,
+};
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::sync::Arc;
+
+/// A value computed at compile time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Const {
+    Int(i64),
+    Float(Float),
+    Bool(bool),
+}
+
+impl Display for Const {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Const::Int(value) => write!(f, "{value}"),
+            Const::Float(value) => write!(f, "{:?}", value.value()),
+            Const::Bool(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// The values of the expressions of a module that could be computed at
+/// compile time.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConstValues {
+    bindings: HashMap<ItemId, Const>,
+    exprs: HashMap<(ItemId, ExprId), Const>,
+    errors: Vec<ConstError>,
+}
+
+impl ConstValues {
+    /// The value of the global binding declared by the given item, if it's a
+    /// constant.
+    pub fn binding_value(&self, item_id: ItemId) -> Option<Const> {
+        self.bindings.get(&item_id).copied()
+    }
+
+    /// The value of an expression in the body of the given item, if it's a
+    /// constant.
+    pub fn expr_value(
+        &self,
+        item_id: ItemId,
+        expr_id: ExprId,
+    ) -> Option<Const> {
+        self.exprs.get(&(item_id, expr_id)).copied()
+    }
+
+    /// Every error found while evaluating constants, in the order they were
+    /// found.
+    pub fn errors(&self) -> &[ConstError] {
+        &self.errors
+    }
+}
+
+/// An expression whose value can't be computed, even though every value it's
+/// computed from is a constant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConstError {
+    /// An integer is divided by zero.
+    DivisionByZero { item: ItemId, expr: ExprId },
+    /// The result of an operator doesn't fit in an `Int`.
+    Overflow { item: ItemId, expr: ExprId },
+    /// An integer literal doesn't fit in an `Int`.
+    OutOfRange { item: ItemId, expr: ExprId },
+}
+
+impl ConstError {
+    /// The item the expression the error is about is in.
+    pub fn item(&self) -> ItemId {
+        match self {
+            ConstError::DivisionByZero { item, .. }
+            | ConstError::Overflow { item, .. }
+            | ConstError::OutOfRange { item, .. } => *item,
+        }
+    }
+
+    /// The expression the error is about.
+    pub fn expr(&self) -> ExprId {
+        match self {
+            ConstError::DivisionByZero { expr, .. }
+            | ConstError::Overflow { expr, .. }
+            | ConstError::OutOfRange { expr, .. } => *expr,
+        }
+    }
+}
+
+/// Computes the value of every expression of a module that only depends on
+/// literals and on other constants, given a function returning the body of an
+/// item along with what the names in it resolve to.
+///
+/// Global bindings are evaluated in the order they are referred to, and a
+/// binding referring to itself (even indirectly) isn't a constant. Neither are
+/// external functions, which may return a different value every time they're
+/// called. Operands of
+/// different types aren't folded, as that's already a type error.
+pub fn eval_module<F>(module: &Module, body: F) -> ConstValues
+where
+    F: FnMut(ItemId) -> (Arc<Body>, Arc<NameResolutions>),
+{
+    eval_module_with_prelude(module, &ConstValues::default(), body)
+}
+
+/// Computes the value of every expression of a module like [`eval_module`],
+/// given the values computed for the prelude (which names of the prelude
+/// evaluate to).
+pub fn eval_module_with_prelude<F>(
+    module: &Module,
+    prelude: &ConstValues,
+    body: F,
+) -> ConstValues
+where
+    F: FnMut(ItemId) -> (Arc<Body>, Arc<NameResolutions>),
+{
+    let mut evaluator = Evaluator {
+        module,
+        prelude,
+        body,
+        items: HashMap::new(),
+        values: ConstValues::default(),
+    };
+
+    for (item_id, item) in module.items.iter() {
+        if !matches!(item, Item::Import(_) | Item::External(_)) {
+            evaluator.eval_item(item_id);
+        }
+    }
+
+    evaluator.values
+}
+
+enum ItemState {
+    InProgress,
+    Done(Option<Const>),
+}
+
+/// The body of the item being evaluated.
+struct ItemBody {
+    item_id: ItemId,
+    body: Arc<Body>,
+    resolutions: Arc<NameResolutions>,
+}
+
+struct Evaluator<'a, F> {
+    module: &'a Module,
+    prelude: &'a ConstValues,
+    body: F,
+    items: HashMap<ItemId, ItemState>,
+    values: ConstValues,
+}
+
+impl<F> Evaluator<'_, F>
+where
+    F: FnMut(ItemId) -> (Arc<Body>, Arc<NameResolutions>),
+{
+    fn eval_item(&mut self, item_id: ItemId) -> Option<Const> {
+        match self.items.get(&item_id) {
+            Some(ItemState::InProgress) => return None,
+            Some(ItemState::Done(value)) => return *value,
+            None => {}
+        }
+
+        if matches!(
+            self.module.items[item_id],
+            Item::Import(_) | Item::External(_)
+        ) {
+            return None;
+        }
+
+        self.items.insert(item_id, ItemState::InProgress);
+        let (body, resolutions) = (self.body)(item_id);
+        let item = ItemBody {
+            item_id,
+            body,
+            resolutions,
+        };
+
+        let value = self.eval_expr(&item, item.body.value);
+        if let (Some(value), Item::Binding(_)) =
+            (value, &self.module.items[item_id])
+        {
+            self.values.bindings.insert(item_id, value);
+        }
+
+        self.items.insert(item_id, ItemState::Done(value));
+        value
+    }
+
+    fn eval_expr(&mut self, item: &ItemBody, expr_id: ExprId) -> Option<Const> {
+        let value = match &item.body.exprs[expr_id] {
+            Expr::Missing => None,
+            Expr::Literal(Literal::Integer(value)) => {
+                match i64::try_from(*value) {
+                    Ok(value) => Some(Const::Int(value)),
+                    Err(_) => self.error(ConstError::OutOfRange {
+                        item: item.item_id,
+                        expr: expr_id,
+                    }),
+                }
+            }
+            Expr::Literal(Literal::Float(value)) => Some(Const::Float(*value)),
+            Expr::Name(_) => match item.resolutions.get(expr_id) {
+                Some(target) => self.eval_item(target),
+                None => item
+                    .resolutions
+                    .prelude(expr_id)
+                    .and_then(|target| self.prelude.binding_value(target)),
+            },
+            Expr::Unary { op, operand } => {
+                self.eval_unary(item, expr_id, *op, *operand)
+            }
+            Expr::Binary { op, lhs, rhs } => {
+                let lhs = self.eval_expr(item, *lhs);
+                let rhs = self.eval_expr(item, *rhs);
+                match lhs.zip(rhs) {
+                    Some((lhs, rhs)) => {
+                        self.eval_binary(item, expr_id, *op, lhs, rhs)
+                    }
+                    None => None,
+                }
+            }
+            // Assignments aren't constants, but their operands may still
+            // contain errors.
+            Expr::Assign { target, value } => {
+                self.eval_expr(item, *target);
+                self.eval_expr(item, *value);
+                None
+            }
+            Expr::Sequence(exprs) => {
+                let mut value = None;
+                for &expr_id in exprs {
+                    value = self.eval_expr(item, expr_id);
+                }
+                value
+            }
+        };
+
+        if let Some(value) = value {
+            self.values.exprs.insert((item.item_id, expr_id), value);
+        }
+
+        value
+    }
+
+    fn eval_unary(
+        &mut self,
+        item: &ItemBody,
+        expr_id: ExprId,
+        op: UnaryOp,
+        operand: ExprId,
+    ) -> Option<Const> {
+        // The smallest `Int` can only be written by negating a literal that
+        // doesn't fit in an `Int` on its own.
+        if let (UnaryOp::Neg, Expr::Literal(Literal::Integer(value))) =
+            (op, &item.body.exprs[operand])
+        {
+            if *value == i64::MIN.unsigned_abs() {
+                return Some(Const::Int(i64::MIN));
+            }
+        }
+
+        match (op, self.eval_expr(item, operand)?) {
+            (UnaryOp::Neg, Const::Int(value)) => match value.checked_neg() {
+                Some(value) => Some(Const::Int(value)),
+                None => self.error(ConstError::Overflow {
+                    item: item.item_id,
+                    expr: expr_id,
+                }),
+            },
+            (UnaryOp::Neg, Const::Float(value)) => {
+                Some(Const::Float(Float::new(-value.value())))
+            }
+            (UnaryOp::Not, Const::Bool(value)) => Some(Const::Bool(!value)),
+            _ => None,
+        }
+    }
+
+    fn eval_binary(
+        &mut self,
+        item: &ItemBody,
+        expr_id: ExprId,
+        op: BinaryOp,
+        lhs: Const,
+        rhs: Const,
+    ) -> Option<Const> {
+        let value = match (lhs, rhs) {
+            (Const::Int(lhs), Const::Int(rhs)) => {
+                let result = match op {
+                    BinaryOp::Add => lhs.checked_add(rhs),
+                    BinaryOp::Sub => lhs.checked_sub(rhs),
+                    BinaryOp::Mul => lhs.checked_mul(rhs),
+                    BinaryOp::Div if rhs == 0 => {
+                        return self.error(ConstError::DivisionByZero {
+                            item: item.item_id,
+                            expr: expr_id,
+                        });
+                    }
+                    BinaryOp::Div => lhs.checked_div(rhs),
+                    _ => return compare(op, &lhs, &rhs).map(Const::Bool),
+                };
+
+                match result {
+                    Some(value) => Const::Int(value),
+                    None => {
+                        return self.error(ConstError::Overflow {
+                            item: item.item_id,
+                            expr: expr_id,
+                        });
+                    }
+                }
+            }
+            (Const::Float(lhs), Const::Float(rhs)) => {
+                let (lhs, rhs) = (lhs.value(), rhs.value());
+                let value = match op {
+                    BinaryOp::Add => lhs + rhs,
+                    BinaryOp::Sub => lhs - rhs,
+                    BinaryOp::Mul => lhs * rhs,
+                    BinaryOp::Div => lhs / rhs,
+                    _ => return compare(op, &lhs, &rhs).map(Const::Bool),
+                };
+                Const::Float(Float::new(value))
+            }
+            (Const::Bool(lhs), Const::Bool(rhs)) => match op {
+                BinaryOp::Eq => Const::Bool(lhs == rhs),
+                BinaryOp::NotEq => Const::Bool(lhs != rhs),
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
+    fn error(&mut self, error: ConstError) -> Option<Const> {
+        self.values.errors.push(error);
+        None
+    }
+}
+
+/// Compares two numbers with a comparison operator (and returns `None` for
+/// any other operator).
+pub(crate) fn compare<T: PartialOrd>(
+    op: BinaryOp,
+    lhs: &T,
+    rhs: &T,
+) -> Option<bool> {
+    let result = match op {
+        BinaryOp::Eq => lhs == rhs,
+        BinaryOp::NotEq => lhs != rhs,
+        BinaryOp::Lt => lhs < rhs,
+        BinaryOp::LtEq => lhs <= rhs,
+        BinaryOp::Gt => lhs > rhs,
+        BinaryOp::GtEq => lhs >= rhs,
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{item_nodes, lower_body, lower_module, ModuleScope};
+    use helios_parser::FileId;
+
+    fn eval(source: &str) -> (Module, ConstValues) {
+        let root = helios_parser::parse(FileId::default(), source).syntax();
+        let (module, _) = lower_module(&root);
+        let scope = ModuleScope::new(&module);
+        let bodies = item_nodes(&root)
+            .map(|node| Arc::new(lower_body(&node).0))
+            .collect::<Vec<_>>();
+
+        let values = eval_module(&module, |item_id| {
+            let body = Arc::clone(&bodies[item_id.into_raw() as usize]);
+            let resolutions = NameResolutions::new(&scope, &body);
+            (body, Arc::new(resolutions))
+        });
+
+        (module, values)
+    }
+
+    fn binding_values(module: &Module, values: &ConstValues) -> Vec<String> {
+        module
+            .bindings()
+            .map(|(item_id, _)| match values.binding_value(item_id) {
+                Some(value) => value.to_string(),
+                None => "?".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_eval_module() {
+        let source = "import foo.x\nlet a = b * 2\nlet b = (1 + 2) - 4\n\
+            let c = 1.5 / 2.0\nlet d = !(b >= 0)\nlet e = a + x\n\
+            let f = f + 1\nlet g = -9_223_372_036_854_775_808\n\
+            let h = 1 + now\nexternal func now: Int\n";
+        let (module, values) = eval(source);
+
+        assert_eq!(
+            binding_values(&module, &values),
+            [
+                "-2",
+                "-1",
+                "0.75",
+                "true",
+                "?",
+                "?",
+                "-9223372036854775808",
+                "?"
+            ]
+        );
+        assert!(values.errors().is_empty(), "{:?}", values.errors());
+    }
+
+    #[test]
+    fn test_const_errors() {
+        let source = "let a = 9_223_372_036_854_775_807\nlet b = a + 1\n\
+            let c = 1 / (a - a)\nlet d = 9_223_372_036_854_775_808\n\
+            let e = 1.0 / 0.0\n";
+        let (module, values) = eval(source);
+
+        assert_eq!(
+            binding_values(&module, &values),
+            ["9223372036854775807", "?", "?", "?", "inf"]
+        );
+        assert!(matches!(
+            values.errors(),
+            [
+                ConstError::Overflow { .. },
+                ConstError::DivisionByZero { .. },
+                ConstError::OutOfRange { .. },
+            ]
+        ));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::Idx;
+use std::fmt::{self, Debug, Display};
+
+pub type ExprId = Idx<Expr>;
+pub type PatId = Idx<Pat>;
+
+/// The name of a binding (or of a module, in an import path).
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Name(String);
+
+impl Name {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Expr {
+    /// An expression that is missing from the source text (or that couldn't
+    /// be parsed).
+    Missing,
+    Literal(Literal),
+    /// A reference to a binding by its name.
+    Name(Name),
+    Unary {
+        op: UnaryOp,
+        operand: ExprId,
+    },
+    Binary {
+        op: BinaryOp,
+        lhs: ExprId,
+        rhs: ExprId,
+    },
+    /// An assignment (such as `a <- 1`).
+    Assign {
+        target: ExprId,
+        value: ExprId,
+    },
+    /// Expressions evaluated one after the other (such as `a; b; c`), whose
+    /// value is the value of the last one.
+    Sequence(Vec<ExprId>),
+}
+
+impl Expr {
+    /// The expressions this expression is made of, in the order they appear.
+    pub fn children(&self) -> Vec<ExprId> {
+        match self {
+            Expr::Missing | Expr::Literal(_) | Expr::Name(_) => Vec::new(),
+            Expr::Unary { operand, .. } => vec![*operand],
+            Expr::Binary { lhs, rhs, .. } => vec![*lhs, *rhs],
+            Expr::Assign { target, value } => vec![*target, *value],
+            Expr::Sequence(exprs) => exprs.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Literal {
+    Integer(u64),
+    Float(Float),
+}
+
+/// A floating-point number, stored as its bits so that literals can be
+/// compared (and hashed) like any other value.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Float(u64);
+
+impl Float {
+    pub fn new(value: f64) -> Self {
+        Self(value.to_bits())
+    }
+
+    pub fn value(self) -> f64 {
+        f64::from_bits(self.0)
+    }
+}
+
+impl Debug for Float {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.value(), f)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum UnaryOp {
+    /// `-`
+    Neg,
+    /// `!`
+    Not,
+}
+
+impl UnaryOp {
+    /// How the operator is spelled in the source text.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            UnaryOp::Neg => "-",
+            UnaryOp::Not => "!",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl BinaryOp {
+    /// How the operator is spelled in the source text.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Eq => "=",
+            BinaryOp::NotEq => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::LtEq => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::GtEq => ">=",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Pat {
+    /// A pattern that is missing from the source text.
+    Missing,
+    /// A pattern binding whatever it matches to a name.
+    Bind(Name),
+}
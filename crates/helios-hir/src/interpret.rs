@@ -0,0 +1,511 @@
+use crate::eval::compare;
+use crate::{
+    BinaryOp, Body, Const, ConstValues, Expr, ExprId, External, Float, Item,
+    ItemId, Module, Name, NameResolutions, UnaryOp,
+};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::sync::Arc;
+
+/// A function of the host, which an external function can be bound to.
+pub type HostFunction = Box<dyn Fn() -> Const + Send + Sync>;
+
+/// The functions of the host the external functions of a module are bound to
+/// (by name) when it's interpreted.
+#[derive(Default)]
+pub struct HostFunctions {
+    functions: HashMap<Name, HostFunction>,
+}
+
+impl HostFunctions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds the external functions with the given name to a function of the
+    /// host, replacing the function they were bound to before (if any).
+    ///
+    /// The function must return a value of the type the external functions
+    /// are declared with.
+    pub fn register<F>(&mut self, name: &str, function: F) -> &mut Self
+    where
+        F: Fn() -> Const + Send + Sync + 'static,
+    {
+        self.functions.insert(Name::new(name), Box::new(function));
+        self
+    }
+
+    /// The function of the host the given name is bound to, if any.
+    pub fn get(&self, name: &Name) -> Option<&HostFunction> {
+        self.functions.get(name)
+    }
+}
+
+impl fmt::Debug for HostFunctions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.functions.keys()).finish()
+    }
+}
+
+/// An error that stops a module from being interpreted, along with the
+/// expression being evaluated when it happened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    /// The item the expression is in.
+    pub item: ItemId,
+    pub expr: ExprId,
+}
+
+impl std::error::Error for RuntimeError {}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuntimeErrorKind {
+    /// An external function isn't bound to any function of the host.
+    UnboundExternal(Name),
+    /// A function of the host returned a value of another type than the one
+    /// its external function is declared with.
+    ExternalTypeMismatch { name: Name, value: Const },
+    /// An integer is divided by zero.
+    DivisionByZero,
+    /// An integer (either a literal or the result of an operator) doesn't fit
+    /// in an `Int`.
+    Overflow,
+    /// The module uses something that can't be interpreted (yet).
+    Unsupported(String),
+}
+
+impl Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnboundExternal(name) => write!(
+                f,
+                "The external function `{name}` isn't bound to a function \
+                of the host"
+            ),
+            Self::ExternalTypeMismatch { name, value } => write!(
+                f,
+                "The external function `{name}` returned a value of the \
+                wrong type: `{value}`"
+            ),
+            Self::DivisionByZero => write!(f, "Attempted to divide by zero"),
+            Self::Overflow => write!(f, "An integer doesn't fit in an `Int`"),
+            Self::Unsupported(message) => {
+                write!(f, "Interpreting {message} isn't supported yet")
+            }
+        }
+    }
+}
+
+/// Interprets a module, given the values computed for its constant
+/// expressions (see [`crate::eval_module_with_prelude`]), the functions of
+/// the host its external functions are bound to and a function returning the
+/// body of an item along with what the names in it resolve to.
+///
+/// The expressions at the top level of the module are evaluated in the order
+/// they appear, followed by the entry point of the program (see
+/// [`Module::main`]). The value of the entry point is returned if there is
+/// one, and the value of the last expression otherwise.
+/// Like the code the module is compiled to, a global binding is evaluated
+/// every time it's referred to, and so is an external function (by calling
+/// the function of the host it's bound to).
+pub fn interpret_module<F>(
+    module: &Module,
+    consts: &ConstValues,
+    host: &HostFunctions,
+    body: F,
+) -> Result<Option<Const>>
+where
+    F: FnMut(ItemId) -> (Arc<Body>, Arc<NameResolutions>),
+{
+    let mut interpreter = Interpreter {
+        module,
+        consts,
+        host,
+        body,
+        in_progress: HashSet::new(),
+    };
+
+    let mut value = None;
+    for (item_id, item) in module.items.iter() {
+        if matches!(item, Item::Expr) {
+            value = Some(interpreter.eval_item(item_id)?);
+        }
+    }
+
+    if let Some(main) = module.main() {
+        value = Some(interpreter.eval_item(main)?);
+    }
+
+    Ok(value)
+}
+
+/// Interprets a test of a module (see [`Module::tests`]) like
+/// [`interpret_module`] would, returning the value of its body.
+///
+/// Nothing else in the module is evaluated, other than what the test refers
+/// to.
+pub fn interpret_test<F>(
+    module: &Module,
+    consts: &ConstValues,
+    host: &HostFunctions,
+    test: ItemId,
+    body: F,
+) -> Result<Const>
+where
+    F: FnMut(ItemId) -> (Arc<Body>, Arc<NameResolutions>),
+{
+    let mut interpreter = Interpreter {
+        module,
+        consts,
+        host,
+        body,
+        in_progress: HashSet::new(),
+    };
+
+    interpreter.eval_item(test)
+}
+
+type Result<T, E = RuntimeError> = std::result::Result<T, E>;
+
+/// The body of the item being interpreted.
+struct ItemBody {
+    item_id: ItemId,
+    body: Arc<Body>,
+    resolutions: Arc<NameResolutions>,
+}
+
+struct Interpreter<'a, F> {
+    module: &'a Module,
+    consts: &'a ConstValues,
+    host: &'a HostFunctions,
+    body: F,
+    /// The global bindings being evaluated, which can't refer to themselves.
+    in_progress: HashSet<ItemId>,
+}
+
+impl<F> Interpreter<'_, F>
+where
+    F: FnMut(ItemId) -> (Arc<Body>, Arc<NameResolutions>),
+{
+    /// Evaluates a global binding or an expression at the top level.
+    fn eval_item(&mut self, item_id: ItemId) -> Result<Const> {
+        let (body, resolutions) = (self.body)(item_id);
+        let item = ItemBody {
+            item_id,
+            body,
+            resolutions,
+        };
+
+        self.in_progress.insert(item_id);
+        let value = self.eval_expr(&item, item.body.value);
+        self.in_progress.remove(&item_id);
+        value
+    }
+
+    fn call_external(
+        &self,
+        external: &External,
+    ) -> Result<Const, RuntimeErrorKind> {
+        let name = external.name.as_ref().ok_or_else(|| {
+            RuntimeErrorKind::Unsupported(
+                "unnamed external functions".to_string(),
+            )
+        })?;
+        let function = self
+            .host
+            .get(name)
+            .ok_or_else(|| RuntimeErrorKind::UnboundExternal(name.clone()))?;
+
+        let value = function();
+        let ty = match value {
+            Const::Int(_) => "Int",
+            Const::Float(_) => "Float",
+            Const::Bool(_) => "Bool",
+        };
+        if external.ty.as_ref().map(Name::as_str) != Some(ty) {
+            return Err(RuntimeErrorKind::ExternalTypeMismatch {
+                name: name.clone(),
+                value,
+            });
+        }
+
+        Ok(value)
+    }
+
+    /// Evaluates an expression. Errors raised by the expression itself (rather
+    /// than by its operands, or by the items it refers to) are located at it.
+    fn eval_expr(&mut self, item: &ItemBody, expr_id: ExprId) -> Result<Const> {
+        // Constants (including the names of the prelude) were already
+        // computed, along with the errors they may have.
+        if let Some(value) = self.consts.expr_value(item.item_id, expr_id) {
+            return Ok(value);
+        }
+
+        let value = match &item.body.exprs[expr_id] {
+            Expr::Literal(_) => Err(RuntimeErrorKind::Overflow),
+            Expr::Name(name) => match item.resolutions.get(expr_id) {
+                Some(target) => match &self.module.items[target] {
+                    Item::External(external) => self.call_external(external),
+                    Item::Import(_) => Err(RuntimeErrorKind::Unsupported(
+                        "imported bindings".to_string(),
+                    )),
+                    // Macros aren't part of the scope of the module, so
+                    // names never resolve to them.
+                    Item::Macro(_) => {
+                        Err(RuntimeErrorKind::Unsupported("macros".to_string()))
+                    }
+                    Item::Binding(_) | Item::Test(_) | Item::Expr
+                        if self.in_progress.contains(&target) =>
+                    {
+                        Err(RuntimeErrorKind::Unsupported(
+                            "bindings referring to themselves".to_string(),
+                        ))
+                    }
+                    Item::Binding(_) | Item::Test(_) | Item::Expr => {
+                        return self.eval_item(target)
+                    }
+                },
+                None => Err(RuntimeErrorKind::Unsupported(format!(
+                    "`{name}`, as it isn't a constant"
+                ))),
+            },
+            Expr::Unary { op, operand } => {
+                match (op, self.eval_expr(item, *operand)?) {
+                    (UnaryOp::Neg, Const::Int(value)) => value
+                        .checked_neg()
+                        .map(Const::Int)
+                        .ok_or(RuntimeErrorKind::Overflow),
+                    (UnaryOp::Neg, Const::Float(value)) => {
+                        Ok(Const::Float(Float::new(-value.value())))
+                    }
+                    (UnaryOp::Not, Const::Bool(value)) => {
+                        Ok(Const::Bool(!value))
+                    }
+                    (op, _) => Err(unsupported_operator(op.symbol())),
+                }
+            }
+            Expr::Binary { op, lhs, rhs } => {
+                let lhs = self.eval_expr(item, *lhs)?;
+                let rhs = self.eval_expr(item, *rhs)?;
+                eval_binary(*op, lhs, rhs)
+            }
+            Expr::Sequence(exprs) => {
+                let mut value = None;
+                for &expr_id in exprs {
+                    value = Some(self.eval_expr(item, expr_id)?);
+                }
+                value.ok_or_else(|| {
+                    RuntimeErrorKind::Unsupported("empty sequences".to_string())
+                })
+            }
+            Expr::Assign { .. } => {
+                Err(RuntimeErrorKind::Unsupported("assignments".to_string()))
+            }
+            Expr::Missing => Err(RuntimeErrorKind::Unsupported(
+                "missing expressions".to_string(),
+            )),
+        };
+
+        value.map_err(|kind| RuntimeError {
+            kind,
+            item: item.item_id,
+            expr: expr_id,
+        })
+    }
+}
+
+fn eval_binary(
+    op: BinaryOp,
+    lhs: Const,
+    rhs: Const,
+) -> Result<Const, RuntimeErrorKind> {
+    let value = match (lhs, rhs) {
+        (Const::Int(lhs), Const::Int(rhs)) => {
+            let result = match op {
+                BinaryOp::Add => lhs.checked_add(rhs),
+                BinaryOp::Sub => lhs.checked_sub(rhs),
+                BinaryOp::Mul => lhs.checked_mul(rhs),
+                BinaryOp::Div if rhs == 0 => {
+                    return Err(RuntimeErrorKind::DivisionByZero)
+                }
+                BinaryOp::Div => lhs.checked_div(rhs),
+                _ => return compare_values(op, &lhs, &rhs),
+            };
+
+            Const::Int(result.ok_or(RuntimeErrorKind::Overflow)?)
+        }
+        (Const::Float(lhs), Const::Float(rhs)) => {
+            let (lhs, rhs) = (lhs.value(), rhs.value());
+            let value = match op {
+                BinaryOp::Add => lhs + rhs,
+                BinaryOp::Sub => lhs - rhs,
+                BinaryOp::Mul => lhs * rhs,
+                BinaryOp::Div => lhs / rhs,
+                _ => return compare_values(op, &lhs, &rhs),
+            };
+            Const::Float(Float::new(value))
+        }
+        (Const::Bool(lhs), Const::Bool(rhs)) => match op {
+            BinaryOp::Eq => Const::Bool(lhs == rhs),
+            BinaryOp::NotEq => Const::Bool(lhs != rhs),
+            _ => return Err(unsupported_operator(op.symbol())),
+        },
+        _ => return Err(unsupported_operator(op.symbol())),
+    };
+
+    Ok(value)
+}
+
+fn compare_values<T: PartialOrd>(
+    op: BinaryOp,
+    lhs: &T,
+    rhs: &T,
+) -> Result<Const, RuntimeErrorKind> {
+    compare(op, lhs, rhs)
+        .map(Const::Bool)
+        .ok_or_else(|| unsupported_operator(op.symbol()))
+}
+
+fn unsupported_operator(symbol: &str) -> RuntimeErrorKind {
+    RuntimeErrorKind::Unsupported(format!("`{symbol}` on these operands"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        eval_module, item_nodes, lower_body, lower_module, ModuleScope,
+    };
+    use helios_parser::FileId;
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    fn interpret(source: &str, host: &HostFunctions) -> Result<Option<Const>> {
+        let root = helios_parser::parse(FileId::default(), source).syntax();
+        let (module, _) = lower_module(&root);
+        let scope = ModuleScope::new(&module);
+        let bodies = item_nodes(&root)
+            .map(|node| Arc::new(lower_body(&node).0))
+            .collect::<Vec<_>>();
+        let body = |item_id: ItemId| {
+            let body = Arc::clone(&bodies[item_id.into_raw() as usize]);
+            let resolutions = NameResolutions::new(&scope, &body);
+            (body, Arc::new(resolutions))
+        };
+
+        let consts = eval_module(&module, body);
+        interpret_module(&module, &consts, host, body)
+    }
+
+    #[test]
+    fn test_interpret_module() {
+        let host = HostFunctions::new();
+        let source = "a - b\nlet a = 6 * 7\nlet b = (a > 0; 2)\n";
+        assert_eq!(interpret(source, &host), Ok(Some(Const::Int(40))));
+        assert_eq!(interpret("let a = 1\n", &host), Ok(None));
+
+        // The entry point is evaluated last, whatever the order of the items.
+        let source = "let main = a + 1\nlet a = 2\na * 10\n";
+        assert_eq!(interpret(source, &host), Ok(Some(Const::Int(3))));
+    }
+
+    #[test]
+    fn test_interpret_externals() {
+        // Every reference to `next` calls the host again.
+        let counter = Arc::new(AtomicI64::new(0));
+        let mut host = HostFunctions::new();
+        host.register("next", {
+            let counter = Arc::clone(&counter);
+            move || Const::Int(counter.fetch_add(1, Ordering::SeqCst))
+        })
+        .register("half", || Const::Float(Float::new(0.5)));
+
+        let source = "let a = next * 10\nexternal func next: Int\n\
+            external func half: Float\n(a; a + next; half < 1.0)\n\
+            a + next\n";
+        assert_eq!(interpret(source, &host), Ok(Some(Const::Int(34))));
+        assert_eq!(counter.load(Ordering::SeqCst), 5);
+
+        let error = |source| interpret(source, &host).unwrap_err().kind;
+        let source = "1 / (next * 0)\nexternal func next: Int\n";
+        assert_eq!(error(source), RuntimeErrorKind::DivisionByZero);
+
+        let source = "now\nexternal func now: Int\n";
+        assert_eq!(
+            error(source),
+            RuntimeErrorKind::UnboundExternal(Name::new("now"))
+        );
+
+        let source = "half\nexternal func half: Int\n";
+        assert_eq!(
+            error(source),
+            RuntimeErrorKind::ExternalTypeMismatch {
+                name: Name::new("half"),
+                value: Const::Float(Float::new(0.5)),
+            }
+        );
+    }
+
+    #[test]
+    fn test_interpret_tests() {
+        let source = "test \"a\" = a = 2\nlet a = 1 + 1\nlet main = 1 / 0\n\
+            test \"b\" = now > 0\nexternal func now: Int\n";
+        let root = helios_parser::parse(FileId::default(), source).syntax();
+        let (module, _) = lower_module(&root);
+        let scope = ModuleScope::new(&module);
+        let bodies = item_nodes(&root)
+            .map(|node| Arc::new(lower_body(&node).0))
+            .collect::<Vec<_>>();
+        let body = |item_id: ItemId| {
+            let body = Arc::clone(&bodies[item_id.into_raw() as usize]);
+            let resolutions = NameResolutions::new(&scope, &body);
+            (body, Arc::new(resolutions))
+        };
+
+        // Tests don't run the entry point (which would divide by zero).
+        let consts = eval_module(&module, body);
+        let host = HostFunctions::new();
+        let tests = module.tests().map(|(item_id, _)| item_id);
+        let results = tests
+            .map(|test| {
+                interpret_test(&module, &consts, &host, test, body)
+                    .map_err(|error| error.kind)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            results,
+            [
+                Ok(Const::Bool(true)),
+                Err(RuntimeErrorKind::UnboundExternal(Name::new("now"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_runtime_error_location() {
+        let mut host = HostFunctions::new();
+        host.register("zero", || Const::Int(0));
+
+        // Errors are located at the expression raising them, even if it's in
+        // another binding than the one being evaluated.
+        let source = "let main = a + 1\nlet a = 6 / zero\n\
+            external func zero: Int\n";
+        let error = interpret(source, &host).unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::DivisionByZero);
+        assert_eq!(error.item, ItemId::from_raw(1));
+        assert_eq!(error.expr, ExprId::from_raw(2));
+
+        // Calling an unbound external function is an error at its name.
+        let error =
+            interpret("1 + now\nexternal func now: Int\n", &host).unwrap_err();
+        assert_eq!(error.item, ItemId::from_raw(0));
+        assert_eq!(error.expr, ExprId::from_raw(1));
+    }
+}
@@ -0,0 +1,56 @@
+//! The high-level intermediate representation (HIR) of Helios.
+//!
+//! The concrete syntax tree produced by the parser keeps every token of the
+//! source text, which is what tools like the formatter need. Most of the
+//! compiler would rather not care about parentheses, indentation or how
+//! operators are spelled, so the tree is lowered to the HIR instead:
+//!
+//! - A file is lowered to a [`Module`], which lists its [`Item`]s (such as
+//!   global bindings and imports) without their bodies. Editing the body of a
+//!   binding doesn't change the module, so everything computed from it can be
+//!   reused.
+//! - The expressions and patterns of an item are lowered to a [`Body`], where
+//!   they refer to each other by their [`ExprId`]s and [`PatId`]s rather than
+//!   by reference. Parenthesized and indented expressions are desugared to the
+//!   expressions they contain, and chains of `;` to a single
+//!   [`Expr::Sequence`]. Macro calls are expanded to the templates of their
+//!   macros (see [`MacroResolver`]), with the arguments of the call lowered
+//!   wherever the parameters are used.
+//! - The names referred to in a body are resolved against the scope of the
+//!   module, and then against the scope of the prelude (see [`ModuleScope`]
+//!   and [`NameResolutions`]). Global bindings can refer to each other in
+//!   any order, as long as none of them depends on itself (see
+//!   [`binding_cycles`]).
+//! - The expressions that only depend on literals and other constants are
+//!   evaluated at compile time (see [`eval_module`]).
+//! - A module can also be interpreted directly (see [`interpret_module`]),
+//!   with its external functions bound to functions of the host (see
+//!   [`HostFunctions`]).
+//!
+//! Neither contains any text ranges, which are kept in separate source maps
+//! ([`ModuleSourceMap`] and [`BodySourceMap`]) so that moving an item around
+//! doesn't change its HIR. Expressions synthesized while desugaring are mapped
+//! to the syntax they were desugared from (see [`ExprSource`]), so anything
+//! reported about them points at what the user actually wrote.
+
+mod arena;
+mod body;
+mod cycle;
+mod eval;
+mod expr;
+mod interpret;
+mod lower;
+mod macros;
+mod module;
+mod scope;
+
+pub use crate::arena::{Arena, Idx};
+pub use crate::body::*;
+pub use crate::cycle::*;
+pub use crate::eval::*;
+pub use crate::expr::*;
+pub use crate::interpret::*;
+pub use crate::lower::*;
+pub use crate::macros::*;
+pub use crate::module::*;
+pub use crate::scope::*;
@@ -0,0 +1,865 @@
+use crate::*;
+use helios_parser::{Number, NumberError};
+use helios_syntax::{SyntaxKind, SyntaxNode, SyntaxToken};
+use rowan::TextRange;
+use std::collections::HashMap;
+
+/// The nodes of a file's syntax tree that are lowered to items, in the order
+/// they appear (so the `n`th node is lowered to the `n`th item).
+pub fn item_nodes(root: &SyntaxNode) -> impl Iterator<Item = SyntaxNode> {
+    root.children().filter(|node| {
+        node.kind().is_declaration() || node.kind().is_expression()
+    })
+}
+
+/// Lowers the syntax tree of a file to a [`Module`].
+pub fn lower_module(root: &SyntaxNode) -> (Module, ModuleSourceMap) {
+    let mut module = Module::default();
+    let mut source_map = ModuleSourceMap::default();
+
+    for node in item_nodes(root) {
+        let (item, name_range) = match node.kind() {
+            SyntaxKind::Dec_GlobalBinding => {
+                let identifier = first_token(&node, SyntaxKind::Identifier);
+                let binding = Binding {
+                    name: identifier.as_ref().map(name),
+                };
+                let range = identifier.map(|token| token.text_range());
+                (Item::Binding(binding), range)
+            }
+            SyntaxKind::Dec_Import => {
+                let identifiers = node
+                    .children_with_tokens()
+                    .filter_map(|element| element.into_token())
+                    .filter(|token| token.kind() == SyntaxKind::Identifier)
+                    .collect::<Vec<_>>();
+
+                let range = match (identifiers.first(), identifiers.last()) {
+                    (Some(first), Some(last)) => Some(TextRange::new(
+                        first.text_range().start(),
+                        last.text_range().end(),
+                    )),
+                    _ => None,
+                };
+
+                let path = identifiers.iter().map(name).collect();
+                (Item::Import(Import { path }), range)
+            }
+            SyntaxKind::Dec_External => {
+                // The name comes before the `:` and the type after it, either
+                // of which may be missing.
+                let mut identifier = None;
+                let mut ty = None;
+                let mut after_colon = false;
+                for token in node
+                    .children_with_tokens()
+                    .filter_map(|element| element.into_token())
+                {
+                    match token.kind() {
+                        SyntaxKind::Sym_Colon => after_colon = true,
+                        SyntaxKind::Identifier if after_colon => {
+                            ty = Some(name(&token))
+                        }
+                        SyntaxKind::Identifier => identifier = Some(token),
+                        _ => {}
+                    }
+                }
+
+                let external = External {
+                    name: identifier.as_ref().map(name),
+                    ty,
+                };
+                let range = identifier.map(|token| token.text_range());
+                (Item::External(external), range)
+            }
+            SyntaxKind::Dec_Macro => {
+                // The name comes before the parameters, and both before the
+                // `=`.
+                let identifiers = node
+                    .children_with_tokens()
+                    .filter_map(|element| element.into_token())
+                    .take_while(|token| token.kind() != SyntaxKind::Sym_Eq)
+                    .filter(|token| token.kind() == SyntaxKind::Identifier)
+                    .collect::<Vec<_>>();
+
+                let macro_ = Macro {
+                    name: identifiers.first().map(name),
+                    params: identifiers.iter().skip(1).map(name).collect(),
+                };
+                let range = identifiers.first().map(|token| token.text_range());
+                (Item::Macro(macro_), range)
+            }
+            SyntaxKind::Dec_Test => {
+                let string = first_token(&node, SyntaxKind::Lit_String);
+                let test = Test {
+                    name: string.as_ref().map(string_value),
+                };
+                let range = string.map(|token| token.text_range());
+                (Item::Test(test), range)
+            }
+            _ => (Item::Expr, None),
+        };
+
+        module.items.alloc(item);
+        source_map.item_ranges.push(trimmed_range(&node));
+        source_map.name_ranges.push(name_range);
+    }
+
+    (module, source_map)
+}
+
+/// Lowers the expressions and patterns of an item (one of the nodes returned
+/// by [`item_nodes`]) to a [`Body`], expanding its macro calls with the macros
+/// declared in the same file.
+///
+/// Items without expressions (such as imports) have a body made of a single
+/// missing expression. Macros are only lowered where they're called, since
+/// their parameters don't mean anything until then.
+pub fn lower_body(node: &SyntaxNode) -> (Body, BodySourceMap) {
+    // Only bodies calling macros need to find them.
+    let calls_macros = node
+        .descendants()
+        .any(|node| node.kind() == SyntaxKind::Exp_MacroCall);
+    let macros = match (calls_macros, node.ancestors().last()) {
+        (true, Some(root)) => FileMacros::new(&root),
+        _ => FileMacros::default(),
+    };
+
+    lower_body_with_macros(node, &macros)
+}
+
+/// Lowers the expressions and patterns of an item to a [`Body`] like
+/// [`lower_body`], expanding its macro calls with the macros the given
+/// resolver finds.
+pub fn lower_body_with_macros(
+    node: &SyntaxNode,
+    macros: &dyn MacroResolver,
+) -> (Body, BodySourceMap) {
+    let mut lowering = BodyLowering {
+        exprs: Arena::default(),
+        pats: Arena::default(),
+        source_map: BodySourceMap::default(),
+        macros,
+        expansions: Vec::new(),
+        current: None,
+    };
+
+    let (pat, value) = match node.kind() {
+        SyntaxKind::Dec_Test => (None, lowering.lower_child_expr(node)),
+        SyntaxKind::Dec_GlobalBinding => {
+            let pat = match first_token(node, SyntaxKind::Identifier) {
+                Some(token) => lowering
+                    .alloc_pat(Pat::Bind(name(&token)), token.text_range()),
+                None => lowering.alloc_pat(Pat::Missing, empty_at_start(node)),
+            };
+            (Some(pat), lowering.lower_child_expr(node))
+        }
+        kind if kind.is_expression() => (None, lowering.lower_expr(node)),
+        _ => (None, lowering.missing(empty_at_start(node))),
+    };
+
+    let body = Body {
+        exprs: lowering.exprs,
+        pats: lowering.pats,
+        pat,
+        value,
+    };
+
+    (body, lowering.source_map)
+}
+
+struct BodyLowering<'a> {
+    exprs: Arena<Expr>,
+    pats: Arena<Pat>,
+    source_map: BodySourceMap,
+    macros: &'a dyn MacroResolver,
+    /// The macro calls expanded so far.
+    expansions: Vec<Expansion>,
+    /// The index of the expansion whose template is being lowered, if any.
+    current: Option<usize>,
+}
+
+/// A macro call being expanded.
+struct Expansion {
+    /// The arguments of the call, by the names of the parameters they're
+    /// given for.
+    args: HashMap<Name, SyntaxNode>,
+    /// The index of the expansion the call was written in (if it comes from
+    /// the template of another macro), which is where its arguments are
+    /// lowered.
+    parent: Option<usize>,
+    /// The range of the outermost call, which everything expanded from it
+    /// is mapped to.
+    range: TextRange,
+    /// How many calls this one is nested in (including itself).
+    depth: usize,
+}
+
+impl BodyLowering<'_> {
+    /// Allocates an expression written at the given range, unless it comes
+    /// from the template of a macro, in which case it's mapped to the call
+    /// it was expanded from (see [`ExprSource::Desugared`]).
+    fn alloc_expr(&mut self, expr: Expr, range: TextRange) -> ExprId {
+        let source = match self.current {
+            Some(index) => ExprSource::Desugared(self.expansions[index].range),
+            None => ExprSource::Written(range),
+        };
+        self.alloc_expr_with_source(expr, source)
+    }
+
+    /// Allocates an expression synthesized when desugaring the syntax at the
+    /// given range (see [`ExprSource::Desugared`]).
+    fn alloc_desugared(&mut self, expr: Expr, range: TextRange) -> ExprId {
+        let range = self.expanded_range(range);
+        self.alloc_expr_with_source(expr, ExprSource::Desugared(range))
+    }
+
+    /// The range anything lowered from the syntax at the given range is
+    /// mapped to, which is the range of the outermost macro call if it comes
+    /// from the template of a macro.
+    fn expanded_range(&self, range: TextRange) -> TextRange {
+        match self.current {
+            Some(index) => self.expansions[index].range,
+            None => range,
+        }
+    }
+
+    fn alloc_expr_with_source(
+        &mut self,
+        expr: Expr,
+        source: ExprSource,
+    ) -> ExprId {
+        self.source_map.expr_sources.push(source);
+        self.exprs.alloc(expr)
+    }
+
+    fn alloc_pat(&mut self, pat: Pat, range: TextRange) -> PatId {
+        self.source_map.pat_ranges.push(range);
+        self.pats.alloc(pat)
+    }
+
+    fn missing(&mut self, range: TextRange) -> ExprId {
+        self.alloc_expr(Expr::Missing, range)
+    }
+
+    /// Lowers the first expression inside the given node, or a missing
+    /// expression at the end of the node if there is none.
+    fn lower_child_expr(&mut self, node: &SyntaxNode) -> ExprId {
+        match child_exprs(node).next() {
+            Some(child) => self.lower_expr(&child),
+            None => self.missing(TextRange::empty(trimmed_range(node).end())),
+        }
+    }
+
+    fn lower_expr(&mut self, node: &SyntaxNode) -> ExprId {
+        let range = trimmed_range(node);
+        let expr = match node.kind() {
+            SyntaxKind::Exp_Literal => match literal(node) {
+                Some(literal) => Expr::Literal(literal),
+                None => Expr::Missing,
+            },
+            SyntaxKind::Exp_VariableRef => {
+                let name = match first_token(node, SyntaxKind::Identifier) {
+                    Some(token) => name(&token),
+                    None => return self.missing(range),
+                };
+
+                match self.lower_arg(&name) {
+                    Some(expr_id) => return expr_id,
+                    None => Expr::Name(name),
+                }
+            }
+            SyntaxKind::Exp_MacroCall => return self.lower_macro_call(node),
+            SyntaxKind::Exp_Paren | SyntaxKind::Exp_Indented => {
+                return self.lower_child_expr(node);
+            }
+            SyntaxKind::Exp_UnaryPrefix => {
+                let op = operator(node).and_then(|token| match token.kind() {
+                    SyntaxKind::Sym_Minus => Some(UnaryOp::Neg),
+                    SyntaxKind::Sym_Bang => Some(UnaryOp::Not),
+                    _ => None,
+                });
+
+                let operand = self.lower_child_expr(node);
+                match op {
+                    Some(op) => Expr::Unary { op, operand },
+                    None => Expr::Missing,
+                }
+            }
+            SyntaxKind::Exp_Binary => return self.lower_binary(node),
+            _ => Expr::Missing,
+        };
+
+        self.alloc_expr(expr, range)
+    }
+
+    /// Lowers the argument given for the parameter with the given name, if
+    /// the template of a macro is being lowered and has such a parameter.
+    ///
+    /// The argument is lowered where the call was written, so names in it
+    /// never refer to the parameters of the macro (which keeps the expansion
+    /// hygienic), and it's lowered again wherever the parameter is used.
+    fn lower_arg(&mut self, name: &Name) -> Option<ExprId> {
+        let index = self.current?;
+        let arg = self.expansions[index].args.get(name)?.clone();
+
+        let current = self.current;
+        self.current = self.expansions[index].parent;
+        let expr_id = self.lower_expr(&arg);
+        self.current = current;
+        Some(expr_id)
+    }
+
+    /// Expands a macro call to the template of its macro, or to a missing
+    /// expression if it can't be (see [`MacroErrorKind`]).
+    fn lower_macro_call(&mut self, node: &SyntaxNode) -> ExprId {
+        let range = self.expanded_range(trimmed_range(node));
+        let name = match first_token(node, SyntaxKind::Identifier) {
+            Some(token) => name(&token),
+            None => return self.missing(trimmed_range(node)),
+        };
+
+        let depth = match self.current {
+            Some(index) => self.expansions[index].depth + 1,
+            None => 1,
+        };
+        let args = child_exprs(node).collect::<Vec<_>>();
+        let error = match self.macros.resolve(&name) {
+            None => Err(MacroErrorKind::Unresolved(name)),
+            Some(def) if def.params.len() != args.len() => {
+                Err(MacroErrorKind::ArgumentCount {
+                    name,
+                    expected: def.params.len(),
+                    found: args.len(),
+                })
+            }
+            Some(_) if depth > MAX_EXPANSION_DEPTH => {
+                Err(MacroErrorKind::TooDeep(name))
+            }
+            Some(def) => Ok(def),
+        };
+
+        let def = match error {
+            Ok(def) => def,
+            Err(kind) => {
+                self.source_map
+                    .macro_errors
+                    .push(MacroError { range, kind });
+                return self.missing(trimmed_range(node));
+            }
+        };
+
+        let template = match def.template {
+            Some(template) => template,
+            None => return self.missing(trimmed_range(node)),
+        };
+
+        self.expansions.push(Expansion {
+            args: def.params.into_iter().zip(args).collect(),
+            parent: self.current,
+            range,
+            depth,
+        });
+
+        let current = self.current;
+        self.current = Some(self.expansions.len() - 1);
+        let expr_id = self.lower_expr(&template);
+        self.current = current;
+        expr_id
+    }
+
+    fn lower_binary(&mut self, node: &SyntaxNode) -> ExprId {
+        let range = trimmed_range(node);
+        let op = match operator(node) {
+            Some(op) => op.kind(),
+            None => return self.missing(range),
+        };
+
+        if op == SyntaxKind::Sym_Semicolon {
+            let mut exprs = Vec::new();
+            self.lower_sequence(node, &mut exprs);
+            return self.alloc_desugared(Expr::Sequence(exprs), range);
+        }
+
+        let mut children = child_exprs(node);
+        let lhs = children.next();
+        let rhs = children.next();
+
+        let lhs = match lhs {
+            Some(lhs) => self.lower_expr(&lhs),
+            None => self.missing(TextRange::empty(range.start())),
+        };
+        let rhs = match rhs {
+            Some(rhs) => self.lower_expr(&rhs),
+            None => self.missing(TextRange::empty(range.end())),
+        };
+
+        let expr = match binary_op(op) {
+            Some(op) => Expr::Binary { op, lhs, rhs },
+            None if op == SyntaxKind::Sym_LThinArrow => Expr::Assign {
+                target: lhs,
+                value: rhs,
+            },
+            None => Expr::Missing,
+        };
+
+        self.alloc_expr(expr, range)
+    }
+
+    /// Lowers a chain of `;` (which is left-associative) to the list of
+    /// expressions it separates.
+    fn lower_sequence(&mut self, node: &SyntaxNode, exprs: &mut Vec<ExprId>) {
+        let mut children = child_exprs(node);
+        match children.next() {
+            Some(lhs) if is_sequence(&lhs) => self.lower_sequence(&lhs, exprs),
+            Some(lhs) => exprs.push(self.lower_expr(&lhs)),
+            None => {
+                let range = empty_at_start(node);
+                exprs.push(self.missing(range));
+            }
+        }
+
+        // A trailing `;` doesn't leave a missing expression behind.
+        if let Some(rhs) = children.next() {
+            exprs.push(self.lower_expr(&rhs));
+        }
+    }
+}
+
+fn is_sequence(node: &SyntaxNode) -> bool {
+    node.kind() == SyntaxKind::Exp_Binary
+        && operator(node)
+            .is_some_and(|op| op.kind() == SyntaxKind::Sym_Semicolon)
+}
+
+fn binary_op(kind: SyntaxKind) -> Option<BinaryOp> {
+    let op = match kind {
+        SyntaxKind::Sym_Plus => BinaryOp::Add,
+        SyntaxKind::Sym_Minus => BinaryOp::Sub,
+        SyntaxKind::Sym_Asterisk => BinaryOp::Mul,
+        SyntaxKind::Sym_ForwardSlash => BinaryOp::Div,
+        SyntaxKind::Sym_Eq => BinaryOp::Eq,
+        SyntaxKind::Sym_BangEq => BinaryOp::NotEq,
+        SyntaxKind::Sym_Lt => BinaryOp::Lt,
+        SyntaxKind::Sym_LtEq => BinaryOp::LtEq,
+        SyntaxKind::Sym_Gt => BinaryOp::Gt,
+        SyntaxKind::Sym_GtEq => BinaryOp::GtEq,
+        _ => return None,
+    };
+
+    Some(op)
+}
+
+/// The value of a number literal (see [`helios_parser::number_value`]).
+fn literal(node: &SyntaxNode) -> Option<Literal> {
+    let token = node.first_token()?;
+    let value = helios_parser::number_value(token.kind(), token.text())?;
+
+    // Literals too large for a `u64` don't fit in an `Int` either, so they're
+    // saturated to be reported as out of range (rather than as missing).
+    // Floats that large are reported when tokenizing, and round to infinity.
+    // Invalid literals are reported when tokenizing as well, and are missing.
+    match (token.kind(), value) {
+        (_, Ok(Number::Integer(value))) => Some(Literal::Integer(value)),
+        (_, Ok(Number::Float(value))) => {
+            Some(Literal::Float(Float::new(value)))
+        }
+        (SyntaxKind::Lit_Integer, Err(NumberError::OutOfRange)) => {
+            Some(Literal::Integer(u64::MAX))
+        }
+        (_, Err(NumberError::OutOfRange)) => {
+            Some(Literal::Float(Float::new(f64::INFINITY)))
+        }
+        (_, Err(NumberError::Invalid)) => None,
+    }
+}
+
+/// The value of a string literal (see [`helios_parser::cook_literal`]).
+fn string_value(token: &SyntaxToken) -> String {
+    helios_parser::cook_literal(token.kind(), token.text())
+        .map(|literal| literal.value)
+        .unwrap_or_default()
+}
+
+fn child_exprs(node: &SyntaxNode) -> impl Iterator<Item = SyntaxNode> {
+    node.children().filter(|child| child.kind().is_expression())
+}
+
+/// The operator token of a unary or binary expression.
+fn operator(node: &SyntaxNode) -> Option<SyntaxToken> {
+    node.children_with_tokens()
+        .filter_map(|element| element.into_token())
+        .find(|token| token.kind().is_symbol())
+}
+
+fn first_token(node: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxToken> {
+    node.children_with_tokens()
+        .filter_map(|element| element.into_token())
+        .find(|token| token.kind() == kind)
+}
+
+fn name(token: &SyntaxToken) -> Name {
+    Name::new(token.text())
+}
+
+fn empty_at_start(node: &SyntaxNode) -> TextRange {
+    TextRange::empty(node.text_range().start())
+}
+
+/// The range of a node without the trivia (such as whitespace) it ends with.
+fn trimmed_range(node: &SyntaxNode) -> TextRange {
+    let mut tokens = node
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| !token.kind().is_trivia());
+
+    match tokens.next() {
+        Some(first) => {
+            let last = tokens.last().unwrap_or_else(|| first.clone());
+            TextRange::new(first.text_range().start(), last.text_range().end())
+        }
+        None => empty_at_start(node),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helios_parser::{Edition, FileId, NodeCache};
+
+    fn parse(source: &str) -> SyntaxNode {
+        helios_parser::parse(FileId::default(), source).syntax()
+    }
+
+    /// Lowers the body of the only item of the given source text.
+    fn body(source: &str) -> (Body, BodySourceMap) {
+        let root = parse(source);
+        let node = item_nodes(&root).next().unwrap();
+        lower_body(&node)
+    }
+
+    fn expr(body: &Body, expr_id: ExprId) -> &Expr {
+        &body.exprs[expr_id]
+    }
+
+    #[test]
+    fn test_lower_module() {
+        let source = "2\nimport foo.bar\nlet a = 1\nlet = 3\n\
+            external func now: Int\nexternal func : Float\n\
+            test \"say \\\"hi\\\"\" = true\n";
+        let (module, source_map) = lower_module(&parse(source));
+
+        let items = module.items.iter().map(|(_, item)| item.clone());
+        assert_eq!(
+            items.collect::<Vec<_>>(),
+            vec![
+                Item::Expr,
+                Item::Import(Import {
+                    path: vec![Name::new("foo"), Name::new("bar")],
+                }),
+                Item::Binding(Binding {
+                    name: Some(Name::new("a")),
+                }),
+                Item::Binding(Binding { name: None }),
+                Item::External(External {
+                    name: Some(Name::new("now")),
+                    ty: Some(Name::new("Int")),
+                }),
+                Item::External(External {
+                    name: None,
+                    ty: Some(Name::new("Float")),
+                }),
+                Item::Test(Test {
+                    name: Some("say \"hi\"".to_string()),
+                }),
+            ]
+        );
+
+        let item_ids =
+            module.items.iter().map(|(id, _)| id).collect::<Vec<_>>();
+        let name_range = |index: usize| {
+            let range = source_map.name_range(item_ids[index])?;
+            Some(&source[range])
+        };
+        assert_eq!(name_range(0), None);
+        assert_eq!(name_range(1), Some("foo.bar"));
+        assert_eq!(name_range(2), Some("a"));
+        assert_eq!(name_range(3), None);
+        assert_eq!(name_range(4), Some("now"));
+        assert_eq!(name_range(5), None);
+        assert_eq!(name_range(6), Some(r#""say \"hi\"""#));
+
+        let item_range =
+            |index: usize| &source[source_map.item_range(item_ids[index])];
+        assert_eq!(item_range(0), "2");
+        assert_eq!(item_range(2), "let a = 1");
+
+        assert_eq!(module.bindings().count(), 2);
+        assert_eq!(module.imports().count(), 1);
+        assert_eq!(module.externals().count(), 2);
+        assert_eq!(module.tests().count(), 1);
+    }
+
+    #[test]
+    fn test_module_ignores_bodies() {
+        let (a, _) = lower_module(&parse("let a = 1 + 2\n"));
+        let (b, _) = lower_module(&parse("let a = (3)\n"));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_lower_binding() {
+        let source = "let a = -(1 + b)\n";
+        let (body, source_map) = body(source);
+
+        let pat = body.pat.unwrap();
+        assert_eq!(body.pats[pat], Pat::Bind(Name::new("a")));
+        assert_eq!(&source[source_map.pat_range(pat)], "a");
+
+        let operand = match expr(&body, body.value) {
+            Expr::Unary {
+                op: UnaryOp::Neg,
+                operand,
+            } => *operand,
+            expr => panic!("Unexpected expression: {expr:?}"),
+        };
+
+        // The parentheses are desugared away.
+        let (lhs, rhs) = match expr(&body, operand) {
+            Expr::Binary {
+                op: BinaryOp::Add,
+                lhs,
+                rhs,
+            } => (*lhs, *rhs),
+            expr => panic!("Unexpected expression: {expr:?}"),
+        };
+        assert_eq!(&source[source_map.expr_range(operand)], "1 + b");
+        assert_eq!(expr(&body, lhs), &Expr::Literal(Literal::Integer(1)));
+        assert_eq!(expr(&body, rhs), &Expr::Name(Name::new("b")));
+
+        let offset =
+            |text: &str| source.find(text).unwrap().try_into().unwrap();
+        assert_eq!(source_map.expr_at_offset(offset("b")), Some(rhs));
+        assert_eq!(source_map.expr_at_offset(offset("+")), Some(operand));
+        assert_eq!(source_map.expr_at_offset(offset("let")), None);
+    }
+
+    #[test]
+    fn test_lower_literals() {
+        let literal = |source: &str| {
+            let (body, _) = body(source);
+            body.exprs[body.value].clone()
+        };
+
+        assert_eq!(literal("1_000"), Expr::Literal(Literal::Integer(1000)));
+        assert_eq!(literal("0xff"), Expr::Literal(Literal::Integer(255)));
+        assert_eq!(literal("0b101"), Expr::Literal(Literal::Integer(5)));
+        assert_eq!(
+            literal("1.5"),
+            Expr::Literal(Literal::Float(Float::new(1.5)))
+        );
+        assert_eq!(
+            literal("6.02e23"),
+            Expr::Literal(Literal::Float(Float::new(6.02e23)))
+        );
+        assert_eq!(
+            literal("1E-9"),
+            Expr::Literal(Literal::Float(Float::new(1e-9)))
+        );
+        assert_eq!(
+            literal("0x1_0000_0000_0000_0000"),
+            Expr::Literal(Literal::Integer(u64::MAX))
+        );
+        assert_eq!(literal("0z"), Expr::Missing);
+        assert_eq!(literal("1e+"), Expr::Missing);
+    }
+
+    #[test]
+    fn test_lower_sequence() {
+        let source = "a; b <- 1; c";
+        let (body, source_map) = body(source);
+
+        let exprs = match expr(&body, body.value) {
+            Expr::Sequence(exprs) => exprs.clone(),
+            expr => panic!("Unexpected expression: {expr:?}"),
+        };
+        assert_eq!(exprs.len(), 3);
+        assert_eq!(expr(&body, exprs[0]), &Expr::Name(Name::new("a")));
+        assert!(matches!(expr(&body, exprs[1]), Expr::Assign { .. }));
+        assert_eq!(expr(&body, exprs[2]), &Expr::Name(Name::new("c")));
+
+        // The sequence is synthesized from the whole chain of `;`.
+        let range = TextRange::up_to(source.len().try_into().unwrap());
+        assert_eq!(
+            source_map.expr_source(body.value),
+            ExprSource::Desugared(range)
+        );
+        assert_eq!(
+            source_map.expr_source(exprs[2]),
+            ExprSource::Written(TextRange::new(11.into(), 12.into()))
+        );
+        assert_eq!(source_map.expr_at_offset(2.into()), Some(body.value));
+    }
+
+    #[test]
+    fn test_lower_macro() {
+        // `macro` is only read as a keyword before the name of a macro unless
+        // it's reserved, which the second (nameless) declaration needs.
+        let source = "macro add x y = x + y\nmacro = 1\n";
+        let root = helios_parser::parse_with_edition(
+            FileId::default(),
+            source,
+            Edition::Edition2026,
+            &mut NodeCache::default(),
+        );
+        let (module, source_map) = lower_module(&root.syntax());
+
+        let macros = module.macros().map(|(_, item)| item.clone());
+        assert_eq!(
+            macros.collect::<Vec<_>>(),
+            vec![
+                Macro {
+                    name: Some(Name::new("add")),
+                    params: vec![Name::new("x"), Name::new("y")],
+                },
+                Macro {
+                    name: None,
+                    params: Vec::new(),
+                },
+            ]
+        );
+
+        let item_id = module.items.iter().next().unwrap().0;
+        assert_eq!(
+            source_map.name_range(item_id).map(|r| &source[r]),
+            Some("add")
+        );
+
+        // Macros are only lowered where they're called.
+        let (body, _) = body("macro add x y = x + y\n");
+        assert_eq!(expr(&body, body.value), &Expr::Missing);
+    }
+
+    /// Lowers the body of the last item of the given source text, which may
+    /// call the macros declared before it.
+    fn last_body(source: &str) -> (Body, BodySourceMap) {
+        let root = parse(source);
+        let node = item_nodes(&root).last().unwrap();
+        lower_body(&node)
+    }
+
+    #[test]
+    fn test_expand_macro() {
+        let source = "macro square x = x * x\nlet a = square!(1 + 2)\n";
+        let (body, source_map) = last_body(source);
+
+        let (lhs, rhs) = match expr(&body, body.value) {
+            Expr::Binary {
+                op: BinaryOp::Mul,
+                lhs,
+                rhs,
+            } => (*lhs, *rhs),
+            expr => panic!("Unexpected expression: {expr:?}"),
+        };
+
+        // The argument is lowered again wherever the parameter is used.
+        assert_ne!(lhs, rhs);
+        for operand in [lhs, rhs] {
+            assert!(matches!(
+                expr(&body, operand),
+                Expr::Binary {
+                    op: BinaryOp::Add,
+                    ..
+                }
+            ));
+            assert_eq!(&source[source_map.expr_range(operand)], "1 + 2");
+        }
+
+        // The template is mapped to the call.
+        let call = source.find("square!").unwrap();
+        let range = TextRange::new(
+            call.try_into().unwrap(),
+            (source.len() - 1).try_into().unwrap(),
+        );
+        assert_eq!(
+            source_map.expr_source(body.value),
+            ExprSource::Desugared(range)
+        );
+        assert!(source_map.macro_errors().is_empty());
+    }
+
+    #[test]
+    fn test_expand_macro_hygiene() {
+        // The `y` given for `x` refers to the binding, not to the parameter.
+        let source = "macro add x y = x + y\nlet y = 1\nlet a = add!(y, 2)\n";
+        let (body, _) = last_body(source);
+
+        let (lhs, rhs) = match expr(&body, body.value) {
+            Expr::Binary { lhs, rhs, .. } => (*lhs, *rhs),
+            expr => panic!("Unexpected expression: {expr:?}"),
+        };
+        assert_eq!(expr(&body, lhs), &Expr::Name(Name::new("y")));
+        assert_eq!(expr(&body, rhs), &Expr::Literal(Literal::Integer(2)));
+
+        // Arguments given in a template refer to the parameters of the macro
+        // the template belongs to.
+        let source = "macro id x = x\nmacro twice x = id!(x) + id!(x)\n\
+            let a = twice!(3)\n";
+        let (body, _) = last_body(source);
+        let (lhs, rhs) = match expr(&body, body.value) {
+            Expr::Binary { lhs, rhs, .. } => (*lhs, *rhs),
+            expr => panic!("Unexpected expression: {expr:?}"),
+        };
+        assert_eq!(expr(&body, lhs), &Expr::Literal(Literal::Integer(3)));
+        assert_eq!(expr(&body, rhs), &Expr::Literal(Literal::Integer(3)));
+    }
+
+    #[test]
+    fn test_macro_errors() {
+        let errors = |source: &'static str| {
+            let (body, source_map) = last_body(source);
+            assert_eq!(expr(&body, body.value), &Expr::Missing);
+            let errors = source_map.macro_errors().iter();
+            errors
+                .map(|error| (&source[error.range], error.kind.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            errors("let a = nope!()\n"),
+            vec![("nope!()", MacroErrorKind::Unresolved(Name::new("nope")))]
+        );
+        assert_eq!(
+            errors("macro add x y = x + y\nlet a = add!(1)\n"),
+            vec![(
+                "add!(1)",
+                MacroErrorKind::ArgumentCount {
+                    name: Name::new("add"),
+                    expected: 2,
+                    found: 1,
+                }
+            )]
+        );
+
+        // Errors inside a template are reported at the outermost call.
+        assert_eq!(
+            errors("macro forever = forever!()\nlet a = forever!()\n"),
+            vec![("forever!()", MacroErrorKind::TooDeep(Name::new("forever")))]
+        );
+    }
+
+    #[test]
+    fn test_lower_missing_expressions() {
+        let (body, source_map) = body("let a = 1 +");
+
+        let rhs = match expr(&body, body.value) {
+            Expr::Binary { rhs, .. } => *rhs,
+            expr => panic!("Unexpected expression: {expr:?}"),
+        };
+        assert_eq!(expr(&body, rhs), &Expr::Missing);
+        assert!(source_map.expr_range(rhs).is_empty());
+
+        let (empty, _) = self::body("let a =");
+        assert_eq!(expr(&empty, empty.value), &Expr::Missing);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::Name;
+use helios_syntax::{SyntaxKind, SyntaxNode};
+use rowan::TextRange;
+use std::collections::HashMap;
+
+/// The most macro calls that can be expanded inside each other, which stops
+/// macros that call themselves from expanding forever.
+pub const MAX_EXPANSION_DEPTH: usize = 32;
+
+/// What a macro call expands to: the expression its macro is declared with
+/// (see [`crate::Item::Macro`]), where its parameters stand for the arguments
+/// of the call.
+#[derive(Clone, Debug)]
+pub struct MacroDef {
+    pub params: Vec<Name>,
+    /// The expression the call expands to, if the declaration has one.
+    pub template: Option<SyntaxNode>,
+}
+
+/// Finds the macro a call refers to by its name, which is how macros are
+/// expanded while lowering bodies (see [`crate::lower_body_with_macros`]).
+///
+/// Only the macros declared in the same file are found for now (see
+/// [`FileMacros`]), but anything able to produce a template can stand in, such
+/// as macros imported from other files or generated at compile time.
+pub trait MacroResolver {
+    fn resolve(&self, name: &Name) -> Option<MacroDef>;
+}
+
+/// The macros declared at the top level of a file.
+///
+/// Calls to a macro declared more than once expand its first declaration.
+#[derive(Clone, Debug, Default)]
+pub struct FileMacros {
+    macros: HashMap<Name, MacroDef>,
+}
+
+impl FileMacros {
+    pub fn new(root: &SyntaxNode) -> Self {
+        let mut macros = HashMap::new();
+        for node in root.children() {
+            if node.kind() != SyntaxKind::Dec_Macro {
+                continue;
+            }
+
+            // The name comes first, followed by the parameters.
+            let mut identifiers = node
+                .children_with_tokens()
+                .filter_map(|element| element.into_token())
+                .take_while(|token| token.kind() != SyntaxKind::Sym_Eq)
+                .filter(|token| token.kind() == SyntaxKind::Identifier)
+                .map(|token| Name::new(token.text()));
+
+            if let Some(name) = identifiers.next() {
+                let template =
+                    node.children().find(|child| child.kind().is_expression());
+                macros.entry(name).or_insert(MacroDef {
+                    params: identifiers.collect(),
+                    template,
+                });
+            }
+        }
+
+        Self { macros }
+    }
+}
+
+impl MacroResolver for FileMacros {
+    fn resolve(&self, name: &Name) -> Option<MacroDef> {
+        self.macros.get(name).cloned()
+    }
+}
+
+/// A macro call that couldn't be expanded, which is lowered to a missing
+/// expression instead.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MacroError {
+    /// The range of the call (or of the outermost call it was expanded from,
+    /// if it comes from the template of another macro).
+    pub range: TextRange,
+    pub kind: MacroErrorKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MacroErrorKind {
+    /// No macro is declared with the name of the call.
+    Unresolved(Name),
+    /// The call doesn't give the macro as many arguments as it has
+    /// parameters.
+    ArgumentCount {
+        name: Name,
+        expected: usize,
+        found: usize,
+    },
+    /// The call is nested in more than [`MAX_EXPANSION_DEPTH`] other calls
+    /// (such as if the macro calls itself).
+    TooDeep(Name),
+}
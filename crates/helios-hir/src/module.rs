@@ -0,0 +1,143 @@
+use crate::{Arena, Idx, Name};
+use rowan::TextRange;
+
+pub type ItemId = Idx<Item>;
+
+/// The items declared in a file, in the order they appear.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Module {
+    pub items: Arena<Item>,
+}
+
+impl Module {
+    /// Every global binding of the module, along with its item.
+    pub fn bindings(&self) -> impl Iterator<Item = (ItemId, &Binding)> + '_ {
+        self.items.iter().filter_map(|(item_id, item)| match item {
+            Item::Binding(binding) => Some((item_id, binding)),
+            _ => None,
+        })
+    }
+
+    /// The global binding named `main`, which is the entry point of a program,
+    /// if the module declares one.
+    pub fn main(&self) -> Option<ItemId> {
+        self.bindings()
+            .find(|(_, binding)| {
+                binding
+                    .name
+                    .as_ref()
+                    .is_some_and(|name| name.as_str() == "main")
+            })
+            .map(|(item_id, _)| item_id)
+    }
+
+    /// Every external function of the module, along with its item.
+    pub fn externals(&self) -> impl Iterator<Item = (ItemId, &External)> + '_ {
+        self.items.iter().filter_map(|(item_id, item)| match item {
+            Item::External(external) => Some((item_id, external)),
+            _ => None,
+        })
+    }
+
+    /// Every test of the module, along with its item.
+    pub fn tests(&self) -> impl Iterator<Item = (ItemId, &Test)> + '_ {
+        self.items.iter().filter_map(|(item_id, item)| match item {
+            Item::Test(test) => Some((item_id, test)),
+            _ => None,
+        })
+    }
+
+    /// Every macro of the module, along with its item.
+    pub fn macros(&self) -> impl Iterator<Item = (ItemId, &Macro)> + '_ {
+        self.items.iter().filter_map(|(item_id, item)| match item {
+            Item::Macro(macro_) => Some((item_id, macro_)),
+            _ => None,
+        })
+    }
+
+    /// Every import of the module, along with its item.
+    pub fn imports(&self) -> impl Iterator<Item = (ItemId, &Import)> + '_ {
+        self.items.iter().filter_map(|(item_id, item)| match item {
+            Item::Import(import) => Some((item_id, import)),
+            _ => None,
+        })
+    }
+}
+
+/// A top-level declaration (or expression) of a file.
+///
+/// Items that have a body (see [`crate::lower_body`]) only describe what they
+/// declare, so that changing the body doesn't change the item.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Item {
+    /// A global binding (such as `let a = 1`).
+    Binding(Binding),
+    /// An import declaration (such as `import foo.bar`).
+    Import(Import),
+    /// A function provided by the host (such as `external func now: Int`),
+    /// which is called every time it's referred to.
+    External(External),
+    /// A test (such as `test "addition" = 1 + 1 == 2`), which passes if its
+    /// body evaluates to `true`.
+    Test(Test),
+    /// A macro (such as `macro twice x = x + x`), whose calls (such as
+    /// `twice!(a)`) are expanded when lowering bodies. Its name can only be
+    /// called, so it isn't part of the scope of the module.
+    Macro(Macro),
+    /// An expression on its own (which only has a body).
+    Expr,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Binding {
+    /// The name of the binding, if it has one.
+    pub name: Option<Name>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Import {
+    /// The path of the imported module.
+    pub path: Vec<Name>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct External {
+    /// The name of the function, if it has one.
+    pub name: Option<Name>,
+    /// The name of the type of the value the function returns, if it has one.
+    pub ty: Option<Name>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Test {
+    /// The name of the test (without quotes or escapes), if it has one.
+    pub name: Option<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Macro {
+    /// The name of the macro, if it has one.
+    pub name: Option<Name>,
+    /// The names of the parameters of the macro.
+    pub params: Vec<Name>,
+}
+
+/// Where the items of a [`Module`] come from in the source text.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ModuleSourceMap {
+    pub(crate) item_ranges: Vec<TextRange>,
+    pub(crate) name_ranges: Vec<Option<TextRange>>,
+}
+
+impl ModuleSourceMap {
+    /// The range of the whole declaration of an item.
+    pub fn item_range(&self, item_id: ItemId) -> TextRange {
+        self.item_ranges[item_id.into_raw() as usize]
+    }
+
+    /// The range of the name a binding, external function, macro or test
+    /// declares (or the path an import imports), if it has one.
+    pub fn name_range(&self, item_id: ItemId) -> Option<TextRange> {
+        self.name_ranges[item_id.into_raw() as usize]
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{Body, Expr, ExprId, Item, ItemId, Module, Name};
+use std::collections::HashMap;
+
+/// The names declared at the top level of a module (by its global bindings,
+/// external functions and imports).
+///
+/// Every name is visible throughout the module, whether it's declared before
+/// or after the item referring to it (so that bindings can refer to each
+/// other). A name can therefore only be declared once per module: any other
+/// declaration of it is a [`Duplicate`], and references to the name resolve
+/// to the first one.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ModuleScope {
+    names: HashMap<Name, ItemId>,
+    duplicates: Vec<Duplicate>,
+}
+
+/// A name that is declared more than once in the same scope.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Duplicate {
+    pub name: Name,
+    /// The item that declared the name first.
+    pub first: ItemId,
+    /// The item declaring the name again.
+    pub duplicate: ItemId,
+}
+
+impl ModuleScope {
+    pub fn new(module: &Module) -> Self {
+        let mut scope = Self::default();
+        for (item_id, item) in module.items.iter() {
+            if let Some(name) = item_name(item) {
+                match scope.names.get(name) {
+                    Some(&first) => scope.duplicates.push(Duplicate {
+                        name: name.clone(),
+                        first,
+                        duplicate: item_id,
+                    }),
+                    None => {
+                        scope.names.insert(name.clone(), item_id);
+                    }
+                }
+            }
+        }
+
+        scope
+    }
+
+    /// The item declaring the given name, if there is one.
+    pub fn get(&self, name: &Name) -> Option<ItemId> {
+        self.names.get(name).copied()
+    }
+
+    /// Every name declared more than once, in the order they appear.
+    pub fn duplicates(&self) -> &[Duplicate] {
+        &self.duplicates
+    }
+}
+
+/// The name an item declares, if any (which is the last segment of the path
+/// of an import, such as `bar` for `import foo.bar`).
+pub fn item_name(item: &Item) -> Option<&Name> {
+    match item {
+        Item::Binding(binding) => binding.name.as_ref(),
+        Item::External(external) => external.name.as_ref(),
+        Item::Import(import) => import.path.last(),
+        Item::Test(_) | Item::Macro(_) | Item::Expr => None,
+    }
+}
+
+/// What the names referred to in a body resolve to.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct NameResolutions {
+    resolved: HashMap<ExprId, ItemId>,
+    prelude: HashMap<ExprId, ItemId>,
+    unresolved: Vec<ExprId>,
+}
+
+impl NameResolutions {
+    /// Resolves every name referred to in the given body.
+    ///
+    /// Expressions don't declare names of their own (yet), so every name is
+    /// looked up in the scope of the module.
+    pub fn new(scope: &ModuleScope, body: &Body) -> Self {
+        Self::with_prelude(scope, &ModuleScope::default(), body)
+    }
+
+    /// Resolves every name referred to in the given body, falling back to the
+    /// scope of the prelude for the names the module doesn't declare (so that
+    /// a module can shadow the names of the prelude).
+    pub fn with_prelude(
+        scope: &ModuleScope,
+        prelude: &ModuleScope,
+        body: &Body,
+    ) -> Self {
+        let mut resolutions = Self::default();
+        for (expr_id, expr) in body.exprs.iter() {
+            if let Expr::Name(name) = expr {
+                if let Some(item_id) = scope.get(name) {
+                    resolutions.resolved.insert(expr_id, item_id);
+                } else if let Some(item_id) = prelude.get(name) {
+                    resolutions.prelude.insert(expr_id, item_id);
+                } else {
+                    resolutions.unresolved.push(expr_id);
+                }
+            }
+        }
+
+        resolutions
+    }
+
+    /// The item the name referred to by the given expression resolves to.
+    pub fn get(&self, expr_id: ExprId) -> Option<ItemId> {
+        self.resolved.get(&expr_id).copied()
+    }
+
+    /// The item of the prelude the name referred to by the given expression
+    /// resolves to, if the module doesn't declare the name itself.
+    pub fn prelude(&self, expr_id: ExprId) -> Option<ItemId> {
+        self.prelude.get(&expr_id).copied()
+    }
+
+    /// Every item of the module the names referred to in the body resolve to
+    /// (once for every reference, in no particular order).
+    pub fn resolved_items(&self) -> impl Iterator<Item = ItemId> + '_ {
+        self.resolved.values().copied()
+    }
+
+    /// Every expression referring to a name that doesn't resolve to anything,
+    /// in the order they were lowered.
+    pub fn unresolved(&self) -> &[ExprId] {
+        &self.unresolved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{item_nodes, lower_body, lower_module};
+    use helios_parser::FileId;
+
+    #[test]
+    fn test_module_scope() {
+        let source = "import foo.a\nlet b = a + c\nlet a = 1\nlet b = 2\n";
+        let root = helios_parser::parse(FileId::default(), source).syntax();
+        let (module, _) = lower_module(&root);
+        let scope = ModuleScope::new(&module);
+
+        let items = module.items.iter().map(|(id, _)| id).collect::<Vec<_>>();
+        assert_eq!(scope.get(&Name::new("a")), Some(items[0]));
+        assert_eq!(scope.get(&Name::new("b")), Some(items[1]));
+        assert_eq!(scope.get(&Name::new("foo")), None);
+        assert_eq!(
+            scope.duplicates(),
+            &[
+                Duplicate {
+                    name: Name::new("a"),
+                    first: items[0],
+                    duplicate: items[2],
+                },
+                Duplicate {
+                    name: Name::new("b"),
+                    first: items[1],
+                    duplicate: items[3],
+                },
+            ]
+        );
+
+        let node = item_nodes(&root).nth(1).unwrap();
+        let (body, _) = lower_body(&node);
+        let resolutions = NameResolutions::new(&scope, &body);
+
+        let names = body
+            .exprs
+            .iter()
+            .filter(|(_, expr)| matches!(expr, Expr::Name(_)))
+            .map(|(expr_id, _)| expr_id)
+            .collect::<Vec<_>>();
+        assert_eq!(resolutions.get(names[0]), Some(items[0]));
+        assert_eq!(resolutions.get(names[1]), None);
+        assert_eq!(resolutions.unresolved(), &[names[1]]);
+    }
+
+    fn item_ids(module: &Module) -> Vec<ItemId> {
+        module.items.iter().map(|(item_id, _)| item_id).collect()
+    }
+
+    #[test]
+    fn test_prelude_resolutions() {
+        let root =
+            helios_parser::parse(FileId::default(), "let a = 1\nlet c = 2\n")
+                .syntax();
+        let (prelude, _) = lower_module(&root);
+        let prelude_scope = ModuleScope::new(&prelude);
+
+        let root = helios_parser::parse(
+            FileId::default(),
+            "let b = a + c + d\nlet c = 3\n",
+        )
+        .syntax();
+        let (module, _) = lower_module(&root);
+        let scope = ModuleScope::new(&module);
+
+        let node = item_nodes(&root).next().unwrap();
+        let (body, _) = lower_body(&node);
+        let resolutions =
+            NameResolutions::with_prelude(&scope, &prelude_scope, &body);
+
+        let names = body
+            .exprs
+            .iter()
+            .filter(|(_, expr)| matches!(expr, Expr::Name(_)))
+            .map(|(expr_id, _)| expr_id)
+            .collect::<Vec<_>>();
+        let prelude_items = item_ids(&prelude);
+        let module_items = item_ids(&module);
+
+        // `a` comes from the prelude, but `c` is shadowed by the module.
+        assert_eq!(resolutions.get(names[0]), None);
+        assert_eq!(resolutions.prelude(names[0]), Some(prelude_items[0]));
+        assert_eq!(resolutions.get(names[1]), Some(module_items[1]));
+        assert_eq!(resolutions.prelude(names[1]), None);
+        assert_eq!(resolutions.unresolved(), &[names[2]]);
+        assert_eq!(
+            resolutions.resolved_items().collect::<Vec<_>>(),
+            [module_items[1]]
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! User settings of the language server.
+//!
+//! Settings are read from the `initializationOptions` the client starts the
+//! server with, and replaced whenever the client sends a
+//! `workspace/didChangeConfiguration` notification. Every key is optional, and
+//! the settings may be nested under a `helios` key (as most editors do). For
+//! example:
+//!
+//! ```json
+//! {
+//!     "helios": {
+//!         "inlayHints": { "enable": false },
+//!         "diagnostics": {
+//!             "severity": { "Unknown character": "warning" }
+//!         },
+//!         "formatting": { "indentWidth": 2, "onType": false },
+//!         "lru": { "capacity": 64 },
+//!         "files": { "exclude": ["examples", "**/*.gen.helios"] }
+//!     }
+//! }
+//! ```
+//!
+//! The formatting settings only apply to documents outside of any project:
+//! those of a project are set by the `[fmt]` section of its `helios.toml`, so
+//! that every editor formats them the same way as `helios fmt` does.
+
+use helios_diagnostics::{Diagnostic, Severity};
+use helios_parser::printer::{self, FormatterConfig};
+use lsp_types::DiagnosticSeverity;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The key the settings of the server may be nested under.
+const SECTION: &str = "helios";
+
+/// The user's settings of the language server.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Config {
+    pub inlay_hints: InlayHintsConfig,
+    pub diagnostics: DiagnosticsConfig,
+    pub formatting: FormattingConfig,
+    pub lru: LruConfig,
+    pub files: FilesConfig,
+}
+
+impl Config {
+    /// Parses the settings sent by the client.
+    ///
+    /// A missing (`null`) value results in the default settings.
+    pub fn from_json(value: Value) -> serde_json::Result<Self> {
+        let value = match value {
+            Value::Object(mut object) if object.contains_key(SECTION) => {
+                object.remove(SECTION).unwrap_or_default()
+            }
+            value => value,
+        };
+
+        if value.is_null() {
+            Ok(Self::default())
+        } else {
+            serde_json::from_value(value)
+        }
+    }
+}
+
+/// Settings of the hints shown inline with the source text.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct InlayHintsConfig {
+    /// Whether or not to show inlay hints (such as inferred types).
+    pub enable: bool,
+}
+
+impl Default for InlayHintsConfig {
+    fn default() -> Self {
+        Self { enable: true }
+    }
+}
+
+/// Settings of the diagnostics reported to the client.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DiagnosticsConfig {
+    /// Whether or not to report diagnostics at all.
+    pub enable: bool,
+    /// The severity to report diagnostics with, by their title.
+    pub severity: HashMap<String, SeverityOverride>,
+}
+
+impl Default for DiagnosticsConfig {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            severity: HashMap::new(),
+        }
+    }
+}
+
+impl DiagnosticsConfig {
+    /// The severity the given diagnostic should be reported with, or `None` if
+    /// it shouldn't be reported.
+    pub fn severity<FileId>(
+        &self,
+        diagnostic: &Diagnostic<FileId>,
+    ) -> Option<DiagnosticSeverity> {
+        if !self.enable {
+            return None;
+        }
+
+        match self.severity.get(&diagnostic.title) {
+            Some(SeverityOverride::Error) => Some(DiagnosticSeverity::ERROR),
+            Some(SeverityOverride::Warning) => {
+                Some(DiagnosticSeverity::WARNING)
+            }
+            Some(SeverityOverride::Information) => {
+                Some(DiagnosticSeverity::INFORMATION)
+            }
+            Some(SeverityOverride::Hint) => Some(DiagnosticSeverity::HINT),
+            Some(SeverityOverride::Off) => None,
+            None => Some(match diagnostic.severity {
+                Severity::Bug | Severity::Error => DiagnosticSeverity::ERROR,
+                Severity::Warning => DiagnosticSeverity::WARNING,
+                Severity::Note => DiagnosticSeverity::INFORMATION,
+            }),
+        }
+    }
+}
+
+/// The severity a kind of diagnostic is reported with instead of its own.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SeverityOverride {
+    Error,
+    Warning,
+    Information,
+    Hint,
+    /// The diagnostic isn't reported.
+    Off,
+}
+
+/// Settings of the formatter.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FormattingConfig {
+    /// The number of spaces per indentation level.
+    pub indent_width: usize,
+    /// Whether or not to format lines as they're typed.
+    pub on_type: bool,
+}
+
+impl Default for FormattingConfig {
+    fn default() -> Self {
+        Self {
+            indent_width: printer::INDENT_WIDTH,
+            on_type: true,
+        }
+    }
+}
+
+impl FormattingConfig {
+    /// The settings to pretty-print documents outside of any project with.
+    pub fn formatter_config(&self) -> FormatterConfig {
+        FormatterConfig {
+            // A width of zero would flatten every block.
+            indent_width: self.indent_width.max(1),
+            ..FormatterConfig::default()
+        }
+    }
+}
+
+/// Settings of the memory used by the server.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LruConfig {
+    /// The number of files whose syntax trees are kept in memory (or zero to
+    /// keep all of them).
+    pub capacity: usize,
+}
+
+impl Default for LruConfig {
+    fn default() -> Self {
+        Self {
+            capacity: helios_query::DEFAULT_LRU_CAPACITY,
+        }
+    }
+}
+
+/// Settings of the files that are part of the workspace.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FilesConfig {
+    /// Globs (relative to each workspace folder) of the files and directories
+    /// to leave out of the workspace.
+    pub exclude: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_json() {
+        assert_eq!(Config::from_json(Value::Null).unwrap(), Config::default());
+        assert_eq!(Config::from_json(json!({})).unwrap(), Config::default());
+
+        let config = Config::from_json(json!({
+            "helios": {
+                "inlayHints": { "enable": false },
+                "formatting": { "indentWidth": 2 },
+                "files": { "exclude": ["examples"] }
+            }
+        }))
+        .unwrap();
+
+        assert!(!config.inlay_hints.enable);
+        assert!(config.diagnostics.enable);
+        assert_eq!(config.formatting.indent_width, 2);
+        assert!(config.formatting.on_type);
+        assert_eq!(config.lru.capacity, helios_query::DEFAULT_LRU_CAPACITY);
+        assert_eq!(config.files.exclude, vec!["examples"]);
+
+        let error = json!({ "diagnostics": { "severity": { "a": "fatal" } } });
+        assert!(Config::from_json(error).is_err());
+    }
+
+    #[test]
+    fn test_diagnostic_severity() {
+        let config = Config::from_json(json!({
+            "diagnostics": {
+                "severity": {
+                    "Unknown character": "hint",
+                    "Unexpected end of file": "off"
+                }
+            }
+        }))
+        .unwrap();
+
+        let severity = |title: &str| {
+            let diagnostic = Diagnostic::<()>::error(title);
+            config.diagnostics.severity(&diagnostic)
+        };
+
+        assert_eq!(
+            severity("Unknown character"),
+            Some(DiagnosticSeverity::HINT)
+        );
+        assert_eq!(severity("Unexpected end of file"), None);
+        assert_eq!(
+            severity("Missing identifier"),
+            Some(DiagnosticSeverity::ERROR)
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use crate::handlers::semantic_tokens_legend;
+use crate::Result;
+use helios_query::FILE_EXTENSION;
+use lsp_server::{Connection, IoThreads, Request, RequestId};
+use lsp_types::notification::{DidChangeWatchedFiles, Notification as _};
+use lsp_types::request::{RegisterCapability, Request as _};
+use lsp_types::{
+    CodeActionKind, CodeActionOptions, CodeActionProviderCapability,
+    CompletionOptions, DidChangeWatchedFilesRegistrationOptions,
+    DocumentOnTypeFormattingOptions, FileSystemWatcher, GlobPattern,
+    HoverProviderCapability, InitializeParams, InitializeResult, OneOf,
+    Registration, RegistrationParams, RenameOptions,
+    SelectionRangeProviderCapability, SemanticTokensFullOptions,
+    SemanticTokensOptions, SemanticTokensServerCapabilities,
+    ServerCapabilities, ServerInfo, TextDocumentSyncCapability,
+    TextDocumentSyncKind, WorkDoneProgressOptions,
+};
+use std::io;
+use std::net::Ipv4Addr;
+
+/// How the server communicates with the client.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Transport {
+    /// Messages are read from stdin and written to stdout.
+    Stdio,
+    /// The server waits for the client to connect to the given local port.
+    ///
+    /// This is handy for debugging, since the server can be started (and
+    /// attached to) separately from the editor.
+    Listen(u16),
+    /// The server connects to the client, which is listening on the given
+    /// local port.
+    Connect(u16),
+}
+
+impl Transport {
+    /// Opens a connection to the client over this transport.
+    pub fn open(self) -> io::Result<(Connection, IoThreads)> {
+        match self {
+            Self::Stdio => Ok(Connection::stdio()),
+            Self::Listen(port) => {
+                tracing::info!(port, "Waiting for a client");
+                Connection::listen((Ipv4Addr::LOCALHOST, port))
+            }
+            Self::Connect(port) => {
+                Connection::connect((Ipv4Addr::LOCALHOST, port))
+            }
+        }
+    }
+}
+
+/// The features supported by the server.
+pub fn server_capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::FULL,
+        )),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        document_on_type_formatting_provider: Some(
+            DocumentOnTypeFormattingOptions {
+                first_trigger_character: "\n".to_string(),
+                more_trigger_character: None,
+            },
+        ),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        inlay_hint_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        code_action_provider: Some(CodeActionProviderCapability::Options(
+            CodeActionOptions {
+                code_action_kinds: Some(vec![
+                    CodeActionKind::QUICKFIX,
+                    CodeActionKind::SOURCE_ORGANIZE_IMPORTS,
+                ]),
+                ..CodeActionOptions::default()
+            },
+        )),
+        definition_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        selection_range_provider: Some(
+            SelectionRangeProviderCapability::Simple(true),
+        ),
+        workspace_symbol_provider: Some(OneOf::Left(true)),
+        semantic_tokens_provider: Some(
+            SemanticTokensServerCapabilities::SemanticTokensOptions(
+                SemanticTokensOptions {
+                    legend: semantic_tokens_legend(),
+                    full: Some(SemanticTokensFullOptions::Bool(true)),
+                    ..SemanticTokensOptions::default()
+                },
+            ),
+        ),
+        rename_provider: Some(OneOf::Right(RenameOptions {
+            prepare_provider: Some(true),
+            work_done_progress_options: WorkDoneProgressOptions::default(),
+        })),
+        experimental: Some(serde_json::json!({
+            "syntaxTree": true,
+            "status": true,
+        })),
+        ..ServerCapabilities::default()
+    }
+}
+
+/// Completes the initialization handshake with the client, returning the
+/// parameters the client initialized the server with.
+pub fn initialize(connection: &Connection) -> Result<InitializeParams> {
+    let (id, params) = connection.initialize_start()?;
+    let result = InitializeResult {
+        capabilities: server_capabilities(),
+        server_info: Some(ServerInfo {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        }),
+    };
+
+    connection.initialize_finish(id, serde_json::to_value(result)?)?;
+    tracing::info!("Initialized server");
+
+    Ok(serde_json::from_value(params)?)
+}
+
+/// Asks the client to notify the server about changes to Helios files made
+/// outside of the client (such as by switching branches), if the client
+/// supports registering for them.
+pub fn register_file_watchers(
+    connection: &Connection,
+    params: &InitializeParams,
+) -> Result<()> {
+    let supported = params
+        .capabilities
+        .workspace
+        .as_ref()
+        .and_then(|workspace| workspace.did_change_watched_files)
+        .and_then(|capabilities| capabilities.dynamic_registration)
+        .unwrap_or(false);
+
+    if !supported {
+        tracing::info!("Client cannot watch files");
+        return Ok(());
+    }
+
+    let options = DidChangeWatchedFilesRegistrationOptions {
+        watchers: vec![FileSystemWatcher {
+            glob_pattern: GlobPattern::String(format!("**/*.{FILE_EXTENSION}")),
+            kind: None,
+        }],
+    };
+
+    let params = RegistrationParams {
+        registrations: vec![Registration {
+            id: DidChangeWatchedFiles::METHOD.to_string(),
+            method: DidChangeWatchedFiles::METHOD.to_string(),
+            register_options: Some(serde_json::to_value(options)?),
+        }],
+    };
+
+    let request = Request::new(
+        RequestId::from(RegisterCapability::METHOD.to_string()),
+        RegisterCapability::METHOD.to_string(),
+        params,
+    );
+
+    connection.sender.send(request.into())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_server::{Message, Notification};
+    use std::io::BufReader;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_connect_transport() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let (connection, io_threads) = Transport::Connect(port).open().unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        let notification = Notification::new("helios/test".to_string(), ());
+        connection.sender.send(notification.into()).unwrap();
+        drop(connection);
+
+        let mut reader = BufReader::new(&stream);
+        match Message::read(&mut reader).unwrap() {
+            Some(Message::Notification(notification)) => {
+                assert_eq!(notification.method, "helios/test")
+            }
+            message => panic!("Unexpected message: {message:?}"),
+        }
+
+        stream.shutdown(std::net::Shutdown::Both).unwrap();
+        io_threads.join().unwrap();
+    }
+}
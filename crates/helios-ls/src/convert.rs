@@ -0,0 +1,172 @@
+//! Conversions between the types used by the server and those used by the
+//! Language Server Protocol.
+//!
+//! Positions in the protocol are given as a line number and a column counted
+//! in UTF-16 code units, whereas the database works with byte offsets.
+
+use helios_diagnostics::Diagnostic;
+use helios_query::{FileId, HeliosDatabase, Input, InputLocation};
+use lsp_types::{
+    DiagnosticRelatedInformation, DiagnosticSeverity, Location, Position, Url,
+};
+use std::ops::Range;
+
+/// Converts a position into a byte offset into the file's source text.
+///
+/// Positions past the end of a line are clamped to the end of that line, and
+/// positions past the last line are clamped to the end of the file.
+pub fn offset(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    position: Position,
+) -> usize {
+    let line_count = db.source_line_indexes(file_id).len();
+    let line_index = position.line as usize;
+    if line_index >= line_count {
+        return db.source_len(file_id);
+    }
+
+    let source = db.source(file_id);
+    let line_range = db.source_line_range(file_id, line_index);
+    let line = source[line_range.clone()].trim_end_matches(['\n', '\r']);
+
+    let mut column = 0;
+    for (offset, c) in line.char_indices() {
+        if column >= position.character as usize {
+            return line_range.start + offset;
+        }
+        column += c.len_utf16();
+    }
+
+    line_range.start + line.len()
+}
+
+/// Converts a byte offset into the file's source text into a position.
+pub fn position(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    offset: usize,
+) -> Position {
+    let source = db.source(file_id);
+    let offset = offset.min(source.len());
+    let line_index = db.source_line_index(file_id, offset);
+    let line_start = db.source_line_start(file_id, line_index);
+
+    let character = source[line_start..offset]
+        .chars()
+        .map(char::len_utf16)
+        .sum::<usize>();
+
+    Position::new(line_index as u32, character as u32)
+}
+
+/// Converts a byte range of the file's source text into a range of positions.
+pub fn range(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    range: Range<usize>,
+) -> lsp_types::Range {
+    lsp_types::Range::new(
+        position(db, file_id, range.start),
+        position(db, file_id, range.end),
+    )
+}
+
+/// Converts a diagnostic into one that can be published to the client.
+///
+/// Its labels are sent as related information, which needs the URI of the
+/// file they point into (labels in files without one are left out).
+pub fn diagnostic<'a>(
+    db: &HeliosDatabase,
+    diagnostic: &Diagnostic<FileId>,
+    severity: DiagnosticSeverity,
+    uri: impl Fn(FileId) -> Option<&'a Url>,
+) -> lsp_types::Diagnostic {
+    let mut message = diagnostic.message.to_markdown();
+    if let Some(hint) = &diagnostic.hint {
+        message = format!("{message}\n\n{}", hint.to_markdown());
+    }
+
+    let related_information = diagnostic
+        .labels
+        .iter()
+        .filter_map(|label| {
+            let file_id = label.location.file_id;
+            let location = Location::new(
+                uri(file_id)?.clone(),
+                range(db, file_id, label.location.range.clone()),
+            );
+            Some(DiagnosticRelatedInformation {
+                location,
+                message: label.message.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    lsp_types::Diagnostic {
+        range: range(
+            db,
+            diagnostic.location.file_id,
+            diagnostic.location.range.clone(),
+        ),
+        severity: Some(severity),
+        source: Some("helios".to_string()),
+        message: format!("{}: {message}", diagnostic.title),
+        related_information: (!related_information.is_empty())
+            .then_some(related_information),
+        ..lsp_types::Diagnostic::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    const FILE_ID: FileId = FileId(0);
+
+    #[test]
+    fn test_offset_and_position() {
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new("let π = 1\nlet 𝛼 = π\n".to_string()));
+
+        assert_eq!(offset(&db, FILE_ID, Position::new(0, 4)), 4);
+        assert_eq!(offset(&db, FILE_ID, Position::new(0, 5)), 6);
+        assert_eq!(offset(&db, FILE_ID, Position::new(0, 99)), 10);
+        assert_eq!(offset(&db, FILE_ID, Position::new(1, 4)), 15);
+        assert_eq!(offset(&db, FILE_ID, Position::new(1, 6)), 19);
+        assert_eq!(offset(&db, FILE_ID, Position::new(1, 9)), 22);
+        assert_eq!(offset(&db, FILE_ID, Position::new(5, 0)), 25);
+
+        assert_eq!(position(&db, FILE_ID, 6), Position::new(0, 5));
+        assert_eq!(position(&db, FILE_ID, 11), Position::new(1, 0));
+        assert_eq!(position(&db, FILE_ID, 19), Position::new(1, 6));
+        assert_eq!(position(&db, FILE_ID, 25), Position::new(2, 0));
+    }
+
+    #[test]
+    fn test_diagnostic_labels() {
+        use helios_diagnostics::Location;
+
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new("let a = 1\nlet a = 2\n".to_string()));
+
+        let uri = Url::parse("file:///main.helios").unwrap();
+        let diagnostic = Diagnostic::error("Duplicate definition")
+            .location(Location::new(FILE_ID, 14..15))
+            .label(Location::new(FILE_ID, 4..5), "first declared here")
+            .label(Location::new(FileId(1), 0..1), "in an unknown file");
+
+        let diagnostic = super::diagnostic(
+            &db,
+            &diagnostic,
+            DiagnosticSeverity::ERROR,
+            |file_id| (file_id == FILE_ID).then_some(&uri),
+        );
+        let related = diagnostic.related_information.unwrap();
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].location.uri, uri);
+        assert_eq!(related[0].location.range, range(&db, FILE_ID, 4..5));
+        assert_eq!(related[0].message, "first declared here");
+    }
+}
@@ -0,0 +1,504 @@
+//! Handlers for the requests and notifications sent by the client.
+
+mod code_action;
+mod completion;
+mod document_symbol;
+mod formatting;
+mod goto_definition;
+mod hover;
+mod inlay_hint;
+mod rename;
+mod selection_range;
+mod semantic_tokens;
+mod status;
+mod syntax_tree;
+mod workspace_symbol;
+
+use crate::config::Config;
+use crate::lsp_ext::{Status, SyntaxTree};
+use crate::{convert, trace, Result, State, StateSnapshot};
+use helios_query::{
+    Cancelled, Diagnostics, FileId, HeliosDatabase, Input, Modules,
+};
+use helios_syntax::{SyntaxKind, SyntaxToken};
+use lsp_server::{Connection, ErrorCode, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeConfiguration, DidChangeTextDocument, DidChangeWatchedFiles,
+    DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics, SetTrace,
+};
+use lsp_types::request::{
+    CodeActionRequest, Completion, DocumentSymbolRequest, Formatting,
+    GotoDefinition, HoverRequest, InlayHintRequest, OnTypeFormatting,
+    PrepareRenameRequest, RangeFormatting, Rename, Request as _,
+    SelectionRangeRequest, SemanticTokensFullRequest, WorkspaceSymbolRequest,
+};
+use lsp_types::{PublishDiagnosticsParams, Url};
+use rowan::{TextSize, TokenAtOffset};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::any::Any;
+use std::fmt::{self, Display};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+
+pub use self::semantic_tokens::legend as semantic_tokens_legend;
+
+/// An error that is sent back to the client in response to a request, such as
+/// when a request cannot be fulfilled at the given position.
+#[derive(Debug)]
+pub struct LspError {
+    code: i32,
+    message: String,
+}
+
+impl LspError {
+    pub fn request_failed(message: impl Into<String>) -> Self {
+        Self {
+            code: ErrorCode::RequestFailed as i32,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::error::Error for LspError {}
+
+impl Display for LspError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// The work needed to respond to a request, which runs on its own thread.
+pub type Task = Box<dyn FnOnce() -> Response + Send>;
+
+pub fn handle_request(state: &State, request: Request) -> Task {
+    match request.method.as_str() {
+        HoverRequest::METHOD => {
+            dispatch::<HoverRequest>(state, request, hover::hover)
+        }
+        PrepareRenameRequest::METHOD => dispatch::<PrepareRenameRequest>(
+            state,
+            request,
+            rename::prepare_rename,
+        ),
+        Rename::METHOD => dispatch::<Rename>(state, request, rename::rename),
+        DocumentSymbolRequest::METHOD => dispatch::<DocumentSymbolRequest>(
+            state,
+            request,
+            document_symbol::document_symbol,
+        ),
+        GotoDefinition::METHOD => dispatch::<GotoDefinition>(
+            state,
+            request,
+            goto_definition::goto_definition,
+        ),
+        Completion::METHOD => {
+            dispatch::<Completion>(state, request, completion::completion)
+        }
+        CodeActionRequest::METHOD => dispatch::<CodeActionRequest>(
+            state,
+            request,
+            code_action::code_action,
+        ),
+        Formatting::METHOD => {
+            dispatch::<Formatting>(state, request, formatting::formatting)
+        }
+        RangeFormatting::METHOD => dispatch::<RangeFormatting>(
+            state,
+            request,
+            formatting::range_formatting,
+        ),
+        SelectionRangeRequest::METHOD => dispatch::<SelectionRangeRequest>(
+            state,
+            request,
+            selection_range::selection_range,
+        ),
+        SemanticTokensFullRequest::METHOD => {
+            dispatch::<SemanticTokensFullRequest>(
+                state,
+                request,
+                semantic_tokens::semantic_tokens_full,
+            )
+        }
+        WorkspaceSymbolRequest::METHOD => dispatch::<WorkspaceSymbolRequest>(
+            state,
+            request,
+            workspace_symbol::workspace_symbol,
+        ),
+        SyntaxTree::METHOD => {
+            dispatch::<SyntaxTree>(state, request, syntax_tree::syntax_tree)
+        }
+        Status::METHOD => dispatch::<Status>(state, request, status::status),
+        InlayHintRequest::METHOD => {
+            dispatch::<InlayHintRequest>(state, request, inlay_hint::inlay_hint)
+        }
+        OnTypeFormatting::METHOD => dispatch::<OnTypeFormatting>(
+            state,
+            request,
+            formatting::on_type_formatting,
+        ),
+        _ => {
+            let response = Response::new_err(
+                request.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("Unsupported request: `{}`", request.method),
+            );
+            Box::new(move || response)
+        }
+    }
+}
+
+pub fn handle_notification(
+    connection: &Connection,
+    state: &mut State,
+    notification: Notification,
+) -> Result<()> {
+    match notification.method.as_str() {
+        DidOpenTextDocument::METHOD => {
+            let params = cast::<DidOpenTextDocument>(notification)?;
+            let document = params.text_document;
+            let dependencies = dependencies(state, &document.uri);
+            state.open_document(&document.uri, document.text);
+            publish_changed_diagnostics(
+                connection,
+                state,
+                document.uri,
+                dependencies,
+            )
+        }
+        DidChangeTextDocument::METHOD => {
+            let params = cast::<DidChangeTextDocument>(notification)?;
+            // The server asks for full document syncing, so the last change
+            // holds the entire text of the document.
+            if let Some(change) = params.content_changes.into_iter().last() {
+                let uri = params.text_document.uri;
+                let dependencies = dependencies(state, &uri);
+                state.open_document(&uri, change.text);
+                publish_changed_diagnostics(
+                    connection,
+                    state,
+                    uri,
+                    dependencies,
+                )?;
+            }
+            Ok(())
+        }
+        DidCloseTextDocument::METHOD => {
+            let params = cast::<DidCloseTextDocument>(notification)?;
+            state.close_document(&params.text_document.uri);
+            clear_diagnostics(connection, params.text_document.uri)?;
+
+            // What was only needed for the document can be freed.
+            state.db().collect_garbage();
+            Ok(())
+        }
+        DidChangeWatchedFiles::METHOD => {
+            let params = cast::<DidChangeWatchedFiles>(notification)?;
+            // Whether a file was created, changed or deleted, reading it
+            // from disk again brings the database up to date (open documents
+            // are left alone, since the client keeps those in sync).
+            for change in params.changes {
+                state.reload_file(&change.uri);
+            }
+
+            // Other files may depend on the ones that changed.
+            publish_all_diagnostics(connection, state)?;
+
+            state.db().collect_garbage();
+            Ok(())
+        }
+        DidChangeConfiguration::METHOD => {
+            let params = cast::<DidChangeConfiguration>(notification)?;
+            match Config::from_json(params.settings) {
+                Ok(config) => {
+                    state.set_config(config);
+                    publish_all_diagnostics(connection, state)
+                }
+                Err(error) => {
+                    tracing::warn!(%error, "Ignoring invalid settings");
+                    Ok(())
+                }
+            }
+        }
+        SetTrace::METHOD => {
+            let params = cast::<SetTrace>(notification)?;
+            trace::set_trace(params.value);
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Deserializes the parameters of a request and builds a task that passes
+/// them to the given handler (along with a snapshot of the state) and turns
+/// the handler's result into a response.
+///
+/// If the state changes while the handler is running, the handler is
+/// cancelled and the client is told that the content was modified. If the
+/// handler panics, the client is told about it with an internal error (rather
+/// than never hearing back).
+fn dispatch<R>(
+    state: &State,
+    request: Request,
+    handler: fn(&StateSnapshot, R::Params) -> Result<R::Result>,
+) -> Task
+where
+    R: lsp_types::request::Request,
+    R::Params: DeserializeOwned + Send + 'static,
+    R::Result: Serialize + 'static,
+{
+    let id = request.id.clone();
+    let params = match serde_json::from_value::<R::Params>(request.params) {
+        Ok(params) => params,
+        Err(error) => {
+            let response = Response::new_err(
+                id,
+                ErrorCode::InvalidParams as i32,
+                error.to_string(),
+            );
+            return Box::new(move || response);
+        }
+    };
+
+    let snapshot = state.snapshot();
+    Box::new(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            handler(&snapshot, params)
+        }));
+
+        match result {
+            Ok(Ok(result)) => Response::new_ok(id, result),
+            Ok(Err(error)) => match error.downcast::<LspError>() {
+                Ok(error) => Response::new_err(id, error.code, error.message),
+                Err(error) => Response::new_err(
+                    id,
+                    ErrorCode::InternalError as i32,
+                    error.to_string(),
+                ),
+            },
+            Err(payload) if payload.is::<Cancelled>() => Response::new_err(
+                id,
+                ErrorCode::ContentModified as i32,
+                "The document was modified".to_string(),
+            ),
+            Err(payload) => Response::new_err(
+                id,
+                ErrorCode::InternalError as i32,
+                panic_message(&*payload),
+            ),
+        }
+    })
+}
+
+/// The message a handler panicked with.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let message = match payload.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => message.as_str(),
+            None => "unknown error",
+        },
+    };
+
+    format!("The request handler panicked: {message}")
+}
+
+/// Finds the most interesting token at the given offset.
+///
+/// If the offset is between two tokens, the one that isn't trivia is picked
+/// (preferring identifiers), so that a cursor at the very end of a name still
+/// refers to that name. Whitespace and line breaks are never returned.
+fn token_at_offset(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    offset: usize,
+) -> Option<SyntaxToken> {
+    let root = db.parse(file_id).syntax();
+    let token = match root.token_at_offset(TextSize::try_from(offset).ok()?) {
+        TokenAtOffset::None => return None,
+        TokenAtOffset::Single(token) => token,
+        TokenAtOffset::Between(left, right) => {
+            let score = |token: &SyntaxToken| match token.kind() {
+                kind if kind.is_identifier() => 2,
+                kind if kind.is_trivia() => 0,
+                _ => 1,
+            };
+
+            if score(&left) > score(&right) {
+                left
+            } else {
+                right
+            }
+        }
+    };
+
+    match token.kind() {
+        SyntaxKind::Whitespace | SyntaxKind::Newline => None,
+        _ => Some(token),
+    }
+}
+
+fn cast<N>(notification: Notification) -> Result<N::Params>
+where
+    N: lsp_types::notification::Notification,
+    N::Params: DeserializeOwned,
+{
+    Ok(notification.extract(N::METHOD)?)
+}
+
+fn send_notification<N>(
+    connection: &Connection,
+    params: N::Params,
+) -> Result<()>
+where
+    N: lsp_types::notification::Notification,
+    N::Params: Serialize,
+{
+    let notification = Notification::new(N::METHOD.to_string(), params);
+    connection.sender.send(notification.into())?;
+    Ok(())
+}
+
+/// Removes any diagnostics of the given document shown by the client.
+fn clear_diagnostics(connection: &Connection, uri: Url) -> Result<()> {
+    send_notification::<PublishDiagnostics>(
+        connection,
+        PublishDiagnosticsParams::new(uri, Vec::new(), None),
+    )
+}
+
+/// The files the given document imports, if the document is known.
+fn dependencies(state: &State, uri: &Url) -> Option<Arc<Vec<FileId>>> {
+    state
+        .file_id(uri)
+        .map(|file_id| state.db().dependencies(file_id))
+}
+
+/// Sends the diagnostics of a document that was just changed to the client.
+///
+/// If the files the document imports changed (given the ones it imported
+/// before), import cycles may have been made or broken anywhere, so the
+/// diagnostics of every open document are sent instead.
+fn publish_changed_diagnostics(
+    connection: &Connection,
+    state: &State,
+    uri: Url,
+    dependencies: Option<Arc<Vec<FileId>>>,
+) -> Result<()> {
+    let unchanged = state.file_id(&uri).zip(dependencies).is_some_and(
+        |(file_id, dependencies)| {
+            state.db().dependencies(file_id) == dependencies
+        },
+    );
+
+    if unchanged {
+        publish_diagnostics(connection, state, uri)
+    } else {
+        publish_all_diagnostics(connection, state)
+    }
+}
+
+/// Sends the diagnostics of every open document to the client.
+fn publish_all_diagnostics(
+    connection: &Connection,
+    state: &State,
+) -> Result<()> {
+    let uris = state.open_documents().cloned().collect::<Vec<_>>();
+    for uri in uris {
+        publish_diagnostics(connection, state, uri)?;
+    }
+
+    Ok(())
+}
+
+/// Sends the diagnostics of the given document to the client.
+fn publish_diagnostics(
+    connection: &Connection,
+    state: &State,
+    uri: Url,
+) -> Result<()> {
+    let file_id = match state.file_id(&uri) {
+        Some(file_id) => file_id,
+        None => return Ok(()),
+    };
+
+    let config = &state.config().diagnostics;
+    let diagnostics = state
+        .db()
+        .diagnostics(file_id)
+        .iter()
+        .filter_map(|diagnostic| {
+            let severity = config.severity(diagnostic)?;
+            Some(convert::diagnostic(
+                state.db(),
+                diagnostic,
+                severity,
+                |file_id| state.uri(file_id),
+            ))
+        })
+        .collect();
+
+    send_notification::<PublishDiagnostics>(
+        connection,
+        PublishDiagnosticsParams::new(uri, diagnostics, None),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::request::HoverRequest;
+    use lsp_types::{
+        HoverParams, Position, TextDocumentIdentifier,
+        TextDocumentPositionParams,
+    };
+
+    fn hover_request() -> Request {
+        let params = HoverParams {
+            text_document_position_params: TextDocumentPositionParams::new(
+                TextDocumentIdentifier::new(
+                    Url::parse("file:///test.helios").unwrap(),
+                ),
+                Position::new(0, 0),
+            ),
+            work_done_progress_params: Default::default(),
+        };
+
+        Request::new(1.into(), HoverRequest::METHOD.to_string(), params)
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let state = State::new();
+
+        let task = dispatch::<HoverRequest>(&state, hover_request(), |_, _| {
+            Err(LspError::request_failed("Nothing to see here").into())
+        });
+        let error = task().error.unwrap();
+        assert_eq!(error.code, ErrorCode::RequestFailed as i32);
+        assert_eq!(error.message, "Nothing to see here");
+
+        let task = dispatch::<HoverRequest>(&state, hover_request(), |_, _| {
+            Cancelled::throw()
+        });
+        let error = task().error.unwrap();
+        assert_eq!(error.code, ErrorCode::ContentModified as i32);
+
+        // Other panics are still answered.
+        let task = dispatch::<HoverRequest>(&state, hover_request(), |_, _| {
+            panic!("Out of bounds")
+        });
+        let error = task().error.unwrap();
+        assert_eq!(error.code, ErrorCode::InternalError as i32);
+        assert_eq!(
+            error.message,
+            "The request handler panicked: Out of bounds"
+        );
+
+        let mut request = hover_request();
+        request.method = "helios/unknown".to_string();
+        let error = handle_request(&state, request)().error.unwrap();
+        assert_eq!(error.code, ErrorCode::MethodNotFound as i32);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{convert, Result, StateSnapshot};
+use helios_parser::imports;
+use helios_query::{Diagnostics, Input};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams,
+    CodeActionResponse, TextEdit, WorkspaceEdit,
+};
+use std::collections::HashMap;
+
+/// Offers the fixes of the diagnostics overlapping the given range as quick
+/// fixes (such as removing an unused binding), along with organizing the
+/// imports of the document if they aren't already.
+///
+/// Diagnostics that aren't reported (as configured by the user) don't offer
+/// their fixes either. Fixes that can't change what the program does (such as
+/// removing an unused import) are marked as preferred, so that editors can
+/// apply them with a single shortcut.
+pub fn code_action(
+    state: &StateSnapshot,
+    params: CodeActionParams,
+) -> Result<Option<CodeActionResponse>> {
+    let uri = params.text_document.uri;
+    let file_id = match state.file_id(&uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    let db = state.db();
+    let start = convert::offset(db, file_id, params.range.start);
+    let end = convert::offset(db, file_id, params.range.end);
+    let config = &state.config().diagnostics;
+
+    let mut actions = Vec::new();
+    for diagnostic in db.diagnostics(file_id).iter() {
+        let range = &diagnostic.location.range;
+        if range.start > end || range.end < start {
+            continue;
+        }
+
+        let severity = match config.severity(diagnostic) {
+            Some(severity) => severity,
+            None => continue,
+        };
+
+        let lsp_diagnostic =
+            convert::diagnostic(db, diagnostic, severity, |file_id| {
+                state.uri(file_id)
+            });
+
+        for fix in &diagnostic.fixes {
+            let fix_uri = match state.uri(fix.location.file_id) {
+                Some(uri) => uri.clone(),
+                None => continue,
+            };
+
+            let edit = TextEdit {
+                range: convert::range(
+                    db,
+                    fix.location.file_id,
+                    fix.location.range.clone(),
+                ),
+                new_text: fix.replacement.clone(),
+            };
+
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: fix.title.clone(),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![lsp_diagnostic.clone()]),
+                edit: Some(WorkspaceEdit::new(HashMap::from([(
+                    fix_uri,
+                    vec![edit],
+                )]))),
+                is_preferred: fix.machine_applicable.then_some(true),
+                ..CodeAction::default()
+            }));
+        }
+    }
+
+    let root = db.parse(file_id).syntax();
+    let edits = imports::organize_imports_edits(&root)
+        .into_iter()
+        .map(|edit| TextEdit {
+            range: convert::range(db, file_id, edit.range),
+            new_text: edit.text,
+        })
+        .collect::<Vec<_>>();
+    if !edits.is_empty() {
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Organize imports".to_string(),
+            kind: Some(CodeActionKind::SOURCE_ORGANIZE_IMPORTS),
+            edit: Some(WorkspaceEdit::new(HashMap::from([(uri, edits)]))),
+            ..CodeAction::default()
+        }));
+    }
+
+    Ok(Some(actions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+    use lsp_types::{
+        CodeActionContext, Position, Range, TextDocumentIdentifier, Url,
+    };
+
+    #[test]
+    fn test_code_action() {
+        let mut state = State::new();
+        let uri = Url::parse("file:///main.helios").unwrap();
+        state.set_document(&uri, "let a = 1\nlet b = 2\nb\n".into());
+
+        let actions = |line| {
+            let params = CodeActionParams {
+                text_document: TextDocumentIdentifier::new(uri.clone()),
+                range: Range::new(
+                    Position::new(line, 4),
+                    Position::new(line, 4),
+                ),
+                context: CodeActionContext::default(),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            };
+
+            code_action(&state.snapshot(), params)
+                .unwrap()
+                .unwrap()
+                .into_iter()
+                .map(|action| match action {
+                    CodeActionOrCommand::CodeAction(action) => action,
+                    command => panic!("Unexpected command: {command:?}"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let unused = actions(0);
+        let titles = unused
+            .iter()
+            .map(|action| action.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, ["Remove the unused binding", "Rename to `_a`"]);
+
+        let changes = unused[0].edit.as_ref().unwrap().changes.as_ref();
+        assert_eq!(
+            changes.unwrap()[&uri],
+            [TextEdit {
+                range: Range::new(Position::new(0, 0), Position::new(1, 0)),
+                new_text: String::new(),
+            }]
+        );
+
+        assert!(actions(1).is_empty());
+    }
+
+    #[test]
+    fn test_organize_imports() {
+        let mut state = State::new();
+        let uri = Url::parse("file:///main.helios").unwrap();
+        state.set_document(&uri, "import b\nimport a\nimport b\n".into());
+
+        let params = CodeActionParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            range: Range::new(Position::new(2, 0), Position::new(2, 0)),
+            context: CodeActionContext::default(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let actions = code_action(&state.snapshot(), params).unwrap().unwrap();
+        let action = actions
+            .into_iter()
+            .find_map(|action| match action {
+                CodeActionOrCommand::CodeAction(action)
+                    if action.title == "Organize imports" =>
+                {
+                    Some(action)
+                }
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(action.kind, Some(CodeActionKind::SOURCE_ORGANIZE_IMPORTS));
+        let changes = action.edit.unwrap().changes.unwrap();
+        assert_eq!(
+            changes[&uri],
+            [TextEdit {
+                range: Range::new(Position::new(0, 0), Position::new(2, 8)),
+                new_text: "import a\nimport b".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{Result, StateSnapshot};
+use helios_query::{BindingKind, Bindings, Interner};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionParams, CompletionResponse,
+};
+use std::collections::HashSet;
+
+/// Suggests the bindings declared in the document.
+///
+/// Every binding is suggested (whatever has been typed so far), since clients
+/// filter the suggestions themselves.
+pub fn completion(
+    state: &StateSnapshot,
+    params: CompletionParams,
+) -> Result<Option<CompletionResponse>> {
+    let params = params.text_document_position;
+    let file_id = match state.file_id(&params.text_document.uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    let db = state.db();
+    let mut names = HashSet::new();
+    let items = db
+        .all_bindings(file_id)
+        .iter()
+        .map(|&binding| db.lookup_intern_binding(binding))
+        .filter(|binding| names.insert(binding.name.clone()))
+        .map(|binding| CompletionItem {
+            label: binding.name,
+            kind: Some(completion_kind(binding.kind)),
+            ..CompletionItem::default()
+        })
+        .collect();
+
+    Ok(Some(CompletionResponse::Array(items)))
+}
+
+fn completion_kind(kind: BindingKind) -> CompletionItemKind {
+    match kind {
+        BindingKind::Global => CompletionItemKind::VARIABLE,
+        BindingKind::Import => CompletionItemKind::MODULE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+    use lsp_types::{
+        Position, TextDocumentIdentifier, TextDocumentPositionParams, Url,
+    };
+
+    #[test]
+    fn test_completion() {
+        let uri = Url::parse("file:///test.helios").unwrap();
+        let mut state = State::new();
+        state.set_document(&uri, "import foo\nlet a = 1\nlet a = a\n".into());
+
+        let params = CompletionParams {
+            text_document_position: TextDocumentPositionParams::new(
+                TextDocumentIdentifier::new(uri),
+                Position::new(2, 9),
+            ),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        };
+
+        let items = match completion(&state.snapshot(), params).unwrap() {
+            Some(CompletionResponse::Array(items)) => items
+                .into_iter()
+                .map(|item| (item.label, item.kind))
+                .collect::<Vec<_>>(),
+            response => panic!("Unexpected response: {response:?}"),
+        };
+
+        assert_eq!(
+            items,
+            vec![
+                ("foo".to_string(), Some(CompletionItemKind::MODULE)),
+                ("a".to_string(), Some(CompletionItemKind::VARIABLE)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{convert, Result, StateSnapshot};
+use helios_query::{BindingKind, Bindings, Interner};
+use lsp_types::{
+    DocumentSymbolParams, DocumentSymbolResponse, Location, SymbolInformation,
+    SymbolKind,
+};
+
+/// Lists the bindings declared in a document, in the order they appear.
+pub fn document_symbol(
+    state: &StateSnapshot,
+    params: DocumentSymbolParams,
+) -> Result<Option<DocumentSymbolResponse>> {
+    let uri = params.text_document.uri;
+    let file_id = match state.file_id(&uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    let db = state.db();
+    let symbols = db
+        .all_bindings(file_id)
+        .iter()
+        .map(|&binding| {
+            let binding = db.lookup_intern_binding(binding);
+            let range = convert::range(db, file_id, binding.range);
+
+            #[allow(deprecated)]
+            SymbolInformation {
+                name: binding.name,
+                kind: symbol_kind(binding.kind),
+                tags: None,
+                deprecated: None,
+                location: Location::new(uri.clone(), range),
+                container_name: None,
+            }
+        })
+        .collect();
+
+    Ok(Some(DocumentSymbolResponse::Flat(symbols)))
+}
+
+fn symbol_kind(kind: BindingKind) -> SymbolKind {
+    match kind {
+        BindingKind::Global => SymbolKind::VARIABLE,
+        BindingKind::Import => SymbolKind::MODULE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+    use lsp_types::{TextDocumentIdentifier, Url};
+
+    #[test]
+    fn test_document_symbol() {
+        let uri = Url::parse("file:///test.helios").unwrap();
+        let mut state = State::new();
+        state.set_document(&uri, "import foo\nlet a = 1\n".to_string());
+
+        let params = DocumentSymbolParams {
+            text_document: TextDocumentIdentifier::new(uri),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+
+        let symbols = match document_symbol(&state.snapshot(), params) {
+            Ok(Some(DocumentSymbolResponse::Flat(symbols))) => symbols
+                .into_iter()
+                .map(|symbol| (symbol.name, symbol.kind))
+                .collect::<Vec<_>>(),
+            response => panic!("Unexpected response: {response:?}"),
+        };
+
+        assert_eq!(
+            symbols,
+            vec![
+                ("foo".to_string(), SymbolKind::MODULE),
+                ("a".to_string(), SymbolKind::VARIABLE),
+            ]
+        );
+    }
+}
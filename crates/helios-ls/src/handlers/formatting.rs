@@ -0,0 +1,266 @@
+use crate::{convert, Result, StateSnapshot};
+use helios_parser::printer::{self, Edit, FormatterConfig};
+use helios_query::{FileId, HeliosDatabase, Input, InputLocation};
+use lsp_types::{
+    DocumentFormattingParams, DocumentOnTypeFormattingParams,
+    DocumentRangeFormattingParams, Position, Range, TextEdit,
+};
+
+/// Pretty-prints the whole document.
+///
+/// Only the whitespace that needs to change is edited, so the cursor and any
+/// selections in the editor stay where they are.
+pub fn formatting(
+    state: &StateSnapshot,
+    params: DocumentFormattingParams,
+) -> Result<Option<Vec<TextEdit>>> {
+    let file_id = match state.file_id(&params.text_document.uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    let db = state.db();
+    let config = state.formatter_config(&params.text_document.uri);
+    let edits = pretty_print_edits(db, file_id, &config);
+    Ok(Some(text_edits(db, file_id, edits, |_| true)))
+}
+
+/// Pretty-prints the selected part of the document.
+///
+/// The whitespace between two tokens is only edited if it's entirely within
+/// the selection.
+pub fn range_formatting(
+    state: &StateSnapshot,
+    params: DocumentRangeFormattingParams,
+) -> Result<Option<Vec<TextEdit>>> {
+    let file_id = match state.file_id(&params.text_document.uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    let db = state.db();
+    let start = convert::offset(db, file_id, params.range.start);
+    let end = convert::offset(db, file_id, params.range.end);
+    let config = state.formatter_config(&params.text_document.uri);
+
+    let edits = pretty_print_edits(db, file_id, &config);
+    Ok(Some(text_edits(db, file_id, edits, |edit| {
+        start <= edit.range.start && edit.range.end <= end
+    })))
+}
+
+/// Indents a new line after the user presses enter.
+///
+/// The new line is indented by one more level if the line before it opens a
+/// block (such as a line ending with `=`). Any whitespace left at the end of
+/// the line before is removed as well.
+pub fn on_type_formatting(
+    state: &StateSnapshot,
+    params: DocumentOnTypeFormattingParams,
+) -> Result<Option<Vec<TextEdit>>> {
+    let position = params.text_document_position.position;
+    let uri = &params.text_document_position.text_document.uri;
+    let file_id = match state.file_id(uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    if !state.config().formatting.on_type
+        || params.ch != "\n"
+        || position.line == 0
+    {
+        return Ok(None);
+    }
+
+    let db = state.db();
+    if position.line as usize >= db.source_line_indexes(file_id).len() {
+        return Ok(None);
+    }
+
+    let source = db.source(file_id);
+    let line = |index: u32| {
+        let range = db.source_line_range(file_id, index as usize);
+        &source[range]
+    };
+
+    let prev_line = line(position.line - 1).trim_end_matches(['\n', '\r']);
+    let current_line = line(position.line).trim_end_matches(['\n', '\r']);
+    let mut edits = Vec::new();
+
+    let trimmed_len = prev_line.trim_end().encode_utf16().count() as u32;
+    let prev_len = prev_line.encode_utf16().count() as u32;
+    if trimmed_len < prev_len {
+        edits.push(TextEdit::new(
+            Range::new(
+                Position::new(position.line - 1, trimmed_len),
+                Position::new(position.line - 1, prev_len),
+            ),
+            String::new(),
+        ));
+    }
+
+    let config = state.formatter_config(uri);
+    let indent = printer::next_line_indent(prev_line, &config);
+    let line_start = db.source_line_start(file_id, position.line as usize);
+    let current_indent = current_line.len() - current_line.trim_start().len();
+    edits.push(TextEdit::new(
+        convert::range(db, file_id, line_start..line_start + current_indent),
+        " ".repeat(indent),
+    ));
+
+    Ok(Some(edits))
+}
+
+/// Works out the edits needed to pretty-print the given file.
+fn pretty_print_edits(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    config: &FormatterConfig,
+) -> Vec<Edit> {
+    let root = db.parse(file_id).syntax();
+    printer::pretty_print_edits(&root, config)
+}
+
+fn text_edits(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    edits: Vec<Edit>,
+    filter: impl Fn(&Edit) -> bool,
+) -> Vec<TextEdit> {
+    edits
+        .into_iter()
+        .filter(filter)
+        .map(|edit| TextEdit {
+            range: convert::range(db, file_id, edit.range),
+            new_text: edit.text,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+    use lsp_types::{
+        FormattingOptions, TextDocumentIdentifier, TextDocumentPositionParams,
+        Url,
+    };
+
+    fn state(source: &str) -> (State, Url) {
+        let uri = Url::parse("file:///test.hl").unwrap();
+        let mut state = State::new();
+        state.set_document(&uri, source.to_string());
+        (state, uri)
+    }
+
+    fn format(source: &str) -> Option<Vec<TextEdit>> {
+        let (state, uri) = state(source);
+
+        let params = DocumentFormattingParams {
+            text_document: TextDocumentIdentifier::new(uri),
+            options: FormattingOptions::default(),
+            work_done_progress_params: Default::default(),
+        };
+
+        formatting(&state.snapshot(), params).unwrap()
+    }
+
+    #[test]
+    fn test_formatting() {
+        let edits = format("let a  =  1\nlet b = a\n").unwrap();
+        let range = |start, end| {
+            Range::new(Position::new(0, start), Position::new(0, end))
+        };
+
+        assert_eq!(
+            edits,
+            vec![
+                TextEdit::new(range(5, 7), " ".to_string()),
+                TextEdit::new(range(8, 10), " ".to_string()),
+            ]
+        );
+
+        assert_eq!(format("let a = 1\n"), Some(Vec::new()));
+        assert_eq!(
+            format("let a = ( 1\n"),
+            Some(vec![TextEdit::new(range(9, 10), String::new())])
+        );
+
+        // Invalid indentation is still pretty-printed.
+        let edit = |(l1, c1), (l2, c2), text: &str| {
+            let range =
+                Range::new(Position::new(l1, c1), Position::new(l2, c2));
+            TextEdit::new(range, text.to_string())
+        };
+        assert_eq!(
+            format("let a = (1 +\n  2) +\n      3\nlet b = 2"),
+            Some(vec![
+                edit((0, 12), (1, 2), "\n    "),
+                edit((1, 6), (2, 6), "\n        "),
+                edit((3, 9), (3, 9), "\n"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_range_formatting() {
+        let (state, uri) = state("let a  =  1\nlet b  = a\n");
+        let params = DocumentRangeFormattingParams {
+            text_document: TextDocumentIdentifier::new(uri),
+            range: Range::new(Position::new(0, 7), Position::new(1, 5)),
+            options: FormattingOptions::default(),
+            work_done_progress_params: Default::default(),
+        };
+
+        assert_eq!(
+            range_formatting(&state.snapshot(), params).unwrap(),
+            Some(vec![TextEdit::new(
+                Range::new(Position::new(0, 8), Position::new(0, 10)),
+                " ".to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn test_on_type_formatting() {
+        let on_type = |source: &str, line| {
+            let (state, uri) = state(source);
+            let params = DocumentOnTypeFormattingParams {
+                text_document_position: TextDocumentPositionParams::new(
+                    TextDocumentIdentifier::new(uri),
+                    Position::new(line, 0),
+                ),
+                ch: "\n".to_string(),
+                options: FormattingOptions::default(),
+            };
+
+            on_type_formatting(&state.snapshot(), params).unwrap()
+        };
+
+        let range = |start: (u32, u32), end: (u32, u32)| {
+            Range::new(
+                Position::new(start.0, start.1),
+                Position::new(end.0, end.1),
+            )
+        };
+
+        assert_eq!(
+            on_type("let a =  \n  \n", 1).unwrap(),
+            vec![
+                TextEdit::new(range((0, 7), (0, 9)), String::new()),
+                TextEdit::new(range((1, 0), (1, 2)), "    ".to_string()),
+            ]
+        );
+        assert_eq!(
+            on_type("    1 + 2\n\n", 1).unwrap(),
+            vec![TextEdit::new(range((1, 0), (1, 0)), "    ".to_string())]
+        );
+        assert_eq!(on_type("let a = 1\n", 5), None);
+
+        // Columns are counted in UTF-16 code units rather than bytes.
+        assert_eq!(
+            on_type("let a =\n\u{3000}\u{3000}1\n", 1).unwrap(),
+            vec![TextEdit::new(range((1, 0), (1, 2)), "    ".to_string())]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{convert, Result, StateSnapshot};
+use helios_query::{BindingKind, Bindings, Interner, Modules};
+use lsp_types::{
+    GotoDefinitionParams, GotoDefinitionResponse, Location, Range,
+};
+
+/// Finds where the binding under the cursor is declared.
+///
+/// The definition of an imported module is the file defining it.
+pub fn goto_definition(
+    state: &StateSnapshot,
+    params: GotoDefinitionParams,
+) -> Result<Option<GotoDefinitionResponse>> {
+    let params = params.text_document_position_params;
+    let file_id = match state.file_id(&params.text_document.uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    let db = state.db();
+    let offset = convert::offset(db, file_id, params.position);
+    let binding = match db.binding_at_offset(file_id, offset) {
+        Some(binding) => db.lookup_intern_binding(binding),
+        None => return Ok(None),
+    };
+
+    let module = match binding.kind {
+        BindingKind::Global => None,
+        BindingKind::Import => {
+            let imports = db.imports(binding.file_id);
+            imports
+                .iter()
+                .find(|import| import.range.end == binding.range.end)
+                .and_then(|import| db.resolve_import(import.path.clone()))
+        }
+    };
+
+    let location = match module {
+        Some(module) => state
+            .uri(module)
+            .map(|uri| Location::new(uri.clone(), Range::default())),
+        None => state.uri(binding.file_id).map(|uri| {
+            let range = convert::range(db, binding.file_id, binding.range);
+            Location::new(uri.clone(), range)
+        }),
+    };
+
+    Ok(location.map(GotoDefinitionResponse::Scalar))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+    use lsp_types::{
+        Position, TextDocumentIdentifier, TextDocumentPositionParams, Url,
+    };
+
+    #[test]
+    fn test_goto_definition() {
+        let mut state = State::new();
+        let main = Url::parse("file:///main.helios").unwrap();
+        let foo = Url::parse("file:///foo.helios").unwrap();
+        state.set_document(&main, "import foo\nlet a = 1\nlet b = a\n".into());
+        state.set_document(&foo, "let c = 2\n".into());
+
+        let definition = |line, character| {
+            let params = GotoDefinitionParams {
+                text_document_position_params: TextDocumentPositionParams::new(
+                    TextDocumentIdentifier::new(main.clone()),
+                    Position::new(line, character),
+                ),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            };
+
+            match goto_definition(&state.snapshot(), params).unwrap() {
+                Some(GotoDefinitionResponse::Scalar(location)) => {
+                    Some((location.uri, location.range.start))
+                }
+                None => None,
+                response => panic!("Unexpected response: {response:?}"),
+            }
+        };
+
+        assert_eq!(definition(2, 8), Some((main.clone(), Position::new(1, 4))));
+        assert_eq!(definition(1, 4), Some((main.clone(), Position::new(1, 4))));
+        assert_eq!(definition(0, 8), Some((foo.clone(), Position::new(0, 0))));
+        assert_eq!(definition(1, 8), None);
+    }
+}
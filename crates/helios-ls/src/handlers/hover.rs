@@ -0,0 +1,227 @@
+use super::token_at_offset;
+use crate::{convert, Result, StateSnapshot};
+use helios_formatting::FormattedString;
+use helios_parser::{Number, NumberError};
+use helios_query::{
+    BindingKind, Bindings, FileId, HeliosDatabase, Interner, Types,
+};
+use helios_syntax::{SyntaxKind, SyntaxToken};
+use lsp_types::{Hover, HoverContents, HoverParams, MarkupContent, MarkupKind};
+
+/// Describes the token under the cursor, along with the type of the expression
+/// (or binding) it's a part of and the documentation of the binding it refers
+/// to (if there are any).
+pub fn hover(
+    state: &StateSnapshot,
+    params: HoverParams,
+) -> Result<Option<Hover>> {
+    let params = params.text_document_position_params;
+    let file_id = match state.file_id(&params.text_document.uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    let db = state.db();
+    let offset = convert::offset(db, file_id, params.position);
+    let token = match token_at_offset(db, file_id, offset) {
+        Some(token) => token,
+        None => return Ok(None),
+    };
+
+    let mut sections = vec![describe(&token).to_markdown()];
+
+    if let Some(value) = number_value(&token) {
+        sections.push(value.to_markdown());
+    }
+
+    if let Some(ty) = db.type_at_offset(file_id, offset) {
+        let ty = FormattedString::new().code_block(ty.to_string());
+        sections.push(ty.to_markdown());
+    }
+
+    if let Some(doc) = documentation(db, file_id, &token) {
+        sections.push(FormattedString::from_markdown(&doc).to_markdown());
+    }
+
+    let range = token.text_range();
+    Ok(Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: sections.join("\n\n---\n\n"),
+        }),
+        range: Some(convert::range(
+            db,
+            file_id,
+            range.start().into()..range.end().into(),
+        )),
+    }))
+}
+
+/// Describes the kind of the given token and the node it's a part of, such as
+/// "an integer literal, in a literal expression".
+fn describe(token: &SyntaxToken) -> FormattedString {
+    let mut repr = token.kind().human_readable_repr();
+    repr.example = None;
+
+    let mut description = FormattedString::from(repr);
+    if let Some(parent) = token.parent() {
+        if parent.kind() != SyntaxKind::Root {
+            let mut repr = parent.kind().human_readable_repr();
+            repr.example = None;
+            description.push(", in ");
+            description.push(repr.to_string());
+        }
+    }
+
+    description
+}
+
+/// Describes the value of the given number literal, unless it's written just
+/// like its value is (such as `42`, rather than `0x2a` or `4_2`).
+fn number_value(token: &SyntaxToken) -> Option<FormattedString> {
+    let value = match helios_parser::number_value(token.kind(), token.text())? {
+        Ok(Number::Integer(value)) => value.to_string(),
+        Ok(Number::Float(value)) => format!("{value:?}"),
+        Err(NumberError::OutOfRange) => {
+            let description = FormattedString::new()
+                .text("This literal is too large to fit in 64 bits.");
+            return Some(description);
+        }
+        Err(NumberError::Invalid) => return None,
+    };
+
+    (value != token.text()).then(|| {
+        FormattedString::new()
+            .text("This literal stands for ")
+            .code(value)
+            .text(".")
+    })
+}
+
+/// Finds the documentation of the global binding the given token declares or
+/// refers to.
+fn documentation(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    token: &SyntaxToken,
+) -> Option<String> {
+    if token.kind() != SyntaxKind::Identifier {
+        return None;
+    }
+
+    let offset = token.text_range().start().into();
+    let binding =
+        db.lookup_intern_binding(db.binding_at_offset(file_id, offset)?);
+    if binding.kind != BindingKind::Global {
+        return None;
+    }
+
+    db.global_bindings(binding.file_id)
+        .iter()
+        .find(|global| global.range == binding.range)
+        .and_then(|global| global.doc.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+    use lsp_types::{
+        Position, TextDocumentIdentifier, TextDocumentPositionParams, Url,
+    };
+
+    fn check(source: &str, position: Position) -> Option<String> {
+        let uri = Url::parse("file:///test.hl").unwrap();
+        let mut state = State::new();
+        state.set_document(&uri, source.to_string());
+
+        let params = HoverParams {
+            text_document_position_params: TextDocumentPositionParams::new(
+                TextDocumentIdentifier::new(uri),
+                position,
+            ),
+            work_done_progress_params: Default::default(),
+        };
+
+        hover(&state.snapshot(), params).unwrap().map(|hover| {
+            match hover.contents {
+                HoverContents::Markup(markup) => markup.value,
+                contents => panic!("Unexpected hover contents: {contents:?}"),
+            }
+        })
+    }
+
+    #[test]
+    fn test_hover_token() {
+        let source = "let a = 1 +  2\n";
+        assert_eq!(
+            check(source, Position::new(0, 1)).as_deref(),
+            Some("the let keyword, in a global binding declaration")
+        );
+        assert_eq!(
+            check(source, Position::new(0, 10)).as_deref(),
+            Some(
+                "a plus symbol (`+`), in a binary expression\n\n---\n\n\
+                ```helios\nInt\n```"
+            )
+        );
+        assert_eq!(check(source, Position::new(0, 12)), None);
+    }
+
+    #[test]
+    fn test_hover_number_value() {
+        let source = "let a = 0xff_ff + 42\nlet b = 1e3\n\
+            let c = 18446744073709551616\n";
+        assert_eq!(
+            check(source, Position::new(0, 8)).as_deref(),
+            Some(
+                "an integer literal, in a literal expression\n\n---\n\n\
+                This literal stands for `65535`.\n\n---\n\n\
+                ```helios\nInt\n```"
+            )
+        );
+        assert_eq!(
+            check(source, Position::new(1, 8)).map(|hover| {
+                hover.contains("This literal stands for `1000.0`.")
+            }),
+            Some(true)
+        );
+        assert_eq!(
+            check(source, Position::new(2, 8)).map(|hover| {
+                hover.contains("This literal is too large to fit in 64 bits.")
+            }),
+            Some(true)
+        );
+
+        // Literals written just like their value aren't described again.
+        assert_eq!(
+            check(source, Position::new(0, 18))
+                .map(|hover| { hover.contains("This literal stands for") }),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_hover_documentation() {
+        let source = "## The answer.\nlet a = 42\n\nlet b = a\n";
+        let expected = "an identifier, in a global binding declaration\n\n\
+            ---\n\n```helios\nInt\n```\n\n---\n\nThe answer.";
+
+        assert_eq!(
+            check(source, Position::new(1, 4)).as_deref(),
+            Some(expected)
+        );
+        assert_eq!(
+            check(source, Position::new(3, 4)).as_deref(),
+            Some(
+                "an identifier, in a global binding declaration\n\n---\n\n\
+                ```helios\nInt\n```"
+            )
+        );
+
+        // Names shadowing the binding don't have its documentation.
+        let source = "## The answer.\nlet x = 42\nmacro id x = x\n";
+        assert!(check(source, Position::new(2, 13))
+            .is_some_and(|hover| !hover.contains("The answer.")));
+    }
+}
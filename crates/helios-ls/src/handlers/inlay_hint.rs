@@ -0,0 +1,95 @@
+use crate::{convert, Result, StateSnapshot};
+use helios_query::{Bindings, Types};
+use lsp_types::{InlayHint, InlayHintKind, InlayHintLabel, InlayHintParams};
+
+/// Shows the inferred type of each global binding in the requested range of a
+/// document after the binding's name.
+///
+/// No hints are shown if they're turned off in the settings.
+pub fn inlay_hint(
+    state: &StateSnapshot,
+    params: InlayHintParams,
+) -> Result<Option<Vec<InlayHint>>> {
+    let file_id = match state.file_id(&params.text_document.uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    if !state.config().inlay_hints.enable {
+        return Ok(Some(Vec::new()));
+    }
+
+    let db = state.db();
+    let start = convert::offset(db, file_id, params.range.start);
+    let end = convert::offset(db, file_id, params.range.end);
+
+    let hints = db
+        .global_bindings(file_id)
+        .iter()
+        .filter(|binding| {
+            start <= binding.range.end && binding.range.end <= end
+        })
+        .filter_map(|binding| {
+            let ty = db.type_at_offset(file_id, binding.range.start)?;
+            Some(InlayHint {
+                position: convert::position(db, file_id, binding.range.end),
+                label: InlayHintLabel::String(format!(": {ty}")),
+                kind: Some(InlayHintKind::TYPE),
+                text_edits: None,
+                tooltip: None,
+                padding_left: None,
+                padding_right: None,
+                data: None,
+            })
+        })
+        .collect();
+
+    Ok(Some(hints))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::State;
+    use lsp_types::{Position, Range, TextDocumentIdentifier, Url};
+    use serde_json::json;
+
+    fn hints(state: &State, uri: &Url) -> Vec<(u32, u32, String)> {
+        let params = InlayHintParams {
+            work_done_progress_params: Default::default(),
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            range: Range::new(Position::new(0, 0), Position::new(10, 0)),
+        };
+
+        inlay_hint(&state.snapshot(), params)
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|hint| match hint.label {
+                InlayHintLabel::String(label) => {
+                    (hint.position.line, hint.position.character, label)
+                }
+                label => panic!("Unexpected label: {label:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_inlay_hint() {
+        let uri = Url::parse("file:///test.hl").unwrap();
+        let mut state = State::new();
+        state.set_document(&uri, "let a = 1\nlet bc = a < 2\n".to_string());
+
+        assert_eq!(
+            hints(&state, &uri),
+            vec![(0, 5, ": Int".to_string()), (1, 6, ": Bool".to_string())]
+        );
+
+        // Turning the hints off leaves none to show.
+        let settings =
+            json!({ "helios": { "inlayHints": { "enable": false } } });
+        state.set_config(Config::from_json(settings).unwrap());
+        assert_eq!(hints(&state, &uri), vec![]);
+    }
+}
@@ -0,0 +1,295 @@
+use super::{token_at_offset, LspError};
+use crate::{convert, Result, StateSnapshot};
+use helios_parser::{Edition, TokenizeOptions};
+use helios_query::{
+    BindingId, BindingKind, Bindings, FileId, HeliosDatabase, Input, Interner,
+};
+use helios_syntax::{SyntaxKind, SyntaxToken};
+use lsp_types::{
+    PrepareRenameResponse, RenameParams, TextDocumentPositionParams, TextEdit,
+    WorkspaceEdit,
+};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Checks whether the token under the cursor can be renamed, returning its
+/// range and current name if it can.
+pub fn prepare_rename(
+    state: &StateSnapshot,
+    params: TextDocumentPositionParams,
+) -> Result<Option<PrepareRenameResponse>> {
+    let file_id = match state.file_id(&params.text_document.uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    let db = state.db();
+    let (token, _) = binding_at(db, file_id, &params)?;
+    let range = token.text_range();
+
+    Ok(Some(PrepareRenameResponse::RangeWithPlaceholder {
+        range: convert::range(
+            db,
+            file_id,
+            range.start().into()..range.end().into(),
+        ),
+        placeholder: token.text().to_string(),
+    }))
+}
+
+/// Renames the binding under the cursor, along with every reference to it.
+pub fn rename(
+    state: &StateSnapshot,
+    params: RenameParams,
+) -> Result<Option<WorkspaceEdit>> {
+    let position = params.text_document_position;
+    let file_id = match state.file_id(&position.text_document.uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    check_new_name(&params.new_name)?;
+
+    let db = state.db();
+    let (_, binding) = binding_at(db, file_id, &position)?;
+    let edits = references(db, file_id, binding)
+        .into_iter()
+        .map(|range| TextEdit {
+            range: convert::range(db, file_id, range),
+            new_text: params.new_name.clone(),
+        })
+        .collect();
+
+    let changes = HashMap::from([(position.text_document.uri, edits)]);
+    Ok(Some(WorkspaceEdit::new(changes)))
+}
+
+/// Finds the identifier at the given position, along with the global binding it
+/// declares or refers to.
+fn binding_at(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    params: &TextDocumentPositionParams,
+) -> Result<(SyntaxToken, BindingId)> {
+    let offset = convert::offset(db, file_id, params.position);
+    let token = token_at_offset(db, file_id, offset).ok_or_else(|| {
+        LspError::request_failed("There is nothing to rename")
+    })?;
+
+    match token.kind() {
+        SyntaxKind::Identifier => {}
+        kind if kind.is_keyword() => {
+            return Err(LspError::request_failed(format!(
+                "`{}` is a keyword and cannot be renamed",
+                token.text()
+            ))
+            .into());
+        }
+        _ => {
+            return Err(LspError::request_failed(
+                "Only bindings can be renamed",
+            )
+            .into());
+        }
+    }
+
+    let binding = db
+        .binding_at_offset(file_id, token.text_range().start().into())
+        .filter(|&binding| {
+            db.lookup_intern_binding(binding).kind == BindingKind::Global
+        });
+
+    match binding {
+        Some(binding) => Ok((token, binding)),
+        None => Err(LspError::request_failed(format!(
+            "`{}` doesn't refer to a known binding",
+            token.text()
+        ))
+        .into()),
+    }
+}
+
+/// Makes sure the given name would be read back as a single identifier.
+fn check_new_name(new_name: &str) -> Result<()> {
+    // A second token is enough to tell the name apart from an identifier. The
+    // name is lexed in the latest edition, so that it doesn't get in the way
+    // of moving on to it.
+    let options = TokenizeOptions {
+        max_tokens: Some(2),
+        edition: Edition::LATEST,
+        ..TokenizeOptions::default()
+    };
+    let (tokens, messages) = helios_parser::tokenize_with_options(
+        FileId::default(),
+        new_name,
+        options,
+    );
+
+    match tokens.as_slice() {
+        [token] if messages.is_empty() => match token.kind {
+            SyntaxKind::Identifier => Ok(()),
+            kind if kind.is_keyword() => {
+                Err(LspError::request_failed(format!(
+                    "`{new_name}` is a keyword and cannot be used as a name"
+                ))
+                .into())
+            }
+            _ => Err(invalid_name(new_name)),
+        },
+        _ => Err(invalid_name(new_name)),
+    }
+}
+
+fn invalid_name(new_name: &str) -> Box<LspError> {
+    Box::new(LspError::request_failed(format!(
+        "`{new_name}` is not a valid identifier"
+    )))
+}
+
+/// The ranges of every identifier of the given file declaring or referring to
+/// the given binding.
+///
+/// Variable references are resolved like everywhere else (see
+/// [`Bindings::binding_at_offset`]), so names shadowing the binding (such as
+/// the parameters of a macro) are left alone.
+fn references(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    binding: BindingId,
+) -> Vec<Range<usize>> {
+    let name = db.lookup_intern_binding(binding).name;
+    db.parse(file_id)
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| {
+            token.kind() == SyntaxKind::Identifier && token.text() == name
+        })
+        .map(|token| {
+            let range = token.text_range();
+            range.start().into()..range.end().into()
+        })
+        .filter(|range: &Range<usize>| {
+            db.binding_at_offset(file_id, range.start) == Some(binding)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+    use lsp_types::{Position, TextDocumentIdentifier, Url};
+
+    const SOURCE: &str = "let a = 1\nlet b = a + a\nlet c = d\n";
+
+    fn state() -> (State, Url) {
+        let uri = Url::parse("file:///test.hl").unwrap();
+        let mut state = State::new();
+        state.set_document(&uri, SOURCE.to_string());
+        (state, uri)
+    }
+
+    fn rename_at(position: Position, new_name: &str) -> Result<Vec<TextEdit>> {
+        let (state, uri) = state();
+        let params = RenameParams {
+            text_document_position: TextDocumentPositionParams::new(
+                TextDocumentIdentifier::new(uri.clone()),
+                position,
+            ),
+            new_name: new_name.to_string(),
+            work_done_progress_params: Default::default(),
+        };
+
+        let edit = rename(&state.snapshot(), params)?.unwrap();
+        Ok(edit.changes.unwrap().remove(&uri).unwrap())
+    }
+
+    #[test]
+    fn test_prepare_rename() {
+        let (state, uri) = state();
+        let params = |line, character| {
+            TextDocumentPositionParams::new(
+                TextDocumentIdentifier::new(uri.clone()),
+                Position::new(line, character),
+            )
+        };
+
+        assert_eq!(
+            prepare_rename(&state.snapshot(), params(1, 9)).unwrap(),
+            Some(PrepareRenameResponse::RangeWithPlaceholder {
+                range: lsp_types::Range::new(
+                    Position::new(1, 8),
+                    Position::new(1, 9)
+                ),
+                placeholder: "a".to_string(),
+            })
+        );
+
+        let error = |line, character| {
+            prepare_rename(&state.snapshot(), params(line, character))
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(error(0, 1), "`let` is a keyword and cannot be renamed");
+        assert_eq!(error(2, 8), "`d` doesn't refer to a known binding");
+        assert_eq!(error(0, 6), "Only bindings can be renamed");
+    }
+
+    #[test]
+    fn test_rename() {
+        let edits = rename_at(Position::new(0, 4), "answer").unwrap();
+        let ranges = edits
+            .iter()
+            .map(|edit| {
+                assert_eq!(edit.new_text, "answer");
+                (edit.range.start.line, edit.range.start.character)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(ranges, vec![(0, 4), (1, 8), (1, 12)]);
+
+        let error = |new_name| {
+            rename_at(Position::new(0, 4), new_name)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            error("let"),
+            "`let` is a keyword and cannot be used as a name"
+        );
+        assert_eq!(
+            error("macro"),
+            "`macro` is a keyword and cannot be used as a name"
+        );
+        assert_eq!(error("a b"), "`a b` is not a valid identifier");
+        assert_eq!(error("1a"), "`1a` is not a valid identifier");
+    }
+
+    #[test]
+    fn test_rename_shadowed() {
+        // The parameter `x` of the macro shadows the global binding.
+        let uri = Url::parse("file:///test.hl").unwrap();
+        let mut state = State::new();
+        let source = "let x = 1\nmacro add x y = x + y\nlet b = add!(x, 2)\n";
+        state.set_document(&uri, source.to_string());
+
+        let params = RenameParams {
+            text_document_position: TextDocumentPositionParams::new(
+                TextDocumentIdentifier::new(uri.clone()),
+                Position::new(0, 4),
+            ),
+            new_name: "z".to_string(),
+            work_done_progress_params: Default::default(),
+        };
+        let edit = rename(&state.snapshot(), params).unwrap().unwrap();
+        let ranges = edit.changes.unwrap()[&uri]
+            .iter()
+            .map(|edit| (edit.range.start.line, edit.range.start.character))
+            .collect::<Vec<_>>();
+
+        assert_eq!(ranges, vec![(0, 4), (2, 13)]);
+    }
+}
@@ -0,0 +1,138 @@
+use super::token_at_offset;
+use crate::{convert, Result, StateSnapshot};
+use helios_query::{FileId, HeliosDatabase, Input};
+use helios_syntax::SyntaxNode;
+use lsp_types::{Position, SelectionRange, SelectionRangeParams};
+use rowan::{TextRange, TextSize};
+
+/// Works out the ranges a selection can be expanded to from each of the given
+/// positions, from the token under the cursor up to the whole file.
+pub fn selection_range(
+    state: &StateSnapshot,
+    params: SelectionRangeParams,
+) -> Result<Option<Vec<SelectionRange>>> {
+    let file_id = match state.file_id(&params.text_document.uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    let db = state.db();
+    let ranges = params
+        .positions
+        .into_iter()
+        .map(|position| selection_range_at(db, file_id, position))
+        .collect();
+
+    Ok(Some(ranges))
+}
+
+fn selection_range_at(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    position: Position,
+) -> SelectionRange {
+    let offset = convert::offset(db, file_id, position);
+    let root = db.parse(file_id).syntax();
+
+    let (mut ranges, node) = match token_at_offset(db, file_id, offset) {
+        Some(token) => (vec![token.text_range()], token.parent()),
+        None => {
+            let offset = TextSize::try_from(offset).unwrap_or_default();
+            let range = TextRange::empty(offset.min(root.text_range().end()));
+            (Vec::new(), root.covering_element(range).into_node())
+        }
+    };
+
+    for node in node.into_iter().flat_map(|node| node.ancestors()) {
+        if let Some(range) = trimmed_range(&node) {
+            if ranges.last() != Some(&range) {
+                ranges.push(range);
+            }
+        }
+    }
+
+    // The whole file (including any leading or trailing whitespace) is always
+    // the last step.
+    if ranges.last() != Some(&root.text_range()) {
+        ranges.push(root.text_range());
+    }
+
+    let to_lsp = |range: TextRange| {
+        convert::range(db, file_id, range.start().into()..range.end().into())
+    };
+
+    ranges
+        .into_iter()
+        .rev()
+        .fold(None, |parent, range| {
+            Some(SelectionRange {
+                range: to_lsp(range),
+                parent: parent.map(Box::new),
+            })
+        })
+        .expect("There should be at least one selection range")
+}
+
+/// The range of a node without any leading or trailing trivia, or `None` if
+/// the node is made up of trivia only.
+fn trimmed_range(node: &SyntaxNode) -> Option<TextRange> {
+    let mut tokens = node
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| !token.kind().is_trivia());
+
+    let first = tokens.next()?;
+    let last = tokens.last().unwrap_or_else(|| first.clone());
+
+    Some(TextRange::new(
+        first.text_range().start(),
+        last.text_range().end(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+    use lsp_types::{TextDocumentIdentifier, Url};
+
+    fn check(source: &str, position: Position) -> Vec<(u32, u32)> {
+        let uri = Url::parse("file:///test.hl").unwrap();
+        let mut state = State::new();
+        state.set_document(&uri, source.to_string());
+
+        let params = SelectionRangeParams {
+            text_document: TextDocumentIdentifier::new(uri),
+            positions: vec![position],
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+
+        let mut ranges =
+            selection_range(&state.snapshot(), params).unwrap().unwrap();
+        let mut selection = Some(Box::new(ranges.remove(0)));
+        let mut columns = Vec::new();
+
+        while let Some(range) = selection {
+            assert_eq!(range.range.start.line, 0);
+            columns
+                .push((range.range.start.character, range.range.end.character));
+            selection = range.parent;
+        }
+
+        columns
+    }
+
+    #[test]
+    fn test_selection_range() {
+        let source = "let a = 1 + (2 * b) # comment";
+        assert_eq!(
+            check(source, Position::new(0, 14)),
+            vec![(13, 14), (13, 18), (12, 19), (8, 19), (0, 19), (0, 29)]
+        );
+        assert_eq!(
+            check(source, Position::new(0, 17)),
+            vec![(17, 18), (13, 18), (12, 19), (8, 19), (0, 19), (0, 29)]
+        );
+    }
+}
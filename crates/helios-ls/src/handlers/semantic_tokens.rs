@@ -0,0 +1,186 @@
+use crate::{convert, Result, StateSnapshot};
+use helios_query::{FileId, HeliosDatabase, Input};
+use helios_syntax::SyntaxKind;
+use lsp_types::{
+    SemanticToken, SemanticTokenType, SemanticTokens, SemanticTokensLegend,
+    SemanticTokensParams, SemanticTokensResult,
+};
+use std::ops::Range;
+
+/// The types of the tokens sent to the client, in the order of their indices.
+///
+/// Editors already highlight most of the source with a grammar, so only what
+/// a grammar can't easily tell apart is sent, such as the escape sequences of
+/// string literals (which are otherwise easy to mistake for plain text).
+const TOKEN_TYPES: &[SemanticTokenType] = &[
+    SemanticTokenType::STRING,
+    SemanticTokenType::new("escapeSequence"),
+];
+
+const STRING: u32 = 0;
+const ESCAPE_SEQUENCE: u32 = 1;
+
+/// The legend the server announces, which tells the client what the types of
+/// the tokens stand for.
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: Vec::new(),
+    }
+}
+
+/// Lists the semantic tokens of a whole document, which are the parts of its
+/// string and character literals (outside of the expressions embedded in
+/// them) and their escape sequences.
+pub fn semantic_tokens_full(
+    state: &StateSnapshot,
+    params: SemanticTokensParams,
+) -> Result<Option<SemanticTokensResult>> {
+    let file_id = match state.file_id(&params.text_document.uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    let db = state.db();
+    let mut tokens = Vec::new();
+    for token in db.parse(file_id).syntax().descendants_with_tokens() {
+        let Some(token) = token.into_token() else {
+            continue;
+        };
+        if !matches!(
+            token.kind(),
+            SyntaxKind::Lit_String
+                | SyntaxKind::Lit_InterpolatedString
+                | SyntaxKind::Lit_Character
+        ) {
+            continue;
+        }
+
+        let start = usize::from(token.text_range().start());
+        let end = usize::from(token.text_range().end());
+        let Some(literal) = db.cooked_literal(file_id, start) else {
+            continue;
+        };
+
+        // The escape sequences and embedded expressions (along with their
+        // braces) split the rest of the literal into string segments.
+        let mut gaps = literal
+            .escapes
+            .iter()
+            .map(|range| (range.clone(), Some(ESCAPE_SEQUENCE)))
+            .chain(
+                literal.holes.iter().map(|hole| {
+                    (hole.range.start - 1..hole.range.end + 1, None)
+                }),
+            )
+            .map(|(range, kind)| (start + range.start..start + range.end, kind))
+            .collect::<Vec<_>>();
+        gaps.sort_by_key(|(range, _)| range.start);
+
+        let mut offset = start;
+        for (range, kind) in gaps {
+            if offset < range.start {
+                tokens.push((offset..range.start, STRING));
+            }
+            if let Some(kind) = kind {
+                tokens.push((range.clone(), kind));
+            }
+            offset = offset.max(range.end);
+        }
+        if offset < end {
+            tokens.push((offset..end, STRING));
+        }
+    }
+
+    let data = encode(db, file_id, &tokens);
+    Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
+        result_id: None,
+        data,
+    })))
+}
+
+/// Encodes the given tokens (in the order they appear) relative to each
+/// other, as the protocol expects.
+///
+/// Literals can't span several lines, so neither can their tokens.
+fn encode(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    tokens: &[(Range<usize>, u32)],
+) -> Vec<SemanticToken> {
+    let mut previous = lsp_types::Position::new(0, 0);
+    tokens
+        .iter()
+        .map(|(range, kind)| {
+            let range = convert::range(db, file_id, range.clone());
+            let (start, end) = (range.start, range.end);
+            let delta_line = start.line - previous.line;
+            let delta_start = match delta_line {
+                0 => start.character - previous.character,
+                _ => start.character,
+            };
+            previous = start;
+
+            SemanticToken {
+                delta_line,
+                delta_start,
+                length: end.character - start.character,
+                token_type: *kind,
+                token_modifiers_bitset: 0,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+    use lsp_types::{TextDocumentIdentifier, Url};
+
+    #[test]
+    fn test_semantic_tokens_full() {
+        let uri = Url::parse("file:///test.helios").unwrap();
+        let mut state = State::new();
+        let source = "let a = \"é\\n\"\nlet b = f\"{a}\\t{{\" + 'c'\n";
+        state.set_document(&uri, source.to_string());
+
+        let params = SemanticTokensParams {
+            text_document: TextDocumentIdentifier::new(uri),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+
+        let tokens = match semantic_tokens_full(&state.snapshot(), params) {
+            Ok(Some(SemanticTokensResult::Tokens(tokens))) => tokens
+                .data
+                .into_iter()
+                .map(|token| {
+                    (
+                        token.delta_line,
+                        token.delta_start,
+                        token.length,
+                        token.token_type,
+                    )
+                })
+                .collect::<Vec<_>>(),
+            response => panic!("Unexpected response: {response:?}"),
+        };
+
+        // The lengths are counted in UTF-16 code units, and the embedded
+        // expression is left out along with its braces.
+        assert_eq!(
+            tokens,
+            vec![
+                (0, 8, 2, STRING),
+                (0, 2, 2, ESCAPE_SEQUENCE),
+                (0, 2, 1, STRING),
+                (1, 8, 2, STRING),
+                (0, 5, 2, ESCAPE_SEQUENCE),
+                (0, 2, 2, ESCAPE_SEQUENCE),
+                (0, 2, 1, STRING),
+                (0, 4, 3, STRING),
+            ]
+        );
+    }
+}
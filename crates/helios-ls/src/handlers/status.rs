@@ -0,0 +1,62 @@
+use crate::lsp_ext::{StatusParams, StatusResponse, SyntaxTreeStats};
+use crate::{Result, StateSnapshot};
+use helios_query::Input;
+
+/// Reports the size and shape of the syntax tree of a document, along with
+/// the number of files in the workspace.
+pub fn status(
+    state: &StateSnapshot,
+    params: StatusParams,
+) -> Result<Option<StatusResponse>> {
+    let file_id = match state.file_id(&params.text_document.uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    let db = state.db();
+    let stats = db.parse(file_id).stats();
+    Ok(Some(StatusResponse {
+        workspace_files: db.workspace_files().len(),
+        syntax_tree: SyntaxTreeStats {
+            nodes: stats.nodes,
+            tokens: stats.tokens,
+            max_depth: stats.max_depth,
+            error_nodes: stats.error_nodes,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+    use lsp_types::{TextDocumentIdentifier, Url};
+
+    #[test]
+    fn test_status() {
+        let uri = Url::parse("file:///test.helios").unwrap();
+        let mut state = State::new();
+        state.set_document(&uri, "let a = 1\n".to_string());
+
+        let params = StatusParams {
+            text_document: TextDocumentIdentifier::new(uri),
+        };
+        let response = status(&state.snapshot(), params).unwrap().unwrap();
+        assert_eq!(
+            response.syntax_tree,
+            SyntaxTreeStats {
+                nodes: 3,
+                tokens: 8,
+                max_depth: 3,
+                error_nodes: 0,
+            }
+        );
+
+        let unknown = StatusParams {
+            text_document: TextDocumentIdentifier::new(
+                Url::parse("file:///unknown.helios").unwrap(),
+            ),
+        };
+        assert_eq!(status(&state.snapshot(), unknown).unwrap(), None);
+    }
+}
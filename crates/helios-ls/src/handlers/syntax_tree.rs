@@ -0,0 +1,139 @@
+use crate::lsp_ext::{
+    SyntaxTreeElement, SyntaxTreeFormat, SyntaxTreeParams, SyntaxTreeResponse,
+};
+use crate::{convert, Result, StateSnapshot};
+use helios_query::{FileId, HeliosDatabase, Input};
+use helios_syntax::SyntaxElement;
+use rowan::{NodeOrToken, TextRange, TextSize};
+
+/// Shows the syntax tree of a document, or of the smallest node (or token)
+/// covering the given range.
+pub fn syntax_tree(
+    state: &StateSnapshot,
+    params: SyntaxTreeParams,
+) -> Result<Option<SyntaxTreeResponse>> {
+    let file_id = match state.file_id(&params.text_document.uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    let db = state.db();
+    let root = db.parse(file_id).syntax();
+    let element = match params.range {
+        Some(range) => {
+            let offset = |position| {
+                let offset = convert::offset(db, file_id, position);
+                TextSize::try_from(offset).unwrap_or_default()
+            };
+
+            let range = TextRange::new(offset(range.start), offset(range.end));
+            root.covering_element(range)
+        }
+        None => NodeOrToken::Node(root),
+    };
+
+    let response = match params.format {
+        SyntaxTreeFormat::Debug => SyntaxTreeResponse::Debug(match element {
+            NodeOrToken::Node(node) => format!("{node:#?}"),
+            NodeOrToken::Token(token) => format!("{token:?}\n"),
+        }),
+        SyntaxTreeFormat::Json => {
+            SyntaxTreeResponse::Json(to_json(db, file_id, element))
+        }
+    };
+
+    Ok(Some(response))
+}
+
+fn to_json(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    element: SyntaxElement,
+) -> SyntaxTreeElement {
+    let range = element.text_range();
+    let range =
+        convert::range(db, file_id, range.start().into()..range.end().into());
+
+    match element {
+        NodeOrToken::Node(node) => SyntaxTreeElement {
+            kind: format!("{:?}", node.kind()),
+            range,
+            text: None,
+            children: node
+                .children_with_tokens()
+                .map(|child| to_json(db, file_id, child))
+                .collect(),
+        },
+        NodeOrToken::Token(token) => SyntaxTreeElement {
+            kind: format!("{:?}", token.kind()),
+            range,
+            text: Some(token.text().to_string()),
+            children: Vec::new(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+    use lsp_types::{Position, Range, TextDocumentIdentifier, Url};
+
+    fn syntax_tree_of(
+        range: Option<Range>,
+        format: SyntaxTreeFormat,
+    ) -> SyntaxTreeResponse {
+        let uri = Url::parse("file:///test.helios").unwrap();
+        let mut state = State::new();
+        state.set_document(&uri, "let a = 1\n".to_string());
+
+        let params = SyntaxTreeParams {
+            text_document: TextDocumentIdentifier::new(uri),
+            range,
+            format,
+        };
+
+        syntax_tree(&state.snapshot(), params).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_syntax_tree_debug() {
+        let range = Range::new(Position::new(0, 8), Position::new(0, 9));
+        match syntax_tree_of(Some(range), SyntaxTreeFormat::Debug) {
+            SyntaxTreeResponse::Debug(tree) => {
+                assert_eq!(tree, "Lit_Integer@8..9 \"1\"\n")
+            }
+            response => panic!("Unexpected response: {response:?}"),
+        }
+
+        match syntax_tree_of(None, SyntaxTreeFormat::Debug) {
+            SyntaxTreeResponse::Debug(tree) => {
+                assert!(tree.starts_with("Root@0..10\n"))
+            }
+            response => panic!("Unexpected response: {response:?}"),
+        }
+    }
+
+    #[test]
+    fn test_syntax_tree_json() {
+        let range = Range::new(Position::new(0, 4), Position::new(0, 9));
+        let tree = match syntax_tree_of(Some(range), SyntaxTreeFormat::Json) {
+            SyntaxTreeResponse::Json(tree) => tree,
+            response => panic!("Unexpected response: {response:?}"),
+        };
+
+        assert_eq!(tree.kind, "Dec_GlobalBinding");
+        assert_eq!(tree.text, None);
+
+        let identifier = tree
+            .children
+            .iter()
+            .find(|child| child.kind == "Identifier")
+            .unwrap();
+        assert_eq!(identifier.text.as_deref(), Some("a"));
+        assert_eq!(
+            identifier.range,
+            Range::new(Position::new(0, 4), Position::new(0, 5))
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{convert, Result, StateSnapshot};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use helios_query::{Bindings, CheckCancelled};
+use lsp_types::{
+    Location, SymbolInformation, SymbolKind, WorkspaceSymbolParams,
+    WorkspaceSymbolResponse,
+};
+
+/// The maximum number of symbols returned for a single query.
+const MAX_SYMBOLS: usize = 128;
+
+/// Finds the top-level declarations across the workspace whose names fuzzily
+/// match the query, with the best matches first.
+pub fn workspace_symbol(
+    state: &StateSnapshot,
+    params: WorkspaceSymbolParams,
+) -> Result<Option<WorkspaceSymbolResponse>> {
+    let db = state.db();
+    let matcher = SkimMatcherV2::default();
+    let symbols = db.workspace_symbols();
+
+    let mut matches = symbols
+        .iter()
+        .filter_map(|(file_id, binding)| {
+            db.unwind_if_cancelled();
+            let score = matcher.fuzzy_match(&binding.name, &params.query)?;
+            Some((score, file_id, binding))
+        })
+        .collect::<Vec<_>>();
+
+    // The sort is stable, so symbols with the same score stay in the order
+    // they were declared in.
+    matches.sort_by_key(|(score, ..)| std::cmp::Reverse(*score));
+
+    let symbols = matches
+        .into_iter()
+        .take(MAX_SYMBOLS)
+        .filter_map(|(_, &file_id, binding)| {
+            let uri = state.uri(file_id)?.clone();
+            let range = convert::range(db, file_id, binding.range.clone());
+
+            #[allow(deprecated)]
+            Some(SymbolInformation {
+                name: binding.name.clone(),
+                kind: SymbolKind::VARIABLE,
+                tags: None,
+                deprecated: None,
+                location: Location::new(uri, range),
+                container_name: None,
+            })
+        })
+        .collect();
+
+    Ok(Some(WorkspaceSymbolResponse::Flat(symbols)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+    use lsp_types::Url;
+
+    #[test]
+    fn test_workspace_symbol() {
+        let mut state = State::new();
+        let a = Url::parse("file:///a.helios").unwrap();
+        let b = Url::parse("file:///b.helios").unwrap();
+        state.set_document(&a, "let foo_bar = 1\n".to_string());
+        state.set_document(&b, "let fab = 2\nlet baz = 3".to_string());
+
+        let query = |query: &str| {
+            let params = WorkspaceSymbolParams {
+                query: query.to_string(),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            };
+
+            match workspace_symbol(&state.snapshot(), params).unwrap() {
+                Some(WorkspaceSymbolResponse::Flat(symbols)) => symbols
+                    .into_iter()
+                    .map(|symbol| {
+                        let uri = symbol.location.uri.to_string();
+                        (symbol.name, uri)
+                    })
+                    .collect::<Vec<_>>(),
+                response => panic!("Unexpected response: {response:?}"),
+            }
+        };
+
+        assert_eq!(query("").len(), 3);
+        assert_eq!(
+            query("ba"),
+            vec![
+                ("baz".to_string(), b.to_string()),
+                ("foo_bar".to_string(), a.to_string()),
+            ]
+        );
+        assert_eq!(query("fab"), vec![("fab".to_string(), b.to_string())]);
+        assert_eq!(query("qux"), Vec::new());
+    }
+}
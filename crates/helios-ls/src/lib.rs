@@ -0,0 +1,210 @@
+//! The Helios language server.
+//!
+//! The server speaks the [Language Server Protocol][lsp] (with the
+//! [`lsp_server`] crate taking care of the transport) and answers requests with
+//! the queries provided by [`helios_query`], so that every editor feature works
+//! off the same incremental database.
+//!
+//! [lsp]: https://microsoft.github.io/language-server-protocol
+
+mod config;
+mod connection;
+mod convert;
+mod handlers;
+mod lsp_ext;
+mod progress;
+mod state;
+mod trace;
+
+use crate::progress::Progress;
+use helios_query::{Bindings, Diagnostics, SourceRoots};
+use lsp_server::{Connection, ErrorCode, Message, RequestId, Response};
+use lsp_types::notification::{Cancel, Notification as _};
+use lsp_types::{CancelParams, InitializeParams, NumberOrString};
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Instant;
+
+pub use crate::config::Config;
+pub use crate::connection::Transport;
+pub use crate::state::{State, StateSnapshot};
+pub use crate::trace::init as init_tracing;
+
+pub type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Starts the language server, communicating with the client over the given
+/// transport until the client asks the server to exit.
+pub fn start(transport: Transport) -> Result<()> {
+    let (connection, io_threads) = transport.open()?;
+
+    let params = connection::initialize(&connection)?;
+    trace::connect(connection.sender.clone(), params.trace.unwrap_or_default());
+    connection::register_file_watchers(&connection, &params)?;
+
+    let mut state = State::new();
+
+    match Config::from_json(params.initialization_options.clone().into()) {
+        Ok(config) => state.set_config(config),
+        Err(error) => tracing::warn!(%error, "Ignoring invalid settings"),
+    }
+
+    index_workspace(&connection, &params, &mut state)?;
+    main_loop(&connection, state)?;
+
+    // The writer thread only stops once every sender has been dropped.
+    trace::disconnect();
+    drop(connection);
+    io_threads.join()?;
+
+    tracing::info!("Shut down server");
+    Ok(())
+}
+
+/// Loads and parses every Helios file in the workspace the client opened,
+/// reporting the progress to the client.
+fn index_workspace(
+    connection: &Connection,
+    params: &InitializeParams,
+    state: &mut State,
+) -> Result<()> {
+    let mut files = Vec::new();
+    for path in workspace_roots(params) {
+        let root_id = state.add_workspace_folder(path);
+        files.extend(state.db().source_root(root_id).find_files());
+    }
+
+    let mut progress = Progress::begin(connection, params, "Indexing")?;
+    let mut file_ids = Vec::with_capacity(files.len());
+    for (index, path) in files.iter().enumerate() {
+        file_ids.extend(state.load_file(path));
+        progress.report(index + 1, files.len())?;
+    }
+
+    // Nothing changes the database while indexing, so this can't be
+    // cancelled.
+    let _ = state.db().map_parallel(&file_ids, |db, file_id| {
+        db.global_bindings(file_id);
+        db.diagnostics(file_id);
+    });
+
+    progress.end()?;
+    tracing::info!(files = files.len(), "Indexed workspace");
+    Ok(())
+}
+
+/// The directories of the workspace the client opened.
+fn workspace_roots(params: &InitializeParams) -> Vec<PathBuf> {
+    #[allow(deprecated)]
+    let uris = match (&params.workspace_folders, &params.root_uri) {
+        (Some(folders), _) => {
+            folders.iter().map(|folder| folder.uri.clone()).collect()
+        }
+        (None, Some(root_uri)) => vec![root_uri.clone()],
+        (None, None) => Vec::new(),
+    };
+
+    uris.into_iter()
+        .filter_map(|uri| uri.to_file_path().ok())
+        .collect()
+}
+
+fn main_loop(connection: &Connection, mut state: State) -> Result<()> {
+    // Requests are handled on their own threads, which send their responses
+    // back here so that cancelled requests can be filtered out.
+    let (sender, receiver) = crossbeam_channel::unbounded::<Response>();
+    let mut pending = HashSet::new();
+
+    loop {
+        crossbeam_channel::select! {
+            recv(connection.receiver) -> message => match message? {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+
+                    let span = tracing::info_span!(
+                        "request",
+                        method = %request.method,
+                        id = %request.id,
+                    );
+
+                    pending.insert(request.id.clone());
+                    let task = span.in_scope(|| {
+                        handlers::handle_request(&state, request)
+                    });
+                    let sender = sender.clone();
+                    let start = Instant::now();
+
+                    thread::spawn(move || {
+                        let _guard = span.enter();
+                        let response = task();
+                        tracing::info!(
+                            elapsed_ms = start.elapsed().as_millis() as u64,
+                            outcome = outcome(&response),
+                            "Handled request",
+                        );
+
+                        // The main loop only stops once the server shuts down,
+                        // after which nobody is waiting for the response.
+                        let _ = sender.send(response);
+                    });
+                }
+                Message::Notification(notification)
+                    if notification.method == Cancel::METHOD =>
+                {
+                    let params: CancelParams =
+                        notification.extract(Cancel::METHOD)?;
+                    let id = match params.id {
+                        NumberOrString::Number(id) => RequestId::from(id),
+                        NumberOrString::String(id) => RequestId::from(id),
+                    };
+
+                    // The request may have been answered already.
+                    if pending.remove(&id) {
+                        tracing::debug!(%id, "Cancelled request");
+                        let response = Response::new_err(
+                            id,
+                            ErrorCode::RequestCanceled as i32,
+                            "The request was cancelled".to_string(),
+                        );
+                        connection.sender.send(response.into())?;
+                    }
+                }
+                Message::Notification(notification) => {
+                    let span = tracing::info_span!(
+                        "notification",
+                        method = %notification.method,
+                    );
+                    let _guard = span.enter();
+                    handlers::handle_notification(
+                        connection,
+                        &mut state,
+                        notification,
+                    )?;
+                }
+                Message::Response(response) => {
+                    tracing::debug!(id = %response.id, "Received response");
+                }
+            },
+            recv(receiver) -> response => {
+                let response = response?;
+                if pending.remove(&response.id) {
+                    connection.sender.send(response.into())?;
+                }
+            }
+        }
+    }
+}
+
+/// Describes how a request turned out, for tracing.
+fn outcome(response: &Response) -> &'static str {
+    match &response.error {
+        None => "ok",
+        Some(error) if error.code == ErrorCode::ContentModified as i32 => {
+            "content modified"
+        }
+        Some(_) => "error",
+    }
+}
@@ -0,0 +1,100 @@
+//! Extensions to the Language Server Protocol that are specific to Helios.
+//!
+//! Clients find out which extensions the server supports from the
+//! `experimental` field of the server's capabilities.
+
+use lsp_types::request::Request;
+use lsp_types::{Range, TextDocumentIdentifier};
+use serde::{Deserialize, Serialize};
+
+/// Returns the concrete syntax tree of a document (or of the smallest part of
+/// it covering a range), to help with developing the grammar.
+pub enum SyntaxTree {}
+
+impl Request for SyntaxTree {
+    type Params = SyntaxTreeParams;
+    type Result = Option<SyntaxTreeResponse>;
+    const METHOD: &'static str = "helios/syntaxTree";
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxTreeParams {
+    pub text_document: TextDocumentIdentifier,
+    /// The range to show the syntax tree of, or the whole document if this
+    /// isn't given.
+    pub range: Option<Range>,
+    #[serde(default)]
+    pub format: SyntaxTreeFormat,
+}
+
+/// The form a syntax tree is returned in.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum SyntaxTreeFormat {
+    /// The same indented text that is printed by the REPL.
+    #[default]
+    Debug,
+    /// A tree of [`SyntaxTreeElement`]s.
+    Json,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum SyntaxTreeResponse {
+    Debug(String),
+    Json(SyntaxTreeElement),
+}
+
+/// A node or token of a syntax tree.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxTreeElement {
+    pub kind: String,
+    pub range: Range,
+    /// The text of a token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// The children of a node.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SyntaxTreeElement>,
+}
+
+/// Returns what the server knows of a document, to help with finding out why
+/// it struggles with it (such as with a syntax tree far larger or deeper than
+/// expected).
+pub enum Status {}
+
+impl Request for Status {
+    type Params = StatusParams;
+    type Result = Option<StatusResponse>;
+    const METHOD: &'static str = "helios/status";
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusResponse {
+    /// The number of files in the workspace.
+    pub workspace_files: usize,
+    pub syntax_tree: SyntaxTreeStats,
+}
+
+/// The size and shape of the syntax tree of a document.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxTreeStats {
+    pub nodes: usize,
+    pub tokens: usize,
+    /// The most nodes nested in each other.
+    pub max_depth: usize,
+    /// The number of nodes holding what the parser couldn't make sense of.
+    pub error_nodes: usize,
+}
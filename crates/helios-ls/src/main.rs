@@ -0,0 +1,37 @@
+use clap::Parser;
+use helios_ls::Transport;
+
+/// The Helios language server.
+#[derive(Parser)]
+#[clap(version)]
+struct HeliosLsOpts {
+    /// Communicates over stdin and stdout (the default)
+    #[clap(long, conflicts_with_all = &["port", "socket"])]
+    stdio: bool,
+    /// Waits for a client to connect to the given local port
+    #[clap(long, conflicts_with = "socket")]
+    port: Option<u16>,
+    /// Connects to a client listening on the given local port
+    #[clap(long)]
+    socket: Option<u16>,
+}
+
+impl HeliosLsOpts {
+    fn transport(&self) -> Transport {
+        match (self.port, self.socket) {
+            (Some(port), _) => Transport::Listen(port),
+            (_, Some(port)) => Transport::Connect(port),
+            _ => Transport::Stdio,
+        }
+    }
+}
+
+fn main() {
+    helios_ls::init_tracing();
+    let opts = HeliosLsOpts::parse();
+
+    if let Err(error) = helios_ls::start(opts.transport()) {
+        tracing::error!(%error, "The language server stopped unexpectedly");
+        std::process::exit(1);
+    }
+}
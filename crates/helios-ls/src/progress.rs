@@ -0,0 +1,179 @@
+//! Progress reporting for long-running work done by the server.
+
+use crate::Result;
+use lsp_server::{Connection, Notification, Request, RequestId};
+use lsp_types::notification::{
+    Notification as _, Progress as ProgressNotification,
+};
+use lsp_types::request::{Request as _, WorkDoneProgressCreate};
+use lsp_types::{
+    InitializeParams, NumberOrString, ProgressParams, ProgressParamsValue,
+    WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressCreateParams,
+    WorkDoneProgressEnd, WorkDoneProgressReport,
+};
+
+/// A progress indicator shown by the client while the server is busy.
+///
+/// Nothing is sent if the client doesn't support progress reporting.
+pub struct Progress<'a> {
+    connection: &'a Connection,
+    token: Option<NumberOrString>,
+    percentage: u32,
+}
+
+impl<'a> Progress<'a> {
+    /// Asks the client to show a new progress indicator with the given title.
+    pub fn begin(
+        connection: &'a Connection,
+        params: &InitializeParams,
+        title: &str,
+    ) -> Result<Self> {
+        let supported = params
+            .capabilities
+            .window
+            .as_ref()
+            .and_then(|window| window.work_done_progress)
+            .unwrap_or(false);
+
+        let mut progress = Self {
+            connection,
+            token: None,
+            percentage: 0,
+        };
+
+        if !supported {
+            return Ok(progress);
+        }
+
+        let token = NumberOrString::String(format!("helios/{title}"));
+        let request = Request::new(
+            RequestId::from(format!(
+                "{}/{title}",
+                WorkDoneProgressCreate::METHOD
+            )),
+            WorkDoneProgressCreate::METHOD.to_string(),
+            WorkDoneProgressCreateParams {
+                token: token.clone(),
+            },
+        );
+
+        connection.sender.send(request.into())?;
+        progress.token = Some(token);
+        progress.send(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+            title: title.to_string(),
+            cancellable: Some(false),
+            message: None,
+            percentage: Some(0),
+        }))?;
+
+        Ok(progress)
+    }
+
+    /// Reports that `done` out of `total` steps are done.
+    ///
+    /// To avoid flooding the client, a report is only sent once the
+    /// percentage changes.
+    pub fn report(&mut self, done: usize, total: usize) -> Result<()> {
+        let percentage = (done * 100).checked_div(total).unwrap_or(100) as u32;
+        if percentage == self.percentage {
+            return Ok(());
+        }
+
+        self.percentage = percentage;
+        self.send(WorkDoneProgress::Report(WorkDoneProgressReport {
+            cancellable: Some(false),
+            message: Some(format!("{done}/{total}")),
+            percentage: Some(percentage),
+        }))
+    }
+
+    /// Asks the client to hide the progress indicator.
+    pub fn end(self) -> Result<()> {
+        self.send(WorkDoneProgress::End(WorkDoneProgressEnd { message: None }))
+    }
+
+    fn send(&self, value: WorkDoneProgress) -> Result<()> {
+        let token = match &self.token {
+            Some(token) => token.clone(),
+            None => return Ok(()),
+        };
+
+        let notification = Notification::new(
+            ProgressNotification::METHOD.to_string(),
+            ProgressParams {
+                token,
+                value: ProgressParamsValue::WorkDone(value),
+            },
+        );
+
+        self.connection.sender.send(notification.into())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_server::Message;
+    use lsp_types::{ClientCapabilities, WindowClientCapabilities};
+
+    fn methods(client: &Connection) -> Vec<String> {
+        client
+            .receiver
+            .try_iter()
+            .map(|message| match message {
+                Message::Request(request) => request.method,
+                Message::Notification(notification) => {
+                    let params = notification.params;
+                    let kind = params["value"]["kind"].as_str().unwrap_or("");
+                    let percentage = &params["value"]["percentage"];
+                    format!("{} {kind} {percentage}", notification.method)
+                }
+                Message::Response(response) => format!("{response:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_progress() {
+        let (server, client) = Connection::memory();
+        #[allow(deprecated)]
+        let mut params = InitializeParams {
+            capabilities: ClientCapabilities {
+                window: Some(WindowClientCapabilities {
+                    work_done_progress: Some(true),
+                    ..WindowClientCapabilities::default()
+                }),
+                ..ClientCapabilities::default()
+            },
+            ..InitializeParams::default()
+        };
+
+        let mut progress =
+            Progress::begin(&server, &params, "Indexing").unwrap();
+        for done in 0..=3 {
+            progress.report(done, 3).unwrap();
+        }
+        progress.report(3, 3).unwrap();
+        progress.end().unwrap();
+
+        assert_eq!(
+            methods(&client),
+            vec![
+                "window/workDoneProgress/create",
+                "$/progress begin 0",
+                "$/progress report 33",
+                "$/progress report 66",
+                "$/progress report 100",
+                "$/progress end null",
+            ]
+        );
+
+        params.capabilities = ClientCapabilities::default();
+        let mut progress =
+            Progress::begin(&server, &params, "Indexing").unwrap();
+        progress.report(1, 1).unwrap();
+        progress.end().unwrap();
+        assert!(methods(&client).is_empty());
+    }
+}
@@ -0,0 +1,339 @@
+use crate::config::Config;
+use helios_parser::printer::FormatterConfig;
+use helios_project::Project;
+use helios_query::{
+    FileId, FileOrigin, HeliosDatabase, SourceRoot, SourceRootId,
+};
+use lsp_types::Url;
+use salsa::{ParallelDatabase, Snapshot};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The state of the language server.
+///
+/// Every document the client opens (and every Helios file found in the
+/// workspace) is stored as a file in the database. Files keep their
+/// [`FileId`] after they're closed, so reopening a document reuses the queries
+/// that were already computed for it.
+///
+/// While a document is open, the client owns its contents: changes made to the
+/// file on disk are ignored until the document is closed again.
+///
+/// Every workspace folder is a source root of the database. Documents outside
+/// of every workspace folder (or that aren't files at all) are part of a
+/// catch-all root instead.
+#[derive(Debug)]
+pub struct State {
+    db: HeliosDatabase,
+    files: Arc<HashMap<Url, FileId>>,
+    uris: Arc<Vec<Url>>,
+    open: HashSet<FileId>,
+    config: Arc<Config>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        let mut db = HeliosDatabase::default();
+        db.add_source_root(SourceRoot::new("", FileOrigin::Workspace));
+
+        Self {
+            db,
+            files: Arc::default(),
+            uris: Arc::default(),
+            open: HashSet::new(),
+            config: Arc::default(),
+        }
+    }
+}
+
+impl State {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The database holding every document known to the server.
+    pub fn db(&self) -> &HeliosDatabase {
+        &self.db
+    }
+
+    /// The user's settings of the server.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Replaces the user's settings of the server.
+    pub fn set_config(&mut self, config: Config) {
+        // Requests being handled with the old settings would be out of date.
+        self.db.request_cancellation();
+        self.db.set_lru_capacity(config.lru.capacity);
+        self.config = Arc::new(config);
+    }
+
+    /// Adds a workspace folder the client opened as a source root, leaving out
+    /// the files excluded by the user's settings.
+    pub fn add_workspace_folder(&mut self, path: PathBuf) -> SourceRootId {
+        let mut root = SourceRoot::new(path, FileOrigin::Workspace);
+        for pattern in &self.config.files.exclude {
+            root = match root.clone().exclude(pattern) {
+                Ok(root) => root,
+                Err(error) => {
+                    tracing::warn!(%pattern, %error, "Ignoring invalid glob");
+                    root
+                }
+            };
+        }
+
+        self.db.add_source_root(root)
+    }
+
+    /// Takes a read-only snapshot of the state that requests can be handled
+    /// with on another thread.
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            db: self.db.snapshot(),
+            files: Arc::clone(&self.files),
+            uris: Arc::clone(&self.uris),
+            config: Arc::clone(&self.config),
+        }
+    }
+
+    /// The file the document with the given URI is stored as, if the document
+    /// is known to the server.
+    pub fn file_id(&self, uri: &Url) -> Option<FileId> {
+        self.files.get(uri).copied()
+    }
+
+    /// The URI of the document stored as the given file.
+    pub fn uri(&self, file_id: FileId) -> Option<&Url> {
+        self.uris.get(file_id.0 as usize)
+    }
+
+    /// Whether or not the document with the given URI is open in the client.
+    pub fn is_open(&self, uri: &Url) -> bool {
+        self.file_id(uri)
+            .is_some_and(|file_id| self.open.contains(&file_id))
+    }
+
+    /// The URIs of the documents that are open in the client.
+    pub fn open_documents(&self) -> impl Iterator<Item = &Url> {
+        self.open.iter().filter_map(|&file_id| self.uri(file_id))
+    }
+
+    /// Stores the text of a document the client opened (or changed).
+    pub fn open_document(&mut self, uri: &Url, text: String) -> FileId {
+        // Requests being handled against the previous text are stale, so they
+        // are cancelled before the edit is applied.
+        self.db.request_cancellation();
+        let file_id = self.set_document(uri, text);
+        self.open.insert(file_id);
+        file_id
+    }
+
+    /// Hands the document with the given URI back to the file system after the
+    /// client closed it, discarding any changes that weren't saved.
+    pub fn close_document(&mut self, uri: &Url) {
+        if let Some(file_id) = self.file_id(uri) {
+            self.open.remove(&file_id);
+            self.reload_file(uri);
+        }
+    }
+
+    /// Stores the given text as the contents of a document, returning the file
+    /// it is stored as.
+    pub fn set_document(&mut self, uri: &Url, text: String) -> FileId {
+        let file_id = match self.files.get(uri) {
+            Some(&file_id) => file_id,
+            None => {
+                let file_id = FileId(self.uris.len() as u32);
+                Arc::make_mut(&mut self.files).insert(uri.clone(), file_id);
+                Arc::make_mut(&mut self.uris).push(uri.clone());
+                file_id
+            }
+        };
+
+        self.db.set_file_at_path(file_id, &document_path(uri), text);
+        file_id
+    }
+
+    /// Reads the document with the given URI from disk again, after it was
+    /// created, changed or deleted outside of the client.
+    ///
+    /// Open documents are left alone. A document that can no longer be read is
+    /// removed from the workspace. Returns whether or not the document is part
+    /// of the workspace afterwards.
+    pub fn reload_file(&mut self, uri: &Url) -> bool {
+        if self.is_open(uri) {
+            return true;
+        }
+
+        let text = uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok());
+
+        match text {
+            Some(text) => {
+                self.set_document(uri, text);
+                true
+            }
+            None => {
+                self.remove_file(uri);
+                false
+            }
+        }
+    }
+
+    /// Removes the document with the given URI from the workspace.
+    fn remove_file(&mut self, uri: &Url) {
+        self.db.remove_file_at_path(&document_path(uri));
+    }
+
+    /// Loads the file at the given path into the workspace, returning the file
+    /// it is stored as if it could be read.
+    pub fn load_file(&mut self, path: &Path) -> Option<FileId> {
+        let uri = Url::from_file_path(path).ok()?;
+        match std::fs::read_to_string(path) {
+            Ok(text) => Some(self.set_document(&uri, text)),
+            Err(error) => {
+                tracing::warn!(path = %path.display(), %error, "Failed to read file");
+                None
+            }
+        }
+    }
+}
+
+/// A read-only snapshot of the [`State`].
+///
+/// The state can't be changed while any snapshot of it is alive. Instead, any
+/// queries running on a snapshot are cancelled (see
+/// [`helios_query::CheckCancelled`]) so that it's dropped as soon as possible.
+#[derive(Debug)]
+pub struct StateSnapshot {
+    db: Snapshot<HeliosDatabase>,
+    files: Arc<HashMap<Url, FileId>>,
+    uris: Arc<Vec<Url>>,
+    config: Arc<Config>,
+}
+
+impl StateSnapshot {
+    /// The database holding every document known to the server.
+    pub fn db(&self) -> &HeliosDatabase {
+        &self.db
+    }
+
+    /// The user's settings of the server.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The file the document with the given URI is stored as, if the document
+    /// is known to the server.
+    pub fn file_id(&self, uri: &Url) -> Option<FileId> {
+        self.files.get(uri).copied()
+    }
+
+    /// The URI of the document stored as the given file.
+    pub fn uri(&self, file_id: FileId) -> Option<&Url> {
+        self.uris.get(file_id.0 as usize)
+    }
+
+    /// The settings to pretty-print the document with the given URI with.
+    ///
+    /// Documents in a project use the `[fmt]` section of its manifest, while
+    /// any other document uses the user's settings.
+    pub fn formatter_config(&self, uri: &Url) -> FormatterConfig {
+        let project = uri
+            .to_file_path()
+            .ok()
+            .map(|path| Project::discover(&path))
+            .transpose();
+
+        match project {
+            Ok(Some(Some(project))) => project.manifest().fmt.clone(),
+            Ok(_) => self.config.formatting.formatter_config(),
+            Err(error) => {
+                tracing::warn!(%error, "Ignoring invalid project manifest");
+                self.config.formatting.formatter_config()
+            }
+        }
+    }
+}
+
+/// The path a document is stored at in the database (its URI if it isn't a
+/// file).
+fn document_path(uri: &Url) -> PathBuf {
+    uri.to_file_path()
+        .unwrap_or_else(|_| PathBuf::from(uri.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helios_query::{Bindings, SourceRoots};
+
+    fn symbol_names(state: &State) -> Vec<String> {
+        let symbols = state.db().workspace_symbols();
+        symbols
+            .iter()
+            .map(|(_, binding)| binding.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_reload_file() {
+        let dir = std::env::temp_dir()
+            .join(format!("helios-ls-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.helios");
+        let uri = Url::from_file_path(&path).unwrap();
+
+        let mut state = State::new();
+        std::fs::write(&path, "let a = 1\n").unwrap();
+        assert!(state.reload_file(&uri));
+        assert_eq!(symbol_names(&state), vec!["a"]);
+
+        // The contents of an open document come from the client.
+        state.open_document(&uri, "let b = 1\n".to_string());
+        std::fs::write(&path, "let c = 1\n").unwrap();
+        assert!(state.reload_file(&uri));
+        assert_eq!(symbol_names(&state), vec!["b"]);
+
+        // Closing the document discards the unsaved changes.
+        state.close_document(&uri);
+        assert!(!state.is_open(&uri));
+        assert_eq!(symbol_names(&state), vec!["c"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(!state.reload_file(&uri));
+        assert!(symbol_names(&state).is_empty());
+        assert_eq!(state.file_id(&uri), Some(FileId(0)));
+    }
+
+    #[test]
+    fn test_add_workspace_folder() {
+        let dir = std::env::temp_dir()
+            .join(format!("helios-ls-folder-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("examples")).unwrap();
+        std::fs::write(dir.join("a.helios"), "let a = 1\n").unwrap();
+        std::fs::write(dir.join("examples/b.helios"), "let b = 1\n").unwrap();
+
+        let mut state = State::new();
+        let mut config = Config::default();
+        config.files.exclude = vec!["examples".to_string(), "[".to_string()];
+        state.set_config(config);
+
+        let root_id = state.add_workspace_folder(dir.clone());
+        for path in state.db().source_root(root_id).find_files() {
+            state.load_file(&path);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(symbol_names(&state), vec!["a"]);
+
+        // Documents outside of every workspace folder are still analyzed.
+        let uri = Url::parse("untitled:Untitled-1").unwrap();
+        state.open_document(&uri, "let c = 1\n".to_string());
+        assert_eq!(symbol_names(&state), vec!["c", "a"]);
+    }
+}
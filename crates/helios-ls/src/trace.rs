@@ -0,0 +1,278 @@
+//! Tracing of the work done by the server.
+//!
+//! Every request is handled in its own span, which records the request's
+//! method and ID, and ends with an event recording how long the request took
+//! and how it turned out. Traces are written to stderr (filtered with the
+//! `RUST_LOG` environment variable, such as `RUST_LOG=helios_ls=debug`) and
+//! can be mirrored to the client with `$/logTrace` notifications, which the
+//! client turns on with the `trace` setting it initializes the server with (or
+//! later on with `$/setTrace`).
+
+use crossbeam_channel::Sender;
+use lsp_server::{Message, Notification};
+use lsp_types::notification::{LogTrace, Notification as _};
+use lsp_types::{LogTraceParams, TraceValue};
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// The client traces are mirrored to.
+static CLIENT: ClientTrace = ClientTrace::new();
+
+/// Sets up tracing for the whole process.
+pub fn init() {
+    let stderr = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .with_filter(EnvFilter::from_default_env());
+
+    tracing_subscriber::registry()
+        .with(stderr)
+        .with(ClientLayer::new(&CLIENT))
+        .init();
+}
+
+/// Starts mirroring traces to the client with the given sender, at the given
+/// level of detail.
+pub fn connect(sender: Sender<Message>, value: TraceValue) {
+    CLIENT.connect(sender, value);
+}
+
+/// Stops mirroring traces to the client.
+///
+/// The sender given to [`connect`] is dropped, which is needed for the
+/// connection to shut down.
+pub fn disconnect() {
+    CLIENT.disconnect();
+}
+
+/// Changes the level of detail of the traces mirrored to the client.
+pub fn set_trace(value: TraceValue) {
+    CLIENT.set_trace(value);
+}
+
+/// Where (and how much of) the traces are mirrored to.
+#[derive(Debug)]
+struct ClientTrace {
+    sender: Mutex<Option<Sender<Message>>>,
+    value: AtomicU8,
+}
+
+impl ClientTrace {
+    const fn new() -> Self {
+        Self {
+            sender: Mutex::new(None),
+            value: AtomicU8::new(0),
+        }
+    }
+
+    fn connect(&self, sender: Sender<Message>, value: TraceValue) {
+        *self.sender.lock().unwrap() = Some(sender);
+        self.set_trace(value);
+    }
+
+    fn disconnect(&self) {
+        *self.sender.lock().unwrap() = None;
+    }
+
+    fn set_trace(&self, value: TraceValue) {
+        let value = match value {
+            TraceValue::Off => 0,
+            TraceValue::Messages => 1,
+            TraceValue::Verbose => 2,
+        };
+
+        self.value.store(value, Ordering::Relaxed);
+    }
+
+    fn trace(&self) -> TraceValue {
+        match self.value.load(Ordering::Relaxed) {
+            0 => TraceValue::Off,
+            1 => TraceValue::Messages,
+            _ => TraceValue::Verbose,
+        }
+    }
+
+    fn send(&self, params: LogTraceParams) {
+        if let Some(sender) = &*self.sender.lock().unwrap() {
+            let notification =
+                Notification::new(LogTrace::METHOD.to_string(), params);
+
+            // The connection may be closing down, in which case nobody is
+            // listening for traces anymore.
+            let _ = sender.send(notification.into());
+        }
+    }
+}
+
+/// A [`Layer`] that mirrors the server's own events to the client.
+///
+/// Only informational events are sent if the client asked for `messages`.
+/// Debug events are sent as well (along with the level and target of every
+/// event) if it asked for `verbose`.
+struct ClientLayer {
+    client: &'static ClientTrace,
+}
+
+impl ClientLayer {
+    fn new(client: &'static ClientTrace) -> Self {
+        Self { client }
+    }
+}
+
+/// The formatted fields of a span, stored in the span's extensions.
+struct SpanFields(String);
+
+impl<S> Layer<S> for ClientLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(
+        &self,
+        attrs: &Attributes<'_>,
+        id: &Id,
+        ctx: Context<'_, S>,
+    ) {
+        let mut fields = FieldVisitor::default();
+        attrs.record(&mut fields);
+
+        if let Some(span) = ctx.span(id) {
+            let name = span.name();
+            let text = format!("{name}{{{}}}", fields.fields);
+            span.extensions_mut().insert(SpanFields(text));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let max_level = match self.client.trace() {
+            TraceValue::Off => return,
+            TraceValue::Messages => Level::INFO,
+            TraceValue::Verbose => Level::DEBUG,
+        };
+
+        if *metadata.level() > max_level
+            || !metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+        {
+            return;
+        }
+
+        let mut fields = FieldVisitor::default();
+        event.record(&mut fields);
+
+        let mut message = String::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(SpanFields(text)) = span.extensions().get() {
+                    let _ = write!(message, "{text}: ");
+                }
+            }
+        }
+        message.push_str(&fields.message);
+        if !fields.fields.is_empty() {
+            let _ = write!(message, " {}", fields.fields);
+        }
+
+        let verbose = (self.client.trace() == TraceValue::Verbose)
+            .then(|| format!("{} {}", metadata.level(), metadata.target()));
+
+        self.client.send(LogTraceParams { message, verbose });
+    }
+}
+
+/// Collects the message and the other fields of an event or span.
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            if !self.fields.is_empty() {
+                self.fields.push(' ');
+            }
+
+            let _ = write!(self.fields, "{}={value:?}", field.name());
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_debug(field, &format_args!("{value}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(value: TraceValue) -> Vec<LogTraceParams> {
+        let client: &'static ClientTrace =
+            Box::leak(Box::new(ClientTrace::new()));
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        client.connect(sender, value);
+
+        let subscriber =
+            tracing_subscriber::registry().with(ClientLayer::new(client));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", method = "hover", id = 1);
+            let _guard = span.enter();
+            tracing::info!(elapsed_ms = 3, outcome = "ok", "Handled request");
+            tracing::debug!("Looking for a token");
+            tracing::trace!("Found nothing");
+        });
+
+        client.disconnect();
+        receiver
+            .try_iter()
+            .map(|message| match message {
+                Message::Notification(notification) => {
+                    notification.extract(LogTrace::METHOD).unwrap()
+                }
+                message => panic!("Unexpected message: {message:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_client_trace() {
+        const HANDLED: &str =
+            "request{method=hover id=1}: Handled request elapsed_ms=3 outcome=ok";
+        const LOOKING: &str = "request{method=hover id=1}: Looking for a token";
+
+        assert!(trace(TraceValue::Off).is_empty());
+
+        assert_eq!(
+            trace(TraceValue::Messages),
+            vec![LogTraceParams {
+                message: HANDLED.to_string(),
+                verbose: None,
+            }]
+        );
+
+        let target = "helios_ls::trace::tests";
+        assert_eq!(
+            trace(TraceValue::Verbose),
+            vec![
+                LogTraceParams {
+                    message: HANDLED.to_string(),
+                    verbose: Some(format!("INFO {target}")),
+                },
+                LogTraceParams {
+                    message: LOOKING.to_string(),
+                    verbose: Some(format!("DEBUG {target}")),
+                },
+            ]
+        );
+    }
+}
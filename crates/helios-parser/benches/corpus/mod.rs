@@ -0,0 +1,72 @@
+//! Generated source files shared by the benchmarks.
+
+use std::fmt::Write;
+
+const OPERATORS: [&str; 10] =
+    ["+", "-", "*", "/", "<", "<=", ">", ">=", "=", "!="];
+
+/// A tiny (but deterministic) pseudo-random number generator, so that the
+/// generated files are the same on every run.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1);
+        ((self.0 >> 33) % n as u64) as usize
+    }
+}
+
+/// Generates a source file with the given number of declarations, which are
+/// representative of real programs: mostly bindings of expressions of various
+/// sizes, along with imports, externals, tests, comments and blank lines.
+///
+/// Different seeds give different files (of the same size).
+pub fn generate(seed: u64, decls: usize) -> String {
+    let mut rng = Rng(seed);
+    let mut source = String::new();
+    for i in 0..decls {
+        match rng.below(10) {
+            0 => writeln!(source, "import module{i}.item").unwrap(),
+            1 => writeln!(source, "external func ext{i}: Int").unwrap(),
+            2 => {
+                let expr = generate_expr(&mut rng, 2);
+                writeln!(source, "test \"case {i}\" = {expr} = {expr}").unwrap()
+            }
+            _ => {
+                if rng.below(4) == 0 {
+                    writeln!(source, "## The value number {i}.").unwrap();
+                }
+                let expr = generate_expr(&mut rng, 4);
+                writeln!(source, "let value{i} = {expr} # binding").unwrap();
+            }
+        }
+
+        if rng.below(3) == 0 {
+            source.push('\n');
+        }
+    }
+
+    source
+}
+
+fn generate_expr(rng: &mut Rng, depth: usize) -> String {
+    match rng.below(6) {
+        _ if depth == 0 => generate_atom(rng),
+        0 | 1 => generate_atom(rng),
+        2 => format!("-({})", generate_expr(rng, depth - 1)),
+        _ => format!(
+            "{} {} {}",
+            generate_expr(rng, depth - 1),
+            OPERATORS[rng.below(OPERATORS.len())],
+            generate_expr(rng, depth - 1)
+        ),
+    }
+}
+
+fn generate_atom(rng: &mut Rng) -> String {
+    match rng.below(3) {
+        0 => rng.below(100_000).to_string(),
+        1 => format!("{}.{}", rng.below(100), rng.below(100)),
+        _ => format!("name{}", rng.below(50)),
+    }
+}
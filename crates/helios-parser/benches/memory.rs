@@ -0,0 +1,84 @@
+//! Measures how much memory the syntax trees of a large generated program
+//! take, with and without sharing their nodes and tokens through a
+//! [`NodeCache`]. Run it with `cargo bench -p helios-parser --bench memory`.
+
+mod corpus;
+
+use helios_parser::{FileId, NodeCache};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of files of the program, and of declarations in every file.
+const FILES: usize = 200;
+const DECLS: usize = 500;
+
+/// Keeps track of how many bytes are allocated at any time.
+struct CountingAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Runs the given function, returning how many more bytes are allocated
+/// afterwards (along with what it returned, so that it stays alive).
+fn retained<T>(f: impl FnOnce() -> T) -> (usize, T) {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let value = f();
+    (ALLOCATED.load(Ordering::Relaxed) - before, value)
+}
+
+fn main() {
+    let sources = (0..FILES)
+        .map(|seed| corpus::generate(seed as u64, DECLS))
+        .collect::<Vec<_>>();
+    let source_len = sources.iter().map(String::len).sum::<usize>();
+
+    let (separate, parses) = retained(|| {
+        sources
+            .iter()
+            .map(|source| helios_parser::parse(FileId::default(), source))
+            .collect::<Vec<_>>()
+    });
+    drop(parses);
+
+    let (shared, parses) = retained(|| {
+        let mut cache = NodeCache::default();
+        let parses = sources
+            .iter()
+            .map(|source| {
+                helios_parser::parse_with_cache(
+                    FileId::default(),
+                    source,
+                    &mut cache,
+                )
+            })
+            .collect::<Vec<_>>();
+        (cache, parses)
+    });
+    drop(parses);
+
+    let mib = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+    println!(
+        "{FILES} files with {DECLS} declarations each ({:.1} MiB of source)",
+        mib(source_len)
+    );
+    println!("separate trees:   {:>7.1} MiB", mib(separate));
+    println!(
+        "shared cache:     {:>7.1} MiB ({:.0}% less)",
+        mib(shared),
+        100.0 * (1.0 - shared as f64 / separate as f64)
+    );
+}
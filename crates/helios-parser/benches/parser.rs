@@ -0,0 +1,66 @@
+//! Benchmarks for the lexer, the indentation processor and the parser.
+//!
+//! Every stage runs on generated source files of a few sizes, so that changes
+//! to how any of them scale show up too. Run them with
+//! `cargo bench -p helios-parser`, and compare them with another revision with
+//! `scripts/bench-compare.sh`.
+
+mod corpus;
+
+use criterion::{
+    criterion_group, criterion_main, BenchmarkId, Criterion, Throughput,
+};
+use helios_parser::{FileId, IndentLayer};
+
+/// The sizes of the generated files, in declarations.
+const SIZES: [(&str, usize); 3] =
+    [("small", 50), ("medium", 1_000), ("large", 20_000)];
+
+fn bench_parser(c: &mut Criterion) {
+    for (name, decls) in SIZES {
+        let source = corpus::generate(decls as u64, decls);
+        let parse = helios_parser::parse(FileId::default(), &source);
+        assert!(parse.messages().is_empty(), "invalid generated file");
+
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        if decls >= 10_000 {
+            group.sample_size(20);
+        }
+
+        group.bench_with_input(
+            BenchmarkId::new("lex", decls),
+            &source,
+            |b, s| b.iter(|| helios_parser::tokenize(FileId::default(), s)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("indent", decls),
+            &source,
+            |b, s| {
+                let (tokens, _) = helios_parser::tokenize(FileId::default(), s);
+                b.iter_batched(
+                    || tokens.clone(),
+                    |tokens| {
+                        IndentLayer::new(
+                            FileId::default(),
+                            s,
+                            tokens.into_iter(),
+                        )
+                        .collect::<Vec<_>>()
+                    },
+                    criterion::BatchSize::LargeInput,
+                )
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("parse", decls),
+            &source,
+            |b, s| b.iter(|| helios_parser::parse(FileId::default(), s)),
+        );
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_parser);
+criterion_main!(benches);
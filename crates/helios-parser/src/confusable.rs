@@ -0,0 +1,66 @@
+//! Detecting Unicode characters that look like the ASCII ones Helios code is
+//! made of.
+//!
+//! Such characters usually end up in source files when code is copied from a
+//! document or a web page, or typed with a keyboard layout that has them (such
+//! as U+037E, the Greek question mark, which looks just like a semicolon). The
+//! lexer reads them as the ASCII character they look like, so that the rest of
+//! the file parses as intended, and reports a warning with a fix replacing
+//! them.
+
+/// A character easily mistaken for an ASCII one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Confusable {
+    /// The ASCII character it looks like.
+    pub intended: char,
+    /// The name of the character, as shown in diagnostics.
+    pub name: &'static str,
+}
+
+/// The characters easily mistaken for an ASCII one, along with the ASCII
+/// character they look like and their names.
+#[rustfmt::skip]
+const CONFUSABLES: &[(char, char, &str)] = &[
+    ('\u{00A0}', ' ', "no-break space"),
+    ('\u{2007}', ' ', "figure space"),
+    ('\u{202F}', ' ', "narrow no-break space"),
+    ('\u{3000}', ' ', "ideographic space"),
+    ('\u{037E}', ';', "Greek question mark"),
+    ('\u{FF1B}', ';', "fullwidth semicolon"),
+    ('\u{FF08}', '(', "fullwidth left parenthesis"),
+    ('\u{FF09}', ')', "fullwidth right parenthesis"),
+    ('\u{FF0C}', ',', "fullwidth comma"),
+    ('\u{FF1A}', ':', "fullwidth colon"),
+    ('\u{FF1D}', '=', "fullwidth equals sign"),
+];
+
+/// Finds the ASCII character the given character may be mistaken for, if any.
+pub fn confusable(c: char) -> Option<Confusable> {
+    CONFUSABLES
+        .iter()
+        .find(|(confusable, _, _)| *confusable == c)
+        .map(|&(_, intended, name)| Confusable { intended, name })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confusable() {
+        assert_eq!(
+            confusable('\u{37e}'),
+            Some(Confusable {
+                intended: ';',
+                name: "Greek question mark",
+            })
+        );
+        assert_eq!(confusable('\u{ff08}').map(|it| it.intended), Some('('));
+        assert_eq!(confusable('\u{a0}').map(|it| it.intended), Some(' '));
+
+        // ASCII characters (and the characters that are valid on their own)
+        // aren't confusable.
+        assert_eq!(confusable(';'), None);
+        assert_eq!(confusable('λ'), None);
+    }
+}
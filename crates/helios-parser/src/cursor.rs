@@ -97,6 +97,12 @@ impl<'source> Cursor<'source> {
         self.source_len() - self.pos()
     }
 
+    /// The unprocessed input.
+    #[inline]
+    pub fn remaining(&self) -> &'source str {
+        self.chars.as_str()
+    }
+
     /// The current position of the cursor.
     #[inline]
     pub fn pos(&self) -> usize {
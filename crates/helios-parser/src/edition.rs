@@ -0,0 +1,84 @@
+//! Editions of the Helios language.
+//!
+//! Reserving a new keyword breaks every program using it as a name, so new
+//! keywords start out as *soft keywords*: in the editions before the one that
+//! reserves them, they're ordinary identifiers everywhere but at the start of
+//! the declarations they introduce (where they can't be mistaken for a name).
+//! Projects opt into the newer edition (and hence into the keywords being
+//! reserved everywhere) once they've renamed whatever clashes with them.
+
+use helios_syntax::SyntaxKind;
+use serde::Deserialize;
+use std::fmt::{self, Display};
+
+/// The keywords that aren't reserved before [`Edition::Edition2026`].
+const SOFT_KEYWORDS: &[SyntaxKind] = &[
+    SyntaxKind::Kwd_External,
+    SyntaxKind::Kwd_Macro,
+    SyntaxKind::Kwd_Test,
+];
+
+/// An edition of the Helios language, which determines which keywords are
+/// reserved.
+///
+/// Editions are written as the year they were introduced in (such as
+/// `edition = "2026"` in a manifest).
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    Hash,
+    Ord,
+    PartialEq,
+    PartialOrd,
+)]
+pub enum Edition {
+    /// The first edition, in which `external`, `macro` and `test` are soft
+    /// keywords.
+    #[default]
+    #[serde(rename = "2025")]
+    Edition2025,
+    /// The edition reserving every keyword everywhere.
+    #[serde(rename = "2026")]
+    Edition2026,
+}
+
+impl Edition {
+    /// The newest edition, which new projects are written in.
+    pub const LATEST: Self = Self::Edition2026;
+
+    /// Whether the given keyword is reserved in this edition, which means the
+    /// lexer never reads it as an identifier.
+    ///
+    /// Keywords that aren't reserved are lexed as identifiers, and the parser
+    /// reads them as keywords where they start a declaration.
+    pub fn is_reserved(self, keyword: SyntaxKind) -> bool {
+        debug_assert!(keyword.is_keyword());
+        self >= Self::Edition2026 || !SOFT_KEYWORDS.contains(&keyword)
+    }
+}
+
+impl Display for Edition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Edition2025 => write!(f, "2025"),
+            Self::Edition2026 => write!(f, "2026"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_reserved() {
+        assert!(Edition::Edition2025.is_reserved(SyntaxKind::Kwd_Let));
+        assert!(!Edition::Edition2025.is_reserved(SyntaxKind::Kwd_Macro));
+        assert!(Edition::Edition2026.is_reserved(SyntaxKind::Kwd_Let));
+        assert!(Edition::Edition2026.is_reserved(SyntaxKind::Kwd_Macro));
+    }
+}
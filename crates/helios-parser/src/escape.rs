@@ -0,0 +1,361 @@
+//! Validating and cooking the escape sequences of string and character
+//! literals.
+//!
+//! The lexer only needs to know where an escape sequence ends to find the end
+//! of a literal, so it accepts a backslash followed by anything. The escape
+//! sequences are instead checked once a literal has been tokenized, so that
+//! every invalid one is reported (rather than just the first of a literal).
+//! Working out what a literal stands for (see [`cook_literal`]) reads its
+//! escape sequences the same way.
+
+use helios_diagnostics::{FileId, Location};
+use helios_syntax::SyntaxKind;
+use std::ops::Range;
+use std::str::CharIndices;
+
+use crate::lexer::{interpolations, Token};
+use crate::message::{LexerMessage, Message};
+
+/// The largest number of digits of a Unicode escape (such as `\u{10FFFF}`).
+const MAX_UNICODE_DIGITS: usize = 6;
+
+/// Checks the escape sequences of the given string or character literal
+/// (outside of any expression embedded in it), returning a message for every
+/// invalid one.
+///
+/// The valid escape sequences are `\\`, `\0`, `\n`, `\t`, `\'` and `\"`, ASCII
+/// escapes made of two hexadecimal digits up to `7F` (such as `\x41`), and
+/// Unicode escapes made of up to six hexadecimal digits in braces (such as
+/// `\u{1F600}`), which must be a Unicode scalar value.
+pub fn validate_escapes(file_id: FileId, token: &Token) -> Vec<Message> {
+    // The expressions embedded in an interpolated string are left to the
+    // parser (along with the literals they hold).
+    let segments = match token.kind {
+        SyntaxKind::Lit_InterpolatedString => interpolations(token),
+        _ => Vec::new(),
+    };
+    let embedded = |offset: usize| {
+        let offset = token.range.start + offset;
+        segments.iter().any(|segment| segment.contains(&offset))
+    };
+
+    let mut messages = Vec::new();
+    let mut chars = token.text.char_indices();
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' || embedded(start) {
+            continue;
+        }
+
+        // A backslash at the end of the literal only happens if it isn't
+        // terminated, which the lexer already reports.
+        let Some(escape) = escape(&mut chars) else {
+            break;
+        };
+
+        if let Err(message) = escape {
+            let end = chars.offset();
+            let range = token.range.start + start..token.range.start + end;
+            let location = Location::new(file_id, range);
+            messages.push(Message::new(message, location));
+        }
+    }
+
+    messages
+}
+
+/// What a string or character literal stands for, along with where its escape
+/// sequences and embedded expressions are (see [`cook_literal`]).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CookedLiteral {
+    /// The text between the quotes of the literal, with its escape sequences
+    /// (and the doubled braces of interpolated strings) replaced by the
+    /// characters they stand for, and without its embedded expressions.
+    ///
+    /// Invalid escape sequences (which are reported when tokenizing) stand for
+    /// U+FFFD, the replacement character.
+    pub value: String,
+    /// The ranges of the escape sequences and doubled braces, relative to the
+    /// start of the literal.
+    pub escapes: Vec<Range<usize>>,
+    /// The expressions embedded in the literal, if it's an interpolated
+    /// string.
+    pub holes: Vec<Hole>,
+}
+
+/// An expression embedded in an interpolated string literal.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Hole {
+    /// The range of the expression (without its braces), relative to the
+    /// start of the literal.
+    pub range: Range<usize>,
+    /// Where the value of the expression goes in the value of the literal, in
+    /// bytes.
+    pub offset: usize,
+}
+
+/// Works out what the given string, interpolated string or character literal
+/// (of the given kind) stands for, or `None` if it's another kind of token.
+///
+/// The literal may be unterminated, in which case it stands for everything
+/// after its opening quote.
+pub fn cook_literal(kind: SyntaxKind, text: &str) -> Option<CookedLiteral> {
+    let (prefix, quote) = match kind {
+        SyntaxKind::Lit_String => ("\"", '"'),
+        SyntaxKind::Lit_InterpolatedString => ("f\"", '"'),
+        SyntaxKind::Lit_Character => ("'", '\''),
+        _ => return None,
+    };
+
+    let token = Token::new(kind, text, 0..text.len());
+    let mut segments = match kind {
+        SyntaxKind::Lit_InterpolatedString => interpolations(&token),
+        _ => Vec::new(),
+    }
+    .into_iter()
+    .peekable();
+
+    let mut literal = CookedLiteral::default();
+    let mut chars = text.char_indices();
+    chars.nth(prefix.len() - 1);
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '\\' => {
+                let Some(escape) = escape(&mut chars) else {
+                    break;
+                };
+
+                literal
+                    .value
+                    .push(escape.unwrap_or(char::REPLACEMENT_CHARACTER));
+                literal.escapes.push(start..chars.offset());
+            }
+            '{' if segments.peek().is_some_and(|s| s.start == start + 1) => {
+                let range = segments.next().unwrap_or_default();
+                literal.holes.push(Hole {
+                    range: range.clone(),
+                    offset: literal.value.len(),
+                });
+
+                // The expression ends at its closing brace, unless the line
+                // ends first.
+                while chars.offset() < range.end {
+                    chars.next();
+                }
+                chars.next();
+            }
+            '{' | '}' if kind == SyntaxKind::Lit_InterpolatedString => {
+                if peek(&chars) == Some(c) {
+                    chars.next();
+                    literal.escapes.push(start..chars.offset());
+                }
+                literal.value.push(c);
+            }
+            c if c == quote => break,
+            c => literal.value.push(c),
+        }
+    }
+
+    Some(literal)
+}
+
+/// Reads an escape sequence (after its backslash), returning the character
+/// it stands for, or `None` if the literal ends first.
+fn escape(chars: &mut CharIndices) -> Option<Result<char, LexerMessage>> {
+    let (_, escape) = chars.next()?;
+    let c = match escape {
+        '\\' | '\'' | '"' => Ok(escape),
+        '0' => Ok('\0'),
+        'n' => Ok('\n'),
+        't' => Ok('\t'),
+        'x' => ascii_escape(chars),
+        'u' => unicode_escape(chars),
+        _ => Err(LexerMessage::UnknownEscape(escape)),
+    };
+
+    Some(c)
+}
+
+/// Reads the digits of an ASCII escape (after its `\x`).
+fn ascii_escape(chars: &mut CharIndices) -> Result<char, LexerMessage> {
+    let mut value = 0;
+    for _ in 0..2 {
+        let digit = peek(chars).and_then(|c| c.to_digit(16));
+        let Some(digit) = digit else {
+            return Err(LexerMessage::InvalidAsciiEscape);
+        };
+
+        chars.next();
+        value = value * 16 + digit;
+    }
+
+    match char::from_u32(value) {
+        Some(c) if c.is_ascii() => Ok(c),
+        _ => Err(LexerMessage::InvalidAsciiEscape),
+    }
+}
+
+/// Reads the braces and digits of a Unicode escape (after its `\u`).
+fn unicode_escape(chars: &mut CharIndices) -> Result<char, LexerMessage> {
+    if peek(chars) != Some('{') {
+        return Err(LexerMessage::InvalidUnicodeEscape);
+    }
+    chars.next();
+
+    let mut digits = 0;
+    let mut value = 0u32;
+    while let Some(digit) = peek(chars).and_then(|c| c.to_digit(16)) {
+        chars.next();
+        digits += 1;
+        value = value.saturating_mul(16).saturating_add(digit);
+    }
+
+    if peek(chars) != Some('}') {
+        return Err(LexerMessage::InvalidUnicodeEscape);
+    }
+    chars.next();
+
+    match (digits, char::from_u32(value)) {
+        (1..=MAX_UNICODE_DIGITS, Some(c)) => Ok(c),
+        _ => Err(LexerMessage::InvalidUnicodeEscape),
+    }
+}
+
+/// Peeks the next character without consuming it.
+fn peek(chars: &CharIndices) -> Option<char> {
+    chars.clone().next().map(|(_, c)| c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(input: &str, expected: Vec<(&str, std::ops::Range<usize>)>) {
+        let (tokens, _) = crate::tokenize(FileId(0), input);
+        let token = &tokens[0];
+        assert!(matches!(
+            token.kind,
+            SyntaxKind::Lit_Character
+                | SyntaxKind::Lit_InterpolatedString
+                | SyntaxKind::Lit_String
+        ));
+
+        let messages = validate_escapes(FileId(0), token)
+            .into_iter()
+            .map(|message| {
+                let diagnostic = message.generate_diagnostic();
+                (diagnostic.title, diagnostic.location.range)
+            })
+            .collect::<Vec<_>>();
+        let expected = expected
+            .into_iter()
+            .map(|(title, range)| (title.to_string(), range))
+            .collect::<Vec<_>>();
+        assert_eq!(messages, expected);
+    }
+
+    #[test]
+    fn test_valid_escapes() {
+        check(r#""\\ \0 \n \t \' \" \x41 \x7f \u{0} \u{1F600}""#, vec![]);
+        check(r"'\u{10FFFF}'", vec![]);
+        check(r"'\x00'", vec![]);
+
+        // The lexer reports unterminated literals.
+        check(r#""abc\"#, vec![]);
+    }
+
+    #[test]
+    fn test_interpolated_strings() {
+        check(r#"f"\n{a}\t{{\"}}""#, vec![]);
+        check(
+            r#"f"\q{"\q"}\q""#,
+            vec![
+                ("Unknown escape sequence", 2..4),
+                ("Unknown escape sequence", 10..12),
+            ],
+        );
+    }
+
+    fn cook(input: &str) -> CookedLiteral {
+        let (tokens, _) = crate::tokenize(FileId(0), input);
+        cook_literal(tokens[0].kind, tokens[0].text).unwrap()
+    }
+
+    #[test]
+    fn test_cook_literal() {
+        let literal = cook(r#""a\n\"b\u{1F600}""#);
+        assert_eq!(literal.value, "a\n\"b\u{1F600}");
+        assert_eq!(literal.escapes, vec![2..4, 4..6, 7..16]);
+        assert!(literal.holes.is_empty());
+
+        assert_eq!(cook(r"'\x41'").value, "A");
+        assert_eq!(cook(r"'\''").escapes, vec![1..3]);
+
+        // Invalid escapes stand for the replacement character, and
+        // unterminated literals for the rest of their line.
+        assert_eq!(cook(r#""\q\x80\u{D800}""#).value, "\u{FFFD}".repeat(3));
+        assert_eq!(cook("\"abc\n").value, "abc");
+        assert_eq!(cook("\"abc\\").value, "abc");
+
+        assert_eq!(cook_literal(SyntaxKind::Lit_Integer, "1"), None);
+    }
+
+    #[test]
+    fn test_cook_interpolated_string() {
+        let literal = cook(r#"f"a{b}\t{{{"}"}}}c""#);
+        assert_eq!(literal.value, "a\t{}c");
+        assert_eq!(literal.escapes, vec![6..8, 8..10, 15..17]);
+        assert_eq!(
+            literal.holes,
+            vec![
+                Hole {
+                    range: 4..5,
+                    offset: 1,
+                },
+                Hole {
+                    range: 11..14,
+                    offset: 3,
+                },
+            ]
+        );
+
+        // An expression the line ends in goes up to the end of the literal.
+        let literal = cook("f\"a{b\n");
+        assert_eq!(literal.value, "a");
+        assert_eq!(literal.holes[0].range, 4..5);
+    }
+
+    #[test]
+    fn test_unknown_escapes() {
+        check(r#""a\qb""#, vec![("Unknown escape sequence", 2..4)]);
+        check(r"'\é'", vec![("Unknown escape sequence", 1..4)]);
+        check(
+            r#""\r \a""#,
+            vec![
+                ("Unknown escape sequence", 1..3),
+                ("Unknown escape sequence", 4..6),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_invalid_ascii_escapes() {
+        check(r#""\x""#, vec![("Invalid ASCII escape", 1..3)]);
+        check(r#""\x4""#, vec![("Invalid ASCII escape", 1..4)]);
+        check(r#""\xg1""#, vec![("Invalid ASCII escape", 1..3)]);
+        check(r#""\x80""#, vec![("Invalid ASCII escape", 1..5)]);
+
+        // Only two digits are part of the escape.
+        check(r#""\x414""#, vec![]);
+    }
+
+    #[test]
+    fn test_invalid_unicode_escapes() {
+        check(r#""\u00""#, vec![("Invalid Unicode escape", 1..3)]);
+        check(r#""\u{}""#, vec![("Invalid Unicode escape", 1..5)]);
+        check(r#""\u{1F6""#, vec![("Invalid Unicode escape", 1..7)]);
+        check(r#""\u{1F6g}""#, vec![("Invalid Unicode escape", 1..7)]);
+        check(r#""\u{1000000}""#, vec![("Invalid Unicode escape", 1..12)]);
+        check(r#""\u{110000}""#, vec![("Invalid Unicode escape", 1..11)]);
+        check(r#""\u{D800}""#, vec![("Invalid Unicode escape", 1..9)]);
+    }
+}
@@ -8,10 +8,7 @@ use helios_syntax::SyntaxKind;
 use crate::parser::marker::CompletedMarker;
 use crate::parser::Parser;
 
-pub(crate) fn root<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
-where
-    FileId: Clone + Default,
-{
+pub(crate) fn root(p: &mut Parser) -> CompletedMarker {
     let m = p.start();
 
     while !p.is_at_end() {
@@ -1,20 +1,27 @@
 use super::*;
 
-pub(super) fn decl<FileId>(p: &mut Parser<FileId>) -> Option<CompletedMarker>
-where
-    FileId: Clone + Default,
-{
+pub(super) fn decl(p: &mut Parser) -> Option<CompletedMarker> {
     if p.is_at(SyntaxKind::Kwd_Let) {
         Some(global_binding(p))
+    } else if p.is_at(SyntaxKind::Kwd_Import) {
+        Some(import(p))
+    } else if p
+        .is_at_soft_keyword(SyntaxKind::Kwd_External, SyntaxKind::Kwd_Func)
+    {
+        Some(external(p))
+    } else if p.is_at_soft_keyword(SyntaxKind::Kwd_Test, SyntaxKind::Lit_String)
+    {
+        Some(test(p))
+    } else if p
+        .is_at_soft_keyword(SyntaxKind::Kwd_Macro, SyntaxKind::Identifier)
+    {
+        Some(macro_decl(p))
     } else {
         expr::expr(p, 0)
     }
 }
 
-fn global_binding<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
-where
-    FileId: Clone + Default,
-{
+fn global_binding(p: &mut Parser) -> CompletedMarker {
     assert!(p.is_at(SyntaxKind::Kwd_Let));
     let m = p.start();
     p.bump();
@@ -23,14 +30,81 @@ where
     p.expect(SyntaxKind::Sym_Eq, SyntaxKind::Dec_GlobalBinding);
 
     expr::expr(p, 0);
-    p.expect(SyntaxKind::Newline, SyntaxKind::Dec_GlobalBinding);
 
     m.complete(p, SyntaxKind::Dec_GlobalBinding)
 }
 
+/// Parses an import declaration, such as `import foo.bar`.
+///
+/// The imported module is named by one or more identifiers separated by dots.
+fn import(p: &mut Parser) -> CompletedMarker {
+    assert!(p.is_at(SyntaxKind::Kwd_Import));
+    let m = p.start();
+    p.bump();
+
+    p.expect(SyntaxKind::Identifier, SyntaxKind::Dec_Import);
+    while p.is_at(SyntaxKind::Sym_Dot) {
+        p.bump();
+        p.expect(SyntaxKind::Identifier, SyntaxKind::Dec_Import);
+    }
+
+    m.complete(p, SyntaxKind::Dec_Import)
+}
+
+/// Parses an external function declaration, such as `external func now: Int`.
+///
+/// The function is provided by the host the program runs on, so only its name
+/// and the type of the value it returns are declared.
+fn external(p: &mut Parser) -> CompletedMarker {
+    let m = p.start();
+    p.bump_as(SyntaxKind::Kwd_External);
+
+    p.expect(SyntaxKind::Kwd_Func, SyntaxKind::Dec_External);
+    p.expect(SyntaxKind::Identifier, SyntaxKind::Dec_External);
+    p.expect(SyntaxKind::Sym_Colon, SyntaxKind::Dec_External);
+    p.expect(SyntaxKind::Identifier, SyntaxKind::Dec_External);
+
+    m.complete(p, SyntaxKind::Dec_External)
+}
+
+/// Parses a test declaration, such as `test "addition" = 1 + 1 == 2`.
+///
+/// The test is named by a string literal, and passes if its expression
+/// evaluates to `true`.
+fn test(p: &mut Parser) -> CompletedMarker {
+    let m = p.start();
+    p.bump_as(SyntaxKind::Kwd_Test);
+
+    p.expect(SyntaxKind::Lit_String, SyntaxKind::Dec_Test);
+    p.expect(SyntaxKind::Sym_Eq, SyntaxKind::Dec_Test);
+
+    expr::expr(p, 0);
+
+    m.complete(p, SyntaxKind::Dec_Test)
+}
+
+/// Parses a macro declaration, such as `macro twice x = x + x`.
+///
+/// The macro is named by the first identifier, which is followed by the names
+/// of its parameters (if any) and by the expression its calls expand to.
+fn macro_decl(p: &mut Parser) -> CompletedMarker {
+    let m = p.start();
+    p.bump_as(SyntaxKind::Kwd_Macro);
+
+    p.expect(SyntaxKind::Identifier, SyntaxKind::Dec_Macro);
+    while p.is_at(SyntaxKind::Identifier) {
+        p.bump();
+    }
+    p.expect(SyntaxKind::Sym_Eq, SyntaxKind::Dec_Macro);
+
+    expr::expr(p, 0);
+
+    m.complete(p, SyntaxKind::Dec_Macro)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::check;
+    use crate::{check, Edition, FileId};
     use expect_test::expect;
 
     #[test]
@@ -51,4 +125,175 @@ mod tests {
             "#]],
         );
     }
+
+    #[test]
+    fn test_parse_multiple_global_binding_declarations() {
+        let parse =
+            crate::parse(FileId(0), "let a = 1\nlet b = a\n\nlet c = 2\n");
+        assert!(parse.messages().is_empty(), "{:?}", parse.messages());
+        check(
+            "let a = 1\nlet b = a\n",
+            expect![[r#"
+                Root@0..20
+                  Dec_GlobalBinding@0..10
+                    Kwd_Let@0..3 "let"
+                    Whitespace@3..4 " "
+                    Identifier@4..5 "a"
+                    Whitespace@5..6 " "
+                    Sym_Eq@6..7 "="
+                    Whitespace@7..8 " "
+                    Exp_Literal@8..10
+                      Lit_Integer@8..9 "1"
+                      Newline@9..10 "\n"
+                  Dec_GlobalBinding@10..20
+                    Kwd_Let@10..13 "let"
+                    Whitespace@13..14 " "
+                    Identifier@14..15 "b"
+                    Whitespace@15..16 " "
+                    Sym_Eq@16..17 "="
+                    Whitespace@17..18 " "
+                    Exp_VariableRef@18..20
+                      Identifier@18..19 "a"
+                      Newline@19..20 "\n"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_import_declaration() {
+        check(
+            "import foo.bar",
+            expect![[r#"
+                Root@0..14
+                  Dec_Import@0..14
+                    Kwd_Import@0..6 "import"
+                    Whitespace@6..7 " "
+                    Identifier@7..10 "foo"
+                    Sym_Dot@10..11 "."
+                    Identifier@11..14 "bar"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_import_declaration_with_missing_identifier() {
+        check(
+            "import foo.",
+            expect![[r#"
+                Root@0..11
+                  Dec_Import@0..11
+                    Kwd_Import@0..6 "import"
+                    Whitespace@6..7 " "
+                    Identifier@7..10 "foo"
+                    Sym_Dot@10..11 "."
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_external_declaration() {
+        check(
+            "external func now: Int",
+            expect![[r#"
+                Root@0..22
+                  Dec_External@0..22
+                    Kwd_External@0..8 "external"
+                    Whitespace@8..9 " "
+                    Kwd_Func@9..13 "func"
+                    Whitespace@13..14 " "
+                    Identifier@14..17 "now"
+                    Sym_Colon@17..18 ":"
+                    Whitespace@18..19 " "
+                    Identifier@19..22 "Int"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_external_declaration_with_missing_type() {
+        check(
+            "external func now",
+            expect![[r#"
+                Root@0..17
+                  Dec_External@0..17
+                    Kwd_External@0..8 "external"
+                    Whitespace@8..9 " "
+                    Kwd_Func@9..13 "func"
+                    Whitespace@13..14 " "
+                    Identifier@14..17 "now"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_macro_declaration() {
+        check(
+            "macro add x y = x + y",
+            expect![[r#"
+                Root@0..21
+                  Dec_Macro@0..21
+                    Kwd_Macro@0..5 "macro"
+                    Whitespace@5..6 " "
+                    Identifier@6..9 "add"
+                    Whitespace@9..10 " "
+                    Identifier@10..11 "x"
+                    Whitespace@11..12 " "
+                    Identifier@12..13 "y"
+                    Whitespace@13..14 " "
+                    Sym_Eq@14..15 "="
+                    Whitespace@15..16 " "
+                    Exp_Binary@16..21
+                      Exp_VariableRef@16..18
+                        Identifier@16..17 "x"
+                        Whitespace@17..18 " "
+                      Sym_Plus@18..19 "+"
+                      Whitespace@19..20 " "
+                      Exp_VariableRef@20..21
+                        Identifier@20..21 "y"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_test_declaration() {
+        check(
+            r#"test "one" = 1"#,
+            expect![[r#"
+                Root@0..14
+                  Dec_Test@0..14
+                    Kwd_Test@0..4 "test"
+                    Whitespace@4..5 " "
+                    Lit_String@5..10 "\"one\""
+                    Whitespace@10..11 " "
+                    Sym_Eq@11..12 "="
+                    Whitespace@12..13 " "
+                    Exp_Literal@13..14
+                      Lit_Integer@13..14 "1"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_soft_keywords() {
+        let parse = |source, edition| {
+            let cache = &mut crate::NodeCache::default();
+            crate::parse_with_edition(FileId(0), source, edition, cache)
+        };
+
+        // Declarations look the same whether their keywords are reserved or
+        // not.
+        let source = "macro id x = x\ntest \"a\" = 1\nexternal func f: Int\n";
+        let tree = parse(source, Edition::Edition2025).debug_tree();
+        assert_eq!(tree, parse(source, Edition::Edition2026).debug_tree());
+        assert!(!tree.contains("Error"));
+
+        // Anywhere else, soft keywords are names in the first edition.
+        // That includes the start of a line, unless what follows on the same
+        // line makes it a declaration.
+        let source = "let test = 1\nlet macro = test\nmacro\ntest\n";
+        let parse_2025 = parse(source, Edition::Edition2025);
+        assert_eq!(parse_2025.messages(), []);
+        assert!(!parse_2025.debug_tree().contains("Dec_Macro"));
+        assert!(!parse(source, Edition::Edition2026).messages().is_empty());
+    }
 }
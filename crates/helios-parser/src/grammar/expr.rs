@@ -44,13 +44,7 @@ fn infix_binding_power(kind: SyntaxKind) -> (u8, u8) {
 }
 
 /// Parses an expression.
-pub(super) fn expr<FileId>(
-    p: &mut Parser<FileId>,
-    min_bp: u8,
-) -> Option<CompletedMarker>
-where
-    FileId: Clone + Default,
-{
+pub(super) fn expr(p: &mut Parser, min_bp: u8) -> Option<CompletedMarker> {
     let mut lhs = lhs(p)?;
 
     // Continuously build expressions if the next token is an infix operator
@@ -77,27 +71,26 @@ where
     Some(lhs)
 }
 
+// String literals only name tests for now, so they can't start an expression.
 const LHS_KINDS: &[SyntaxKind] = &[
     SyntaxKind::Lit_Character,
     SyntaxKind::Lit_Float,
     SyntaxKind::Lit_Integer,
-    SyntaxKind::Lit_String,
     SyntaxKind::Identifier,
     SyntaxKind::Sym_LParen,
 ];
 
 /// Parses the left-hand side of an expression.
-fn lhs<FileId>(p: &mut Parser<FileId>) -> Option<CompletedMarker>
-where
-    FileId: Clone + Default,
-{
+fn lhs(p: &mut Parser) -> Option<CompletedMarker> {
     let lhs_kinds_or_prefix_ops = &[LHS_KINDS, PREFIX_OPS].concat();
 
     // We'll check if the next `SyntaxKind` can start a LHS expression (either
     // any of `LHS_KINDS` or `PREFIX_OPS`)
     let cm = if let Some(kind) = p.is_at_either(lhs_kinds_or_prefix_ops) {
         match kind {
-            SyntaxKind::Lit_Integer | SyntaxKind::Lit_Float => literal(p),
+            SyntaxKind::Lit_Character
+            | SyntaxKind::Lit_Integer
+            | SyntaxKind::Lit_Float => literal(p),
             SyntaxKind::Identifier => variable_ref(p),
             SyntaxKind::Sym_LParen => paren_expr(p),
             SyntaxKind::Indent => indented_expr(p),
@@ -113,35 +106,53 @@ where
 }
 
 /// Parses a literal that may stand alone as an expression.
-fn literal<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
-where
-    FileId: Clone + Default,
-{
+fn literal(p: &mut Parser) -> CompletedMarker {
     use SyntaxKind::*;
-    assert!(p.is_at(Lit_Integer) || p.is_at(Lit_Float));
+    assert!(
+        p.is_at(Lit_Character) || p.is_at(Lit_Integer) || p.is_at(Lit_Float)
+    );
 
     let m = p.start();
     p.bump();
     m.complete(p, Exp_Literal)
 }
 
-/// Parses an identifier as a variable reference.
-fn variable_ref<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
-where
-    FileId: Clone + Default,
-{
+/// Parses an identifier as a variable reference, or as the name of the macro
+/// called if a `!` follows it right away (such as `twice!(a)`).
+fn variable_ref(p: &mut Parser) -> CompletedMarker {
     assert!(p.is_at(SyntaxKind::Identifier));
 
     let m = p.start();
     p.bump();
+
+    if p.is_right_at(SyntaxKind::Sym_Bang) {
+        macro_call(p);
+        return m.complete(p, SyntaxKind::Exp_MacroCall);
+    }
+
     m.complete(p, SyntaxKind::Exp_VariableRef)
 }
 
+/// Parses the `!` and the parenthesized arguments (separated by commas) of a
+/// macro call, whose name was just consumed.
+fn macro_call(p: &mut Parser) {
+    assert!(p.is_at(SyntaxKind::Sym_Bang));
+    p.bump();
+
+    p.expect(SyntaxKind::Sym_LParen, SyntaxKind::Exp_MacroCall);
+    if !p.is_at(SyntaxKind::Sym_RParen) {
+        expr(p, 0);
+        while p.is_at(SyntaxKind::Sym_Comma) {
+            p.bump();
+            expr(p, 0);
+        }
+    }
+
+    p.expect(SyntaxKind::Sym_RParen, SyntaxKind::Exp_MacroCall);
+}
+
 /// Parses a unary expression with a prefixed operator.
-fn unary_prefix_expr<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
-where
-    FileId: Clone + Default,
-{
+fn unary_prefix_expr(p: &mut Parser) -> CompletedMarker {
     let m = p.start();
 
     // Get the right binding power of the operator
@@ -156,10 +167,7 @@ where
 }
 
 /// Parses an expression surrounded by parenthesis.
-fn paren_expr<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
-where
-    FileId: Clone + Default,
-{
+fn paren_expr(p: &mut Parser) -> CompletedMarker {
     assert!(p.is_at(SyntaxKind::Sym_LParen));
 
     let m = p.start();
@@ -175,10 +183,7 @@ where
 }
 
 /// Parses an indented expression surrounded by `Indent` and `Dedent` tokens.
-fn indented_expr<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
-where
-    FileId: Clone + Default,
-{
+fn indented_expr(p: &mut Parser) -> CompletedMarker {
     assert!(p.is_at(SyntaxKind::Indent));
 
     let m = p.start();
@@ -195,7 +200,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::check;
+    use crate::{check, FileId};
     use expect_test::expect;
 
     #[test]
@@ -210,6 +215,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_lone_character() {
+        check(
+            r"'\n'",
+            expect![[r#"
+                Root@0..4
+                  Exp_Literal@0..4
+                    Lit_Character@0..4 "'\\n'"
+            "#]],
+        );
+    }
+
     #[test]
     fn test_parse_lone_identifier() {
         check(
@@ -222,6 +239,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_macro_call() {
+        check(
+            "twice!(1, a)",
+            expect![[r#"
+                Root@0..12
+                  Exp_MacroCall@0..12
+                    Identifier@0..5 "twice"
+                    Sym_Bang@5..6 "!"
+                    Sym_LParen@6..7 "("
+                    Exp_Literal@7..8
+                      Lit_Integer@7..8 "1"
+                    Sym_Comma@8..9 ","
+                    Whitespace@9..10 " "
+                    Exp_VariableRef@10..11
+                      Identifier@10..11 "a"
+                    Sym_RParen@11..12 ")"
+            "#]],
+        );
+
+        // The `!` has to follow the name right away.
+        check(
+            "twice !(a)",
+            expect![[r#"
+                Root@0..10
+                  Exp_VariableRef@0..6
+                    Identifier@0..5 "twice"
+                    Whitespace@5..6 " "
+                  Exp_UnaryPrefix@6..10
+                    Sym_Bang@6..7 "!"
+                    Exp_Paren@7..10
+                      Sym_LParen@7..8 "("
+                      Exp_VariableRef@8..9
+                        Identifier@8..9 "a"
+                      Sym_RParen@9..10 ")"
+            "#]],
+        );
+    }
+
     #[test]
     fn test_unary_prefix_expression_simple() {
         check(
@@ -427,7 +483,7 @@ mod tests {
         let source = "
 1 + 1 +
     10";
-        let tree = crate::parse(0, source);
+        let tree = crate::parse(FileId(0), source);
         println!("{}", tree.debug_tree());
     }
 
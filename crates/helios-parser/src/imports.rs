@@ -0,0 +1,203 @@
+//! Organizing the imports of Helios source files.
+//!
+//! Imports are sorted by their paths (comparing one name at a time, so
+//! `import a.b` comes before `import a_b`), and imports of the same path are
+//! merged into one. Only groups of imports on consecutive lines are
+//! organized: a blank line, a comment or any other declaration between two
+//! imports starts a new group, so that comments stay next to the imports
+//! they're about. Invalid imports (such as `import a.`) and imports followed
+//! by a comment on the same line are left where they are.
+
+use helios_syntax::{SyntaxKind, SyntaxNode};
+use std::ops::Range;
+
+use crate::printer::{apply_edits, Edit};
+
+/// An import that may be moved around: the range of its text (without the
+/// trivia after it) and the names of its path.
+struct Import {
+    range: Range<usize>,
+    path: Vec<String>,
+}
+
+/// Organizes the imports of the given syntax tree.
+pub fn organize_imports(root: &SyntaxNode) -> String {
+    let source = root.text().to_string();
+    apply_edits(&source, organize_imports_edits(root))
+}
+
+/// Works out the edits needed to organize the imports of the given syntax
+/// tree, with one edit per group of imports that needs to change.
+///
+/// The edits are sorted by their position in the source text and never
+/// overlap.
+pub fn organize_imports_edits(root: &SyntaxNode) -> Vec<Edit> {
+    let source = root.text().to_string();
+    let mut edits = Vec::new();
+    let mut group: Vec<Import> = Vec::new();
+
+    for node in root.children() {
+        let import = match node.kind() {
+            SyntaxKind::Dec_Import => import(&node, &source),
+            _ => None,
+        };
+
+        let Some(import) = import else {
+            edits.extend(organize(&std::mem::take(&mut group)));
+            continue;
+        };
+
+        let next_line = group.last().is_some_and(|last| {
+            let gap = &source[last.range.end..import.range.start];
+            gap.trim_start_matches([' ', '\t', '\r']) == "\n"
+        });
+        if !next_line {
+            edits.extend(organize(&std::mem::take(&mut group)));
+        }
+        group.push(import);
+    }
+
+    edits.extend(organize(&group));
+    edits
+}
+
+/// Finds the path of the given import declaration, unless the import is
+/// invalid or doesn't have a line of its own (such as if it's followed by a
+/// comment, which would otherwise end up next to another import).
+fn import(node: &SyntaxNode, source: &str) -> Option<Import> {
+    let start = usize::from(node.text_range().start());
+    if start > 0 && !source[..start].ends_with('\n') {
+        return None;
+    }
+
+    let tokens = node
+        .children_with_tokens()
+        .filter(|element| !element.kind().is_trivia())
+        .map(|element| element.into_token())
+        .collect::<Option<Vec<_>>>()?;
+    let (keyword, tokens) = tokens.split_first()?;
+    if keyword.kind() != SyntaxKind::Kwd_Import || tokens.len() % 2 == 0 {
+        return None;
+    }
+
+    // The path alternates between names and dots, starting and ending with a
+    // name.
+    let mut path = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        match (i % 2, token.kind()) {
+            (0, SyntaxKind::Identifier) => path.push(token.text().to_string()),
+            (1, SyntaxKind::Sym_Dot) => {}
+            _ => return None,
+        }
+    }
+
+    let end = usize::from(tokens.last()?.text_range().end());
+    let rest_of_line = source[end..].split('\n').next().unwrap_or_default();
+    rest_of_line.trim().is_empty().then_some(Import {
+        range: start..end,
+        path,
+    })
+}
+
+/// Works out the edit needed to sort (and remove the duplicates of) the given
+/// group of imports, unless it's already organized.
+fn organize(group: &[Import]) -> Option<Edit> {
+    let (first, last) = (group.first()?, group.last()?);
+
+    let mut paths = group.iter().map(|import| &import.path).collect::<Vec<_>>();
+    paths.sort();
+    paths.dedup();
+
+    let organized = paths.len() == group.len()
+        && paths
+            .iter()
+            .zip(group)
+            .all(|(path, import)| **path == import.path);
+    if organized {
+        return None;
+    }
+
+    let text = paths
+        .into_iter()
+        .map(|path| format!("import {}", path.join(".")))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(Edit {
+        range: first.range.start..last.range.end,
+        text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileId;
+    use expect_test::{expect, Expect};
+
+    fn check(input: &str, expected: Expect) {
+        let root = crate::parse(FileId(0), input).syntax();
+        let organized = organize_imports(&root);
+        expected.assert_eq(&organized);
+
+        // Organizing imports should be idempotent.
+        let root = crate::parse(FileId(0), &organized).syntax();
+        assert_eq!(organize_imports_edits(&root), Vec::new());
+    }
+
+    #[test]
+    fn test_organize_imports() {
+        check(
+            "import math.vector\nimport io\nimport  math\nimport io\n",
+            expect![[r#"
+                import io
+                import math
+                import math.vector
+            "#]],
+        );
+
+        // Names are compared one at a time.
+        check(
+            "import a_b\nimport a.b\n",
+            expect![[r#"
+                import a.b
+                import a_b
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_organize_import_groups() {
+        // Blank lines, comments and other declarations separate groups.
+        check(
+            "import c\nimport b\n\nimport a\n# why\nimport z\nimport y # how\n\
+             import x\nlet a = 1\nimport w\nimport v\n",
+            expect![[r#"
+                import b
+                import c
+
+                import a
+                # why
+                import z
+                import y # how
+                import x
+                let a = 1
+                import v
+                import w
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_organize_invalid_imports() {
+        // Invalid imports are left where they are.
+        check(
+            "import c\nimport b.\nimport a\nimport z\n",
+            expect![[r#"
+                import c
+                import b.
+                import a
+                import z
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,475 @@
+//! Processing the indentation of Helios source files.
+//!
+//! The [`Lexer`] only emits a `Newline` token (holding the line break, which
+//! is either `\n` or `\r\n`, and the spaces after it) at the start of every
+//! line. The parser however relies on `Indent` and `Dedent` tokens to know
+//! where blocks start and end, which the [`IndentLayer`] works out as it goes
+//! through the tokens of the lexer.
+//!
+//! [`Lexer`]: crate::lexer::Lexer
+
+use helios_diagnostics::{FileId, Location};
+use helios_syntax::SyntaxKind;
+use std::collections::VecDeque;
+
+use crate::lexer::{LexerItem, Token};
+use crate::message::{LexerMessage, Message};
+
+/// The number of columns a tab in the indentation of a line goes to the next
+/// multiple of.
+pub const TAB_WIDTH: usize = 4;
+
+/// The width of the given indentation, in columns.
+pub(crate) fn indent_width(indent: &str) -> usize {
+    indent.chars().fold(0, |width, c| match c {
+        '\t' => (width / TAB_WIDTH + 1) * TAB_WIDTH,
+        _ => width + 1,
+    })
+}
+
+/// A lazy adapter turning the `Newline` tokens of a token stream into `Indent`
+/// and `Dedent` tokens wherever the indentation of a line changes.
+///
+/// Every line indented by more spaces than the one before it starts with an
+/// `Indent` token, and every line indented by fewer spaces starts with as many
+/// `Dedent` tokens as the number of blocks it closes. Only the last of these
+/// holds the text of the newline, the others are empty. Any blocks still open
+/// at the end of the input are closed with empty `Dedent` tokens.
+///
+/// Blank lines and lines holding nothing but a comment never change the
+/// indentation. A run of blank lines is coalesced into the newline before it,
+/// so there is only ever one newline (or indentation token) between two lines
+/// that aren't blank (not counting the comments of any line in between).
+///
+/// Only spaces indent lines: a tab in the indentation of a line is reported as
+/// an error, and so is a line that isn't lined up with any of the blocks it's
+/// dedented to (in which case it's treated as if it was lined up with the
+/// closest block indented by fewer spaces). So that lines indented with tabs
+/// (or a mix of tabs and spaces) still make up the blocks they look like they
+/// do, tabs go to the next multiple of [`TAB_WIDTH`] columns, as they do in
+/// most editors.
+pub struct IndentLayer<'source, I>
+where
+    I: Iterator<Item = Token<'source>>,
+{
+    file_id: FileId,
+    source: &'source str,
+    tokens: I,
+    /// The tokens read ahead of time (while looking for the end of a line),
+    /// which come before any other token of the stream.
+    lookahead: VecDeque<Token<'source>>,
+    /// The tokens (and messages) that are ready to be yielded.
+    pending: VecDeque<LexerItem<'source>>,
+    /// The indentation of every block open, from the outermost block (which
+    /// isn't indented) to the innermost one.
+    indent_stack: Vec<usize>,
+    /// The end of the last token, where the last `Dedent` tokens go.
+    end: usize,
+}
+
+impl<'source, I> IndentLayer<'source, I>
+where
+    I: Iterator<Item = Token<'source>>,
+{
+    /// Constructs a new [`IndentLayer`] over the given tokens of the given
+    /// source text.
+    pub fn new(file_id: FileId, source: &'source str, tokens: I) -> Self {
+        Self {
+            file_id,
+            source,
+            tokens,
+            lookahead: VecDeque::new(),
+            pending: VecDeque::new(),
+            indent_stack: vec![0],
+            end: 0,
+        }
+    }
+
+    fn next_token(&mut self) -> Option<Token<'source>> {
+        self.lookahead.pop_front().or_else(|| self.tokens.next())
+    }
+
+    /// Peeks the token the given number of tokens ahead, reading it ahead of
+    /// time if needed.
+    fn peek_kind(&mut self, n: usize) -> Option<SyntaxKind> {
+        while self.lookahead.len() <= n {
+            let token = self.tokens.next()?;
+            self.lookahead.push_back(token);
+        }
+
+        Some(self.lookahead[n].kind)
+    }
+
+    /// Merges the blank lines following a newline into it.
+    fn coalesce(&mut self, newline: Token<'source>) -> Token<'source> {
+        let mut end = newline.range.end;
+        loop {
+            let blank_line = match self.peek_kind(0) {
+                Some(SyntaxKind::Newline) => 1,
+                Some(SyntaxKind::Whitespace)
+                    if self.peek_kind(1) == Some(SyntaxKind::Newline) =>
+                {
+                    2
+                }
+                _ => break,
+            };
+
+            for _ in 0..blank_line {
+                end = self.next_token().map_or(end, |token| token.range.end);
+            }
+        }
+
+        let range = newline.range.start..end;
+        Token::new(SyntaxKind::Newline, &self.source[range.clone()], range)
+    }
+
+    fn message(&self, message: LexerMessage, token: &Token) -> Message {
+        let location = Location::new(self.file_id, token.range.clone());
+        Message::new(message, location)
+    }
+
+    /// Reads the rest of the line started by a newline ahead of time,
+    /// returning whether there is anything else than whitespace and comments
+    /// on it.
+    fn read_line(&mut self) -> bool {
+        let is_blank = |kind| {
+            matches!(
+                kind,
+                SyntaxKind::Whitespace
+                    | SyntaxKind::Comment
+                    | SyntaxKind::DocComment
+            )
+        };
+
+        let mut i = 0;
+        loop {
+            match self.peek_kind(i) {
+                None | Some(SyntaxKind::Newline) => return false,
+                Some(kind) if is_blank(kind) => i += 1,
+                Some(_) => return true,
+            }
+        }
+    }
+
+    /// Works out the tokens a newline starting a line that isn't blank turns
+    /// into.
+    fn indent(&mut self, newline: Token<'source>) {
+        // Only spaces indent lines, so any tab in the whitespace following them
+        // is an error. The tabs are still counted though, along with any
+        // spaces mixed in with them.
+        let spaces = newline.text.len() - newline.text.rfind('\n').unwrap() - 1;
+        let mut width = spaces;
+        let tab = match self.lookahead.front() {
+            Some(token)
+                if token.kind == SyntaxKind::Whitespace
+                    && token.range.start == newline.range.end
+                    && token.text.contains('\t') =>
+            {
+                let indent =
+                    &self.source[token.range.start - spaces..token.range.end];
+                width = indent_width(indent);
+                let message = LexerMessage::TabIndentation {
+                    width: width - spaces,
+                };
+                let message = self.message(message, token);
+                self.lookahead
+                    .pop_front()
+                    .map(|token| (token, Some(message)))
+            }
+            _ => None,
+        };
+
+        let last_indent = *self.indent_stack.last().unwrap_or(&0);
+        if width > last_indent {
+            self.indent_stack.push(width);
+            let indent = Token {
+                kind: SyntaxKind::Indent,
+                ..newline
+            };
+            self.pending.push_back((indent, None));
+        } else if width == last_indent {
+            self.pending.push_back((newline, None));
+        } else {
+            let blocks = self
+                .indent_stack
+                .iter()
+                .rev()
+                .take_while(|&&indent| indent > width)
+                .count();
+            self.indent_stack.truncate(self.indent_stack.len() - blocks);
+
+            let start = newline.range.start;
+            for _ in 1..blocks {
+                let dedent = Token::new(SyntaxKind::Dedent, "", start..start);
+                self.pending.push_back((dedent, None));
+            }
+
+            // The line should line up with the block it's dedented to.
+            let expected = *self.indent_stack.last().unwrap_or(&0);
+            let message = (width != expected).then(|| {
+                let message = LexerMessage::InvalidIndentation {
+                    expected,
+                    found: width,
+                };
+                self.message(message, &newline)
+            });
+            let dedent = Token {
+                kind: SyntaxKind::Dedent,
+                ..newline
+            };
+            self.pending.push_back((dedent, message));
+        }
+
+        self.pending.extend(tab);
+    }
+}
+
+impl<'source, I> Iterator for IndentLayer<'source, I>
+where
+    I: Iterator<Item = Token<'source>>,
+{
+    type Item = LexerItem<'source>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(item) = self.pending.pop_front() {
+            return Some(item);
+        }
+
+        let Some(token) = self.next_token() else {
+            // Close the blocks still open at the end of the input.
+            return (self.indent_stack.len() > 1).then(|| {
+                self.indent_stack.pop();
+                let end = self.end;
+                (Token::new(SyntaxKind::Dedent, "", end..end), None)
+            });
+        };
+
+        if token.kind != SyntaxKind::Newline {
+            self.end = token.range.end;
+            return Some((token, None));
+        }
+
+        let token = self.coalesce(token);
+        self.end = token.range.end;
+        if !self.read_line() {
+            return Some((token, None));
+        }
+
+        self.indent(token);
+        self.pending.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(input: &str, expected_tokens: Vec<Token>) {
+        let (tokens, _) = crate::tokenize(FileId(0), input);
+        let (tokens, messages) =
+            IndentLayer::new(FileId(0), input, tokens.into_iter())
+                .unzip::<_, _, Vec<_>, Vec<_>>();
+        assert_eq!(tokens, expected_tokens);
+        assert_eq!(messages.into_iter().flatten().count(), 0);
+    }
+
+    fn check_message(input: &str, title: &str, range: std::ops::Range<usize>) {
+        let (tokens, _) = crate::tokenize(FileId(0), input);
+        let messages = IndentLayer::new(FileId(0), input, tokens.into_iter())
+            .filter_map(|(_, message)| message)
+            .map(|message| message.generate_diagnostic())
+            .collect::<Vec<_>>();
+
+        assert_eq!(messages.len(), 1, "{messages:?}");
+        assert_eq!(messages[0].title, title);
+        assert_eq!(messages[0].location.range, range);
+    }
+
+    #[test]
+    fn test_indents_and_dedents() {
+        check(
+            "a\n  b\n    c\nd",
+            vec![
+                Token::new(SyntaxKind::Identifier, "a", 0..1),
+                Token::new(SyntaxKind::Indent, "\n  ", 1..4),
+                Token::new(SyntaxKind::Identifier, "b", 4..5),
+                Token::new(SyntaxKind::Indent, "\n    ", 5..10),
+                Token::new(SyntaxKind::Identifier, "c", 10..11),
+                Token::new(SyntaxKind::Dedent, "", 11..11),
+                Token::new(SyntaxKind::Dedent, "\n", 11..12),
+                Token::new(SyntaxKind::Identifier, "d", 12..13),
+            ],
+        );
+        check(
+            "a\n  b",
+            vec![
+                Token::new(SyntaxKind::Identifier, "a", 0..1),
+                Token::new(SyntaxKind::Indent, "\n  ", 1..4),
+                Token::new(SyntaxKind::Identifier, "b", 4..5),
+                Token::new(SyntaxKind::Dedent, "", 5..5),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_blank_lines() {
+        check(
+            "a\n  b\n\n    \n  c\n",
+            vec![
+                Token::new(SyntaxKind::Identifier, "a", 0..1),
+                Token::new(SyntaxKind::Indent, "\n  ", 1..4),
+                Token::new(SyntaxKind::Identifier, "b", 4..5),
+                Token::new(SyntaxKind::Newline, "\n\n    \n  ", 5..14),
+                Token::new(SyntaxKind::Identifier, "c", 14..15),
+                Token::new(SyntaxKind::Newline, "\n", 15..16),
+                Token::new(SyntaxKind::Dedent, "", 16..16),
+            ],
+        );
+
+        // The blank lines are part of the indentation token that follows them.
+        check(
+            "a\n\n\n  b\n\n\nc",
+            vec![
+                Token::new(SyntaxKind::Identifier, "a", 0..1),
+                Token::new(SyntaxKind::Indent, "\n\n\n  ", 1..6),
+                Token::new(SyntaxKind::Identifier, "b", 6..7),
+                Token::new(SyntaxKind::Dedent, "\n\n\n", 7..10),
+                Token::new(SyntaxKind::Identifier, "c", 10..11),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_comment_lines() {
+        check(
+            "a\n    # comment\n  b\n# comment",
+            vec![
+                Token::new(SyntaxKind::Identifier, "a", 0..1),
+                Token::new(SyntaxKind::Newline, "\n    ", 1..6),
+                Token::new(SyntaxKind::Comment, "# comment", 6..15),
+                Token::new(SyntaxKind::Indent, "\n  ", 15..18),
+                Token::new(SyntaxKind::Identifier, "b", 18..19),
+                Token::new(SyntaxKind::Newline, "\n", 19..20),
+                Token::new(SyntaxKind::Comment, "# comment", 20..29),
+                Token::new(SyntaxKind::Dedent, "", 29..29),
+            ],
+        );
+        check(
+            "a\n\n# comment\n\nb",
+            vec![
+                Token::new(SyntaxKind::Identifier, "a", 0..1),
+                Token::new(SyntaxKind::Newline, "\n\n", 1..3),
+                Token::new(SyntaxKind::Comment, "# comment", 3..12),
+                Token::new(SyntaxKind::Newline, "\n\n", 12..14),
+                Token::new(SyntaxKind::Identifier, "b", 14..15),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_crlf() {
+        check(
+            "a\r\n  b\r\n\r\n    c\r\nd",
+            vec![
+                Token::new(SyntaxKind::Identifier, "a", 0..1),
+                Token::new(SyntaxKind::Indent, "\r\n  ", 1..5),
+                Token::new(SyntaxKind::Identifier, "b", 5..6),
+                Token::new(SyntaxKind::Indent, "\r\n\r\n    ", 6..14),
+                Token::new(SyntaxKind::Identifier, "c", 14..15),
+                Token::new(SyntaxKind::Dedent, "", 15..15),
+                Token::new(SyntaxKind::Dedent, "\r\n", 15..17),
+                Token::new(SyntaxKind::Identifier, "d", 17..18),
+            ],
+        );
+
+        // Lines with nothing but whitespace are still blank.
+        check(
+            "a\r\n  \r\nb",
+            vec![
+                Token::new(SyntaxKind::Identifier, "a", 0..1),
+                Token::new(SyntaxKind::Newline, "\r\n  \r\n", 1..7),
+                Token::new(SyntaxKind::Identifier, "b", 7..8),
+            ],
+        );
+
+        check_message("a\r\n\tb", "Tab in indentation", 3..4);
+        check_message("a\r\n    b\r\n  c", "Invalid indentation", 8..12);
+    }
+
+    #[test]
+    fn test_tabs() {
+        check_message("a\n\tb", "Tab in indentation", 2..3);
+        check_message("a\n  \t b", "Tab in indentation", 4..6);
+        check_message("a\n\t\t b", "Tab in indentation", 2..5);
+
+        // The tabs can be replaced with as many spaces as they're counted as.
+        let input = "a\n  \t b";
+        let (tokens, _) = crate::tokenize(FileId(0), input);
+        let diagnostic = IndentLayer::new(FileId(0), input, tokens.into_iter())
+            .find_map(|(_, message)| message)
+            .unwrap()
+            .generate_diagnostic();
+        assert_eq!(diagnostic.fixes[0].location.range, 4..6);
+        assert_eq!(diagnostic.fixes[0].replacement, "   ");
+
+        // Tabs (along with any spaces mixed in with them) still indent lines.
+        let input = "a\n\tb\n    c\n  \t  d\n\t  e\n\tf\ng";
+        let (tokens, _) = crate::tokenize(FileId(0), input);
+        let kinds = IndentLayer::new(FileId(0), input, tokens.into_iter())
+            .map(|(token, _)| token.kind)
+            .filter(|&kind| kind != SyntaxKind::Whitespace)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                SyntaxKind::Identifier,
+                SyntaxKind::Indent,
+                SyntaxKind::Identifier,
+                SyntaxKind::Newline,
+                SyntaxKind::Identifier,
+                SyntaxKind::Indent,
+                SyntaxKind::Identifier,
+                SyntaxKind::Newline,
+                SyntaxKind::Identifier,
+                SyntaxKind::Dedent,
+                SyntaxKind::Identifier,
+                SyntaxKind::Dedent,
+                SyntaxKind::Identifier,
+            ]
+        );
+
+        // Tabs are fine anywhere else (including on blank lines).
+        check(
+            "a\t\n\t\nb",
+            vec![
+                Token::new(SyntaxKind::Identifier, "a", 0..1),
+                Token::new(SyntaxKind::Whitespace, "\t", 1..2),
+                Token::new(SyntaxKind::Newline, "\n\t\n", 2..5),
+                Token::new(SyntaxKind::Identifier, "b", 5..6),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_invalid_dedent() {
+        check_message("a\n    b\n  c", "Invalid indentation", 7..10);
+
+        let input = "a\n    b\n  c\nd";
+        let (tokens, _) = crate::tokenize(FileId(0), input);
+        let kinds = IndentLayer::new(FileId(0), input, tokens.into_iter())
+            .map(|(token, _)| token.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                SyntaxKind::Identifier,
+                SyntaxKind::Indent,
+                SyntaxKind::Identifier,
+                SyntaxKind::Dedent,
+                SyntaxKind::Identifier,
+                SyntaxKind::Newline,
+                SyntaxKind::Identifier,
+            ]
+        );
+    }
+}
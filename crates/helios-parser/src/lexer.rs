@@ -14,12 +14,16 @@
 //!
 //! [`parse`]: crate::parse
 
-use helios_diagnostics::Location;
+use helios_diagnostics::{FileId, Location};
 use helios_syntax::{self, SyntaxKind};
+use std::iter::Peekable;
 use std::ops::Range;
+use std::str::CharIndices;
 use unicode_xid::UnicodeXID;
 
+use crate::confusable::confusable;
 use crate::cursor::Cursor;
+use crate::edition::Edition;
 use crate::message::{LexerMessage, Message};
 
 /// Determines whether or not the given character is a valid beginning of an
@@ -61,17 +65,208 @@ fn is_digit(c: char) -> bool {
     matches!(c, '0'..='9')
 }
 
+/// Determines whether or not the given character may continue a number literal.
+///
+/// This includes letters, which may be digits in other bases (or invalid ones,
+/// which are reported later on rather than split into separate tokens).
+fn is_digit_continue(c: char) -> bool {
+    matches!(c, '_' | '0'..='9' | 'a'..='z' | 'A'..='Z')
+}
+
+/// Determines whether or not the given character may continue the digits of a
+/// decimal number literal (including its exponent).
+fn is_decimal_continue(c: char) -> bool {
+    c == '_' || is_digit(c)
+}
+
+/// Finds the exponent (such as `e23`) at the end of the given digits of a
+/// decimal number literal, starting from its `e` or `E`.
+///
+/// The exponent has to follow the digits right away, so digits mixed with other
+/// letters (such as `1a2e3`, which isn't a valid number anyway) don't have one.
+fn exponent_of(digits: &str) -> Option<&str> {
+    let exponent = digits.trim_start_matches(is_decimal_continue);
+    exponent.starts_with(['e', 'E']).then_some(exponent)
+}
+
 /// Checks whether or not the given character is a whitespace delimiter.
 fn is_whitespace(c: char) -> bool {
     matches!(c, ' ' | '\t' | '\r')
 }
 
+/// The parts of an interpolated string literal (such as `f"{a} + {b}"`), as
+/// found by [`scan_interpolated_string`].
+struct InterpolatedString {
+    /// The length of the literal after its opening `f"`, in bytes.
+    len: usize,
+    /// The ranges of the embedded expressions (without their braces), relative
+    /// to the end of the opening `f"`.
+    segments: Vec<Range<usize>>,
+    /// How the literal ends.
+    end: InterpolatedStringEnd,
+}
+
+enum InterpolatedStringEnd {
+    /// The literal ends with its closing quote.
+    Quote,
+    /// The line (or the file) ends before the closing quote.
+    Line,
+    /// The line (or the file) ends in the embedded expression whose opening
+    /// brace is at the given offset.
+    Segment(usize),
+}
+
+/// Scans the text of an interpolated string literal, starting right after its
+/// opening `f"`.
+///
+/// An embedded expression starts at an opening curly brace and ends at the
+/// matching closing one, skipping any string or character literal it holds
+/// (which may contain braces and quotes of their own). Doubled braces (`{{` and
+/// `}}`) stand for the braces themselves, and a backslash escapes the
+/// character after it.
+fn scan_interpolated_string(text: &str) -> InterpolatedString {
+    use InterpolatedStringEnd::*;
+
+    let mut chars = text.char_indices().peekable();
+    let mut segments = Vec::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            // A carriage return ends the line if a line feed follows it.
+            '\n' | '\r'
+                if c == '\n'
+                    || chars.peek().is_some_and(|&(_, next)| next == '\n') =>
+            {
+                return InterpolatedString {
+                    len: i,
+                    segments,
+                    end: Line,
+                };
+            }
+            '"' => {
+                return InterpolatedString {
+                    len: i + 1,
+                    segments,
+                    end: Quote,
+                };
+            }
+            '\\' => skip_escaped(&mut chars),
+            '{' | '}' if chars.peek().is_some_and(|&(_, next)| next == c) => {
+                chars.next();
+            }
+            '{' => {
+                let end = scan_segment(&mut chars);
+                let len = chars.peek().map_or(text.len(), |&(i, _)| i);
+                segments.push(i + 1..end.unwrap_or(len));
+                if end.is_none() {
+                    return InterpolatedString {
+                        len,
+                        segments,
+                        end: Segment(i),
+                    };
+                }
+            }
+            _ => {}
+        }
+    }
+
+    InterpolatedString {
+        len: text.len(),
+        segments,
+        end: Line,
+    }
+}
+
+/// Skips an embedded expression of an interpolated string literal (after its
+/// opening brace), returning the offset of its closing brace, or `None` if the
+/// line ends first.
+fn scan_segment(chars: &mut Peekable<CharIndices>) -> Option<usize> {
+    let mut depth = 0;
+    while let Some(&(i, c)) = chars.peek() {
+        if is_at_line_break(chars) {
+            return None;
+        }
+
+        chars.next();
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            '"' | '\'' => {
+                while let Some(&(_, next)) = chars.peek() {
+                    if is_at_line_break(chars) {
+                        break;
+                    }
+
+                    chars.next();
+                    match next {
+                        '\\' => skip_escaped(chars),
+                        next if next == c => break,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Skips the character escaped by a backslash, unless the line ends there.
+fn skip_escaped(chars: &mut Peekable<CharIndices>) {
+    if chars.peek().is_some() && !is_at_line_break(chars) {
+        chars.next();
+    }
+}
+
+/// Checks whether or not the given characters start with a line break (a
+/// line feed, or a carriage return followed by one).
+fn is_at_line_break(chars: &Peekable<CharIndices>) -> bool {
+    let mut chars = chars.clone().map(|(_, c)| c);
+    match chars.next() {
+        Some('\n') => true,
+        Some('\r') => chars.next() == Some('\n'),
+        _ => false,
+    }
+}
+
+/// Finds the embedded expressions of the given interpolated string literal,
+/// returning their ranges (without their braces).
+pub(crate) fn interpolations(token: &Token) -> Vec<Range<usize>> {
+    let offset = token.range.start + 2;
+    scan_interpolated_string(&token.text[2..])
+        .segments
+        .into_iter()
+        .map(|range| range.start + offset..range.end + offset)
+        .collect()
+}
+
+/// Determines whether or not the given string literal ends before its closing
+/// quote, in which case it takes up the rest of its line.
+pub(crate) fn is_unterminated(token: &Token) -> bool {
+    match token.kind {
+        SyntaxKind::Lit_String => match token.text[1..].strip_suffix('"') {
+            // The closing quote may have been escaped (by an odd number of
+            // backslashes).
+            Some(text) => {
+                (text.len() - text.trim_end_matches('\\').len()) % 2 == 1
+            }
+            None => true,
+        },
+        SyntaxKind::Lit_InterpolatedString => !matches!(
+            scan_interpolated_string(&token.text[2..]).end,
+            InterpolatedStringEnd::Quote
+        ),
+        _ => false,
+    }
+}
+
 /// A tuple of a tokenized token and possibly a diagnostic message if there was
 /// an issue during the tokenization process.
-pub type LexerItem<'source, FileId> = (Token<'source>, Option<Message<FileId>>);
+pub type LexerItem<'source> = (Token<'source>, Option<Message>);
 
 /// Internal type returned by all tokenization methods in the lexer.
-type LexerReturn<FileId> = (SyntaxKind, Option<Message<FileId>>);
+type LexerReturn = (SyntaxKind, Option<Message>);
 
 /// The unit of a tokenized Helios source file.
 ///
@@ -107,15 +302,15 @@ impl<'source> Token<'source> {
 /// tokenization and parsing processes of a Helios source.
 ///
 /// [`parse`]: crate::parse
-pub struct Lexer<'source, FileId> {
+pub struct Lexer<'source> {
     file_id: FileId,
     cursor: Cursor<'source>,
+    /// The edition of the source text, which determines which keywords are
+    /// lexed as identifiers.
+    edition: Edition,
 }
 
-impl<'source, FileId> Lexer<'source, FileId>
-where
-    FileId: Clone + Default,
-{
+impl<'source> Lexer<'source> {
     /// Construct a new [`Lexer`] with a reference to the source text.
     ///
     /// The lexer will initialise with the default [`LexerMode`] and set the
@@ -124,22 +319,202 @@ where
         Self {
             file_id,
             cursor: Cursor::new(source),
+            edition: Edition::default(),
         }
     }
 
+    /// Lexes the source text as written in the given edition (instead of the
+    /// default one).
+    pub fn with_edition(mut self, edition: Edition) -> Self {
+        self.edition = edition;
+        self
+    }
+
     /// Returns a [`SyntaxKind::UnknownChar`] with an error message detailing
     /// the provided unknown character and its location in the file.
-    fn unknown(&self, character: char, start: usize) -> LexerReturn<FileId> {
+    fn unknown(&self, character: char, start: usize) -> LexerReturn {
         let message = Message::new(
             LexerMessage::UnknownCharacter(character),
-            Location::new(self.file_id.clone(), start..(start + 1)),
+            Location::new(self.file_id, start..(start + 1)),
         );
 
         (SyntaxKind::UnknownChar, Some(message))
     }
+
+    /// Tokenizes a character that looks like an ASCII one as if it was that
+    /// character (so as a single space or symbol), with a warning suggesting
+    /// to replace it (see [`crate::confusable`]). Any other character is
+    /// unknown.
+    fn lex_confusable(&mut self, character: char, start: usize) -> LexerReturn {
+        let intended = match confusable(character) {
+            Some(confusable) => confusable.intended,
+            None => return self.unknown(character, start),
+        };
+
+        let kind = match intended {
+            ' ' => SyntaxKind::Whitespace,
+            _ => helios_syntax::symbol_from_char(intended),
+        };
+        let message = Message::new(
+            LexerMessage::ConfusableCharacter(character),
+            Location::new(self.file_id, start..self.current_pos()),
+        );
+
+        (kind, Some(message))
+    }
+
+    /// Tokenizes a string literal (such as `"hello, world!"`), whose opening
+    /// quote was just consumed.
+    ///
+    /// A string ends at the next unescaped quote, and a backslash escapes the
+    /// character after it. Strings can't span multiple lines, so an error is
+    /// reported if the line (or the file) ends first. Lexing then carries on
+    /// with the next line as usual, and the parser doesn't report what's
+    /// missing because of the string (see [`is_unterminated`]).
+    fn lex_string(&mut self, start: usize) -> LexerReturn {
+        loop {
+            if self.is_at_end() || self.is_at_line_break() {
+                let message = Message::new(
+                    LexerMessage::UnterminatedString,
+                    Location::new(self.file_id, start..self.current_pos()),
+                );
+                return (SyntaxKind::Lit_String, Some(message));
+            }
+
+            match self.next_char() {
+                Some('"') => return (SyntaxKind::Lit_String, None),
+                Some('\\') if !self.is_at_line_break() => {
+                    self.next_char();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Tokenizes an interpolated string literal (such as `f"{a} + {b}"`), whose
+    /// `f` was just consumed.
+    ///
+    /// Like other strings, an interpolated string can't span multiple lines,
+    /// and neither can the expressions embedded in it. The ranges of these
+    /// expressions can be found with [`interpolations`].
+    fn lex_interpolated_string(&mut self, start: usize) -> LexerReturn {
+        self.next_char();
+        let string = scan_interpolated_string(self.cursor.remaining());
+        let offset = self.current_pos();
+        while self.current_pos() < offset + string.len {
+            self.next_char();
+        }
+
+        let (message, range) = match string.end {
+            InterpolatedStringEnd::Quote => {
+                return (SyntaxKind::Lit_InterpolatedString, None);
+            }
+            InterpolatedStringEnd::Line => {
+                (LexerMessage::UnterminatedString, start..self.current_pos())
+            }
+            InterpolatedStringEnd::Segment(brace) => (
+                LexerMessage::UnterminatedInterpolation,
+                offset + brace..self.current_pos(),
+            ),
+        };
+
+        let location = Location::new(self.file_id, range);
+        (
+            SyntaxKind::Lit_InterpolatedString,
+            Some(Message::new(message, location)),
+        )
+    }
+
+    /// Tokenizes a character literal (such as `'a'` or `'\n'`), whose opening
+    /// quote was just consumed.
+    ///
+    /// A character literal holds a single character, which may be escaped
+    /// with a backslash (including ASCII and Unicode escapes such as `'\x41'`
+    /// and `'\u{1F600}'`).
+    /// An error is reported if it's empty, or if it doesn't end with a quote
+    /// right after its character. If there is a quote later on the same line,
+    /// the literal is taken to hold several characters and ends there instead
+    /// (so that `'ab'` is a single token rather than `'a` followed by `b'`).
+    fn lex_character(&mut self, start: usize) -> LexerReturn {
+        let error = |lexer: &Self, message| {
+            let location =
+                Location::new(lexer.file_id, start..lexer.current_pos());
+            (
+                SyntaxKind::Lit_Character,
+                Some(Message::new(message, location)),
+            )
+        };
+
+        match self.peek() {
+            _ if self.is_at_end() => {
+                return error(self, LexerMessage::UnterminatedCharacter);
+            }
+            _ if self.is_at_line_break() => {
+                return error(self, LexerMessage::UnterminatedCharacter);
+            }
+            '\'' => {
+                self.next_char();
+                return error(self, LexerMessage::EmptyCharacter);
+            }
+            '\\' => {
+                self.next_char();
+                match self.peek() {
+                    _ if self.is_at_line_break() => {}
+                    'x' => {
+                        self.next_char();
+                        for _ in 0..2 {
+                            if self.peek().is_ascii_hexdigit() {
+                                self.next_char();
+                            }
+                        }
+                    }
+                    'u' if self.peek_at(1) == '{' => {
+                        self.next_char();
+                        self.next_char();
+                        self.consume_while(|c| c.is_ascii_hexdigit());
+                        self.consume('}');
+                    }
+                    _ => {
+                        self.next_char();
+                    }
+                }
+            }
+            _ => {
+                self.next_char();
+            }
+        }
+
+        if self.consume('\'') {
+            (SyntaxKind::Lit_Character, None)
+        } else if let Some(len) = self.closing_quote_on_line() {
+            for _ in 0..len {
+                self.next_char();
+            }
+            error(self, LexerMessage::MultipleCharacters)
+        } else {
+            error(self, LexerMessage::UnterminatedCharacter)
+        }
+    }
+
+    /// The number of characters up to (and including) the next quote on the
+    /// current line that isn't escaped, if there is one.
+    fn closing_quote_on_line(&self) -> Option<usize> {
+        let mut len = 0;
+        loop {
+            match self.peek_at(len) {
+                '\\' => match self.peek_at(len + 1) {
+                    '\n' | '\r' | '\0' => return None,
+                    _ => len += 2,
+                },
+                '\'' => return Some(len + 1),
+                '\n' | '\r' | '\0' => return None,
+                _ => len += 1,
+            }
+        }
+    }
 }
 
-impl<'source, FileId> Lexer<'source, FileId> {
+impl<'source> Lexer<'source> {
     /// Retrieves the next character in the iterator.
     fn next_char(&mut self) -> Option<char> {
         self.cursor.advance()
@@ -160,6 +535,24 @@ impl<'source, FileId> Lexer<'source, FileId> {
         self.cursor.is_at_end()
     }
 
+    /// Checks if the next characters are a line break, which is either a line
+    /// feed (`\n`) or a carriage return followed by one (`\r\n`).
+    fn is_at_line_break(&self) -> bool {
+        match self.peek() {
+            '\n' => true,
+            '\r' => self.peek_at(1) == '\n',
+            _ => false,
+        }
+    }
+
+    /// Consumes the input up to the end of the current line (or of the file),
+    /// leaving the line break itself.
+    fn consume_line(&mut self) {
+        while !self.is_at_end() && !self.is_at_line_break() {
+            self.next_char();
+        }
+    }
+
     /// Returns the current position of the lexer.
     pub(crate) fn current_pos(&self) -> usize {
         self.cursor.pos()
@@ -167,7 +560,6 @@ impl<'source, FileId> Lexer<'source, FileId> {
 
     /// Attempts to consume the next character if it matches the provided
     /// character `c`. Returns a `bool` indicating if it was successful or not.
-    #[allow(dead_code)]
     fn consume(&mut self, c: char) -> bool {
         if self.peek() == c {
             self.next_char();
@@ -193,7 +585,7 @@ impl<'source, FileId> Lexer<'source, FileId> {
 
     /// Consumes the input while the given `predicate` holds true, returning a
     /// slice over the characters consumed.
-    fn consume_build<F>(&mut self, predicate: F) -> &str
+    fn consume_build<F>(&mut self, predicate: F) -> &'source str
     where
         F: Fn(char) -> bool,
     {
@@ -203,10 +595,16 @@ impl<'source, FileId> Lexer<'source, FileId> {
     }
 }
 
-impl<'source, FileId> Lexer<'source, FileId> {
-    fn lex_newline(&mut self, _: char) -> LexerReturn<FileId> {
-        // We only count spaces as indentation sigils.
-        // TODO: Emit an error if we find a TAB character here.
+impl<'source> Lexer<'source> {
+    /// Tokenizes a line break (a line feed, or a carriage return followed by
+    /// one) along with the spaces indenting the next line.
+    fn lex_newline(&mut self, c: char) -> LexerReturn {
+        if c == '\r' {
+            self.consume('\n');
+        }
+
+        // We only count spaces as indentation sigils (the `IndentLayer`
+        // reports tabs).
         self.consume_while(|c| c == ' ');
         (SyntaxKind::Newline, None)
     }
@@ -214,32 +612,65 @@ impl<'source, FileId> Lexer<'source, FileId> {
     /// Tokenizes a line comment.
     ///
     /// A line comment starts with a pound/hashtag (`#`) and ends at the next
-    /// line feed or the end of file, whichever comes first. This function also
+    /// line break or the end of file, whichever comes first. This function also
     /// handles documentation comments, which start with two pounds (`##`) or
     /// the familiar shebang sequence (`#!`).
-    fn lex_comment(&mut self, _: char) -> LexerReturn<FileId> {
+    fn lex_comment(&mut self, _: char) -> LexerReturn {
         // Check if it is a doc-comment
         if self.peek() == '#' || self.peek() == '!' {
-            self.consume_while(|c| c != '\n');
+            self.consume_line();
             (SyntaxKind::DocComment, None)
         } else {
-            self.consume_while(|c| c != '\n');
+            self.consume_line();
             (SyntaxKind::Comment, None)
         }
     }
 
-    /// Tokenizes a contiguous series of whitespace delimiters.
-    fn lex_whitespace(&mut self, _: char) -> LexerReturn<FileId> {
-        self.consume_while(is_whitespace);
+    /// Tokenizes a block comment, whose `#` was just consumed.
+    ///
+    /// A block comment starts with `#[` and ends with a matching `]#`, and may
+    /// span multiple lines. Block comments nest, so every `#[` in one has to be
+    /// closed before the comment itself ends (which makes it easy to comment
+    /// out code that already has block comments in it).
+    fn lex_block_comment(&mut self, start: usize) -> LexerReturn {
+        self.next_char();
+
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next_char() {
+                Some('#') if self.peek() == '[' => {
+                    self.next_char();
+                    depth += 1;
+                }
+                Some(']') if self.peek() == '#' => {
+                    self.next_char();
+                    depth -= 1;
+                }
+                Some(_) => {}
+                None => {
+                    let message = Message::new(
+                        LexerMessage::UnterminatedBlockComment,
+                        Location::new(self.file_id, start..start + 2),
+                    );
+                    return (SyntaxKind::Comment, Some(message));
+                }
+            }
+        }
+
+        (SyntaxKind::Comment, None)
+    }
+
+    /// Tokenizes a contiguous series of whitespace delimiters, up to the next
+    /// line break (whose carriage return belongs to the newline).
+    fn lex_whitespace(&mut self, _: char) -> LexerReturn {
+        while is_whitespace(self.peek()) && !self.is_at_line_break() {
+            self.next_char();
+        }
         (SyntaxKind::Whitespace, None)
     }
 
     /// Tokenizes a valid symbol.
-    ///
-    /// _TODO:_ Perhaps we could handle cases with confused symbols, such as
-    /// U+037E, the Greek question mark, which looks like a semicolon (compare
-    /// ';' with ';').
-    fn lex_symbol(&mut self, symbol: char) -> LexerReturn<FileId> {
+    fn lex_symbol(&mut self, symbol: char) -> LexerReturn {
         match symbol {
             '?' => {
                 if (self.peek(), self.peek_at(1)) == ('?', '?') {
@@ -269,7 +700,7 @@ impl<'source, FileId> Lexer<'source, FileId> {
     ///
     /// This includes upper- and lower-case letters, decimal digits and the
     /// underscore.
-    fn lex_identifier(&mut self, first_char: char) -> LexerReturn<FileId> {
+    fn lex_identifier(&mut self, first_char: char) -> LexerReturn {
         let mut string = String::new();
         string.push(first_char);
         string.push_str(self.consume_build(is_identifier_continue));
@@ -277,60 +708,106 @@ impl<'source, FileId> Lexer<'source, FileId> {
     }
 
     /// Attempts to tokenize the provided string into a keyword or identifier.
-    #[rustfmt::skip]
+    ///
+    /// Keywords that aren't reserved in the edition being lexed are
+    /// identifiers (see [`Edition::is_reserved`]).
     fn lex_keyword_or_identifier(&mut self, slice: &str) -> SyntaxKind {
-        match slice {
-            "and"       => SyntaxKind::Kwd_And,
-            "as"        => SyntaxKind::Kwd_As,
-            "case"      => SyntaxKind::Kwd_Case,
-            "else"      => SyntaxKind::Kwd_Else,
-            "enum"      => SyntaxKind::Kwd_Enum,
-            "for"       => SyntaxKind::Kwd_For,
-            "forall"    => SyntaxKind::Kwd_Forall,
-            "func"      => SyntaxKind::Kwd_Func,
-            "if"        => SyntaxKind::Kwd_If,
-            "impl"      => SyntaxKind::Kwd_Impl,
-            "import"    => SyntaxKind::Kwd_Import,
-            "in"        => SyntaxKind::Kwd_In,
-            "iter"      => SyntaxKind::Kwd_Iter,
-            "let"       => SyntaxKind::Kwd_Let,
-            "module"    => SyntaxKind::Kwd_Module,
-            "not"       => SyntaxKind::Kwd_Not,
-            "of"        => SyntaxKind::Kwd_Of,
-            "or"        => SyntaxKind::Kwd_Or,
-            "range"     => SyntaxKind::Kwd_Range,
-            "record"    => SyntaxKind::Kwd_Record,
-            "type"      => SyntaxKind::Kwd_Type,
-            "var"       => SyntaxKind::Kwd_Var,
-            "while"     => SyntaxKind::Kwd_While,
-            "with"      => SyntaxKind::Kwd_With,
-            "yield"     => SyntaxKind::Kwd_Yield,
-            "_"         => SyntaxKind::ReservedIdentifier,
-            _           => SyntaxKind::Identifier,
+        match helios_syntax::keyword_from_str(slice) {
+            Some(kind) if self.edition.is_reserved(kind) => kind,
+            _ if slice == "_" => SyntaxKind::ReservedIdentifier,
+            _ => SyntaxKind::Identifier,
         }
     }
 
+    /// Tokenizes the rest of the exponent of a float literal (such as the `-9`
+    /// of `1e-9`), whose `e` and the characters after it were just consumed.
+    ///
+    /// An exponent is made of an `e` (or `E`), an optional sign and at least
+    /// one decimal digit.
+    fn lex_exponent(&mut self, exponent: &str) -> LexerReturn {
+        let start = self.current_pos() - exponent.len();
+
+        // The sign isn't consumed along with the digits of the number, so the
+        // digits after it are consumed here.
+        let signed = exponent.len() == 1 && matches!(self.peek(), '+' | '-');
+        let digits = if signed {
+            self.next_char();
+            self.consume_build(is_digit_continue)
+        } else {
+            &exponent[1..]
+        };
+
+        let valid = digits.chars().all(is_decimal_continue)
+            && digits.contains(is_digit);
+        let message = (!valid).then(|| {
+            Message::new(
+                LexerMessage::MalformedExponent,
+                Location::new(self.file_id, start..self.current_pos()),
+            )
+        });
+
+        (SyntaxKind::Lit_Float, message)
+    }
+
     /// Tokenizes a contiguous series of characters that may be part of an
     /// integer or float literal.
     ///
-    /// _NOTE:_ The lexer does not verify if the the number literal is correctly
-    /// formatted in its base.
-    fn lex_number(&mut self, c: char) -> LexerReturn<FileId> {
-        fn is_digit_continue(c: char) -> bool {
-            matches!(c, '_' | '0'..='9' | 'a'..='z' | 'A'..='Z')
-        }
-
+    /// Decimal literals may end with an exponent (such as `6.02e23` or `1E-9`),
+    /// which makes them float literals.
+    ///
+    /// The digits of binary, octal and hexadecimal literals are checked against
+    /// their base (and there must be at least one after the prefix), but
+    /// decimal ones are not (apart from the exponent).
+    fn lex_number(&mut self, c: char) -> LexerReturn {
         // First, we'll check if the number literal is in a non-decimal base.
         if matches!((c, self.peek()), ('0', 'b') | ('0', 'o') | ('0', 'x')) {
             // Number literals of non-decimal base can only be integers, so
             // we'll consume any digit that may be part of a number (including
             // invalid letters like 'z').
-            self.consume_while(is_digit_continue);
-            (SyntaxKind::Lit_Integer, None)
+            let radix = match self.next_char() {
+                Some('b') => 2,
+                Some('o') => 8,
+                _ => 16,
+            };
+            let digits = self.consume_build(is_digit_continue);
+            let start = self.current_pos() - digits.len();
+
+            // Only the first invalid digit is reported, but the whole literal
+            // is still a single token.
+            let message = digits
+                .char_indices()
+                .find(|&(_, c)| c != '_' && !c.is_digit(radix))
+                .map(|(offset, digit)| {
+                    let start = start + offset;
+                    Message::new(
+                        LexerMessage::InvalidDigitForBase { digit, radix },
+                        Location::new(
+                            self.file_id,
+                            start..start + digit.len_utf8(),
+                        ),
+                    )
+                })
+                .or_else(|| {
+                    // Underscores only separate digits, so a prefix followed
+                    // by nothing else (such as `0x` or `0b_`) has no value.
+                    digits.chars().all(|c| c == '_').then(|| {
+                        Message::new(
+                            LexerMessage::MissingDigitsForBase { radix },
+                            Location::new(
+                                self.file_id,
+                                start - 2..self.current_pos(),
+                            ),
+                        )
+                    })
+                });
+
+            (SyntaxKind::Lit_Integer, message)
         } else {
             // This number literal is in decimal base, so we'll consume the
             // integer part first.
-            self.consume_while(is_digit_continue);
+            let integer = self.consume_build(is_digit_continue);
+            let mut exponent = exponent_of(integer);
+            let mut kind = SyntaxKind::Lit_Integer;
 
             // If there is a dot after the integer part, and the next character
             // after it does NOT start an identifier, then this must be a float
@@ -339,20 +816,27 @@ impl<'source, FileId> Lexer<'source, FileId> {
             // here in the lexer.
             if self.peek() == '.' && !is_identifier_start(self.peek_at(1)) {
                 self.next_char();
-                self.consume_while(is_digit_continue);
-                (SyntaxKind::Lit_Float, None)
-            } else {
-                (SyntaxKind::Lit_Integer, None)
+                let fraction = self.consume_build(is_digit_continue);
+
+                // The exponent comes after the fractional part in that case.
+                exponent = if integer.chars().all(is_decimal_continue) {
+                    exponent_of(fraction)
+                } else {
+                    None
+                };
+                kind = SyntaxKind::Lit_Float;
+            }
+
+            match exponent {
+                Some(exponent) => self.lex_exponent(exponent),
+                None => (kind, None),
             }
         }
     }
 }
 
-impl<'source, FileId> Iterator for Lexer<'source, FileId>
-where
-    FileId: Clone + Default,
-{
-    type Item = LexerItem<'source, FileId>;
+impl<'source> Iterator for Lexer<'source> {
+    type Item = LexerItem<'source>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.cursor.checkpoint();
@@ -360,12 +844,17 @@ where
 
         let (kind, message) = match self.cursor.advance()? {
             c if c == '\n' => self.lex_newline(c),
+            '\r' if self.peek() == '\n' => self.lex_newline('\r'),
+            '#' if self.peek() == '[' => self.lex_block_comment(start),
             c if c == '#' => self.lex_comment(c),
             c if is_whitespace(c) => self.lex_whitespace(c),
             c if is_symbol(c) => self.lex_symbol(c),
+            'f' if self.peek() == '"' => self.lex_interpolated_string(start),
             c if is_identifier_start(c) => self.lex_identifier(c),
             c if is_digit(c) => self.lex_number(c),
-            c => self.unknown(c, start),
+            '"' => self.lex_string(start),
+            '\'' => self.lex_character(start),
+            c => self.lex_confusable(c, start),
         };
 
         let end = self.current_pos();
@@ -380,7 +869,7 @@ mod tests {
     use super::*;
 
     fn check(input: &str, kind: SyntaxKind) {
-        let mut lexer = Lexer::new(0u8, input);
+        let mut lexer = Lexer::new(FileId(0), input);
         let (token, _) = lexer.next().unwrap();
         assert_eq!(token.kind, kind);
         assert_eq!(token.text, input);
@@ -407,6 +896,75 @@ mod tests {
         check("#! This is a random line comment", SyntaxKind::DocComment);
     }
 
+    #[test]
+    fn test_lex_line_breaks() {
+        let tokens = |input| {
+            Lexer::new(FileId(0), input)
+                .map(|(token, _)| (token.kind, token.text))
+                .collect::<Vec<_>>()
+        };
+
+        // The carriage return of a CRLF is part of the newline, and so isn't
+        // part of the whitespace, comment or string before it.
+        assert_eq!(
+            tokens("a \r\n  # b\r\n\"c\r\nf\"{d\r\n"),
+            vec![
+                (SyntaxKind::Identifier, "a"),
+                (SyntaxKind::Whitespace, " "),
+                (SyntaxKind::Newline, "\r\n  "),
+                (SyntaxKind::Comment, "# b"),
+                (SyntaxKind::Newline, "\r\n"),
+                (SyntaxKind::Lit_String, "\"c"),
+                (SyntaxKind::Newline, "\r\n"),
+                (SyntaxKind::Lit_InterpolatedString, "f\"{d"),
+                (SyntaxKind::Newline, "\r\n"),
+            ]
+        );
+        assert_eq!(
+            tokens("'\r\n'\\\r\n"),
+            vec![
+                (SyntaxKind::Lit_Character, "'"),
+                (SyntaxKind::Newline, "\r\n"),
+                (SyntaxKind::Lit_Character, "'\\"),
+                (SyntaxKind::Newline, "\r\n"),
+            ]
+        );
+
+        // Other carriage returns are whitespace.
+        assert_eq!(
+            tokens("a\r\rb"),
+            vec![
+                (SyntaxKind::Identifier, "a"),
+                (SyntaxKind::Whitespace, "\r\r"),
+                (SyntaxKind::Identifier, "b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lex_block_comment() {
+        check("#[]#", SyntaxKind::Comment);
+        check("#[ abc ]#", SyntaxKind::Comment);
+        check("#[ abc\n  123\n]#", SyntaxKind::Comment);
+        check("#[ a #[ b ]# c ]#", SyntaxKind::Comment);
+        check("#[ # ] [ ]#", SyntaxKind::Comment);
+
+        // A block comment ends at its matching `]#`.
+        let mut lexer = Lexer::new(FileId(0), "#[ a ]# b ]#");
+        let (token, _) = lexer.next().unwrap();
+        assert_eq!((token.kind, token.text), (SyntaxKind::Comment, "#[ a ]#"));
+
+        // Documentation comments can't be block comments.
+        check("##[ abc", SyntaxKind::DocComment);
+
+        let mut lexer = Lexer::new(FileId(0), "#[ a #[ b ]#\nc");
+        let (token, message) = lexer.next().unwrap();
+        assert_eq!(token.text, "#[ a #[ b ]#\nc");
+        let diagnostic = message.unwrap().generate_diagnostic();
+        assert_eq!(diagnostic.title, "Unterminated block comment");
+        assert_eq!(diagnostic.location.range, 0..2);
+    }
+
     #[test]
     fn test_lex_keywords() {
         check("and", SyntaxKind::Kwd_And);
@@ -436,6 +994,29 @@ mod tests {
         check("yield", SyntaxKind::Kwd_Yield);
     }
 
+    #[test]
+    fn test_lex_soft_keywords() {
+        let kind = |input, edition| {
+            let mut lexer = Lexer::new(FileId(0), input).with_edition(edition);
+            lexer.next().unwrap().0.kind
+        };
+
+        for (input, keyword) in [
+            ("external", SyntaxKind::Kwd_External),
+            ("macro", SyntaxKind::Kwd_Macro),
+            ("test", SyntaxKind::Kwd_Test),
+        ] {
+            assert_eq!(
+                kind(input, Edition::Edition2025),
+                SyntaxKind::Identifier
+            );
+            assert_eq!(kind(input, Edition::Edition2026), keyword);
+        }
+
+        // Every other keyword is reserved in every edition.
+        assert_eq!(kind("let", Edition::Edition2025), SyntaxKind::Kwd_Let);
+    }
+
     #[test]
     fn test_lex_symbols() {
         check("&", SyntaxKind::Sym_Ampersand);
@@ -545,6 +1126,75 @@ mod tests {
         check("0xfzeyfx_fwevfu", SyntaxKind::Lit_Integer);
     }
 
+    #[test]
+    fn test_lex_invalid_digits_for_base() {
+        let check_error = |input: &str, range| {
+            let mut lexer = Lexer::new(FileId(0), input);
+            let (token, message) = lexer.next().unwrap();
+            assert_eq!(token.kind, SyntaxKind::Lit_Integer);
+            assert_eq!(token.text, input);
+            let diagnostic = message.unwrap().generate_diagnostic();
+            assert_eq!(diagnostic.title, "Invalid digit for base");
+            assert_eq!(diagnostic.location.range, range);
+        };
+
+        check_error("0b102", 4..5);
+        check_error("0b_2_1", 3..4);
+        check_error("0o78", 3..4);
+        check_error("0xfzf", 3..4);
+        check_error("0xFFg_h", 4..5);
+
+        let mut lexer = Lexer::new(FileId(0), "0b1_01 0o17 0xDead_beef 09");
+        assert!(lexer.all(|(_, message)| message.is_none()));
+    }
+
+    #[test]
+    fn test_lex_missing_digits_for_base() {
+        let check_error = |input: &str| {
+            let mut lexer = Lexer::new(FileId(0), input);
+            let (token, message) = lexer.next().unwrap();
+            assert_eq!(token.kind, SyntaxKind::Lit_Integer);
+            assert_eq!(token.text, input);
+            let diagnostic = message.unwrap().generate_diagnostic();
+            assert_eq!(diagnostic.title, "Missing digits for base");
+            assert_eq!(diagnostic.location.range, 0..input.len());
+        };
+
+        check_error("0x");
+        check_error("0b");
+        check_error("0b_");
+        check_error("0o__");
+    }
+
+    #[test]
+    fn test_lex_confusable_characters() {
+        let check_warning = |input: &str, kind, intended| {
+            let mut lexer = Lexer::new(FileId(0), input);
+            let (token, message) = lexer.next().unwrap();
+            assert_eq!(token.kind, kind);
+            assert_eq!(token.text, input);
+
+            let message = message.unwrap();
+            assert!(!message.is_error());
+            let diagnostic = message.generate_diagnostic();
+            assert_eq!(diagnostic.title, "Confusable character");
+            assert_eq!(diagnostic.location.range, 0..input.len());
+            assert_eq!(diagnostic.fixes[0].replacement, intended);
+            assert!(diagnostic.fixes[0].machine_applicable);
+        };
+
+        check_warning("\u{37e}", SyntaxKind::Sym_Semicolon, ";");
+        check_warning("\u{ff08}", SyntaxKind::Sym_LParen, "(");
+        check_warning("\u{ff09}", SyntaxKind::Sym_RParen, ")");
+        check_warning("\u{a0}", SyntaxKind::Whitespace, " ");
+
+        // Other characters are still unknown.
+        let mut lexer = Lexer::new(FileId(0), "\u{2603}");
+        let (token, message) = lexer.next().unwrap();
+        assert_eq!(token.kind, SyntaxKind::UnknownChar);
+        assert!(message.unwrap().is_error());
+    }
+
     #[test]
     fn test_lex_semantically_valid_literal_floats() {
         check("0.", SyntaxKind::Lit_Float);
@@ -567,6 +1217,173 @@ mod tests {
         check("1a2b3c4d5e.6", SyntaxKind::Lit_Float);
     }
 
+    #[test]
+    fn test_lex_literal_floats_with_exponents() {
+        check("1e10", SyntaxKind::Lit_Float);
+        check("1E10", SyntaxKind::Lit_Float);
+        check("6.02e23", SyntaxKind::Lit_Float);
+        check("1E-9", SyntaxKind::Lit_Float);
+        check("1e+9", SyntaxKind::Lit_Float);
+        check("1_000e1_0", SyntaxKind::Lit_Float);
+
+        // A dot followed by a letter still isn't part of a number.
+        let (token, _) = Lexer::new(FileId(0), "1.e5").next().unwrap();
+        assert_eq!((token.kind, token.text), (SyntaxKind::Lit_Integer, "1"));
+
+        let check_error = |input, text, range| {
+            let mut lexer = Lexer::new(FileId(0), input);
+            let (token, message) = lexer.next().unwrap();
+            assert_eq!((token.kind, token.text), (SyntaxKind::Lit_Float, text));
+            let diagnostic = message.unwrap().generate_diagnostic();
+            assert_eq!(diagnostic.title, "Malformed exponent");
+            assert_eq!(diagnostic.location.range, range);
+        };
+
+        check_error("1e", "1e", 1..2);
+        check_error("1e+", "1e+", 1..3);
+        check_error("2.5E- 1", "2.5E-", 3..5);
+        check_error("1e_", "1e_", 1..3);
+        check_error("1e10x", "1e10x", 1..5);
+        check_error("1e-2x", "1e-2x", 1..5);
+
+        // Other letters mean the literal doesn't have an exponent.
+        check("1a2e3", SyntaxKind::Lit_Integer);
+        check("1a.2e3", SyntaxKind::Lit_Float);
+    }
+
+    #[test]
+    fn test_lex_strings() {
+        check(r#""""#, SyntaxKind::Lit_String);
+        check(r#""hello, world!""#, SyntaxKind::Lit_String);
+        check(r#""say \"hi\"\\""#, SyntaxKind::Lit_String);
+
+        let mut lexer = Lexer::new(FileId(0), "\"abc\n\"");
+        let (token, message) = lexer.next().unwrap();
+        assert_eq!((token.kind, token.text), (SyntaxKind::Lit_String, "\"abc"));
+        let diagnostic = message.unwrap().generate_diagnostic();
+        assert_eq!(diagnostic.title, "Unterminated string");
+        assert_eq!(diagnostic.location.range, 0..4);
+    }
+
+    #[test]
+    fn test_lex_interpolated_strings() {
+        check(r#"f"""#, SyntaxKind::Lit_InterpolatedString);
+        check(r#"f"hello, {name}!""#, SyntaxKind::Lit_InterpolatedString);
+        check(r#"f"{{}} \" {a + b}""#, SyntaxKind::Lit_InterpolatedString);
+        check(r#"f"{"}" + '{'}""#, SyntaxKind::Lit_InterpolatedString);
+        check(r#"f"{f"{a}"}""#, SyntaxKind::Lit_InterpolatedString);
+
+        // The `f` is only a prefix right before a quote.
+        let mut lexer = Lexer::new(FileId(0), r#"f "a""#);
+        let kinds = lexer.by_ref().map(|(token, _)| token.kind).take(3);
+        assert_eq!(
+            kinds.collect::<Vec<_>>(),
+            [
+                SyntaxKind::Identifier,
+                SyntaxKind::Whitespace,
+                SyntaxKind::Lit_String
+            ]
+        );
+
+        let check_error = |input, text, title, range| {
+            let mut lexer = Lexer::new(FileId(0), input);
+            let (token, message) = lexer.next().unwrap();
+            assert_eq!(
+                (token.kind, token.text),
+                (SyntaxKind::Lit_InterpolatedString, text)
+            );
+            let diagnostic = message.unwrap().generate_diagnostic();
+            assert_eq!(diagnostic.title, title);
+            assert_eq!(diagnostic.location.range, range);
+        };
+
+        check_error("f\"ab\n\"", "f\"ab", "Unterminated string", 0..4);
+        check_error("f\"a{b", "f\"a{b", "Unterminated interpolation", 3..5);
+        check_error(
+            "f\"{\"}\"\n",
+            "f\"{\"}\"",
+            "Unterminated interpolation",
+            2..6,
+        );
+    }
+
+    #[test]
+    fn test_interpolations() {
+        let check = |input: &str, expected: &[&str]| {
+            let (token, _) = Lexer::new(FileId(0), input).next().unwrap();
+            let segments = interpolations(&token)
+                .into_iter()
+                .map(|range| &input[range])
+                .collect::<Vec<_>>();
+            assert_eq!(segments, expected);
+        };
+
+        check(r#"f"hello""#, &[]);
+        check(r#"f"{{a}} {b}""#, &["b"]);
+        check(r#"f"{a} + {b} = { a + b }""#, &["a", "b", " a + b "]);
+        check(r#"f"{"}"} {'}'} {{a: b}}""#, &[r#""}""#, "'}'"]);
+        check(r#"f"{ {a} }""#, &[" {a} "]);
+        check(r#"f"{f"{a}"}""#, &[r#"f"{a}""#]);
+        check(r#"f"{a"#, &["a"]);
+    }
+
+    #[test]
+    fn test_is_unterminated() {
+        let check = |input: &str, expected| {
+            let (token, message) = Lexer::new(FileId(0), input).next().unwrap();
+            assert_eq!(is_unterminated(&token), expected, "{input}");
+            assert_eq!(message.is_some(), expected, "{input}");
+        };
+
+        check(r#""abc""#, false);
+        check(r#""\\""#, false);
+        check(r#"f"{"}"}""#, false);
+        check(r#"""#, true);
+        check("\"abc\n\"", true);
+        check(r#""abc\""#, true);
+        check(r#""abc\\\""#, true);
+        check(r#"f"abc"#, true);
+        check(r#"f"{"}""#, true);
+    }
+
+    #[test]
+    fn test_lex_characters() {
+        check("'a'", SyntaxKind::Lit_Character);
+        check("' '", SyntaxKind::Lit_Character);
+        check("'\"'", SyntaxKind::Lit_Character);
+        check("'é'", SyntaxKind::Lit_Character);
+        check(r"'\n'", SyntaxKind::Lit_Character);
+        check(r"'\''", SyntaxKind::Lit_Character);
+        check(r"'\\'", SyntaxKind::Lit_Character);
+        check(r"'\x41'", SyntaxKind::Lit_Character);
+        check(r"'\u{1F600}'", SyntaxKind::Lit_Character);
+
+        let check_error = |input, text, title, range| {
+            let mut lexer = Lexer::new(FileId(0), input);
+            let (token, message) = lexer.next().unwrap();
+            assert_eq!(
+                (token.kind, token.text),
+                (SyntaxKind::Lit_Character, text)
+            );
+            let diagnostic = message.unwrap().generate_diagnostic();
+            assert_eq!(diagnostic.title, title);
+            assert_eq!(diagnostic.location.range, range);
+        };
+
+        check_error("''", "''", "Empty character", 0..2);
+        check_error("'", "'", "Unterminated character", 0..1);
+        check_error("'a", "'a", "Unterminated character", 0..2);
+        check_error("'\n'", "'", "Unterminated character", 0..1);
+        check_error("'ab", "'a", "Unterminated character", 0..2);
+        check_error("'ab\n'", "'a", "Unterminated character", 0..2);
+        check_error("'\\\n", "'\\", "Unterminated character", 0..2);
+
+        // Literals with several characters are a single token.
+        check_error("'ab'", "'ab'", "Multiple characters", 0..4);
+        check_error("'\\x414'", "'\\x414'", "Multiple characters", 0..7);
+        check_error("'a\\''", "'a\\''", "Multiple characters", 0..5);
+    }
+
     #[test]
     fn test_lex_identifiers() {
         check("_", SyntaxKind::ReservedIdentifier);
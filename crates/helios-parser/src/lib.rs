@@ -6,176 +6,143 @@
 //!
 //! [`rowan`]: https://docs.rs/rowan/0.15.3/rowan
 
+mod confusable;
 mod cursor;
+mod edition;
+mod escape;
 mod grammar;
+pub mod imports;
+mod indent;
 mod lexer;
 pub mod message;
+mod number;
 mod parser;
+pub mod printer;
+mod trivia;
 
+pub use helios_diagnostics::FileId;
 use helios_syntax::{SyntaxKind, SyntaxNode};
-use rowan::GreenNode;
-use std::cmp::Ordering;
-
-use crate::lexer::{Lexer, Token};
+pub use rowan::NodeCache;
+use rowan::{GreenNode, WalkEvent};
+use std::sync::Arc;
+
+pub use crate::edition::Edition;
+use crate::escape::validate_escapes;
+pub use crate::escape::{cook_literal, CookedLiteral, Hole};
+pub use crate::indent::IndentLayer;
+pub use crate::lexer::Token;
+use crate::lexer::{Lexer, LexerItem};
 pub use crate::message::*;
+use crate::number::validate_number;
+pub use crate::number::{number_value, Number, NumberError};
 use crate::parser::sink::Sink;
 use crate::parser::source::Source;
 use crate::parser::Parser;
+pub use crate::trivia::{TokenWithTrivia, TriviaLayer};
 
-/// Tokenizes the given source text.
-pub fn tokenize<FileId>(
+/// Tokenizes the given source text, checking the escape sequences of its
+/// literals (and that its float literals aren't too large) along the way.
+///
+/// Every token is kept, and the indentation isn't processed (which the parser
+/// does once it's given the tokens). See [`tokenize_with_options`] to change
+/// either of these.
+pub fn tokenize(
     file_id: FileId,
     source: &str,
-) -> (Vec<Token>, Vec<Message<FileId>>)
-where
-    FileId: Clone + Default,
-{
-    let mut tokens = Vec::new();
-    let mut errors = Vec::new();
-
-    for (token, error) in Lexer::new(file_id, source) {
-        tokens.push(token);
-        if let Some(error) = error {
-            errors.push(error);
-        }
-    }
-
-    (tokens, errors)
+) -> (Vec<Token<'_>>, Vec<Message>) {
+    tokenize_with_options(file_id, source, TokenizeOptions::default())
 }
 
-/// Processes indentation for a given vector of tokens by inserting indent and
-/// dedent tokens where appropriate and returning a new vector with these
-/// changes.
+/// How [`tokenize_with_options`] tokenizes a source text.
 ///
-/// Since the [`Lexer`] structure and the [`tokenize`] function only emit
-/// `Newline` tokens on every line feed it encounters, any subsequent operations
-/// that require a tokenized output with indentation tokens (such as the parser)
-/// cannot use their outputs. This function is able to act as an intermediary by
-/// processing these indentations for you. It is ideal to call this function
-/// right after tokenizing.
-pub fn process_indents<'source>(
-    source: &'source str,
-    tokens: Vec<Token<'source>>,
-) -> Vec<Token<'source>> {
-    // Our resulting vector will have at least the same size as the input vector
-    // (in the case that there is no indentation to be processed).
-    let mut processed_tokens = Vec::with_capacity(tokens.capacity());
-    let mut indent_stack = vec![0];
-
-    let mut i = 0;
-    while i < tokens.len() {
-        // TODO: assert!(indent_stack.is_sorted());
-        let curr_token = tokens[i].clone();
-
-        if curr_token.kind == SyntaxKind::Newline {
-            // Skip the newline character and count the number of spaces.
-            let curr_indent = curr_token.text[1..].len();
-            let last_indent = indent_stack.last().unwrap_or(&0);
-
-            match curr_indent.cmp(last_indent) {
-                // We haven't indented or dedented, so push the token as-is.
-                Ordering::Equal => {
-                    processed_tokens.push(curr_token);
-                    i += 1;
-                }
-                // We've indented, so we'll push an `Indent` token.
-                Ordering::Greater => {
-                    indent_stack.push(curr_indent);
-                    processed_tokens.push(Token {
-                        kind: SyntaxKind::Indent,
-                        ..curr_token
-                    });
-                    i += 1;
-                }
-                // We've dedented, so we'll push as many `Dedent` tokens
-                // necessary to get the new indentation level.
-                Ordering::Less => {
-                    'emit_dedents: loop {
-                        // We won't push a dedent token just yet because we need
-                        // to make sure the current indent is NOT greater than
-                        // the second-last indent (`new_last_indent`).
-                        let old_indent = indent_stack.pop().unwrap();
-                        let new_last_indent = indent_stack.last().unwrap_or(&0);
-
-                        match curr_indent.cmp(new_last_indent) {
-                            // We can emit a dedent token for the old indent and
-                            // continue this loop.
-                            Ordering::Less => {
-                                processed_tokens.push(Token {
-                                    kind: SyntaxKind::Dedent,
-                                    ..curr_token.clone()
-                                });
-                                continue 'emit_dedents;
-                            }
-                            // We can emit a dedent token for the old indent and
-                            // break out of this loop.
-                            Ordering::Equal => {
-                                processed_tokens.push(Token {
-                                    kind: SyntaxKind::Dedent,
-                                    ..curr_token.clone()
-                                });
-
-                                // We've finished dealing with the current
-                                // token, so we'll increment `i`.
-                                i += 1;
-                                break 'emit_dedents;
-                            }
-                            // The current indent is between the second-last
-                            // and the last indents, signifying an incorrect
-                            // dedent. Thus, we'll invalidate the whole line and
-                            // emit an error token instead.
-                            Ordering::Greater => {
-                                let start = curr_token.range.start;
-                                let mut end = curr_token.range.end;
-
-                                // Skip the current newline token.
-                                i += 1;
-
-                                // Skip until we find the next newline token.
-                                while i < tokens.len() {
-                                    if tokens[i].kind == SyntaxKind::Newline {
-                                        break;
-                                    }
-
-                                    end = tokens[i].range.end;
-                                    i += 1;
-                                }
-
-                                processed_tokens.push(Token {
-                                    kind: SyntaxKind::Error,
-                                    text: &source[start..end],
-                                    range: start..end,
-                                });
-
-                                // Put the old indent back as an indentation
-                                // error doesn't indicate a dedent.
-                                indent_stack.push(old_indent);
-                                break 'emit_dedents;
-                            }
-                        }
-                    }
-                }
-            }
-        } else {
-            // Push the token as-is.
-            processed_tokens.push(curr_token);
-            i += 1;
+/// The default options are those [`tokenize`] uses.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TokenizeOptions {
+    /// Whether the whitespace, line breaks and comments are kept.
+    ///
+    /// Line breaks are only left out once the indentation is processed (if it
+    /// is), since it's worked out from them.
+    pub keep_trivia: bool,
+    /// Whether line breaks are turned into `Indent` and `Dedent` tokens where
+    /// the indentation changes, as the parser expects (see [`IndentLayer`]).
+    pub process_indentation: bool,
+    /// The most tokens to return, if any. The source text isn't tokenized any
+    /// further than it takes to find them, which saves tokenizing a whole file
+    /// when only its first few tokens are needed.
+    pub max_tokens: Option<usize>,
+    /// The edition the source text is written in, which determines which
+    /// keywords are lexed as identifiers.
+    pub edition: Edition,
+}
+
+impl Default for TokenizeOptions {
+    fn default() -> Self {
+        Self {
+            keep_trivia: true,
+            process_indentation: false,
+            max_tokens: None,
+            edition: Edition::default(),
         }
     }
+}
 
-    // Emit any remainder dedents required.
-    let end = processed_tokens.last().map(|t| t.range.end).unwrap_or(0);
-    while let Some(indent) = indent_stack.pop() {
-        // We won't emit a dedent token for the first column.
-        if indent == 0 {
-            break;
+/// Tokenizes the given source text like [`tokenize`], with the given options.
+///
+/// The messages are only about the tokens that were tokenized, along with
+/// their indentation if it's processed.
+pub fn tokenize_with_options(
+    file_id: FileId,
+    source: &str,
+    options: TokenizeOptions,
+) -> (Vec<Token<'_>>, Vec<Message>) {
+    let mut messages = Vec::new();
+    let lexer = Lexer::new(file_id, source).with_edition(options.edition);
+    let tokens = lexer.map(|(token, message)| {
+        // Number literals the lexer already reports (such as those with an
+        // invalid digit) aren't reported again for being invalid.
+        let is_number = matches!(
+            token.kind,
+            SyntaxKind::Lit_Integer | SyntaxKind::Lit_Float
+        );
+        let is_reported = message.is_some();
+        messages.extend(message);
+        if !(is_number && is_reported) {
+            messages.extend(validate_literal(file_id, &token));
         }
+        token
+    });
+
+    let tokens: Box<dyn Iterator<Item = LexerItem>> =
+        match options.process_indentation {
+            true => Box::new(IndentLayer::new(file_id, source, tokens)),
+            false => Box::new(tokens.map(|token| (token, None))),
+        };
+
+    let mut indent_messages = Vec::new();
+    let tokens = tokens
+        .filter_map(|(token, message)| {
+            indent_messages.extend(message);
+            (options.keep_trivia || !token.kind.is_trivia()).then_some(token)
+        })
+        .take(options.max_tokens.unwrap_or(usize::MAX))
+        .collect::<Vec<_>>();
+
+    messages.extend(indent_messages);
+    (tokens, messages)
+}
 
-        // Zero-width dedent token.
-        processed_tokens.push(Token::new(SyntaxKind::Dedent, "", end..end));
+/// Checks the escape sequences of the given string or character literal, or
+/// that the given number literal is valid (see [`validate_number`]).
+fn validate_literal(file_id: FileId, token: &Token) -> Vec<Message> {
+    match token.kind {
+        SyntaxKind::Lit_Character
+        | SyntaxKind::Lit_InterpolatedString
+        | SyntaxKind::Lit_String => validate_escapes(file_id, token),
+        SyntaxKind::Lit_Integer | SyntaxKind::Lit_Float => {
+            validate_number(file_id, token).into_iter().collect()
+        }
+        _ => Vec::new(),
     }
-
-    processed_tokens
 }
 
 /// The entry point of the parsing process.
@@ -183,36 +150,132 @@ pub fn process_indents<'source>(
 /// This function parses the given source text (a `&str`) and returns a
 /// [`Parse`], which holds a [`GreenNode`] tree describing the structure of a
 /// Helios program.
-pub fn parse<FileId>(file_id: FileId, source: &str) -> Parse<FileId>
-where
-    FileId: Clone + Default,
-{
-    let (tokens, mut messages) = tokenize(file_id.clone(), source);
-    let tokens = process_indents(source, tokens);
-    let source = Source::new(&tokens);
-
-    let parser = Parser::new(file_id, source);
-    let (events, parser_messages) = parser.parse();
-    let sink = Sink::new(&tokens, events);
-
-    messages.extend(parser_messages);
-    sink.finish(messages)
+pub fn parse(file_id: FileId, source: &str) -> Parse {
+    parse_with_cache(file_id, source, &mut NodeCache::default())
+}
+
+/// Parses the given source text like [`parse`], sharing the nodes and tokens
+/// of the syntax tree with every other tree built with the same cache.
+///
+/// Most tokens (such as keywords, symbols and whitespace) and many small nodes
+/// are the same across the files of a program, so parsing all of them with the
+/// same cache saves quite a lot of memory. The cache keeps everything it holds
+/// alive though, so it's best used for a set of files that doesn't change
+/// much (such as when building a program).
+pub fn parse_with_cache(
+    file_id: FileId,
+    source: &str,
+    cache: &mut NodeCache,
+) -> Parse {
+    parse_with_edition(file_id, source, Edition::default(), cache)
+}
+
+/// Parses the given source text like [`parse_with_cache`], as written in the
+/// given edition of the language.
+///
+/// The syntax tree of a source text is the same in every edition it's valid
+/// in, since the keywords an edition doesn't reserve are still read as
+/// keywords where they start a declaration (see [`Edition`]).
+pub fn parse_with_edition(
+    file_id: FileId,
+    source: &str,
+    edition: Edition,
+    cache: &mut NodeCache,
+) -> Parse {
+    parse_with_subscriber(file_id, source, edition, cache, &mut |_| {})
+}
+
+/// Parses the given source text like [`parse_with_edition`], passing every
+/// message to the given subscriber as soon as it's reported.
+///
+/// The messages of the lexer are reported once the source text has been
+/// tokenized, and the others as they're found, so the subscriber hears of them
+/// before the syntax tree is done (which is useful for showing them on the fly,
+/// such as while checking a large file). The returned [`Parse`] holds every
+/// message all the same.
+pub fn parse_with_subscriber(
+    file_id: FileId,
+    source: &str,
+    edition: Edition,
+    cache: &mut NodeCache,
+    subscriber: &mut dyn FnMut(&Message),
+) -> Parse {
+    let options = TokenizeOptions {
+        edition,
+        ..TokenizeOptions::default()
+    };
+    let (tokens, messages) = tokenize_with_options(file_id, source, options);
+    messages.iter().for_each(&mut *subscriber);
+    parse_tokens_with(file_id, source, tokens, messages, cache, subscriber)
+}
+
+/// Parses the given tokens of the given source text, as returned by
+/// [`tokenize`].
+///
+/// This saves lexing the source text again when the tokens are already at hand
+/// (or were relexed incrementally). The returned [`Parse`] doesn't hold the
+/// messages of the lexer though, since [`tokenize`] returns them already.
+pub fn parse_tokens<'source>(
+    file_id: FileId,
+    source: &'source str,
+    tokens: Vec<Token<'source>>,
+) -> Parse {
+    parse_tokens_with(
+        file_id,
+        source,
+        tokens,
+        Vec::new(),
+        &mut NodeCache::default(),
+        &mut |_| {},
+    )
+}
+
+/// Processes the indentation of the given tokens and parses them, adding the
+/// messages reported along the way to the given ones.
+fn parse_tokens_with<'source>(
+    file_id: FileId,
+    source: &'source str,
+    tokens: Vec<Token<'source>>,
+    mut messages: Vec<Message>,
+    cache: &mut NodeCache,
+    subscriber: &mut dyn FnMut(&Message),
+) -> Parse {
+    let mut report = |message: Message| {
+        subscriber(&message);
+        messages.push(message);
+    };
+
+    let tokens = IndentLayer::new(file_id, source, tokens.into_iter())
+        .map(|(token, message)| {
+            message.into_iter().for_each(&mut report);
+            token
+        })
+        .collect::<Vec<_>>();
+
+    let parser = Parser::new(file_id, Source::new(&tokens), &mut report);
+    let events = parser.parse();
+    Sink::new(&tokens, events, cache).finish(messages)
 }
 
 /// The result of parsing a source text.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Parse<FileId> {
+pub struct Parse {
     /// The root green node of the syntax tree.
     green_node: GreenNode,
-    messages: Vec<Message<FileId>>,
+    /// The messages reported while parsing, which are shared between clones
+    /// (as the result of a query, a [`Parse`] is cloned quite a lot).
+    messages: Arc<[Message]>,
 }
 
-impl<FileId> Parse<FileId> {
-    /// Construct a [`Parse`] with the given [`GreenNode`].
-    pub fn new(green_node: GreenNode, messages: Vec<Message<FileId>>) -> Self {
+impl Parse {
+    /// Construct a [`Parse`] with the given [`GreenNode`] and messages.
+    pub fn new(
+        green_node: GreenNode,
+        messages: impl Into<Arc<[Message]>>,
+    ) -> Self {
         Self {
             green_node,
-            messages,
+            messages: messages.into(),
         }
     }
 
@@ -220,20 +283,105 @@ impl<FileId> Parse<FileId> {
         SyntaxNode::new_root(self.green_node.clone())
     }
 
-    pub fn messages(&self) -> &[Message<FileId>] {
+    pub fn messages(&self) -> &[Message] {
         &self.messages
     }
 
+    /// Returns the messages reported while parsing, without copying them.
+    pub fn shared_messages(&self) -> Arc<[Message]> {
+        Arc::clone(&self.messages)
+    }
+
     /// Returns a formatted string representation of the syntax tree.
     pub fn debug_tree(&self) -> String {
         let syntax_node = SyntaxNode::new_root(self.green_node.clone());
         format!("{:#?}", syntax_node)
     }
+
+    /// Counts the nodes and tokens of the syntax tree, which helps with
+    /// spotting inputs the parser copes badly with (such as deeply nested
+    /// expressions, or code it gives up on).
+    pub fn stats(&self) -> ParseStats {
+        let mut stats = ParseStats::default();
+        let mut depth = 0;
+        for event in self.syntax().preorder_with_tokens() {
+            match event {
+                WalkEvent::Enter(element) => match element.into_node() {
+                    Some(node) => {
+                        stats.nodes += 1;
+                        if node.kind() == SyntaxKind::Error {
+                            stats.error_nodes += 1;
+                        }
+                        depth += 1;
+                        stats.max_depth = stats.max_depth.max(depth);
+                    }
+                    None => stats.tokens += 1,
+                },
+                WalkEvent::Leave(element) => {
+                    if element.as_node().is_some() {
+                        depth -= 1;
+                    }
+                }
+            }
+        }
+
+        stats
+    }
+
+    /// Checks that the syntax tree holds exactly the given source text, which
+    /// it always should: the parser never drops or adds any text, not even
+    /// when it runs into errors.
+    ///
+    /// # Panics
+    ///
+    /// Panics (with the first offset the texts differ at) if the text of the
+    /// syntax tree isn't the same as the source text.
+    pub fn verify_lossless(&self, source: &str) {
+        let text = self.syntax().text().to_string();
+        let offset = text
+            .bytes()
+            .zip(source.bytes())
+            .position(|(actual, expected)| actual != expected)
+            .or_else(|| {
+                (text.len() != source.len())
+                    .then(|| text.len().min(source.len()))
+            });
+
+        if let Some(offset) = offset {
+            let snippet = |text: &str| {
+                let end = (offset + 16).min(text.len());
+                String::from_utf8_lossy(&text.as_bytes()[offset..end])
+                    .into_owned()
+            };
+            panic!(
+                "The syntax tree differs from the source text at offset \
+                {offset} (expected {:?}, found {:?})",
+                snippet(source),
+                snippet(&text)
+            );
+        }
+    }
+}
+
+/// The size and shape of a syntax tree (see [`Parse::stats`]).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ParseStats {
+    /// The number of nodes, including the root.
+    pub nodes: usize,
+    /// The number of tokens, including whitespace and comments.
+    pub tokens: usize,
+    /// The most nodes nested in each other (which is `1` for a tree that is
+    /// just a root).
+    pub max_depth: usize,
+    /// The number of `Error` nodes, which hold what the parser couldn't make
+    /// sense of.
+    pub error_nodes: usize,
 }
 
 #[cfg(test)]
 fn check(input: &str, expected_tree: expect_test::Expect) {
-    let parse = parse(0u8, input);
+    let parse = parse(FileId(0), input);
+    parse.verify_lossless(input);
     expected_tree.assert_eq(&parse.debug_tree());
 }
 
@@ -242,8 +390,10 @@ mod tests {
     use super::*;
 
     fn check(input: &str, expected_tokens: Vec<Token>) {
-        let (tokens, _) = tokenize(0u8, input);
-        let tokens = process_indents(input, tokens);
+        let (tokens, _) = tokenize(FileId(0), input);
+        let tokens = IndentLayer::new(FileId(0), input, tokens.into_iter())
+            .map(|(token, _)| token)
+            .collect::<Vec<_>>();
         assert_eq!(tokens, expected_tokens);
     }
 
@@ -290,4 +440,217 @@ mod tests {
             ],
         );
     }
+
+    #[test]
+    fn test_tokenize_crlf_input() {
+        check(
+            "let\r\n  x = 1 \r\n  y\r\n",
+            vec![
+                Token::new(SyntaxKind::Kwd_Let, "let", 0..3),
+                Token::new(SyntaxKind::Indent, "\r\n  ", 3..7),
+                Token::new(SyntaxKind::Identifier, "x", 7..8),
+                Token::new(SyntaxKind::Whitespace, " ", 8..9),
+                Token::new(SyntaxKind::Sym_Eq, "=", 9..10),
+                Token::new(SyntaxKind::Whitespace, " ", 10..11),
+                Token::new(SyntaxKind::Lit_Integer, "1", 11..12),
+                Token::new(SyntaxKind::Whitespace, " ", 12..13),
+                Token::new(SyntaxKind::Newline, "\r\n  ", 13..17),
+                Token::new(SyntaxKind::Identifier, "y", 17..18),
+                Token::new(SyntaxKind::Newline, "\r\n", 18..20),
+                Token::new(SyntaxKind::Dedent, "", 20..20),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_tokenize_with_options() {
+        let source = "let a =\n  1 # one\nlet b = 1e999\n";
+        let check = |keep_trivia, process_indentation, max_tokens| {
+            let options = TokenizeOptions {
+                keep_trivia,
+                process_indentation,
+                max_tokens,
+                ..TokenizeOptions::default()
+            };
+            let (tokens, messages) =
+                tokenize_with_options(FileId(0), source, options);
+            let kinds = tokens.iter().map(|token| token.kind);
+            (kinds.collect::<Vec<_>>(), messages.len())
+        };
+
+        assert_eq!(
+            check(false, false, None).0,
+            [
+                SyntaxKind::Kwd_Let,
+                SyntaxKind::Identifier,
+                SyntaxKind::Sym_Eq,
+                SyntaxKind::Lit_Integer,
+                SyntaxKind::Kwd_Let,
+                SyntaxKind::Identifier,
+                SyntaxKind::Sym_Eq,
+                SyntaxKind::Lit_Float,
+            ]
+        );
+        assert_eq!(
+            check(false, true, None).0,
+            [
+                SyntaxKind::Kwd_Let,
+                SyntaxKind::Identifier,
+                SyntaxKind::Sym_Eq,
+                SyntaxKind::Indent,
+                SyntaxKind::Lit_Integer,
+                SyntaxKind::Dedent,
+                SyntaxKind::Kwd_Let,
+                SyntaxKind::Identifier,
+                SyntaxKind::Sym_Eq,
+                SyntaxKind::Lit_Float,
+            ]
+        );
+
+        // The messages of the tokens past the limit aren't reported, since
+        // they're never tokenized.
+        assert_eq!(check(true, false, None).1, 1);
+        assert_eq!(
+            check(true, false, Some(3)),
+            (
+                vec![
+                    SyntaxKind::Kwd_Let,
+                    SyntaxKind::Whitespace,
+                    SyntaxKind::Identifier
+                ],
+                0
+            )
+        );
+        assert_eq!(
+            check(true, false, None).0,
+            tokenize(FileId(0), source)
+                .0
+                .iter()
+                .map(|token| token.kind)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_parse_stats() {
+        let parse = parse(FileId(0), "let a = (1 +\n        2 +\n      3)\n");
+        assert_eq!(
+            parse.stats(),
+            ParseStats {
+                nodes: 11,
+                tokens: 18,
+                max_depth: 5,
+                error_nodes: 3,
+            }
+        );
+
+        let parse = crate::parse(FileId(0), "");
+        assert_eq!(
+            parse.stats(),
+            ParseStats {
+                nodes: 1,
+                tokens: 0,
+                max_depth: 1,
+                error_nodes: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_crlf() {
+        // Files with CRLF line breaks parse just like the ones with LF line
+        // breaks.
+        let source = "## Doc\nlet a =\n  1 + # one\n\n    2\nlet b = \"b\n";
+        let kinds = |source: &str| {
+            let parse = parse(FileId(0), source);
+            let kinds = parse.syntax().descendants_with_tokens();
+            let kinds = kinds.map(|element| element.kind());
+            (kinds.collect::<Vec<_>>(), parse.messages().len())
+        };
+
+        assert_eq!(kinds(&source.replace('\n', "\r\n")), kinds(source));
+    }
+
+    #[test]
+    fn test_parse_with_cache() {
+        let mut cache = NodeCache::default();
+        let a =
+            parse_with_cache(FileId(0), "let a = 1 + b\n", &mut cache).syntax();
+        let b =
+            parse_with_cache(FileId(0), "let b = 2 + b\n", &mut cache).syntax();
+        assert_eq!(a.text(), "let a = 1 + b\n");
+
+        // Both trees share the green data of their `let` keywords...
+        let first_token =
+            |root: &SyntaxNode| root.first_token().unwrap().green().to_owned();
+        assert!(std::ptr::eq(&*first_token(&a), &*first_token(&b)));
+
+        // ... and of their variable references.
+        let last_node = |root: &SyntaxNode| {
+            root.descendants().last().unwrap().green().into_owned()
+        };
+        assert!(std::ptr::eq(&*last_node(&a), &*last_node(&b)));
+    }
+
+    #[test]
+    fn test_parse_tokens() {
+        let source = "let a = 'ab'\n  let b =\n";
+        let (tokens, lexer_messages) = tokenize(FileId(0), source);
+        let parse = parse_tokens(FileId(0), source, tokens);
+        let expected = super::parse(FileId(0), source);
+        assert_eq!(parse.debug_tree(), expected.debug_tree());
+
+        // The messages of the lexer are left out.
+        let mut messages = lexer_messages;
+        messages.extend_from_slice(parse.messages());
+        assert_eq!(messages, expected.messages());
+    }
+
+    #[test]
+    fn test_parse_with_subscriber() {
+        let source = "let a = 'ab'\n  let b =\n";
+        let mut reported = Vec::new();
+        let parse = parse_with_subscriber(
+            FileId(0),
+            source,
+            Edition::default(),
+            &mut NodeCache::default(),
+            &mut |message| reported.push(message.clone()),
+        );
+
+        // The subscriber hears of every message (lexer, indentation and parser
+        // ones alike), in the order they're reported.
+        assert_eq!(reported.len(), 3);
+        assert_eq!(reported, parse.messages());
+        assert_eq!(
+            parse.messages(),
+            super::parse(FileId(0), source).messages()
+        );
+
+        // Clones share the messages.
+        let clone = parse.clone();
+        assert!(Arc::ptr_eq(
+            &parse.shared_messages(),
+            &clone.shared_messages()
+        ));
+    }
+
+    #[test]
+    fn test_verify_lossless() {
+        let source = "let x = (1 +\n  ~ \"unterminated";
+        parse(FileId(0), source).verify_lossless(source);
+        parse(FileId(0), "").verify_lossless("");
+    }
+
+    #[test]
+    #[should_panic(expected = "differs from the source text at offset 4")]
+    fn test_verify_lossless_with_different_text() {
+        parse(FileId(0), "let x = 1").verify_lossless("let y = 1");
+    }
+
+    #[test]
+    #[should_panic(expected = "differs from the source text at offset 9")]
+    fn test_verify_lossless_with_missing_text() {
+        parse(FileId(0), "let x = 1").verify_lossless("let x = 10");
+    }
 }
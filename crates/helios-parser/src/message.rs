@@ -1,17 +1,16 @@
-use helios_diagnostics::{Diagnostic, Location};
+use helios_diagnostics::{Diagnostic, FileId, Location};
 use helios_formatting::FormattedString;
 use helios_syntax::SyntaxKind;
 
+use crate::confusable::{confusable, Confusable};
+
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Message<FileId> {
+pub struct Message {
     kind: MessageKind,
     location: Location<FileId>,
 }
 
-impl<FileId> Message<FileId>
-where
-    FileId: Clone + Default,
-{
+impl Message {
     pub fn new(
         kind: impl Into<MessageKind>,
         location: Location<FileId>,
@@ -22,6 +21,18 @@ where
         }
     }
 
+    /// Whether the message is about an error, rather than about something
+    /// that was understood anyway (such as a confusable character).
+    pub fn is_error(&self) -> bool {
+        !matches!(
+            self.kind,
+            MessageKind::Lexer(
+                LexerMessage::ConfusableCharacter(_)
+                    | LexerMessage::FloatOutOfRange
+            )
+        )
+    }
+
     pub fn generate_diagnostic(&self) -> Diagnostic<FileId> {
         match &self.kind {
             MessageKind::Lexer(it) => it.diagnostic(self.location.clone()),
@@ -30,20 +41,14 @@ where
     }
 }
 
-impl<FileId> From<Message<FileId>> for Diagnostic<FileId>
-where
-    FileId: Clone + Default,
-{
-    fn from(message: Message<FileId>) -> Self {
+impl From<Message> for Diagnostic<FileId> {
+    fn from(message: Message) -> Self {
         message.generate_diagnostic()
     }
 }
 
-impl<FileId> From<&Message<FileId>> for Diagnostic<FileId>
-where
-    FileId: Clone + Default,
-{
-    fn from(message: &Message<FileId>) -> Self {
+impl From<&Message> for Diagnostic<FileId> {
+    fn from(message: &Message) -> Self {
         message.generate_diagnostic()
     }
 }
@@ -69,17 +74,46 @@ impl From<ParserMessage> for MessageKind {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum LexerMessage {
     UnknownCharacter(char),
-    InvalidIndentation { expected: usize, found: usize },
+    /// A character that looks like an ASCII one, which is read as if it was
+    /// (see [`crate::confusable`]).
+    ConfusableCharacter(char),
+    UnterminatedBlockComment,
+    UnterminatedString,
+    UnterminatedInterpolation,
+    EmptyCharacter,
+    /// A character literal holding more than one character (such as `'ab'`).
+    MultipleCharacters,
+    UnterminatedCharacter,
+    MalformedExponent,
+    /// A float literal so large that it rounds to infinity.
+    FloatOutOfRange,
+    /// A number literal that doesn't have a value (such as `123abc`), which
+    /// isn't reported by anything more specific.
+    InvalidNumber,
+    InvalidDigitForBase {
+        digit: char,
+        radix: u32,
+    },
+    /// A `0b`, `0o` or `0x` prefix without any digits after it.
+    MissingDigitsForBase {
+        radix: u32,
+    },
+    UnknownEscape(char),
+    InvalidAsciiEscape,
+    InvalidUnicodeEscape,
+    /// A tab in the indentation of a line, whose whitespace is as wide as the
+    /// given number of spaces.
+    TabIndentation {
+        width: usize,
+    },
+    InvalidIndentation {
+        expected: usize,
+        found: usize,
+    },
 }
 
 impl LexerMessage {
-    pub fn diagnostic<FileId>(
-        &self,
-        location: Location<FileId>,
-    ) -> Diagnostic<FileId>
-    where
-        FileId: Default,
-    {
+    pub fn diagnostic(&self, location: Location<FileId>) -> Diagnostic<FileId> {
         match self {
             LexerMessage::UnknownCharacter(character) => {
                 let description = FormattedString::default()
@@ -95,13 +129,343 @@ impl LexerMessage {
                     .description(description)
                     .message(message)
             }
-            LexerMessage::InvalidIndentation { .. } => {
-                todo!()
+            LexerMessage::ConfusableCharacter(character) => {
+                let Confusable { intended, name } = confusable(*character)
+                    .expect("only confusable characters are reported");
+                // Spaces don't show up well in code spans.
+                let intended_text = |string: FormattedString| match intended {
+                    ' ' => string.text("a space"),
+                    _ => string.code(intended.to_string()),
+                };
+
+                let description = FormattedString::default()
+                    .text("This character looks like an ASCII one, but isn't:");
+
+                let message = FormattedString::default().text(format!(
+                    "This is U+{:04X} (the {name}), which I read as ",
+                    *character as u32
+                ));
+                let message = intended_text(message)
+                    .text(" since it looks just like one.");
+
+                let hint = intended_text(
+                    FormattedString::default().text("Try replacing it with "),
+                )
+                .text(" instead.");
+
+                let fix = match intended {
+                    ' ' => "Replace with a space".to_string(),
+                    _ => format!("Replace with `{intended}`"),
+                };
+
+                Diagnostic::warning("Confusable character")
+                    .location(location.clone())
+                    .description(description)
+                    .message(message)
+                    .hint(hint)
+                    .machine_applicable_fix(fix, location, intended.to_string())
+            }
+            LexerMessage::UnterminatedBlockComment => {
+                let description = FormattedString::default()
+                    .text("This block comment is never closed:");
+
+                let message = FormattedString::default()
+                    .text("Block comments start with ")
+                    .code("#[")
+                    .text(" and end with a matching ")
+                    .code("]#")
+                    .text(", which must be closed once for every block ")
+                    .text("comment nested inside them.");
+
+                Diagnostic::error("Unterminated block comment")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
+            LexerMessage::UnterminatedString => {
+                let description = FormattedString::default()
+                    .text("This string is missing its closing quote:");
+
+                let message = FormattedString::default()
+                    .text("Strings must end with a ")
+                    .code("\"")
+                    .text(" on the same line they start on.");
+
+                Diagnostic::error("Unterminated string")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
+            LexerMessage::UnterminatedInterpolation => {
+                let description = FormattedString::default().text(
+                    "This embedded expression is missing its closing brace:",
+                );
+
+                let message = FormattedString::default()
+                    .text("Expressions embedded in a string must end with a ")
+                    .code("}")
+                    .text(" on the same line they start on. To write a ")
+                    .code("{")
+                    .text(" in the string instead, double it (")
+                    .code("{{")
+                    .text(").");
+
+                Diagnostic::error("Unterminated interpolation")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
+            LexerMessage::MultipleCharacters => {
+                let description = FormattedString::default().text(
+                    "This character literal holds more than one character:",
+                );
+
+                let message = FormattedString::default()
+                    .text("A character literal must contain exactly one ")
+                    .text("character (such as ")
+                    .code("'a'")
+                    .text(").");
+
+                let hint = FormattedString::default()
+                    .text("Text made of several characters is written as a ")
+                    .text("string literal instead (such as ")
+                    .code("\"ab\"")
+                    .text(").");
+
+                Diagnostic::error("Multiple characters")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+                    .hint(hint)
+            }
+            LexerMessage::EmptyCharacter => {
+                let description = FormattedString::default()
+                    .text("This character literal doesn't hold a character:");
+
+                let message = FormattedString::default()
+                    .text("Character literals hold exactly one character ")
+                    .text("(such as ")
+                    .code("'a'")
+                    .text("). Did you mean to write ")
+                    .code("'\\''")
+                    .text("?");
+
+                Diagnostic::error("Empty character")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
+            LexerMessage::UnterminatedCharacter => {
+                let description = FormattedString::default().text(
+                    "This character literal is missing its closing quote:",
+                );
+
+                let message = FormattedString::default()
+                    .text("Character literals hold exactly one character and ")
+                    .text("must end with a ")
+                    .code("'")
+                    .text(" right after it.");
+
+                Diagnostic::error("Unterminated character")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
+            LexerMessage::MalformedExponent => {
+                let description = FormattedString::default()
+                    .text("This float literal has a malformed exponent:");
+
+                let message = FormattedString::default()
+                    .text("An exponent is made of an ")
+                    .code("e")
+                    .text(" (or ")
+                    .code("E")
+                    .text("), an optional ")
+                    .code("+")
+                    .text(" or ")
+                    .code("-")
+                    .text(" sign and at least one digit (such as ")
+                    .code("1e-9")
+                    .text(").");
+
+                Diagnostic::error("Malformed exponent")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
+            LexerMessage::FloatOutOfRange => {
+                let description = FormattedString::default()
+                    .text("This float literal is too large to be stored:");
+
+                let message = FormattedString::default()
+                    .text("The literal is larger than the largest ")
+                    .code("Float")
+                    .text(", so I'll round it to infinity.");
+
+                let hint = FormattedString::default()
+                    .text("A ")
+                    .code("Float")
+                    .text(" can only hold numbers up to about ")
+                    .code("1.8e308")
+                    .text(".");
+
+                Diagnostic::warning("Float literal out of range")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+                    .hint(hint)
+            }
+            LexerMessage::InvalidNumber => {
+                let description = FormattedString::default()
+                    .text("This number literal is malformed:");
+
+                let message = FormattedString::default()
+                    .text("Integer literals are made of digits, and float ")
+                    .text("literals of digits around a ")
+                    .code(".")
+                    .text(" followed by an optional exponent (such as ")
+                    .code("6.02e23")
+                    .text("). Digits may be separated by ")
+                    .code("_")
+                    .text(", but nothing else can be part of a number.");
+
+                Diagnostic::error("Invalid number literal")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
+            LexerMessage::InvalidDigitForBase { digit, radix } => {
+                let (base, prefix, digits) = base_of_radix(*radix);
+
+                let description = FormattedString::default().text(format!(
+                    "This digit isn't valid in a {base} integer:"
+                ));
+
+                let message = FormattedString::default()
+                    .text("Integer literals starting with ")
+                    .code(prefix)
+                    .text(format!(" can only use the digits {digits}, so "))
+                    .code(format!("{:?}", digit))
+                    .text(" can't be part of one.");
+
+                Diagnostic::error("Invalid digit for base")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
+            LexerMessage::MissingDigitsForBase { radix } => {
+                let (base, prefix, digits) = base_of_radix(*radix);
+
+                let description = FormattedString::default()
+                    .text(format!("This {base} integer has no digits:"));
+
+                let message = FormattedString::default()
+                    .text("I expected digits after the ")
+                    .code(prefix)
+                    .text(format!(" prefix (using {digits})."));
+
+                Diagnostic::error("Missing digits for base")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
+            LexerMessage::UnknownEscape(character) => {
+                let description = FormattedString::default()
+                    .text("I don't know this escape sequence:");
+
+                let message = FormattedString::default()
+                    .text("A backslash can't escape ")
+                    .code(format!("{:?}", character))
+                    .text(". Did you mean to write ")
+                    .code("\\\\")
+                    .text(" (an escaped backslash)?");
+
+                Diagnostic::error("Unknown escape sequence")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
+            LexerMessage::InvalidAsciiEscape => {
+                let description = FormattedString::default()
+                    .text("This ASCII escape isn't valid:");
+
+                let message = FormattedString::default()
+                    .text("ASCII escapes are made of two hexadecimal digits ")
+                    .text("up to ")
+                    .code("7F")
+                    .text(" (such as ")
+                    .code("\\x41")
+                    .text(").");
+
+                Diagnostic::error("Invalid ASCII escape")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
+            LexerMessage::InvalidUnicodeEscape => {
+                let description = FormattedString::default()
+                    .text("This Unicode escape isn't valid:");
+
+                let message = FormattedString::default()
+                    .text("Unicode escapes are made of up to six hexadecimal ")
+                    .text("digits in braces (such as ")
+                    .code("\\u{1F600}")
+                    .text("), which must be a Unicode scalar value.");
+
+                Diagnostic::error("Invalid Unicode escape")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
+            LexerMessage::TabIndentation { width } => {
+                let description = FormattedString::default()
+                    .text("This line is indented with a tab:");
+
+                let message = FormattedString::default()
+                    .text("Only spaces can indent lines, so that they always ")
+                    .text("line up the same way. Try replacing the tab with ")
+                    .text(format!(
+                        "spaces (it's counted as going to the next multiple \
+                        of {} columns for now).",
+                        crate::indent::TAB_WIDTH
+                    ));
+
+                Diagnostic::error("Tab in indentation")
+                    .location(location.clone())
+                    .description(description)
+                    .message(message)
+                    .fix("Replace with spaces", location, " ".repeat(*width))
+            }
+            LexerMessage::InvalidIndentation { expected, found } => {
+                let description = FormattedString::default().text(
+                    "This line doesn't line up with any enclosing block:",
+                );
+
+                let message = FormattedString::default().text(format!(
+                    "This line is indented by {found} spaces, but the \
+                    block it goes back to is indented by {expected}. \
+                    Dedented lines must line up with a block they're in."
+                ));
+
+                Diagnostic::error("Invalid indentation")
+                    .location(location)
+                    .description(description)
+                    .message(message)
             }
         }
     }
 }
 
+/// The name, prefix and digits of the base of integer literals with the given
+/// radix (which isn't 10).
+fn base_of_radix(radix: u32) -> (&'static str, &'static str, &'static str) {
+    match radix {
+        2 => ("binary", "0b", "0 and 1"),
+        8 => ("octal", "0o", "0 to 7"),
+        _ => ("hexadecimal", "0x", "0 to 9 and a to f (in either case)"),
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ParserMessage {
     MissingKind {
@@ -116,13 +480,7 @@ pub enum ParserMessage {
 }
 
 impl ParserMessage {
-    pub fn diagnostic<FileId>(
-        &self,
-        location: Location<FileId>,
-    ) -> Diagnostic<FileId>
-    where
-        FileId: Default,
-    {
+    pub fn diagnostic(&self, location: Location<FileId>) -> Diagnostic<FileId> {
         match self {
             ParserMessage::MissingKind { context, expected } => {
                 let error = format!(
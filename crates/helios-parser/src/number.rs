@@ -0,0 +1,154 @@
+//! Working out the values of integer and float literals.
+//!
+//! The lexer only finds where number literals end, so that a literal with an
+//! invalid digit is still a single token. Their values are instead read here,
+//! for anything that needs them (from lowering to hovering over a literal).
+
+use helios_diagnostics::{FileId, Location};
+use helios_syntax::SyntaxKind;
+use std::num::IntErrorKind;
+
+use crate::lexer::Token;
+use crate::message::{LexerMessage, Message};
+
+/// The value of an integer or float literal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Number {
+    Integer(u64),
+    Float(f64),
+}
+
+/// Why the value of a number literal couldn't be worked out.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NumberError {
+    /// The literal has characters that aren't digits of its base, or a
+    /// malformed exponent.
+    Invalid,
+    /// The value doesn't fit in 64 bits, which means it's larger than
+    /// [`u64::MAX`] for integers, or rounds to infinity for floats.
+    OutOfRange,
+}
+
+/// Works out the value of the given integer or float literal (of the given
+/// kind), or `None` if it's another kind of token.
+///
+/// Integer literals may be written in binary, octal or hexadecimal (with the
+/// `0b`, `0o` and `0x` prefixes), and underscores between the digits of any
+/// literal are ignored.
+pub fn number_value(
+    kind: SyntaxKind,
+    text: &str,
+) -> Option<Result<Number, NumberError>> {
+    let text = text.replace('_', "");
+    let value = match kind {
+        SyntaxKind::Lit_Integer => {
+            let (digits, radix) = match text.get(..2) {
+                Some("0b") => (&text[2..], 2),
+                Some("0o") => (&text[2..], 8),
+                Some("0x") => (&text[2..], 16),
+                _ => (text.as_str(), 10),
+            };
+
+            match u64::from_str_radix(digits, radix) {
+                Ok(value) => Ok(Number::Integer(value)),
+                Err(error) if *error.kind() == IntErrorKind::PosOverflow => {
+                    Err(NumberError::OutOfRange)
+                }
+                Err(_) => Err(NumberError::Invalid),
+            }
+        }
+        // Only digits, a dot and an exponent make up a valid float literal,
+        // so the words Rust also parses as floats (such as `inf`) can't come
+        // up here.
+        SyntaxKind::Lit_Float => match text.parse::<f64>() {
+            Ok(value) if value.is_infinite() => Err(NumberError::OutOfRange),
+            Ok(value) => Ok(Number::Float(value)),
+            Err(_) => Err(NumberError::Invalid),
+        },
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+/// Checks that the given number literal has a value, and that it fits in a
+/// `Float` if it's a float literal, returning a message if it doesn't.
+///
+/// Integer literals are checked when evaluating constants instead, since
+/// whether they fit in an `Int` depends on whether they're negated.
+pub fn validate_number(file_id: FileId, token: &Token) -> Option<Message> {
+    let message = match (token.kind, number_value(token.kind, token.text)?) {
+        (SyntaxKind::Lit_Float, Err(NumberError::OutOfRange)) => {
+            LexerMessage::FloatOutOfRange
+        }
+        (_, Err(NumberError::Invalid)) => LexerMessage::InvalidNumber,
+        _ => return None,
+    };
+
+    Some(Message::new(
+        message,
+        Location::new(file_id, token.range.clone()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_value() {
+        let check = |kind, text| number_value(kind, text).unwrap();
+
+        assert_eq!(
+            check(SyntaxKind::Lit_Integer, "1_000"),
+            Ok(Number::Integer(1000))
+        );
+        assert_eq!(
+            check(SyntaxKind::Lit_Integer, "0xff_ff"),
+            Ok(Number::Integer(0xffff))
+        );
+        assert_eq!(
+            check(SyntaxKind::Lit_Integer, "0b101"),
+            Ok(Number::Integer(5))
+        );
+        assert_eq!(
+            check(SyntaxKind::Lit_Integer, "0o17"),
+            Ok(Number::Integer(15))
+        );
+        assert_eq!(
+            check(SyntaxKind::Lit_Float, "6.02e2_3"),
+            Ok(Number::Float(6.02e23))
+        );
+        assert_eq!(check(SyntaxKind::Lit_Float, "1."), Ok(Number::Float(1.0)));
+
+        // Values that don't fit in 64 bits.
+        assert_eq!(
+            check(SyntaxKind::Lit_Integer, "0xffff_ffff_ffff_ffff"),
+            Ok(Number::Integer(u64::MAX))
+        );
+        assert_eq!(
+            check(SyntaxKind::Lit_Integer, "18446744073709551616"),
+            Err(NumberError::OutOfRange)
+        );
+        assert_eq!(
+            check(SyntaxKind::Lit_Float, "1e400"),
+            Err(NumberError::OutOfRange)
+        );
+
+        // Invalid literals.
+        assert_eq!(
+            check(SyntaxKind::Lit_Integer, "0b102"),
+            Err(NumberError::Invalid)
+        );
+        assert_eq!(
+            check(SyntaxKind::Lit_Integer, "1z2y3x"),
+            Err(NumberError::Invalid)
+        );
+        assert_eq!(
+            check(SyntaxKind::Lit_Float, "1.5e"),
+            Err(NumberError::Invalid)
+        );
+
+        assert_eq!(number_value(SyntaxKind::Identifier, "a"), None);
+    }
+}
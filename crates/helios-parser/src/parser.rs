@@ -5,39 +5,44 @@ pub(crate) mod marker;
 pub(crate) mod sink;
 pub(crate) mod source;
 
-use helios_diagnostics::Location;
+use helios_diagnostics::{FileId, Location};
 use helios_syntax::SyntaxKind;
 
 use self::event::Event;
 use self::marker::Marker;
 use self::source::Source;
-use crate::lexer::Token;
+use crate::lexer::{is_unterminated, Token};
 use crate::{Message, ParserMessage};
 
-const RECOVERY_SET: [SyntaxKind; 1] = [SyntaxKind::Kwd_Let];
+const RECOVERY_SET: [SyntaxKind; 2] =
+    [SyntaxKind::Kwd_Import, SyntaxKind::Kwd_Let];
 
 /// A lazy, lossless, error-tolerant parser for the Helios programming language.
-pub struct Parser<'source, 'tokens, FileId> {
+pub struct Parser<'source, 'tokens, 'report> {
     file_id: FileId,
     source: Source<'tokens, 'source>,
     events: Vec<Event>,
     expected_kinds: Vec<SyntaxKind>,
-    messages: Vec<Message<FileId>>,
+    /// Whether the last token added to the tree is an unterminated string.
+    after_unterminated: bool,
+    report: &'report mut dyn FnMut(Message),
 }
 
-impl<'source, 'tokens, FileId> Parser<'source, 'tokens, FileId>
-where
-    FileId: Clone + Default,
-{
-    /// Constructs a new [`Parser`] with a [`Source`] and a channel that sends
-    /// [`Diagnostic`]s.
-    pub fn new(file_id: FileId, source: Source<'tokens, 'source>) -> Self {
+impl<'source, 'tokens, 'report> Parser<'source, 'tokens, 'report> {
+    /// Constructs a new [`Parser`] with a [`Source`] and a function that's
+    /// called with every message as soon as it's reported.
+    pub fn new(
+        file_id: FileId,
+        source: Source<'tokens, 'source>,
+        report: &'report mut dyn FnMut(Message),
+    ) -> Self {
         Self {
             file_id,
             source,
             events: Vec::new(),
             expected_kinds: Vec::new(),
-            messages: Vec::new(),
+            after_unterminated: false,
+            report,
         }
     }
 
@@ -45,19 +50,18 @@ where
     ///
     /// This function will attempt to build a concrete syntax tree with the
     /// given source text (no matter how invalid it is). Once done, it will
-    /// return a [`Parse`] containing a root green node.
+    /// return the events describing the tree, which a [`Sink`] turns into a
+    /// [`Parse`].
     ///
     /// [`Parse`]: crate::Parse
-    pub fn parse(mut self) -> (Vec<Event>, Vec<Message<FileId>>) {
+    /// [`Sink`]: self::sink::Sink
+    pub fn parse(mut self) -> Vec<Event> {
         crate::grammar::root(&mut self);
-        (self.events, self.messages)
+        self.events
     }
 }
 
-impl<'source, 'tokens, FileId> Parser<'source, 'tokens, FileId>
-where
-    FileId: Clone + Default,
-{
+impl<'source, 'tokens, 'report> Parser<'source, 'tokens, 'report> {
     /// Determines if the next [`SyntaxKind`] is the given `kind`.
     pub(crate) fn is_at(&mut self, kind: SyntaxKind) -> bool {
         self.expected_kinds.push(kind);
@@ -73,6 +77,36 @@ where
             .and_then(|kind| kinds.iter().find(|&&it| kind == it))
     }
 
+    /// Determines if the next token is the given keyword, which may be a soft
+    /// keyword lexed as an identifier (see [`Edition`]). An identifier only
+    /// counts as a soft keyword if it's followed by `next` on the same line,
+    /// so that it's still read as a name anywhere else.
+    ///
+    /// The keyword is added with [`Parser::bump_as`].
+    ///
+    /// [`Edition`]: crate::Edition
+    pub(crate) fn is_at_soft_keyword(
+        &mut self,
+        keyword: SyntaxKind,
+        next: SyntaxKind,
+    ) -> bool {
+        if self.is_at(keyword) {
+            return true;
+        }
+
+        let spelled = self.source.peek_token().is_some_and(|token| {
+            token.kind == SyntaxKind::Identifier
+                && helios_syntax::keyword_from_str(token.text) == Some(keyword)
+        });
+        spelled && self.source.peek_kind_after_next() == Some(next)
+    }
+
+    /// Determines if the next token is the given `kind`, with nothing (not even
+    /// whitespace) between it and the previous token.
+    pub(crate) fn is_right_at(&mut self, kind: SyntaxKind) -> bool {
+        self.source.peek_kind_raw() == Some(kind)
+    }
+
     /// Peeks the next [`SyntaxKind`] token without consuming it.
     fn peek(&mut self) -> Option<SyntaxKind> {
         self.source.peek_kind()
@@ -81,10 +115,20 @@ where
     /// Adds the next token to the syntax tree (via the [`GreenNodeBuilder`]).
     pub(crate) fn bump(&mut self) {
         self.expected_kinds.clear();
-        self.source.next_token().unwrap();
+        let token = self.source.next_token().unwrap();
+        self.after_unterminated = is_unterminated(token);
         self.events.push(Event::AddToken)
     }
 
+    /// Adds the next token to the syntax tree as a token of the given kind,
+    /// such as a soft keyword lexed as an identifier.
+    pub(crate) fn bump_as(&mut self, kind: SyntaxKind) {
+        self.expected_kinds.clear();
+        let token = self.source.next_token().unwrap();
+        self.after_unterminated = is_unterminated(token);
+        self.events.push(Event::AddTokenAs { kind })
+    }
+
     /// Starts a new node, returning a [`Marker`].
     pub(crate) fn start(&mut self) -> Marker {
         let pos = self.events.len();
@@ -107,6 +151,12 @@ where
     pub(crate) fn error(&mut self, context: impl Into<Option<SyntaxKind>>) {
         let current_token = self.source.peek_token();
 
+        // An unterminated string takes up the rest of its line, so anything
+        // expected after it (or instead of it) is missing because of it. The
+        // lexer already reports it, so nothing else is.
+        let unterminated = self.after_unterminated
+            || current_token.is_some_and(is_unterminated);
+
         let (given, range) =
             if let Some(Token { kind, range, .. }) = current_token {
                 (Some(*kind), range.clone())
@@ -130,10 +180,12 @@ where
             }
         };
 
-        self.messages.push(Message::new(
-            message_kind,
-            Location::new(self.file_id.clone(), range),
-        ));
+        if !unterminated {
+            (self.report)(Message::new(
+                message_kind,
+                Location::new(self.file_id, range),
+            ));
+        }
 
         if expected_len > 1
             && !self.is_at_set(&RECOVERY_SET)
@@ -7,6 +7,11 @@ pub enum Event {
         forward_parent: Option<usize>,
     },
     AddToken,
+    /// Adds the next token as a token of the given kind, which is how soft
+    /// keywords lexed as identifiers end up as keywords in the syntax tree.
+    AddTokenAs {
+        kind: SyntaxKind,
+    },
     FinishNode,
     Placeholder,
 }
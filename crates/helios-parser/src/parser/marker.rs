@@ -17,9 +17,9 @@ impl Marker {
         }
     }
 
-    pub(crate) fn complete<FileId>(
+    pub(crate) fn complete(
         mut self,
-        parser: &mut Parser<FileId>,
+        parser: &mut Parser,
         kind: SyntaxKind,
     ) -> CompletedMarker {
         self.bomb.defuse();
@@ -43,10 +43,7 @@ pub(crate) struct CompletedMarker {
 }
 
 impl CompletedMarker {
-    pub(crate) fn precede<FileId>(self, p: &mut Parser<FileId>) -> Marker
-    where
-        FileId: Clone + Default,
-    {
+    pub(crate) fn precede(self, p: &mut Parser) -> Marker {
         let new_m = p.start();
 
         if let Event::StartNode {
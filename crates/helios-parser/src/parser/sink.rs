@@ -1,31 +1,35 @@
-use helios_syntax::HeliosLanguage;
-use rowan::{GreenNodeBuilder, Language};
+use helios_syntax::{HeliosLanguage, SyntaxKind};
+use rowan::{GreenNodeBuilder, Language, NodeCache};
 
 use crate::lexer::Token;
 use crate::parser::event::Event;
 use crate::{Message, Parse};
 
-pub struct Sink<'tokens, 'source> {
+pub struct Sink<'tokens, 'source, 'cache> {
     tokens: &'tokens [Token<'source>],
     events: Vec<Event>,
-    builder: GreenNodeBuilder<'static>,
+    builder: GreenNodeBuilder<'cache>,
     cursor: usize,
 }
 
-impl<'tokens, 'source> Sink<'tokens, 'source> {
-    pub fn new(tokens: &'tokens [Token<'source>], events: Vec<Event>) -> Self {
+impl<'tokens, 'source, 'cache> Sink<'tokens, 'source, 'cache> {
+    /// Creates a sink building the syntax tree out of the given tokens and
+    /// events, reusing the nodes and tokens of the given cache when they're
+    /// the same (and adding new ones to it).
+    pub fn new(
+        tokens: &'tokens [Token<'source>],
+        events: Vec<Event>,
+        cache: &'cache mut NodeCache,
+    ) -> Self {
         Self {
             tokens,
             events,
-            builder: GreenNodeBuilder::new(),
+            builder: GreenNodeBuilder::with_cache(cache),
             cursor: 0,
         }
     }
 
-    pub fn finish<FileId>(
-        mut self,
-        messages: Vec<Message<FileId>>,
-    ) -> Parse<FileId> {
+    pub fn finish(mut self, messages: Vec<Message>) -> Parse {
         use std::mem;
 
         for i in 0..self.events.len() {
@@ -63,6 +67,7 @@ impl<'tokens, 'source> Sink<'tokens, 'source> {
                     }
                 }
                 Event::AddToken => self.token(),
+                Event::AddTokenAs { kind } => self.token_as(kind),
                 Event::FinishNode => self.builder.finish_node(),
                 Event::Placeholder => {}
             }
@@ -84,7 +89,11 @@ impl<'tokens, 'source> Sink<'tokens, 'source> {
     }
 
     fn token(&mut self) {
-        let Token { kind, text, .. } = self.tokens[self.cursor];
+        self.token_as(self.tokens[self.cursor].kind);
+    }
+
+    fn token_as(&mut self, kind: SyntaxKind) {
+        let Token { text, .. } = self.tokens[self.cursor];
         self.builder.token(HeliosLanguage::kind_to_raw(kind), text);
         self.cursor += 1;
     }
@@ -52,6 +52,18 @@ impl<'source, 'tokens> Source<'source, 'tokens> {
         self.peek_token_raw()
     }
 
+    /// Peeks the kind of the token after the next one, as long as it's on the
+    /// same line (which means only whitespace and comments are between them).
+    pub(crate) fn peek_kind_after_next(&mut self) -> Option<SyntaxKind> {
+        self.eat_trivia();
+        self.tokens
+            .get(self.cursor + 1..)?
+            .iter()
+            .map(|Token { kind, .. }| *kind)
+            .find(|&kind| !kind.is_trivia() || kind == SyntaxKind::Newline)
+            .filter(|&kind| kind != SyntaxKind::Newline)
+    }
+
     fn eat_trivia(&mut self) {
         while self.at_trivia() {
             self.cursor += 1;
@@ -62,7 +74,8 @@ impl<'source, 'tokens> Source<'source, 'tokens> {
         self.peek_kind_raw().map_or(false, SyntaxKind::is_trivia)
     }
 
-    fn peek_kind_raw(&self) -> Option<SyntaxKind> {
+    /// Peeks the kind of the next token, even if it's trivia.
+    pub(crate) fn peek_kind_raw(&self) -> Option<SyntaxKind> {
         self.peek_token_raw().map(|Token { kind, .. }| *kind)
     }
 
@@ -0,0 +1,458 @@
+//! Pretty-printing Helios source files.
+//!
+//! The printer never moves, adds or removes any tokens other than whitespace
+//! and line breaks, so its output always parses to the same tree (minus the
+//! trivia). Instead of building a new string from scratch, it works out what
+//! the whitespace between every pair of neighbouring tokens should be, which
+//! also makes it easy to only touch the parts of a file that actually need to
+//! change (see [`pretty_print_edits`]).
+//!
+//! The rules are intentionally simple for now:
+//!
+//! - tokens on the same line are separated by a single space, except around
+//!   brackets, before commas and after prefix operators (such as `-` in `-1`);
+//! - every indentation level is four spaces wide;
+//! - there is never more than one blank line in a row, and none at the start or
+//!   end of a file;
+//! - a file always ends with a single line break.
+//!
+//! The width of indentation levels and the number of blank lines in a row can
+//! be changed with a [`FormatterConfig`] (usually read from the `[fmt]` section
+//! of a project's `helios.toml`).
+
+use helios_diagnostics::FileId;
+use helios_syntax::{SyntaxKind, SyntaxNode, SyntaxToken};
+use serde::Deserialize;
+use std::ops::Range;
+
+use crate::indent;
+
+/// The default maximum width of a line.
+pub const MAX_WIDTH: usize = 80;
+
+/// The default number of spaces per indentation level.
+pub const INDENT_WIDTH: usize = 4;
+
+/// The default maximum number of blank lines in a row.
+pub const MAX_BLANK_LINES: usize = 1;
+
+/// Settings controlling how source files are pretty-printed.
+///
+/// The settings are deserialized from kebab-case keys (such as
+/// `indent-width`), any of which may be left out.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FormatterConfig {
+    /// The maximum width of a line, in characters.
+    ///
+    /// _NOTE:_ The printer never breaks lines for now, so this is only a limit
+    /// for tools (such as editors) to show.
+    pub max_width: usize,
+    /// The number of spaces per indentation level.
+    pub indent_width: usize,
+    /// Whether comma-separated lists end with a comma.
+    ///
+    /// _NOTE:_ None of the syntax the printer handles has such lists yet.
+    pub trailing_commas: TrailingCommas,
+    /// The maximum number of blank lines in a row (anywhere but at the start
+    /// or the end of a file, where there are none).
+    pub max_blank_lines: usize,
+}
+
+impl Default for FormatterConfig {
+    fn default() -> Self {
+        Self {
+            max_width: MAX_WIDTH,
+            indent_width: INDENT_WIDTH,
+            trailing_commas: TrailingCommas::default(),
+            max_blank_lines: MAX_BLANK_LINES,
+        }
+    }
+}
+
+/// When comma-separated lists end with a comma.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TrailingCommas {
+    Always,
+    Never,
+    /// Only lists spanning multiple lines end with a comma.
+    #[default]
+    Multiline,
+}
+
+/// A replacement of a range of the original source text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Edit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+/// Pretty-prints the given syntax tree.
+pub fn pretty_print(root: &SyntaxNode, config: &FormatterConfig) -> String {
+    let source = root.text().to_string();
+    apply_edits(&source, pretty_print_edits(root, config))
+}
+
+/// Applies the given edits to the source text they were worked out for.
+///
+/// The edits must be sorted by their position in the source text and mustn't
+/// overlap.
+pub fn apply_edits(source: &str, edits: Vec<Edit>) -> String {
+    let mut source = source.to_string();
+    for edit in edits.into_iter().rev() {
+        source.replace_range(edit.range, &edit.text);
+    }
+
+    source
+}
+
+/// Works out the edits needed to pretty-print the given syntax tree.
+///
+/// The edits are sorted by their position in the source text and never
+/// overlap. Only the whitespace that actually differs from the pretty-printed
+/// output is edited.
+pub fn pretty_print_edits(
+    root: &SyntaxNode,
+    config: &FormatterConfig,
+) -> Vec<Edit> {
+    let source = root.text().to_string();
+    let mut edits = Vec::new();
+    let mut indents = IndentStack::default();
+    let mut prev: Option<SyntaxToken> = None;
+
+    let tokens = root
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| !is_whitespace(token.kind()));
+
+    for token in tokens.map(Some).chain(std::iter::once(None)) {
+        // Line comments run until the end of the line, so any whitespace at the
+        // end of one is treated as part of the gap after it.
+        let start = prev.as_ref().map_or(0, |prev| {
+            let end = usize::from(prev.text_range().end());
+            end - (prev.text().len() - prev.text().trim_end().len())
+        });
+        let end = token
+            .as_ref()
+            .map_or(source.len(), |token| token.text_range().start().into());
+        let range = start..end;
+
+        let gap = &source[range.clone()];
+        let text = match (&prev, &token) {
+            // Nothing but whitespace goes before the first token...
+            (None, Some(_)) => {
+                let level = indents.level(indent_width(gap));
+                " ".repeat(config.indent_width * level)
+            }
+            // ... or after the last one.
+            (Some(_), None) => "\n".to_string(),
+            (None, None) => String::new(),
+            (Some(prev), Some(token)) => {
+                let line_breaks = gap.matches('\n').count();
+                if line_breaks == 0 {
+                    let space = needs_space(prev, token);
+                    if space { " " } else { "" }.to_string()
+                } else {
+                    let level = indents.level(indent_width(gap));
+                    "\n".repeat(line_breaks.min(config.max_blank_lines + 1))
+                        + &" ".repeat(config.indent_width * level)
+                }
+            }
+        };
+
+        if gap != text {
+            edits.push(Edit { range, text });
+        }
+
+        prev = token;
+    }
+
+    edits
+}
+
+/// Keywords and symbols that start an indented block when they end a line.
+const BLOCK_OPENERS: &[&str] = &[
+    "=", "->", "=>", "<-", ":=", "then", "else", "of", "with", "do",
+];
+
+/// Works out how many spaces a line following the given one should be indented
+/// by.
+///
+/// The new line is indented by one more level if the given line ends with a
+/// token that opens a block (such as `=` or `of`), and is lined up with the
+/// given line otherwise.
+pub fn next_line_indent(line: &str, config: &FormatterConfig) -> usize {
+    let line = line.trim_end_matches(['\n', '\r']);
+    let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+    let indent = indent::indent_width(&line[..indent]);
+
+    let (tokens, _) = crate::tokenize(FileId::default(), line);
+    let last_token = tokens.iter().rev().find(|token| !token.kind.is_trivia());
+
+    match last_token {
+        Some(token) if BLOCK_OPENERS.contains(&token.text) => {
+            // Round down to a whole level first, in case the line itself isn't
+            // indented properly.
+            (indent / config.indent_width + 1) * config.indent_width
+        }
+        _ => indent,
+    }
+}
+
+/// Keeps track of the indentation of the lines seen so far, in the same way
+/// as the lexer does, so that every line can be given an indentation level.
+#[derive(Debug)]
+struct IndentStack(Vec<usize>);
+
+impl Default for IndentStack {
+    fn default() -> Self {
+        Self(vec![0])
+    }
+}
+
+impl IndentStack {
+    /// The indentation level of a line indented by the given width.
+    fn level(&mut self, width: usize) -> usize {
+        while self.0.last().is_some_and(|&last| last > width) {
+            self.0.pop();
+        }
+
+        if self.0.last().is_none_or(|&last| last < width) {
+            self.0.push(width);
+        }
+
+        self.0.len() - 1
+    }
+}
+
+/// The width of the indentation at the end of the given whitespace.
+fn indent_width(gap: &str) -> usize {
+    let last_line = gap.rsplit('\n').next().unwrap_or_default();
+    indent::indent_width(last_line)
+}
+
+fn is_whitespace(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::Whitespace
+            | SyntaxKind::Newline
+            | SyntaxKind::Indent
+            | SyntaxKind::Dedent
+    )
+}
+
+/// Determines whether a space goes between two tokens on the same line.
+fn needs_space(prev: &SyntaxToken, next: &SyntaxToken) -> bool {
+    let parent_kind = |token: &SyntaxToken| token.parent().map(|p| p.kind());
+
+    match (prev.kind(), next.kind()) {
+        (SyntaxKind::Sym_LParen, _)
+        | (SyntaxKind::Sym_LBracket, _)
+        | (SyntaxKind::Sym_LBrace, _)
+        | (_, SyntaxKind::Sym_RParen)
+        | (_, SyntaxKind::Sym_RBracket)
+        | (_, SyntaxKind::Sym_RBrace)
+        | (_, SyntaxKind::Sym_Comma)
+        | (SyntaxKind::Sym_Dot, _)
+        | (_, SyntaxKind::Sym_Dot) => false,
+        // A prefix operator sticks to its operand, and a postfix operator to
+        // the expression before it.
+        (kind, _)
+            if kind.is_symbol()
+                && parent_kind(prev) == Some(SyntaxKind::Exp_UnaryPrefix) =>
+        {
+            false
+        }
+        (_, kind)
+            if kind.is_symbol()
+                && parent_kind(next) == Some(SyntaxKind::Exp_UnaryPostfix) =>
+        {
+            false
+        }
+        // The `!` of a macro call sticks to its name and to its arguments.
+        (SyntaxKind::Sym_Bang, _) | (_, SyntaxKind::Sym_Bang)
+            if parent_kind(prev) == Some(SyntaxKind::Exp_MacroCall)
+                && parent_kind(next) == Some(SyntaxKind::Exp_MacroCall) =>
+        {
+            false
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::{expect, Expect};
+
+    fn check(input: &str, expected: Expect) {
+        check_with(input, &FormatterConfig::default(), expected);
+    }
+
+    fn check_with(input: &str, config: &FormatterConfig, expected: Expect) {
+        let root = crate::parse(FileId(0), input).syntax();
+        let printed = pretty_print(&root, config);
+        expected.assert_eq(&printed);
+
+        // Pretty-printing should be idempotent.
+        let root = crate::parse(FileId(0), &printed).syntax();
+        assert_eq!(pretty_print(&root, config), printed);
+    }
+
+    #[test]
+    fn test_pretty_print_spacing() {
+        check(
+            "let   a=1+-  2*( a  -3 )   # comment   \n",
+            expect![[r#"
+                let a = 1 + -2 * (a - 3) # comment
+            "#]],
+        );
+        check(
+            "macro  add x  y=x+y\nlet b = add!( 1,-b )+!a\n",
+            expect![[r#"
+                macro add x y = x + y
+                let b = add!(1, -b) + !a
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_block_comments() {
+        // The text of a block comment is left as it is, even if it spans
+        // multiple lines.
+        check(
+            "let a =#[ one\n  two ]#  1   #[ three ]#\n",
+            expect![[r#"
+                let a = #[ one
+                  two ]# 1 #[ three ]#
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_blank_lines() {
+        check(
+            "\n\n## Docs\nlet a = 1\n\n\n\nlet b = 2\nlet c = 3\n\n\n",
+            expect![[r#"
+                ## Docs
+                let a = 1
+
+                let b = 2
+                let c = 3
+            "#]],
+        );
+        check("", expect![[""]]);
+
+        let config = FormatterConfig {
+            max_blank_lines: 2,
+            ..FormatterConfig::default()
+        };
+        check_with(
+            "let a = 1\n\n\n\n\nlet b = 2\n\nlet c = 3\n",
+            &config,
+            expect![[r#"
+                let a = 1
+
+
+                let b = 2
+
+                let c = 3
+            "#]],
+        );
+        let config = FormatterConfig {
+            max_blank_lines: 0,
+            ..FormatterConfig::default()
+        };
+        check_with(
+            "let a = 1\n\nlet b = 2\n",
+            &config,
+            expect![[r#"
+                let a = 1
+                let b = 2
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_indentation() {
+        check(
+            "let a = 1 +\n  2\n\n  # comment\nlet b = 2",
+            expect![[r#"
+                let a = 1 +
+                    2
+
+                    # comment
+                let b = 2
+            "#]],
+        );
+
+        // Tabs are replaced with spaces, counting them the same way as the
+        // lexer does.
+        check(
+            "let a = 1 +\n\t2 +\n\t  3\nlet b = 2",
+            expect![[r#"
+                let a = 1 +
+                    2 +
+                        3
+                let b = 2
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_indent_width() {
+        check_with(
+            "let a = 1 +\n    2\nlet b = 2",
+            &FormatterConfig {
+                indent_width: 2,
+                ..FormatterConfig::default()
+            },
+            expect![[r#"
+                let a = 1 +
+                  2
+                let b = 2
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_next_line_indent() {
+        let next_line_indent =
+            |line| super::next_line_indent(line, &FormatterConfig::default());
+
+        assert_eq!(next_line_indent(""), 0);
+        assert_eq!(next_line_indent("let a = 1"), 0);
+        assert_eq!(next_line_indent("let a =\n"), 4);
+        assert_eq!(next_line_indent("let a = # comment"), 4);
+        assert_eq!(next_line_indent("let a = #[ comment ]#"), 4);
+        assert_eq!(next_line_indent("    case x of"), 8);
+        assert_eq!(next_line_indent("      if a then"), 8);
+        assert_eq!(next_line_indent("    1 +"), 4);
+        assert_eq!(next_line_indent("\t case x of"), 8);
+
+        let config = FormatterConfig {
+            indent_width: 2,
+            ..FormatterConfig::default()
+        };
+        assert_eq!(super::next_line_indent("  case x of", &config), 4);
+    }
+
+    #[test]
+    fn test_pretty_print_edits() {
+        let root = crate::parse(FileId(0), "let a  = 1\nlet b = 2").syntax();
+        assert_eq!(
+            pretty_print_edits(&root, &FormatterConfig::default()),
+            vec![
+                Edit {
+                    range: 5..7,
+                    text: " ".to_string()
+                },
+                Edit {
+                    range: 20..20,
+                    text: "\n".to_string()
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! Attaching the trivia of a token stream to the tokens around it.
+//!
+//! The lexer emits whitespace and comments as tokens of their own, which is
+//! what the parser wants, but tools working on the tokens of a source text
+//! (such as formatters and documentation extractors) usually want to know
+//! which token a comment belongs to. The [`TriviaLayer`] works this out once,
+//! following the same rule as most other tools:
+//!
+//! - Whitespace and comments following a token on the same line are its
+//!   trailing trivia.
+//! - Any other whitespace and comments are the leading trivia of the token
+//!   after them.
+//!
+//! Line breaks (along with the `Indent` and `Dedent` tokens holding them) are
+//! tokens of their own rather than trivia, since they end statements. Hence a
+//! comment on a line of its own is the leading trivia of the line break ending
+//! that line.
+
+use helios_syntax::SyntaxKind;
+use std::iter::Peekable;
+use std::ops::Range;
+
+use crate::lexer::Token;
+
+/// A token along with the whitespace and comments attached to it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenWithTrivia<'source> {
+    /// The whitespace and comments before the token.
+    pub leading: Vec<Token<'source>>,
+    pub token: Token<'source>,
+    /// The whitespace and comments after the token on the same line.
+    pub trailing: Vec<Token<'source>>,
+}
+
+impl TokenWithTrivia<'_> {
+    /// The range of the token along with its trivia.
+    pub fn full_range(&self) -> Range<usize> {
+        let start = self.leading.first().unwrap_or(&self.token).range.start;
+        let end = self.trailing.last().unwrap_or(&self.token).range.end;
+        start..end
+    }
+}
+
+/// A lazy adapter grouping the whitespace and comments of a token stream with
+/// the tokens they're attached to (see the [module documentation]).
+///
+/// The grouping is lossless: every token of the stream ends up in exactly one
+/// [`TokenWithTrivia`], in the same order. So that this holds at the ends of
+/// the stream, the trivia after the last line break of the stream is the
+/// trailing trivia of that line break, and in a stream made of nothing but
+/// whitespace and comments, the first of them stands in for a token.
+///
+/// [module documentation]: crate::trivia
+pub struct TriviaLayer<'source, I>
+where
+    I: Iterator<Item = Token<'source>>,
+{
+    tokens: Peekable<I>,
+    /// The leading trivia of the next token, which was read while looking for
+    /// the end of the stream.
+    leading: Vec<Token<'source>>,
+}
+
+impl<'source, I> TriviaLayer<'source, I>
+where
+    I: Iterator<Item = Token<'source>>,
+{
+    /// Constructs a new [`TriviaLayer`] over the given tokens.
+    pub fn new(tokens: I) -> Self {
+        Self {
+            tokens: tokens.peekable(),
+            leading: Vec::new(),
+        }
+    }
+
+    /// Reads the whitespace and comments coming next.
+    fn trivia(&mut self) -> Vec<Token<'source>> {
+        let mut trivia = Vec::new();
+        while let Some(token) =
+            self.tokens.next_if(|token| is_trivia(token.kind))
+        {
+            trivia.push(token);
+        }
+
+        trivia
+    }
+}
+
+impl<'source, I> Iterator for TriviaLayer<'source, I>
+where
+    I: Iterator<Item = Token<'source>>,
+{
+    type Item = TokenWithTrivia<'source>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut leading = std::mem::take(&mut self.leading);
+        leading.extend(self.trivia());
+
+        let token = match self.tokens.next() {
+            Some(token) => token,
+            None if leading.is_empty() => return None,
+            None => {
+                let token = leading.remove(0);
+                return Some(TokenWithTrivia {
+                    leading: Vec::new(),
+                    token,
+                    trailing: leading,
+                });
+            }
+        };
+
+        let trailing = if ends_line(token.kind) {
+            self.leading = self.trivia();
+            match self.tokens.peek() {
+                Some(_) => Vec::new(),
+                None => std::mem::take(&mut self.leading),
+            }
+        } else {
+            self.trivia()
+        };
+
+        Some(TokenWithTrivia {
+            leading,
+            token,
+            trailing,
+        })
+    }
+}
+
+fn is_trivia(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::Whitespace | SyntaxKind::Comment | SyntaxKind::DocComment
+    )
+}
+
+fn ends_line(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::Newline | SyntaxKind::Indent | SyntaxKind::Dedent
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileId;
+
+    /// Groups the tokens of the given input, checking that the groups hold
+    /// every token, and returns the text of each of them.
+    fn check(input: &str) -> Vec<(Vec<&str>, &str, Vec<&str>)> {
+        let (tokens, _) = crate::tokenize(FileId(0), input);
+        let groups = TriviaLayer::new(tokens.into_iter()).collect::<Vec<_>>();
+
+        let mut end = 0;
+        for group in &groups {
+            assert_eq!(group.full_range().start, end);
+            end = group.full_range().end;
+        }
+        assert_eq!(end, input.len());
+
+        fn texts<'source>(tokens: &[Token<'source>]) -> Vec<&'source str> {
+            tokens.iter().map(|token| token.text).collect()
+        }
+
+        groups
+            .iter()
+            .map(|group| {
+                (
+                    texts(&group.leading),
+                    group.token.text,
+                    texts(&group.trailing),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_trailing_trivia() {
+        assert_eq!(
+            check("let a = 1 # one\n"),
+            [
+                (vec![], "let", vec![" "]),
+                (vec![], "a", vec![" "]),
+                (vec![], "=", vec![" "]),
+                (vec![], "1", vec![" ", "# one"]),
+                (vec![], "\n", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_leading_trivia() {
+        assert_eq!(
+            check("## Doc\n  #[ a ]# let\n"),
+            [
+                (vec!["## Doc"], "\n  ", vec![]),
+                (vec!["#[ a ]#", " "], "let", vec![]),
+                (vec![], "\n", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trivia_at_the_ends() {
+        assert_eq!(
+            check("a\n# end"),
+            [(vec![], "a", vec![]), (vec![], "\n", vec!["# end"])]
+        );
+        assert_eq!(check("# only\n"), [(vec!["# only"], "\n", vec![])]);
+        assert_eq!(check("# a #[ b ]#"), [(vec![], "# a #[ b ]#", vec![])]);
+        assert_eq!(check(" # a"), [(vec![], " ", vec!["# a"])]);
+        assert_eq!(check(""), []);
+    }
+}
@@ -0,0 +1,113 @@
+//! Snapshot tests for the files of `tests/corpus`.
+//!
+//! Every `.helios` file in the corpus (or in any of its subdirectories) is
+//! parsed, and its syntax tree and diagnostics are compared with the snapshots
+//! next to it: `<name>.cst` and `<name>.diagnostics`. Running the tests with
+//! `UPDATE_EXPECT=1` (like `expect-test`) writes the snapshots instead, so
+//! that changes to the grammar show up as changes to the snapshots.
+
+use helios_diagnostics::{Diagnostic, FileId};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Finds every `.helios` file in the given directory and its subdirectories,
+/// in alphabetical order.
+fn corpus_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "helios") {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// Renders the diagnostics of a file, one per paragraph (such as
+/// `Error 11..11: Unexpected end of file`, followed by its message).
+fn render_diagnostics(diagnostics: &[Diagnostic<FileId>]) -> String {
+    let mut rendered = String::new();
+    for diagnostic in diagnostics {
+        let range = &diagnostic.location.range;
+        writeln!(
+            rendered,
+            "{:?} {}..{}: {}",
+            diagnostic.severity, range.start, range.end, diagnostic.title
+        )
+        .unwrap();
+        for line in diagnostic.message.to_markdown().lines() {
+            writeln!(rendered, "    {line}").unwrap();
+        }
+    }
+
+    rendered
+}
+
+/// Compares a snapshot with what it should be (or writes it when updating
+/// snapshots), returning whether it matched.
+fn check_snapshot(path: &Path, actual: &str, update: bool) -> bool {
+    if update {
+        std::fs::write(path, actual).unwrap();
+        return true;
+    }
+
+    let expected = std::fs::read_to_string(path).unwrap_or_default();
+    if expected == actual {
+        return true;
+    }
+
+    eprintln!(
+        "Snapshot mismatch in {}\n\n--- Expected\n{expected}\n--- Actual\n\
+        {actual}",
+        path.display()
+    );
+    false
+}
+
+#[test]
+fn test_corpus() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let update = std::env::var_os("UPDATE_EXPECT").is_some();
+    let files = corpus_files(&dir);
+    assert!(!files.is_empty(), "no files in {}", dir.display());
+
+    let mut mismatches = Vec::new();
+    for path in files {
+        let source = std::fs::read_to_string(&path).unwrap();
+        let parse = helios_parser::parse(FileId::default(), &source);
+        parse.verify_lossless(&source);
+        let diagnostics = parse
+            .messages()
+            .iter()
+            .map(Diagnostic::from)
+            .collect::<Vec<_>>();
+
+        let cst = check_snapshot(
+            &path.with_extension("cst"),
+            &parse.debug_tree(),
+            update,
+        );
+        let diagnostics = check_snapshot(
+            &path.with_extension("diagnostics"),
+            &render_diagnostics(&diagnostics),
+            update,
+        );
+
+        if !cst || !diagnostics {
+            mismatches.push(path.strip_prefix(&dir).unwrap().to_path_buf());
+        }
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "the snapshots of {mismatches:?} don't match (run the tests with \
+        `UPDATE_EXPECT=1` to update them)"
+    );
+}
@@ -0,0 +1,271 @@
+//! Property-based tests for the pretty printer.
+//!
+//! Random (but valid) syntax trees are generated and rendered to source text
+//! with random spacing, comments and blank lines. Parsing the text should give
+//! back the generated tree, and so should parsing the pretty-printed text,
+//! which catches asymmetries between the printer and the grammar (such as the
+//! printer gluing together two tokens that then lex as one).
+
+use helios_parser::printer::{pretty_print, FormatterConfig};
+use helios_parser::FileId;
+use helios_syntax::{SyntaxKind, SyntaxNode};
+use proptest::prelude::*;
+
+/// The structure of a syntax tree, leaving out trivia.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Shape {
+    Node(SyntaxKind, Vec<Shape>),
+    Token(SyntaxKind, String),
+}
+
+impl Shape {
+    fn node(kind: SyntaxKind, children: impl Into<Vec<Shape>>) -> Self {
+        Self::Node(kind, children.into())
+    }
+
+    fn token(kind: SyntaxKind, text: impl Into<String>) -> Self {
+        Self::Token(kind, text.into())
+    }
+
+    /// Works out the structure of a parsed syntax tree.
+    fn of(node: &SyntaxNode) -> Self {
+        let children = node
+            .children_with_tokens()
+            .filter_map(|child| match child {
+                rowan::NodeOrToken::Node(node) => Some(Self::of(&node)),
+                rowan::NodeOrToken::Token(token)
+                    if token.kind().is_trivia() =>
+                {
+                    None
+                }
+                rowan::NodeOrToken::Token(token) => {
+                    Some(Self::token(token.kind(), token.text()))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Self::Node(node.kind(), children)
+    }
+
+    /// The tokens of the tree, in order.
+    fn tokens(&self) -> Vec<&str> {
+        match self {
+            Self::Node(_, children) => {
+                children.iter().flat_map(Self::tokens).collect()
+            }
+            Self::Token(_, text) => vec![text],
+        }
+    }
+}
+
+/// A declaration along with the trivia around it.
+#[derive(Clone, Debug)]
+struct Line {
+    decl: Shape,
+    comment: Option<String>,
+    blank_lines: usize,
+}
+
+/// Renders the declarations to source text, separating tokens by the given
+/// numbers of spaces (cycling through them).
+fn render(lines: &[Line], spaces: &[usize]) -> String {
+    let mut spaces = spaces.iter().cycle();
+    let mut source = String::new();
+    for line in lines {
+        let tokens = line.decl.tokens();
+        for (i, token) in tokens.iter().enumerate() {
+            if i > 0 {
+                // Tokens can only touch if they can't be lexed as one.
+                let sticky = |token: &str| matches!(token, "(" | ")" | ".");
+                let min = match sticky(tokens[i - 1]) || sticky(token) {
+                    true => 0,
+                    false => 1,
+                };
+                let count = *spaces.next().unwrap();
+                source.push_str(&" ".repeat(count.max(min)));
+            }
+            source.push_str(token);
+        }
+
+        if let Some(comment) = &line.comment {
+            source.push_str(&format!(" #{comment}"));
+        }
+        source.push_str(&"\n".repeat(line.blank_lines + 1));
+    }
+
+    source
+}
+
+fn identifier() -> impl Strategy<Value = String> {
+    "[a-z][a-z0-9_]{0,5}".prop_filter("keywords aren't identifiers", |text| {
+        let (tokens, _) = helios_parser::tokenize(FileId::default(), text);
+        matches!(&tokens[..], [token] if token.kind == SyntaxKind::Identifier)
+    })
+}
+
+/// Wraps binary expressions in parentheses, so that an operand doesn't depend
+/// on the precedence of the operators around it.
+fn operand(expr: Shape) -> Shape {
+    match expr {
+        Shape::Node(SyntaxKind::Exp_Binary, _) => Shape::node(
+            SyntaxKind::Exp_Paren,
+            [
+                Shape::token(SyntaxKind::Sym_LParen, "("),
+                expr,
+                Shape::token(SyntaxKind::Sym_RParen, ")"),
+            ],
+        ),
+        expr => expr,
+    }
+}
+
+fn expr() -> impl Strategy<Value = Shape> {
+    let literal = prop_oneof![
+        any::<u32>()
+            .prop_map(|n| Shape::token(SyntaxKind::Lit_Integer, n.to_string())),
+        "[a-z ]|\\\\[n'\\\\]".prop_map(|c| Shape::token(
+            SyntaxKind::Lit_Character,
+            format!("'{c}'")
+        )),
+        (any::<u16>(), any::<u16>()).prop_map(|(whole, fraction)| {
+            Shape::token(SyntaxKind::Lit_Float, format!("{whole}.{fraction}"))
+        }),
+    ]
+    .prop_map(|literal| Shape::node(SyntaxKind::Exp_Literal, [literal]));
+    let variable = identifier().prop_map(|name| {
+        let name = Shape::token(SyntaxKind::Identifier, name);
+        Shape::node(SyntaxKind::Exp_VariableRef, [name])
+    });
+
+    let prefix_op = prop_oneof![
+        Just(Shape::token(SyntaxKind::Sym_Minus, "-")),
+        Just(Shape::token(SyntaxKind::Sym_Bang, "!")),
+    ];
+    let infix_op = prop::sample::select(vec![
+        (SyntaxKind::Sym_Asterisk, "*"),
+        (SyntaxKind::Sym_BangEq, "!="),
+        (SyntaxKind::Sym_Eq, "="),
+        (SyntaxKind::Sym_ForwardSlash, "/"),
+        (SyntaxKind::Sym_Gt, ">"),
+        (SyntaxKind::Sym_GtEq, ">="),
+        (SyntaxKind::Sym_Lt, "<"),
+        (SyntaxKind::Sym_LtEq, "<="),
+        (SyntaxKind::Sym_LThinArrow, "<-"),
+        (SyntaxKind::Sym_Minus, "-"),
+        (SyntaxKind::Sym_Plus, "+"),
+        (SyntaxKind::Sym_Semicolon, ";"),
+    ])
+    .prop_map(|(kind, text)| Shape::token(kind, text));
+
+    prop_oneof![literal, variable].prop_recursive(4, 32, 2, move |inner| {
+        prop_oneof![
+            (prefix_op.clone(), inner.clone()).prop_map(|(op, expr)| {
+                Shape::node(SyntaxKind::Exp_UnaryPrefix, [op, operand(expr)])
+            }),
+            inner.clone().prop_map(|expr| Shape::node(
+                SyntaxKind::Exp_Paren,
+                [
+                    Shape::token(SyntaxKind::Sym_LParen, "("),
+                    expr,
+                    Shape::token(SyntaxKind::Sym_RParen, ")"),
+                ],
+            )),
+            (inner.clone(), infix_op.clone(), inner).prop_map(
+                |(lhs, op, rhs)| Shape::node(
+                    SyntaxKind::Exp_Binary,
+                    [operand(lhs), op, operand(rhs)],
+                )
+            ),
+        ]
+    })
+}
+
+fn decl() -> impl Strategy<Value = Shape> {
+    let global_binding = (identifier(), expr()).prop_map(|(name, expr)| {
+        Shape::node(
+            SyntaxKind::Dec_GlobalBinding,
+            [
+                Shape::token(SyntaxKind::Kwd_Let, "let"),
+                Shape::token(SyntaxKind::Identifier, name),
+                Shape::token(SyntaxKind::Sym_Eq, "="),
+                expr,
+            ],
+        )
+    });
+    let import = prop::collection::vec(identifier(), 1..4).prop_map(|path| {
+        let mut children = vec![Shape::token(SyntaxKind::Kwd_Import, "import")];
+        for (i, name) in path.into_iter().enumerate() {
+            if i > 0 {
+                children.push(Shape::token(SyntaxKind::Sym_Dot, "."));
+            }
+            children.push(Shape::token(SyntaxKind::Identifier, name));
+        }
+        Shape::node(SyntaxKind::Dec_Import, children)
+    });
+    let external = (identifier(), identifier()).prop_map(|(name, ty)| {
+        Shape::node(
+            SyntaxKind::Dec_External,
+            [
+                Shape::token(SyntaxKind::Kwd_External, "external"),
+                Shape::token(SyntaxKind::Kwd_Func, "func"),
+                Shape::token(SyntaxKind::Identifier, name),
+                Shape::token(SyntaxKind::Sym_Colon, ":"),
+                Shape::token(SyntaxKind::Identifier, ty),
+            ],
+        )
+    });
+    let test = ("[a-z ]{0,12}", expr()).prop_map(|(name, expr)| {
+        Shape::node(
+            SyntaxKind::Dec_Test,
+            [
+                Shape::token(SyntaxKind::Kwd_Test, "test"),
+                Shape::token(SyntaxKind::Lit_String, format!("\"{name}\"")),
+                Shape::token(SyntaxKind::Sym_Eq, "="),
+                expr,
+            ],
+        )
+    });
+
+    prop_oneof![global_binding, import, external, test]
+}
+
+fn lines() -> impl Strategy<Value = Vec<Line>> {
+    let line = (decl(), prop::option::of("[ a-z]{0,8}"), 0..3usize).prop_map(
+        |(decl, comment, blank_lines)| Line {
+            decl,
+            comment,
+            blank_lines,
+        },
+    );
+
+    prop::collection::vec(line, 0..6)
+}
+
+proptest! {
+    #[test]
+    fn test_pretty_print_round_trip(
+        lines in lines(),
+        spaces in prop::collection::vec(0..4usize, 1..8),
+        indent_width in 1..8usize,
+    ) {
+        let source = render(&lines, &spaces);
+        let decls = lines.into_iter().map(|line| line.decl).collect::<Vec<_>>();
+        let tree = Shape::node(SyntaxKind::Root, decls);
+
+        // The generated tree should be valid in the first place...
+        let parse = helios_parser::parse(FileId::default(), &source);
+        prop_assert!(parse.messages().is_empty(), "{}", parse.debug_tree());
+        prop_assert_eq!(&Shape::of(&parse.syntax()), &tree);
+
+        // ... and should survive being pretty-printed.
+        let config = FormatterConfig {
+            indent_width,
+            ..FormatterConfig::default()
+        };
+        let printed = pretty_print(&parse.syntax(), &config);
+        let parse = helios_parser::parse(FileId::default(), &printed);
+        prop_assert!(parse.messages().is_empty(), "{}", parse.debug_tree());
+        prop_assert_eq!(&Shape::of(&parse.syntax()), &tree);
+        prop_assert_eq!(pretty_print(&parse.syntax(), &config), printed);
+    }
+}
@@ -0,0 +1,684 @@
+//! Helios projects and their manifests.
+//!
+//! A project is a directory containing a `helios.toml` manifest, which
+//! describes the package the project builds. For example:
+//!
+//! ```toml
+//! [package]
+//! name = "hello"
+//! version = "0.1.0"
+//! edition = "2026"
+//! source-dir = "src"
+//! entry-point = "main.helios"
+//!
+//! [dependencies]
+//! math = { path = "../math" }
+//!
+//! [fmt]
+//! indent-width = 2
+//! max-blank-lines = 2
+//! ```
+//!
+//! The source directory (relative to the project's root) and the entry point
+//! (relative to the source directory) default to the values above. The
+//! edition of the language the package is written in (see [`Edition`])
+//! defaults to the first one, so that projects written before editions existed
+//! keep building; new projects are created with the latest one.
+//!
+//! Dependencies are other projects on disk, given by the path of their root
+//! (relative to the project's root). Their modules are namespaced by their
+//! package's name, so `import math.vector` imports `vector.helios` from the
+//! source directory of `math`.
+//!
+//! The `[fmt]` section configures how the project's files are formatted (by
+//! `helios fmt` and by the language server). Every key is optional; see
+//! [`FormatterConfig`] for what they are.
+//!
+//! New projects are created with [`Project::create`].
+
+use helios_parser::printer::FormatterConfig;
+use helios_parser::Edition;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+
+/// The name of the manifest file at the root of every project.
+pub const MANIFEST_NAME: &str = "helios.toml";
+
+/// The entry point of new projects.
+const MAIN_TEMPLATE: &str = "\
+## Where the program starts: its value is the exit code of the program.
+let main = 0
+
+test \"main exits successfully\" = main = 0
+";
+
+/// The contents of a `helios.toml` file.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub package: Package,
+    /// The packages this package depends on, by name.
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Dependency>,
+    /// How the package's files are formatted.
+    #[serde(default)]
+    pub fmt: FormatterConfig,
+}
+
+/// The `[package]` section of a manifest.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Package {
+    /// The name of the package (and of the programs it's compiled to).
+    pub name: String,
+    /// The version of the package, as `major.minor.patch`.
+    pub version: String,
+    /// The edition of the language the package's source files are written in.
+    #[serde(default)]
+    pub edition: Edition,
+    /// The directory containing the package's source files, relative to the
+    /// root of the project.
+    #[serde(default = "default_source_dir")]
+    pub source_dir: PathBuf,
+    /// The file where the program starts, relative to the source directory.
+    #[serde(default = "default_entry_point")]
+    pub entry_point: PathBuf,
+}
+
+/// Where a dependency comes from.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Dependency {
+    /// The root of the dependency's project, relative to the root of the
+    /// project depending on it.
+    pub path: Option<PathBuf>,
+    /// The URL of the dependency's git repository (which isn't supported
+    /// yet).
+    pub git: Option<String>,
+}
+
+fn default_source_dir() -> PathBuf {
+    PathBuf::from("src")
+}
+
+fn default_entry_point() -> PathBuf {
+    PathBuf::from("main.helios")
+}
+
+impl Manifest {
+    /// Parses and validates a manifest from the given TOML source text.
+    pub fn from_toml(source: &str) -> Result<Self, Error> {
+        let manifest: Self = toml::from_str(source)
+            .map_err(|error| Error::Parse(error.to_string()))?;
+        manifest.package.validate()?;
+
+        for (key, value) in [
+            ("max-width", manifest.fmt.max_width),
+            ("indent-width", manifest.fmt.indent_width),
+        ] {
+            if value == 0 {
+                return Err(Error::Invalid(format!(
+                    "`fmt.{key}` must be at least 1"
+                )));
+            }
+        }
+
+        for (name, dependency) in &manifest.dependencies {
+            if !is_valid_name(name) {
+                return Err(Error::Invalid(format!(
+                    "`{name}` is not a valid dependency name"
+                )));
+            }
+            if is_keyword(name, manifest.package.edition) {
+                return Err(Error::Invalid(format!(
+                    "`{name}` is a keyword, so it can't be the name of a \
+                    dependency"
+                )));
+            }
+
+            match dependency {
+                Dependency {
+                    path: Some(_),
+                    git: None,
+                } => {}
+                Dependency { git: Some(_), .. } => {
+                    return Err(Error::Invalid(format!(
+                        "the dependency `{name}` comes from git, which isn't \
+                        supported yet (only `path` is)"
+                    )))
+                }
+                Dependency {
+                    path: None,
+                    git: None,
+                } => {
+                    return Err(Error::Invalid(format!(
+                        "the dependency `{name}` must have a `path`"
+                    )))
+                }
+            }
+        }
+
+        Ok(manifest)
+    }
+}
+
+impl Package {
+    fn validate(&self) -> Result<(), Error> {
+        if !is_valid_name(&self.name) {
+            return Err(Error::Invalid(format!(
+                "`{}` is not a valid package name (names must start with a \
+                letter, followed by letters, digits, `-` or `_`)",
+                self.name
+            )));
+        }
+        if is_keyword(&self.name, self.edition) {
+            return Err(Error::Invalid(format!(
+                "`{}` is a keyword, so it can't be the name of a package",
+                self.name
+            )));
+        }
+
+        if !is_valid_version(&self.version) {
+            return Err(Error::Invalid(format!(
+                "`{}` is not a valid version (versions are written as \
+                `major.minor.patch`, such as `0.1.0`)",
+                self.version
+            )));
+        }
+
+        for (key, path) in [
+            ("source-dir", &self.source_dir),
+            ("entry-point", &self.entry_point),
+        ] {
+            if path.is_absolute() {
+                return Err(Error::Invalid(format!(
+                    "`{key}` must be a relative path, not `{}`",
+                    path.display()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether both paths lead to the same directory (even if they're written
+/// differently).
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Whether the given package name is a keyword reserved in the given edition,
+/// which means it can't be used as the module name of the package (see
+/// [`Project::module_name`]).
+fn is_keyword(name: &str, edition: Edition) -> bool {
+    helios_syntax::keyword_from_str(&name.replace('-', "_"))
+        .is_some_and(|keyword| edition.is_reserved(keyword))
+}
+
+fn is_valid_version(version: &str) -> bool {
+    let parts = version.split('.').collect::<Vec<_>>();
+    parts.len() == 3
+        && parts.iter().all(|part| {
+            !part.is_empty()
+                && part.chars().all(|c| c.is_ascii_digit())
+                && (part.len() == 1 || !part.starts_with('0'))
+        })
+}
+
+/// A project: the directory containing a manifest, along with the manifest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Project {
+    root: PathBuf,
+    manifest: Manifest,
+}
+
+impl Project {
+    /// Loads the project whose root is the given directory.
+    pub fn load(root: impl Into<PathBuf>) -> Result<Self, Error> {
+        let root = root.into();
+        let path = root.join(MANIFEST_NAME);
+        let source = std::fs::read_to_string(&path)
+            .map_err(|error| Error::Io(path, error.to_string()))?;
+
+        Ok(Self {
+            manifest: Manifest::from_toml(&source)?,
+            root,
+        })
+    }
+
+    /// Loads the project containing the given directory, which is the closest
+    /// directory (starting with the given one and walking up its ancestors)
+    /// that has a manifest.
+    ///
+    /// `None` is returned if none of them have one.
+    pub fn discover(start: &Path) -> Result<Option<Self>, Error> {
+        start
+            .ancestors()
+            .find(|dir| dir.join(MANIFEST_NAME).is_file())
+            .map(Self::load)
+            .transpose()
+    }
+
+    /// Creates a new project named `name` in the given directory (creating it
+    /// if needed), made of a manifest, an entry point and a `.gitignore`
+    /// listing what building the project writes.
+    ///
+    /// The directory mustn't already contain a manifest, but its other files
+    /// are kept as they are.
+    pub fn create(root: impl Into<PathBuf>, name: &str) -> Result<Self, Error> {
+        let root = root.into();
+        let manifest_path = root.join(MANIFEST_NAME);
+        if manifest_path.exists() {
+            return Err(Error::AlreadyExists(root));
+        }
+
+        let manifest_source = format!(
+            "[package]\nname = \"{name}\"\nversion = \"0.1.0\"\n\
+            edition = \"{}\"\n\n[dependencies]\n",
+            Edition::LATEST
+        );
+        let project = Self {
+            manifest: Manifest::from_toml(&manifest_source)?,
+            root,
+        };
+
+        let source_dir = project.source_dir();
+        std::fs::create_dir_all(&source_dir)
+            .map_err(|error| Error::Io(source_dir, error.to_string()))?;
+
+        // Everything building the project writes goes in `target`.
+        let gitignore = "/target\n";
+        for (path, contents) in [
+            (project.entry_point(), MAIN_TEMPLATE),
+            (project.root.join(".gitignore"), gitignore),
+            (manifest_path, manifest_source.as_str()),
+        ] {
+            if !path.exists() {
+                std::fs::write(&path, contents)
+                    .map_err(|error| Error::Io(path, error.to_string()))?;
+            }
+        }
+
+        Ok(project)
+    }
+
+    /// The directory containing the project's manifest.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    pub fn name(&self) -> &str {
+        &self.manifest.package.name
+    }
+
+    pub fn version(&self) -> &str {
+        &self.manifest.package.version
+    }
+
+    pub fn edition(&self) -> Edition {
+        self.manifest.package.edition
+    }
+
+    /// The name the project's modules are namespaced by when it's a
+    /// dependency, which is its name with `-` replaced by `_` (since module
+    /// paths are made of identifiers).
+    ///
+    /// Names that would make a module name be a keyword are rejected when
+    /// loading the manifest, so this is always a valid identifier.
+    pub fn module_name(&self) -> String {
+        self.name().replace('-', "_")
+    }
+
+    /// Loads the projects this project depends on, in the order they're
+    /// declared.
+    pub fn dependencies(&self) -> Result<Vec<Project>, Error> {
+        let dependencies = &self.manifest.dependencies;
+        dependencies
+            .iter()
+            .map(|(name, dependency)| {
+                let path = dependency.path.as_ref().expect("Validated above");
+                let project = Self::load(self.root.join(path))?;
+                if project.name() != name {
+                    return Err(Error::Invalid(format!(
+                        "the dependency `{name}` points to `{}`, whose \
+                        package is named `{}`",
+                        project.root.display(),
+                        project.name()
+                    )));
+                }
+
+                Ok(project)
+            })
+            .collect()
+    }
+
+    /// Loads every project this project depends on, directly or indirectly,
+    /// with every package appearing once (even if several projects depend on
+    /// it).
+    ///
+    /// Two different projects can't have the same name, since their modules
+    /// would have the same namespace.
+    pub fn packages(&self) -> Result<Vec<Project>, Error> {
+        // The project itself comes first, so that dependencies depending on
+        // it back aren't loaded again.
+        let mut packages = vec![self.clone()];
+        let mut stack = self.dependencies()?;
+        stack.reverse();
+
+        while let Some(project) = stack.pop() {
+            match packages.iter().find(|it| it.name() == project.name()) {
+                Some(other) if same_dir(&other.root, &project.root) => continue,
+                Some(other) => {
+                    return Err(Error::Invalid(format!(
+                        "two different packages are named `{}` (in `{}` and \
+                        `{}`)",
+                        project.name(),
+                        other.root.display(),
+                        project.root.display()
+                    )))
+                }
+                None => {}
+            }
+
+            let mut dependencies = project.dependencies()?;
+            dependencies.reverse();
+            stack.extend(dependencies);
+            packages.push(project);
+        }
+
+        packages.remove(0);
+        Ok(packages)
+    }
+
+    /// The directory containing the project's source files.
+    pub fn source_dir(&self) -> PathBuf {
+        self.root.join(&self.manifest.package.source_dir)
+    }
+
+    /// The file where the project's program starts.
+    pub fn entry_point(&self) -> PathBuf {
+        self.source_dir().join(&self.manifest.package.entry_point)
+    }
+}
+
+/// The formatter settings of the file at the given path: those of the project
+/// containing it, or else the default ones.
+pub fn formatter_config(path: &Path) -> Result<FormatterConfig, Error> {
+    // Relative paths are made absolute first, so that projects above the
+    // current directory are found too.
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.into());
+    let project = Project::discover(&path)?;
+    Ok(project
+        .map(|project| project.manifest.fmt)
+        .unwrap_or_default())
+}
+
+/// An error encountered while loading a project.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// The file at the given path couldn't be read or written.
+    Io(PathBuf, String),
+    /// A project can't be created in the given directory, which already
+    /// contains one.
+    AlreadyExists(PathBuf),
+    /// The manifest isn't valid TOML, or is missing some keys.
+    Parse(String),
+    /// The manifest is well-formed, but one of its values isn't valid.
+    Invalid(String),
+}
+
+impl std::error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, error) => {
+                write!(f, "failed to access `{}`: {error}", path.display())
+            }
+            Self::AlreadyExists(root) => write!(
+                f,
+                "`{}` already contains a `{MANIFEST_NAME}`",
+                root.display()
+            ),
+            Self::Parse(error) => {
+                write!(f, "failed to parse the manifest: {error}")
+            }
+            Self::Invalid(error) => write!(f, "invalid manifest: {error}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helios_parser::printer::TrailingCommas;
+
+    #[test]
+    fn test_minimal_manifest() {
+        let manifest = Manifest::from_toml(
+            "[package]\nname = \"hello\"\nversion = \"0.1.0\"",
+        )
+        .unwrap();
+
+        assert_eq!(manifest.package.name, "hello");
+        assert_eq!(manifest.package.version, "0.1.0");
+        assert_eq!(manifest.package.edition, Edition::Edition2025);
+        assert_eq!(manifest.package.source_dir, Path::new("src"));
+        assert_eq!(manifest.package.entry_point, Path::new("main.helios"));
+        assert!(manifest.dependencies.is_empty());
+        assert_eq!(manifest.fmt, FormatterConfig::default());
+    }
+
+    #[test]
+    fn test_full_manifest() {
+        let manifest = Manifest::from_toml(
+            r#"
+[package]
+name = "hello-world"
+version = "1.10.0"
+edition = "2026"
+source-dir = "lib"
+entry-point = "app/start.helios"
+
+[dependencies]
+math = { path = "../math" }
+
+[fmt]
+max-width = 100
+indent-width = 2
+trailing-commas = "never"
+max-blank-lines = 2
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.package.edition, Edition::Edition2026);
+        assert_eq!(manifest.package.source_dir, Path::new("lib"));
+        assert_eq!(manifest.package.entry_point, Path::new("app/start.helios"));
+        assert_eq!(manifest.dependencies.keys().collect::<Vec<_>>(), ["math"]);
+        assert_eq!(
+            manifest.fmt,
+            FormatterConfig {
+                max_width: 100,
+                indent_width: 2,
+                trailing_commas: TrailingCommas::Never,
+                max_blank_lines: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_manifest() {
+        let manifest = |package: &str| {
+            Manifest::from_toml(&format!("[package]\n{package}"))
+        };
+
+        assert!(matches!(manifest(""), Err(Error::Parse(_))));
+        assert!(matches!(
+            manifest("name = \"a\"\nversion = \"0.1.0\"\nsrc = \"lib\""),
+            Err(Error::Parse(_))
+        ));
+        assert!(matches!(
+            manifest("name = \"a\"\nversion = \"0.1.0\"\n[fmt]\nwidth = 80"),
+            Err(Error::Parse(_))
+        ));
+        assert!(matches!(
+            manifest("name = \"a\"\nversion = \"0.1.0\"\nedition = \"2\""),
+            Err(Error::Parse(_))
+        ));
+
+        for package in [
+            "name = \"1a\"\nversion = \"0.1.0\"",
+            "name = \"a b\"\nversion = \"0.1.0\"",
+            "name = \"a\"\nversion = \"0.1\"",
+            "name = \"a\"\nversion = \"0.01.0\"",
+            "name = \"a\"\nversion = \"0.1.0\"\nsource-dir = \"/src\"",
+            "name = \"a\"\nversion = \"0.1.0\"\n[fmt]\nindent-width = 0",
+            "name = \"let\"\nversion = \"0.1.0\"",
+            "name = \"macro\"\nversion = \"0.1.0\"\nedition = \"2026\"",
+        ] {
+            assert!(
+                matches!(manifest(package), Err(Error::Invalid(_))),
+                "{package}"
+            );
+        }
+
+        // Soft keywords are only reserved from the edition reserving them.
+        assert!(manifest("name = \"macro\"\nversion = \"0.1.0\"").is_ok());
+
+        let dependency = |dependency: &str| {
+            manifest(&format!(
+                "name = \"a\"\nversion = \"0.1.0\"\n[dependencies]\n\
+                {dependency}"
+            ))
+        };
+        assert!(dependency("b = { path = \"../b\" }").is_ok());
+        assert!(matches!(dependency("b = \"0.1.0\""), Err(Error::Parse(_))));
+        for invalid in [
+            "b = {}",
+            "b = { git = \"https://example.com/b\" }",
+            "\"1b\" = { path = \"../b\" }",
+            "import = { path = \"../import\" }",
+        ] {
+            assert!(
+                matches!(dependency(invalid), Err(Error::Invalid(_))),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_discover_project() {
+        let root = std::env::temp_dir()
+            .join(format!("helios-project-test-{}", std::process::id()));
+        let nested = root.join("src").join("nested");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(
+            root.join(MANIFEST_NAME),
+            "[package]\nname = \"hello\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+
+        let project = Project::discover(&nested);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let project = project.unwrap().unwrap();
+        assert_eq!(project.root(), root);
+        assert_eq!(project.name(), "hello");
+        assert_eq!(project.entry_point(), root.join("src").join("main.helios"));
+    }
+
+    #[test]
+    fn test_formatter_config() {
+        let root = std::env::temp_dir()
+            .join(format!("helios-fmt-test-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join(MANIFEST_NAME),
+            "[package]\nname = \"hello\"\nversion = \"0.1.0\"\n\n\
+             [fmt]\nindent-width = 2\n",
+        )
+        .unwrap();
+
+        let inside = formatter_config(&root.join("src").join("main.helios"));
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(inside.unwrap().indent_width, 2);
+
+        // Files outside of any project use the default settings.
+        let outside = formatter_config(Path::new("/main.helios"));
+        assert_eq!(outside, Ok(FormatterConfig::default()));
+    }
+
+    #[test]
+    fn test_create_project() {
+        let root = std::env::temp_dir()
+            .join(format!("helios-create-test-{}", std::process::id()));
+        let project = Project::create(&root, "hello");
+        let loaded = Project::load(&root);
+        let main = std::fs::read_to_string(root.join("src/main.helios"));
+        let gitignore = std::fs::read_to_string(root.join(".gitignore"));
+        let again = Project::create(&root, "hello");
+        let invalid = Project::create(root.join("invalid"), "1hello");
+        let keyword = Project::create(root.join("keyword"), "let");
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let project = project.unwrap();
+        assert_eq!(loaded.unwrap(), project);
+        assert_eq!(project.name(), "hello");
+        assert_eq!(project.version(), "0.1.0");
+        assert_eq!(project.edition(), Edition::LATEST);
+        assert_eq!(main.unwrap(), MAIN_TEMPLATE);
+        assert_eq!(gitignore.unwrap(), "/target\n");
+        assert_eq!(again, Err(Error::AlreadyExists(root)));
+        assert!(matches!(invalid, Err(Error::Invalid(_))));
+        assert!(matches!(keyword, Err(Error::Invalid(_))));
+    }
+
+    #[test]
+    fn test_load_packages() {
+        let root = std::env::temp_dir()
+            .join(format!("helios-packages-test-{}", std::process::id()));
+        let write = |dir: &str, name: &str, dependencies: &str| {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            std::fs::write(
+                root.join(dir).join(MANIFEST_NAME),
+                format!(
+                    "[package]\nname = \"{name}\"\nversion = \"0.1.0\"\n\
+                    [dependencies]\n{dependencies}"
+                ),
+            )
+            .unwrap();
+        };
+
+        // Packages depending on each other are only loaded once.
+        write("app", "app", "my-math = { path = \"../math\" }");
+        write("math", "my-math", "util = { path = \"../util\" }");
+        write("util", "util", "my-math = { path = \"../math\" }");
+        write("wrong", "app", "util = { path = \"../math\" }");
+        let packages = Project::load(root.join("app")).unwrap().packages();
+        let wrong = Project::load(root.join("wrong")).unwrap().packages();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let packages = packages.unwrap();
+        let names = packages.iter().map(Project::name).collect::<Vec<_>>();
+        assert_eq!(names, ["my-math", "util"]);
+        assert_eq!(packages[0].module_name(), "my_math");
+        assert!(matches!(wrong, Err(Error::Invalid(_))));
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{BindingData, BindingId, BindingKind, CheckCancelled, FileId};
+use crate::{Modules, Scopes};
+use helios_syntax::{SyntaxKind, SyntaxNode, SyntaxToken};
+use rowan::TextSize;
+use std::ops::Range;
+use std::sync::Arc;
+
+#[salsa::query_group(BindingsDatabase)]
+pub trait Bindings: Modules + Scopes {
+    /// All the global bindings declared in a file, in the order they appear.
+    fn global_bindings(&self, file_id: FileId) -> Arc<Vec<GlobalBinding>>;
+
+    /// The global bindings declared in every file of the workspace (see
+    /// [`Input::workspace_files`]), grouped by file.
+    fn workspace_symbols(&self) -> Arc<Vec<(FileId, GlobalBinding)>>;
+
+    /// The documentation comments immediately preceding each item of a file
+    /// (without the leading `##` or `#!`), in the order the items appear.
+    fn item_docs(&self, file_id: FileId) -> Arc<Vec<Option<String>>>;
+
+    /// Every binding declared in a file (by global bindings and imports), in
+    /// the order they appear.
+    fn all_bindings(&self, file_id: FileId) -> Arc<Vec<BindingId>>;
+
+    /// The binding declared or referred to by the identifier at the given
+    /// offset, if there is one.
+    ///
+    /// A variable reference refers to the binding its name resolves to (see
+    /// [`Scopes::name_resolutions`]).
+    fn binding_at_offset(
+        &self,
+        file_id: FileId,
+        offset: usize,
+    ) -> Option<BindingId>;
+}
+
+/// A global binding (i.e. a top-level `let` declaration) found in a file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GlobalBinding {
+    /// The name of the binding.
+    pub name: String,
+    /// The range of the binding's identifier.
+    pub range: Range<usize>,
+    /// The text of the documentation comments immediately preceding the
+    /// binding (without the leading `##` or `#!`), if there are any.
+    pub doc: Option<String>,
+}
+
+fn global_bindings(
+    db: &dyn Bindings,
+    file_id: FileId,
+) -> Arc<Vec<GlobalBinding>> {
+    db.unwind_if_cancelled();
+    let root = db.parse(file_id).syntax();
+    let bindings = root
+        .children()
+        .filter(|node| node.kind() == SyntaxKind::Dec_GlobalBinding)
+        .filter_map(|node| global_binding(&node))
+        .collect();
+
+    Arc::new(bindings)
+}
+
+fn workspace_symbols(db: &dyn Bindings) -> Arc<Vec<(FileId, GlobalBinding)>> {
+    let symbols = db
+        .workspace_files()
+        .iter()
+        .flat_map(|&file_id| {
+            db.unwind_if_cancelled();
+            db.global_bindings(file_id)
+                .iter()
+                .map(|binding| (file_id, binding.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    Arc::new(symbols)
+}
+
+fn item_docs(db: &dyn Bindings, file_id: FileId) -> Arc<Vec<Option<String>>> {
+    db.unwind_if_cancelled();
+    let root = db.parse(file_id).syntax();
+    let docs = helios_hir::item_nodes(&root)
+        .map(|node| node.first_token().and_then(|token| doc_comment(&token)))
+        .collect();
+
+    Arc::new(docs)
+}
+
+fn all_bindings(db: &dyn Bindings, file_id: FileId) -> Arc<Vec<BindingId>> {
+    db.unwind_if_cancelled();
+    let globals = db.global_bindings(file_id);
+    let globals = globals.iter().map(|binding| BindingData {
+        file_id,
+        name: binding.name.clone(),
+        kind: BindingKind::Global,
+        range: binding.range.clone(),
+    });
+
+    let imports = db.imports(file_id);
+    let imports = imports.iter().filter_map(|import| {
+        let name = import.path.last()?;
+        Some(BindingData {
+            file_id,
+            name: name.clone(),
+            kind: BindingKind::Import,
+            range: import.range.end - name.len()..import.range.end,
+        })
+    });
+
+    let mut bindings = globals.chain(imports).collect::<Vec<_>>();
+    bindings.sort_by_key(|binding| binding.range.start);
+
+    let bindings = bindings
+        .into_iter()
+        .map(|binding| db.intern_binding(binding))
+        .collect();
+
+    Arc::new(bindings)
+}
+
+fn binding_at_offset(
+    db: &dyn Bindings,
+    file_id: FileId,
+    offset: usize,
+) -> Option<BindingId> {
+    let root = db.parse(file_id).syntax();
+    let identifier = root
+        .token_at_offset(TextSize::try_from(offset).ok()?)
+        .find(|token| token.kind() == SyntaxKind::Identifier)?;
+
+    let range = identifier.text_range();
+    let range = usize::from(range.start())..usize::from(range.end());
+    let bindings = db
+        .all_bindings(file_id)
+        .iter()
+        .map(|&binding| (binding, db.lookup_intern_binding(binding)))
+        .collect::<Vec<_>>();
+
+    if let Some((binding, _)) =
+        bindings.iter().find(|(_, data)| data.range == range)
+    {
+        return Some(*binding);
+    }
+
+    let is_reference = identifier
+        .parent()
+        .is_some_and(|parent| parent.kind() == SyntaxKind::Exp_VariableRef);
+    if !is_reference {
+        return None;
+    }
+
+    let offset = identifier.text_range().start();
+    let (module, source_map) = db.hir_module_with_source_map(file_id);
+    let (item_id, _) = module.items.iter().find(|&(item_id, _)| {
+        source_map.item_range(item_id).contains(offset)
+    })?;
+
+    let (_, body_source_map) = db.body_with_source_map(file_id, item_id);
+    let expr_id = body_source_map.expr_at_offset(offset)?;
+    let target = db.name_resolutions(file_id, item_id).get(expr_id)?;
+
+    // The name of an import is the last segment of its path, which ends where
+    // the path does.
+    let end = usize::from(source_map.name_range(target)?.end());
+    bindings
+        .iter()
+        .find(|(_, data)| data.range.end == end)
+        .map(|(binding, _)| *binding)
+}
+
+fn global_binding(node: &SyntaxNode) -> Option<GlobalBinding> {
+    let identifier = node
+        .children_with_tokens()
+        .filter_map(|element| element.into_token())
+        .find(|token| token.kind() == SyntaxKind::Identifier)?;
+
+    let range = identifier.text_range();
+    let doc = node.first_token().and_then(|token| doc_comment(&token));
+
+    Some(GlobalBinding {
+        name: identifier.text().to_string(),
+        range: range.start().into()..range.end().into(),
+        doc,
+    })
+}
+
+/// Collects the documentation comments directly above the given token.
+///
+/// Only whitespace and single line breaks may separate the comments from the
+/// token (and each other); anything else (including a blank line) ends the
+/// documentation.
+fn doc_comment(token: &SyntaxToken) -> Option<String> {
+    let mut lines = Vec::new();
+    let mut token = token.prev_token();
+    let mut after_newline = false;
+
+    while let Some(current) = token {
+        match current.kind() {
+            SyntaxKind::DocComment => {
+                let text = current.text();
+                let text = text.get(2..).unwrap_or_default();
+                lines.push(text.strip_prefix(' ').unwrap_or(text).to_string());
+                after_newline = false;
+            }
+            SyntaxKind::Whitespace => {}
+            // A blank line separates the comments from the token (blank lines
+            // are part of the newline before them).
+            SyntaxKind::Newline
+                if after_newline
+                    || current.text().matches('\n').count() > 1 =>
+            {
+                break
+            }
+            SyntaxKind::Newline => after_newline = true,
+            _ => break,
+        }
+
+        token = current.prev_token();
+    }
+
+    if lines.is_empty() {
+        None
+    } else {
+        lines.reverse();
+        Some(lines.join("\n"))
+    }
+}
@@ -1,11 +1,39 @@
 use std::error::Error;
 use std::fmt::{self, Display};
+use std::panic::{self, UnwindSafe};
 
 pub type Cancelable<T> = Result<T, Cancelled>;
 
+/// The payload a thread unwinds with when the revision it was computing
+/// queries for is cancelled (i.e. when an input is about to be changed).
 #[derive(Debug)]
 pub struct Cancelled;
 
+impl Cancelled {
+    /// Unwinds the current thread with [`Cancelled`] as the payload.
+    pub fn throw() -> ! {
+        // `resume_unwind` doesn't run the panic hook, so no message is printed
+        // for what is an expected event.
+        panic::resume_unwind(Box::new(Cancelled))
+    }
+
+    /// Runs the given function, turning a cancellation into an error.
+    ///
+    /// Any other panic is propagated as-is.
+    pub fn catch<F, T>(f: F) -> Cancelable<T>
+    where
+        F: FnOnce() -> T + UnwindSafe,
+    {
+        match panic::catch_unwind(f) {
+            Ok(value) => Ok(value),
+            Err(payload) => match payload.downcast::<Cancelled>() {
+                Ok(cancelled) => Err(*cancelled),
+                Err(payload) => panic::resume_unwind(payload),
+            },
+        }
+    }
+}
+
 impl Display for Cancelled {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "Cancelled")
@@ -13,3 +41,20 @@ impl Display for Cancelled {
 }
 
 impl Error for Cancelled {}
+
+/// Cooperative cancellation of long-running queries.
+///
+/// Changing an input blocks until every snapshot of the database is dropped,
+/// so queries running on a snapshot should regularly call
+/// [`CheckCancelled::unwind_if_cancelled`] to get out of the way as soon as
+/// their results are outdated.
+pub trait CheckCancelled: salsa::Database {
+    /// Unwinds with [`Cancelled`] if an input is waiting to be changed.
+    fn unwind_if_cancelled(&self) {
+        if self.salsa_runtime().is_current_revision_canceled() {
+            Cancelled::throw();
+        }
+    }
+}
+
+impl<DB: salsa::Database + ?Sized> CheckCancelled for DB {}
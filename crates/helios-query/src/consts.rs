@@ -0,0 +1,222 @@
+use crate::{CheckCancelled, FileId, Scopes, PRELUDE_FILE_ID};
+use helios_diagnostics::{Diagnostic, Location};
+use helios_formatting::FormattedString;
+use helios_hir::{
+    ConstError, ConstValues, Expr, RuntimeError, RuntimeErrorKind,
+};
+use std::sync::Arc;
+
+#[salsa::query_group(ConstsDatabase)]
+pub trait Consts: Scopes {
+    /// The values of the expressions of a file that can be computed at compile
+    /// time.
+    fn const_values(&self, file_id: FileId) -> Arc<ConstValues>;
+
+    /// Diagnostics for the constant expressions of a file that can't be
+    /// evaluated (such as divisions by zero or integer literals that don't fit
+    /// in an `Int`).
+    fn const_diagnostics(
+        &self,
+        file_id: FileId,
+    ) -> Arc<Vec<Diagnostic<FileId>>>;
+}
+
+fn const_values(db: &dyn Consts, file_id: FileId) -> Arc<ConstValues> {
+    let module = db.hir_module(file_id);
+    let prelude = match file_id {
+        PRELUDE_FILE_ID => Arc::default(),
+        _ => db.const_values(PRELUDE_FILE_ID),
+    };
+
+    let values =
+        helios_hir::eval_module_with_prelude(&module, &prelude, |item_id| {
+            db.unwind_if_cancelled();
+            let body = db.body(file_id, item_id);
+            (body, db.name_resolutions(file_id, item_id))
+        });
+
+    Arc::new(values)
+}
+
+fn const_diagnostics(
+    db: &dyn Consts,
+    file_id: FileId,
+) -> Arc<Vec<Diagnostic<FileId>>> {
+    let values = db.const_values(file_id);
+    let mut diagnostics = Vec::new();
+
+    for error in values.errors() {
+        let (body, source_map) = db.body_with_source_map(file_id, error.item());
+        let location =
+            |expr_id| Location::new(file_id, source_map.expr_range(expr_id));
+
+        let diagnostic = match error {
+            ConstError::DivisionByZero { expr, .. } => {
+                let diagnostic = division_by_zero();
+                match body.exprs[*expr] {
+                    Expr::Binary { rhs, .. } => {
+                        diagnostic.label(location(rhs), "this evaluates to `0`")
+                    }
+                    _ => diagnostic,
+                }
+            }
+            ConstError::Overflow { .. } => integer_overflow(),
+            ConstError::OutOfRange { expr, .. } => {
+                let range = source_map.expr_range(*expr);
+                let source = db.source(file_id);
+                let literal = &source[usize::from(range.start())..]
+                    [..usize::from(range.len())];
+                literal_out_of_range(literal)
+            }
+        };
+
+        diagnostics.push(diagnostic.location(location(error.expr())));
+    }
+
+    Arc::new(diagnostics)
+}
+
+/// The diagnostic for an error raised while interpreting a file (see
+/// [`helios_hir::interpret_module`]), located at the expression raising it.
+pub fn runtime_error_diagnostic(
+    db: &dyn Consts,
+    file_id: FileId,
+    error: &RuntimeError,
+) -> Diagnostic<FileId> {
+    let (body, source_map) = db.body_with_source_map(file_id, error.item);
+    let location =
+        |expr_id| Location::new(file_id, source_map.expr_range(expr_id));
+
+    let diagnostic = match &error.kind {
+        RuntimeErrorKind::DivisionByZero => {
+            let diagnostic = division_by_zero();
+            match body.exprs[error.expr] {
+                Expr::Binary { rhs, .. } => {
+                    diagnostic.label(location(rhs), "this evaluated to `0`")
+                }
+                _ => diagnostic,
+            }
+        }
+        RuntimeErrorKind::Overflow => integer_overflow(),
+        RuntimeErrorKind::UnboundExternal(name) => {
+            let description = FormattedString::default()
+                .text("This external function isn't bound to anything:");
+
+            let message = FormattedString::default()
+                .code(name.to_string())
+                .text(" is declared as an external function, but the host ")
+                .text("running the program has no function with that name.");
+
+            Diagnostic::error("Unbound external function")
+                .description(description)
+                .message(message)
+        }
+        RuntimeErrorKind::ExternalTypeMismatch { name, value } => {
+            let description = FormattedString::default()
+                .text("This external function returned a value of the wrong ")
+                .text("type:");
+
+            let message = FormattedString::default()
+                .text("The function of the host bound to ")
+                .code(name.to_string())
+                .text(" returned ")
+                .code(value.to_string())
+                .text(", which doesn't have the type ")
+                .code(name.to_string())
+                .text(" is declared with.");
+
+            Diagnostic::error("Mistyped external function")
+                .description(description)
+                .message(message)
+        }
+        RuntimeErrorKind::Unsupported(what) => {
+            let description = FormattedString::default()
+                .text("I don't know how to run this expression:");
+
+            let message = FormattedString::default()
+                .text(format!("Interpreting {what} isn't supported yet."));
+
+            Diagnostic::error("Unsupported expression")
+                .description(description)
+                .message(message)
+        }
+    };
+
+    diagnostic.location(location(error.expr))
+}
+
+fn division_by_zero() -> Diagnostic<FileId> {
+    let description =
+        FormattedString::default().text("This expression divides by zero:");
+
+    let message = FormattedString::default()
+        .text("Integers can't be divided by ")
+        .code("0")
+        .text(", so I can't compute the value of this expression.");
+
+    Diagnostic::error("Division by zero")
+        .description(description)
+        .message(message)
+}
+
+fn integer_overflow() -> Diagnostic<FileId> {
+    let description = FormattedString::default()
+        .text("This integer is too large to be stored:");
+
+    let message = FormattedString::default()
+        .text("An ")
+        .code("Int")
+        .text(" can only hold integers from ")
+        .code(i64::MIN.to_string())
+        .text(" to ")
+        .code(i64::MAX.to_string())
+        .text(".");
+
+    Diagnostic::error("Integer overflow")
+        .description(description)
+        .message(message)
+}
+
+fn literal_out_of_range(literal: &str) -> Diagnostic<FileId> {
+    let description = FormattedString::default()
+        .text("This integer literal is too large to be stored:");
+
+    let message = FormattedString::default()
+        .text("The literal ")
+        .code(literal)
+        .text(" doesn't fit in an ")
+        .code("Int")
+        .text(", and I won't truncate it to a smaller value.");
+
+    // The range is written in the same base as the literal, since a
+    // non-decimal literal is usually meant to have a given number of bits.
+    let (min, max) = match literal.get(..2) {
+        Some("0b") => (
+            format!("-0b{:b}", i64::MIN.unsigned_abs()),
+            format!("0b{:b}", i64::MAX),
+        ),
+        Some("0o") => (
+            format!("-0o{:o}", i64::MIN.unsigned_abs()),
+            format!("0o{:o}", i64::MAX),
+        ),
+        Some("0x") => (
+            format!("-0x{:x}", i64::MIN.unsigned_abs()),
+            format!("0x{:x}", i64::MAX),
+        ),
+        _ => (i64::MIN.to_string(), i64::MAX.to_string()),
+    };
+
+    let hint = FormattedString::default()
+        .text("An ")
+        .code("Int")
+        .text(" can only hold integers from ")
+        .code(min)
+        .text(" to ")
+        .code(max)
+        .text(".");
+
+    Diagnostic::error("Integer literal out of range")
+        .description(description)
+        .message(message)
+        .hint(hint)
+}
@@ -0,0 +1,54 @@
+use crate::{Bindings, CheckCancelled, Consts, FileId, Modules, Scopes, Types};
+use helios_diagnostics::Diagnostic;
+use std::cmp::Reverse;
+use std::sync::Arc;
+
+#[salsa::query_group(DiagnosticsDatabase)]
+pub trait Diagnostics: Bindings + Consts + Modules + Scopes + Types {
+    /// Every diagnostic reported for a file (by the parser, when resolving its
+    /// imports and names, for its unused declarations, when inferring its
+    /// types and when evaluating its constants), sorted by where they are in
+    /// the file and without any duplicates.
+    fn diagnostics(&self, file_id: FileId) -> Arc<Vec<Diagnostic<FileId>>>;
+}
+
+fn diagnostics(
+    db: &dyn Diagnostics,
+    file_id: FileId,
+) -> Arc<Vec<Diagnostic<FileId>>> {
+    db.unwind_if_cancelled();
+    let mut diagnostics = db.parse_diagnostics(file_id).to_vec();
+    diagnostics.extend(db.module_diagnostics(file_id).iter().cloned());
+    diagnostics.extend(db.name_diagnostics(file_id).iter().cloned());
+    diagnostics.extend(db.unused_diagnostics(file_id).iter().cloned());
+    diagnostics.extend(db.type_diagnostics(file_id).iter().cloned());
+    diagnostics.extend(db.const_diagnostics(file_id).iter().cloned());
+
+    // The sort is stable, so diagnostics at the same location stay in the
+    // order they were reported in (after the more severe ones).
+    diagnostics.sort_by_key(sort_key);
+
+    let mut unique =
+        Vec::<Diagnostic<FileId>>::with_capacity(diagnostics.len());
+    for diagnostic in diagnostics {
+        let key = sort_key(&diagnostic);
+        let is_duplicate = unique
+            .iter()
+            .rev()
+            .take_while(|other| sort_key(other) == key)
+            .any(|other| *other == diagnostic);
+
+        if !is_duplicate {
+            unique.push(diagnostic);
+        }
+    }
+
+    Arc::new(unique)
+}
+
+fn sort_key<FileId>(
+    diagnostic: &Diagnostic<FileId>,
+) -> (usize, usize, Reverse<helios_diagnostics::Severity>) {
+    let range = &diagnostic.location.range;
+    (range.start, range.end, Reverse(diagnostic.severity))
+}
@@ -0,0 +1,64 @@
+use crate::{CheckCancelled, FileId, Input};
+use helios_hir::{Body, BodySourceMap, ItemId, Module, ModuleSourceMap};
+use std::sync::Arc;
+
+#[salsa::query_group(HirDatabase)]
+pub trait Hir: Input {
+    /// The HIR of a file, along with where its items come from.
+    fn hir_module_with_source_map(
+        &self,
+        file_id: FileId,
+    ) -> (Arc<Module>, Arc<ModuleSourceMap>);
+
+    /// The HIR of a file.
+    ///
+    /// Unlike [`Hir::hir_module_with_source_map`], this only changes when the
+    /// items of the file do (rather than on every edit), so queries that don't
+    /// need text ranges should depend on this instead.
+    fn hir_module(&self, file_id: FileId) -> Arc<Module>;
+
+    /// The expressions and patterns of an item of a file, along with where
+    /// they come from.
+    fn body_with_source_map(
+        &self,
+        file_id: FileId,
+        item_id: ItemId,
+    ) -> (Arc<Body>, Arc<BodySourceMap>);
+
+    /// The expressions and patterns of an item of a file, which only change
+    /// when the item's body does.
+    fn body(&self, file_id: FileId, item_id: ItemId) -> Arc<Body>;
+}
+
+fn hir_module_with_source_map(
+    db: &dyn Hir,
+    file_id: FileId,
+) -> (Arc<Module>, Arc<ModuleSourceMap>) {
+    db.unwind_if_cancelled();
+    let root = db.parse(file_id).syntax();
+    let (module, source_map) = helios_hir::lower_module(&root);
+    (Arc::new(module), Arc::new(source_map))
+}
+
+fn hir_module(db: &dyn Hir, file_id: FileId) -> Arc<Module> {
+    db.hir_module_with_source_map(file_id).0
+}
+
+fn body_with_source_map(
+    db: &dyn Hir,
+    file_id: FileId,
+    item_id: ItemId,
+) -> (Arc<Body>, Arc<BodySourceMap>) {
+    db.unwind_if_cancelled();
+    let root = db.parse(file_id).syntax();
+    let (body, source_map) = helios_hir::item_nodes(&root)
+        .nth(item_id.into_raw() as usize)
+        .map(|node| helios_hir::lower_body(&node))
+        .expect("Items should come from the HIR of the same file");
+
+    (Arc::new(body), Arc::new(source_map))
+}
+
+fn body(db: &dyn Hir, file_id: FileId, item_id: ItemId) -> Arc<Body> {
+    db.body_with_source_map(file_id, item_id).0
+}
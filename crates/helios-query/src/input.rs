@@ -1,27 +1,96 @@
 #![allow(unused)]
 
-use crate::interner::{BindingData, BindingId, Interner};
+use crate::interner::Interner;
+use crate::CheckCancelled;
 use helios_diagnostics::Diagnostic;
-use helios_parser::Parse;
-use std::sync::Arc;
+use helios_parser::{CookedLiteral, Edition, NodeCache, Parse};
+use rowan::TextSize;
+use salsa::Durability;
+use std::sync::{Arc, Mutex};
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct FileId(pub u32);
+pub use helios_diagnostics::FileId;
+
+/// Where a file comes from, which determines how often it's expected to
+/// change.
+///
+/// Salsa only re-validates the queries depending on inputs that are at least
+/// as durable as the input that changed. Storing the files of the standard
+/// library and dependencies as durable inputs means that editing one of the
+/// user's files doesn't re-validate everything computed for them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum FileOrigin {
+    /// A file of the user's own workspace, which may change at any time.
+    Workspace,
+    /// A file of a dependency, which rarely changes.
+    Dependency,
+    /// A file of the standard library, which never changes.
+    StandardLibrary,
+}
+
+impl FileOrigin {
+    /// The durability the inputs of a file with this origin are stored with.
+    pub fn durability(self) -> Durability {
+        match self {
+            FileOrigin::Workspace => Durability::LOW,
+            FileOrigin::Dependency => Durability::MEDIUM,
+            FileOrigin::StandardLibrary => Durability::HIGH,
+        }
+    }
+}
+
+/// Gives queries access to the cache the syntax trees of files share their
+/// nodes and tokens through (see [`helios_parser::parse_with_cache`]).
+pub trait SyntaxCache {
+    /// The cache syntax trees are built with, if there is one.
+    fn syntax_cache(&self) -> Option<&Mutex<NodeCache>>;
+}
 
 #[salsa::query_group(InputDatabase)]
-pub trait Input: Interner {
+pub trait Input: Interner + SyntaxCache {
+    /// The name of a file (usually its path) as shown to users.
+    #[salsa::input]
+    fn file_name(&self, file_id: FileId) -> Arc<String>;
+
+    /// Every file in the workspace (such as the files of a project).
+    #[salsa::input]
+    fn workspace_files(&self) -> Arc<Vec<FileId>>;
+
     /// The source text of a file.
     #[salsa::input]
     fn source(&self, file_id: FileId) -> Arc<String>;
 
+    /// The edition of the language files are written in, which is the edition
+    /// of the workspace's project (if there is one).
+    ///
+    /// Every file is parsed in this edition, including those of dependencies
+    /// and the prelude (which is valid in every edition).
+    #[salsa::input]
+    fn edition(&self) -> Edition;
+
     /// The length of a file's source text.
     fn source_len(&self, file_id: FileId) -> usize;
 
     /// The parsed syntax tree of the given file.
-    fn parse(&self, file_id: FileId) -> Parse<FileId>;
+    fn parse(&self, file_id: FileId) -> Parse;
 
     /// Diagnostics emitted by the parser for a given file.
-    fn diagnostics(&self, file_id: FileId) -> Arc<Vec<Diagnostic<FileId>>>;
+    ///
+    /// Most consumers want [`crate::Diagnostics::diagnostics`] instead, which
+    /// includes every kind of diagnostic reported for the file.
+    fn parse_diagnostics(
+        &self,
+        file_id: FileId,
+    ) -> Arc<Vec<Diagnostic<FileId>>>;
+
+    /// What the string, interpolated string or character literal starting at
+    /// the given offset of a file stands for, along with where its escape
+    /// sequences and embedded expressions are (see
+    /// [`helios_parser::cook_literal`]), or `None` if no literal starts there.
+    fn cooked_literal(
+        &self,
+        file_id: FileId,
+        offset: usize,
+    ) -> Option<Arc<CookedLiteral>>;
 }
 
 fn source_len(db: &dyn Input, file_id: FileId) -> usize {
@@ -29,12 +98,35 @@ fn source_len(db: &dyn Input, file_id: FileId) -> usize {
     source.len()
 }
 
-fn parse(db: &dyn Input, file_id: FileId) -> Parse<FileId> {
+fn parse(db: &dyn Input, file_id: FileId) -> Parse {
+    db.unwind_if_cancelled();
     let source = db.source(file_id);
-    helios_parser::parse(file_id, &source)
+    let edition = db.edition();
+    let cache = db.syntax_cache().and_then(|cache| cache.try_lock().ok());
+    let parse = match cache {
+        Some(mut cache) => helios_parser::parse_with_edition(
+            file_id, &source, edition, &mut cache,
+        ),
+        // Files are parsed in parallel, and it's quicker to build the odd tree
+        // without the cache than to wait for another thread to be done with it.
+        None => helios_parser::parse_with_edition(
+            file_id,
+            &source,
+            edition,
+            &mut NodeCache::default(),
+        ),
+    };
+
+    // Everything else (from diagnostics to edits) relies on the syntax tree
+    // holding every byte of the source text, so debug builds check it does.
+    if cfg!(debug_assertions) {
+        parse.verify_lossless(&source);
+    }
+
+    parse
 }
 
-fn diagnostics(
+fn parse_diagnostics(
     db: &dyn Input,
     file_id: FileId,
 ) -> Arc<Vec<Diagnostic<FileId>>> {
@@ -42,3 +134,22 @@ fn diagnostics(
     let messages = parse.messages();
     Arc::new(messages.iter().map(|message| message.into()).collect())
 }
+
+fn cooked_literal(
+    db: &dyn Input,
+    file_id: FileId,
+    offset: usize,
+) -> Option<Arc<CookedLiteral>> {
+    let offset = TextSize::try_from(offset).ok()?;
+    let root = db.parse(file_id).syntax();
+    if !root.text_range().contains(offset) {
+        return None;
+    }
+
+    let token = root.token_at_offset(offset).right_biased()?;
+    if token.text_range().start() != offset {
+        return None;
+    }
+
+    helios_parser::cook_literal(token.kind(), token.text()).map(Arc::new)
+}
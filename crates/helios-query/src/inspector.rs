@@ -0,0 +1,61 @@
+use crate::{FileId, HeliosDatabase, Input, InputLocation};
+use helios_diagnostics::{Error, FileInspector, Result};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// The source text of a file stored in the database.
+///
+/// This is a thin wrapper around the `Arc<String>` returned by
+/// [`Input::source`] so that it can be used as a [`FileInspector::Source`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SourceText(pub Arc<String>);
+
+impl AsRef<str> for SourceText {
+    fn as_ref(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+/// Allows diagnostics to be emitted directly against the files stored in the
+/// database (with [`helios_diagnostics::emit`]).
+impl<'a> FileInspector<'a> for HeliosDatabase {
+    type FileId = FileId;
+    type Name = Arc<String>;
+    type Source = SourceText;
+
+    fn name(&'a self, id: Self::FileId) -> Result<Self::Name> {
+        Ok(self.file_name(id))
+    }
+
+    fn source(&'a self, id: Self::FileId) -> Result<Self::Source> {
+        Ok(SourceText(Input::source(self, id)))
+    }
+
+    fn line_count(&'a self, id: Self::FileId) -> Result<usize> {
+        Ok(self.source_line_indexes(id).len())
+    }
+
+    fn line_index(
+        &'a self,
+        id: Self::FileId,
+        byte_index: usize,
+    ) -> Result<usize> {
+        Ok(self.source_line_index(id, byte_index))
+    }
+
+    fn line_range(
+        &'a self,
+        id: Self::FileId,
+        line_index: usize,
+    ) -> Result<Range<usize>> {
+        let line_count = self.line_count(id)?;
+        if line_index >= line_count {
+            return Err(Error::OutOfBounds {
+                given: line_index,
+                max: line_count - 1,
+            });
+        }
+
+        Ok(self.source_line_range(id, line_index))
+    }
+}
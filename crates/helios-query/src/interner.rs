@@ -1,12 +1,33 @@
+use crate::FileId;
+use std::ops::Range;
+
 #[salsa::query_group(InternerDatabase)]
 pub trait Interner: salsa::Database {
     #[salsa::interned]
     fn intern_binding(&self, binding: BindingData) -> BindingId;
 }
 
+/// A name declared somewhere in a file.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct BindingData {
-    pub identifier: String,
+    /// The file the binding is declared in.
+    pub file_id: FileId,
+    /// The name of the binding.
+    pub name: String,
+    /// What declares the binding.
+    pub kind: BindingKind,
+    /// The range of the identifier the binding is declared with.
+    pub range: Range<usize>,
+}
+
+/// What declares a binding.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum BindingKind {
+    /// A global binding (i.e. a top-level `let` declaration).
+    Global,
+    /// An import declaration, which binds the last segment of the imported
+    /// module's path (such as `bar` for `import foo.bar`).
+    Import,
 }
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
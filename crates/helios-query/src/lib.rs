@@ -1,30 +1,183 @@
+pub mod bindings;
 pub mod cancel;
+pub mod consts;
+pub mod diagnostics;
+pub mod hir;
 pub mod input;
+pub mod inspector;
 pub mod interner;
 pub mod location;
+pub mod modules;
+mod parallel;
+pub mod prelude;
+pub mod profile;
+pub mod scopes;
+pub mod source_root;
+pub mod types;
 
+use crate::profile::QueryProfiler;
+use helios_parser::{Edition, NodeCache};
+use salsa::{Durability, SweepStrategy};
 use std::fmt::{self, Debug};
+use std::sync::{Arc, Mutex};
 
+pub use crate::bindings::*;
+pub use crate::cancel::*;
+pub use crate::consts::*;
+pub use crate::diagnostics::*;
+pub use crate::hir::*;
 pub use crate::input::*;
+pub use crate::inspector::*;
 pub use crate::interner::*;
 pub use crate::location::*;
+pub use crate::modules::*;
+pub use crate::prelude::*;
+pub use crate::profile::{QueryStat, QueryStats};
+pub use crate::scopes::*;
+pub use crate::source_root::*;
+pub use crate::types::*;
 
-#[salsa::database(InputLocationDatabase, InputDatabase, InternerDatabase)]
-#[derive(Default)]
+#[salsa::database(
+    BindingsDatabase,
+    ConstsDatabase,
+    DiagnosticsDatabase,
+    HirDatabase,
+    InputLocationDatabase,
+    InputDatabase,
+    InternerDatabase,
+    ModulesDatabase,
+    ScopesDatabase,
+    TypesDatabase,
+    SourceRootsDatabase
+)]
 pub struct HeliosDatabase {
     storage: salsa::Storage<HeliosDatabase>,
+    profiler: Option<Arc<QueryProfiler>>,
+    syntax_cache: Option<Arc<Mutex<NodeCache>>>,
 }
 
-impl salsa::Database for HeliosDatabase {}
+impl Default for HeliosDatabase {
+    fn default() -> Self {
+        let mut db = Self {
+            storage: salsa::Storage::default(),
+            profiler: None,
+            syntax_cache: None,
+        };
+
+        db.set_lru_capacity(DEFAULT_LRU_CAPACITY);
+        db.set_source_roots(Arc::new(Vec::new()));
+        db.set_workspace_files(Arc::new(Vec::new()));
+        db.set_edition_with_durability(Edition::default(), Durability::HIGH);
+        db.set_file(
+            PRELUDE_FILE_ID,
+            PRELUDE_FILE_NAME,
+            PRELUDE_SOURCE,
+            FileOrigin::StandardLibrary,
+        );
+        db
+    }
+}
+
+impl salsa::Database for HeliosDatabase {
+    fn salsa_event(&self, event: salsa::Event) {
+        if let Some(profiler) = &self.profiler {
+            profiler.record(self, &event);
+        }
+    }
+
+    fn on_propagated_panic(&self) -> ! {
+        // A query this one depends on was being computed on another thread
+        // that unwound, most likely because its revision was cancelled.
+        Cancelled::throw()
+    }
+}
+
+/// The default number of files whose syntax trees are kept in memory.
+pub const DEFAULT_LRU_CAPACITY: usize = 128;
+
+impl HeliosDatabase {
+    /// Sets how many files' syntax trees are kept in memory at most.
+    ///
+    /// Syntax trees are by far the largest values stored in the database. Once
+    /// there are more than `capacity` of them, the least recently used ones are
+    /// evicted (and parsed again if they're needed later on). A capacity of
+    /// zero keeps every syntax tree.
+    pub fn set_lru_capacity(&mut self, capacity: usize) {
+        ParseQuery.in_db_mut(self).set_lru_capacity(capacity);
+    }
+
+    /// Makes the syntax trees of every file parsed from now on (on this
+    /// database and every snapshot taken of it) share their nodes and tokens
+    /// wherever they're the same, which saves memory on large programs.
+    ///
+    /// Nothing is ever removed from the cache though, so it's meant for short
+    /// lived databases (such as when building a program) rather than ones that
+    /// keep on parsing edited files (such as the language server's).
+    pub fn enable_syntax_cache(&mut self) {
+        if self.syntax_cache.is_none() {
+            self.syntax_cache = Some(Arc::default());
+        }
+    }
+
+    /// Stores the name and source text of a file with the durability of its
+    /// origin.
+    pub fn set_file(
+        &mut self,
+        file_id: FileId,
+        name: impl Into<String>,
+        source: impl Into<String>,
+        origin: FileOrigin,
+    ) {
+        let durability = origin.durability();
+        self.set_file_name_with_durability(
+            file_id,
+            Arc::new(name.into()),
+            durability,
+        );
+        self.set_source_with_durability(
+            file_id,
+            Arc::new(source.into()),
+            durability,
+        );
+    }
+
+    /// Removes every memoized value that wasn't used since the last input was
+    /// changed.
+    ///
+    /// This is meant to be called every so often by long-lived users of the
+    /// database (such as the language server), after the values they need have
+    /// been computed again.
+    pub fn collect_garbage(&self) {
+        use salsa::Database;
+        self.sweep_all(SweepStrategy::discard_outdated());
+    }
+
+    /// Cancels every query running on a snapshot of the database.
+    ///
+    /// This blocks until every snapshot is dropped, so it must not be called
+    /// while the current thread holds one.
+    pub fn request_cancellation(&mut self) {
+        use salsa::Database;
+        self.salsa_runtime_mut().synthetic_write(Durability::LOW);
+    }
+}
 
 impl salsa::ParallelDatabase for HeliosDatabase {
     fn snapshot(&self) -> salsa::Snapshot<Self> {
         salsa::Snapshot::new(HeliosDatabase {
             storage: self.storage.snapshot(),
+            profiler: self.profiler.clone(),
+            syntax_cache: self.syntax_cache.clone(),
         })
     }
 }
 
+impl SyntaxCache for HeliosDatabase {
+    fn syntax_cache(&self) -> Option<&Mutex<NodeCache>> {
+        self.syntax_cache.as_deref()
+    }
+}
+
 impl Debug for HeliosDatabase {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("HeliosDatabase").finish()
@@ -108,25 +261,973 @@ mod tests {
         assert_eq!(db.source_position_at_offset(FILE_ID, 31), (4, 0));
     }
 
-    /*
+    #[test]
+    fn test_file_inspector() {
+        use helios_diagnostics::FileInspector;
+
+        let mut db = HeliosDatabase::default();
+        db.set_file_name(FILE_ID, Arc::new("test.hl".to_string()));
+        db.set_source(FILE_ID, Arc::new(SOURCE.to_string()));
+
+        assert_eq!(db.name(FILE_ID), Ok(Arc::new("test.hl".to_string())));
+        assert_eq!(
+            FileInspector::source(&db, FILE_ID).unwrap().as_ref(),
+            SOURCE
+        );
+        assert_eq!(db.line_count(FILE_ID), Ok(5));
+        assert_eq!(db.line_index(FILE_ID, 15), Ok(1));
+        assert_eq!(db.line_range(FILE_ID, 3), Ok(21..31));
+        assert_eq!(db.line_range(FILE_ID, 4), Ok(31..31));
+        assert!(db.line_range(FILE_ID, 5).is_err());
+        assert_eq!(db.column_number(FILE_ID, 15), Ok(6));
+    }
+
+    #[test]
+    fn test_global_bindings() {
+        let source = "## The answer.\n##\n## See `b`.\nlet a = 42\n\n## Unused\n\nlet b = a\n";
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+
+        let bindings = db.global_bindings(FILE_ID);
+        assert_eq!(bindings.len(), 2);
+
+        assert_eq!(bindings[0].name, "a");
+        assert_eq!(bindings[0].range, 34..35);
+        assert_eq!(bindings[0].doc.as_deref(), Some("The answer.\n\nSee `b`."));
+
+        assert_eq!(bindings[1].name, "b");
+        assert_eq!(bindings[1].doc, None);
+
+        let docs = db.item_docs(FILE_ID);
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0], bindings[0].doc);
+        assert_eq!(docs[1], None);
+    }
+
+    #[test]
+    fn test_workspace_symbols() {
+        let mut db = HeliosDatabase::default();
+        db.set_source(FileId(0), Arc::new("let a = 1\n".to_string()));
+        db.set_source(FileId(1), Arc::new("let b = 2\nlet c = 3".to_string()));
+        db.set_source(FileId(2), Arc::new("let d = 4".to_string()));
+        db.set_workspace_files(Arc::new(vec![FileId(0), FileId(1)]));
+
+        let names = |db: &HeliosDatabase| {
+            db.workspace_symbols()
+                .iter()
+                .map(|(file_id, binding)| (file_id.0, binding.name.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(&db),
+            vec![
+                (0, "a".to_string()),
+                (1, "b".to_string()),
+                (1, "c".to_string())
+            ]
+        );
+
+        db.set_source(FileId(0), Arc::new("let e = 5".to_string()));
+        assert_eq!(names(&db)[0], (0, "e".to_string()));
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new("let = 1\nlet _b = )\n".to_string()));
+
+        let parse_diagnostics = db.parse_diagnostics(FILE_ID);
+        let diagnostics = db.diagnostics(FILE_ID);
+        assert!(!diagnostics.is_empty());
+        assert!(diagnostics.len() <= parse_diagnostics.len());
+
+        let ranges = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.location.range.clone())
+            .collect::<Vec<_>>();
+        let mut sorted_ranges = ranges.clone();
+        sorted_ranges.sort_by_key(|range| (range.start, range.end));
+        assert_eq!(ranges, sorted_ranges);
+
+        for (i, diagnostic) in diagnostics.iter().enumerate() {
+            assert!(!diagnostics[..i].contains(diagnostic));
+        }
+    }
+
+    #[test]
+    fn test_cancellation() {
+        use salsa::{Database, ParallelDatabase};
+        use std::panic::AssertUnwindSafe;
+
+        let mut db = HeliosDatabase::default();
+        db.set_source(FileId(0), Arc::new("let a = 1\n".to_string()));
+        db.set_workspace_files(Arc::new(vec![FileId(0)]));
+
+        let snapshot = db.snapshot();
+        let writer = std::thread::spawn(move || {
+            // This blocks until the snapshot is dropped.
+            db.set_source(FileId(0), Arc::new("let b = 2\n".to_string()));
+            db.workspace_symbols()[0].1.name.clone()
+        });
+
+        while !snapshot.salsa_runtime().is_current_revision_canceled() {
+            std::thread::yield_now();
+        }
+
+        let result =
+            Cancelled::catch(AssertUnwindSafe(|| snapshot.workspace_symbols()));
+        assert!(result.is_err());
+
+        drop(snapshot);
+        assert_eq!(writer.join().unwrap(), "b");
+    }
+
+    #[test]
+    fn test_request_cancellation() {
+        use salsa::{Database, ParallelDatabase};
+        use std::panic::AssertUnwindSafe;
+
+        let mut db = HeliosDatabase::default();
+        db.set_source(FileId(0), Arc::new("let a = 1\n".to_string()));
+        db.set_workspace_files(Arc::new(vec![FileId(0)]));
+        let symbols = db.workspace_symbols();
+
+        let snapshot = db.snapshot();
+        let writer = std::thread::spawn(move || {
+            db.request_cancellation();
+            db.workspace_symbols()
+        });
+
+        while !snapshot.salsa_runtime().is_current_revision_canceled() {
+            std::thread::yield_now();
+        }
+
+        let result = Cancelled::catch(AssertUnwindSafe(|| {
+            snapshot.all_bindings(FileId(0))
+        }));
+        assert!(result.is_err());
+
+        // Nothing changed, so the symbols are the same as before.
+        drop(snapshot);
+        assert_eq!(writer.join().unwrap(), symbols);
+    }
+
+    #[test]
+    fn test_module_graph() {
+        let mut db = HeliosDatabase::default();
+        let files = [
+            ("src/main.helios", "import foo\nimport util.bar\nimport baz"),
+            ("src/foo.helios", "import util.bar"),
+            ("src/util/bar.helios", "let a = 1"),
+            ("lib/foo.helios", ""),
+        ];
+        for (i, (name, source)) in files.iter().enumerate() {
+            let file_id = FileId(i as u32);
+            db.set_file_name(file_id, Arc::new(name.to_string()));
+            db.set_source(file_id, Arc::new(source.to_string()));
+        }
+        db.set_workspace_files(Arc::new((0..4).map(FileId).collect()));
+
+        let module = db.module_of_file(FileId(2));
+        assert_eq!(module.as_slice(), ["src", "util", "bar"]);
+
+        let imports = db.imports(FileId(0));
+        assert_eq!(imports.len(), 3);
+        assert_eq!(imports[1].path.as_slice(), ["util", "bar"]);
+        assert_eq!(imports[1].range, 18..26);
+
+        // Both `src/foo` and `lib/foo` match `foo`, and the first file wins.
+        assert_eq!(
+            db.dependencies(FileId(0)),
+            Arc::new(vec![FileId(1), FileId(2)])
+        );
+        assert_eq!(
+            db.reverse_dependencies(FileId(2)),
+            Arc::new(vec![FileId(0), FileId(1)])
+        );
+        assert_eq!(
+            db.build_order(),
+            Arc::new(vec![FileId(2), FileId(1), FileId(0), FileId(3)])
+        );
+        assert!(db.import_cycles().is_empty());
+
+        let diagnostics = db.module_diagnostics(FileId(0));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].title, "Unresolved import");
+        assert_eq!(diagnostics[0].location.range, 34..37);
+    }
+
+    #[test]
+    fn test_imported_modules() {
+        let mut db = HeliosDatabase::default();
+        let files = [
+            ("main.helios", "import util\nimport util.helper\n"),
+            ("util.helios", "let helper = 1\nexternal func f\nimport b\n"),
+            ("b.helios", ""),
+        ];
+        for (i, (name, source)) in files.iter().enumerate() {
+            let file_id = FileId(i as u32);
+            db.set_file_name(file_id, Arc::new(name.to_string()));
+            db.set_source(file_id, Arc::new(source.to_string()));
+        }
+        db.set_workspace_files(Arc::new((0..3).map(FileId).collect()));
+
+        // `util` is a module, so `helper` can't be imported from it.
+        let diagnostics = db.module_diagnostics(FileId(0));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].title, "Unsupported import");
+        assert_eq!(diagnostics[0].location.range, 19..30);
+
+        // The bindings and external functions of an imported module aren't
+        // unused, but its imports may be.
+        let diagnostics = db.unused_diagnostics(FileId(1));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].title, "Unused import");
+    }
+
+    #[test]
+    fn test_import_cycles() {
+        let mut db = HeliosDatabase::default();
+        let files = [
+            ("a.helios", "import b"),
+            ("b.helios", "import c"),
+            ("c.helios", "import a\nimport d"),
+            ("d.helios", "import d"),
+            ("e.helios", "import a"),
+        ];
+        for (i, (name, source)) in files.iter().enumerate() {
+            let file_id = FileId(i as u32);
+            db.set_file_name(file_id, Arc::new(name.to_string()));
+            db.set_source(file_id, Arc::new(source.to_string()));
+        }
+        db.set_workspace_files(Arc::new((0..5).map(FileId).collect()));
+
+        assert_eq!(
+            db.import_cycles(),
+            Arc::new(vec![
+                vec![FileId(3)],
+                vec![FileId(0), FileId(1), FileId(2)]
+            ])
+        );
+
+        let titles = |db: &HeliosDatabase, file_id| {
+            db.diagnostics(file_id)
+                .iter()
+                .map(|diagnostic| diagnostic.title.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // Nothing refers to the names of the imports either.
+        assert_eq!(
+            titles(&db, FileId(2)),
+            vec!["Import cycle", "Unused import", "Unused import"]
+        );
+        assert_eq!(
+            titles(&db, FileId(3)),
+            vec!["Import cycle", "Unused import"]
+        );
+        assert_eq!(titles(&db, FileId(4)), vec!["Unused import"]);
+
+        // Breaking the cycle gets rid of its diagnostics.
+        db.set_source(FileId(1), Arc::new(String::new()));
+        assert_eq!(db.import_cycles(), Arc::new(vec![vec![FileId(3)]]));
+        assert_eq!(titles(&db, FileId(0)), vec!["Unused import"]);
+    }
+
+    #[test]
+    fn test_set_file() {
+        use salsa::Durability;
+
+        let mut db = HeliosDatabase::default();
+        let std = FileOrigin::StandardLibrary;
+        db.set_file(FileId(0), "std.helios", "let a = 1\n", std);
+        db.set_file(
+            FileId(1),
+            "main.helios",
+            "let b = a\n",
+            FileOrigin::Workspace,
+        );
+
+        assert_eq!(db.file_name(FileId(0)).as_str(), "std.helios");
+        assert_eq!(db.global_bindings(FileId(0))[0].name, "a");
+        assert_eq!(db.global_bindings(FileId(1))[0].name, "b");
+
+        assert_eq!(std.durability(), Durability::HIGH);
+        assert_eq!(FileOrigin::Dependency.durability(), Durability::MEDIUM);
+        assert_eq!(FileOrigin::Workspace.durability(), Durability::LOW);
+    }
+
+    #[test]
+    fn test_lru_capacity() {
+        let mut db = HeliosDatabase::default();
+        db.set_lru_capacity(1);
+        db.set_source(FileId(0), Arc::new("let a = 1\n".to_string()));
+        db.set_source(FileId(1), Arc::new("let b = 2\n".to_string()));
+
+        // Evicted syntax trees are parsed again when they're needed.
+        let tree = db.parse(FileId(0)).debug_tree();
+        db.parse(FileId(1));
+        assert_eq!(db.parse(FileId(0)).debug_tree(), tree);
+
+        db.set_source(FileId(1), Arc::new("let c = 3\n".to_string()));
+        db.global_bindings(FileId(1));
+        db.collect_garbage();
+        assert_eq!(db.global_bindings(FileId(0))[0].name, "a");
+        assert_eq!(db.global_bindings(FileId(1))[0].name, "c");
+    }
+
+    #[test]
+    fn test_analyze_parallel() {
+        let mut db = HeliosDatabase::default();
+        let files = (0..16).map(FileId).collect::<Vec<_>>();
+        for &file_id in &files {
+            let source = if file_id.0 % 2 == 0 {
+                "let a = 1"
+            } else {
+                "let = 1"
+            };
+            db.set_source(file_id, Arc::new(source.to_string()));
+        }
+        db.set_workspace_files(Arc::new(files.clone()));
+
+        let diagnostics = db.analyze_parallel(&files).unwrap();
+        assert_eq!(diagnostics.len(), files.len());
+        for (&file_id, diagnostics) in files.iter().zip(&diagnostics) {
+            assert_eq!(*diagnostics, db.diagnostics(file_id));
+        }
+        assert_ne!(diagnostics[0], diagnostics[1]);
+
+        let names = db
+            .map_parallel(&files[..2], |db, file_id| {
+                db.global_bindings(file_id).len()
+            })
+            .unwrap();
+        assert_eq!(names, vec![1, 0]);
+        assert!(db.analyze_parallel(&[]).unwrap().is_empty());
+    }
+
     #[test]
     fn test_all_bindings() {
-        fn print_bindings(
-            db: &mut HeliosDatabase,
-            bindings: Arc<Vec<BindingId>>,
-        ) {
-            for binding in bindings.iter() {
-                let binding_data: BindingData =
-                    db.lookup_intern_binding(*binding);
-                println!("{:?} => {:?}", binding, binding_data.identifier);
-            }
+        let source = "import foo.bar\nlet a = 1\nlet b = a + bar\nlet a = a\n";
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+
+        let bindings = db
+            .all_bindings(FILE_ID)
+            .iter()
+            .map(|&binding| {
+                let data = db.lookup_intern_binding(binding);
+                (data.name, data.kind, data.range)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            bindings,
+            vec![
+                ("bar".to_string(), BindingKind::Import, 11..14),
+                ("a".to_string(), BindingKind::Global, 19..20),
+                ("b".to_string(), BindingKind::Global, 29..30),
+                ("a".to_string(), BindingKind::Global, 45..46),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_binding_at_offset() {
+        let source = "import foo.bar\nlet a = 1\nlet b = a + bar\nlet a = a\n";
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+
+        let range_at = |offset| {
+            db.binding_at_offset(FILE_ID, offset)
+                .map(|binding| db.lookup_intern_binding(binding).range)
+        };
+
+        // Declarations, with the cursor on either side of the identifier.
+        assert_eq!(range_at(19), Some(19..20));
+        assert_eq!(range_at(20), Some(19..20));
+        assert_eq!(range_at(14), Some(11..14));
+
+        // References, which refer to the first declaration of their name.
+        assert_eq!(range_at(33), Some(19..20));
+        assert_eq!(range_at(38), Some(11..14));
+        assert_eq!(range_at(49), Some(19..20));
+
+        // Neither declarations nor references.
+        assert_eq!(range_at(8), None);
+        assert_eq!(range_at(16), None);
+    }
+
+    #[test]
+    fn test_cooked_literal() {
+        let source = "let a = f\"\\t{b}!\"\nlet c = 'd'\n";
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+
+        let literal = db.cooked_literal(FILE_ID, 8).unwrap();
+        assert_eq!(literal.value, "\t!");
+        assert_eq!(literal.escapes.len(), 1);
+        assert_eq!(literal.escapes[0], 2..4);
+        assert_eq!(literal.holes.len(), 1);
+        assert_eq!(literal.holes[0].range, 5..6);
+        assert_eq!(literal.holes[0].offset, 1);
+        assert_eq!(db.cooked_literal(FILE_ID, 26).unwrap().value, "d");
+
+        // Only literals are cooked, and only from their start.
+        assert_eq!(db.cooked_literal(FILE_ID, 4), None);
+        assert_eq!(db.cooked_literal(FILE_ID, 9), None);
+        assert_eq!(db.cooked_literal(FILE_ID, 100), None);
+    }
+
+    #[test]
+    fn test_edition() {
+        let source = "let macro = 1\nmacro twice x = x + x\n";
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+        assert!(db.parse_diagnostics(FILE_ID).is_empty());
+
+        // `macro` is only reserved everywhere from the 2026 edition on.
+        db.set_edition(Edition::Edition2026);
+        assert!(!db.parse_diagnostics(FILE_ID).is_empty());
+    }
+
+    #[test]
+    fn test_name_diagnostics() {
+        let source = "let a = b\nimport foo.a\n";
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+
+        let diagnostics = db.name_diagnostics(FILE_ID);
+        assert_eq!(diagnostics.len(), 2);
+
+        let duplicate = &diagnostics[0];
+        assert_eq!(duplicate.title, "Duplicate definition");
+        assert_eq!(duplicate.location.range, 21..22);
+        assert_eq!(duplicate.labels.len(), 1);
+        assert_eq!(duplicate.labels[0].location.range, 4..5);
+
+        let unresolved = &diagnostics[1];
+        assert_eq!(unresolved.title, "Unresolved name");
+        assert_eq!(unresolved.location.range, 8..9);
+    }
+
+    #[test]
+    fn test_macro_diagnostics() {
+        let source = "macro add x y = x + y + z\nlet a = add!(1)\n\
+            let b = add!(1, 2)\nlet c = sub!()\n";
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+
+        // Names that can't be resolved in a template are reported at the
+        // call it was expanded from.
+        let diagnostics = db.name_diagnostics(FILE_ID);
+        let diagnostics = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let range = diagnostic.location.range.clone();
+                (diagnostic.title.as_str(), &source[range])
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            [
+                ("Wrong number of arguments", "add!(1)"),
+                ("Unresolved name", "add!(1, 2)"),
+                ("Unresolved macro", "sub!()"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_binding_cycles() {
+        let source = "let a = b + 1\nlet b = c\nlet c = a\nlet d = d\n";
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+
+        let module = db.hir_module(FILE_ID);
+        let items = module.items.iter().map(|(id, _)| id).collect::<Vec<_>>();
+        assert_eq!(
+            *db.binding_cycles(FILE_ID),
+            [vec![items[0], items[1], items[2]], vec![items[3]]]
+        );
+
+        let diagnostics = db.name_diagnostics(FILE_ID);
+        assert_eq!(diagnostics.len(), 2);
+
+        let cycle = &diagnostics[0];
+        assert_eq!(cycle.title, "Circular definition");
+        assert_eq!(cycle.location.range, 4..5);
+        let labels = cycle
+            .labels
+            .iter()
+            .map(|label| (label.location.range.clone(), label.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            [
+                (18..19, "which refers to `c`"),
+                (28..29, "which refers to `a`")
+            ]
+        );
+
+        assert_eq!(diagnostics[1].title, "Circular definition");
+        assert_eq!(diagnostics[1].location.range, 38..39);
+        assert!(diagnostics[1].labels.is_empty());
+    }
+
+    #[test]
+    fn test_unused_diagnostics() {
+        let source = "import foo.bar  # why\nlet a = b + a\nlet b = 1\n\
+            external func now: Int\nlet _c = 2\nlet a = 3\n";
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+
+        // `a` only refers to itself, and its duplicate is already an error.
+        let diagnostics = db.unused_diagnostics(FILE_ID);
+        let unused = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let range = diagnostic.location.range.clone();
+                (diagnostic.title.as_str(), &source[range])
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            unused,
+            [
+                ("Unused import", "bar"),
+                ("Unused binding", "a"),
+                ("Unused external function", "now"),
+            ]
+        );
+
+        let fixes = diagnostics
+            .iter()
+            .flat_map(|diagnostic| &diagnostic.fixes)
+            .map(|fix| {
+                let range = fix.location.range.clone();
+                (fix.title.as_str(), &source[range], fix.machine_applicable)
+            })
+            .collect::<Vec<_>>();
+
+        // Only removing imports can't change what the program does, and
+        // comments after them are kept.
+        assert_eq!(
+            fixes,
+            [
+                ("Remove the unused import", "import foo.bar  ", true),
+                ("Remove the unused binding", "let a = b + a\n", false),
+                ("Rename to `_a`", "a", false),
+                (
+                    "Remove the unused external function",
+                    "external func now: Int\n",
+                    false
+                ),
+            ]
+        );
+
+        assert!(db.unused_diagnostics(PRELUDE_FILE_ID).is_empty());
+
+        db.set_source(FILE_ID, Arc::new("let main = 1\n".to_string()));
+        assert!(db.unused_diagnostics(FILE_ID).is_empty());
+    }
+
+    #[test]
+    fn test_types() {
+        let source = "let a = 1 + 2.5\nlet b = a < 3\nlet c = -b\n";
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+
+        let type_at = |offset| {
+            db.type_at_offset(FILE_ID, offset)
+                .map(|scheme| scheme.to_string())
+        };
+        assert_eq!(type_at(4).as_deref(), Some("Int"));
+        assert_eq!(type_at(12).as_deref(), Some("Float"));
+        assert_eq!(type_at(20).as_deref(), Some("Bool"));
+        assert_eq!(type_at(1), None);
+
+        let diagnostics = db.type_diagnostics(FILE_ID);
+        let labels = |index: usize| {
+            diagnostics[index]
+                .labels
+                .iter()
+                .map(|label| label.location.range.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(diagnostics.len(), 2);
+
+        assert_eq!(diagnostics[0].title, "Mismatched operands");
+        assert_eq!(diagnostics[0].location.range, 8..15);
+        assert_eq!(labels(0), [8..9, 12..15]);
+
+        assert_eq!(diagnostics[1].title, "Unsupported operand");
+        assert_eq!(diagnostics[1].location.range, 38..40);
+        assert_eq!(diagnostics[1].labels[0].location.range, 39..40);
+
+        let source = "import util\nlet main = util + 1\n";
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+        let diagnostics = db.type_diagnostics(FILE_ID);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].title, "Module used as a value");
+        assert_eq!(&source[diagnostics[0].location.range.clone()], "util");
+    }
+
+    #[test]
+    fn test_entry_point_diagnostics() {
+        let mut db = HeliosDatabase::default();
+        let titles = |db: &mut HeliosDatabase, source: &str| {
+            db.set_source(FILE_ID, Arc::new(source.to_string()));
+            db.entry_point_diagnostics(FILE_ID)
+                .iter()
+                .map(|diagnostic| {
+                    (
+                        diagnostic.title.clone(),
+                        diagnostic.location.range.clone(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert!(titles(&mut db, "let main = a + 1\nlet a = 1\n").is_empty());
+        assert!(titles(&mut db, "let a = 1\na\n").is_empty());
+
+        let missing = ("Missing entry point".to_string(), 0..0);
+        assert_eq!(titles(&mut db, "let a = 1\n"), [missing]);
+
+        let mistyped = |range| ("Mistyped entry point".to_string(), range);
+        assert_eq!(titles(&mut db, "1\nlet main = 1.5\n"), [mistyped(6..10)]);
+        assert_eq!(
+            titles(&mut db, "external func main: Int\n"),
+            [mistyped(14..18)]
+        );
+
+        // Errors in the type of `main` are reported where they come from.
+        assert!(titles(&mut db, "let main = 1 + true\n").is_empty());
+        assert!(!db.diagnostics(FILE_ID).is_empty());
+    }
+
+    #[test]
+    fn test_externals() {
+        let source = "external func now: Int\nexternal func f: Foo\nnow + f\n";
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+
+        let type_at = |offset| {
+            db.type_at_offset(FILE_ID, offset)
+                .map(|scheme| scheme.to_string())
+        };
+        assert_eq!(type_at(15).as_deref(), Some("Int"));
+        assert_eq!(type_at(44).as_deref(), Some("Int"));
+        assert!(db.name_diagnostics(FILE_ID).is_empty());
+
+        let diagnostics = db.type_diagnostics(FILE_ID);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].title, "Unknown type");
+        assert_eq!(&source[diagnostics[0].location.range.clone()], "Foo");
+    }
+
+    #[test]
+    fn test_consts() {
+        let source = "let a = 2 * 3\nlet b = a / (a - 6)\n";
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+
+        let module = db.hir_module(FILE_ID);
+        let values = db.const_values(FILE_ID);
+        let (item_id, _) = module.bindings().next().unwrap();
+        assert_eq!(
+            values.binding_value(item_id),
+            Some(helios_hir::Const::Int(6))
+        );
+
+        let diagnostics = db.const_diagnostics(FILE_ID);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].title, "Division by zero");
+        assert_eq!(diagnostics[0].location.range, 22..33);
+        assert_eq!(diagnostics[0].labels[0].location.range, 27..32);
+    }
+
+    #[test]
+    fn test_runtime_error_diagnostic() {
+        let source = "let main = a + 1\nlet a = 6 / zero\n\
+            external func zero: Int\n";
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+
+        let mut host = helios_hir::HostFunctions::new();
+        host.register("zero", || helios_hir::Const::Int(0));
+        let error = helios_hir::interpret_module(
+            &db.hir_module(FILE_ID),
+            &db.const_values(FILE_ID),
+            &host,
+            |item_id| {
+                let body = db.body(FILE_ID, item_id);
+                (body, db.name_resolutions(FILE_ID, item_id))
+            },
+        )
+        .unwrap_err();
+
+        let diagnostic = runtime_error_diagnostic(&db, FILE_ID, &error);
+        assert_eq!(diagnostic.title, "Division by zero");
+        assert_eq!(&source[diagnostic.location.range.clone()], "6 / zero");
+        assert_eq!(
+            &source[diagnostic.labels[0].location.range.clone()],
+            "zero"
+        );
+    }
+
+    #[test]
+    fn test_literals_out_of_range() {
+        let source = "let a = 9223372036854775808\n\
+            let b = -9223372036854775808\n\
+            let c = 0xffff_ffff_ffff_ffff\n\
+            let d = 0b1 + 99999999999999999999\n";
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+
+        let diagnostics = db.const_diagnostics(FILE_ID);
+        let ranges = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                assert_eq!(diagnostic.title, "Integer literal out of range");
+                diagnostic.location.range.clone()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, [8..27, 65..86, 101..121]);
+
+        // The allowed range is written in the base of the literal.
+        let hint = |index: usize| {
+            diagnostics[index].hint.as_ref().unwrap().to_string()
+        };
+        assert!(hint(0).contains("9223372036854775807"));
+        assert!(hint(1).contains("0x7fffffffffffffff"));
+        assert!(hint(1).contains("-0x8000000000000000"));
+        assert!(hint(2).contains("9223372036854775807"));
+    }
+
+    #[test]
+    fn test_prelude() {
+        let source = "let a = !false\nlet b = max_int - 1\nlet pi = 3\n\
+            let c = pi + e\n";
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new(source.to_string()));
+
+        // The prelude is neither part of the workspace nor has any errors.
+        assert!(!db.workspace_files().contains(&PRELUDE_FILE_ID));
+        assert!(db.diagnostics(PRELUDE_FILE_ID).is_empty());
+
+        let module = db.hir_module(FILE_ID);
+        let types = db.infer(FILE_ID);
+        let values = db.const_values(FILE_ID);
+        let bindings = module
+            .bindings()
+            .map(|(item_id, _)| {
+                let ty = types.binding_type(item_id).unwrap().to_string();
+                let value =
+                    values.binding_value(item_id).map(|v| v.to_string());
+                (ty, value)
+            })
+            .collect::<Vec<_>>();
+
+        // `pi` is shadowed by the file, so `pi + e` adds an `Int` to a
+        // `Float`.
+        let int = |value: i64| ("Int".to_string(), Some(value.to_string()));
+        assert_eq!(bindings[0], ("Bool".to_string(), Some("true".to_string())));
+        assert_eq!(bindings[1], int(i64::MAX - 1));
+        assert_eq!(bindings[2], int(3));
+        assert_eq!(db.name_diagnostics(FILE_ID).len(), 0);
+        assert_eq!(db.type_diagnostics(FILE_ID).len(), 1);
+    }
+
+    #[test]
+    fn test_source_roots() {
+        use std::path::{Path, PathBuf};
+
+        let mut db = HeliosDatabase::default();
+        let workspace = SourceRoot::new("/project", FileOrigin::Workspace)
+            .exclude("examples")
+            .unwrap();
+        let workspace = db.add_source_root(workspace);
+        let dependency = db.add_source_root(SourceRoot::new(
+            "/project/deps/foo",
+            FileOrigin::Dependency,
+        ));
+
+        let main = Path::new("/project/main.helios");
+        let foo = Path::new("/project/deps/foo/foo.helios");
+        let example = Path::new("/project/examples/a.helios");
+
+        // Files belong in the innermost root containing them.
+        assert_eq!(db.source_root_for_path(main), Some(workspace));
+        assert_eq!(db.source_root_for_path(foo), Some(dependency));
+        assert_eq!(db.source_root_for_path(example), None);
+
+        assert_eq!(
+            db.set_file_at_path(FileId(0), main, "let a = 1\n"),
+            Some(workspace)
+        );
+        assert_eq!(
+            db.set_file_at_path(FileId(1), foo, "let b = 1\n"),
+            Some(dependency)
+        );
+        assert_eq!(db.file_name(FileId(0)).as_str(), "/project/main.helios");
+        assert_eq!(db.file_at_path(PathBuf::from(foo)), Some(FileId(1)));
+        assert_eq!(db.source_root_of_file(FileId(1)), Some(dependency));
+
+        // Only files of workspace roots are part of the workspace.
+        assert_eq!(*db.workspace_files(), vec![FileId(0)]);
+
+        db.set_file_at_path(FileId(0), main, "let c = 1\n");
+        assert_eq!(db.global_bindings(FileId(0))[0].name, "c");
+
+        assert_eq!(db.remove_file_at_path(main), Some(FileId(0)));
+        assert_eq!(db.remove_file_at_path(main), None);
+        assert_eq!(db.file_at_path(PathBuf::from(main)), None);
+        assert!(db.workspace_files().is_empty());
+    }
+
+    #[test]
+    fn test_dependency_modules() {
+        use std::path::Path;
+
+        let mut db = HeliosDatabase::default();
+        db.add_source_root(SourceRoot::new("/app/src", FileOrigin::Workspace));
+        db.add_source_root(
+            SourceRoot::new("/math/src", FileOrigin::Dependency)
+                .with_package("math"),
+        );
+
+        let files = [
+            (
+                "/app/src/main.helios",
+                "import math.vector\nimport vector\n",
+            ),
+            ("/math/src/vector.helios", "import math.util\n"),
+            ("/math/src/util.helios", ""),
+        ];
+        for (i, (path, source)) in files.iter().enumerate() {
+            db.set_file_at_path(FileId(i as u32), Path::new(path), *source);
         }
 
+        // Modules of dependencies are namespaced by their package, and can
+        // only be imported by their full path.
+        assert_eq!(db.module_of_file(FileId(1)).as_slice(), ["math", "vector"]);
+        assert_eq!(*db.workspace_files(), [FileId(0)]);
+        assert_eq!(*db.dependency_files(), [FileId(2), FileId(1)]);
+        assert_eq!(*db.dependencies(FileId(0)), [FileId(1)]);
+        assert_eq!(*db.dependencies(FileId(1)), [FileId(2)]);
+
+        let diagnostics = db.module_diagnostics(FileId(0));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].title, "Unresolved import");
+    }
+
+    #[test]
+    fn test_find_files() {
+        let dir = std::env::temp_dir()
+            .join(format!("helios-query-find-{}", std::process::id()));
+        for path in [
+            "a.helios",
+            "b/c.helios",
+            "b/d.gen.helios",
+            ".git/e.helios",
+            "target/f.helios",
+            "examples/g.helios",
+            "h.txt",
+        ] {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let root = SourceRoot::new(&dir, FileOrigin::Workspace)
+            .exclude("examples")
+            .and_then(|root| root.exclude("**/*.gen.helios"))
+            .unwrap();
+        let files = root.find_files();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files, vec![dir.join("a.helios"), dir.join("b/c.helios")]);
+    }
+
+    #[test]
+    fn test_query_stats() {
         let mut db = HeliosDatabase::default();
-        db.set_source(FILE_ID, Arc::new(SOURCE.to_string()));
+        assert_eq!(db.query_stats(), None);
+
+        db.enable_query_stats();
+        db.set_source(FileId(0), Arc::new("let a = 1\n".to_string()));
+        db.global_bindings(FileId(0));
+        db.global_bindings(FileId(0));
+
+        // Changing another file doesn't execute anything again.
+        db.set_source(FileId(1), Arc::new("let b = 1\n".to_string()));
+        db.global_bindings(FileId(0));
+
+        let stats = db.query_stats().unwrap();
+        let stat = |name: &str| {
+            let stat = stats.queries.iter().find(|stat| stat.name == name);
+            stat.map(|stat| (stat.executions, stat.reuses))
+        };
+
+        assert_eq!(stat("parse"), Some((1, 1)));
+        assert_eq!(stat("global_bindings"), Some((1, 1)));
+        assert_eq!(stat("workspace_symbols"), None);
 
-        let bindings = db.all_bindings(FILE_ID);
-        print_bindings(&mut db, bindings);
+        let report = stats.to_string();
+        assert!(report.starts_with("query            executed  reused"));
+        assert_eq!(report.lines().count(), stats.queries.len() + 1);
+    }
+
+    #[test]
+    fn test_hir() {
+        use helios_hir::{Expr, Item, Literal, Name};
+
+        let mut db = HeliosDatabase::default();
+        db.set_source(FILE_ID, Arc::new("import foo\nlet a = 1\n".to_string()));
+
+        let module = db.hir_module(FILE_ID);
+        let (item_id, binding) = module.bindings().next().unwrap();
+        assert_eq!(binding.name, Some(Name::new("a")));
+        assert!(matches!(
+            module.items.iter().next(),
+            Some((_, Item::Import(_)))
+        ));
+
+        let body = db.body(FILE_ID, item_id);
+        assert_eq!(body.exprs[body.value], Expr::Literal(Literal::Integer(1)));
+
+        // Editing the body of an item doesn't change the module.
+        db.set_source(
+            FILE_ID,
+            Arc::new("import foo\nlet a = 22\n".to_string()),
+        );
+        assert_eq!(db.hir_module(FILE_ID), module);
+
+        let body = db.body(FILE_ID, item_id);
+        assert_eq!(body.exprs[body.value], Expr::Literal(Literal::Integer(22)));
+        let (_, source_map) = db.body_with_source_map(FILE_ID, item_id);
+        assert_eq!(u32::from(source_map.expr_range(body.value).start()), 19);
+    }
+
+    #[test]
+    fn test_syntax_cache() {
+        let shares_tokens = |db: &HeliosDatabase| {
+            let a = db.parse(FileId(0)).syntax().first_token().unwrap();
+            let b = db.parse(FileId(1)).syntax().first_token().unwrap();
+            std::ptr::eq(a.green(), b.green())
+        };
+
+        let mut db = HeliosDatabase::default();
+        db.set_source(FileId(0), Arc::new(SOURCE.to_string()));
+        db.set_source(FileId(1), Arc::new(SOURCE.to_string()));
+        assert!(!shares_tokens(&db));
+
+        let mut db = HeliosDatabase::default();
+        db.enable_syntax_cache();
+        db.set_source(FileId(0), Arc::new(SOURCE.to_string()));
+        db.set_source(FileId(1), Arc::new(SOURCE.to_string()));
+        assert!(shares_tokens(&db));
     }
-    */
 }
@@ -0,0 +1,346 @@
+use crate::{CheckCancelled, FileId, SourceRoots};
+use helios_diagnostics::{Diagnostic, Location};
+use helios_formatting::FormattedString;
+use helios_syntax::{SyntaxKind, SyntaxNode};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Component, Path};
+use std::sync::Arc;
+
+#[salsa::query_group(ModulesDatabase)]
+pub trait Modules: SourceRoots {
+    /// The path of the module a file defines, which is the file's path
+    /// without its extension (so `src/foo/bar.helios` defines `src.foo.bar`).
+    ///
+    /// The modules of a package's source root are namespaced by the package
+    /// instead, starting from the root (so `src/foo/bar.helios` defines
+    /// `math.foo.bar` if `src` is the source root of the `math` package).
+    fn module_of_file(&self, file_id: FileId) -> Arc<Vec<String>>;
+
+    /// All the import declarations of a file, in the order they appear.
+    fn imports(&self, file_id: FileId) -> Arc<Vec<Import>>;
+
+    /// The file defining the module with the given path, if any.
+    ///
+    /// A module of the workspace may be imported by any suffix of its path
+    /// (such as `bar` or `foo.bar` for `src.foo.bar`), but a module of a
+    /// dependency can only be imported by its full path (starting with the
+    /// name of its package). If several files match, the one with the shortest
+    /// path wins.
+    fn resolve_import(&self, path: Arc<Vec<String>>) -> Option<FileId>;
+
+    /// The files a file imports, in the order they are first imported.
+    /// Imports that can't be resolved are left out.
+    fn dependencies(&self, file_id: FileId) -> Arc<Vec<FileId>>;
+
+    /// The workspace files importing a file.
+    fn reverse_dependencies(&self, file_id: FileId) -> Arc<Vec<FileId>>;
+
+    /// Every group of workspace files that (directly or indirectly) import
+    /// each other, including files that import themselves.
+    fn import_cycles(&self) -> Arc<Vec<Vec<FileId>>>;
+
+    /// Every workspace file, ordered so that a file always comes after the
+    /// files it imports (unless they are part of the same import cycle).
+    fn build_order(&self) -> Arc<Vec<FileId>>;
+
+    /// Diagnostics for the imports of a file that can't be resolved or that
+    /// are part of an import cycle.
+    ///
+    /// Only modules can be imported, so importing something from a module
+    /// (such as `import util.helper` where `util` is a module) is reported as
+    /// such rather than as a missing module.
+    fn module_diagnostics(
+        &self,
+        file_id: FileId,
+    ) -> Arc<Vec<Diagnostic<FileId>>>;
+}
+
+/// An import declaration (such as `import foo.bar`) found in a file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Import {
+    /// The path of the imported module.
+    pub path: Arc<Vec<String>>,
+    /// The range of the imported module's path.
+    pub range: Range<usize>,
+}
+
+fn module_of_file(db: &dyn Modules, file_id: FileId) -> Arc<Vec<String>> {
+    let file_name = db.file_name(file_id);
+    let mut path = Path::new(file_name.as_str()).to_path_buf();
+    let mut components = Vec::new();
+
+    let root = db
+        .source_root_of_file(file_id)
+        .map(|root_id| db.source_root(root_id));
+    if let Some(root) = &root {
+        if let Some(package) = root.package() {
+            components.push(package.to_string());
+            path = path.strip_prefix(root.path()).unwrap_or(&path).into();
+        }
+    }
+
+    components.extend(path.with_extension("").components().filter_map(
+        |component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        },
+    ));
+
+    Arc::new(components)
+}
+
+fn imports(db: &dyn Modules, file_id: FileId) -> Arc<Vec<Import>> {
+    db.unwind_if_cancelled();
+    let root = db.parse(file_id).syntax();
+    let imports = root
+        .children()
+        .filter(|node| node.kind() == SyntaxKind::Dec_Import)
+        .filter_map(|node| import(&node))
+        .collect();
+
+    Arc::new(imports)
+}
+
+fn import(node: &SyntaxNode) -> Option<Import> {
+    let identifiers = node
+        .children_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| token.kind() == SyntaxKind::Identifier)
+        .collect::<Vec<_>>();
+
+    let start = identifiers.first()?.text_range().start();
+    let end = identifiers.last()?.text_range().end();
+    let path = identifiers
+        .iter()
+        .map(|identifier| identifier.text().to_string())
+        .collect();
+
+    Some(Import {
+        path: Arc::new(path),
+        range: start.into()..end.into(),
+    })
+}
+
+fn resolve_import(db: &dyn Modules, path: Arc<Vec<String>>) -> Option<FileId> {
+    let workspace = db.workspace_files();
+    let dependencies = db.dependency_files();
+    let workspace = workspace.iter().map(|&file_id| (file_id, true));
+    let dependencies = dependencies.iter().map(|&file_id| (file_id, false));
+
+    workspace
+        .chain(dependencies)
+        .map(|(file_id, is_workspace)| {
+            db.unwind_if_cancelled();
+            (file_id, is_workspace, db.module_of_file(file_id))
+        })
+        .filter(|(_, is_workspace, module)| match is_workspace {
+            true => module.ends_with(&path),
+            false => **module == *path,
+        })
+        .min_by_key(|(file_id, _, module)| (module.len(), *file_id))
+        .map(|(file_id, _, _)| file_id)
+}
+
+fn dependencies(db: &dyn Modules, file_id: FileId) -> Arc<Vec<FileId>> {
+    let mut dependencies = Vec::new();
+    for import in db.imports(file_id).iter() {
+        if let Some(dependency) = db.resolve_import(import.path.clone()) {
+            if !dependencies.contains(&dependency) {
+                dependencies.push(dependency);
+            }
+        }
+    }
+
+    Arc::new(dependencies)
+}
+
+fn reverse_dependencies(db: &dyn Modules, file_id: FileId) -> Arc<Vec<FileId>> {
+    let reverse_dependencies = db
+        .workspace_files()
+        .iter()
+        .copied()
+        .filter(|&other| {
+            db.unwind_if_cancelled();
+            db.dependencies(other).contains(&file_id)
+        })
+        .collect();
+
+    Arc::new(reverse_dependencies)
+}
+
+fn import_cycles(db: &dyn Modules) -> Arc<Vec<Vec<FileId>>> {
+    let cycles = strongly_connected_components(db)
+        .into_iter()
+        .filter(|component| match component.as_slice() {
+            [file_id] => db.dependencies(*file_id).contains(file_id),
+            _ => true,
+        })
+        .collect();
+
+    Arc::new(cycles)
+}
+
+fn build_order(db: &dyn Modules) -> Arc<Vec<FileId>> {
+    let order = strongly_connected_components(db).concat();
+    Arc::new(order)
+}
+
+fn module_diagnostics(
+    db: &dyn Modules,
+    file_id: FileId,
+) -> Arc<Vec<Diagnostic<FileId>>> {
+    let imports = db.imports(file_id);
+    if imports.is_empty() {
+        return Arc::new(Vec::new());
+    }
+
+    let cycles = db.import_cycles();
+    let cycle = cycles.iter().find(|cycle| cycle.contains(&file_id));
+
+    let diagnostics = imports
+        .iter()
+        .filter_map(|import| {
+            let location = Location::new(file_id, import.range.clone());
+            match (db.resolve_import(import.path.clone()), cycle) {
+                (None, _) => {
+                    let diagnostic = match import.path.split_last() {
+                        Some((member, parent))
+                            if !parent.is_empty()
+                                && db
+                                    .resolve_import(Arc::new(parent.to_vec()))
+                                    .is_some() =>
+                        {
+                            imported_member(parent, member)
+                        }
+                        _ => unresolved_import(&import.path),
+                    };
+                    Some(diagnostic.location(location))
+                }
+                (Some(dependency), Some(cycle))
+                    if cycle.contains(&dependency) =>
+                {
+                    Some(import_cycle(db, cycle).location(location))
+                }
+                _ => None,
+            }
+        })
+        .collect();
+
+    Arc::new(diagnostics)
+}
+
+fn unresolved_import(path: &[String]) -> Diagnostic<FileId> {
+    let description = FormattedString::default()
+        .text("I couldn't find the module imported here:");
+
+    let message = FormattedString::default()
+        .text("There is no module named ")
+        .code(path.join("."))
+        .text(" in the workspace.");
+
+    Diagnostic::error("Unresolved import")
+        .description(description)
+        .message(message)
+}
+
+fn imported_member(module: &[String], member: &str) -> Diagnostic<FileId> {
+    let module = module.join(".");
+    let description = FormattedString::default()
+        .text("I can't import something from a module:");
+
+    let message = FormattedString::default()
+        .text("Imports bring whole modules into scope, and there is no module ")
+        .text("named ")
+        .code(format!("{module}.{member}"))
+        .text(". Only ")
+        .code(module.as_str())
+        .text(" is a module, whose bindings can't be imported one by one.");
+
+    Diagnostic::error("Unsupported import")
+        .description(description)
+        .message(message)
+}
+
+fn import_cycle(db: &dyn Modules, cycle: &[FileId]) -> Diagnostic<FileId> {
+    let description = FormattedString::default()
+        .text("This import makes a module depend on itself:");
+
+    let files = cycle
+        .iter()
+        .map(|&file_id| {
+            FormattedString::default().code(db.file_name(file_id).as_str())
+        })
+        .collect::<Vec<_>>();
+
+    let message = FormattedString::default()
+        .text(
+            "The following files import each other, so none of them can be \
+             built before the others:",
+        )
+        .list(files);
+
+    Diagnostic::error("Import cycle")
+        .description(description)
+        .message(message)
+}
+
+/// Groups the workspace files into the strongly connected components of the
+/// dependency graph (with Tarjan's algorithm).
+///
+/// A component is only listed after every component it depends on, and the
+/// files of a component are listed in the order they were first reached.
+fn strongly_connected_components(db: &dyn Modules) -> Vec<Vec<FileId>> {
+    struct Tarjan<'a> {
+        db: &'a dyn Modules,
+        indexes: HashMap<FileId, usize>,
+        stack: Vec<FileId>,
+        components: Vec<Vec<FileId>>,
+    }
+
+    impl Tarjan<'_> {
+        /// Visits a file that hasn't been visited yet, returning the lowest
+        /// index reachable from it that is still on the stack.
+        fn visit(&mut self, file_id: FileId) -> usize {
+            self.db.unwind_if_cancelled();
+
+            let index = self.indexes.len();
+            let mut low_link = index;
+            self.indexes.insert(file_id, index);
+            self.stack.push(file_id);
+
+            for &dependency in self.db.dependencies(file_id).iter() {
+                match self.indexes.get(&dependency) {
+                    None => low_link = low_link.min(self.visit(dependency)),
+                    Some(&other) if self.stack.contains(&dependency) => {
+                        low_link = low_link.min(other);
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            if low_link == index {
+                let start =
+                    self.stack.iter().rposition(|&it| it == file_id).unwrap();
+                self.components.push(self.stack.split_off(start));
+            }
+
+            low_link
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        db,
+        indexes: HashMap::new(),
+        stack: Vec::new(),
+        components: Vec::new(),
+    };
+
+    for &file_id in db.workspace_files().iter() {
+        if !tarjan.indexes.contains_key(&file_id) {
+            tarjan.visit(file_id);
+        }
+    }
+
+    tarjan.components
+}
@@ -0,0 +1,81 @@
+use crate::{Cancelable, Cancelled, Diagnostics, FileId, HeliosDatabase};
+use helios_diagnostics::Diagnostic;
+use salsa::ParallelDatabase;
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+impl HeliosDatabase {
+    /// Computes the diagnostics of the given files on every available core,
+    /// returning them in the same order as the files.
+    ///
+    /// Parsing the files (and everything else the diagnostics depend on) is
+    /// memoized in the database as usual, so later queries can reuse it.
+    pub fn analyze_parallel(
+        &self,
+        files: &[FileId],
+    ) -> Cancelable<Vec<Arc<Vec<Diagnostic<FileId>>>>> {
+        self.map_parallel(files, |db, file_id| db.diagnostics(file_id))
+    }
+
+    /// Runs the given read-only work for every file on a pool of threads, each
+    /// with its own snapshot of the database, returning the results in the
+    /// same order as the files.
+    ///
+    /// If the database is changed while the work is being done, the work is
+    /// cancelled and [`Cancelled`] is returned.
+    pub fn map_parallel<T, F>(
+        &self,
+        files: &[FileId],
+        f: F,
+    ) -> Cancelable<Vec<T>>
+    where
+        T: Send,
+        F: Fn(&HeliosDatabase, FileId) -> T + Sync,
+    {
+        let threads = thread::available_parallelism()
+            .map_or(1, NonZeroUsize::get)
+            .min(files.len());
+
+        // Files are handed out one at a time, so a thread that gets a large
+        // file doesn't hold up the others.
+        let next = AtomicUsize::new(0);
+        let work = |db: &HeliosDatabase| {
+            let mut results = Vec::new();
+            loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                match files.get(index) {
+                    Some(&file_id) => results.push((index, f(db, file_id))),
+                    None => return results,
+                }
+            }
+        };
+
+        let mut results = thread::scope(|scope| {
+            let workers = (0..threads)
+                .map(|_| {
+                    let snapshot = self.snapshot();
+                    let work = &work;
+                    scope.spawn(move || {
+                        Cancelled::catch(AssertUnwindSafe(|| work(&snapshot)))
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut results = Vec::with_capacity(files.len());
+            for worker in workers {
+                match worker.join() {
+                    Ok(worker_results) => results.extend(worker_results?),
+                    Err(payload) => panic::resume_unwind(payload),
+                }
+            }
+
+            Ok(results)
+        })?;
+
+        results.sort_by_key(|(index, _)| *index);
+        Ok(results.into_iter().map(|(_, result)| result).collect())
+    }
+}
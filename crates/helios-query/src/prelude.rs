@@ -0,0 +1,14 @@
+use crate::FileId;
+
+/// The file the prelude is stored as.
+///
+/// The prelude isn't part of the workspace (so it's neither built nor checked
+/// along with the user's files), and its names are resolved in every other file
+/// without being imported.
+pub const PRELUDE_FILE_ID: FileId = FileId(u32::MAX);
+
+/// The name of the file the prelude is stored as.
+pub const PRELUDE_FILE_NAME: &str = "prelude.helios";
+
+/// The source text of the prelude, which is bundled with the compiler.
+pub const PRELUDE_SOURCE: &str = include_str!("prelude.helios");
@@ -0,0 +1,212 @@
+//! Instrumentation of the queries executed by the database.
+//!
+//! Profiling is opt-in (see [`HeliosDatabase::enable_query_stats`]): once it's
+//! enabled, every salsa event is recorded to count how often each query is
+//! executed or reused, and to measure how long its executions take.
+//!
+//! Salsa only reports when a query *starts* executing, so a query is taken to
+//! have finished once its thread starts executing (or reusing) a query that
+//! isn't nested in it. The work a query does after its last nested query is
+//! therefore only counted if its thread goes on to execute something else.
+
+use crate::HeliosDatabase;
+use salsa::{DatabaseKeyIndex, Event, EventKind, RuntimeId};
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Records the queries executed by a database (and all of its snapshots).
+#[derive(Debug, Default)]
+pub(crate) struct QueryProfiler {
+    state: Mutex<ProfilerState>,
+}
+
+#[derive(Debug, Default)]
+struct ProfilerState {
+    /// The statistics of every query, by their group and query indexes.
+    queries: HashMap<(u16, u16), QueryStat>,
+    /// The queries being executed on every thread.
+    threads: HashMap<RuntimeId, ThreadState>,
+}
+
+#[derive(Debug)]
+struct ThreadState {
+    /// The queries that started executing and that haven't finished yet, from
+    /// the outermost to the innermost.
+    stack: Vec<(DatabaseKeyIndex, Instant)>,
+    /// When the last event happened on the thread.
+    last_event: Instant,
+}
+
+impl QueryProfiler {
+    /// Records a salsa event that happened on the given database.
+    pub(crate) fn record(&self, db: &HeliosDatabase, event: &Event) {
+        use salsa::Database;
+
+        let (database_key, executed) = match event.kind {
+            EventKind::WillExecute { database_key } => (database_key, true),
+            EventKind::DidValidateMemoizedValue { database_key } => {
+                (database_key, false)
+            }
+            EventKind::WillBlockOn { .. } => return,
+        };
+
+        let now = Instant::now();
+        let active_query = db.salsa_runtime().active_query();
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+
+        let thread =
+            state.threads.entry(event.runtime_id).or_insert_with(|| {
+                ThreadState {
+                    stack: Vec::new(),
+                    last_event: now,
+                }
+            });
+        thread.last_event = now;
+
+        // Every query above the one that's active on the thread has finished.
+        while let Some(&(key, start)) = thread.stack.last() {
+            if Some(key) == active_query {
+                break;
+            }
+
+            thread.stack.pop();
+            if let Some(stat) = state.queries.get_mut(&query_index(key)) {
+                stat.duration += now - start;
+            }
+        }
+
+        let stat = state
+            .queries
+            .entry(query_index(database_key))
+            .or_insert_with(|| QueryStat::new(query_name(db, database_key)));
+
+        if executed {
+            stat.executions += 1;
+            thread.stack.push((database_key, now));
+        } else {
+            stat.reuses += 1;
+        }
+    }
+
+    /// The statistics recorded so far, with the queries that haven't finished
+    /// yet taken to have finished with the last event of their thread.
+    pub(crate) fn stats(&self) -> QueryStats {
+        let state = self.state.lock().unwrap();
+        let mut queries = state.queries.clone();
+
+        for thread in state.threads.values() {
+            for &(key, start) in &thread.stack {
+                if let Some(stat) = queries.get_mut(&query_index(key)) {
+                    stat.duration += thread.last_event - start;
+                }
+            }
+        }
+
+        let mut queries = queries.into_values().collect::<Vec<_>>();
+        queries.sort_by(|a, b| {
+            b.duration
+                .cmp(&a.duration)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        QueryStats { queries }
+    }
+}
+
+fn query_index(key: DatabaseKeyIndex) -> (u16, u16) {
+    (key.group_index(), key.query_index())
+}
+
+/// The name of the query a key belongs to (such as `parse` for
+/// `parse(FileId(0))`).
+fn query_name(db: &HeliosDatabase, key: DatabaseKeyIndex) -> String {
+    let key = format!("{:?}", key.debug(db));
+    match key.split_once('(') {
+        Some((name, _)) => name.to_string(),
+        None => key,
+    }
+}
+
+/// How often a query was executed or reused, and how long it took.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QueryStat {
+    /// The name of the query.
+    pub name: String,
+    /// The number of times the query was executed.
+    pub executions: usize,
+    /// The number of times a memoized value of the query was reused after
+    /// checking that it's still up to date.
+    pub reuses: usize,
+    /// The total time spent executing the query, including the queries it
+    /// executed in turn.
+    pub duration: Duration,
+}
+
+impl QueryStat {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            executions: 0,
+            reuses: 0,
+            duration: Duration::ZERO,
+        }
+    }
+}
+
+/// The statistics of every query that was executed or reused, sorted from the
+/// one that took the longest.
+///
+/// This is displayed as a table, with one query per line.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct QueryStats {
+    pub queries: Vec<QueryStat>,
+}
+
+impl Display for QueryStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .queries
+            .iter()
+            .map(|stat| stat.name.len())
+            .chain(Some("query".len()))
+            .max()
+            .unwrap_or_default();
+
+        writeln!(f, "{:width$}  executed  reused  time (ms)", "query")?;
+        for stat in &self.queries {
+            writeln!(
+                f,
+                "{:width$}  {:>8}  {:>6}  {:>9.3}",
+                stat.name,
+                stat.executions,
+                stat.reuses,
+                stat.duration.as_secs_f64() * 1000.0,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl HeliosDatabase {
+    /// Starts recording how often every query is executed (or reused), and
+    /// how long it takes, on this database and every snapshot taken of it from
+    /// now on.
+    ///
+    /// This slows every query down a little, so it's meant for performance
+    /// work rather than everyday use.
+    pub fn enable_query_stats(&mut self) {
+        if self.profiler.is_none() {
+            self.profiler = Some(Arc::new(QueryProfiler::default()));
+        }
+    }
+
+    /// The statistics recorded since [`HeliosDatabase::enable_query_stats`]
+    /// was called, if it was.
+    pub fn query_stats(&self) -> Option<QueryStats> {
+        self.profiler.as_ref().map(|profiler| profiler.stats())
+    }
+}
@@ -0,0 +1,395 @@
+use crate::{CheckCancelled, FileId, Hir, Modules, PRELUDE_FILE_ID};
+use helios_diagnostics::{Diagnostic, Location};
+use helios_formatting::FormattedString;
+use helios_hir::{
+    item_name, Expr, Item, ItemId, MacroErrorKind, ModuleScope,
+    ModuleSourceMap, Name, NameResolutions, MAX_EXPANSION_DEPTH,
+};
+use std::collections::HashSet;
+use std::ops::Range;
+use std::sync::Arc;
+
+#[salsa::query_group(ScopesDatabase)]
+pub trait Scopes: Hir + Modules {
+    /// The names declared at the top level of a file.
+    fn module_scope(&self, file_id: FileId) -> Arc<ModuleScope>;
+
+    /// What the names referred to in the body of an item resolve to.
+    ///
+    /// Names that aren't declared in the file resolve to the bindings of the
+    /// prelude (see [`PRELUDE_FILE_ID`]), if it declares them.
+    fn name_resolutions(
+        &self,
+        file_id: FileId,
+        item_id: ItemId,
+    ) -> Arc<NameResolutions>;
+
+    /// The global bindings of a file whose values depend on themselves (see
+    /// [`helios_hir::binding_cycles`]).
+    fn binding_cycles(&self, file_id: FileId) -> Arc<Vec<Vec<ItemId>>>;
+
+    /// Diagnostics for the names of a file that are declared more than once
+    /// or that can't be resolved, for the macro calls that can't be expanded,
+    /// and for the global bindings whose values depend on themselves.
+    fn name_diagnostics(&self, file_id: FileId)
+        -> Arc<Vec<Diagnostic<FileId>>>;
+
+    /// Warnings for the global bindings, external functions and imports of a
+    /// file that are never referred to (other than by themselves), along with
+    /// fixes removing them.
+    ///
+    /// Names starting with an underscore are never reported, and neither are
+    /// the bindings of the prelude, the entry point of the program (see
+    /// [`helios_hir::Module::main`]) or the global bindings and external
+    /// functions of a module other files import (which declares them for
+    /// those files).
+    fn unused_diagnostics(
+        &self,
+        file_id: FileId,
+    ) -> Arc<Vec<Diagnostic<FileId>>>;
+}
+
+fn module_scope(db: &dyn Scopes, file_id: FileId) -> Arc<ModuleScope> {
+    let module = db.hir_module(file_id);
+    Arc::new(ModuleScope::new(&module))
+}
+
+fn name_resolutions(
+    db: &dyn Scopes,
+    file_id: FileId,
+    item_id: ItemId,
+) -> Arc<NameResolutions> {
+    let scope = db.module_scope(file_id);
+    let body = db.body(file_id, item_id);
+    if file_id == PRELUDE_FILE_ID {
+        return Arc::new(NameResolutions::new(&scope, &body));
+    }
+
+    let prelude = db.module_scope(PRELUDE_FILE_ID);
+    Arc::new(NameResolutions::with_prelude(&scope, &prelude, &body))
+}
+
+fn binding_cycles(db: &dyn Scopes, file_id: FileId) -> Arc<Vec<Vec<ItemId>>> {
+    let module = db.hir_module(file_id);
+    let cycles = helios_hir::binding_cycles(&module, |item_id| {
+        db.unwind_if_cancelled();
+        db.name_resolutions(file_id, item_id)
+    });
+
+    Arc::new(cycles)
+}
+
+fn name_diagnostics(
+    db: &dyn Scopes,
+    file_id: FileId,
+) -> Arc<Vec<Diagnostic<FileId>>> {
+    let (module, source_map) = db.hir_module_with_source_map(file_id);
+    let mut diagnostics = Vec::new();
+
+    for duplicate in db.module_scope(file_id).duplicates() {
+        let name = &duplicate.name;
+        let ranges = (
+            declared_range(&source_map, duplicate.first, name),
+            declared_range(&source_map, duplicate.duplicate, name),
+        );
+
+        if let (Some(first), Some(range)) = ranges {
+            let diagnostic = duplicate_definition(name)
+                .location(Location::new(file_id, range))
+                .label(
+                    Location::new(file_id, first),
+                    format!("`{name}` is first declared here"),
+                );
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    for (item_id, _) in module.items.iter() {
+        db.unwind_if_cancelled();
+        let (body, body_source_map) = db.body_with_source_map(file_id, item_id);
+        for error in body_source_map.macro_errors() {
+            let location = Location::new(file_id, error.range);
+            diagnostics.push(macro_error(&error.kind).location(location));
+        }
+
+        let resolutions = db.name_resolutions(file_id, item_id);
+        for &expr_id in resolutions.unresolved() {
+            if let Expr::Name(name) = &body.exprs[expr_id] {
+                let range = body_source_map.expr_range(expr_id);
+                let location = Location::new(file_id, range);
+                diagnostics.push(unresolved_name(name).location(location));
+            }
+        }
+    }
+
+    for cycle in db.binding_cycles(file_id).iter() {
+        let names = cycle
+            .iter()
+            .filter_map(|&item_id| item_name(&module.items[item_id]))
+            .collect::<Vec<_>>();
+        let ranges = cycle
+            .iter()
+            .zip(&names)
+            .filter_map(|(&item_id, name)| {
+                declared_range(&source_map, item_id, name)
+            })
+            .collect::<Vec<_>>();
+
+        if names.len() != cycle.len() || ranges.len() != cycle.len() {
+            continue;
+        }
+
+        let mut diagnostic = circular_definition(&names)
+            .location(Location::new(file_id, ranges[0].clone()));
+        for (i, range) in ranges.iter().enumerate().skip(1) {
+            let next = names[(i + 1) % names.len()];
+            diagnostic = diagnostic.label(
+                Location::new(file_id, range.clone()),
+                format!("which refers to `{next}`"),
+            );
+        }
+
+        diagnostics.push(diagnostic);
+    }
+
+    Arc::new(diagnostics)
+}
+
+fn unused_diagnostics(
+    db: &dyn Scopes,
+    file_id: FileId,
+) -> Arc<Vec<Diagnostic<FileId>>> {
+    if file_id == PRELUDE_FILE_ID {
+        return Arc::default();
+    }
+
+    let (module, source_map) = db.hir_module_with_source_map(file_id);
+    let scope = db.module_scope(file_id);
+    let mut used = HashSet::new();
+    for (item_id, _) in module.items.iter() {
+        db.unwind_if_cancelled();
+        let resolutions = db.name_resolutions(file_id, item_id);
+        used.extend(
+            resolutions
+                .resolved_items()
+                .filter(|&target| target != item_id),
+        );
+    }
+
+    // The entry point is used by whatever runs the program.
+    used.extend(module.main());
+    let is_imported = !db.reverse_dependencies(file_id).is_empty();
+
+    let source = db.source(file_id);
+    let mut diagnostics = Vec::new();
+    for (item_id, item) in module.items.iter() {
+        let name = match item_name(item) {
+            Some(name) if !name.as_str().starts_with('_') => name,
+            _ => continue,
+        };
+
+        // Later declarations of a name are already reported as duplicates.
+        if used.contains(&item_id) || scope.get(name) != Some(item_id) {
+            continue;
+        }
+
+        let range = match declared_range(&source_map, item_id, name) {
+            Some(range) => range,
+            None => continue,
+        };
+
+        let (kind, title) = match item {
+            Item::Binding(_) | Item::External(_) if is_imported => continue,
+            Item::Binding(_) => ("binding", "Unused binding"),
+            Item::External(_) => {
+                ("external function", "Unused external function")
+            }
+            Item::Import(_) => ("import", "Unused import"),
+            Item::Test(_) | Item::Macro(_) | Item::Expr => continue,
+        };
+
+        // The whole declaration is removed, along with the spaces and the
+        // line break after it (but not a comment after it on the same line,
+        // which is left on a line of its own).
+        let item_range = source_map.item_range(item_id);
+        let start = usize::from(item_range.start());
+        let rest = source[usize::from(item_range.end())..]
+            .trim_start_matches([' ', '\t']);
+        let rest = rest
+            .strip_prefix("\r\n")
+            .or_else(|| rest.strip_prefix('\n'))
+            .unwrap_or(rest);
+        let end = source.len() - rest.len();
+
+        // Nothing can depend on an unused import, whereas removing a binding
+        // or an external function would also remove what its declaration
+        // does, which may be the point of it.
+        let location = Location::new(file_id, start..end);
+        let fix = format!("Remove the unused {kind}");
+        let mut diagnostic = unused(title, kind, name)
+            .location(Location::new(file_id, range.clone()));
+        diagnostic = match item {
+            Item::Import(_) => {
+                diagnostic.machine_applicable_fix(fix, location, "")
+            }
+            _ => diagnostic.fix(fix, location, ""),
+        };
+
+        // Renaming an external function would change the function of the
+        // host it's bound to.
+        if matches!(item, Item::Binding(_)) {
+            diagnostic = diagnostic.fix(
+                format!("Rename to `_{name}`"),
+                Location::new(file_id, range),
+                format!("_{name}"),
+            );
+        }
+
+        diagnostics.push(diagnostic);
+    }
+
+    Arc::new(diagnostics)
+}
+
+/// The range of the name an item declares (which is the last segment of the
+/// path of an import).
+fn declared_range(
+    source_map: &ModuleSourceMap,
+    item_id: ItemId,
+    name: &Name,
+) -> Option<Range<usize>> {
+    let end = usize::from(source_map.name_range(item_id)?.end());
+    Some(end - name.as_str().len()..end)
+}
+
+fn duplicate_definition(name: &Name) -> Diagnostic<FileId> {
+    let description = FormattedString::default()
+        .text("This name is already declared elsewhere in the module:");
+
+    let message = FormattedString::default().code(name.as_str()).text(
+        " is declared more than once, so I can't tell which declaration \
+             it refers to.",
+    );
+
+    let hint = FormattedString::default()
+        .text("Rename one of the declarations of ")
+        .code(name.as_str())
+        .text(".");
+
+    Diagnostic::error("Duplicate definition")
+        .description(description)
+        .message(message)
+        .hint(hint)
+}
+
+fn unused(title: &str, kind: &str, name: &Name) -> Diagnostic<FileId> {
+    let description =
+        FormattedString::default().text("This name is never used:");
+
+    let message = FormattedString::default()
+        .text(format!("The {kind} "))
+        .code(name.as_str())
+        .text(" is declared, but nothing refers to it.");
+
+    let hint = FormattedString::default()
+        .text("Remove it, or start its name with an underscore (such as ")
+        .code(format!("_{name}"))
+        .text(") if it's meant to be unused.");
+
+    Diagnostic::warning(title)
+        .description(description)
+        .message(message)
+        .hint(hint)
+}
+
+fn unresolved_name(name: &Name) -> Diagnostic<FileId> {
+    let description = FormattedString::default()
+        .text("I couldn't find what this name refers to:");
+
+    let message = FormattedString::default()
+        .text("There is no binding named ")
+        .code(name.as_str())
+        .text(" in scope.");
+
+    Diagnostic::error("Unresolved name")
+        .description(description)
+        .message(message)
+}
+
+fn macro_error(kind: &MacroErrorKind) -> Diagnostic<FileId> {
+    match kind {
+        MacroErrorKind::Unresolved(name) => {
+            let description = FormattedString::default()
+                .text("I couldn't find the macro this calls:");
+            let message = FormattedString::default()
+                .text("There is no macro named ")
+                .code(name.as_str())
+                .text(" in this file.");
+
+            Diagnostic::error("Unresolved macro")
+                .description(description)
+                .message(message)
+        }
+        MacroErrorKind::ArgumentCount {
+            name,
+            expected,
+            found,
+        } => {
+            let plural = |count: usize| if count == 1 { "" } else { "s" };
+            let description = FormattedString::default().text(
+                "This macro call doesn't have the right number of arguments:",
+            );
+            let message = FormattedString::default()
+                .text("The macro ")
+                .code(name.as_str())
+                .text(format!(
+                    " takes {expected} argument{}, but {found} {} given.",
+                    plural(*expected),
+                    if *found == 1 { "was" } else { "were" },
+                ));
+
+            Diagnostic::error("Wrong number of arguments")
+                .description(description)
+                .message(message)
+        }
+        MacroErrorKind::TooDeep(name) => {
+            let description = FormattedString::default()
+                .text("This macro call expands to too many other calls:");
+            let message = FormattedString::default()
+                .text("Expanding ")
+                .code(name.as_str())
+                .text(format!(
+                    " takes more than {MAX_EXPANSION_DEPTH} nested macro \
+                     calls, so it probably calls itself forever."
+                ));
+
+            Diagnostic::error("Macro expansion too deep")
+                .description(description)
+                .message(message)
+        }
+    }
+}
+
+/// A diagnostic for a cycle of global bindings, where each binding refers to
+/// the next one (and the last one to the first one).
+fn circular_definition(names: &[&Name]) -> Diagnostic<FileId> {
+    let description = FormattedString::default()
+        .text("The value of this binding depends on itself:");
+
+    let path = names
+        .iter()
+        .chain(names.first())
+        .map(|name| name.as_str())
+        .collect::<Vec<_>>()
+        .join(" -> ");
+
+    let message = FormattedString::default()
+        .text("A binding is computed from the bindings it refers to, so ")
+        .code(names[0].as_str())
+        .text(" can never be computed:")
+        .code_block(path);
+
+    Diagnostic::error("Circular definition")
+        .description(description)
+        .message(message)
+}
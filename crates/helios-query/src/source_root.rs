@@ -0,0 +1,322 @@
+use crate::{FileId, FileOrigin, HeliosDatabase, Input};
+use glob::{MatchOptions, Pattern, PatternError};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The extension of Helios source files.
+pub const FILE_EXTENSION: &str = "helios";
+
+/// How exclusion globs are matched: `*` doesn't cross directories (but `**`
+/// does).
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[salsa::query_group(SourceRootsDatabase)]
+pub trait SourceRoots: Input {
+    /// Every source root of the project, in the order they were added.
+    #[salsa::input]
+    fn source_roots(&self) -> Arc<Vec<SourceRootId>>;
+
+    /// A source root of the project, along with the files known to be in it.
+    #[salsa::input]
+    fn source_root(&self, root_id: SourceRootId) -> Arc<SourceRoot>;
+
+    /// The source root a file is part of, if it's part of any.
+    fn source_root_of_file(&self, file_id: FileId) -> Option<SourceRootId>;
+
+    /// The file stored for the given path, if it's part of a source root.
+    fn file_at_path(&self, path: PathBuf) -> Option<FileId>;
+
+    /// Every file of the source roots of dependencies, which can be imported
+    /// but aren't part of the workspace.
+    fn dependency_files(&self) -> Arc<Vec<FileId>>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SourceRootId(pub u32);
+
+/// A directory whose Helios files are part of the project (such as the user's
+/// workspace, or a dependency), along with the files known to be in it.
+///
+/// A file belongs in the innermost root containing it, unless it's excluded by
+/// one of the root's exclusion globs.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SourceRoot {
+    path: PathBuf,
+    origin: FileOrigin,
+    package: Option<String>,
+    exclude: Vec<Pattern>,
+    files: BTreeMap<PathBuf, FileId>,
+}
+
+impl SourceRoot {
+    /// Creates an empty source root for the given directory.
+    ///
+    /// A root with an empty path contains every file (which is useful for
+    /// files that don't belong in any other root).
+    pub fn new(path: impl Into<PathBuf>, origin: FileOrigin) -> Self {
+        Self {
+            path: path.into(),
+            origin,
+            package: None,
+            exclude: Vec::new(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Excludes the files and directories matching the given glob, relative
+    /// to the root (such as `examples` or `**/*.gen.helios`).
+    pub fn exclude(mut self, pattern: &str) -> Result<Self, PatternError> {
+        self.exclude.push(Pattern::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Makes the root the source directory of the package with the given
+    /// name, whose modules are then namespaced by it (so `math/vector.helios`
+    /// defines `math.vector` in the root of the `math` package).
+    pub fn with_package(mut self, name: impl Into<String>) -> Self {
+        self.package = Some(name.into());
+        self
+    }
+
+    /// The name of the package the root is the source directory of, if any.
+    pub fn package(&self) -> Option<&str> {
+        self.package.as_deref()
+    }
+
+    /// The directory of the root.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Where the files of the root come from.
+    pub fn origin(&self) -> FileOrigin {
+        self.origin
+    }
+
+    /// Whether or not the file at the given path belongs in the root (whether
+    /// it's known to be in it or not).
+    pub fn contains(&self, path: &Path) -> bool {
+        match path.strip_prefix(&self.path) {
+            Ok(relative) => !relative
+                .ancestors()
+                .filter(|ancestor| !ancestor.as_os_str().is_empty())
+                .any(|ancestor| self.is_excluded(ancestor)),
+            Err(_) => false,
+        }
+    }
+
+    fn is_excluded(&self, relative: &Path) -> bool {
+        self.exclude
+            .iter()
+            .any(|pattern| pattern.matches_path_with(relative, MATCH_OPTIONS))
+    }
+
+    /// The file stored for the given path, if it's known to be in the root.
+    pub fn file_id(&self, path: &Path) -> Option<FileId> {
+        self.files.get(path).copied()
+    }
+
+    /// The path of the given file, if it's known to be in the root.
+    pub fn file_path(&self, file_id: FileId) -> Option<&Path> {
+        self.files
+            .iter()
+            .find(|(_, &id)| id == file_id)
+            .map(|(path, _)| path.as_path())
+    }
+
+    /// Every file known to be in the root, sorted by their paths.
+    pub fn files(&self) -> impl Iterator<Item = (&Path, FileId)> + '_ {
+        self.files.iter().map(|(path, &id)| (path.as_path(), id))
+    }
+
+    /// Records that the file at the given path is stored as the given file.
+    pub fn insert(&mut self, path: impl Into<PathBuf>, file_id: FileId) {
+        self.files.insert(path.into(), file_id);
+    }
+
+    /// Forgets the file at the given path, returning the file it was stored
+    /// as.
+    pub fn remove(&mut self, path: &Path) -> Option<FileId> {
+        self.files.remove(path)
+    }
+
+    /// Finds every Helios file on disk that belongs in the root, sorted by
+    /// their paths.
+    ///
+    /// Hidden directories (such as `.git`), `target` directories and
+    /// directories that can't be read are skipped.
+    pub fn find_files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        let mut directories = vec![self.path.clone()];
+
+        while let Some(directory) = directories.pop() {
+            let entries = match std::fs::read_dir(&directory) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+
+            for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+                if !self.contains(&path) {
+                    continue;
+                }
+
+                let name =
+                    path.file_name().unwrap_or_default().to_string_lossy();
+                if path.is_dir() {
+                    if !name.starts_with('.') && name != "target" {
+                        directories.push(path);
+                    }
+                } else if path
+                    .extension()
+                    .is_some_and(|ext| ext == FILE_EXTENSION)
+                {
+                    files.push(path);
+                }
+            }
+        }
+
+        files.sort();
+        files
+    }
+}
+
+fn source_root_of_file(
+    db: &dyn SourceRoots,
+    file_id: FileId,
+) -> Option<SourceRootId> {
+    db.source_roots()
+        .iter()
+        .copied()
+        .find(|&root_id| db.source_root(root_id).file_path(file_id).is_some())
+}
+
+fn file_at_path(db: &dyn SourceRoots, path: PathBuf) -> Option<FileId> {
+    db.source_roots()
+        .iter()
+        .find_map(|&root_id| db.source_root(root_id).file_id(&path))
+}
+
+fn dependency_files(db: &dyn SourceRoots) -> Arc<Vec<FileId>> {
+    let files = db
+        .source_roots()
+        .iter()
+        .map(|&root_id| db.source_root(root_id))
+        .filter(|root| root.origin() == FileOrigin::Dependency)
+        .flat_map(|root| {
+            root.files().map(|(_, file_id)| file_id).collect::<Vec<_>>()
+        })
+        .collect();
+
+    Arc::new(files)
+}
+
+impl HeliosDatabase {
+    /// Adds a source root to the project, returning its ID.
+    ///
+    /// The files the root already knows about are added to the workspace if
+    /// the root is part of it.
+    pub fn add_source_root(&mut self, root: SourceRoot) -> SourceRootId {
+        let mut roots = (*self.source_roots()).clone();
+        let root_id = SourceRootId(roots.len() as u32);
+        roots.push(root_id);
+
+        self.set_root(root_id, root);
+        self.set_source_roots(Arc::new(roots));
+        self.update_workspace_files();
+
+        root_id
+    }
+
+    /// The innermost source root the file at the given path belongs in.
+    pub fn source_root_for_path(&self, path: &Path) -> Option<SourceRootId> {
+        self.source_roots()
+            .iter()
+            .map(|&root_id| (root_id, self.source_root(root_id)))
+            .filter(|(_, root)| root.contains(path))
+            .max_by_key(|(_, root)| root.path().components().count())
+            .map(|(root_id, _)| root_id)
+    }
+
+    /// Stores the source text of the file at the given path as the given
+    /// file, adding it to the source root it belongs in (and to the workspace,
+    /// if the root is part of it).
+    ///
+    /// Returns the root the file was added to, if it belongs in any.
+    pub fn set_file_at_path(
+        &mut self,
+        file_id: FileId,
+        path: &Path,
+        source: impl Into<String>,
+    ) -> Option<SourceRootId> {
+        let root_id = self.source_root_for_path(path);
+        let root = root_id.map(|root_id| self.source_root(root_id));
+        let origin = root
+            .as_ref()
+            .map_or(FileOrigin::Workspace, |root| root.origin());
+        let is_known = root
+            .as_ref()
+            .is_some_and(|root| root.file_id(path) == Some(file_id));
+
+        if is_known {
+            self.set_source_with_durability(
+                file_id,
+                Arc::new(source.into()),
+                origin.durability(),
+            );
+            return root_id;
+        }
+
+        self.set_file(file_id, path.display().to_string(), source, origin);
+        if let (Some(root_id), Some(root)) = (root_id, root) {
+            let mut root = (*root).clone();
+            root.insert(path, file_id);
+            self.set_root(root_id, root);
+            self.update_workspace_files();
+        }
+
+        root_id
+    }
+
+    /// Removes the file at the given path from the source root it's in (and
+    /// from the workspace), returning the file it was stored as.
+    pub fn remove_file_at_path(&mut self, path: &Path) -> Option<FileId> {
+        let root_id = self.source_root_for_path(path)?;
+        let mut root = (*self.source_root(root_id)).clone();
+        let file_id = root.remove(path)?;
+
+        self.set_root(root_id, root);
+        self.update_workspace_files();
+        Some(file_id)
+    }
+
+    fn set_root(&mut self, root_id: SourceRootId, root: SourceRoot) {
+        let durability = root.origin().durability();
+        self.set_source_root_with_durability(
+            root_id,
+            Arc::new(root),
+            durability,
+        );
+    }
+
+    /// Makes the workspace the files of every source root that's part of it.
+    fn update_workspace_files(&mut self) {
+        let files = self
+            .source_roots()
+            .iter()
+            .map(|&root_id| self.source_root(root_id))
+            .filter(|root| root.origin() == FileOrigin::Workspace)
+            .flat_map(|root| {
+                root.files().map(|(_, file_id)| file_id).collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        if *self.workspace_files() != files {
+            self.set_workspace_files(Arc::new(files));
+        }
+    }
+}
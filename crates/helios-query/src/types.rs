@@ -0,0 +1,355 @@
+use crate::{CheckCancelled, FileId, Scopes, PRELUDE_FILE_ID};
+use helios_diagnostics::{Diagnostic, Location};
+use helios_formatting::FormattedString;
+use helios_hir::{Expr, Item, Name, UnaryOp};
+use helios_types::{InferenceResult, Scheme, Ty, TypeError};
+use rowan::TextSize;
+use std::sync::Arc;
+
+#[salsa::query_group(TypesDatabase)]
+pub trait Types: Scopes {
+    /// The types inferred for the items of a file.
+    fn infer(&self, file_id: FileId) -> Arc<InferenceResult>;
+
+    /// The type of the expression (or of the global binding declared by the
+    /// identifier) at the given offset, if there is one.
+    ///
+    /// Expressions are given the type they were inferred to have where they
+    /// are, so only global bindings can be generic.
+    fn type_at_offset(&self, file_id: FileId, offset: usize) -> Option<Scheme>;
+
+    /// Diagnostics for the expressions of a file whose types couldn't be
+    /// unified with the types they were expected to have, for operators
+    /// applied to operands they don't support, for imported modules used as
+    /// values, and for external functions declared with a type that doesn't
+    /// exist.
+    fn type_diagnostics(&self, file_id: FileId)
+        -> Arc<Vec<Diagnostic<FileId>>>;
+
+    /// Diagnostics for the file a program is built from, which must either
+    /// declare its entry point (a global binding named `main` whose value is
+    /// an `Int`, the exit code of the program) or have expressions at its top
+    /// level.
+    ///
+    /// Only the file a program starts from needs an entry point, so these
+    /// aren't part of [`crate::Diagnostics::diagnostics`].
+    fn entry_point_diagnostics(
+        &self,
+        file_id: FileId,
+    ) -> Arc<Vec<Diagnostic<FileId>>>;
+}
+
+fn infer(db: &dyn Types, file_id: FileId) -> Arc<InferenceResult> {
+    let module = db.hir_module(file_id);
+    let prelude = match file_id {
+        PRELUDE_FILE_ID => Arc::default(),
+        _ => db.infer(PRELUDE_FILE_ID),
+    };
+
+    let result =
+        helios_types::infer_module_with_prelude(&module, &prelude, |item_id| {
+            db.unwind_if_cancelled();
+            let body = db.body(file_id, item_id);
+            (body, db.name_resolutions(file_id, item_id))
+        });
+
+    Arc::new(result)
+}
+
+fn type_at_offset(
+    db: &dyn Types,
+    file_id: FileId,
+    offset: usize,
+) -> Option<Scheme> {
+    let offset = TextSize::from(offset as u32);
+    let (module, source_map) = db.hir_module_with_source_map(file_id);
+    let (item_id, item) = module.items.iter().find(|&(item_id, _)| {
+        source_map.item_range(item_id).contains_inclusive(offset)
+    })?;
+
+    let inference = db.infer(file_id);
+    let is_name = source_map
+        .name_range(item_id)
+        .is_some_and(|range| range.contains_inclusive(offset));
+    if is_name && matches!(item, Item::Binding(_) | Item::External(_)) {
+        return inference.binding_type(item_id).cloned();
+    }
+
+    let (_, body_source_map) = db.body_with_source_map(file_id, item_id);
+    let expr_id = body_source_map.expr_at_offset(offset)?;
+    inference.expr_type(item_id, expr_id).map(Scheme::mono)
+}
+
+fn type_diagnostics(
+    db: &dyn Types,
+    file_id: FileId,
+) -> Arc<Vec<Diagnostic<FileId>>> {
+    let inference = db.infer(file_id);
+    let mut diagnostics = Vec::new();
+
+    for error in inference.errors() {
+        let (body, source_map) = db.body_with_source_map(file_id, error.item());
+        let location =
+            |expr_id| Location::new(file_id, source_map.expr_range(expr_id));
+
+        let diagnostic = match *error {
+            TypeError::Mismatch {
+                expected, found, ..
+            } => mismatched_types(expected, found),
+            TypeError::MismatchedOperands { expr, lhs, rhs, .. } => {
+                let Expr::Binary {
+                    op,
+                    lhs: lhs_id,
+                    rhs: rhs_id,
+                } = body.exprs[expr]
+                else {
+                    continue;
+                };
+
+                mismatched_operands(op.symbol(), lhs, rhs)
+                    .label(location(lhs_id), type_label(lhs))
+                    .label(location(rhs_id), type_label(rhs))
+            }
+            TypeError::UnsupportedOperand { expr, ty, .. } => {
+                let (symbol, is_logical) = match body.exprs[expr] {
+                    Expr::Unary { op, .. } => (op.symbol(), op == UnaryOp::Not),
+                    Expr::Binary { op, .. } => (op.symbol(), false),
+                    _ => continue,
+                };
+
+                let operands = body.exprs[expr].children();
+                let diagnostic =
+                    unsupported_operand(symbol, is_logical, operands.len(), ty);
+                operands
+                    .into_iter()
+                    .fold(diagnostic, |diagnostic, operand| {
+                        diagnostic.label(location(operand), type_label(ty))
+                    })
+            }
+            TypeError::ModuleAsValue { expr, .. } => match &body.exprs[expr] {
+                Expr::Name(name) => module_as_value(name),
+                _ => continue,
+            },
+        };
+
+        diagnostics.push(diagnostic.location(location(error.expr())));
+    }
+
+    let (module, source_map) = db.hir_module_with_source_map(file_id);
+    for (item_id, external) in module.externals() {
+        let ty = match &external.ty {
+            Some(ty) if Ty::from_name(ty.as_str()).is_none() => ty,
+            _ => continue,
+        };
+
+        // The type is the last thing an external function declares.
+        let end = usize::from(source_map.item_range(item_id).end());
+        let range = end - ty.as_str().len()..end;
+        diagnostics
+            .push(unknown_type(ty).location(Location::new(file_id, range)));
+    }
+
+    Arc::new(diagnostics)
+}
+
+fn entry_point_diagnostics(
+    db: &dyn Types,
+    file_id: FileId,
+) -> Arc<Vec<Diagnostic<FileId>>> {
+    let (module, source_map) = db.hir_module_with_source_map(file_id);
+    let main = db.module_scope(file_id).get(&Name::new("main"));
+    let location = |item_id| {
+        let range = source_map
+            .name_range(item_id)
+            .unwrap_or_else(|| source_map.item_range(item_id));
+        Location::new(file_id, range)
+    };
+
+    let diagnostic = match main {
+        Some(main) => {
+            let ty = match &module.items[main] {
+                Item::Binding(_) => {
+                    db.infer(file_id).binding_type(main).cloned()
+                }
+                _ => None,
+            };
+
+            match ty {
+                Some(scheme)
+                    if scheme.vars.is_empty() && scheme.ty == Ty::Int =>
+                {
+                    return Arc::default();
+                }
+                // The error is reported wherever the type comes from.
+                Some(scheme) if scheme.ty == Ty::Error => {
+                    return Arc::default()
+                }
+                ty => mistyped_entry_point(ty).location(location(main)),
+            }
+        }
+        None if module.items.iter().any(|(_, item)| *item == Item::Expr) => {
+            return Arc::default();
+        }
+        None => missing_entry_point().location(Location::new(file_id, 0..0)),
+    };
+
+    Arc::new(vec![diagnostic])
+}
+
+fn mistyped_entry_point(ty: Option<Scheme>) -> Diagnostic<FileId> {
+    let description = FormattedString::default()
+        .text("This can't be the entry point of the program:");
+
+    let message = FormattedString::default()
+        .text(
+            "The entry point of a program must be a global binding whose \
+             value is an ",
+        )
+        .code("Int")
+        .text(" (the exit code of the program)");
+    let message = match ty {
+        Some(scheme) => message
+            .text(", but ")
+            .code("main")
+            .text(" is of type ")
+            .code(scheme.to_string())
+            .text("."),
+        None => message
+            .text(", but ")
+            .code("main")
+            .text(" isn't a global binding."),
+    };
+
+    Diagnostic::error("Mistyped entry point")
+        .description(description)
+        .message(message)
+}
+
+fn missing_entry_point() -> Diagnostic<FileId> {
+    let description = FormattedString::default()
+        .text("This file doesn't say where the program starts:");
+
+    let message = FormattedString::default()
+        .text(
+            "A program runs the expressions at its top level, and then its \
+             global binding named ",
+        )
+        .code("main")
+        .text(", but this file has neither.");
+
+    let hint = FormattedString::default()
+        .text("Declare the entry point of the program, such as ")
+        .code("let main = 0")
+        .text(".");
+
+    Diagnostic::error("Missing entry point")
+        .description(description)
+        .message(message)
+        .hint(hint)
+}
+
+fn type_label(ty: Ty) -> String {
+    format!("this is of type `{ty}`")
+}
+
+fn unknown_type(name: &Name) -> Diagnostic<FileId> {
+    let description =
+        FormattedString::default().text("I couldn't find this type:");
+
+    let message = FormattedString::default()
+        .text("There is no type named ")
+        .code(name.as_str())
+        .text(".");
+
+    let hint = FormattedString::default()
+        .text("External functions can only return an ")
+        .code("Int")
+        .text(", a ")
+        .code("Float")
+        .text(" or a ")
+        .code("Bool")
+        .text(".");
+
+    Diagnostic::error("Unknown type")
+        .description(description)
+        .message(message)
+        .hint(hint)
+}
+
+fn module_as_value(name: &Name) -> Diagnostic<FileId> {
+    let description = FormattedString::default()
+        .text("This refers to a module, which can't be used as a value:");
+
+    let message = FormattedString::default()
+        .code(name.as_str())
+        .text(" is the name of an imported module, and modules aren't values.");
+
+    Diagnostic::error("Module used as a value")
+        .description(description)
+        .message(message)
+}
+
+fn mismatched_types(expected: Ty, found: Ty) -> Diagnostic<FileId> {
+    let description = FormattedString::default()
+        .text("This expression doesn't have the type I expected:");
+
+    let message = FormattedString::default()
+        .text("I expected a value of type ")
+        .code(expected.to_string())
+        .text(", but this is a value of type ")
+        .code(found.to_string())
+        .text(":")
+        .diff(expected.to_string(), found.to_string());
+
+    Diagnostic::error("Mismatched types")
+        .description(description)
+        .message(message)
+}
+
+fn mismatched_operands(symbol: &str, lhs: Ty, rhs: Ty) -> Diagnostic<FileId> {
+    let description = FormattedString::default()
+        .text("The operands of this operator don't have the same type:");
+
+    let message = FormattedString::default()
+        .text("Both operands of ")
+        .code(symbol)
+        .text(" should have the same type, but they differ:")
+        .diff(lhs.to_string(), rhs.to_string());
+
+    Diagnostic::error("Mismatched operands")
+        .description(description)
+        .message(message)
+}
+
+fn unsupported_operand(
+    symbol: &str,
+    is_logical: bool,
+    operand_count: usize,
+    ty: Ty,
+) -> Diagnostic<FileId> {
+    let description = FormattedString::default()
+        .text("This operator can't be applied to its operands:");
+
+    let message = FormattedString::default().code(symbol);
+    let message = if is_logical {
+        message.text(" can only be applied to a ").code("Bool")
+    } else {
+        message
+            .text(" can only be applied to numbers (")
+            .code("Int")
+            .text(" or ")
+            .code("Float")
+            .text(")")
+    };
+
+    let operands = if operand_count == 1 {
+        ", but its operand is of type "
+    } else {
+        ", but its operands are of type "
+    };
+    let message = message.text(operands).code(ty.to_string()).text(".");
+
+    Diagnostic::error("Unsupported operand")
+        .description(description)
+        .message(message)
+}
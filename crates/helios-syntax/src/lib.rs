@@ -9,6 +9,7 @@ use crate::repr::{Article, HumanReadableRepr};
 
 pub type SyntaxNode = rowan::SyntaxNode<HeliosLanguage>;
 pub type SyntaxToken = rowan::SyntaxToken<HeliosLanguage>;
+pub type SyntaxElement = rowan::SyntaxElement<HeliosLanguage>;
 
 /// A convenient way to construct new `SyntaxNode` symbols.
 ///
@@ -71,6 +72,7 @@ pub enum SyntaxKind {
     Kwd_Case,
     Kwd_Else,
     Kwd_Enum,
+    Kwd_External,
     Kwd_For,
     Kwd_Forall,
     Kwd_Func,
@@ -80,12 +82,14 @@ pub enum SyntaxKind {
     Kwd_In,
     Kwd_Iter,
     Kwd_Let,
+    Kwd_Macro,
     Kwd_Module,
     Kwd_Not,
     Kwd_Of,
     Kwd_Or,
     Kwd_Range,
     Kwd_Record,
+    Kwd_Test,
     Kwd_Type,
     Kwd_Var,
     Kwd_While,
@@ -135,18 +139,24 @@ pub enum SyntaxKind {
     Lit_Character,
     Lit_Float,
     Lit_Integer,
+    Lit_InterpolatedString,
     Lit_String,
 
     Exp_Binary,
     Exp_Indented,
     Exp_Literal,
+    Exp_MacroCall,
     Exp_Paren,
     Exp_UnaryPrefix,
     Exp_UnaryPostfix,
     Exp_VariableRef,
     Exp_Unnamed,
 
+    Dec_External,
     Dec_GlobalBinding,
+    Dec_Import,
+    Dec_Macro,
+    Dec_Test,
 
     Comment,
     DocComment,
@@ -205,7 +215,7 @@ impl SyntaxKind {
 
     #[inline]
     pub fn is_declaration(self) -> bool {
-        self == SyntaxKind::Dec_GlobalBinding
+        self >= SyntaxKind::Dec_External && self <= SyntaxKind::Dec_Test
     }
 
     #[inline]
@@ -243,8 +253,11 @@ impl SyntaxKind {
             | SyntaxKind::Sym_LBracket
             | SyntaxKind::Sym_LParen
             | SyntaxKind::Lit_Integer
+            | SyntaxKind::Lit_InterpolatedString
             | SyntaxKind::Exp_Indented
             | SyntaxKind::Exp_Unnamed
+            | SyntaxKind::Dec_External
+            | SyntaxKind::Dec_Import
             | SyntaxKind::Indent
             | SyntaxKind::Identifier
             | SyntaxKind::UnknownChar
@@ -275,6 +288,7 @@ impl SyntaxKind {
             SyntaxKind::Kwd_Case => "case",
             SyntaxKind::Kwd_Else => "else",
             SyntaxKind::Kwd_Enum => "enum",
+            SyntaxKind::Kwd_External => "external",
             SyntaxKind::Kwd_For => "for",
             SyntaxKind::Kwd_Forall => "forall",
             SyntaxKind::Kwd_Func => "func",
@@ -284,12 +298,14 @@ impl SyntaxKind {
             SyntaxKind::Kwd_In => "in",
             SyntaxKind::Kwd_Iter => "iter",
             SyntaxKind::Kwd_Let => "let",
+            SyntaxKind::Kwd_Macro => "macro",
             SyntaxKind::Kwd_Module => "module",
             SyntaxKind::Kwd_Not => "not",
             SyntaxKind::Kwd_Of => "of",
             SyntaxKind::Kwd_Or => "or",
             SyntaxKind::Kwd_Range => "range",
             SyntaxKind::Kwd_Record => "record",
+            SyntaxKind::Kwd_Test => "test",
             SyntaxKind::Kwd_Type => "type",
             SyntaxKind::Kwd_Var => "var",
             SyntaxKind::Kwd_While => "while",
@@ -334,17 +350,23 @@ impl SyntaxKind {
             SyntaxKind::Lit_Character => "character",
             SyntaxKind::Lit_Float => "float",
             SyntaxKind::Lit_Integer => "integer",
+            SyntaxKind::Lit_InterpolatedString => "interpolated string",
             SyntaxKind::Lit_String => "string",
             // expressions
             SyntaxKind::Exp_Binary => "binary",
             SyntaxKind::Exp_Indented => "indented",
             SyntaxKind::Exp_Literal => "literal",
+            SyntaxKind::Exp_MacroCall => "macro call",
             SyntaxKind::Exp_Paren => "parenthesized",
             SyntaxKind::Exp_UnaryPrefix => "prefixed unary",
             SyntaxKind::Exp_UnaryPostfix => "postfixed unary",
             SyntaxKind::Exp_VariableRef => "variable reference",
             // declarations
+            SyntaxKind::Dec_External => "external function",
             SyntaxKind::Dec_GlobalBinding => "global binding",
+            SyntaxKind::Dec_Import => "import",
+            SyntaxKind::Dec_Macro => "macro",
+            SyntaxKind::Dec_Test => "test",
             // other
             SyntaxKind::DocComment => "documentation",
             SyntaxKind::ReservedIdentifier => "reserved",
@@ -426,6 +448,7 @@ impl SyntaxKind {
             SyntaxKind::Lit_Character => "'a'",
             SyntaxKind::Lit_Float => "123.456",
             SyntaxKind::Lit_Integer => "123",
+            SyntaxKind::Lit_InterpolatedString => r#"f"hello, {name}!""#,
             SyntaxKind::Lit_String => r#""hello, world!""#,
             SyntaxKind::Identifier => "foo",
             _ => return None,
@@ -448,13 +471,65 @@ impl Display for SyntaxKind {
 }
 
 /// An array of all the keywords defined in the Helios grammar.
+///
+/// Some of these are only reserved in certain positions in older editions of
+/// the language (see `helios_parser::Edition`).
 pub const KEYWORDS: &[&str] = &[
-    "and", "as", "case", "else", "enum", "for", "forall", "func", "if", "impl",
-    "import", "in", "iter", "let", "module", "not", "of", "or", "range",
-    "record", "return", "test", "trait", "type", "var", "while", "with",
+    "and", "as", "case", "else", "enum", "external", "for", "forall", "func",
+    "if", "impl", "import", "in", "iter", "let", "macro", "module", "not",
+    "of", "or", "range", "record", "test", "type", "var", "while", "with",
     "yield",
 ];
 
+/// Creates a new keyword variant of [`SyntaxKind`] that corresponds to the
+/// given string, or `None` if it isn't a keyword.
+///
+/// # Examples
+///
+/// ```rust
+/// use helios_syntax::{keyword_from_str, SyntaxKind};
+///
+/// assert_eq!(keyword_from_str("let"), Some(SyntaxKind::Kwd_Let));
+/// assert_eq!(keyword_from_str("forall"), Some(SyntaxKind::Kwd_Forall));
+/// assert_eq!(keyword_from_str("return"), None);
+/// ```
+#[rustfmt::skip]
+pub fn keyword_from_str(s: &str) -> Option<SyntaxKind> {
+    let kind = match s {
+        "and"       => SyntaxKind::Kwd_And,
+        "as"        => SyntaxKind::Kwd_As,
+        "case"      => SyntaxKind::Kwd_Case,
+        "else"      => SyntaxKind::Kwd_Else,
+        "enum"      => SyntaxKind::Kwd_Enum,
+        "external"  => SyntaxKind::Kwd_External,
+        "for"       => SyntaxKind::Kwd_For,
+        "forall"    => SyntaxKind::Kwd_Forall,
+        "func"      => SyntaxKind::Kwd_Func,
+        "if"        => SyntaxKind::Kwd_If,
+        "impl"      => SyntaxKind::Kwd_Impl,
+        "import"    => SyntaxKind::Kwd_Import,
+        "in"        => SyntaxKind::Kwd_In,
+        "iter"      => SyntaxKind::Kwd_Iter,
+        "let"       => SyntaxKind::Kwd_Let,
+        "macro"     => SyntaxKind::Kwd_Macro,
+        "module"    => SyntaxKind::Kwd_Module,
+        "not"       => SyntaxKind::Kwd_Not,
+        "of"        => SyntaxKind::Kwd_Of,
+        "or"        => SyntaxKind::Kwd_Or,
+        "range"     => SyntaxKind::Kwd_Range,
+        "record"    => SyntaxKind::Kwd_Record,
+        "test"      => SyntaxKind::Kwd_Test,
+        "type"      => SyntaxKind::Kwd_Type,
+        "var"       => SyntaxKind::Kwd_Var,
+        "while"     => SyntaxKind::Kwd_While,
+        "with"      => SyntaxKind::Kwd_With,
+        "yield"     => SyntaxKind::Kwd_Yield,
+        _           => return None,
+    };
+
+    Some(kind)
+}
+
 /// Creates a new symbol variant of [`SyntaxKind`] that corresponds to the given
 /// character.
 ///
@@ -588,6 +663,28 @@ mod tests {
         check!([':', '='] => Sym_Walrus);
     }
 
+    #[test]
+    fn test_keywords() {
+        // Every keyword kind is listed exactly once, in the order of the
+        // variants.
+        let kinds = KEYWORDS
+            .iter()
+            .map(|keyword| keyword_from_str(keyword).unwrap())
+            .collect::<Vec<_>>();
+        assert!(kinds.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(kinds.first(), Some(&SyntaxKind::Kwd_And));
+        assert_eq!(kinds.last(), Some(&SyntaxKind::Kwd_Yield));
+        assert_eq!(
+            kinds.len(),
+            SyntaxKind::Kwd_Yield as usize - SyntaxKind::Kwd_And as usize + 1
+        );
+
+        for (keyword, kind) in KEYWORDS.iter().zip(kinds) {
+            assert!(kind.is_keyword());
+            assert_eq!(kind.description().as_deref(), Some(*keyword));
+        }
+    }
+
     #[test]
     fn test_is_trivia() {
         assert!(SyntaxKind::Comment.is_trivia());
@@ -623,9 +720,11 @@ mod tests {
 
         check(Kwd_And, "the and keyword");
         check(Kwd_Enum, "the enum keyword");
+        check(Kwd_External, "the external keyword");
         check(Kwd_Impl, "the impl keyword");
         check(Kwd_Module, "the module keyword");
         check(Kwd_Record, "the record keyword");
+        check(Kwd_Test, "the test keyword");
         check(Kwd_Yield, "the yield keyword");
 
         check(Sym_Ampersand, "an ampersand symbol (`&`)");
@@ -644,6 +743,10 @@ mod tests {
         check(Lit_Character, "a character literal (such as `'a'`)");
         check(Lit_Float, "a float literal (such as `123.456`)");
         check(Lit_Integer, "an integer literal (such as `123`)");
+        check(
+            Lit_InterpolatedString,
+            "an interpolated string literal (such as `f\"hello, {name}!\"`)",
+        );
         check(Lit_String, "a string literal (such as `\"hello, world!\"`)");
 
         check(Exp_Binary, "a binary expression");
@@ -655,7 +758,10 @@ mod tests {
         check(Exp_VariableRef, "a variable reference expression");
         check(Exp_Unnamed, "an expression");
 
+        check(Dec_External, "an external function declaration");
         check(Dec_GlobalBinding, "a global binding declaration");
+        check(Dec_Import, "an import declaration");
+        check(Dec_Test, "a test declaration");
 
         check(Comment, "a comment");
         check(DocComment, "a documentation comment");
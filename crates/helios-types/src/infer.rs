@@ -0,0 +1,588 @@
+use crate::{Scheme, Ty, TyVar};
+use helios_hir::{
+    BinaryOp, Body, Expr, ExprId, External, Item, ItemId, Literal, Module,
+    NameResolutions, UnaryOp,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The types inferred for the items of a module.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InferenceResult {
+    bindings: HashMap<ItemId, Scheme>,
+    exprs: HashMap<(ItemId, ExprId), Ty>,
+    errors: Vec<TypeError>,
+}
+
+impl InferenceResult {
+    /// The type of the global binding (or of the value of the external
+    /// function) declared by the given item.
+    pub fn binding_type(&self, item_id: ItemId) -> Option<&Scheme> {
+        self.bindings.get(&item_id)
+    }
+
+    /// The type of an expression in the body of the given item.
+    pub fn expr_type(&self, item_id: ItemId, expr_id: ExprId) -> Option<Ty> {
+        self.exprs.get(&(item_id, expr_id)).copied()
+    }
+
+    /// Every type error found in the module.
+    pub fn errors(&self) -> &[TypeError] {
+        &self.errors
+    }
+}
+
+/// An error found while inferring the type of an expression in the body of
+/// an item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypeError {
+    /// The type of the expression couldn't be unified with the type it was
+    /// expected to have.
+    Mismatch {
+        item: ItemId,
+        expr: ExprId,
+        expected: Ty,
+        found: Ty,
+    },
+    /// The operands of a binary operator don't have the same type.
+    MismatchedOperands {
+        item: ItemId,
+        expr: ExprId,
+        lhs: Ty,
+        rhs: Ty,
+    },
+    /// An operator is applied to operands of a type it doesn't support (such
+    /// as `!` to an `Int`, or `+` to `Bool`s).
+    UnsupportedOperand { item: ItemId, expr: ExprId, ty: Ty },
+    /// A name refers to an imported module, which isn't a value.
+    ModuleAsValue { item: ItemId, expr: ExprId },
+}
+
+impl TypeError {
+    /// The item the expression the error is about is in.
+    pub fn item(&self) -> ItemId {
+        match self {
+            TypeError::Mismatch { item, .. }
+            | TypeError::MismatchedOperands { item, .. }
+            | TypeError::UnsupportedOperand { item, .. }
+            | TypeError::ModuleAsValue { item, .. } => *item,
+        }
+    }
+
+    /// The expression the error is about.
+    pub fn expr(&self) -> ExprId {
+        match self {
+            TypeError::Mismatch { expr, .. }
+            | TypeError::MismatchedOperands { expr, .. }
+            | TypeError::UnsupportedOperand { expr, .. }
+            | TypeError::ModuleAsValue { expr, .. } => *expr,
+        }
+    }
+}
+
+/// Infers the types of every item of a module, given a function returning the
+/// body of an item along with what the names in it resolve to.
+///
+/// Neither imports nor external functions have a body, so the function is
+/// never called for them. The type of an external function is the one it's
+/// declared with (or `{unknown}` if there is no such type).
+pub fn infer_module<F>(module: &Module, body: F) -> InferenceResult
+where
+    F: FnMut(ItemId) -> (Arc<Body>, Arc<NameResolutions>),
+{
+    infer_module_with_prelude(module, &InferenceResult::default(), body)
+}
+
+/// Infers the types of every item of a module like [`infer_module`], given
+/// the types inferred for the prelude (which names of the prelude are
+/// instantiated from).
+pub fn infer_module_with_prelude<F>(
+    module: &Module,
+    prelude: &InferenceResult,
+    body: F,
+) -> InferenceResult
+where
+    F: FnMut(ItemId) -> (Arc<Body>, Arc<NameResolutions>),
+{
+    let mut ctx = InferenceContext {
+        module,
+        prelude,
+        body,
+        items: HashMap::new(),
+        substitution: Vec::new(),
+        numeric: Vec::new(),
+        result: InferenceResult::default(),
+    };
+
+    for (item_id, item) in module.items.iter() {
+        match item {
+            Item::Import(_) => {}
+            Item::External(external) => {
+                let scheme = Scheme::mono(external_type(external));
+                ctx.result.bindings.insert(item_id, scheme);
+            }
+            _ => ctx.infer_item(item_id),
+        }
+    }
+
+    ctx.finish()
+}
+
+/// The type of the value an external function returns.
+fn external_type(external: &External) -> Ty {
+    external
+        .ty
+        .as_ref()
+        .and_then(|name| Ty::from_name(name.as_str()))
+        .unwrap_or(Ty::Error)
+}
+
+enum ItemState {
+    /// The item is being inferred, so it can only be referred to with the
+    /// given (monomorphic) type.
+    InProgress(Ty),
+    Done(Scheme),
+}
+
+/// The body of the item being inferred.
+struct ItemBody {
+    item_id: ItemId,
+    body: Arc<Body>,
+    resolutions: Arc<NameResolutions>,
+}
+
+struct InferenceContext<'a, F> {
+    module: &'a Module,
+    prelude: &'a InferenceResult,
+    body: F,
+    items: HashMap<ItemId, ItemState>,
+    /// The type each type variable was unified with, if any.
+    substitution: Vec<Option<Ty>>,
+    /// The operator expressions whose operands must be numbers, along with
+    /// the type of their operands (which may not be known until every item is
+    /// inferred).
+    numeric: Vec<(ItemId, ExprId, Ty)>,
+    result: InferenceResult,
+}
+
+impl<F> InferenceContext<'_, F>
+where
+    F: FnMut(ItemId) -> (Arc<Body>, Arc<NameResolutions>),
+{
+    fn infer_item(&mut self, item_id: ItemId) {
+        if self.items.contains_key(&item_id) {
+            return;
+        }
+
+        // Tests pass or fail depending on whether their body is `true`.
+        let ty = match self.module.items[item_id] {
+            Item::Test(_) => Ty::Bool,
+            _ => self.new_var(),
+        };
+        self.items.insert(item_id, ItemState::InProgress(ty));
+
+        let (body, resolutions) = (self.body)(item_id);
+        let item = ItemBody {
+            item_id,
+            body,
+            resolutions,
+        };
+        self.check_expr(&item, item.body.value, ty);
+
+        self.items.remove(&item_id);
+        let scheme = self.generalize(ty);
+        if matches!(self.module.items[item_id], Item::Binding(_)) {
+            self.result.bindings.insert(item_id, scheme.clone());
+        }
+        self.items.insert(item_id, ItemState::Done(scheme));
+    }
+
+    /// The type of a reference to the given item, inferring the item first if
+    /// it hasn't been yet.
+    ///
+    /// Imports bring modules into scope, which aren't values, so a reference
+    /// to one is an error (and of an unknown type).
+    fn item_type(&mut self, item: &ItemBody, expr_id: ExprId) -> Ty {
+        let item_id = item.resolutions.get(expr_id).expect("Resolved name");
+        match &self.module.items[item_id] {
+            Item::Import(_) => {
+                self.result.errors.push(TypeError::ModuleAsValue {
+                    item: item.item_id,
+                    expr: expr_id,
+                });
+                return Ty::Error;
+            }
+            Item::External(external) => return external_type(external),
+            _ => {}
+        }
+
+        self.infer_item(item_id);
+        match &self.items[&item_id] {
+            ItemState::InProgress(ty) => *ty,
+            ItemState::Done(scheme) => {
+                let scheme = scheme.clone();
+                self.instantiate(&scheme)
+            }
+        }
+    }
+
+    /// The type of a reference to the given item of the prelude.
+    fn prelude_type(&mut self, item_id: ItemId) -> Ty {
+        match self.prelude.binding_type(item_id) {
+            Some(scheme) => self.instantiate(scheme),
+            None => Ty::Error,
+        }
+    }
+
+    fn infer_expr(&mut self, item: &ItemBody, expr_id: ExprId) -> Ty {
+        let ty = match &item.body.exprs[expr_id] {
+            Expr::Missing => Ty::Error,
+            Expr::Literal(Literal::Integer(_)) => Ty::Int,
+            Expr::Literal(Literal::Float(_)) => Ty::Float,
+            Expr::Name(_) => match item.resolutions.get(expr_id) {
+                Some(_) => self.item_type(item, expr_id),
+                None => match item.resolutions.prelude(expr_id) {
+                    Some(target) => self.prelude_type(target),
+                    None => Ty::Error,
+                },
+            },
+            Expr::Unary {
+                op: UnaryOp::Neg,
+                operand,
+            } => {
+                let ty = self.infer_expr(item, *operand);
+                self.numeric.push((item.item_id, expr_id, ty));
+                ty
+            }
+            Expr::Unary {
+                op: UnaryOp::Not,
+                operand,
+            } => {
+                let ty = self.infer_expr(item, *operand);
+                if !self.unify(ty, Ty::Bool) {
+                    self.result.errors.push(TypeError::UnsupportedOperand {
+                        item: item.item_id,
+                        expr: expr_id,
+                        ty: self.resolve(ty),
+                    });
+                }
+                Ty::Bool
+            }
+            Expr::Binary { op, lhs, rhs } => {
+                let lhs = self.infer_expr(item, *lhs);
+                let rhs = self.infer_expr(item, *rhs);
+                if !self.unify(lhs, rhs) {
+                    self.result.errors.push(TypeError::MismatchedOperands {
+                        item: item.item_id,
+                        expr: expr_id,
+                        lhs: self.resolve(lhs),
+                        rhs: self.resolve(rhs),
+                    });
+                } else if !matches!(op, BinaryOp::Eq | BinaryOp::NotEq) {
+                    self.numeric.push((item.item_id, expr_id, lhs));
+                }
+
+                if is_comparison(*op) {
+                    Ty::Bool
+                } else {
+                    lhs
+                }
+            }
+            Expr::Assign { target, value } => {
+                let ty = self.infer_expr(item, *target);
+                self.check_expr(item, *value, ty);
+                Ty::Unit
+            }
+            Expr::Sequence(exprs) => {
+                let mut ty = Ty::Unit;
+                for &expr_id in exprs {
+                    ty = self.infer_expr(item, expr_id);
+                }
+                ty
+            }
+        };
+
+        self.result.exprs.insert((item.item_id, expr_id), ty);
+        ty
+    }
+
+    /// Infers the type of an expression and unifies it with the type it's
+    /// expected to have.
+    fn check_expr(&mut self, item: &ItemBody, expr_id: ExprId, expected: Ty) {
+        let found = self.infer_expr(item, expr_id);
+        if !self.unify(expected, found) {
+            self.result.errors.push(TypeError::Mismatch {
+                item: item.item_id,
+                expr: expr_id,
+                expected: self.resolve(expected),
+                found: self.resolve(found),
+            });
+        }
+    }
+
+    fn new_var(&mut self) -> Ty {
+        let var = TyVar(self.substitution.len() as u32);
+        self.substitution.push(None);
+        Ty::Var(var)
+    }
+
+    /// Follows the type variables that were unified with other types, until
+    /// reaching either a type variable that wasn't or another type.
+    fn resolve(&self, mut ty: Ty) -> Ty {
+        while let Ty::Var(var) = ty {
+            match self.substitution[var.0 as usize] {
+                Some(next) => ty = next,
+                None => break,
+            }
+        }
+
+        ty
+    }
+
+    /// Unifies two types, returning whether or not they could be.
+    ///
+    /// Types don't contain other types (yet), so there's no need for an
+    /// occurs check.
+    fn unify(&mut self, a: Ty, b: Ty) -> bool {
+        match (self.resolve(a), self.resolve(b)) {
+            (a, b) if a == b => true,
+            (Ty::Var(var), ty) | (ty, Ty::Var(var)) => {
+                self.substitution[var.0 as usize] = Some(ty);
+                true
+            }
+            (Ty::Error, _) | (_, Ty::Error) => true,
+            _ => false,
+        }
+    }
+
+    /// Generalizes the type variable left in a type (if any), unless it's
+    /// also in the type of an item that is still being inferred (as the item
+    /// may unify it with another type later on).
+    fn generalize(&self, ty: Ty) -> Scheme {
+        let ty = self.resolve(ty);
+        let is_free = |var| {
+            !self.items.values().any(|state| match state {
+                ItemState::InProgress(ty) => self.resolve(*ty) == Ty::Var(var),
+                ItemState::Done(_) => false,
+            })
+        };
+
+        match ty {
+            Ty::Var(var) if is_free(var) => Scheme {
+                vars: vec![var],
+                ty,
+            },
+            _ => Scheme::mono(ty),
+        }
+    }
+
+    /// Replaces the type variables a scheme is generic over with fresh ones.
+    fn instantiate(&mut self, scheme: &Scheme) -> Ty {
+        match scheme.ty {
+            Ty::Var(var) if scheme.vars.contains(&var) => self.new_var(),
+            ty => ty,
+        }
+    }
+
+    /// Resolves the types that were inferred, now that every type variable
+    /// that could be unified with another type has been.
+    ///
+    /// This is also when operands that must be numbers are checked, as their
+    /// types may only have been unified with a number by a later item.
+    fn finish(mut self) -> InferenceResult {
+        let mut result = std::mem::take(&mut self.result);
+        let resolve = |ty: &mut Ty| *ty = self.resolve(*ty);
+
+        result.exprs.values_mut().for_each(resolve);
+        for scheme in result.bindings.values_mut() {
+            resolve(&mut scheme.ty);
+        }
+        for error in &mut result.errors {
+            match error {
+                TypeError::Mismatch {
+                    expected, found, ..
+                } => {
+                    resolve(expected);
+                    resolve(found);
+                }
+                TypeError::MismatchedOperands { lhs, rhs, .. } => {
+                    resolve(lhs);
+                    resolve(rhs);
+                }
+                TypeError::UnsupportedOperand { ty, .. } => resolve(ty),
+                TypeError::ModuleAsValue { .. } => {}
+            }
+        }
+
+        for &(item, expr, ty) in &self.numeric {
+            let ty = self.resolve(ty);
+            if !matches!(ty, Ty::Int | Ty::Float | Ty::Var(_) | Ty::Error) {
+                result.errors.push(TypeError::UnsupportedOperand {
+                    item,
+                    expr,
+                    ty,
+                });
+            }
+        }
+
+        result
+    }
+}
+
+fn is_comparison(op: BinaryOp) -> bool {
+    matches!(
+        op,
+        BinaryOp::Eq
+            | BinaryOp::NotEq
+            | BinaryOp::Lt
+            | BinaryOp::LtEq
+            | BinaryOp::Gt
+            | BinaryOp::GtEq
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helios_hir::{item_nodes, lower_body, lower_module, ModuleScope};
+    use helios_parser::FileId;
+
+    fn infer(source: &str) -> (Module, Vec<Arc<Body>>, InferenceResult) {
+        let root = helios_parser::parse(FileId::default(), source).syntax();
+        let (module, _) = lower_module(&root);
+        let scope = ModuleScope::new(&module);
+        let bodies = item_nodes(&root)
+            .map(|node| Arc::new(lower_body(&node).0))
+            .collect::<Vec<_>>();
+
+        let result = infer_module(&module, |item_id| {
+            let body = Arc::clone(&bodies[item_id.into_raw() as usize]);
+            let resolutions = NameResolutions::new(&scope, &body);
+            (body, Arc::new(resolutions))
+        });
+
+        (module, bodies, result)
+    }
+
+    fn binding_types(module: &Module, result: &InferenceResult) -> Vec<String> {
+        module
+            .bindings()
+            .map(|(item_id, _)| result.binding_type(item_id).unwrap())
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_infer_bindings() {
+        let source = "let b = a < 3\nlet a = -(1 + 2)\nlet c = !b\n";
+        let (module, _, result) = infer(source);
+
+        assert_eq!(binding_types(&module, &result), ["Bool", "Int", "Bool"]);
+        assert!(result.errors().is_empty());
+    }
+
+    #[test]
+    fn test_infer_externals() {
+        let source = "let a = now + 1\nlet b = a < 0\nexternal func now: Int\n\
+            external func unknown: Foo\nlet c = !unknown\n";
+        let (module, _, result) = infer(source);
+
+        assert_eq!(binding_types(&module, &result), ["Int", "Bool", "Bool"]);
+        let external_types = module
+            .externals()
+            .map(|(item_id, _)| result.binding_type(item_id).unwrap())
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(external_types, ["Int", "{unknown}"]);
+        assert!(result.errors().is_empty(), "{:?}", result.errors());
+
+        let (_, _, result) = infer("let a = !now\nexternal func now: Float\n");
+        assert!(matches!(
+            result.errors(),
+            [TypeError::UnsupportedOperand { ty: Ty::Float, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_let_generalization() {
+        // `a` refers to itself, so nothing is known about its type.
+        let source = "let a = a\nlet b = a + 1.5\nlet c = a + 1\n";
+        let (module, _, result) = infer(source);
+
+        let types = binding_types(&module, &result);
+        assert!(types[0].starts_with("forall t"), "{}", types[0]);
+        assert_eq!(types[1..], ["Float", "Int"]);
+        assert!(result.errors().is_empty());
+    }
+
+    #[test]
+    fn test_type_errors() {
+        let source = "let a = 1 + 2.5\nlet b = a + c\nlet d = a <- !a\n\
+            let e = -(1 < 2)\n";
+        let (module, bodies, result) = infer(source);
+
+        // The unresolved name `c` doesn't lead to another error.
+        let types = binding_types(&module, &result);
+        assert_eq!(types, ["Int", "Int", "Unit", "Bool"]);
+
+        let errors = result.errors();
+        assert_eq!(errors.len(), 4);
+        assert!(matches!(
+            errors[0],
+            TypeError::MismatchedOperands {
+                lhs: Ty::Int,
+                rhs: Ty::Float,
+                ..
+            }
+        ));
+        let body = &bodies[errors[0].item().into_raw() as usize];
+        assert!(matches!(
+            body.exprs[errors[0].expr()],
+            Expr::Binary {
+                op: BinaryOp::Add,
+                ..
+            }
+        ));
+
+        // `!a` doesn't support an `Int`, and then assigns a `Bool` to one.
+        assert!(matches!(
+            errors[1],
+            TypeError::UnsupportedOperand { ty: Ty::Int, .. }
+        ));
+        assert!(matches!(
+            errors[2],
+            TypeError::Mismatch {
+                expected: Ty::Int,
+                found: Ty::Bool,
+                ..
+            }
+        ));
+        assert!(matches!(
+            errors[3],
+            TypeError::UnsupportedOperand { ty: Ty::Bool, .. }
+        ));
+    }
+
+    #[test]
+    fn test_numeric_operands() {
+        // The type of `a` isn't known, so it may well be a number.
+        let (_, _, result) = infer("let a = -a\n");
+        assert!(result.errors().is_empty(), "{:?}", result.errors());
+
+        // The type of `a` is only known to be a `Bool` after `-a`.
+        let (_, _, result) = infer("let a = (-a; 1 < 2)\n");
+        assert!(matches!(
+            result.errors(),
+            [TypeError::UnsupportedOperand { ty: Ty::Bool, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_modules_as_values() {
+        let (module, _, result) = infer("import util\nlet a = util + 1\n");
+
+        // The module doesn't lead to mismatched operands.
+        assert_eq!(binding_types(&module, &result), ["{unknown}"]);
+        assert!(matches!(result.errors(), [TypeError::ModuleAsValue { .. }]));
+    }
+}
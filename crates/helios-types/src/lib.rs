@@ -0,0 +1,25 @@
+//! Type inference for Helios.
+//!
+//! Types are inferred for the [`Module`](helios_hir::Module) of a file with
+//! the Hindley–Milner algorithm:
+//!
+//! - Every expression is given a type, which starts out as a fresh type
+//!   variable whenever it isn't known yet. Using two expressions together
+//!   (such as adding them) unifies their types, and a [`TypeError`] is
+//!   recorded whenever they can't be unified (or when an operator is applied
+//!   to operands of a type it doesn't support).
+//! - Global bindings are inferred one after the other, in the order they are
+//!   referred to (regardless of the order they're declared in). Once the
+//!   type of a binding is known, the type variables that are left in it are
+//!   generalized into a [`Scheme`], which is instantiated with fresh type
+//!   variables wherever the binding is referred to.
+//!
+//! Imports only bring modules into scope, which aren't values, so every
+//! reference to one is an error (and of the `{unknown}` type, which doesn't
+//! lead to further errors).
+
+mod infer;
+mod ty;
+
+pub use crate::infer::*;
+pub use crate::ty::*;
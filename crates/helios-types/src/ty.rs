@@ -0,0 +1,78 @@
+use std::fmt::{self, Display};
+
+/// A type variable, standing for a type that isn't known (yet).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TyVar(pub u32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Ty {
+    Int,
+    Float,
+    Bool,
+    /// The type of expressions evaluated for their effects only (such as
+    /// assignments).
+    Unit,
+    Var(TyVar),
+    /// The type of expressions that are missing or that refer to names that
+    /// can't be resolved, which unifies with every type so that a single
+    /// error doesn't lead to many others.
+    Error,
+}
+
+impl Ty {
+    /// The type with the given name, if there is one that can be named (such
+    /// as in the declaration of an external function).
+    pub fn from_name(name: &str) -> Option<Ty> {
+        match name {
+            "Int" => Some(Ty::Int),
+            "Float" => Some(Ty::Float),
+            "Bool" => Some(Ty::Bool),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ty::Int => write!(f, "Int"),
+            Ty::Float => write!(f, "Float"),
+            Ty::Bool => write!(f, "Bool"),
+            Ty::Unit => write!(f, "Unit"),
+            Ty::Var(var) => write!(f, "t{}", var.0),
+            Ty::Error => write!(f, "{{unknown}}"),
+        }
+    }
+}
+
+/// A type that may be generic over some type variables (such as `forall t0.
+/// t0`), as given to global bindings.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Scheme {
+    pub vars: Vec<TyVar>,
+    pub ty: Ty,
+}
+
+impl Scheme {
+    /// A scheme that isn't generic over any type variable.
+    pub fn mono(ty: Ty) -> Self {
+        Self {
+            vars: Vec::new(),
+            ty,
+        }
+    }
+}
+
+impl Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.vars.is_empty() {
+            write!(f, "forall")?;
+            for var in &self.vars {
+                write!(f, " {}", Ty::Var(*var))?;
+            }
+            write!(f, ". ")?;
+        }
+
+        write!(f, "{}", self.ty)
+    }
+}
@@ -1,99 +1,676 @@
+use crate::cache::BuildCache;
+use crate::diagnostics::{DiagnosticsConfig, DiagnosticsOpts};
+use crate::target::{Profile, TargetDir};
+use crate::timings::Analyzer;
+use crate::verbosity::Verbosity;
 use colored::*;
-use helios_diagnostics::{Diagnostic, ManyFiles, Severity};
+use helios_diagnostics::{FileRegistry, Severity};
+use helios_project::Project;
+use helios_query::{
+    Consts, FileId, FileOrigin, HeliosDatabase, Hir, Input, Scopes, SourceRoot,
+    SourceRoots, Types,
+};
 use std::fmt::Display;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 
 /// Compiling support for Helios files
 #[derive(clap::Parser)]
 pub struct HeliosBuildOpts {
-    /// The entry point file for the program to be built
-    pub file: String,
+    /// The files of the program to be built, the first of which is its entry
+    /// point (by default, the project containing the current directory, as
+    /// declared in its `helios.toml`). Globs (such as `src/*.helios`) are
+    /// expanded, and `-` is read from the standard input
+    pub files: Vec<String>,
+    /// Prints how often every query was executed and how long it took
+    #[clap(long)]
+    pub query_stats: bool,
+    /// Analyzes every file again, instead of reusing the diagnostics cached
+    /// (in `target/helios-cache`) for the files that haven't changed since the
+    /// previous build
+    #[clap(long)]
+    pub no_cache: bool,
+    /// Prints how long every phase of the analysis took (lexing, parsing,
+    /// resolving names and inferring types), and writes it as JSON to the
+    /// given file (if any)
+    #[clap(long, value_name = "FILE", require_equals = true)]
+    pub timings: Option<Option<PathBuf>>,
+    /// Compiles the program to an object file (`obj`) or to an executable
+    /// (`bin`) for the host
+    #[clap(long, value_enum)]
+    pub emit: Option<Emit>,
+    /// Compiles the program for the given target (`wasm32` emits a `.wasm`
+    /// module along with `helios.js`, which runs it)
+    #[clap(long, value_enum, default_value = "native")]
+    pub target: Target,
+    /// Compiles the program with optimizations, in the `release` profile
+    /// (rather than the `debug` one)
+    #[clap(long)]
+    pub release: bool,
+    /// Where to write the compiled program (by default, a file named after the
+    /// project, or else after the entry point file, in the directory of the
+    /// target and profile in `target`, such as `target/debug`)
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+    /// Applies the fixes of the reported diagnostics that can't change what
+    /// the program does (such as removing unused imports) to the files before
+    /// building them, like `helios fix`, so that only the remaining
+    /// diagnostics are reported
+    #[clap(long)]
+    pub fix: bool,
+    #[clap(flatten)]
+    pub diagnostics: DiagnosticsOpts,
 }
 
-type Result<T> = std::result::Result<T, Error>;
+/// What a program can be compiled for.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum Target {
+    Native,
+    Wasm32,
+}
+
+/// What a program can be compiled to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum Emit {
+    Obj,
+    Bin,
+}
+
+/// The path standing for the standard input on the command line.
+pub(crate) const STDIN_PATH: &str = "-";
+
+/// The name of the file read from the standard input, as shown in
+/// diagnostics.
+pub(crate) const STDIN_NAME: &str = "<stdin>";
+
+pub(crate) type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
-enum Error {
-    BuildError(usize),
-    IoError(String),
+pub(crate) enum Error {
+    Build(usize),
+    Codegen(String),
+    Input(String),
+    Io(String),
+    Project(String),
+    Runtime,
+    TestFailures(usize),
 }
 
 impl std::error::Error for Error {}
 
 impl From<std::io::Error> for Error {
     fn from(error: std::io::Error) -> Self {
-        Self::IoError(error.to_string())
+        Self::Io(error.to_string())
+    }
+}
+
+impl From<helios_project::Error> for Error {
+    fn from(error: helios_project::Error) -> Self {
+        Self::Project(error.to_string())
+    }
+}
+
+impl From<helios_codegen::CodegenError> for Error {
+    fn from(error: helios_codegen::CodegenError) -> Self {
+        Self::Codegen(error.to_string())
     }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::BuildError(count) => {
+            Self::Build(count) => {
                 let suffix = if *count == 1 { "" } else { "s" };
                 write!(
                     f,
                     "Failed to build due to {count} previous error{suffix}"
                 )
             }
-            Self::IoError(error) => {
+            Self::Codegen(error) => write!(f, "{error}"),
+            Self::Input(error) => {
+                write!(f, "Failed to read the input files: {error}")
+            }
+            Self::Io(error) => {
                 write!(f, "Failed to build due to an IO error: {error}")
             }
+            Self::Project(error) => {
+                write!(f, "Failed to build the project: {error}")
+            }
+            Self::Runtime => {
+                write!(f, "Failed to run due to the previous error")
+            }
+            Self::TestFailures(count) => {
+                let suffix = if *count == 1 { "" } else { "s" };
+                write!(f, "Failed due to {count} failing test{suffix}")
+            }
         }
     }
 }
 
-fn __build(path: &str) -> Result<()> {
-    let source = std::fs::read_to_string(path)?;
-    let mut stdout = std::io::stdout();
-    let mut files = ManyFiles::new();
+/// What is being built.
+pub(crate) enum Program {
+    /// The files given on the command line (where [`STDIN_PATH`] stands for
+    /// the standard input), the first of which is the entry point.
+    Files(Vec<String>),
+    /// The project containing the current directory.
+    Project(Project),
+}
 
-    let file_id = files.add(path, source);
-    let file = files.get(file_id).unwrap();
+impl Program {
+    /// Finds the program to build: the given files (once their globs are
+    /// expanded), or else the project containing the current directory.
+    pub(crate) fn new(files: &[String]) -> Result<Self> {
+        if !files.is_empty() {
+            return expand_inputs(files).map(Self::Files);
+        }
 
-    let parse = helios_parser::parse(file_id, file.source());
-    println!("{}", parse.debug_tree().cyan());
+        let cwd = std::env::current_dir()?;
+        match Project::discover(&cwd)? {
+            Some(project) => Ok(Self::Project(project)),
+            None => Err(Error::Project(no_project_error(&cwd))),
+        }
+    }
 
-    let mut emitted_ranges = Vec::new();
-    let mut severities = Vec::new();
+    /// The name of the program: the name of the project, or else of its
+    /// entry point file (`stdin` for the standard input).
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Self::Files(paths) if paths[0] == STDIN_PATH => "stdin",
+            Self::Files(paths) => Path::new(&paths[0])
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("main"),
+            Self::Project(project) => project.name(),
+        }
+    }
 
-    for message in parse.messages() {
-        let diagnostic = Diagnostic::from(message);
-        severities.push(diagnostic.severity);
+    /// The directory build artifacts are written to: `target` in the root of
+    /// the project, or else in the current directory.
+    pub(crate) fn target_dir(&self) -> TargetDir {
+        match self {
+            Self::Files(_) => TargetDir::new("target"),
+            Self::Project(project) => {
+                TargetDir::new(project.root().join("target"))
+            }
+        }
+    }
+
+    /// The name of the module defined by the file at the given path, which is
+    /// the path of the file relative to the source directory of the project
+    /// (or else the name of the file) without its extension, such as
+    /// `util::math` for `src/util/math.helios`.
+    pub(crate) fn module_name(&self, path: &Path) -> String {
+        let path = match self {
+            Self::Files(_) => Path::new(path.file_name().unwrap_or_default()),
+            Self::Project(project) => {
+                let source_dir = project.source_dir();
+                path.strip_prefix(source_dir).unwrap_or(path)
+            }
+        };
 
-        if !(emitted_ranges.contains(&diagnostic.location)) {
-            emitted_ranges.push(diagnostic.location.clone());
-            helios_diagnostics::emit(&mut stdout, &files, &diagnostic)
-                .expect("Failed to print diagnostic");
+        path.with_extension("")
+            .iter()
+            .map(|component| component.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("::")
+    }
+
+    /// Loads the files of the program into the database, returning its entry
+    /// point.
+    ///
+    /// Every Helios file in the source directory of a project is part of the
+    /// program, so that its modules can import each other.
+    pub(crate) fn load(&self, db: &mut HeliosDatabase) -> Result<FileId> {
+        match self {
+            Self::Files(paths) => {
+                let mut registry = FileRegistry::new();
+                let mut files = Vec::with_capacity(paths.len());
+                for path in paths {
+                    // A file given more than once is only loaded once.
+                    let file_id = registry.intern(path);
+                    if files.contains(&file_id) {
+                        continue;
+                    }
+
+                    let (name, source) = read_input(path)?;
+                    db.set_file_name(file_id, Arc::new(name.to_string()));
+                    db.set_source(file_id, Arc::new(source));
+                    files.push(file_id);
+                }
+
+                let entry_point = files[0];
+                db.set_workspace_files(Arc::new(files));
+                Ok(entry_point)
+            }
+            Self::Project(project) => {
+                db.set_edition(project.edition());
+                let mut roots = vec![SourceRoot::new(
+                    project.source_dir(),
+                    FileOrigin::Workspace,
+                )];
+                for package in project.packages()? {
+                    roots.push(
+                        SourceRoot::new(
+                            package.source_dir(),
+                            FileOrigin::Dependency,
+                        )
+                        .with_package(package.module_name()),
+                    );
+                }
+
+                let mut files = Vec::new();
+                for root in roots {
+                    files.extend(root.find_files());
+                    db.add_source_root(root);
+                }
+
+                let mut registry = FileRegistry::new();
+                for path in &files {
+                    let source = std::fs::read_to_string(path)?;
+                    db.set_file_at_path(registry.intern(path), path, source);
+                }
+
+                let entry_point = project.entry_point();
+                db.file_at_path(entry_point.clone()).ok_or_else(|| {
+                    Error::Project(format!(
+                        "the entry point `{}` doesn't exist",
+                        entry_point.display()
+                    ))
+                })
+            }
         }
     }
+}
 
-    // An empty vector (i.e. no messages to report) or a vector of severities
-    // lower in importance than error is okay
-    let is_ok = {
-        severities.is_empty()
-            || severities
-                .iter()
-                .any(|severity| *severity < Severity::Error)
+impl Display for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Files(paths) => {
+                let names = paths
+                    .iter()
+                    .map(|path| match path.as_str() {
+                        STDIN_PATH => STDIN_NAME,
+                        path => path,
+                    })
+                    .collect::<Vec<_>>();
+                write!(f, "{}", names.join(", "))
+            }
+            Self::Project(project) => write!(
+                f,
+                "{} v{} ({})",
+                project.name(),
+                project.version(),
+                project.root().display()
+            ),
+        }
+    }
+}
+
+/// The error reported when there isn't a project containing the given
+/// directory.
+pub(crate) fn no_project_error(dir: &Path) -> String {
+    format!(
+        "could not find `{}` in `{}` or any of its parents",
+        helios_project::MANIFEST_NAME,
+        dir.display()
+    )
+}
+
+/// Expands the globs among the given paths (such as `src/*.helios`) into the
+/// files they match, in alphabetical order, leaving out the files given more
+/// than once.
+pub(crate) fn expand_inputs(inputs: &[String]) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    for input in inputs {
+        if input == STDIN_PATH || !input.contains(['*', '?', '[']) {
+            paths.push(input.clone());
+            continue;
+        }
+
+        let matches = glob::glob(input)
+            .map_err(|error| {
+                Error::Input(format!("invalid glob `{input}`: {error}"))
+            })?
+            .map(|path| {
+                let path = path.map_err(|error| {
+                    Error::Input(format!("could not read {error}"))
+                })?;
+                Ok(path.to_string_lossy().into_owned())
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if matches.is_empty() {
+            return Err(Error::Input(format!("no files match `{input}`")));
+        }
+        paths.extend(matches);
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    paths.retain(|path| seen.insert(path.clone()));
+    Ok(paths)
+}
+
+/// Reads the file at the given path (or the standard input, for
+/// [`STDIN_PATH`]), returning its name along with its source.
+fn read_input(path: &str) -> Result<(&str, String)> {
+    if path == STDIN_PATH {
+        let mut source = String::new();
+        std::io::stdin().read_to_string(&mut source)?;
+        return Ok((STDIN_NAME, source));
+    }
+
+    let source = std::fs::read_to_string(path).map_err(|error| {
+        Error::Input(format!("could not read `{path}`: {error}"))
+    })?;
+    Ok((path, source))
+}
+
+/// Analyzes every file of the program and prints their diagnostics (along
+/// with the diagnostics of its entry point, if it needs one), failing if any
+/// of them are errors.
+///
+/// The diagnostics of the files that haven't changed since they were cached
+/// are reused from the given cache (if any), without analyzing them again.
+/// The other files are analyzed by the given analyzer. Diagnostics are
+/// reported as the given configuration says (so warnings may fail the build
+/// too).
+pub(crate) fn check(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    needs_entry_point: bool,
+    cache: Option<&mut BuildCache>,
+    analyzer: &mut Analyzer,
+    config: &DiagnosticsConfig,
+    verbosity: Verbosity,
+) -> Result<()> {
+    let files = db.workspace_files();
+    let diagnostics = match cache {
+        Some(cache) => cache.analyze(db, &files, analyzer),
+        None => analyzer.analyze(db, &files),
+    };
+
+    let mut diagnostics = diagnostics
+        .iter()
+        .flat_map(|diagnostics| diagnostics.iter().cloned())
+        .collect::<Vec<_>>();
+
+    if needs_entry_point {
+        diagnostics.extend(db.entry_point_diagnostics(file_id).iter().cloned());
+    }
+
+    let diagnostics = diagnostics
+        .into_iter()
+        .filter_map(|diagnostic| config.apply(diagnostic))
+        .collect::<Vec<_>>();
+
+    let shown = diagnostics
+        .iter()
+        .filter(|diagnostic| verbosity.shows(diagnostic.severity))
+        .cloned()
+        .collect::<Vec<_>>();
+    helios_diagnostics::emit_all(&mut std::io::stdout(), db, &shown)
+        .expect("Failed to print diagnostics");
+
+    // Only errors stop the build (warnings and notes are just reported)
+    let error_count = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity >= Severity::Error)
+        .count();
+
+    match error_count {
+        0 => Ok(()),
+        _ => Err(Error::Build(error_count)),
+    }
+}
+
+/// Lists the size and shape of the syntax tree of every file of the workspace
+/// (see [`helios_parser::Parse::stats`]), one file per line.
+fn syntax_stats(db: &HeliosDatabase) -> String {
+    let files = db
+        .workspace_files()
+        .iter()
+        .map(|&file_id| (db.file_name(file_id), db.parse(file_id).stats()))
+        .collect::<Vec<_>>();
+    let width = files
+        .iter()
+        .map(|(name, _)| name.len())
+        .chain(Some("file".len()))
+        .max()
+        .unwrap_or_default();
+
+    let mut table =
+        format!("{:width$}  nodes  tokens  depth  errors\n", "file");
+    for (name, stats) in files {
+        table.push_str(&format!(
+            "{:width$}  {:>5}  {:>6}  {:>5}  {:>6}\n",
+            name, stats.nodes, stats.tokens, stats.max_depth, stats.error_nodes,
+        ));
+    }
+
+    table
+}
+
+/// Builds the program, returning where what it was compiled to was written
+/// to (if it was compiled).
+fn __build(
+    opts: &HeliosBuildOpts,
+    program: &Program,
+    verbosity: Verbosity,
+) -> Result<Option<PathBuf>> {
+    let mut db = HeliosDatabase::default();
+    db.enable_syntax_cache();
+    if opts.query_stats || verbosity.is_verbose() {
+        db.enable_query_stats();
+    }
+
+    let file_id = program.load(&mut db)?;
+    if !verbosity.is_quiet() {
+        println!("{}", db.parse(file_id).debug_tree().cyan());
+    }
+
+    // Only executables need to know where the program starts.
+    let needs_entry_point =
+        opts.emit == Some(Emit::Bin) || opts.target == Target::Wasm32;
+    let cache_dir = program.target_dir().cache_dir();
+    let mut cache = (!opts.no_cache).then(|| BuildCache::load(&cache_dir));
+    let config = DiagnosticsConfig::new(&opts.diagnostics);
+    if opts.fix {
+        let fixed = crate::fix::apply_fixes(&mut db, &config);
+        crate::fix::write_fixes(&db, &fixed, verbosity)?;
+    }
+
+    let mut analyzer = Analyzer::new(
+        opts.timings.is_some() || verbosity.is_verbose(),
+        verbosity,
+    );
+    let result = check(
+        &db,
+        file_id,
+        needs_entry_point,
+        cache.as_mut(),
+        &mut analyzer,
+        &config,
+        verbosity,
+    );
+    if let Some(cache) = &cache {
+        cache.save(&cache_dir)?;
+    }
+
+    if let Some(stats) = db.query_stats() {
+        println!("\n{}\n{}", "Query statistics".bold(), stats);
+    }
+
+    if verbosity.is_verbose() {
+        println!("\n{}\n{}", "Syntax trees".bold(), syntax_stats(&db));
+    }
+
+    if let Some(timings) = analyzer.timings() {
+        println!("\n{}\n{}", "Timings".bold(), timings);
+        if let Some(Some(path)) = &opts.timings {
+            std::fs::write(path, timings.to_json())?;
+        }
+    }
+
+    result?;
+
+    let target_dir = program.target_dir();
+    let dir = target_dir.artifact_dir(opts.target, Profile::new(opts.release));
+    let output = match (opts.target, opts.emit) {
+        (Target::Native, Some(emit)) => {
+            Some(compile(&db, file_id, program.name(), emit, &dir, opts)?)
+        }
+        (Target::Native, None) => None,
+        (Target::Wasm32, _) => {
+            Some(compile_wasm(&db, file_id, program.name(), &dir, opts)?)
+        }
     };
 
-    let message_count = emitted_ranges.len();
+    Ok(output)
+}
+
+/// Compiles the given file to what should be emitted, returning where it was
+/// written to (by default, in the given artifact directory).
+fn compile(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    name: &str,
+    emit: Emit,
+    dir: &Path,
+    opts: &HeliosBuildOpts,
+) -> Result<PathBuf> {
+    let module = db.hir_module(file_id);
+    let types = db.infer(file_id);
+    let consts = db.const_values(file_id);
+    let object = helios_codegen::compile_module(
+        name,
+        Profile::new(opts.release).opt_level(),
+        &module,
+        &types,
+        &consts,
+        |item_id| {
+            let body = db.body(file_id, item_id);
+            (body, db.name_resolutions(file_id, item_id))
+        },
+    )?;
+
+    // Executables are linked from an object file, which is kept next to them.
+    std::fs::create_dir_all(dir)?;
+    let object_path = match (emit, &opts.output) {
+        (Emit::Obj, Some(output)) => output.clone(),
+        _ => dir.join(format!("{name}.o")),
+    };
+    std::fs::write(&object_path, object)?;
 
-    if is_ok {
-        Ok(())
-    } else {
-        Err(Error::BuildError(message_count))
+    match emit {
+        Emit::Obj => Ok(object_path),
+        Emit::Bin => {
+            let output = opts.output.clone().unwrap_or_else(|| dir.join(name));
+            helios_codegen::link(&object_path, &output)?;
+            Ok(output)
+        }
     }
 }
 
-/// Starts the build process with the given path to a file.
-pub fn build(path: &str) {
-    println!("\n{} {}\n", "Building".green().bold(), path.underline());
+/// Compiles the given file to a WebAssembly module, and writes the runtime
+/// running it next to it, returning where the module was written to (by
+/// default, in the given artifact directory).
+fn compile_wasm(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    name: &str,
+    dir: &Path,
+    opts: &HeliosBuildOpts,
+) -> Result<PathBuf> {
+    let module = db.hir_module(file_id);
+    let types = db.infer(file_id);
+    let consts = db.const_values(file_id);
+    let wasm = helios_codegen::compile_wasm_module(
+        &module,
+        &types,
+        &consts,
+        |item_id| {
+            let body = db.body(file_id, item_id);
+            (body, db.name_resolutions(file_id, item_id))
+        },
+    )?;
+
+    std::fs::create_dir_all(dir)?;
+    let default = dir.join(format!("{name}.wasm"));
+    let output = opts.output.clone().unwrap_or(default);
+    std::fs::write(&output, wasm)?;
+
+    let runtime = output.with_file_name("helios.js");
+    std::fs::write(runtime, helios_codegen::WASM_RUNTIME)?;
 
-    if let Err(error) = __build(path) {
-        let error = format!("{}", error).red().bold();
-        eprintln!("{}", error);
-        std::process::exit(1);
+    Ok(output)
+}
+
+/// Starts the build process with the given options.
+pub fn build(opts: &HeliosBuildOpts, verbosity: Verbosity) {
+    let start = Instant::now();
+    let result = Program::new(&opts.files).and_then(|program| {
+        verbosity.banner("Building", &program);
+        __build(opts, &program, verbosity)
+    });
+
+    match result {
+        Ok(Some(output)) => {
+            let message = format!("Finished building: {}", output.display());
+            verbosity.finished(message, start);
+        }
+        Ok(None) => verbosity.finished("Finished building", start),
+        Err(error) => {
+            let error = format!("{}", error).red().bold();
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_inputs() {
+        let dir = std::env::temp_dir()
+            .join(format!("helios-inputs-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["b.helios", "a.helios", "c.txt"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        let inputs = [path("*.helios"), "-".to_string(), path("b.helios")];
+        let expanded = expand_inputs(&inputs);
+        let unmatched = expand_inputs(&[path("*.rs")]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Files given more than once are only kept the first time.
+        assert_eq!(
+            expanded.unwrap(),
+            [path("a.helios"), path("b.helios"), "-".to_string()]
+        );
+        assert!(matches!(unmatched, Err(Error::Input(_))));
     }
 
-    println!("{}", "Finished building".green().bold());
+    #[test]
+    fn test_syntax_stats() {
+        let mut db = HeliosDatabase::default();
+        db.set_file(
+            FileId(0),
+            "main.helios".to_string(),
+            "let a = (1\n".to_string(),
+            FileOrigin::Workspace,
+        );
+        db.set_workspace_files(Arc::new(vec![FileId(0)]));
+
+        assert_eq!(
+            syntax_stats(&db),
+            "file         nodes  tokens  depth  errors\n\
+             main.helios      4       9      4       0\n"
+        );
+    }
 }
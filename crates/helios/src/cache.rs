@@ -0,0 +1,306 @@
+use crate::timings::Analyzer;
+use helios_diagnostics::Diagnostic;
+use helios_query::{FileId, HeliosDatabase, Input, Modules, SourceRoots};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The file the cache is stored in, inside the cache directory.
+const CACHE_FILE: &str = "diagnostics.json";
+
+/// The diagnostics of the files of a program from previous builds, which are
+/// reused for the files that haven't changed since (along with the files they
+/// import, directly or indirectly).
+///
+/// Files are identified by their paths and their contents by their hashes, so
+/// the cache can be stored on disk between builds.
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct BuildCache {
+    /// The version of Helios that wrote the cache, since other versions may
+    /// report different diagnostics for the same files.
+    version: String,
+    files: BTreeMap<String, CachedFile>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct CachedFile {
+    /// The hash of the source of the file.
+    hash: u64,
+    /// The hash of the paths of every file of the workspace and of its
+    /// dependencies, since adding or removing files changes which files
+    /// imports resolve to, along with the edition they're parsed in.
+    workspace: u64,
+    /// The hashes of the sources of the files the file imports, directly or
+    /// indirectly.
+    dependencies: BTreeMap<String, u64>,
+    /// Whether other files import the file, since the bindings of imported
+    /// files aren't reported as unused.
+    imported: bool,
+    diagnostics: Vec<Diagnostic<String>>,
+}
+
+impl BuildCache {
+    /// Loads the cache stored in the given directory.
+    ///
+    /// The cache is empty if there isn't one yet, or if it can't be read (such
+    /// as if it was written by another version of Helios).
+    pub(crate) fn load(dir: &Path) -> Self {
+        let cache = std::fs::read_to_string(dir.join(CACHE_FILE))
+            .ok()
+            .and_then(|cache| serde_json::from_str::<Self>(&cache).ok())
+            .filter(|cache| cache.version == env!("CARGO_PKG_VERSION"));
+
+        cache.unwrap_or_else(|| Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            files: BTreeMap::new(),
+        })
+    }
+
+    /// Stores the cache in the given directory (creating it if needed).
+    pub(crate) fn save(&self, dir: &Path) -> std::io::Result<()> {
+        let cache = serde_json::to_string(self)
+            .expect("The build cache can always be serialized");
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join(CACHE_FILE), cache)
+    }
+
+    /// Computes the diagnostics of the given files in parallel, returning them
+    /// in the same order as the files.
+    ///
+    /// The cached diagnostics of the files that haven't changed are reused,
+    /// and the diagnostics of the other files replace them in the cache.
+    /// Files that aren't part of the workspace anymore are forgotten.
+    pub(crate) fn analyze(
+        &mut self,
+        db: &HeliosDatabase,
+        files: &[FileId],
+        analyzer: &mut Analyzer,
+    ) -> Vec<Arc<Vec<Diagnostic<FileId>>>> {
+        // Files can import the files of dependencies as well.
+        let known_files = db
+            .workspace_files()
+            .iter()
+            .chain(db.dependency_files().iter())
+            .copied()
+            .collect::<Vec<_>>();
+        let paths = known_files
+            .iter()
+            .map(|&file_id| (db.file_name(file_id).to_string(), file_id))
+            .collect::<HashMap<_, _>>();
+        let hashes = known_files
+            .iter()
+            .map(|&file_id| (file_id, hash(db.source(file_id).as_bytes())))
+            .collect::<HashMap<_, _>>();
+
+        let mut workspace = known_files
+            .iter()
+            .map(|&file_id| db.file_name(file_id).to_string())
+            .collect::<Vec<_>>();
+        workspace.sort();
+        workspace.push(format!("edition {}", db.edition()));
+        let workspace = hash(workspace.join("\n").as_bytes());
+
+        let mut results = files
+            .iter()
+            .map(|&file_id| {
+                let path = db.file_name(file_id);
+                let cached = self.files.get(path.as_str())?;
+                let is_fresh = cached.hash == hashes[&file_id]
+                    && cached.workspace == workspace
+                    && cached.imported == is_imported(db, file_id)
+                    && cached.dependencies.iter().all(|(path, &hash)| {
+                        paths.get(path).map(|file_id| hashes[file_id])
+                            == Some(hash)
+                    });
+
+                if !is_fresh {
+                    return None;
+                }
+
+                cached
+                    .diagnostics
+                    .iter()
+                    .map(|diagnostic| {
+                        let diagnostic = diagnostic.clone();
+                        let mut is_known = true;
+                        let diagnostic = diagnostic.map_files(|path| {
+                            paths.get(&path).copied().unwrap_or_else(|| {
+                                is_known = false;
+                                FileId::default()
+                            })
+                        });
+                        is_known.then_some(diagnostic)
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .map(Arc::new)
+            })
+            .collect::<Vec<_>>();
+
+        let stale = files
+            .iter()
+            .zip(&results)
+            .filter(|(_, result)| result.is_none())
+            .map(|(&file_id, _)| file_id)
+            .collect::<Vec<_>>();
+        log::info!(
+            "Reusing the cached diagnostics of {} out of {} files",
+            files.len() - stale.len(),
+            files.len()
+        );
+
+        analyzer.reuse(files.len() - stale.len());
+        let mut analyzed = analyzer.analyze(db, &stale).into_iter();
+
+        self.files.retain(|path, _| paths.contains_key(path));
+        for (&file_id, result) in files.iter().zip(&mut results) {
+            if result.is_some() {
+                continue;
+            }
+
+            let diagnostics = analyzed.next().unwrap();
+            let dependencies = transitive_dependencies(db, file_id)
+                .into_iter()
+                .map(|file_id| {
+                    (db.file_name(file_id).to_string(), hashes[&file_id])
+                })
+                .collect();
+
+            let cached = CachedFile {
+                hash: hashes[&file_id],
+                workspace,
+                dependencies,
+                imported: is_imported(db, file_id),
+                diagnostics: diagnostics
+                    .iter()
+                    .map(|diagnostic| {
+                        diagnostic.clone().map_files(|file_id| {
+                            db.file_name(file_id).to_string()
+                        })
+                    })
+                    .collect(),
+            };
+
+            self.files.insert(db.file_name(file_id).to_string(), cached);
+            *result = Some(diagnostics);
+        }
+
+        results.into_iter().map(Option::unwrap).collect()
+    }
+}
+
+/// Whether other files of the workspace import the given file.
+fn is_imported(db: &HeliosDatabase, file_id: FileId) -> bool {
+    !db.reverse_dependencies(file_id).is_empty()
+}
+
+/// The files a file imports, directly or indirectly.
+fn transitive_dependencies(
+    db: &HeliosDatabase,
+    file_id: FileId,
+) -> Vec<FileId> {
+    let mut dependencies = Vec::new();
+    let mut stack = vec![file_id];
+    while let Some(file_id) = stack.pop() {
+        for &dependency in db.dependencies(file_id).iter() {
+            if !dependencies.contains(&dependency) {
+                dependencies.push(dependency);
+                stack.push(dependency);
+            }
+        }
+    }
+
+    dependencies
+}
+
+/// Hashes bytes with FNV-1a, whose hashes (unlike the ones of the standard
+/// library's hasher) are the same across versions of Rust.
+fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verbosity::Verbosity;
+    use helios_parser::Edition;
+
+    fn database(files: &[(&str, &str)]) -> HeliosDatabase {
+        let mut db = HeliosDatabase::default();
+        for (index, (path, source)) in files.iter().enumerate() {
+            let file_id = FileId(index as u32);
+            db.set_file_name(file_id, Arc::new(path.to_string()));
+            db.set_source(file_id, Arc::new(source.to_string()));
+        }
+
+        let files = (0..files.len() as u32).map(FileId).collect();
+        db.set_workspace_files(Arc::new(files));
+        db
+    }
+
+    fn titles(diagnostics: &[Arc<Vec<Diagnostic<FileId>>>]) -> Vec<Vec<&str>> {
+        diagnostics
+            .iter()
+            .map(|diagnostics| {
+                diagnostics.iter().map(|it| it.title.as_str()).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_build_cache() {
+        let dir = std::env::temp_dir()
+            .join(format!("helios-cache-test-{}", std::process::id()));
+        let files = [FileId(0), FileId(1)];
+        let mut analyzer = Analyzer::new(false, Verbosity::Quiet);
+
+        let db = database(&[
+            ("a.helios", "import b\nlet x = y\n"),
+            ("b.helios", ""),
+        ]);
+        let mut cache = BuildCache::load(&dir);
+        let first = cache.analyze(&db, &files, &mut analyzer);
+        cache.save(&dir).unwrap();
+
+        // Cached diagnostics are reused (even though they no longer match
+        // what the file would report, to make sure they come from the cache).
+        let mut cache = BuildCache::load(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        cache.files.get_mut("a.helios").unwrap().diagnostics.clear();
+        assert_eq!(
+            titles(&cache.analyze(&db, &files, &mut analyzer)),
+            [Vec::<&str>::new(), Vec::new()]
+        );
+
+        // Changing a file invalidates it along with the files importing it.
+        cache.files.get_mut("a.helios").unwrap().diagnostics.clear();
+        let db = database(&[
+            ("a.helios", "import b\nlet x = y\n"),
+            ("b.helios", "let b = 1\n"),
+        ]);
+        let diagnostics = cache.analyze(&db, &files, &mut analyzer);
+        assert!(!first[0].is_empty());
+        assert_eq!(titles(&diagnostics)[0], titles(&first)[0]);
+        assert!(diagnostics[1].is_empty());
+
+        // The bindings of a file are unused once no other file imports it.
+        let db = database(&[
+            ("a.helios", "let x = y\n"),
+            ("b.helios", "let b = 1\n"),
+        ]);
+        let diagnostics = cache.analyze(&db, &files, &mut analyzer);
+        assert_eq!(titles(&diagnostics)[1], ["Unused binding"]);
+
+        // Changing the edition invalidates every file, since it changes which
+        // names are keywords.
+        let mut db =
+            database(&[("a.helios", "let macro = 0\n"), ("b.helios", "")]);
+        cache.analyze(&db, &files, &mut analyzer);
+        cache.files.get_mut("a.helios").unwrap().diagnostics.clear();
+        db.set_edition(Edition::Edition2026);
+        let diagnostics = cache.analyze(&db, &files, &mut analyzer);
+        assert!(!diagnostics[0].is_empty());
+    }
+}
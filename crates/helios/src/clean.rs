@@ -0,0 +1,58 @@
+use crate::build::Program;
+use crate::verbosity::Verbosity;
+use colored::*;
+use helios_project::Project;
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// Removes the build artifacts of the project containing the current
+/// directory
+#[derive(clap::Parser)]
+pub struct HeliosCleanOpts {
+    /// Only removes the documentation of the project (in `target/doc`)
+    #[clap(long)]
+    pub doc: bool,
+}
+
+/// Removes the artifacts the options ask for, returning the directory they
+/// were in, unless there wasn't anything to remove.
+fn __clean(opts: &HeliosCleanOpts) -> Result<Option<PathBuf>, String> {
+    let cwd = std::env::current_dir().map_err(|error| error.to_string())?;
+    let project = Project::discover(&cwd)
+        .map_err(|error| error.to_string())?
+        .ok_or_else(|| crate::build::no_project_error(&cwd))?;
+
+    let program = Program::Project(project);
+    let target_dir = program.target_dir();
+    let dir = match opts.doc {
+        true => target_dir.doc_dir(program.name()),
+        false => target_dir.root().to_path_buf(),
+    };
+
+    if !dir.exists() {
+        return Ok(None);
+    }
+
+    std::fs::remove_dir_all(&dir).map_err(|error| {
+        format!("could not remove `{}`: {error}", dir.display())
+    })?;
+    Ok(Some(dir))
+}
+
+/// Removes the build artifacts of the project containing the current
+/// directory (or only its documentation).
+pub fn clean(opts: &HeliosCleanOpts, verbosity: Verbosity) {
+    let start = Instant::now();
+    match __clean(opts) {
+        Ok(Some(dir)) => {
+            let message = format!("Removed {}", dir.display());
+            verbosity.finished(message, start);
+        }
+        Ok(None) => verbosity.finished("Nothing to clean", start),
+        Err(error) => {
+            let error = format!("Failed to clean the project: {error}");
+            eprintln!("{}", error.red().bold());
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use helios_diagnostics::{Diagnostic, Severity};
+use std::collections::HashMap;
+
+/// The code standing for every warning in `--deny`, `--warn` and `--allow`.
+const WARNINGS: &str = "warnings";
+
+/// Flags changing how diagnostics are reported, given their codes (which are
+/// their titles in kebab-case, such as `unused-binding`)
+#[derive(Clone, Debug, Default, clap::Args)]
+pub struct DiagnosticsOpts {
+    /// Reports the diagnostics with the given code as warnings
+    #[clap(short = 'W', long = "warn", value_name = "CODE")]
+    pub warn: Vec<String>,
+    /// Doesn't report the warnings and notes with the given code (errors are
+    /// always reported)
+    #[clap(short = 'A', long = "allow", value_name = "CODE")]
+    pub allow: Vec<String>,
+    /// Reports the diagnostics with the given code as errors, failing the
+    /// build (`-D warnings` does so for every warning)
+    #[clap(short = 'D', long = "deny", value_name = "CODE")]
+    pub deny: Vec<String>,
+}
+
+/// What is done with a kind of diagnostic, from the most to the least lenient.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+/// How diagnostics are reported, by their codes.
+///
+/// A level given for a code takes precedence over the one given for every
+/// warning, and the strictest level wins if a code is given several.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DiagnosticsConfig {
+    levels: HashMap<String, Level>,
+}
+
+impl DiagnosticsConfig {
+    pub fn new(opts: &DiagnosticsOpts) -> Self {
+        let mut config = Self::default();
+        for (codes, level) in [
+            (&opts.allow, Level::Allow),
+            (&opts.warn, Level::Warn),
+            (&opts.deny, Level::Deny),
+        ] {
+            for code in codes {
+                config.set(code, level);
+            }
+        }
+
+        config
+    }
+
+    /// Sets the level of the diagnostics with the given code (or of every
+    /// warning for `warnings`), unless a stricter one was set already.
+    pub fn set(&mut self, code: &str, level: Level) {
+        let current = self.levels.entry(code.to_lowercase()).or_insert(level);
+        *current = (*current).max(level);
+    }
+
+    /// The diagnostic as it should be reported, or `None` if it shouldn't be.
+    ///
+    /// Errors are always reported as they are.
+    pub fn apply<FileId>(
+        &self,
+        diagnostic: Diagnostic<FileId>,
+    ) -> Option<Diagnostic<FileId>> {
+        if diagnostic.severity >= Severity::Error {
+            return Some(diagnostic);
+        }
+
+        let level = self.levels.get(&diagnostic.code()).or_else(|| {
+            match diagnostic.severity {
+                Severity::Warning => self.levels.get(WARNINGS),
+                _ => None,
+            }
+        });
+
+        let severity = match level {
+            None => diagnostic.severity,
+            Some(Level::Allow) => return None,
+            Some(Level::Warn) => Severity::Warning,
+            Some(Level::Deny) => Severity::Error,
+        };
+
+        Some(Diagnostic {
+            severity,
+            ..diagnostic
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostics_config() {
+        let opts = DiagnosticsOpts {
+            warn: vec!["some-note".to_string()],
+            allow: vec!["unused-import".to_string(), "warnings".to_string()],
+            deny: vec![
+                "Unused-Binding".to_string(),
+                "unused-import".to_string(),
+            ],
+        };
+        let config = DiagnosticsConfig::new(&opts);
+        let severity = |diagnostic: Diagnostic<()>| {
+            config
+                .apply(diagnostic)
+                .map(|diagnostic| diagnostic.severity)
+        };
+
+        // Specific codes win over `warnings`, and stricter levels win.
+        assert_eq!(
+            severity(Diagnostic::warning("Unused binding")),
+            Some(Severity::Error)
+        );
+        assert_eq!(
+            severity(Diagnostic::warning("Unused import")),
+            Some(Severity::Error)
+        );
+        assert_eq!(severity(Diagnostic::warning("Shadowed name")), None);
+        assert_eq!(
+            severity(Diagnostic::note("Some note")),
+            Some(Severity::Warning)
+        );
+        assert_eq!(
+            severity(Diagnostic::note("Other note")),
+            Some(Severity::Note)
+        );
+
+        // Errors can't be allowed.
+        let mut config = DiagnosticsConfig::default();
+        config.set("unresolved-name", Level::Allow);
+        let error = Diagnostic::<()>::error("Unresolved name");
+        assert_eq!(config.apply(error.clone()), Some(error));
+    }
+}
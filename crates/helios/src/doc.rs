@@ -0,0 +1,71 @@
+use crate::build::Program;
+use crate::verbosity::Verbosity;
+use colored::*;
+use helios_query::{FileId, HeliosDatabase, Input, Modules};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Documentation generation for Helios files
+#[derive(clap::Parser)]
+pub struct HeliosDocOpts {
+    /// The entry point file for the program to be documented
+    pub file: String,
+    /// The directory to write the documentation to (by default,
+    /// `target/doc/<name>` in the project containing the current directory,
+    /// or else in the current directory, where the name is the one of the
+    /// project or else of the file)
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+/// Writes the documentation, returning the directory it was written to.
+fn __doc(opts: &HeliosDocOpts) -> std::io::Result<PathBuf> {
+    let path = &opts.file;
+    let source = std::fs::read_to_string(path)?;
+
+    let mut db = HeliosDatabase::default();
+    let file_id = FileId(0);
+    db.set_file_name(file_id, Arc::new(path.to_string()));
+    db.set_source(file_id, Arc::new(source));
+    db.set_workspace_files(Arc::new(vec![file_id]));
+
+    let modules = db
+        .build_order()
+        .iter()
+        .map(|&file_id| helios_doc::module_doc(&db, file_id))
+        .collect::<Vec<_>>();
+
+    // The documentation goes with the project containing the current
+    // directory, if there is one.
+    let program = Program::new(&[])
+        .unwrap_or_else(|_| Program::Files(vec![path.clone()]));
+    let output = match &opts.output {
+        Some(output) => output.clone(),
+        None => program.target_dir().doc_dir(program.name()),
+    };
+
+    helios_doc::write_site(&modules, &output)?;
+    Ok(output)
+}
+
+/// Writes the documentation of the given file (as HTML and JSON) to the
+/// output directory.
+pub fn doc(opts: &HeliosDocOpts, verbosity: Verbosity) {
+    let start = Instant::now();
+    verbosity.banner("Documenting", &opts.file);
+
+    let output = match __doc(opts) {
+        Ok(output) => output,
+        Err(error) => {
+            let error =
+                format!("Failed to document due to an IO error: {error}");
+            eprintln!("{}", error.red().bold());
+            std::process::exit(1);
+        }
+    };
+
+    let index = output.join("index.html");
+    let message = format!("Finished documenting: {}", index.display());
+    verbosity.finished(message, start);
+}
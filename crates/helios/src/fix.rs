@@ -0,0 +1,368 @@
+use crate::build::{Program, Result, STDIN_NAME};
+use crate::diagnostics::{DiagnosticsConfig, DiagnosticsOpts};
+use crate::verbosity::Verbosity;
+use colored::*;
+use helios_query::{Diagnostics, FileId, HeliosDatabase, Input};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// The most times the fixes of a program are applied, in case fixing an issue
+/// keeps causing another one.
+const MAX_PASSES: usize = 10;
+
+/// The number of unchanged lines shown around the changed ones in diffs.
+const CONTEXT_LINES: usize = 3;
+
+/// Applies the fixes of the diagnostics of a program that can't change what
+/// it does (such as removing unused imports)
+#[derive(clap::Parser)]
+pub struct HeliosFixOpts {
+    /// The files of the program to be fixed (by default, the project
+    /// containing the current directory, as declared in its `helios.toml`).
+    /// Globs (such as `src/*.helios`) are expanded
+    pub files: Vec<String>,
+    /// Prints the changes as a diff instead of writing them to the files
+    #[clap(long)]
+    pub dry_run: bool,
+    #[clap(flatten)]
+    pub diagnostics: DiagnosticsOpts,
+}
+
+/// A file of the program that was fixed.
+pub(crate) struct FixedFile {
+    pub(crate) file_id: FileId,
+    /// The source of the file before it was fixed.
+    pub(crate) original: Arc<String>,
+    /// How many fixes were applied to it.
+    pub(crate) count: usize,
+}
+
+/// Applies the machine-applicable fixes of the diagnostics of every file of
+/// the program that are reported (as the given configuration says), updating
+/// their sources in the database, until there aren't any left to apply (since
+/// fixing an issue may reveal another one, such as an import that was only
+/// used by a removed import).
+///
+/// The standard input is left as it is, since it can't be written back to.
+pub(crate) fn apply_fixes(
+    db: &mut HeliosDatabase,
+    config: &DiagnosticsConfig,
+) -> Vec<FixedFile> {
+    let mut fixed: Vec<FixedFile> = Vec::new();
+    for _ in 0..MAX_PASSES {
+        let mut applied = false;
+        for &file_id in db.workspace_files().iter() {
+            if db.file_name(file_id).as_str() == STDIN_NAME {
+                continue;
+            }
+
+            let original = db.source(file_id);
+            let (source, count) = fix_source(db, file_id, config);
+            if count == 0 {
+                continue;
+            }
+
+            db.set_source(file_id, Arc::new(source));
+            applied = true;
+            match fixed.iter_mut().find(|file| file.file_id == file_id) {
+                Some(file) => file.count += count,
+                None => fixed.push(FixedFile {
+                    file_id,
+                    original,
+                    count,
+                }),
+            }
+        }
+
+        if !applied {
+            break;
+        }
+    }
+
+    fixed
+}
+
+/// Applies the machine-applicable fixes of the reported diagnostics of the
+/// given file to its source, returning the fixed source along with how many
+/// fixes were applied.
+fn fix_source(
+    db: &HeliosDatabase,
+    file_id: FileId,
+    config: &DiagnosticsConfig,
+) -> (String, usize) {
+    let mut fixes = db
+        .diagnostics(file_id)
+        .iter()
+        .cloned()
+        .filter_map(|diagnostic| config.apply(diagnostic))
+        .flat_map(|diagnostic| diagnostic.fixes)
+        .filter(|fix| fix.machine_applicable && fix.location.file_id == file_id)
+        .collect::<Vec<_>>();
+    fixes.sort_by_key(|fix| fix.location.range.start);
+
+    // The fixes are applied from the end of the file, so that the ranges of
+    // the others stay the same. A fix overlapping one that was already applied
+    // is left for the next pass, since the text it was meant for has changed.
+    let mut source = db.source(file_id).to_string();
+    let mut end = source.len();
+    let mut count = 0;
+    for fix in fixes.iter().rev() {
+        let range = fix.location.range.clone();
+        if range.end > end {
+            continue;
+        }
+
+        source.replace_range(range.clone(), &fix.replacement);
+        end = range.start;
+        count += 1;
+    }
+
+    (source, count)
+}
+
+/// Writes the fixed files back, printing how many fixes were applied to each
+/// of them (unless in quiet mode).
+pub(crate) fn write_fixes(
+    db: &HeliosDatabase,
+    fixed: &[FixedFile],
+    verbosity: Verbosity,
+) -> Result<()> {
+    for file in fixed {
+        let path = db.file_name(file.file_id);
+        std::fs::write(path.as_str(), db.source(file.file_id).as_str())?;
+        if !verbosity.is_quiet() {
+            let count = file.count;
+            let suffix = if count == 1 { "" } else { "es" };
+            println!("{} {count} fix{suffix} to {path}", "Applied".bold());
+        }
+    }
+
+    Ok(())
+}
+
+/// Works out the unified diff between two versions of the file at the given
+/// path, with [`CONTEXT_LINES`] unchanged lines around every change.
+fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    // Only the lines between the ones both versions start and end with are
+    // compared, which is usually a small part of the file.
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    // The lengths of the longest common subsequences of the ends of `a` and
+    // `b`, starting at every pair of lines.
+    let mut lcs = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = match a[i] == b[j] {
+                true => lcs[i + 1][j + 1] + 1,
+                false => lcs[i + 1][j].max(lcs[i][j + 1]),
+            };
+        }
+    }
+
+    let mut lines = old[..prefix]
+        .iter()
+        .map(|line| (' ', *line))
+        .collect::<Vec<_>>();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            lines.push((' ', a[i]));
+            (i, j) = (i + 1, j + 1);
+        } else if i < a.len()
+            && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1])
+        {
+            lines.push(('-', a[i]));
+            i += 1;
+        } else {
+            lines.push(('+', b[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[old.len() - suffix..].iter().map(|line| (' ', *line)));
+
+    // Changes close enough for their context to overlap share a hunk.
+    let changed = (0..lines.len())
+        .filter(|&index| lines[index].0 != ' ')
+        .collect::<Vec<_>>();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for index in changed {
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = (index + CONTEXT_LINES + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some(hunk) if hunk.1 >= start => hunk.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut diff = format!("--- {path}\n+++ {path}\n");
+    for (start, end) in hunks {
+        // Lines are numbered from 1, and an empty range starts at the line
+        // before it.
+        let count = |range: &[(char, &str)], kind| {
+            range
+                .iter()
+                .filter(|(k, _)| *k == ' ' || *k == kind)
+                .count()
+        };
+        let old_start = count(&lines[..start], '-');
+        let new_start = count(&lines[..start], '+');
+        let old_count = count(&lines[start..end], '-');
+        let new_count = count(&lines[start..end], '+');
+        let line = |start: usize, count| start + usize::from(count > 0);
+        diff.push_str(&format!(
+            "@@ -{},{old_count} +{},{new_count} @@\n",
+            line(old_start, old_count),
+            line(new_start, new_count),
+        ));
+
+        for (kind, line) in &lines[start..end] {
+            diff.push_str(&format!("{kind}{line}\n"));
+        }
+    }
+
+    diff
+}
+
+/// Prints the diff of a fixed file, coloring removed lines in red and added
+/// ones in green.
+fn print_diff(diff: &str) {
+    for line in diff.lines() {
+        match line.chars().next() {
+            _ if line.starts_with("---") || line.starts_with("+++") => {
+                println!("{}", line.bold())
+            }
+            Some('@') => println!("{}", line.cyan()),
+            Some('-') => println!("{}", line.red()),
+            Some('+') => println!("{}", line.green()),
+            _ => println!("{line}"),
+        }
+    }
+}
+
+/// Fixes the program (or only prints the diff of the fixes), returning the
+/// message to finish with.
+fn __fix(
+    opts: &HeliosFixOpts,
+    program: &Program,
+    verbosity: Verbosity,
+) -> Result<String> {
+    let mut db = HeliosDatabase::default();
+    program.load(&mut db)?;
+    let config = DiagnosticsConfig::new(&opts.diagnostics);
+    let fixed = apply_fixes(&mut db, &config);
+    if fixed.is_empty() {
+        return Ok("Nothing to fix".to_string());
+    }
+
+    if opts.dry_run {
+        for file in &fixed {
+            let path = db.file_name(file.file_id);
+            let source = db.source(file.file_id);
+            print_diff(&unified_diff(&path, &file.original, &source));
+        }
+    } else {
+        write_fixes(&db, &fixed, verbosity)?;
+    }
+
+    let count = fixed.iter().map(|file| file.count).sum::<usize>();
+    let files = fixed.len();
+    Ok(format!(
+        "{} {count} fix{} to {files} file{}",
+        if opts.dry_run {
+            "Would apply"
+        } else {
+            "Applied"
+        },
+        if count == 1 { "" } else { "es" },
+        if files == 1 { "" } else { "s" },
+    ))
+}
+
+/// Applies the machine-applicable fixes of the program (or only prints them).
+pub fn fix(opts: &HeliosFixOpts, verbosity: Verbosity) {
+    let start = Instant::now();
+    let result = Program::new(&opts.files).and_then(|program| {
+        verbosity.banner("Fixing", &program);
+        __fix(opts, &program, verbosity)
+    });
+
+    match result {
+        Ok(message) => verbosity.finished(message, start),
+        Err(error) => {
+            let error = format!("{}", error).red().bold();
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_fixes() {
+        let dir = std::env::temp_dir()
+            .join(format!("helios-fix-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        std::fs::write(path("math.helios"), "let pi = 3\n").unwrap();
+        std::fs::write(
+            path("main.helios"),
+            "import math # why\nimport math\nlet a = 1\n",
+        )
+        .unwrap();
+
+        let program =
+            Program::Files(vec![path("main.helios"), path("math.helios")]);
+        let mut db = HeliosDatabase::default();
+        let file_id = program.load(&mut db).unwrap();
+        let config = DiagnosticsConfig::default();
+        let fixed = apply_fixes(&mut db, &config);
+        let result = write_fixes(&db, &fixed, Verbosity::Quiet);
+        let written = std::fs::read_to_string(path("main.helios"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Removing the unused import reveals that the other one is unused too
+        // (it was a duplicate until then), while removing an unused binding
+        // could change what the program does.
+        result.unwrap();
+        let source = "# why\nlet a = 1\n";
+        assert_eq!(written.unwrap(), source);
+        assert_eq!(db.source(file_id).as_str(), source);
+        assert_eq!(fixed.len(), 1);
+        assert_eq!(fixed[0].count, 2);
+        assert_eq!(
+            fixed[0].original.as_str(),
+            "import math # why\nimport math\nlet a = 1\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        let new = "b\nc\nd\ne\nf\ng\nh\ni\nJ\nk\nl\n";
+        assert_eq!(
+            unified_diff("main.helios", old, new),
+            "--- main.helios\n+++ main.helios\n\
+             @@ -1,4 +1,3 @@\n-a\n b\n c\n d\n\
+             @@ -7,5 +6,6 @@\n g\n h\n i\n-j\n+J\n k\n+l\n"
+        );
+
+        assert_eq!(
+            unified_diff("main.helios", "a\n", ""),
+            "--- main.helios\n+++ main.helios\n@@ -1,1 +0,0 @@\n-a\n"
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use crate::build::{STDIN_NAME, STDIN_PATH};
+use crate::verbosity::Verbosity;
+use colored::*;
+use helios_parser::FileId;
+use helios_parser::{imports, printer};
+use helios_project::Project;
+use helios_query::{FileOrigin, SourceRoot};
+use std::io::Read;
+use std::path::Path;
+use std::time::Instant;
+
+/// Formats the source files of the project containing the current directory,
+/// as configured by the `[fmt]` section of its `helios.toml`
+#[derive(clap::Parser)]
+pub struct HeliosFmtOpts {
+    /// The files to format (by default, every file of the project containing
+    /// the current directory). Globs (such as `src/*.helios`) are expanded,
+    /// and `-` is read from the standard input and written to the standard
+    /// output
+    pub files: Vec<String>,
+    /// Lists the files that aren't formatted (failing if there are any),
+    /// instead of formatting them
+    #[clap(long)]
+    pub check: bool,
+    /// Sorts the imports of the files (merging the ones importing the same
+    /// module) before formatting them
+    #[clap(long)]
+    pub organize_imports: bool,
+}
+
+/// Finds the files to format: the given files (once their globs are
+/// expanded), or else every file of the project containing the current
+/// directory.
+fn files(opts: &HeliosFmtOpts) -> Result<Vec<String>, String> {
+    if !opts.files.is_empty() {
+        return crate::build::expand_inputs(&opts.files).map_err(|error| {
+            match error {
+                crate::build::Error::Input(error) => error,
+                error => error.to_string(),
+            }
+        });
+    }
+
+    let cwd = std::env::current_dir().map_err(|error| error.to_string())?;
+    let project = Project::discover(&cwd)
+        .map_err(|error| error.to_string())?
+        .ok_or_else(|| crate::build::no_project_error(&cwd))?;
+
+    let root = SourceRoot::new(project.source_dir(), FileOrigin::Workspace);
+    let files = root.find_files().into_iter();
+    Ok(files
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Pretty-prints the source text of the file at the given path (shown as the
+/// given name in errors), with the settings of the project containing it. Its
+/// imports are organized first if the options ask for it.
+///
+/// Files with syntax errors are left alone, since the printer could only
+/// guess what they were meant to look like.
+fn format_source(
+    opts: &HeliosFmtOpts,
+    path: &Path,
+    name: &str,
+    source: &str,
+) -> Result<String, String> {
+    let config = helios_project::formatter_config(path)
+        .map_err(|error| error.to_string())?;
+    let mut parse = helios_parser::parse(FileId::default(), source);
+    if parse.messages().iter().any(|message| message.is_error()) {
+        return Err(format!(
+            "`{name}` has syntax errors (run `helios build` to see them)"
+        ));
+    }
+
+    if opts.organize_imports {
+        let organized = imports::organize_imports(&parse.syntax());
+        parse = helios_parser::parse(FileId::default(), &organized);
+    }
+
+    Ok(printer::pretty_print(&parse.syntax(), &config))
+}
+
+/// Formats (or checks) every file the options ask for, returning the message
+/// to finish with, unless the output was written to the standard output.
+fn __fmt(opts: &HeliosFmtOpts) -> Result<Option<String>, String> {
+    let files = files(opts)?;
+    let mut changed = Vec::new();
+    let mut wrote_stdout = false;
+
+    for path in &files {
+        if path == STDIN_PATH {
+            let mut source = String::new();
+            std::io::stdin()
+                .read_to_string(&mut source)
+                .map_err(|error| error.to_string())?;
+
+            // The settings are those of the project containing the current
+            // directory.
+            let path = Path::new(STDIN_PATH);
+            let formatted = format_source(opts, path, STDIN_NAME, &source)?;
+            if formatted != source {
+                changed.push(STDIN_NAME);
+            }
+            if !opts.check {
+                print!("{formatted}");
+                wrote_stdout = true;
+            }
+            continue;
+        }
+
+        let source = std::fs::read_to_string(path)
+            .map_err(|error| format!("could not read `{path}`: {error}"))?;
+        let formatted = format_source(opts, Path::new(path), path, &source)?;
+        if formatted == source {
+            continue;
+        }
+
+        changed.push(path);
+        if !opts.check {
+            std::fs::write(path, formatted).map_err(|error| {
+                format!("could not write `{path}`: {error}")
+            })?;
+        }
+    }
+
+    let plural = |count: usize| if count == 1 { "" } else { "s" };
+    let count = changed.len();
+    if opts.check {
+        // The files are listed even in quiet mode, since that's what the
+        // subcommand was asked for.
+        for path in &changed {
+            println!("{path}");
+        }
+
+        return match count {
+            0 => {
+                let total = files.len();
+                Ok(Some(format!("Checked {total} file{}", plural(total))))
+            }
+            1 => Err("1 file isn't formatted".to_string()),
+            _ => Err(format!("{count} files aren't formatted")),
+        };
+    }
+
+    match wrote_stdout {
+        true => Ok(None),
+        false => Ok(Some(format!("Formatted {count} file{}", plural(count)))),
+    }
+}
+
+/// Formats the given files (or every file of the project containing the
+/// current directory), or only checks whether they're formatted.
+pub fn fmt(opts: &HeliosFmtOpts, verbosity: Verbosity) {
+    let start = Instant::now();
+    match __fmt(opts) {
+        Ok(Some(message)) => verbosity.finished(message, start),
+        Ok(None) => {}
+        Err(error) => {
+            let error = format!("Failed to format the files: {error}");
+            eprintln!("{}", error.red().bold());
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_source() {
+        let mut opts = HeliosFmtOpts {
+            files: Vec::new(),
+            check: false,
+            organize_imports: false,
+        };
+        let path = Path::new("/main.helios");
+        let format = |opts: &HeliosFmtOpts, source| {
+            format_source(opts, path, "main.helios", source)
+        };
+
+        assert_eq!(
+            format(&opts, "let a  =  1\n\n\n\nlet b = a\n"),
+            Ok("let a = 1\n\nlet b = a\n".to_string())
+        );
+        assert_eq!(
+            format(&opts, "let a = (1\n"),
+            Err(
+                "`main.helios` has syntax errors (run `helios build` to see \
+                them)"
+                    .to_string()
+            )
+        );
+
+        let imports = "import b\nimport  a\nimport b\n";
+        assert_eq!(format(&opts, imports), Ok(imports.replace("  ", " ")));
+        opts.organize_imports = true;
+        assert_eq!(
+            format(&opts, imports),
+            Ok("import a\nimport b\n".to_string())
+        );
+    }
+}
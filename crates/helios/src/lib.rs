@@ -1,2 +1,14 @@
 pub mod build;
+mod cache;
+pub mod clean;
+pub mod diagnostics;
+pub mod doc;
+pub mod fix;
+pub mod fmt;
+pub mod new;
 pub mod repl;
+pub mod run;
+mod target;
+pub mod test;
+mod timings;
+pub mod verbosity;
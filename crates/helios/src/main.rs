@@ -1,16 +1,26 @@
 use clap::Parser;
 
 use helios::build::HeliosBuildOpts;
+use helios::clean::HeliosCleanOpts;
+use helios::doc::HeliosDocOpts;
+use helios::fix::HeliosFixOpts;
+use helios::fmt::HeliosFmtOpts;
+use helios::new::{HeliosInitOpts, HeliosNewOpts};
 use helios::repl::HeliosReplOpts;
+use helios::run::HeliosRunOpts;
+use helios::test::HeliosTestOpts;
+use helios::verbosity::Verbosity;
 
 #[derive(Parser)]
 #[clap(version = "0.2.0")]
 struct HeliosOpts {
-    /// Enables quiet mode (no output to stdout)
-    #[clap(short, long)]
+    /// Enables quiet mode (only errors are printed, without banners, syntax
+    /// trees, warnings or notes)
+    #[clap(short, long, global = true, conflicts_with = "verbose")]
     quiet: bool,
-    /// The verbosity of the output to stdout
-    #[clap(short, long)]
+    /// Enables verbose mode (how long everything took and how often queries
+    /// were executed are printed as well)
+    #[clap(short, long, global = true)]
     verbose: bool,
     /// Recognized subcommands
     #[clap(subcommand)]
@@ -20,20 +30,61 @@ struct HeliosOpts {
 #[derive(Parser)]
 enum HeliosSubcommand {
     Build(HeliosBuildOpts),
+    Clean(HeliosCleanOpts),
+    Doc(HeliosDocOpts),
+    Fix(HeliosFixOpts),
+    Fmt(HeliosFmtOpts),
+    Init(HeliosInitOpts),
+    New(HeliosNewOpts),
     Repl(HeliosReplOpts),
+    Run(HeliosRunOpts),
+    Test(HeliosTestOpts),
 }
 
 fn main() {
     env_logger::init();
     let opts = HeliosOpts::parse();
+    let verbosity = Verbosity::new(opts.quiet, opts.verbose);
     match opts.subcommand {
         HeliosSubcommand::Build(build_opts) => {
             log::trace!("Starting build process...");
-            helios::build::build(&build_opts.file);
+            helios::build::build(&build_opts, verbosity);
         }
-        HeliosSubcommand::Repl(_repl_opts) => {
+        HeliosSubcommand::Clean(clean_opts) => {
+            log::trace!("Starting clean process...");
+            helios::clean::clean(&clean_opts, verbosity);
+        }
+        HeliosSubcommand::Doc(doc_opts) => {
+            log::trace!("Starting documentation process...");
+            helios::doc::doc(&doc_opts, verbosity);
+        }
+        HeliosSubcommand::Fix(fix_opts) => {
+            log::trace!("Starting fix process...");
+            helios::fix::fix(&fix_opts, verbosity);
+        }
+        HeliosSubcommand::Fmt(fmt_opts) => {
+            log::trace!("Starting format process...");
+            helios::fmt::fmt(&fmt_opts, verbosity);
+        }
+        HeliosSubcommand::Init(init_opts) => {
+            log::trace!("Starting project creation...");
+            helios::new::init(&init_opts, verbosity);
+        }
+        HeliosSubcommand::New(new_opts) => {
+            log::trace!("Starting project creation...");
+            helios::new::new(&new_opts, verbosity);
+        }
+        HeliosSubcommand::Repl(repl_opts) => {
             log::trace!("Starting new REPL session...");
-            helios::repl::start();
+            helios::repl::start(&repl_opts, verbosity);
+        }
+        HeliosSubcommand::Run(run_opts) => {
+            log::trace!("Starting run process...");
+            helios::run::run(&run_opts, verbosity);
+        }
+        HeliosSubcommand::Test(test_opts) => {
+            log::trace!("Starting test process...");
+            helios::test::test(&test_opts, verbosity);
         }
     }
 }
@@ -0,0 +1,84 @@
+use crate::verbosity::Verbosity;
+use colored::*;
+use helios_project::Project;
+use std::path::{Path, PathBuf};
+
+/// Creates a new Helios project in a new directory
+#[derive(clap::Parser)]
+pub struct HeliosNewOpts {
+    /// The name of the project, which is also the name of its directory
+    pub name: String,
+}
+
+/// Creates a new Helios project in the current directory
+#[derive(clap::Parser)]
+pub struct HeliosInitOpts {
+    /// The name of the project (by default, the name of the current
+    /// directory)
+    #[clap(long)]
+    pub name: Option<String>,
+}
+
+fn __init(opts: &HeliosInitOpts) -> Result<Project, String> {
+    let root = std::env::current_dir().map_err(|error| error.to_string())?;
+    let name = match &opts.name {
+        Some(name) => name.clone(),
+        None => root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or("the current directory doesn't have a name")?,
+    };
+
+    Project::create(root, &name).map_err(|error| error.to_string())
+}
+
+/// Creates a project in a new directory named after it.
+pub fn new(opts: &HeliosNewOpts, verbosity: Verbosity) {
+    let root = PathBuf::from(&opts.name);
+    let result = if root.exists() {
+        Err(format!("`{}` already exists", root.display()))
+    } else {
+        Project::create(&root, &opts.name).map_err(|error| error.to_string())
+    };
+
+    finish(result, Some(&root), verbosity);
+}
+
+/// Creates a project in the current directory.
+pub fn init(opts: &HeliosInitOpts, verbosity: Verbosity) {
+    finish(__init(opts), None, verbosity);
+}
+
+/// Reports how creating a project went, along with how to run it (from the
+/// given directory, if it isn't the current one).
+fn finish(
+    result: Result<Project, String>,
+    dir: Option<&Path>,
+    verbosity: Verbosity,
+) {
+    let project = match result {
+        Ok(project) => project,
+        Err(error) => {
+            let error = format!("Failed to create the project: {error}");
+            eprintln!("\n{}", error.red().bold());
+            std::process::exit(1);
+        }
+    };
+
+    if verbosity.is_quiet() {
+        return;
+    }
+
+    println!(
+        "\n{} {} ({})\n",
+        "Created".green().bold(),
+        project.name().underline(),
+        project.root().display()
+    );
+
+    let run = match dir {
+        Some(dir) => format!("cd {} && helios run", dir.display()),
+        None => "helios run".to_string(),
+    };
+    println!("Run it with `{run}`, or test it with `helios test`.");
+}
@@ -1,8 +1,23 @@
 //! REPL support for the Helios programming language.
 
+pub mod config;
+mod editor;
+mod pager;
+mod session;
+mod value;
+
+use crate::verbosity::Verbosity;
 use colored::*;
-use helios_diagnostics::{Diagnostic, ManyFiles};
+use rustyline::error::ReadlineError;
+use rustyline::Editor;
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+pub use self::config::{OutputMode, ReplConfig, Theme};
+use self::editor::ReplHelper;
+pub use self::session::{ReplOutcome, ReplSession};
+pub use self::value::ReplValue;
 
 const LOGO_BANNER: &[&str] = &[
     r"          __   __     __              ",
@@ -15,14 +30,22 @@ const LOGO_BANNER: &[&str] = &[
 
 /// Starts a new REPL session
 #[derive(clap::Parser)]
-pub struct HeliosReplOpts {}
+pub struct HeliosReplOpts {
+    /// The configuration file to use instead of `~/.config/helios/repl.toml`
+    #[clap(long)]
+    pub config: Option<PathBuf>,
+    /// Runs the inputs and commands in the given file instead of reading them
+    /// interactively, printing a transcript of the session
+    #[clap(long)]
+    pub script: Option<PathBuf>,
+}
 
-fn print_logo_banner() -> io::Result<()> {
+fn print_logo_banner(theme: &Theme) -> io::Result<()> {
     for (i, line) in LOGO_BANNER.iter().enumerate() {
         match i {
             2 => println!(
                 "{}{}",
-                line.yellow().bold(),
+                line.color(theme.banner).bold(),
                 format!(
                     "Version {} ({})",
                     env!("CARGO_PKG_VERSION"),
@@ -32,88 +55,174 @@ fn print_logo_banner() -> io::Result<()> {
             ),
             3 => println!(
                 "{}{}",
-                line.yellow().bold(),
+                line.color(theme.banner).bold(),
                 env!("CARGO_PKG_REPOSITORY").italic()
             ),
             4 => println!(
                 "{}{}",
-                line.yellow().bold(),
+                line.color(theme.banner).bold(),
                 format!(
                     "Type {} to exit, {} for help",
-                    ":exit".blue(),
-                    ":help".blue()
+                    ":exit".color(theme.info),
+                    ":help".color(theme.info)
                 )
                 .italic()
             ),
-            _ => println!("{}", line.trim_end().yellow().bold()),
+            _ => println!("{}", line.trim_end().color(theme.banner).bold()),
         }
     }
 
     Ok(())
 }
 
-fn start_main_loop() -> io::Result<()> {
-    print_logo_banner()?;
+/// Loads the REPL configuration, falling back to the default configuration
+/// (with a warning) if the configuration file could not be loaded.
+fn load_config(opts: &HeliosReplOpts) -> ReplConfig {
+    let path = opts.config.clone().or_else(ReplConfig::default_path);
+    let config = match path {
+        Some(path) => {
+            log::trace!("Loading REPL configuration from {}", path.display());
+            ReplConfig::load(path)
+        }
+        None => Ok(ReplConfig::default()),
+    };
+
+    config.unwrap_or_else(|error| {
+        let default = ReplConfig::default();
+        eprintln!("{}", format!("Warning: {error}").color(default.theme.error));
+        default
+    })
+}
+
+/// Evaluates an input and writes its outcome (along with how long evaluating it
+/// took in verbose mode).
+fn eval_input(
+    session: &mut ReplSession,
+    input: &str,
+    verbosity: Verbosity,
+    f: &mut impl Write,
+) -> io::Result<ReplOutcome> {
+    let start = Instant::now();
+    let outcome = session.eval_line(input);
+    let elapsed = start.elapsed();
+    session.write_outcome(&outcome, f)?;
+
+    let is_evaluated = matches!(
+        outcome,
+        ReplOutcome::Parsed { .. } | ReplOutcome::Loaded { .. }
+    );
+    if verbosity.is_verbose() && is_evaluated {
+        let timing = format!("(evaluated in {elapsed:.2?})");
+        writeln!(f, "{}", timing.color(session.config().theme.info).dimmed())?;
+    }
+
+    Ok(outcome)
+}
+
+fn start_main_loop(
+    config: &ReplConfig,
+    verbosity: Verbosity,
+) -> io::Result<()> {
+    let theme = &config.theme;
+
+    if config.show_banner && !verbosity.is_quiet() {
+        print_logo_banner(theme)?;
+    }
+
+    let mut session = ReplSession::new(config.clone());
+
+    let editor_config = rustyline::Config::builder()
+        .max_history_size(config.history_size)
+        .auto_add_history(true)
+        .build();
+    let mut editor = Editor::with_config(editor_config);
+    editor.set_helper(Some(ReplHelper));
+    let prompt = config.prompt.color(theme.prompt).to_string();
 
-    let stdin = io::stdin();
+    loop {
+        let input = match editor.readline(&prompt) {
+            Ok(input) => input,
+            // Ctrl-C cancels the current line, but keeps the session running.
+            Err(ReadlineError::Interrupted) => continue,
+            // Ctrl-D on an empty line ends the session.
+            Err(ReadlineError::Eof) => break,
+            Err(error) => return Err(io::Error::other(error)),
+        };
+
+        // The output is collected first so that it can be paged if it turns
+        // out to be taller than the terminal.
+        let mut output = Vec::new();
+        let outcome = eval_input(&mut session, &input, verbosity, &mut output)?;
+        pager::page(&String::from_utf8_lossy(&output), config.pager)?;
+
+        if outcome == ReplOutcome::Exit {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs every line of the given script as if it was typed into the REPL,
+/// printing a transcript of the inputs and their outputs.
+fn run_script(
+    path: &Path,
+    config: &ReplConfig,
+    verbosity: Verbosity,
+) -> io::Result<()> {
+    let script = std::fs::read_to_string(path)?;
+    let mut session = ReplSession::new(config.clone());
     let mut stdout = io::stdout();
 
+    let prompt = config.prompt.color(config.theme.prompt);
     let mut input = String::new();
-    let mut files = ManyFiles::new();
 
-    loop {
-        write!(stdout, "{}", "> ".blue())?;
-        stdout.flush()?;
-        stdin.read_line(&mut input)?;
+    // Lines are grouped into inputs just like they would be when typed in.
+    for line in script.lines() {
+        if input.is_empty() {
+            writeln!(stdout, "{prompt}{line}")?;
+            input.push_str(line);
+        } else {
+            writeln!(stdout, "{}{line}", " ".repeat(config.prompt.len()))?;
+            input.push('\n');
+            input.push_str(line);
+        }
 
-        if input.trim().is_empty() {
+        if editor::is_incomplete(&input) {
             continue;
         }
 
-        if input.trim().starts_with(':') {
-            let input = input[1..].trim();
-            match input {
-                "exit" => break,
-                "help" => {
-                    println!(
-                        "{}",
-                        "Sorry, help is unavailable at the moment".blue()
-                    )
-                }
-                command => {
-                    let msg = format!("Unknown command: `{command}`").red();
-                    eprintln!("{msg}");
-                }
-            }
-            println!()
-        } else {
-            let file_id = files.add("<repl>", input.to_string());
-            let file = files.get(file_id).unwrap();
-
-            let parse = helios_parser::parse(file_id, file.source());
-            println!("{}", parse.debug_tree().cyan());
-
-            let mut emitted_ranges = Vec::new();
-            for message in parse.messages() {
-                let diagnostic = Diagnostic::from(message);
-                if !(emitted_ranges.contains(&diagnostic.location)) {
-                    emitted_ranges.push(diagnostic.location.clone());
-                    helios_diagnostics::emit(&mut stdout, &files, &diagnostic)
-                        .expect("Failed to print diagnostic");
-                }
-            }
+        let outcome = eval_input(&mut session, &input, verbosity, &mut stdout)?;
+        if outcome == ReplOutcome::Exit {
+            return stdout.flush();
         }
 
         input.clear();
     }
 
-    Ok(())
+    // The script may end with an input that was never completed.
+    if !input.trim().is_empty() {
+        eval_input(&mut session, &input, verbosity, &mut stdout)?;
+    }
+    stdout.flush()
 }
 
-/// Starts a new REPL session.
-pub fn start() {
-    match start_main_loop() {
-        Ok(_) => println!("{}", "Goodbye!".blue()),
+/// Starts a new REPL session with the given options.
+pub fn start(opts: &HeliosReplOpts, verbosity: Verbosity) {
+    let config = load_config(opts);
+
+    if let Some(script) = &opts.script {
+        if let Err(error) = run_script(script, &config, verbosity) {
+            let msg = format!("Failed to run `{}`: {error}", script.display());
+            eprintln!("{}", msg.color(config.theme.error));
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    match start_main_loop(&config, verbosity) {
+        Ok(_) if verbosity.is_quiet() => {}
+        Ok(_) => println!("{}", "Goodbye!".color(config.theme.info)),
         Err(error) => eprintln!("An error occurred: {error}"),
     }
 }
@@ -0,0 +1,216 @@
+//! Configuration for the Helios REPL.
+//!
+//! The configuration is read from `~/.config/helios/repl.toml` (or
+//! `$XDG_CONFIG_HOME/helios/repl.toml` if that variable is set) when a new
+//! session starts. Every key is optional, so an empty or missing file results
+//! in the default configuration. For example:
+//!
+//! ```toml
+//! prompt = "helios> "
+//! show-banner = false
+//! history-size = 500
+//! output-mode = "value"
+//! show-types = false
+//! pager = false
+//!
+//! [theme]
+//! prompt = "green"
+//! tree = "bright black"
+//! ```
+
+use colored::Color;
+use serde::{Deserialize, Deserializer};
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+
+/// The kind of output the REPL prints after every input.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputMode {
+    /// Prints the concrete syntax tree of the input.
+    #[default]
+    Tree,
+    /// Prints the value of the input (the value of its entry point, or else of
+    /// its last expression), unless it has errors.
+    Value,
+}
+
+/// The colors used by the REPL to print its output.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Theme {
+    /// The color of the prompt.
+    #[serde(deserialize_with = "deserialize_color")]
+    pub prompt: Color,
+    /// The color of the logo banner.
+    #[serde(deserialize_with = "deserialize_color")]
+    pub banner: Color,
+    /// The color of printed syntax trees.
+    #[serde(deserialize_with = "deserialize_color")]
+    pub tree: Color,
+    /// The color of informational messages (such as the help text).
+    #[serde(deserialize_with = "deserialize_color")]
+    pub info: Color,
+    /// The color of error messages reported by the REPL itself.
+    #[serde(deserialize_with = "deserialize_color")]
+    pub error: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            prompt: Color::Blue,
+            banner: Color::Yellow,
+            tree: Color::Cyan,
+            info: Color::Blue,
+            error: Color::Red,
+        }
+    }
+}
+
+/// Deserializes a color from its name (e.g. `"blue"` or `"bright green"`).
+fn deserialize_color<'de, D>(deserializer: D) -> Result<Color, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?;
+    name.parse().map_err(|_| {
+        serde::de::Error::custom(format!("unknown color `{name}`"))
+    })
+}
+
+/// The user's configuration of the REPL.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ReplConfig {
+    /// The string printed before every line of input.
+    pub prompt: String,
+    /// The colors used to print the REPL's output.
+    pub theme: Theme,
+    /// Whether or not to print the logo banner when a session starts.
+    pub show_banner: bool,
+    /// The maximum number of entries kept in the input history.
+    pub history_size: usize,
+    /// The kind of output printed after every input.
+    pub output_mode: OutputMode,
+    /// Whether or not to print the types of values (such as `42 : Int`).
+    pub show_types: bool,
+    /// Whether or not to page outputs that don't fit in the terminal.
+    pub pager: bool,
+}
+
+impl Default for ReplConfig {
+    fn default() -> Self {
+        Self {
+            prompt: "> ".to_string(),
+            theme: Theme::default(),
+            show_banner: true,
+            history_size: 1000,
+            output_mode: OutputMode::default(),
+            show_types: true,
+            pager: true,
+        }
+    }
+}
+
+impl ReplConfig {
+    /// Parses a configuration from the given TOML source text.
+    pub fn from_toml(source: &str) -> Result<Self, Error> {
+        toml::from_str(source).map_err(|error| Error::Parse(error.to_string()))
+    }
+
+    /// Loads the configuration from the given path.
+    ///
+    /// A missing file is not an error; the default configuration is returned
+    /// instead.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        match std::fs::read_to_string(path) {
+            Ok(source) => Self::from_toml(&source),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                Ok(Self::default())
+            }
+            Err(error) => Err(Error::Io(error.to_string())),
+        }
+    }
+
+    /// The default location of the configuration file.
+    ///
+    /// This is `$XDG_CONFIG_HOME/helios/repl.toml` if `XDG_CONFIG_HOME` is set,
+    /// or `~/.config/helios/repl.toml` otherwise. `None` is returned if neither
+    /// location can be determined.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .map(|home| PathBuf::from(home).join(".config"))
+            })?;
+
+        Some(config_dir.join("helios").join("repl.toml"))
+    }
+}
+
+/// An error encountered while loading the REPL's configuration.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    Io(String),
+    Parse(String),
+}
+
+impl std::error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => {
+                write!(f, "failed to read the REPL configuration: {error}")
+            }
+            Self::Parse(error) => {
+                write!(f, "failed to parse the REPL configuration: {error}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_config() {
+        assert_eq!(ReplConfig::from_toml(""), Ok(ReplConfig::default()));
+    }
+
+    #[test]
+    fn test_partial_config() {
+        let config = ReplConfig::from_toml(
+            r#"
+prompt = "helios> "
+show-banner = false
+output-mode = "value"
+show-types = false
+pager = false
+
+[theme]
+prompt = "bright green"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.prompt, "helios> ");
+        assert!(!config.show_banner);
+        assert_eq!(config.history_size, 1000);
+        assert_eq!(config.output_mode, OutputMode::Value);
+        assert!(!config.show_types);
+        assert!(!config.pager);
+        assert_eq!(config.theme.prompt, Color::BrightGreen);
+        assert_eq!(config.theme.tree, Color::Cyan);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(ReplConfig::from_toml("output-mode = \"graph\"").is_err());
+        assert!(ReplConfig::from_toml("[theme]\nprompt = \"plaid\"").is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! Line editing support for the REPL.
+//!
+//! The REPL uses [`rustyline`] for line editing, which provides the usual
+//! Emacs-style key bindings, including incremental reverse history search with
+//! Ctrl-R. This module teaches the editor when an input continues onto the next
+//! line, so that multi-line inputs are entered (and stored in the history) as a
+//! single unit.
+
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::Helper;
+
+/// Symbols that cannot end a complete input, since they expect an operand or
+/// an expression to follow them.
+const CONTINUATION_SYMBOLS: &[&str] = &[
+    "=", "+", "-", "*", "/", "<", ">", "<=", ">=", "!=", "<-", "->", "=>", ";",
+];
+
+/// Determines whether the given input continues onto the next line.
+///
+/// An input is incomplete if it has unclosed delimiters, if its last line ends
+/// with a symbol that expects something after it (such as `=`), or if its last
+/// line is indented (in which case an empty line ends the input). Commands are
+/// always a single line long.
+pub(super) fn is_incomplete(input: &str) -> bool {
+    if input.trim_start().starts_with(':') {
+        return false;
+    }
+
+    let mut depth = 0_i32;
+    for line in input.lines() {
+        for c in strip_comment(line).chars() {
+            match c {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    if depth > 0 {
+        return true;
+    }
+
+    // `str::lines` would skip a trailing empty line, which is significant here.
+    let last_line = input.split('\n').next_back().unwrap_or_default();
+    let code = strip_comment(last_line).trim_end();
+
+    if CONTINUATION_SYMBOLS
+        .iter()
+        .any(|symbol| code.ends_with(symbol))
+    {
+        return true;
+    }
+
+    // An indented last line means we're inside a block, which only ends once
+    // an empty line is entered.
+    input.contains('\n')
+        && !last_line.trim().is_empty()
+        && last_line.starts_with(char::is_whitespace)
+}
+
+/// Removes a trailing comment from the given line.
+fn strip_comment(line: &str) -> &str {
+    line.split_once('#').map_or(line, |(code, _)| code)
+}
+
+/// The [`rustyline`] helper used by the REPL's line editor.
+pub(super) struct ReplHelper;
+
+impl Helper for ReplHelper {}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {
+    fn validate(
+        &self,
+        ctx: &mut ValidationContext,
+    ) -> rustyline::Result<ValidationResult> {
+        if is_incomplete(ctx.input()) {
+            Ok(ValidationResult::Incomplete)
+        } else {
+            Ok(ValidationResult::Valid(None))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_incomplete() {
+        assert!(!is_incomplete(""));
+        assert!(!is_incomplete("1 + 2"));
+        assert!(!is_incomplete(":doc foo"));
+        assert!(!is_incomplete("let x = 1 # one ="));
+
+        assert!(is_incomplete("let x ="));
+        assert!(is_incomplete("1 +   "));
+        assert!(is_incomplete("(1 + 2"));
+        assert!(is_incomplete("let x = (1 +\n  2"));
+        assert!(is_incomplete("let x = (1 +\n  2)"));
+        assert!(!is_incomplete("let x = (1 +\n  2)\n"));
+
+        assert!(is_incomplete("let x =\n  1"));
+        assert!(!is_incomplete("let x =\n  1\n"));
+    }
+}
@@ -0,0 +1,101 @@
+//! Paging support for large REPL outputs.
+//!
+//! Outputs that fit in the terminal are printed as-is. Anything taller is
+//! handed to the program named by the `PAGER` environment variable, or to a
+//! small built-in pager if that variable is not set (or the program could not
+//! be started).
+
+use std::io::{self, BufRead, Write};
+use std::process::{Command, Stdio};
+use terminal_size::{terminal_size, Height};
+
+/// Prints the given output, paging it if it doesn't fit in the terminal.
+///
+/// Paging is skipped entirely if `enabled` is `false` or if stdout is not a
+/// terminal.
+pub fn page(output: &str, enabled: bool) -> io::Result<()> {
+    let height = match terminal_size() {
+        Some((_, Height(height))) if enabled => height as usize,
+        _ => return print(output),
+    };
+
+    // Keep one line free for the prompt that follows the output.
+    if output.lines().count() < height {
+        return print(output);
+    }
+
+    match std::env::var("PAGER") {
+        Ok(pager) if !pager.trim().is_empty() => {
+            if let Err(error) = external_pager(&pager, output) {
+                log::warn!("Failed to run pager `{pager}`: {error}");
+                internal_pager(output, height)
+            } else {
+                Ok(())
+            }
+        }
+        _ => internal_pager(output, height),
+    }
+}
+
+fn print(output: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(output.as_bytes())?;
+    stdout.flush()
+}
+
+/// Pipes the output to the given pager command (such as `less -R`).
+fn external_pager(pager: &str, output: &str) -> io::Result<()> {
+    let mut args = pager.split_whitespace();
+    let program = args.next().unwrap_or_default();
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager may exit before reading everything (e.g. if the user quits
+        // early), which is not an error worth reporting.
+        match stdin.write_all(output.as_bytes()) {
+            Err(error) if error.kind() != io::ErrorKind::BrokenPipe => {
+                return Err(error)
+            }
+            _ => {}
+        }
+    }
+
+    child.wait()?;
+    Ok(())
+}
+
+/// Prints the output one page at a time, waiting for the user to press enter
+/// before printing the next page. Entering `q` stops paging.
+fn internal_pager(output: &str, height: usize) -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let page_size = height.saturating_sub(1).max(1);
+    let lines = output.lines().collect::<Vec<_>>();
+
+    for (i, page) in lines.chunks(page_size).enumerate() {
+        for line in page {
+            writeln!(stdout, "{line}")?;
+        }
+
+        let shown = (i + 1) * page_size;
+        if shown >= lines.len() {
+            break;
+        }
+
+        write!(stdout, "-- More ({shown}/{}) -- ", lines.len())?;
+        stdout.flush()?;
+
+        let mut answer = String::new();
+        if stdin.lock().read_line(&mut answer)? == 0
+            || answer.trim().eq_ignore_ascii_case("q")
+        {
+            break;
+        }
+    }
+
+    stdout.flush()
+}
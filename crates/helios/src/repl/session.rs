@@ -0,0 +1,561 @@
+use colored::*;
+use helios_diagnostics::{Diagnostic, FileRegistry, Severity};
+use helios_formatting::FormattedString;
+use helios_hir::{HostFunctions, Item, Name, RuntimeError};
+use helios_query::{
+    Bindings, Consts, Diagnostics, FileId, GlobalBinding, HeliosDatabase, Hir,
+    Input, Scopes, Types,
+};
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use super::{OutputMode, ReplConfig, ReplValue};
+
+/// The result of evaluating a single input with [`ReplSession::eval_line`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ReplOutcome {
+    /// The input was empty, so there is nothing to report.
+    Empty,
+    /// The input was Helios source text, which has been parsed.
+    Parsed {
+        /// The file the input was stored as.
+        file_id: FileId,
+        /// The debug representation of the input's syntax tree.
+        tree: String,
+        /// The diagnostics reported for the input.
+        diagnostics: Arc<Vec<Diagnostic<FileId>>>,
+        /// What the input evaluated to, unless it has errors or nothing to
+        /// evaluate.
+        value: Option<Result<ReplValue, RuntimeError>>,
+    },
+    /// A file was loaded into the session with `:load`.
+    Loaded {
+        /// The file the loaded file was stored as.
+        file_id: FileId,
+        /// The diagnostics reported for the loaded file.
+        diagnostics: Arc<Vec<Diagnostic<FileId>>>,
+    },
+    /// The documentation requested with `:doc`.
+    Doc(FormattedString),
+    /// An informational message.
+    Info(String),
+    /// The input could not be handled (e.g. an unknown command).
+    Error(String),
+    /// The session was asked to end with `:exit`.
+    Exit,
+}
+
+/// A REPL session that is driven programmatically.
+///
+/// A session doesn't read from stdin or write to stdout by itself: inputs are
+/// given to [`ReplSession::eval_line`], which returns a [`ReplOutcome`]
+/// describing what happened. The outcome can then be inspected directly (such
+/// as by tests or an editor integration) or printed the same way the
+/// interactive REPL does with [`ReplSession::write_outcome`].
+///
+/// Every input (and every file loaded with `:load`) is stored as a new file in
+/// the session's database. The global bindings, external functions and macros
+/// declared by the inputs and loaded files without errors are added to the
+/// files of the later inputs, so that they can be referred to later on (unless
+/// a later input declares the same name again, which replaces them). Entry
+/// points aren't, since each input evaluates to its own.
+pub struct ReplSession {
+    config: ReplConfig,
+    db: HeliosDatabase,
+    /// The names of the inputs and loaded files, in order.
+    files: FileRegistry,
+    /// The declarations later inputs can refer to, in the order they were
+    /// declared.
+    declarations: Vec<Declaration>,
+}
+
+/// A declaration of an earlier input or loaded file.
+struct Declaration {
+    name: Name,
+    /// Whether the declaration is a macro's, whose names are separate from
+    /// those of bindings and external functions.
+    is_macro: bool,
+    /// The source text of the declaration, including its documentation.
+    source: String,
+}
+
+impl Declaration {
+    /// Whether this declaration declares the same name as the other one.
+    fn replaces(&self, other: &Declaration) -> bool {
+        self.name == other.name && self.is_macro == other.is_macro
+    }
+}
+
+impl ReplSession {
+    /// Creates a new session with the given configuration.
+    pub fn new(config: ReplConfig) -> Self {
+        // Inputs aren't part of any workspace (the database starts with an
+        // empty one), so imports can't be resolved.
+        let db = HeliosDatabase::default();
+
+        Self {
+            config,
+            db,
+            files: FileRegistry::new(),
+            declarations: Vec::new(),
+        }
+    }
+
+    /// The configuration of the session.
+    pub fn config(&self) -> &ReplConfig {
+        &self.config
+    }
+
+    /// The database holding every input and loaded file of the session.
+    pub fn db(&self) -> &HeliosDatabase {
+        &self.db
+    }
+
+    /// Handles a single input (either a command or Helios source text).
+    pub fn eval_line(&mut self, input: &str) -> ReplOutcome {
+        if input.trim().is_empty() {
+            return ReplOutcome::Empty;
+        }
+
+        if let Some(command) = input.trim().strip_prefix(':') {
+            let (command, argument) = command
+                .trim()
+                .split_once(char::is_whitespace)
+                .map_or((command.trim(), ""), |(command, argument)| {
+                    (command, argument.trim())
+                });
+
+            self.eval_command(command, argument)
+        } else {
+            let (file_id, tree) = self.add_input(input);
+            let diagnostics = self.input_diagnostics(file_id);
+            let has_errors = diagnostics
+                .iter()
+                .any(|diagnostic| diagnostic.severity >= Severity::Error);
+            if !has_errors {
+                self.declare(file_id, input.len());
+            }
+
+            ReplOutcome::Parsed {
+                file_id,
+                tree,
+                diagnostics,
+                value: if has_errors { None } else { self.eval(file_id) },
+            }
+        }
+    }
+
+    /// Prints the given outcome the same way the interactive REPL does,
+    /// following the session's configuration.
+    pub fn write_outcome(
+        &self,
+        outcome: &ReplOutcome,
+        f: &mut dyn Write,
+    ) -> io::Result<()> {
+        let theme = &self.config.theme;
+
+        match outcome {
+            ReplOutcome::Empty | ReplOutcome::Exit => return Ok(()),
+            ReplOutcome::Parsed {
+                tree,
+                diagnostics,
+                value,
+                ..
+            } => {
+                if self.config.output_mode == OutputMode::Tree {
+                    writeln!(f, "{}", tree.color(theme.tree))?;
+                }
+
+                self.emit_diagnostics(diagnostics, f);
+                if self.config.output_mode == OutputMode::Value {
+                    match value {
+                        Some(Ok(value)) => writeln!(
+                            f,
+                            "{}",
+                            value.display(self.config.show_types)
+                        )?,
+                        Some(Err(error)) => {
+                            let error = error.to_string();
+                            writeln!(f, "{}", error.color(theme.error))?
+                        }
+                        None => {}
+                    }
+                }
+
+                return Ok(());
+            }
+            ReplOutcome::Loaded { diagnostics, .. } => {
+                self.emit_diagnostics(diagnostics, f)
+            }
+            ReplOutcome::Doc(doc) => writeln!(f, "{}", doc.clone().finish())?,
+            ReplOutcome::Info(msg) => writeln!(f, "{}", msg.color(theme.info))?,
+            ReplOutcome::Error(msg) => {
+                writeln!(f, "{}", msg.color(theme.error))?
+            }
+        }
+
+        writeln!(f)
+    }
+
+    fn eval_command(&mut self, command: &str, argument: &str) -> ReplOutcome {
+        match command {
+            "exit" => ReplOutcome::Exit,
+            "help" => ReplOutcome::Info(
+                "Sorry, help is unavailable at the moment".to_string(),
+            ),
+            "doc" => self.doc(argument),
+            "load" => match std::fs::read_to_string(argument) {
+                Ok(source) => {
+                    let len = source.len();
+                    let file_id = self.add_file(argument, source);
+                    let diagnostics = self.db.diagnostics(file_id);
+                    if diagnostics
+                        .iter()
+                        .all(|it| it.severity < Severity::Error)
+                    {
+                        self.declare(file_id, len);
+                    }
+
+                    ReplOutcome::Loaded {
+                        file_id,
+                        diagnostics,
+                    }
+                }
+                Err(error) => ReplOutcome::Error(format!(
+                    "Failed to load `{argument}`: {error}"
+                )),
+            },
+            command => {
+                ReplOutcome::Error(format!("Unknown command: `{command}`"))
+            }
+        }
+    }
+
+    /// Stores the given source text as a new file in the database.
+    fn add_file(
+        &mut self,
+        name: impl Into<String>,
+        source: impl Into<String>,
+    ) -> FileId {
+        let name = name.into();
+        let file_id = self.files.add(&name);
+
+        self.db.set_file_name(file_id, Arc::new(name));
+        self.db.set_source(file_id, Arc::new(source.into()));
+
+        file_id
+    }
+
+    /// Stores an input as a new file, followed by the declarations of the
+    /// earlier inputs it doesn't declare again, and returns the debug
+    /// representation of the input's own syntax tree.
+    ///
+    /// The input comes first so that the ranges of its diagnostics are the
+    /// same as if it was on its own. Inputs with syntax errors are stored on
+    /// their own, as the declarations after them would be parsed as part of
+    /// whatever they didn't finish.
+    fn add_input(&mut self, input: &str) -> (FileId, String) {
+        let source = format!("{input}\n");
+        let file_id = self.add_file("<repl>", source.clone());
+        let parse = self.db.parse(file_id);
+        let tree = parse.debug_tree();
+        if parse.messages().iter().any(|message| message.is_error()) {
+            return (file_id, tree);
+        }
+
+        let declared = self.declarations_of(file_id, source.len());
+        let earlier = self
+            .declarations
+            .iter()
+            .filter(|it| !declared.iter().any(|other| other.replaces(it)))
+            .map(|declaration| declaration.source.as_str())
+            .collect::<Vec<_>>();
+
+        if !earlier.is_empty() {
+            let source = format!("{source}\n{}", earlier.concat());
+            self.db.set_source(file_id, Arc::new(source));
+        }
+
+        (file_id, tree)
+    }
+
+    /// Makes the declarations of a file (up to the given offset) visible to
+    /// the later inputs, replacing the earlier declarations of the same names.
+    fn declare(&mut self, file_id: FileId, end: usize) {
+        let declared = self.declarations_of(file_id, end);
+        self.declarations
+            .retain(|it| !declared.iter().any(|other| other.replaces(it)));
+        self.declarations.extend(declared);
+    }
+
+    /// The global bindings, external functions and macros declared by a file
+    /// before the given offset, other than its entry point.
+    fn declarations_of(&self, file_id: FileId, end: usize) -> Vec<Declaration> {
+        let (module, source_map) = self.db.hir_module_with_source_map(file_id);
+        let docs = self.db.item_docs(file_id);
+        let source = self.db.source(file_id);
+
+        module
+            .items
+            .iter()
+            .zip(docs.iter())
+            .filter_map(|((item_id, item), doc)| {
+                let name = match item {
+                    Item::Binding(binding) => binding.name.clone(),
+                    Item::External(external) => external.name.clone(),
+                    Item::Macro(macro_) => macro_.name.clone(),
+                    Item::Import(_) | Item::Test(_) | Item::Expr => None,
+                }?;
+                let range = source_map.item_range(item_id);
+                if name.as_str() == "main" || usize::from(range.start()) >= end
+                {
+                    return None;
+                }
+
+                let mut declaration = String::new();
+                for line in doc.iter().flat_map(|doc| doc.lines()) {
+                    declaration.push_str(&format!("## {line}\n"));
+                }
+                declaration.push_str(&source[range]);
+                declaration.push('\n');
+
+                Some(Declaration {
+                    name,
+                    is_macro: matches!(item, Item::Macro(_)),
+                    source: declaration,
+                })
+            })
+            .collect()
+    }
+
+    /// Interprets an input, returning the value of its entry point (or else of
+    /// its last expression) along with its type, if it has either.
+    ///
+    /// Inputs are interpreted without any functions of the host, so referring
+    /// to an external function is a runtime error.
+    fn eval(&self, file_id: FileId) -> Option<Result<ReplValue, RuntimeError>> {
+        let module = self.db.hir_module(file_id);
+        let types = self.db.infer(file_id);
+        let ty = match module.main() {
+            Some(main) => types.binding_type(main).map(|scheme| scheme.ty),
+            None => {
+                let (item_id, _) = module
+                    .items
+                    .iter()
+                    .filter(|(_, item)| **item == Item::Expr)
+                    .last()?;
+                let body = self.db.body(file_id, item_id);
+                types.expr_type(item_id, body.value)
+            }
+        };
+
+        let consts = self.db.const_values(file_id);
+        let host = HostFunctions::new();
+        let value =
+            helios_hir::interpret_module(&module, &consts, &host, |item_id| {
+                let body = self.db.body(file_id, item_id);
+                (body, self.db.name_resolutions(file_id, item_id))
+            });
+
+        value
+            .transpose()
+            .map(|value| value.map(|value| ReplValue::new(value, ty)))
+    }
+
+    /// The diagnostics of an input, other than for its unused declarations
+    /// (which later inputs may refer to, along with the declarations of the
+    /// earlier inputs it's stored with).
+    fn input_diagnostics(
+        &self,
+        file_id: FileId,
+    ) -> Arc<Vec<Diagnostic<FileId>>> {
+        let unused = self.db.unused_diagnostics(file_id);
+        let diagnostics = self
+            .db
+            .diagnostics(file_id)
+            .iter()
+            .filter(|diagnostic| !unused.contains(diagnostic))
+            .cloned()
+            .collect();
+
+        Arc::new(diagnostics)
+    }
+
+    fn emit_diagnostics(
+        &self,
+        diagnostics: &[Diagnostic<FileId>],
+        f: &mut dyn Write,
+    ) {
+        helios_diagnostics::emit_all(f, &self.db, diagnostics)
+            .expect("Failed to print diagnostics");
+    }
+
+    /// Finds the documentation of the most recent global binding with the
+    /// given name, searching every input and loaded file of the session.
+    fn doc(&self, name: &str) -> ReplOutcome {
+        if name.is_empty() {
+            return ReplOutcome::Error("Usage: :doc <name>".to_string());
+        }
+
+        let binding = self.files.iter().rev().find_map(|(file_id, _)| {
+            self.db
+                .global_bindings(file_id)
+                .iter()
+                .rev()
+                .find(|binding| binding.name == name)
+                .cloned()
+        });
+
+        match binding {
+            Some(GlobalBinding { doc: Some(doc), .. }) => {
+                ReplOutcome::Doc(FormattedString::from_markdown(&doc))
+            }
+            Some(_) => ReplOutcome::Info(format!("`{name}` is not documented")),
+            None => ReplOutcome::Error(format!("Unknown binding: `{name}`")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helios_hir::{Const, Name, RuntimeErrorKind};
+    use helios_types::Ty;
+
+    #[test]
+    fn test_eval_commands() {
+        let mut session = ReplSession::new(ReplConfig::default());
+
+        assert_eq!(session.eval_line("  "), ReplOutcome::Empty);
+        assert_eq!(
+            session.eval_line(":foo bar"),
+            ReplOutcome::Error("Unknown command: `foo`".to_string())
+        );
+        assert_eq!(
+            session.eval_line(":doc"),
+            ReplOutcome::Error("Usage: :doc <name>".to_string())
+        );
+        assert_eq!(session.eval_line(" :exit "), ReplOutcome::Exit);
+    }
+
+    #[test]
+    fn test_eval_source() {
+        let mut session = ReplSession::new(ReplConfig::default());
+
+        match session.eval_line("1 +") {
+            ReplOutcome::Parsed {
+                file_id,
+                diagnostics,
+                ..
+            } => {
+                assert_eq!(file_id, FileId(0));
+                assert_eq!(diagnostics.len(), 1);
+            }
+            outcome => panic!("Unexpected outcome: {outcome:?}"),
+        }
+
+        assert_eq!(
+            session.eval_line(":doc a"),
+            ReplOutcome::Error("Unknown binding: `a`".to_string())
+        );
+
+        // Nothing else in the input could refer to `a`.
+        match session.eval_line("let a = 1") {
+            ReplOutcome::Parsed { diagnostics, .. } => {
+                assert!(diagnostics.is_empty(), "{diagnostics:?}")
+            }
+            outcome => panic!("Unexpected outcome: {outcome:?}"),
+        }
+        assert_eq!(
+            session.eval_line(":doc a"),
+            ReplOutcome::Info("`a` is not documented".to_string())
+        );
+    }
+
+    #[test]
+    fn test_eval_values() {
+        let mut session = ReplSession::new(ReplConfig::default());
+        let mut value = |input| match session.eval_line(input) {
+            ReplOutcome::Parsed { value, .. } => value,
+            outcome => panic!("Unexpected outcome: {outcome:?}"),
+        };
+
+        let int = |value| ReplValue::new(Const::Int(value), Some(Ty::Int));
+        assert_eq!(value("1 + 2"), Some(Ok(int(3))));
+        assert_eq!(value("let main = a * 2\nlet a = 21\n0"), Some(Ok(int(42))));
+        assert_eq!(value("let a = 1"), None);
+
+        // Inputs with errors aren't evaluated.
+        assert_eq!(value("1 / 0"), None);
+        let error = value("external func now: Int\nnow").unwrap().unwrap_err();
+        assert_eq!(
+            error.kind,
+            RuntimeErrorKind::UnboundExternal(Name::new("now"))
+        );
+    }
+
+    #[test]
+    fn test_earlier_inputs() {
+        let mut session = ReplSession::new(ReplConfig::default());
+        let mut eval = |input| match session.eval_line(input) {
+            ReplOutcome::Parsed {
+                diagnostics, value, ..
+            } => (diagnostics, value),
+            outcome => panic!("Unexpected outcome: {outcome:?}"),
+        };
+
+        let int =
+            |value| Some(Ok(ReplValue::new(Const::Int(value), Some(Ty::Int))));
+        assert_eq!(eval("## The answer.\nlet a = 1").1, None);
+        assert_eq!(eval("a + 1").1, int(2));
+        assert_eq!(eval("macro twice x = x + x").1, None);
+        assert_eq!(eval("twice!(a)").1, int(2));
+
+        // Inputs with errors don't declare anything.
+        let (diagnostics, _) = eval("let b = 1 + true");
+        assert!(!diagnostics.is_empty());
+        let (diagnostics, _) = eval("b");
+        assert_eq!(diagnostics[0].title, "Unresolved name");
+
+        // Entry points only belong to their input.
+        assert_eq!(eval("let main = 3").1, int(3));
+        assert_eq!(eval("a").1, int(1));
+        assert_eq!(
+            session.eval_line(":doc a"),
+            ReplOutcome::Doc(FormattedString::from_markdown("The answer."))
+        );
+
+        // Declaring a name again replaces the earlier declaration.
+        let mut eval = |input| match session.eval_line(input) {
+            ReplOutcome::Parsed { value, .. } => value,
+            outcome => panic!("Unexpected outcome: {outcome:?}"),
+        };
+        assert_eq!(eval("let a = 20"), None);
+        assert_eq!(eval("twice!(a) + 2"), int(42));
+    }
+
+    #[test]
+    fn test_write_outcome() {
+        colored::control::set_override(false);
+
+        let session = ReplSession::new(ReplConfig {
+            output_mode: OutputMode::Value,
+            ..ReplConfig::default()
+        });
+
+        let mut output = Vec::new();
+        let outcome = ReplOutcome::Info("Hello".to_string());
+        session.write_outcome(&outcome, &mut output).unwrap();
+        session
+            .write_outcome(&ReplOutcome::Exit, &mut output)
+            .unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "Hello\n\n");
+
+        let mut session = session;
+        let mut output = Vec::new();
+        let outcome = session.eval_line("1.5 > 1.0");
+        session.write_outcome(&outcome, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "true : Bool\n");
+    }
+}
@@ -0,0 +1,70 @@
+use helios_hir::Const;
+use helios_types::Ty;
+use std::fmt::{self, Display};
+
+/// The value an input evaluated to, along with its type.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ReplValue {
+    pub value: Const,
+    /// The type inferred for the value, unless it isn't fully known.
+    pub ty: Option<Ty>,
+}
+
+impl ReplValue {
+    pub fn new(value: Const, ty: Option<Ty>) -> Self {
+        let ty = ty.filter(|ty| !matches!(ty, Ty::Var(_) | Ty::Error));
+        Self { value, ty }
+    }
+
+    /// Prints the value the way it would be written in Helios (such as `1.0`
+    /// rather than `1` for a `Float`), followed by its type if asked to (such
+    /// as `42 : Int`).
+    pub fn display(&self, show_type: bool) -> impl Display + '_ {
+        DisplayValue {
+            value: self,
+            show_type,
+        }
+    }
+}
+
+struct DisplayValue<'a> {
+    value: &'a ReplValue,
+    show_type: bool,
+}
+
+impl Display for DisplayValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value.value)?;
+        match self.value.ty {
+            Some(ty) if self.show_type => write!(f, " : {ty}"),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helios_hir::Float;
+
+    #[test]
+    fn test_display_value() {
+        let value = |value, ty| ReplValue::new(value, Some(ty));
+
+        let int = value(Const::Int(-42), Ty::Int);
+        assert_eq!(int.display(true).to_string(), "-42 : Int");
+        assert_eq!(int.display(false).to_string(), "-42");
+
+        let float = value(Const::Float(Float::new(1.0)), Ty::Float);
+        assert_eq!(float.display(true).to_string(), "1.0 : Float");
+        let float = value(Const::Float(Float::new(f64::INFINITY)), Ty::Float);
+        assert_eq!(float.display(false).to_string(), "inf");
+
+        let bool = value(Const::Bool(true), Ty::Bool);
+        assert_eq!(bool.display(true).to_string(), "true : Bool");
+
+        // Types that aren't fully known aren't printed.
+        let unknown = ReplValue::new(Const::Int(1), Some(Ty::Error));
+        assert_eq!(unknown.display(true).to_string(), "1");
+    }
+}
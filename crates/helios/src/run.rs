@@ -0,0 +1,137 @@
+use crate::build::{Error, Program, Result};
+use crate::diagnostics::DiagnosticsConfig;
+use crate::timings::Analyzer;
+use crate::verbosity::Verbosity;
+use colored::*;
+use helios_hir::{Const, HostFunctions};
+use helios_query::{Consts, HeliosDatabase, Hir, Scopes};
+use std::time::Instant;
+
+/// Running support for Helios programs
+#[derive(clap::Parser)]
+pub struct HeliosRunOpts {
+    /// The entry point file for the program to be run (by default, the entry
+    /// point of the project containing the current directory, as declared in
+    /// its `helios.toml`)
+    pub file: Option<String>,
+    /// The arguments passed to the program (given after `--`)
+    #[clap(last = true)]
+    pub args: Vec<String>,
+}
+
+/// The functions of the host a program can bind its external functions to.
+///
+/// Programs can't handle strings yet, so the only thing they can know about
+/// their arguments is how many there are (`external func arg_count: Int`).
+pub(crate) fn host_functions(args: &[String]) -> HostFunctions {
+    let arg_count = args.len() as i64;
+    let mut host = HostFunctions::new();
+    host.register("arg_count", move || Const::Int(arg_count));
+    host
+}
+
+/// Interprets the program, returning the value of its entry point (or else of
+/// its last expression).
+///
+/// The program is checked like an executable is when it's built, so it must
+/// have either of them (and a `main` must be an `Int`).
+fn __run(
+    opts: &HeliosRunOpts,
+    program: &Program,
+    verbosity: Verbosity,
+) -> Result<Option<Const>> {
+    let mut db = HeliosDatabase::default();
+    db.enable_syntax_cache();
+    let file_id = program.load(&mut db)?;
+    let config = DiagnosticsConfig::default();
+    let mut analyzer = Analyzer::new(false, verbosity);
+    crate::build::check(
+        &db,
+        file_id,
+        true,
+        None,
+        &mut analyzer,
+        &config,
+        verbosity,
+    )?;
+
+    let module = db.hir_module(file_id);
+    let consts = db.const_values(file_id);
+    let host = host_functions(&opts.args);
+    let value =
+        helios_hir::interpret_module(&module, &consts, &host, |item_id| {
+            let body = db.body(file_id, item_id);
+            (body, db.name_resolutions(file_id, item_id))
+        });
+
+    value.map_err(|error| {
+        let diagnostic =
+            helios_query::runtime_error_diagnostic(&db, file_id, &error);
+        helios_diagnostics::emit(&mut std::io::stdout(), &db, &diagnostic)
+            .expect("Failed to print diagnostics");
+        Error::Runtime
+    })
+}
+
+/// Builds and interprets the given program, printing its value.
+///
+/// Like a compiled program, the process exits with the value of the program
+/// if it's an `Int` (and with `0` otherwise).
+pub fn run(opts: &HeliosRunOpts, verbosity: Verbosity) {
+    let start = Instant::now();
+    let result = Program::new(opts.file.as_slice()).and_then(|program| {
+        verbosity.banner("Running", &program);
+        __run(opts, &program, verbosity)
+    });
+
+    let value = match result {
+        Ok(value) => value,
+        Err(error) => {
+            eprintln!("{}", error.to_string().red().bold());
+            std::process::exit(1);
+        }
+    };
+
+    match value {
+        Some(value) => {
+            verbosity.finished(format!("Finished running: {value}"), start)
+        }
+        None => verbosity.finished("Finished running", start),
+    }
+
+    if let Some(Const::Int(code)) = value {
+        std::process::exit(code as i32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_checks_entry_point() {
+        let dir = std::env::temp_dir()
+            .join(format!("helios-run-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let run = |source: &str| {
+            let path = dir.join("main.helios");
+            std::fs::write(&path, source).unwrap();
+            let program =
+                Program::Files(vec![path.to_string_lossy().into_owned()]);
+            let opts = HeliosRunOpts {
+                file: None,
+                args: Vec::new(),
+            };
+            __run(&opts, &program, Verbosity::Quiet)
+        };
+
+        let valid = run("let main = 3\n");
+        let mistyped = run("let main = 1.5\n");
+        let missing = run("let a = 1\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(valid, Ok(Some(Const::Int(3)))));
+        assert!(matches!(mistyped, Err(Error::Build(1))));
+        assert!(matches!(missing, Err(Error::Build(1))));
+    }
+}
@@ -0,0 +1,111 @@
+use crate::build::Target;
+use helios_codegen::OptLevel;
+use std::path::{Path, PathBuf};
+
+/// How a program is compiled, which decides where its artifacts go.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) enum Profile {
+    /// Compiled as quickly as possible, without optimizations.
+    #[default]
+    Debug,
+    /// Compiled with optimizations (`--release`).
+    Release,
+}
+
+impl Profile {
+    pub(crate) fn new(release: bool) -> Self {
+        match release {
+            true => Self::Release,
+            false => Self::Debug,
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Release => "release",
+        }
+    }
+
+    pub(crate) fn opt_level(self) -> OptLevel {
+        match self {
+            Self::Debug => OptLevel::None,
+            Self::Release => OptLevel::Speed,
+        }
+    }
+}
+
+/// The directory build artifacts are written to, which is laid out as
+/// follows (where `<name>` is the name of the package, or else of its entry
+/// point file):
+///
+/// - `<profile>/<name>` and `<profile>/<name>.o`: the executables and object
+///   files compiled for the host, in the `debug` or `release` profile.
+/// - `wasm32/<profile>/<name>.wasm`: the WebAssembly modules, along with the
+///   `helios.js` runtime running them.
+/// - `doc/<name>`: the documentation of the package.
+/// - `helios-cache`: the diagnostics cached between builds.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct TargetDir {
+    root: PathBuf,
+}
+
+impl TargetDir {
+    pub(crate) fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub(crate) fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The directory the artifacts compiled for the given target in the given
+    /// profile are written to.
+    pub(crate) fn artifact_dir(
+        &self,
+        target: Target,
+        profile: Profile,
+    ) -> PathBuf {
+        match target {
+            Target::Native => self.root.join(profile.as_str()),
+            Target::Wasm32 => self.root.join("wasm32").join(profile.as_str()),
+        }
+    }
+
+    /// The directory the documentation of the package with the given name is
+    /// written to.
+    pub(crate) fn doc_dir(&self, name: &str) -> PathBuf {
+        self.root.join("doc").join(name)
+    }
+
+    /// The directory the diagnostics cached between builds are stored in.
+    pub(crate) fn cache_dir(&self) -> PathBuf {
+        self.root.join("helios-cache")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_dir() {
+        let target = TargetDir::new("project/target");
+        assert_eq!(
+            target.artifact_dir(Target::Native, Profile::Debug),
+            Path::new("project/target/debug")
+        );
+        assert_eq!(
+            target.artifact_dir(Target::Wasm32, Profile::new(true)),
+            Path::new("project/target/wasm32/release")
+        );
+        assert_eq!(
+            target.doc_dir("hello"),
+            Path::new("project/target/doc/hello")
+        );
+        assert_eq!(
+            target.cache_dir(),
+            Path::new("project/target/helios-cache")
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use crate::build::{Error, Program, Result};
+use crate::diagnostics::DiagnosticsConfig;
+use crate::timings::Analyzer;
+use crate::verbosity::Verbosity;
+use colored::*;
+use helios_diagnostics::{Diagnostic, Location};
+use helios_formatting::FormattedString;
+use helios_hir::{Const, ItemId, RuntimeError};
+use helios_query::{
+    Consts, FileId, HeliosDatabase, Hir, Input, Modules, Scopes,
+};
+use std::path::Path;
+use std::time::Instant;
+
+/// Testing support for Helios programs
+#[derive(clap::Parser)]
+pub struct HeliosTestOpts {
+    /// Only runs the tests whose name contains this filter (the name of a test
+    /// starts with the name of its module, such as `parser::addition`)
+    pub filter: Option<String>,
+    /// The file whose tests are run (by default, every file of the project
+    /// containing the current directory, as declared in its `helios.toml`)
+    #[clap(long)]
+    pub file: Option<String>,
+}
+
+/// A test that was run, along with how it went.
+struct TestRun {
+    file_id: FileId,
+    item_id: ItemId,
+    name: String,
+    outcome: Outcome,
+}
+
+enum Outcome {
+    Passed,
+    /// The test evaluated to `false`.
+    Failed,
+    /// The test couldn't be evaluated.
+    Error(RuntimeError),
+}
+
+/// Runs every test of the program that matches the filter, printing how each
+/// of them went (only failing ones in quiet mode) followed by a summary.
+fn __test(
+    opts: &HeliosTestOpts,
+    program: &Program,
+    verbosity: Verbosity,
+) -> Result<()> {
+    let start = Instant::now();
+    let mut db = HeliosDatabase::default();
+    db.enable_syntax_cache();
+    let file_id = program.load(&mut db)?;
+    let config = DiagnosticsConfig::default();
+    let mut analyzer = Analyzer::new(false, verbosity);
+    crate::build::check(
+        &db,
+        file_id,
+        false,
+        None,
+        &mut analyzer,
+        &config,
+        verbosity,
+    )?;
+
+    let mut files = db
+        .build_order()
+        .iter()
+        .map(|&file_id| {
+            let path = db.file_name(file_id);
+            (program.module_name(Path::new(path.as_str())), file_id)
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let host = crate::run::host_functions(&[]);
+    let mut runs = Vec::new();
+    let mut filtered_out = 0;
+    for (module_name, file_id) in files {
+        let module = db.hir_module(file_id);
+        let consts = db.const_values(file_id);
+        for (item_id, test) in module.tests() {
+            let name = format!(
+                "{module_name}::{}",
+                test.name.as_deref().unwrap_or_default()
+            );
+            if opts
+                .filter
+                .as_ref()
+                .is_some_and(|filter| !name.contains(filter))
+            {
+                filtered_out += 1;
+                continue;
+            }
+
+            let value = helios_hir::interpret_test(
+                &module,
+                &consts,
+                &host,
+                item_id,
+                |item_id| {
+                    let body = db.body(file_id, item_id);
+                    (body, db.name_resolutions(file_id, item_id))
+                },
+            );
+
+            let outcome = match value {
+                Ok(Const::Bool(true)) => Outcome::Passed,
+                Ok(_) => Outcome::Failed,
+                Err(error) => Outcome::Error(error),
+            };
+
+            match outcome {
+                Outcome::Passed if verbosity.is_quiet() => {}
+                Outcome::Passed => println!("test {name} ... {}", "ok".green()),
+                Outcome::Failed | Outcome::Error(_) => {
+                    println!("test {name} ... {}", "FAILED".red())
+                }
+            }
+
+            runs.push(TestRun {
+                file_id,
+                item_id,
+                name,
+                outcome,
+            });
+        }
+    }
+
+    let failures = runs
+        .iter()
+        .filter(|run| !matches!(run.outcome, Outcome::Passed))
+        .collect::<Vec<_>>();
+
+    if !failures.is_empty() {
+        println!("\n{}\n", "Failures:".bold());
+        let mut stdout = std::io::stdout();
+        for run in &failures {
+            let diagnostic = failure_diagnostic(&db, run);
+            helios_diagnostics::emit(&mut stdout, &db, &diagnostic)
+                .expect("Failed to print diagnostics");
+        }
+    }
+
+    let passed = runs.len() - failures.len();
+    let result = match failures.len() {
+        0 => "ok".green(),
+        _ => "FAILED".red(),
+    };
+    print!(
+        "\ntest result: {result}. {passed} passed; {} failed; {filtered_out} \
+        filtered out",
+        failures.len()
+    );
+    match verbosity {
+        Verbosity::Verbose => println!("; finished in {:.2?}", start.elapsed()),
+        Verbosity::Quiet | Verbosity::Normal => println!(),
+    }
+
+    match failures.len() {
+        0 => Ok(()),
+        count => Err(Error::TestFailures(count)),
+    }
+}
+
+/// The diagnostic explaining why a test failed, located at its body.
+fn failure_diagnostic(
+    db: &HeliosDatabase,
+    run: &TestRun,
+) -> Diagnostic<FileId> {
+    let (body, source_map) = db.body_with_source_map(run.file_id, run.item_id);
+    let location =
+        Location::new(run.file_id, source_map.expr_range(body.value));
+
+    let error = match &run.outcome {
+        Outcome::Error(error) => error,
+        Outcome::Passed | Outcome::Failed => {
+            let description = FormattedString::default()
+                .text("The test ")
+                .code(&run.name)
+                .text(" evaluated to ")
+                .code("false")
+                .text(":");
+
+            let message = FormattedString::default()
+                .text("Tests pass when they evaluate to ")
+                .code("true")
+                .text(".");
+
+            return Diagnostic::error("Test failed")
+                .description(description)
+                .message(message)
+                .location(location);
+        }
+    };
+
+    let diagnostic =
+        helios_query::runtime_error_diagnostic(db, run.file_id, error);
+    let title = format!("Test failed: {}", diagnostic.title);
+    Diagnostic {
+        title,
+        ..diagnostic
+    }
+}
+
+/// Builds the given program and runs its tests.
+pub fn test(opts: &HeliosTestOpts, verbosity: Verbosity) {
+    let result = Program::new(opts.file.as_slice()).and_then(|program| {
+        verbosity.banner("Testing", &program);
+        __test(opts, &program, verbosity)
+    });
+
+    if let Err(error) = result {
+        eprintln!("\n{}", error.to_string().red().bold());
+        std::process::exit(1);
+    }
+}
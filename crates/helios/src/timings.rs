@@ -0,0 +1,368 @@
+use crate::verbosity::Verbosity;
+use helios_diagnostics::Diagnostic;
+use helios_parser::TokenizeOptions;
+use helios_query::{
+    Consts, Diagnostics, FileId, HeliosDatabase, Input, Modules, Scopes, Types,
+};
+use std::fmt::{self, Display};
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The least number of files a progress bar is shown for, since analyzing
+/// fewer files is usually too quick for it to be worth it.
+const PROGRESS_THRESHOLD: usize = 32;
+
+/// The number of characters the bar of a progress bar is made of.
+const PROGRESS_WIDTH: usize = 30;
+
+/// A phase of the analysis of files.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Phase {
+    Lex,
+    Parse,
+    /// Resolving imports and names (along with lowering files).
+    Resolve,
+    /// Inferring types (along with evaluating constants).
+    Typecheck,
+}
+
+impl Phase {
+    const ALL: [Phase; 4] =
+        [Phase::Lex, Phase::Parse, Phase::Resolve, Phase::Typecheck];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Phase::Lex => "lex",
+            Phase::Parse => "parse",
+            Phase::Resolve => "resolve",
+            Phase::Typecheck => "typecheck",
+        }
+    }
+
+    /// What a progress bar says while files go through the phase.
+    fn action(self) -> &'static str {
+        match self {
+            Phase::Lex => "Lexing",
+            Phase::Parse => "Parsing",
+            Phase::Resolve => "Resolving",
+            Phase::Typecheck => "Typechecking",
+        }
+    }
+}
+
+/// How long every phase of the analysis of the files of a program took.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Timings {
+    /// The number of files that were analyzed.
+    files: usize,
+    /// The number of files whose diagnostics were reused from the cache
+    /// instead.
+    cached_files: usize,
+    phases: Vec<(Phase, Duration)>,
+    total: Duration,
+}
+
+impl Timings {
+    /// Serializes the timings to (pretty-printed) JSON, where durations are
+    /// in seconds.
+    pub(crate) fn to_json(&self) -> String {
+        let phases = self
+            .phases
+            .iter()
+            .map(|(phase, duration)| {
+                (phase.as_str().to_string(), duration.as_secs_f64().into())
+            })
+            .collect::<serde_json::Map<_, _>>();
+
+        let timings = serde_json::json!({
+            "files": self.files,
+            "cached_files": self.cached_files,
+            "phases": phases,
+            "total": self.total.as_secs_f64(),
+        });
+
+        serde_json::to_string_pretty(&timings)
+            .expect("Timings can always be serialized")
+    }
+}
+
+impl Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} files analyzed, {} reused from the cache",
+            self.files, self.cached_files
+        )?;
+
+        let total = self.total.as_secs_f64();
+        for (phase, duration) in &self.phases {
+            let share = match total {
+                total if total > 0.0 => duration.as_secs_f64() / total,
+                _ => 0.0,
+            };
+            writeln!(
+                f,
+                "{:<10} {:>10} {:>6.1}%",
+                phase.as_str(),
+                format!("{duration:.2?}"),
+                share * 100.0
+            )?;
+        }
+
+        write!(f, "{:<10} {:>10}", "total", format!("{:.2?}", self.total))
+    }
+}
+
+/// Analyzes the files of a program, measuring how long every phase took if
+/// asked to and showing a progress bar when there are many files.
+pub(crate) struct Analyzer {
+    timings: Option<Timings>,
+    show_progress: bool,
+}
+
+impl Analyzer {
+    /// Creates an analyzer, which measures how long every phase took if asked
+    /// to. Progress bars are only shown on terminals, outside of quiet mode.
+    pub(crate) fn new(measure: bool, verbosity: Verbosity) -> Self {
+        Self {
+            timings: measure.then(Timings::default),
+            show_progress: !verbosity.is_quiet()
+                && std::io::stderr().is_terminal(),
+        }
+    }
+
+    /// How long every phase took, if they were measured.
+    pub(crate) fn timings(&self) -> Option<&Timings> {
+        self.timings.as_ref()
+    }
+
+    /// Records that the diagnostics of the given number of files were reused
+    /// from the cache.
+    pub(crate) fn reuse(&mut self, count: usize) {
+        if let Some(timings) = &mut self.timings {
+            timings.cached_files += count;
+        }
+    }
+
+    /// Computes the diagnostics of the given files in parallel, returning them
+    /// in the same order as the files.
+    ///
+    /// When measuring how long every phase took, every file goes through a
+    /// phase before any file goes through the next one (otherwise, queries
+    /// would run the phases of the files they depend on in between).
+    pub(crate) fn analyze(
+        &mut self,
+        db: &HeliosDatabase,
+        files: &[FileId],
+    ) -> Vec<Arc<Vec<Diagnostic<FileId>>>> {
+        let show_progress =
+            self.show_progress && files.len() >= PROGRESS_THRESHOLD;
+        let start = Instant::now();
+
+        let progress =
+            |action| show_progress.then(|| Progress::new(action, files.len()));
+
+        let Some(timings) = &mut self.timings else {
+            let progress = progress("Analyzing");
+            return map_parallel(
+                db,
+                files,
+                progress.as_ref(),
+                |db, file_id| db.diagnostics(file_id),
+            );
+        };
+
+        let mut phases = Vec::with_capacity(Phase::ALL.len());
+        for phase in Phase::ALL {
+            let progress = progress(phase.action());
+            let start = Instant::now();
+            map_parallel(db, files, progress.as_ref(), |db, file_id| {
+                run_phase(db, file_id, phase)
+            });
+            phases.push((phase, start.elapsed()));
+        }
+
+        // Parsing lexes files again, which was already measured on its own.
+        let lex = phases[0].1;
+        phases[1].1 = phases[1].1.saturating_sub(lex);
+
+        let diagnostics = map_parallel(db, files, None, |db, file_id| {
+            db.diagnostics(file_id)
+        });
+
+        timings.files += files.len();
+        for (phase, duration) in phases {
+            match timings.phases.iter_mut().find(|(it, _)| *it == phase) {
+                Some((_, total)) => *total += duration,
+                None => timings.phases.push((phase, duration)),
+            }
+        }
+        timings.total += start.elapsed();
+
+        diagnostics
+    }
+}
+
+/// Runs a phase of the analysis of a file.
+fn run_phase(db: &HeliosDatabase, file_id: FileId, phase: Phase) {
+    match phase {
+        Phase::Lex => {
+            let source = db.source(file_id);
+            let options = TokenizeOptions {
+                process_indentation: true,
+                edition: db.edition(),
+                ..TokenizeOptions::default()
+            };
+            helios_parser::tokenize_with_options(file_id, &source, options);
+        }
+        Phase::Parse => {
+            db.parse_diagnostics(file_id);
+        }
+        Phase::Resolve => {
+            db.module_diagnostics(file_id);
+            db.name_diagnostics(file_id);
+            db.unused_diagnostics(file_id);
+        }
+        Phase::Typecheck => {
+            db.type_diagnostics(file_id);
+            db.const_diagnostics(file_id);
+        }
+    }
+}
+
+/// Runs the given work for every file in parallel, updating the given
+/// progress bar (if there is one) whenever a file is done.
+fn map_parallel<T, F>(
+    db: &HeliosDatabase,
+    files: &[FileId],
+    progress: Option<&Progress>,
+    f: F,
+) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&HeliosDatabase, FileId) -> T + Sync,
+{
+    // There is no need to start any threads (or print any progress).
+    if files.is_empty() {
+        return Vec::new();
+    }
+
+    let results = db
+        .map_parallel(files, |db, file_id| {
+            let result = f(db, file_id);
+            if let Some(progress) = progress {
+                progress.advance();
+            }
+            result
+        })
+        .expect("Nothing else changes the database while building");
+
+    if let Some(progress) = progress {
+        progress.clear();
+    }
+
+    results
+}
+
+/// A progress bar, printed to the standard error stream (such as
+/// `Parsing [=========>          ] 12/40`).
+struct Progress {
+    action: &'static str,
+    done: AtomicUsize,
+    total: usize,
+}
+
+impl Progress {
+    fn new(action: &'static str, total: usize) -> Self {
+        Self {
+            action,
+            done: AtomicUsize::new(0),
+            total,
+        }
+    }
+
+    /// Records that another file is done and prints the progress bar again.
+    fn advance(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut stderr = std::io::stderr().lock();
+        let progress = render_progress(self.action, done, self.total);
+        let _ = write!(stderr, "\r{progress}");
+        let _ = stderr.flush();
+    }
+
+    /// Erases the progress bar.
+    fn clear(&self) {
+        let width = render_progress(self.action, self.total, self.total).len();
+        eprint!("\r{}\r", " ".repeat(width));
+    }
+}
+
+fn render_progress(action: &str, done: usize, total: usize) -> String {
+    let filled = (PROGRESS_WIDTH * done).checked_div(total).unwrap_or(0);
+    let bar = match filled {
+        0 => " ".repeat(PROGRESS_WIDTH),
+        filled if filled >= PROGRESS_WIDTH => "=".repeat(PROGRESS_WIDTH),
+        filled => {
+            let empty = " ".repeat(PROGRESS_WIDTH - filled);
+            format!("{}>{empty}", "=".repeat(filled - 1))
+        }
+    };
+
+    format!("{action:>12} [{bar}] {done}/{total}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timings() {
+        let mut db = HeliosDatabase::default();
+        let files = (0..4).map(FileId).collect::<Vec<_>>();
+        for &file_id in &files {
+            db.set_source(file_id, Arc::new("let a = b\n".to_string()));
+        }
+        db.set_workspace_files(Arc::new(files.clone()));
+
+        let mut analyzer = Analyzer::new(true, Verbosity::Quiet);
+        analyzer.reuse(2);
+        let diagnostics = analyzer.analyze(&db, &files);
+        for (&file_id, diagnostics) in files.iter().zip(&diagnostics) {
+            assert_eq!(*diagnostics, db.diagnostics(file_id));
+        }
+
+        let timings = analyzer.timings().unwrap();
+        assert_eq!((timings.files, timings.cached_files), (4, 2));
+        let phases = timings
+            .phases
+            .iter()
+            .map(|(phase, _)| *phase)
+            .collect::<Vec<_>>();
+        assert_eq!(phases, Phase::ALL);
+
+        let json = timings.to_json();
+        assert!(json.contains(r#""cached_files": 2"#), "{json}");
+        assert!(json.contains(r#""typecheck": "#), "{json}");
+
+        let analyzer = Analyzer::new(false, Verbosity::Quiet);
+        assert!(analyzer.timings().is_none());
+    }
+
+    #[test]
+    fn test_render_progress() {
+        assert_eq!(
+            render_progress("Parsing", 0, 3),
+            format!("     Parsing [{}] 0/3", " ".repeat(30))
+        );
+        assert_eq!(
+            render_progress("Parsing", 1, 3),
+            format!("     Parsing [{}>{}] 1/3", "=".repeat(9), " ".repeat(20))
+        );
+        assert_eq!(
+            render_progress("Parsing", 3, 3),
+            format!("     Parsing [{}] 3/3", "=".repeat(30))
+        );
+    }
+}
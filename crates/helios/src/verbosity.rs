@@ -0,0 +1,81 @@
+use colored::*;
+use helios_diagnostics::Severity;
+use std::fmt::Display;
+use std::time::Instant;
+
+/// How much the subcommands print, set by `--quiet` and `--verbose`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Verbosity {
+    /// Only errors (and what the subcommand was asked for) are printed.
+    Quiet,
+    /// Banners, syntax trees and every diagnostic are printed.
+    #[default]
+    Normal,
+    /// How long everything took and how often queries were executed are
+    /// printed as well.
+    Verbose,
+}
+
+impl Verbosity {
+    pub fn new(quiet: bool, verbose: bool) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Self::Quiet,
+            (false, true) => Self::Verbose,
+            (false, false) => Self::Normal,
+        }
+    }
+
+    pub fn is_quiet(self) -> bool {
+        self == Self::Quiet
+    }
+
+    pub fn is_verbose(self) -> bool {
+        self == Self::Verbose
+    }
+
+    /// Whether diagnostics of the given severity are printed (warnings and
+    /// notes are left out in quiet mode).
+    pub fn shows(self, severity: Severity) -> bool {
+        !self.is_quiet() || severity >= Severity::Error
+    }
+
+    /// Prints the banner announcing what a subcommand is working on (such as
+    /// `Building hello v0.1.0`), unless in quiet mode.
+    pub(crate) fn banner(self, action: &str, subject: impl Display) {
+        if !self.is_quiet() {
+            let subject = subject.to_string();
+            println!("\n{} {}\n", action.green().bold(), subject.underline());
+        }
+    }
+
+    /// Prints that a subcommand finished (along with how long it took since
+    /// the given instant in verbose mode), unless in quiet mode.
+    pub(crate) fn finished(self, message: impl Display, start: Instant) {
+        let message = message.to_string();
+        match self {
+            Self::Quiet => {}
+            Self::Normal => println!("{}", message.green().bold()),
+            Self::Verbose => println!(
+                "{} {}",
+                message.green().bold(),
+                format!("(in {:.2?})", start.elapsed()).dimmed()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verbosity() {
+        assert_eq!(Verbosity::new(false, false), Verbosity::Normal);
+        assert_eq!(Verbosity::new(false, true), Verbosity::Verbose);
+        assert_eq!(Verbosity::new(true, false), Verbosity::Quiet);
+
+        assert!(Verbosity::Quiet.shows(Severity::Error));
+        assert!(!Verbosity::Quiet.shows(Severity::Warning));
+        assert!(Verbosity::Normal.shows(Severity::Note));
+    }
+}
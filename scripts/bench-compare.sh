@@ -0,0 +1,41 @@
+#!/usr/bin/env bash
+#
+# Compares the parser benchmarks of the working tree with those of another
+# revision (the `main` branch by default), and fails if any of them regressed
+# by more than the given percentage (5% by default).
+#
+# Usage: scripts/bench-compare.sh [BASE] [THRESHOLD]
+#
+# Benchmarks are noisy, so run this on an otherwise idle machine (and before
+# releases) rather than on CI.
+
+set -euo pipefail
+
+base="${1:-main}"
+threshold="${2:-5}"
+
+root="$(git rev-parse --show-toplevel)"
+worktree="$(mktemp -d)"
+output="$(mktemp)"
+trap 'git -C "$root" worktree remove --force "$worktree"; rm -f "$output"' EXIT
+
+# Both revisions share a target directory, so that Criterion can compare the
+# new results with the saved baseline.
+export CARGO_TARGET_DIR="$root/target"
+args=(--noise-threshold "$(awk "BEGIN { print $threshold / 100 }")")
+
+echo "Benchmarking $base..."
+git -C "$root" worktree add --detach --quiet "$worktree" "$base"
+(cd "$worktree" && cargo bench -p helios-parser --bench parser -- \
+    --save-baseline base "${args[@]}")
+
+echo "Benchmarking the working tree..."
+(cd "$root" && cargo bench -p helios-parser --bench parser -- \
+    --baseline base "${args[@]}") | tee "$output"
+
+if grep -q "Performance has regressed" "$output"; then
+    echo "Some benchmarks regressed by more than $threshold% against $base."
+    exit 1
+fi
+
+echo "No benchmarks regressed by more than $threshold% against $base."